@@ -0,0 +1,184 @@
+// see `benchmarks/README.md`
+//
+// the full path of some data through a store, with Semi-AVID and `komodo::fs`, i.e. the blocks
+// are dispersed, dumped to the disk, read back, verified and decoded, because real deployments
+// are often bound by the IO rather than by the in-memory operations of `benches/protocols.rs`.
+use std::path::{Path, PathBuf};
+
+use ark_bls12_381::{Fr, G1Projective};
+use ark_poly::univariate::DensePolynomial;
+use ark_serialize::{Compress, Validate};
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use komodo::{
+    algebra::linalg::Matrix,
+    fec,
+    fs::{self, Compression, Encryption, Header, Layout},
+    semi_avid::{self, Block},
+    zk::{self, Powers},
+};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+type P = DensePolynomial<Fr>;
+
+/// the number of source shards
+const K: usize = 4;
+/// the total number of shards
+const N: usize = 8;
+/// the sizes of the data, in bytes
+const NB_BYTES: [usize; 2] = [1 << 16, 1 << 20];
+
+const LAYOUT: Layout = Layout::Flat;
+const COMPRESS: Compress = Compress::Yes;
+const VALIDATE: Validate = Validate::Yes;
+
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(0)
+}
+
+fn data(nb_bytes: usize) -> Vec<u8> {
+    let mut bytes = vec![0; nb_bytes];
+    rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+/// encode and prove some data, i.e. the blocks that would be sent to the storage nodes
+fn disperse(
+    bytes: &[u8],
+    encoding_mat: &Matrix<Fr>,
+    powers: &Powers<Fr, G1Projective>,
+) -> Vec<Block<Fr, G1Projective>> {
+    let shards = fec::encode::<Fr>(bytes, encoding_mat).unwrap();
+    let proof = semi_avid::prove::<Fr, G1Projective, P>(bytes, powers, K).unwrap();
+    semi_avid::build::<Fr, G1Projective, P>(&shards, &proof)
+}
+
+/// empty `block_dir` before dumping blocks into it
+///
+/// > **Note**
+/// >
+/// > dumping is idempotent, the blocks of a previous iteration would not be written again.
+fn clear(block_dir: &Path) {
+    let _ = std::fs::remove_dir_all(block_dir);
+}
+
+/// dump the blocks to `block_dir` and return their hashes
+fn dump(blocks: &[Block<Fr, G1Projective>], block_dir: &Path) -> Vec<String> {
+    let header =
+        Header::new("semi-avid", "BLS12-381", &[]).for_blocks(K as u32, &blocks[0].shard.hash);
+    blocks
+        .iter()
+        .map(|b| {
+            fs::dump_block(
+                b,
+                block_dir,
+                LAYOUT,
+                &header,
+                COMPRESS,
+                Compression::None,
+                Encryption::None,
+            )
+            .unwrap()
+        })
+        .collect()
+}
+
+fn read(hashes: &[String], block_dir: &Path) -> Vec<Block<Fr, G1Projective>> {
+    fs::read_blocks(
+        hashes,
+        block_dir,
+        LAYOUT,
+        COMPRESS,
+        VALIDATE,
+        Encryption::None,
+    )
+    .unwrap()
+    .into_iter()
+    .map(|(_, b)| b)
+    .collect()
+}
+
+fn verify(blocks: &[Block<Fr, G1Projective>], powers: &Powers<Fr, G1Projective>) {
+    for block in blocks {
+        assert!(semi_avid::verify::<Fr, G1Projective, P>(block, powers).unwrap());
+    }
+}
+
+fn decode(blocks: &[Block<Fr, G1Projective>]) -> Vec<u8> {
+    fec::decode::<Fr>(blocks.iter().map(|b| b.shard.clone()).collect()).unwrap()
+}
+
+/// a temporary block directory, removed when dropped
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        Self(std::env::temp_dir().join(format!("komodo-{}-{}", name, std::process::id())))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn pipeline(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pipeline");
+    group.sample_size(10);
+    let block_dir = TempDir::new("pipeline");
+
+    for nb_bytes in NB_BYTES {
+        let bytes = data(nb_bytes);
+        let powers =
+            zk::setup::<Fr, G1Projective>(zk::nb_elements_in_setup::<Fr>(nb_bytes), &mut rng())
+                .unwrap();
+        let encoding_mat = Matrix::random(K, N, &mut rng());
+        let blocks = disperse(&bytes, &encoding_mat, &powers);
+        clear(&block_dir.0);
+        let hashes = dump(&blocks, &block_dir.0);
+
+        group.throughput(Throughput::Bytes(nb_bytes as u64));
+        group.bench_function(BenchmarkId::new("disperse", nb_bytes), |b| {
+            b.iter(|| disperse(black_box(&bytes), &encoding_mat, &powers))
+        });
+        group.bench_function(BenchmarkId::new("dump", nb_bytes), |b| {
+            b.iter_batched(
+                || clear(&block_dir.0),
+                |_| dump(black_box(&blocks), &block_dir.0),
+                BatchSize::PerIteration,
+            )
+        });
+        group.bench_function(BenchmarkId::new("read", nb_bytes), |b| {
+            b.iter(|| read(black_box(&hashes), &block_dir.0))
+        });
+        group.bench_function(BenchmarkId::new("verify", nb_bytes), |b| {
+            b.iter(|| verify(black_box(&blocks), &powers))
+        });
+        group.bench_function(BenchmarkId::new("decode", nb_bytes), |b| {
+            b.iter_batched(
+                || blocks[N - K..].to_vec(),
+                |blocks| decode(&blocks),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("end-to-end", nb_bytes), |b| {
+            b.iter_batched(
+                || clear(&block_dir.0),
+                |_| {
+                    let blocks = disperse(black_box(&bytes), &encoding_mat, &powers);
+                    let hashes = dump(&blocks, &block_dir.0);
+                    let blocks = read(&hashes[N - K..], &block_dir.0);
+                    verify(&blocks, &powers);
+                    assert_eq!(decode(&blocks), bytes);
+                },
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, pipeline);
+criterion_main!(benches);
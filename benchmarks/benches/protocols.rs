@@ -0,0 +1,289 @@
+// see `benchmarks/README.md`
+//
+// the statistical counterpart of the PLNK benchmarks of `src/bin/`, with Criterion: each
+// operation of each protocol is measured on the same data, and compared to the previous run, or
+// to a saved baseline, to detect the performance regressions.
+use ark_bls12_381::{Bls12_381, Fr, G1Projective};
+use ark_ec::AffineRepr;
+use ark_ff::PrimeField;
+use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial};
+use ark_poly_commit::kzg10::KZG10;
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use dragoonfri::algorithms::Sha3_512;
+use komodo::{
+    algebra,
+    algebra::linalg::Matrix,
+    aplonk, fec, fri, kzg, semi_avid,
+    zk::{self, trim},
+};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+type P = DensePolynomial<Fr>;
+
+/// the number of source shards
+const K: usize = 4;
+/// the total number of shards
+const N: usize = 8;
+/// the number of polynomials of the data, a power of $2$ for aPlonK
+const NB_POLYNOMIALS: [usize; 2] = [8, 64];
+/// the number of queries of FRI
+const NB_QUERIES: usize = 1;
+
+/// the data of a benchmark, the same for all the runs so that they can be compared
+fn data(nb_polynomials: usize) -> Vec<u8> {
+    let nb_bytes = K * nb_polynomials * (Fr::MODULUS_BIT_SIZE as usize / 8);
+    let mut bytes = vec![0; nb_bytes];
+    rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(0)
+}
+
+fn vandermonde() -> (Vec<Fr>, Matrix<Fr>) {
+    let points: Vec<Fr> = (0..N)
+        .map(|i| Fr::from_le_bytes_mod_order(&i.to_le_bytes()))
+        .collect();
+    let encoding_mat = Matrix::vandermonde_unchecked(&points, K);
+    (points, encoding_mat)
+}
+
+fn polynomials(bytes: &[u8]) -> Vec<P> {
+    algebra::split_data_into_field_elements::<Fr>(bytes, K)
+        .chunks(K)
+        .map(|c| P::from_coefficients_vec(c.to_vec()))
+        .collect()
+}
+
+fn fec(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fec");
+    for nb_polynomials in NB_POLYNOMIALS {
+        let bytes = data(nb_polynomials);
+        let encoding_mat = Matrix::random(K, N, &mut rng());
+        let shards = fec::encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        let coeffs = [Fr::from(2u64), Fr::from(3u64)];
+
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(BenchmarkId::new("encode", bytes.len()), |b| {
+            b.iter(|| fec::encode::<Fr>(black_box(&bytes), &encoding_mat).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("decode", bytes.len()), |b| {
+            b.iter_batched(
+                || shards[N - K..].to_vec(),
+                |shards| fec::decode::<Fr>(shards).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("recode", bytes.len()), |b| {
+            b.iter(|| fec::recode_with_coeffs(black_box(&shards[..2]), &coeffs))
+        });
+    }
+    group.finish();
+}
+
+fn semi_avid(c: &mut Criterion) {
+    let mut group = c.benchmark_group("semi-avid");
+    for nb_polynomials in NB_POLYNOMIALS {
+        let bytes = data(nb_polynomials);
+        let powers =
+            zk::setup::<Fr, G1Projective>(zk::nb_elements_in_setup::<Fr>(bytes.len()), &mut rng())
+                .unwrap();
+        let shards = fec::encode::<Fr>(&bytes, &Matrix::random(K, N, &mut rng())).unwrap();
+        let proof = semi_avid::prove::<Fr, G1Projective, P>(&bytes, &powers, K).unwrap();
+        let blocks = semi_avid::build::<Fr, G1Projective, P>(&shards, &proof);
+        let polynomial = P::rand(nb_polynomials - 1, &mut rng());
+        let coeffs = [Fr::from(2u64), Fr::from(3u64)];
+
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(BenchmarkId::new("commit", bytes.len()), |b| {
+            b.iter(|| zk::commit(&powers, black_box(&polynomial)).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("prove", bytes.len()), |b| {
+            b.iter(|| {
+                semi_avid::prove::<Fr, G1Projective, P>(black_box(&bytes), &powers, K).unwrap()
+            })
+        });
+        group.bench_function(BenchmarkId::new("verify", bytes.len()), |b| {
+            b.iter(|| {
+                semi_avid::verify::<Fr, G1Projective, P>(black_box(&blocks[0]), &powers).unwrap()
+            })
+        });
+        group.bench_function(BenchmarkId::new("recode", bytes.len()), |b| {
+            b.iter(|| semi_avid::recode_with_coeffs(black_box(&blocks[..2]), &coeffs).unwrap())
+        });
+        // NOTE: a recoded block has to be verified by the node receiving it
+        let recoded = semi_avid::recode_with_coeffs(&blocks[..2], &coeffs)
+            .unwrap()
+            .unwrap();
+        group.bench_function(BenchmarkId::new("verify-recoded", bytes.len()), |b| {
+            b.iter(|| {
+                semi_avid::verify::<Fr, G1Projective, P>(black_box(&recoded), &powers).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn kzg(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kzg");
+    let params = KZG10::<Bls12_381, P>::setup(K - 1, false, &mut rng()).unwrap();
+    let (powers, verifier_key) = trim(params, K - 1);
+    let (points, encoding_mat) = vandermonde();
+
+    for nb_polynomials in NB_POLYNOMIALS {
+        let bytes = data(nb_polynomials);
+        let polynomials = polynomials(&bytes);
+        let shards = fec::encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        let (commits, _) = kzg::commit(&powers, &polynomials).unwrap();
+        let blocks = kzg::prove::<Bls12_381, P>(
+            commits.clone(),
+            polynomials.clone(),
+            shards.clone(),
+            points.clone(),
+            powers.clone(),
+        )
+        .unwrap();
+
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(BenchmarkId::new("commit", bytes.len()), |b| {
+            b.iter(|| kzg::commit(&powers, black_box(&polynomials)).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("prove", bytes.len()), |b| {
+            b.iter_batched(
+                || {
+                    (
+                        commits.clone(),
+                        polynomials.clone(),
+                        shards.clone(),
+                        points.clone(),
+                        powers.clone(),
+                    )
+                },
+                |(c, p, s, x, w)| kzg::prove::<Bls12_381, P>(c, p, s, x, w).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("verify", bytes.len()), |b| {
+            b.iter(|| kzg::verify::<Bls12_381, P>(black_box(&blocks[0]), points[0], &verifier_key))
+        });
+    }
+    group.finish();
+}
+
+fn aplonk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("aplonk");
+    let (points, encoding_mat) = vandermonde();
+
+    for nb_polynomials in NB_POLYNOMIALS {
+        let bytes = data(nb_polynomials);
+        let polynomials = polynomials(&bytes);
+        let params = aplonk::setup::<Bls12_381, P>(K - 1, polynomials.len()).unwrap();
+        let (_, vk_psi) = trim(params.kzg.clone(), K - 1);
+        let (tau_1, g_1, g_2) = (
+            params.ipa.tau_1,
+            params.kzg.powers_of_g[0].into_group(),
+            params.kzg.h.into_group(),
+        );
+        let shards = fec::encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        let commit = aplonk::commit(polynomials.clone(), params.clone()).unwrap();
+        let blocks = aplonk::prove::<Bls12_381, P>(
+            commit.clone(),
+            polynomials.clone(),
+            shards.clone(),
+            points.clone(),
+            params.clone(),
+        )
+        .unwrap();
+
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(BenchmarkId::new("commit", bytes.len()), |b| {
+            b.iter_batched(
+                || (polynomials.clone(), params.clone()),
+                |(p, s)| aplonk::commit(p, s).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("prove", bytes.len()), |b| {
+            b.iter_batched(
+                || {
+                    (
+                        commit.clone(),
+                        polynomials.clone(),
+                        shards.clone(),
+                        points.clone(),
+                        params.clone(),
+                    )
+                },
+                |(c, p, s, x, w)| aplonk::prove::<Bls12_381, P>(c, p, s, x, w).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("verify", bytes.len()), |b| {
+            b.iter(|| {
+                aplonk::verify::<Bls12_381, P>(
+                    black_box(&blocks[0]),
+                    points[0],
+                    &vk_psi,
+                    tau_1,
+                    g_1,
+                    g_2,
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn fri(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fri");
+    for nb_polynomials in NB_POLYNOMIALS {
+        let bytes = data(nb_polynomials);
+        let evaluations = fri::evaluate::<Fr>(&bytes, K, N);
+        let shards = fri::encode::<Fr>(&bytes, evaluations.clone(), K);
+        let blocks = fri::prove::<2, Fr, Sha3_512, P>(
+            evaluations.clone(),
+            shards.clone(),
+            N / K,
+            2,
+            NB_QUERIES,
+        )
+        .unwrap();
+
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(BenchmarkId::new("encode", bytes.len()), |b| {
+            b.iter(|| {
+                let evaluations = fri::evaluate::<Fr>(black_box(&bytes), K, N);
+                fri::encode::<Fr>(&bytes, evaluations, K)
+            })
+        });
+        group.bench_function(BenchmarkId::new("prove", bytes.len()), |b| {
+            b.iter_batched(
+                || (evaluations.clone(), shards.clone()),
+                |(e, s)| fri::prove::<2, Fr, Sha3_512, P>(e, s, N / K, 2, NB_QUERIES).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("verify", bytes.len()), |b| {
+            b.iter_batched(
+                || blocks[0].clone(),
+                |block| fri::verify::<2, Fr, Sha3_512, P>(block, N, NB_QUERIES).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("decode", bytes.len()), |b| {
+            b.iter_batched(
+                || blocks[..K].to_vec(),
+                |blocks| fri::decode::<Fr, Sha3_512>(blocks, N),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fec, semi_avid, kzg, aplonk, fri);
+criterion_main!(benches);
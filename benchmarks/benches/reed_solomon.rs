@@ -0,0 +1,136 @@
+// see `benchmarks/README.md`
+//
+// the erasure code of Komodo, over a prime field, against two classic Reed-Solomon libraries over
+// $GF(2^8)$ and $GF(2^{16})$, on the same data and with the same $k$ and $n$, so that the cost of
+// the field-based coding, which is what makes the proofs and the recoding possible, can be seen.
+use ark_bls12_381::Fr;
+use benchmarks::fields::Fq128;
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use komodo::{algebra::linalg::Matrix, fec};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use reed_solomon_erasure::galois_8::ReedSolomon;
+
+/// the number of source shards
+const K: usize = 4;
+/// the total number of shards
+const N: usize = 8;
+/// the sizes of the data, in bytes
+const NB_BYTES: [usize; 3] = [1 << 10, 1 << 16, 1 << 20];
+
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(0)
+}
+
+fn data(nb_bytes: usize) -> Vec<u8> {
+    let mut bytes = vec![0; nb_bytes];
+    rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+/// split the data into $k$ shards of the same size, padded with zeros
+///
+/// > **Note**
+/// >
+/// > `reed-solomon-simd` requires the size of the shards to be even.
+fn split(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let shard_size = bytes.len().div_ceil(K).next_multiple_of(2);
+    let mut padded = bytes.to_vec();
+    padded.resize(shard_size * K, 0);
+    padded.chunks(shard_size).map(|c| c.to_vec()).collect()
+}
+
+fn komodo(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reed-solomon/komodo");
+    for nb_bytes in NB_BYTES {
+        let bytes = data(nb_bytes);
+        group.throughput(Throughput::Bytes(nb_bytes as u64));
+
+        macro_rules! bench {
+            ($f:ty, $name:literal) => {{
+                let encoding_mat = Matrix::<$f>::vandermonde_unchecked(
+                    &(0..N).map(|i| <$f>::from(i as u64 + 1)).collect::<Vec<_>>(),
+                    K,
+                );
+                let shards = fec::encode::<$f>(&bytes, &encoding_mat).unwrap();
+
+                group.bench_function(BenchmarkId::new(concat!("encode-", $name), nb_bytes), |b| {
+                    b.iter(|| fec::encode::<$f>(black_box(&bytes), &encoding_mat).unwrap())
+                });
+                // NOTE: the last shards are the parity ones, which is the worst case of the other
+                // libraries
+                group.bench_function(BenchmarkId::new(concat!("decode-", $name), nb_bytes), |b| {
+                    b.iter_batched(
+                        || shards[N - K..].to_vec(),
+                        |shards| fec::decode::<$f>(shards).unwrap(),
+                        BatchSize::SmallInput,
+                    )
+                });
+            }};
+        }
+
+        bench!(Fr, "bls12-381");
+        bench!(Fq128, "fp128");
+    }
+    group.finish();
+}
+
+fn reed_solomon_erasure(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reed-solomon/reed-solomon-erasure");
+    let rs = ReedSolomon::new(K, N - K).unwrap();
+    for nb_bytes in NB_BYTES {
+        let mut shards = split(&data(nb_bytes));
+        shards.resize(N, vec![0; shards[0].len()]);
+        rs.encode(&mut shards).unwrap();
+
+        group.throughput(Throughput::Bytes(nb_bytes as u64));
+        group.bench_function(BenchmarkId::new("encode", nb_bytes), |b| {
+            b.iter_batched(
+                || shards.clone(),
+                |mut shards| rs.encode(&mut shards).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("decode", nb_bytes), |b| {
+            b.iter_batched(
+                || {
+                    let mut lossy: Vec<_> = shards.iter().cloned().map(Some).collect();
+                    lossy[..N - K].fill(None);
+                    lossy
+                },
+                |mut lossy| rs.reconstruct_data(&mut lossy).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn reed_solomon_simd(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reed-solomon/reed-solomon-simd");
+    for nb_bytes in NB_BYTES {
+        let original = split(&data(nb_bytes));
+        let recovery = reed_solomon_simd::encode(K, N - K, &original).unwrap();
+
+        group.throughput(Throughput::Bytes(nb_bytes as u64));
+        group.bench_function(BenchmarkId::new("encode", nb_bytes), |b| {
+            b.iter(|| reed_solomon_simd::encode(K, N - K, black_box(&original)).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("decode", nb_bytes), |b| {
+            b.iter(|| {
+                reed_solomon_simd::decode(
+                    K,
+                    N - K,
+                    std::iter::empty::<(usize, &Vec<u8>)>(),
+                    recovery.iter().enumerate(),
+                )
+                .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, komodo, reed_solomon_erasure, reed_solomon_simd);
+criterion_main!(benches);
@@ -0,0 +1,230 @@
+//! the rows written by the benchmarks, with a stable and versioned schema
+//!
+//! every measure is a single row, written and flushed as soon as it is known, so that the results
+//! can be streamed to a file and read while the benchmarks are still running.
+//!
+//! # Schema
+//! the current version of the schema is [`SCHEMA_VERSION`], each row has the following fields, in
+//! this order
+//! - `schema`: the version of the schema, i.e. [`SCHEMA_VERSION`]
+//! - `protocol`: the protocol or the operation being measured, e.g. `semi-avid` or `fec`
+//! - `curve`: the curve or the finite field of the measure, e.g. `BLS12-381`
+//! - `params`: the parameters of the measure, e.g. the number of bytes, $k$ and $n$
+//! - `metric`: what is being measured, e.g. `proving` or `proof`
+//! - `value`: the value of the measure, an integer
+//! - `unit`: the unit of the value, either `ns` or `B`, see [`Unit`]
+//!
+//! ## NDJSON
+//! one JSON object per line, where `params` is an object, e.g.
+//! ```json
+//! {"schema": 1, "protocol": "semi-avid", "curve": "BLS12-381", "params": {"bytes": 1024, "k": 4, "n": 8}, "metric": "proof", "value": 384, "unit": "B"}
+//! ```
+//!
+//! ## CSV
+//! a header line with the names of the fields, then one line per row, where `params` is a list of
+//! `key=value` separated by `;`, e.g.
+//! ```text
+//! schema,protocol,curve,params,metric,value,unit
+//! 1,semi-avid,BLS12-381,bytes=1024;k=4;n=8,proof,384,B
+//! ```
+//!
+//! > **Note**
+//! >
+//! > fields will only be added at the end of the rows and a change to the meaning of an existing
+//! > field will bump [`SCHEMA_VERSION`].
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+
+/// the version of the schema of the rows, see the crate documentation
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// the format of the rows
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Format {
+    /// one JSON object per line
+    #[default]
+    Ndjson,
+    /// comma-separated values, with a header
+    Csv,
+}
+
+/// the unit of the value of a row
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    /// a duration, in nanoseconds
+    Nanoseconds,
+    /// a size, in bytes
+    Bytes,
+}
+
+impl Unit {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Unit::Nanoseconds => "ns",
+            Unit::Bytes => "B",
+        }
+    }
+}
+
+/// the value of a parameter of a row
+#[derive(Clone, Debug, PartialEq)]
+pub enum Param {
+    Int(usize),
+    Str(String),
+}
+
+impl From<usize> for Param {
+    fn from(value: usize) -> Self {
+        Param::Int(value)
+    }
+}
+
+impl From<&str> for Param {
+    fn from(value: &str) -> Self {
+        Param::Str(value.to_string())
+    }
+}
+
+impl Param {
+    fn json(&self) -> String {
+        match self {
+            Param::Int(x) => x.to_string(),
+            Param::Str(s) => format!("{:?}", s),
+        }
+    }
+
+    fn csv(&self) -> String {
+        match self {
+            Param::Int(x) => x.to_string(),
+            Param::Str(s) => s.clone(),
+        }
+    }
+}
+
+/// a single measure
+pub struct Row<'a> {
+    pub protocol: &'a str,
+    pub curve: &'a str,
+    pub params: &'a [(&'a str, Param)],
+    pub metric: &'a str,
+    pub value: u128,
+    pub unit: Unit,
+}
+
+/// write [`Row`]s to `W` in a given [`Format`]
+pub struct Report<W: Write> {
+    out: W,
+    format: Format,
+    has_header: bool,
+}
+
+impl Report<io::Stdout> {
+    /// a report written to _stdout_
+    pub fn stdout(format: Format) -> Self {
+        Self::new(io::stdout(), format)
+    }
+}
+
+impl<W: Write> Report<W> {
+    pub fn new(out: W, format: Format) -> Self {
+        Self {
+            out,
+            format,
+            has_header: false,
+        }
+    }
+
+    /// write and flush a single row, preceded by the header the first time with CSV
+    pub fn write(&mut self, row: &Row) -> io::Result<()> {
+        match self.format {
+            Format::Ndjson => {
+                // NOTE: the `Debug` representation of the strings is valid JSON, as long as they
+                // are made of printable ASCII characters, which is the case of all the names
+                let params = row
+                    .params
+                    .iter()
+                    .map(|(k, v)| format!("{:?}: {}", k, v.json()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                writeln!(
+                    self.out,
+                    r#"{{"schema": {}, "protocol": {:?}, "curve": {:?}, "params": {{{}}}, "metric": {:?}, "value": {}, "unit": {:?}}}"#,
+                    SCHEMA_VERSION,
+                    row.protocol,
+                    row.curve,
+                    params,
+                    row.metric,
+                    row.value,
+                    row.unit.as_str(),
+                )?;
+            }
+            Format::Csv => {
+                if !self.has_header {
+                    writeln!(self.out, "schema,protocol,curve,params,metric,value,unit")?;
+                    self.has_header = true;
+                }
+                let params = row
+                    .params
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v.csv()))
+                    .collect::<Vec<_>>()
+                    .join(";");
+                writeln!(
+                    self.out,
+                    "{},{},{},{},{},{},{}",
+                    SCHEMA_VERSION,
+                    row.protocol,
+                    row.curve,
+                    params,
+                    row.metric,
+                    row.value,
+                    row.unit.as_str(),
+                )?;
+            }
+        }
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Format, Report, Row, Unit};
+
+    fn render(format: Format) -> String {
+        let mut out = vec![];
+        let mut report = Report::new(&mut out, format);
+        for (metric, value) in [("proving", 123), ("verifying", 45)] {
+            report
+                .write(&Row {
+                    protocol: "semi-avid",
+                    curve: "BLS12-381",
+                    params: &[("bytes", 1024.into()), ("hash", "sha3-512".into())],
+                    metric,
+                    value,
+                    unit: Unit::Nanoseconds,
+                })
+                .unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            render(Format::Ndjson),
+            concat!(
+                r#"{"schema": 1, "protocol": "semi-avid", "curve": "BLS12-381", "params": {"bytes": 1024, "hash": "sha3-512"}, "metric": "proving", "value": 123, "unit": "ns"}"#,
+                "\n",
+                r#"{"schema": 1, "protocol": "semi-avid", "curve": "BLS12-381", "params": {"bytes": 1024, "hash": "sha3-512"}, "metric": "verifying", "value": 45, "unit": "ns"}"#,
+                "\n",
+            )
+        );
+        assert_eq!(
+            render(Format::Csv),
+            "schema,protocol,curve,params,metric,value,unit\n\
+             1,semi-avid,BLS12-381,bytes=1024;hash=sha3-512,proving,123,ns\n\
+             1,semi-avid,BLS12-381,bytes=1024;hash=sha3-512,verifying,45,ns\n"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+// see `benchmarks/README.md`
+//
+// a single cell of a sweep, i.e. the end-to-end measure of one protocol, on one curve, with given
+// $k$, $n$ and number of bytes, written as rows of `benchmarks::report`.
+use std::{
+    ops::Div,
+    time::{Duration, Instant},
+};
+
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::PrimeField;
+use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial};
+use ark_poly_commit::kzg10::KZG10;
+use benchmarks::report::{Format, Report, Row, Unit};
+use clap::{Parser, ValueEnum};
+use dragoonfri::algorithms::Sha3_512;
+use komodo::{
+    algebra,
+    algebra::linalg::Matrix,
+    aplonk, fec, fri, kzg, semi_avid,
+    zk::{self, trim},
+};
+use rand::RngCore;
+
+/// the duration of each step of a protocol, in order
+type Steps = Vec<(&'static str, Duration)>;
+
+fn timeit<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let res = f();
+    (res, start.elapsed())
+}
+
+fn vandermonde<F: PrimeField>(k: usize, n: usize) -> (Vec<F>, Matrix<F>) {
+    let points: Vec<F> = (0..n)
+        .map(|i| F::from_le_bytes_mod_order(&i.to_le_bytes()))
+        .collect();
+    let encoding_mat = Matrix::vandermonde_unchecked(&points, k);
+    (points, encoding_mat)
+}
+
+fn polynomials<F: PrimeField, P: DenseUVPolynomial<F>>(bytes: &[u8], k: usize) -> Vec<P> {
+    algebra::split_data_into_field_elements::<F>(bytes, k)
+        .chunks(k)
+        .map(|c| P::from_coefficients_vec(c.to_vec()))
+        .collect()
+}
+
+fn semi_avid<F, G, P>(bytes: &[u8], k: usize, n: usize, rng: &mut impl RngCore) -> Steps
+where
+    F: PrimeField,
+    G: CurveGroup<ScalarField = F>,
+    P: DenseUVPolynomial<F>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    let encoding_mat = Matrix::random(k, n, rng);
+    let (powers, t_setup) =
+        timeit(|| zk::setup::<F, G>(zk::nb_elements_in_setup::<F>(bytes.len()), rng).unwrap());
+    let (shards, t_encode) = timeit(|| fec::encode::<F>(bytes, &encoding_mat).unwrap());
+    let (proof, t_prove) = timeit(|| semi_avid::prove::<F, G, P>(bytes, &powers, k).unwrap());
+    let blocks = semi_avid::build::<F, G, P>(&shards, &proof);
+    let (_, t_verify) = timeit(|| {
+        for block in &blocks {
+            assert!(semi_avid::verify::<F, G, P>(block, &powers).unwrap());
+        }
+    });
+    let (_, t_decode) = timeit(|| fec::decode::<F>(shards[n - k..].to_vec()).unwrap());
+
+    vec![
+        ("setup", t_setup),
+        ("encode", t_encode),
+        ("prove", t_prove),
+        ("verify", t_verify),
+        ("decode", t_decode),
+    ]
+}
+
+fn kzg<E, P>(bytes: &[u8], k: usize, n: usize, rng: &mut impl RngCore) -> Steps
+where
+    E: Pairing,
+    P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    let ((powers, verifier_key), t_setup) = timeit(|| {
+        let params = KZG10::<E, P>::setup(k - 1, false, rng).unwrap();
+        trim(params, k - 1)
+    });
+    let polynomials = polynomials::<E::ScalarField, P>(bytes, k);
+    let (points, encoding_mat) = vandermonde::<E::ScalarField>(k, n);
+    let (shards, t_encode) =
+        timeit(|| fec::encode::<E::ScalarField>(bytes, &encoding_mat).unwrap());
+    let (blocks, t_prove) = timeit(|| {
+        let (commits, _) = kzg::commit(&powers, &polynomials).unwrap();
+        kzg::prove::<E, P>(commits, polynomials, shards.clone(), points.clone(), powers).unwrap()
+    });
+    let (_, t_verify) = timeit(|| {
+        for (block, point) in blocks.iter().zip(points) {
+            assert!(kzg::verify::<E, P>(block, point, &verifier_key));
+        }
+    });
+    let (_, t_decode) = timeit(|| fec::decode::<E::ScalarField>(shards[n - k..].to_vec()).unwrap());
+
+    vec![
+        ("setup", t_setup),
+        ("encode", t_encode),
+        ("prove", t_prove),
+        ("verify", t_verify),
+        ("decode", t_decode),
+    ]
+}
+
+fn aplonk<E, P>(bytes: &[u8], k: usize, n: usize) -> Result<Steps, String>
+where
+    E: Pairing,
+    P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    let polynomials = polynomials::<E::ScalarField, P>(bytes, k);
+    if !polynomials.len().is_power_of_two() {
+        return Err(format!(
+            "{} polynomials is not a power of 2",
+            polynomials.len()
+        ));
+    }
+    let (params, t_setup) = timeit(|| aplonk::setup::<E, P>(k - 1, polynomials.len()).unwrap());
+    let (_, vk_psi) = trim(params.kzg.clone(), k - 1);
+    let (tau_1, g_1, g_2) = (
+        params.ipa.tau_1,
+        params.kzg.powers_of_g[0].into_group(),
+        params.kzg.h.into_group(),
+    );
+    let (points, encoding_mat) = vandermonde::<E::ScalarField>(k, n);
+    let (shards, t_encode) =
+        timeit(|| fec::encode::<E::ScalarField>(bytes, &encoding_mat).unwrap());
+    let (blocks, t_prove) = timeit(|| {
+        let commit = aplonk::commit(polynomials.clone(), params.clone()).unwrap();
+        aplonk::prove::<E, P>(commit, polynomials, shards.clone(), points.clone(), params).unwrap()
+    });
+    let (_, t_verify) = timeit(|| {
+        for (block, point) in blocks.iter().zip(points) {
+            assert!(aplonk::verify::<E, P>(block, point, &vk_psi, tau_1, g_1, g_2).unwrap());
+        }
+    });
+    let (_, t_decode) = timeit(|| fec::decode::<E::ScalarField>(shards[n - k..].to_vec()).unwrap());
+
+    Ok(vec![
+        ("setup", t_setup),
+        ("encode", t_encode),
+        ("prove", t_prove),
+        ("verify", t_verify),
+        ("decode", t_decode),
+    ])
+}
+
+/// FRI, with a folding factor of $2$, a single query and a remainder of degree $0$
+fn fri<F: PrimeField>(bytes: &[u8], k: usize, n: usize) -> Steps {
+    let ((evaluations, shards), t_encode) = timeit(|| {
+        let evaluations = fri::evaluate::<F>(bytes, k, n);
+        (evaluations.clone(), fri::encode::<F>(bytes, evaluations, k))
+    });
+    let (blocks, t_prove) = timeit(|| {
+        fri::prove::<2, F, Sha3_512, DensePolynomial<F>>(evaluations, shards, n / k, 1, 1).unwrap()
+    });
+    let (_, t_verify) = timeit(|| {
+        for block in &blocks {
+            fri::verify::<2, F, Sha3_512, DensePolynomial<F>>(block.clone(), n, 1).unwrap();
+        }
+    });
+    let (_, t_decode) = timeit(|| fri::decode::<F, Sha3_512>(blocks[..k].to_vec(), n));
+
+    // NOTE: FRI is transparent, there is no setup
+    vec![
+        ("encode", t_encode),
+        ("prove", t_prove),
+        ("verify", t_verify),
+        ("decode", t_decode),
+    ]
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Protocol {
+    SemiAvid,
+    Kzg,
+    Aplonk,
+    Fri,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Curve {
+    BLS12381,
+    BN254,
+    Pallas,
+}
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    #[arg(long, value_enum)]
+    protocol: Protocol,
+    #[arg(long, value_enum)]
+    curve: Curve,
+
+    #[arg(short)]
+    k: usize,
+    #[arg(short)]
+    n: usize,
+    /// the size of the data, in bytes
+    #[arg(long)]
+    bytes: usize,
+
+    /// the number of times the whole protocol is run
+    #[arg(long, default_value_t = 1)]
+    nb_measurements: usize,
+
+    /// the format of the rows, see `benchmarks::report`
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+}
+
+/// run `protocol` once on `curve`, or the reason why the cell is skipped
+fn run(cli: &Cli, bytes: &[u8], rng: &mut impl RngCore) -> Result<Steps, String> {
+    use ark_bls12_381::Bls12_381;
+    use ark_bn254::Bn254;
+
+    type P<F> = DensePolynomial<F>;
+    type Bls = ark_bls12_381::Fr;
+    type Bn = ark_bn254::Fr;
+    type Pallas = ark_pallas::Fr;
+
+    let (k, n) = (cli.k, cli.n);
+    if cli.protocol == Protocol::Fri && !(k.is_power_of_two() && n.is_power_of_two()) {
+        return Err("k and n should be powers of 2".to_string());
+    }
+
+    Ok(match (cli.protocol, cli.curve) {
+        (Protocol::SemiAvid, Curve::BLS12381) => {
+            semi_avid::<Bls, ark_bls12_381::G1Projective, P<Bls>>(bytes, k, n, rng)
+        }
+        (Protocol::SemiAvid, Curve::BN254) => {
+            semi_avid::<Bn, ark_bn254::G1Projective, P<Bn>>(bytes, k, n, rng)
+        }
+        (Protocol::SemiAvid, Curve::Pallas) => {
+            semi_avid::<Pallas, ark_pallas::Projective, P<Pallas>>(bytes, k, n, rng)
+        }
+        (Protocol::Kzg, Curve::BLS12381) => kzg::<Bls12_381, P<Bls>>(bytes, k, n, rng),
+        (Protocol::Kzg, Curve::BN254) => kzg::<Bn254, P<Bn>>(bytes, k, n, rng),
+        (Protocol::Aplonk, Curve::BLS12381) => aplonk::<Bls12_381, P<Bls>>(bytes, k, n)?,
+        (Protocol::Aplonk, Curve::BN254) => aplonk::<Bn254, P<Bn>>(bytes, k, n)?,
+        (Protocol::Fri, Curve::BLS12381) => fri::<Bls>(bytes, k, n),
+        (Protocol::Fri, Curve::BN254) => fri::<Bn>(bytes, k, n),
+        // NOTE: KZG and aPlonK need a pairing and the FFT of FRI a large 2-adicity
+        (Protocol::Kzg | Protocol::Aplonk | Protocol::Fri, Curve::Pallas) => {
+            return Err("not supported on Pallas".to_string())
+        }
+    })
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let (k, n) = (cli.k, cli.n);
+    assert!(
+        (2..=n).contains(&k),
+        "expected 2 <= k <= n, found k = {} and n = {}",
+        k,
+        n
+    );
+
+    let protocol = cli.protocol.to_possible_value().unwrap();
+    let curve = cli.curve.to_possible_value().unwrap();
+    let params = [
+        ("bytes", cli.bytes.into()),
+        ("k", k.into()),
+        ("n", n.into()),
+    ];
+
+    let mut rng = rand::thread_rng();
+    let mut report = Report::stdout(cli.format);
+    for _ in 0..cli.nb_measurements {
+        let mut bytes = vec![0; cli.bytes];
+        rng.fill_bytes(&mut bytes);
+
+        let steps = match run(&cli, &bytes, &mut rng) {
+            Ok(steps) => steps,
+            Err(reason) => {
+                eprintln!("skipping {}: {}", protocol.get_name(), reason);
+                return;
+            }
+        };
+
+        for (metric, time) in steps {
+            report
+                .write(&Row {
+                    protocol: protocol.get_name(),
+                    curve: curve.get_name(),
+                    params: &params,
+                    metric,
+                    value: time.as_nanos(),
+                    unit: Unit::Nanoseconds,
+                })
+                .unwrap();
+        }
+    }
+}
@@ -1,11 +1,14 @@
 // see `examples/benches/README.md`
+use ark_ec::CurveGroup;
 use ark_ff::PrimeField;
-use ark_std::rand::Rng;
+use ark_poly::univariate::DensePolynomial;
+use ark_std::rand::{Rng, RngCore};
 
 use clap::{arg, command, Parser, ValueEnum};
 use komodo::{
-    algebra,
-    fec::{recode_with_coeffs, Shard},
+    algebra::{self, linalg::Matrix},
+    fec::{self, recode_with_coeffs, Shard},
+    semi_avid, zk,
 };
 use plnk::Bencher;
 
@@ -41,13 +44,63 @@ fn bench_template<F: PrimeField>(b: &Bencher, nb_bytes: usize, k: usize, nb_shar
     plnk::bench(
         b,
         &format!(
-            r#"{{"bytes": {}, "shards": {}, "k": {}}}"#,
+            r#"{{"protocol": "fec", "step": "recode", "bytes": {}, "shards": {}, "k": {}}}"#,
             nb_bytes, nb_shards, k
         ),
         || plnk::timeit(|| recode_with_coeffs(&shards, &coeffs)),
     );
 }
 
+/// measure the recoding of `nb_shards` Semi-AVID blocks, proven from `nb_bytes`, and the
+/// verification of the recoded block, which is what a node receiving it has to do
+fn bench_semi_avid<F: PrimeField, G: CurveGroup<ScalarField = F>>(
+    b: &Bencher,
+    nb_bytes: usize,
+    k: usize,
+    nb_shards: usize,
+) {
+    let mut rng = rand::thread_rng();
+    let mut bytes = vec![0; nb_bytes];
+    rng.fill_bytes(&mut bytes);
+
+    let powers = zk::setup::<F, G>(zk::nb_elements_in_setup::<F>(nb_bytes), &mut rng).unwrap();
+    let shards = fec::encode::<F>(&bytes, &Matrix::random(k, nb_shards, &mut rng)).unwrap();
+    let proof = semi_avid::prove::<F, G, DensePolynomial<F>>(&bytes, &powers, k).unwrap();
+    let blocks = semi_avid::build::<F, G, DensePolynomial<F>>(&shards, &proof);
+
+    let coeffs: Vec<F> = (0..nb_shards)
+        .map(|_| to_curve::<F>(rng.gen::<u128>()))
+        .collect();
+    let recoded = semi_avid::recode_with_coeffs(&blocks, &coeffs)
+        .unwrap()
+        .unwrap();
+
+    let label = |step: &str| {
+        format!(
+            r#"{{"protocol": "semi-avid", "step": "{}", "bytes": {}, "shards": {}, "k": {}}}"#,
+            step, nb_bytes, nb_shards, k
+        )
+    };
+    plnk::bench(b, &label("recode"), || {
+        plnk::timeit(|| semi_avid::recode_with_coeffs(&blocks, &coeffs))
+    });
+    plnk::bench(b, &label("verify"), || {
+        plnk::timeit(|| semi_avid::verify::<F, G, DensePolynomial<F>>(&recoded, &powers))
+    });
+}
+
+/// the protocols whose recoding can be measured
+///
+/// > **Note**
+/// >
+/// > KZG, aPlonK and FRI do not support recoding, their proofs are bound to the evaluation points
+/// > of the original shards.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Fec,
+    SemiAvid,
+}
+
 #[derive(ValueEnum, Clone, Hash, PartialEq, Eq)]
 enum Curve {
     BLS12381,
@@ -70,6 +123,17 @@ struct Cli {
     #[arg(short, long, num_args=1.., value_delimiter = ' ')]
     curves: Vec<Curve>,
 
+    /// the protocols to measure, the generic recoding of the shards by default
+    #[arg(
+        short,
+        long,
+        value_enum,
+        num_args = 1..,
+        value_delimiter = ' ',
+        default_values_t = [Protocol::Fec]
+    )]
+    protocols: Vec<Protocol>,
+
     /// the number of measurements to repeat each case, larger values will reduce the variance of
     /// the measurements
     #[arg(short, long)]
@@ -85,22 +149,53 @@ fn main() {
         for s in &cli.shards {
             for k in &cli.ks {
                 for curve in &cli.curves {
-                    match curve {
-                        Curve::BLS12381 => bench_template::<ark_bls12_381::Fr>(
-                            &bencher.with_name("BLS12-381"),
-                            b,
-                            *k,
-                            *s,
-                        ),
-                        Curve::BN254 => {
-                            bench_template::<ark_bn254::Fr>(&bencher.with_name("BN254"), b, *k, *s)
+                    for protocol in &cli.protocols {
+                        match (protocol, curve) {
+                            (Protocol::Fec, Curve::BLS12381) => {
+                                bench_template::<ark_bls12_381::Fr>(
+                                    &bencher.with_name("BLS12-381"),
+                                    b,
+                                    *k,
+                                    *s,
+                                )
+                            }
+                            (Protocol::Fec, Curve::BN254) => bench_template::<ark_bn254::Fr>(
+                                &bencher.with_name("BN254"),
+                                b,
+                                *k,
+                                *s,
+                            ),
+                            (Protocol::Fec, Curve::Pallas) => bench_template::<ark_pallas::Fr>(
+                                &bencher.with_name("PALLAS"),
+                                b,
+                                *k,
+                                *s,
+                            ),
+                            (Protocol::SemiAvid, Curve::BLS12381) => {
+                                bench_semi_avid::<ark_bls12_381::Fr, ark_bls12_381::G1Projective>(
+                                    &bencher.with_name("BLS12-381"),
+                                    b,
+                                    *k,
+                                    *s,
+                                )
+                            }
+                            (Protocol::SemiAvid, Curve::BN254) => {
+                                bench_semi_avid::<ark_bn254::Fr, ark_bn254::G1Projective>(
+                                    &bencher.with_name("BN254"),
+                                    b,
+                                    *k,
+                                    *s,
+                                )
+                            }
+                            (Protocol::SemiAvid, Curve::Pallas) => {
+                                bench_semi_avid::<ark_pallas::Fr, ark_pallas::Projective>(
+                                    &bencher.with_name("PALLAS"),
+                                    b,
+                                    *k,
+                                    *s,
+                                )
+                            }
                         }
-                        Curve::Pallas => bench_template::<ark_pallas::Fr>(
-                            &bencher.with_name("PALLAS"),
-                            b,
-                            *k,
-                            *s,
-                        ),
                     }
                 }
             }
@@ -0,0 +1,212 @@
+// see `benchmarks/README.md`
+use ark_bls12_381::{Bls12_381, Fr, G1Projective};
+use ark_ff::PrimeField;
+use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial};
+use ark_poly_commit::kzg10::KZG10;
+use ark_serialize::{CanonicalSerialize, Compress};
+use benchmarks::report::{Format, Report, Row, Unit};
+use clap::{Parser, ValueEnum};
+use dragoonfri::algorithms::Sha3_512;
+use komodo::{
+    algebra,
+    algebra::linalg::Matrix,
+    aplonk, fec, fri, kzg, semi_avid,
+    zk::{self, trim},
+};
+use rand::RngCore;
+
+type P = DensePolynomial<Fr>;
+
+const COMPRESS: Compress = Compress::Yes;
+
+/// the serialized sizes of a single block and of the setup, in bytes
+///
+/// the size of a block is the sum of the sizes of its shard, its commitments and its proof.
+struct Sizes {
+    shard: usize,
+    commit: usize,
+    proof: usize,
+    setup: usize,
+}
+
+fn random_bytes(n: usize, rng: &mut impl RngCore) -> Vec<u8> {
+    let mut bytes = vec![0; n];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn vandermonde(k: usize, n: usize) -> (Vec<Fr>, Matrix<Fr>) {
+    let points: Vec<Fr> = (0..n)
+        .map(|i| Fr::from_le_bytes_mod_order(&i.to_le_bytes()))
+        .collect();
+    let encoding_mat = Matrix::vandermonde_unchecked(&points, k);
+    (points, encoding_mat)
+}
+
+fn polynomials(bytes: &[u8], k: usize) -> Vec<P> {
+    algebra::split_data_into_field_elements::<Fr>(bytes, k)
+        .chunks(k)
+        .map(|c| P::from_coefficients_vec(c.to_vec()))
+        .collect()
+}
+
+fn semi_avid(bytes: &[u8], k: usize, n: usize, rng: &mut impl RngCore) -> Sizes {
+    let powers =
+        zk::setup::<Fr, G1Projective>(zk::nb_elements_in_setup::<Fr>(bytes.len()), rng).unwrap();
+    let shards = fec::encode::<Fr>(bytes, &Matrix::random(k, n, rng)).unwrap();
+    let proof = semi_avid::prove::<Fr, G1Projective, P>(bytes, &powers, k).unwrap();
+    let blocks = semi_avid::build::<Fr, G1Projective, P>(&shards, &proof);
+
+    // NOTE: the commitments of Semi-AVID are its proof, there is nothing else in a block
+    let shard = blocks[0].shard.serialized_size(COMPRESS);
+    Sizes {
+        shard,
+        commit: blocks[0].serialized_size(COMPRESS) - shard,
+        proof: 0,
+        setup: powers.serialized_size(COMPRESS),
+    }
+}
+
+fn kzg(bytes: &[u8], k: usize, n: usize, rng: &mut impl RngCore) -> Sizes {
+    let params = KZG10::<Bls12_381, P>::setup(k - 1, false, rng).unwrap();
+    let setup = params.serialized_size(COMPRESS);
+    let (powers, _) = trim(params, k - 1);
+
+    let polynomials = polynomials(bytes, k);
+    let (commits, _) = kzg::commit(&powers, &polynomials).unwrap();
+    let (points, encoding_mat) = vandermonde(k, n);
+    let shards = fec::encode::<Fr>(bytes, &encoding_mat).unwrap();
+    let blocks = kzg::prove::<Bls12_381, P>(commits, polynomials, shards, points, powers).unwrap();
+
+    let shard = blocks[0].shard.serialized_size(COMPRESS);
+    let commit = blocks[0].commits().serialized_size(COMPRESS);
+    Sizes {
+        shard,
+        commit,
+        proof: blocks[0].serialized_size(COMPRESS) - shard - commit,
+        setup,
+    }
+}
+
+fn aplonk(bytes: &[u8], k: usize, n: usize) -> Sizes {
+    let polynomials = polynomials(bytes, k);
+    let params = aplonk::setup::<Bls12_381, P>(k - 1, polynomials.len()).unwrap();
+    let setup = params.serialized_size(COMPRESS);
+
+    let commit = aplonk::commit(polynomials.clone(), params.clone()).unwrap();
+    let (points, encoding_mat) = vandermonde(k, n);
+    let shards = fec::encode::<Fr>(bytes, &encoding_mat).unwrap();
+    let blocks =
+        aplonk::prove::<Bls12_381, P>(commit, polynomials, shards, points, params).unwrap();
+
+    let shard = blocks[0].shard.serialized_size(COMPRESS);
+    let commit = blocks[0].commit().serialized_size(COMPRESS);
+    Sizes {
+        shard,
+        commit,
+        proof: blocks[0].serialized_size(COMPRESS) - shard - commit,
+        setup,
+    }
+}
+
+fn fri(bytes: &[u8], k: usize, n: usize) -> Sizes {
+    let evaluations = fri::evaluate::<Fr>(bytes, k, n);
+    let shards = fri::encode::<Fr>(bytes, evaluations.clone(), k);
+    let blocks = fri::prove::<2, Fr, Sha3_512, P>(evaluations, shards, n / k, 2, 1).unwrap();
+
+    // NOTE: FRI is transparent, there is no setup
+    Sizes {
+        shard: blocks[0].shard.serialized_size(COMPRESS),
+        commit: blocks[0].commit.serialized_size(COMPRESS),
+        proof: blocks[0].proof.serialized_size(COMPRESS),
+        setup: 0,
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Protocol {
+    SemiAvid,
+    Kzg,
+    Aplonk,
+    Fri,
+}
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    /// the sizes of the data to consider
+    #[arg(num_args = 1.., value_delimiter = ' ')]
+    sizes: Vec<usize>,
+
+    #[arg(short, long, num_args = 1.., value_delimiter = ' ')]
+    protocols: Vec<Protocol>,
+
+    #[arg(short)]
+    k: usize,
+    #[arg(short)]
+    n: usize,
+
+    /// the format of the rows, see `benchmarks::report`
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let (k, n) = (cli.k, cli.n);
+    assert!(
+        (2..=n).contains(&k),
+        "expected 2 <= k <= n, found k = {} and n = {}",
+        k,
+        n
+    );
+
+    let mut rng = rand::thread_rng();
+    let mut report = Report::stdout(cli.format);
+
+    for nb_bytes in cli.sizes {
+        let bytes = random_bytes(nb_bytes, &mut rng);
+        let nb_polynomials = polynomials(&bytes, k).len();
+
+        for protocol in &cli.protocols {
+            let sizes = match protocol {
+                Protocol::SemiAvid => semi_avid(&bytes, k, n, &mut rng),
+                Protocol::Kzg => kzg(&bytes, k, n, &mut rng),
+                Protocol::Aplonk if !nb_polynomials.is_power_of_two() => {
+                    eprintln!(
+                        "skipping aPlonK for {} bytes: {} polynomials is not a power of 2",
+                        nb_bytes, nb_polynomials
+                    );
+                    continue;
+                }
+                Protocol::Aplonk => aplonk(&bytes, k, n),
+                Protocol::Fri if !(k.is_power_of_two() && n.is_power_of_two()) => {
+                    eprintln!("skipping FRI: k and n should be powers of 2");
+                    continue;
+                }
+                Protocol::Fri => fri(&bytes, k, n),
+            };
+
+            let protocol = protocol.to_possible_value().unwrap();
+            let params = [("bytes", nb_bytes.into()), ("k", k.into()), ("n", n.into())];
+            for (metric, value) in [
+                ("shard", sizes.shard),
+                ("commit", sizes.commit),
+                ("proof", sizes.proof),
+                ("block", sizes.shard + sizes.commit + sizes.proof),
+                ("setup", sizes.setup),
+            ] {
+                report
+                    .write(&Row {
+                        protocol: protocol.get_name(),
+                        curve: "BLS12-381",
+                        params: &params,
+                        metric,
+                        value: value as u128,
+                        unit: Unit::Bytes,
+                    })
+                    .unwrap();
+            }
+        }
+    }
+}
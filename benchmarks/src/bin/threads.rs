@@ -0,0 +1,132 @@
+// see `benchmarks/README.md`
+//
+// the scaling of the operations that run in parallel with the `parallel` feature of Komodo, each
+// one being measured in a Rayon pool of each one of the requested sizes.
+use std::time::{Duration, Instant};
+
+use ark_bls12_381::{Fr, G1Projective};
+use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial};
+use benchmarks::report::{Format, Report, Row, Unit};
+use clap::Parser;
+use komodo::{algebra::linalg::Matrix, fec, semi_avid, zk};
+use rand::RngCore;
+
+type P = DensePolynomial<Fr>;
+
+/// the time of `f`, run in `pool`
+fn timeit_in<T: Send>(pool: &rayon::ThreadPool, f: impl FnOnce() -> T + Send) -> Duration {
+    pool.install(|| {
+        let start = Instant::now();
+        let _ = f();
+        start.elapsed()
+    })
+}
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    /// the sizes of the thread pools
+    #[arg(short, long, num_args = 1.., value_delimiter = ' ', default_values_t = [1, 2, 4, 8])]
+    threads: Vec<usize>,
+
+    /// the size of the data, in bytes
+    #[arg(long)]
+    bytes: usize,
+    #[arg(short)]
+    k: usize,
+    #[arg(short)]
+    n: usize,
+
+    /// the number of measurements of each operation with each pool
+    #[arg(long, default_value_t = 10)]
+    nb_measurements: usize,
+
+    /// the format of the rows, see `benchmarks::report`
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let (k, n) = (cli.k, cli.n);
+
+    let mut rng = rand::thread_rng();
+    let mut bytes = vec![0; cli.bytes];
+    rng.fill_bytes(&mut bytes);
+
+    let mat_a = Matrix::<Fr>::random(n, n, &mut rng);
+    let mat_b = Matrix::<Fr>::random(n, n, &mut rng);
+    let encoding_mat = Matrix::random(k, n, &mut rng);
+    let powers =
+        zk::setup::<Fr, G1Projective>(zk::nb_elements_in_setup::<Fr>(cli.bytes), &mut rng).unwrap();
+    let polynomial = P::rand(zk::nb_elements_in_setup::<Fr>(cli.bytes) - 1, &mut rng);
+    let shards = fec::encode::<Fr>(&bytes, &encoding_mat).unwrap();
+    let proof = semi_avid::prove::<Fr, G1Projective, P>(&bytes, &powers, k).unwrap();
+    let blocks = semi_avid::build::<Fr, G1Projective, P>(&shards, &proof);
+
+    let mut report = Report::stdout(cli.format);
+    for nb_threads in cli.threads {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(nb_threads)
+            .build()
+            .unwrap();
+        let params = [
+            ("bytes", cli.bytes.into()),
+            ("k", k.into()),
+            ("n", n.into()),
+            ("threads", nb_threads.into()),
+        ];
+
+        for _ in 0..cli.nb_measurements {
+            let times = [
+                (
+                    "matrix-mul",
+                    timeit_in(&pool, || mat_a.mul(&mat_b).unwrap()),
+                ),
+                (
+                    "encode",
+                    timeit_in(&pool, || fec::encode::<Fr>(&bytes, &encoding_mat).unwrap()),
+                ),
+                (
+                    "decode",
+                    timeit_in(&pool, || {
+                        fec::decode::<Fr>(shards[n - k..].to_vec()).unwrap()
+                    }),
+                ),
+                (
+                    "commit",
+                    timeit_in(&pool, || zk::commit(&powers, &polynomial).unwrap()),
+                ),
+                (
+                    "prove",
+                    timeit_in(&pool, || {
+                        semi_avid::prove::<Fr, G1Projective, P>(&bytes, &powers, k).unwrap()
+                    }),
+                ),
+                (
+                    "verify",
+                    timeit_in(&pool, || {
+                        for block in &blocks {
+                            assert!(
+                                semi_avid::verify::<Fr, G1Projective, P>(block, &powers).unwrap()
+                            );
+                        }
+                    }),
+                ),
+            ];
+
+            for (metric, time) in times {
+                report
+                    .write(&Row {
+                        protocol: "semi-avid",
+                        curve: "BLS12-381",
+                        params: &params,
+                        metric,
+                        value: time.as_nanos(),
+                        unit: Unit::Nanoseconds,
+                    })
+                    .unwrap();
+            }
+        }
+    }
+}
@@ -1 +1,3 @@
 pub mod fields;
+pub mod suites;
+pub use report;
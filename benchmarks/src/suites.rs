@@ -0,0 +1,147 @@
+//! the suites of measures of the main operations of Komodo, generic over the curve
+//!
+//! the suites are the field operations, the trusted setup and the commitments, the erasure code
+//! and the recoding. they do not repeat nor record anything themselves, this is the role of the
+//! [`Bencher`] they are given, e.g. the one of the `bench` command of SACLIN.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial};
+use ark_std::rand::RngCore;
+use rand::{seq::SliceRandom, Rng};
+
+use komodo::{algebra::linalg::Matrix, fec, zk};
+
+/// repeat and record the measures of the suites
+pub trait Bencher {
+    /// measure the operation `label`
+    ///
+    /// `f` prepares the input of the operation and times a single run of it, e.g. with
+    /// [`timeit`].
+    fn bench(&mut self, label: &str, f: impl FnMut() -> Result<Duration>) -> Result<()>;
+}
+
+/// time a single run of `f`
+pub fn timeit<T>(f: impl FnOnce() -> T) -> Duration {
+    let start = Instant::now();
+    black_box(f());
+    start.elapsed()
+}
+
+/// $n$ random bytes
+pub fn random_bytes(n: usize, rng: &mut impl RngCore) -> Vec<u8> {
+    let mut bytes = vec![0; n];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// the arithmetic of the scalar field, i.e. $9$ measures
+pub fn field<F: PrimeField>(b: &mut impl Bencher, rng: &mut impl RngCore) -> Result<()> {
+    b.bench("random sampling", || Ok(timeit(|| F::rand(rng))))?;
+    b.bench("addition", || {
+        let (f1, f2) = (F::rand(rng), F::rand(rng));
+        Ok(timeit(|| f1 + f2))
+    })?;
+    b.bench("substraction", || {
+        let (f1, f2) = (F::rand(rng), F::rand(rng));
+        Ok(timeit(|| f1 - f2))
+    })?;
+    b.bench("double", || {
+        let f1 = F::rand(rng);
+        Ok(timeit(|| f1.double()))
+    })?;
+    b.bench("multiplication", || {
+        let (f1, f2) = (F::rand(rng), F::rand(rng));
+        Ok(timeit(|| f1 * f2))
+    })?;
+    b.bench("square", || {
+        let f1 = F::rand(rng);
+        Ok(timeit(|| f1.square()))
+    })?;
+    b.bench("inverse", || {
+        let f1 = F::rand(rng);
+        Ok(timeit(|| f1.inverse()))
+    })?;
+    b.bench("exponentiation", || {
+        let f1 = F::rand(rng);
+        Ok(timeit(|| f1.pow(F::MODULUS)))
+    })?;
+    b.bench("into bigint", || {
+        let f1 = F::rand(rng);
+        Ok(timeit(|| f1.into_bigint()))
+    })
+}
+
+/// the setup and the commitment of a polynomial of the degree needed to prove `nb_bytes`, i.e.
+/// $2$ measures
+pub fn commit<F: PrimeField, G: CurveGroup<ScalarField = F>>(
+    b: &mut impl Bencher,
+    nb_bytes: usize,
+    rng: &mut impl RngCore,
+) -> Result<()> {
+    let degree = zk::nb_elements_in_setup::<F>(nb_bytes);
+
+    b.bench(&format!("setup degree {}", degree), || {
+        Ok(timeit(|| zk::setup::<F, G>(degree, rng)))
+    })?;
+
+    let powers = zk::setup::<F, G>(degree, rng)?;
+    b.bench(&format!("commit degree {}", degree), || {
+        let polynomial = DensePolynomial::<F>::rand(degree, rng);
+        Ok(timeit(|| zk::commit(&powers, &polynomial)))
+    })
+}
+
+/// the encoding with a random matrix and the decoding from $k$ random shards, i.e. $2$ measures
+pub fn fec<F: PrimeField>(
+    b: &mut impl Bencher,
+    (nb_bytes, k, n): (usize, usize, usize),
+    rng: &mut impl Rng,
+) -> Result<()> {
+    let label = |step: &str| {
+        format!(
+            r#"{{"bytes": {}, "step": "{}", "method": "random", "k": {}, "n": {}}}"#,
+            nb_bytes, step, k, n
+        )
+    };
+
+    b.bench(&label("encode"), || {
+        let bytes = random_bytes(nb_bytes, rng);
+        let encoding_mat = Matrix::random(k, n, rng);
+        Ok(timeit(|| fec::encode::<F>(&bytes, &encoding_mat)))
+    })?;
+
+    b.bench(&label("decode"), || {
+        let bytes = random_bytes(nb_bytes, rng);
+        let mut shards = fec::encode::<F>(&bytes, &Matrix::random(k, n, rng))?;
+        shards.shuffle(rng);
+        shards.truncate(k);
+        let start = Instant::now();
+        let decoded = fec::decode(shards)?;
+        let elapsed = start.elapsed();
+        if decoded != bytes {
+            return Err(anyhow!("the decoded data is not the original data"));
+        }
+        Ok(elapsed)
+    })
+}
+
+/// the recoding of two shards encoded from `nb_bytes` with random coefficients, i.e. $1$ measure
+pub fn recoding<F: PrimeField>(
+    b: &mut impl Bencher,
+    (nb_bytes, k, n): (usize, usize, usize),
+    rng: &mut impl RngCore,
+) -> Result<()> {
+    b.bench(
+        &format!(r#"{{"bytes": {}, "shards": 2, "k": {}}}"#, nb_bytes, k),
+        || {
+            let bytes = random_bytes(nb_bytes, rng);
+            let shards = fec::encode::<F>(&bytes, &Matrix::random(k, n, rng))?;
+            let coeffs = [F::rand(rng), F::rand(rng)];
+            Ok(timeit(|| fec::recode_with_coeffs(&shards[..2], &coeffs)))
+        },
+    )
+}
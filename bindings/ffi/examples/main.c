@@ -0,0 +1,50 @@
+// encode, prove, verify and decode some data with the C API of Komodo, see `../README.md`
+#include <stdio.h>
+#include <string.h>
+
+#include "komodo.h"
+
+#define K 3
+#define N 5
+
+#define CHECK(call)                                                                              \
+    do {                                                                                         \
+        uint32_t status = (call);                                                                \
+        if (status != KOMODO_OK) {                                                               \
+            fprintf(stderr, "%s failed with %u: %s\n", #call, status, komodo_last_error());      \
+            return 1;                                                                            \
+        }                                                                                        \
+    } while (0)
+
+int main(void) {
+    const char *data = "hello world, this is some data to be stored with Komodo";
+    size_t len = strlen(data);
+
+    KomodoSetup *setup = NULL;
+    CHECK(komodo_setup_new(len, &setup));
+
+    KomodoBuffer shards[N];
+    CHECK(komodo_encode((const uint8_t *)data, len, K, N, shards));
+
+    KomodoBlock *blocks[N];
+    CHECK(komodo_prove(setup, (const uint8_t *)data, len, shards, N, blocks));
+
+    for (size_t i = 0; i < N; i++) {
+        bool valid = false;
+        CHECK(komodo_verify(setup, blocks[i], &valid));
+        printf("block %zu: %s\n", i, valid ? "valid" : "invalid");
+    }
+
+    KomodoBuffer decoded;
+    CHECK(komodo_decode((const KomodoBlock *const *)&blocks[N - K], K, &decoded));
+    printf("decoded: %.*s\n", (int)decoded.len, decoded.data);
+
+    komodo_buffer_free(decoded);
+    for (size_t i = 0; i < N; i++) {
+        komodo_buffer_free(shards[i]);
+        komodo_block_free(blocks[i]);
+    }
+    komodo_setup_free(setup);
+
+    return 0;
+}
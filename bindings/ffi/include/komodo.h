@@ -0,0 +1,60 @@
+/*
+ * the C API of Komodo, see `bindings/ffi/src/lib.rs` for the documentation of each function.
+ *
+ * every function returns a status: KOMODO_OK or an error code, i.e. KOMODO_NULL_POINTER,
+ * KOMODO_PANIC or one of the stable codes of Komodo errors, see `KomodoError::code`. the message
+ * of the last error of the current thread is given by komodo_last_error().
+ */
+#ifndef KOMODO_H
+#define KOMODO_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define KOMODO_OK 0
+#define KOMODO_NULL_POINTER 1
+#define KOMODO_PANIC 2
+
+/* a trusted setup, freed with komodo_setup_free() */
+typedef struct KomodoSetup KomodoSetup;
+
+/* a shard of encoded data together with its proof, freed with komodo_block_free() */
+typedef struct KomodoBlock KomodoBlock;
+
+/* a contiguous sequence of bytes, freed with komodo_buffer_free() when written by Komodo */
+typedef struct KomodoBuffer {
+    uint8_t *data;
+    size_t len;
+} KomodoBuffer;
+
+const char *komodo_last_error(void);
+
+void komodo_buffer_free(KomodoBuffer buffer);
+
+uint32_t komodo_setup_new(size_t nb_bytes, KomodoSetup **out);
+uint32_t komodo_setup_from_bytes(const uint8_t *data, size_t len, KomodoSetup **out);
+uint32_t komodo_setup_to_bytes(const KomodoSetup *setup, KomodoBuffer *out);
+void komodo_setup_free(KomodoSetup *setup);
+
+/* `shards` should have room for `n` buffers */
+uint32_t komodo_encode(const uint8_t *data, size_t len, size_t k, size_t n, KomodoBuffer *shards);
+/* `blocks` should have room for `nb_shards` handles */
+uint32_t komodo_prove(const KomodoSetup *setup, const uint8_t *data, size_t len,
+                      const KomodoBuffer *shards, size_t nb_shards, KomodoBlock **blocks);
+uint32_t komodo_verify(const KomodoSetup *setup, const KomodoBlock *block, bool *valid);
+uint32_t komodo_decode(const KomodoBlock *const *blocks, size_t nb_blocks, KomodoBuffer *out);
+
+uint32_t komodo_block_from_bytes(const uint8_t *data, size_t len, KomodoBlock **out);
+uint32_t komodo_block_to_bytes(const KomodoBlock *block, KomodoBuffer *out);
+void komodo_block_free(KomodoBlock *block);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KOMODO_H */
@@ -0,0 +1,492 @@
+//! a C ABI for Komodo, to embed Semi-AVID in C, C++ or Go storage daemons
+//!
+//! see `include/komodo.h` for the C declarations.
+//!
+//! # Conventions
+//! - every function returns a status: [`KOMODO_OK`] or an error code, i.e. [`KOMODO_NULL_POINTER`],
+//!   [`KOMODO_PANIC`] or the [`KomodoError::code`] of the error. the message of the last error of
+//!   the current thread is given by [`komodo_last_error`]
+//! - the results are written to the "out" pointers, only when the status is [`KOMODO_OK`]
+//! - the trusted setups and the blocks are opaque handles, created by Komodo and freed with
+//!   [`komodo_setup_free`] and [`komodo_block_free`]
+//! - the buffers written by Komodo belong to the caller and are freed with [`komodo_buffer_free`]
+//!
+//! > **Note**
+//! >
+//! > the curve is BLS12-381 and the encoding matrix is random. the serialized setups and blocks
+//! > are the ones of the rest of Komodo, i.e. they can be exchanged with native Rust nodes.
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use ark_bls12_381::{Fr, G1Projective};
+use ark_poly::univariate::DensePolynomial;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use komodo::{
+    algebra::linalg::Matrix,
+    error::KomodoError,
+    fec::{self, Shard},
+    semi_avid::{self, Block},
+    zk::{self, Powers},
+};
+use rand::rngs::OsRng;
+
+type F = Fr;
+type G = G1Projective;
+type P = DensePolynomial<F>;
+
+/// the call succeeded
+pub const KOMODO_OK: u32 = 0;
+/// a pointer that should not be null was null
+pub const KOMODO_NULL_POINTER: u32 = 1;
+/// Komodo panicked, which is a bug
+pub const KOMODO_PANIC: u32 = 2;
+
+/// a trusted setup
+pub struct KomodoSetup(Powers<F, G>);
+
+/// a shard of encoded data together with its proof
+pub struct KomodoBlock(Block<F, G>);
+
+/// a contiguous sequence of bytes
+#[repr(C)]
+pub struct KomodoBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl KomodoBuffer {
+    fn new(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        Self { data, len }
+    }
+}
+
+enum Error {
+    NullPointer,
+    Panic(String),
+    Komodo(KomodoError),
+}
+
+impl Error {
+    fn code(&self) -> u32 {
+        match self {
+            Self::NullPointer => KOMODO_NULL_POINTER,
+            Self::Panic(_) => KOMODO_PANIC,
+            Self::Komodo(e) => e.code(),
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Self::NullPointer => "unexpected null pointer".to_string(),
+            Self::Panic(msg) => format!("Komodo panicked: {}", msg),
+            Self::Komodo(e) => e.to_string(),
+        }
+    }
+}
+
+impl From<KomodoError> for Error {
+    fn from(error: KomodoError) -> Self {
+        Self::Komodo(error)
+    }
+}
+
+impl From<SerializationError> for Error {
+    fn from(error: SerializationError) -> Self {
+        Self::Komodo(error.into())
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// run `f`, catching any panic, and turn its result into a status
+fn status(f: impl FnOnce() -> Result<(), Error>) -> u32 {
+    let res = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(Error::Panic(msg))
+    });
+
+    match res {
+        Ok(()) => KOMODO_OK,
+        Err(error) => {
+            // NOTE: an interior NUL byte is not possible in the messages of Komodo
+            let msg = CString::new(error.message()).ok();
+            LAST_ERROR.with(|last| *last.borrow_mut() = msg);
+            error.code()
+        }
+    }
+}
+
+unsafe fn as_slice<'a, T>(data: *const T, len: usize) -> Result<&'a [T], Error> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        return Err(Error::NullPointer);
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+unsafe fn as_ref<'a, T>(ptr: *const T) -> Result<&'a T, Error> {
+    ptr.as_ref().ok_or(Error::NullPointer)
+}
+
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), Error> {
+    if out.is_null() {
+        return Err(Error::NullPointer);
+    }
+    out.write(value);
+    Ok(())
+}
+
+fn serialize<T: CanonicalSerialize>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![];
+    value.serialize_compressed(&mut bytes)?;
+    Ok(bytes)
+}
+
+// NOTE: the bytes come from the caller, they are always validated
+unsafe fn deserialize<T: CanonicalDeserialize>(data: *const u8, len: usize) -> Result<T, Error> {
+    Ok(T::deserialize_compressed(as_slice(data, len)?)?)
+}
+
+/// the message of the last error of the current thread, or null if there has been none
+///
+/// the message is valid until the next error of the same thread.
+#[no_mangle]
+pub extern "C" fn komodo_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |msg| msg.as_ptr())
+    })
+}
+
+/// free a buffer written by Komodo
+///
+/// # Safety
+/// `buffer` should have been written by Komodo and not have been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_buffer_free(buffer: KomodoBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+/// build a random trusted setup, large enough to prove `nb_bytes` of data
+///
+/// # Safety
+/// `out` should be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_setup_new(nb_bytes: usize, out: *mut *mut KomodoSetup) -> u32 {
+    status(|| {
+        let powers = zk::setup_secure::<F, G>(zk::nb_elements_in_setup::<F>(nb_bytes), &mut OsRng)?;
+        write(out, Box::into_raw(Box::new(KomodoSetup(powers))))
+    })
+}
+
+/// load a trusted setup from its serialized bytes
+///
+/// # Safety
+/// `data` should be valid for `len` reads and `out` should be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_setup_from_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut KomodoSetup,
+) -> u32 {
+    status(|| {
+        let powers = deserialize(data, len)?;
+        write(out, Box::into_raw(Box::new(KomodoSetup(powers))))
+    })
+}
+
+/// serialize a trusted setup
+///
+/// # Safety
+/// `setup` should be a live handle and `out` should be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_setup_to_bytes(
+    setup: *const KomodoSetup,
+    out: *mut KomodoBuffer,
+) -> u32 {
+    status(|| write(out, KomodoBuffer::new(serialize(&as_ref(setup)?.0)?)))
+}
+
+/// free a trusted setup
+///
+/// # Safety
+/// `setup` should be null or a live handle, which is not live anymore afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_setup_free(setup: *mut KomodoSetup) {
+    if !setup.is_null() {
+        drop(Box::from_raw(setup));
+    }
+}
+
+/// encode some data into $n$ serialized shards, any $k$ of which are enough to decode it
+///
+/// # Safety
+/// `data` should be valid for `len` reads and `shards` should be valid for `n` writes.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_encode(
+    data: *const u8,
+    len: usize,
+    k: usize,
+    n: usize,
+    shards: *mut KomodoBuffer,
+) -> u32 {
+    status(|| {
+        let bytes = as_slice(data, len)?;
+        let encoded = fec::encode::<F>(bytes, &Matrix::random(k, n, &mut OsRng))?
+            .iter()
+            .map(serialize)
+            .collect::<Result<Vec<_>, _>>()?;
+        if shards.is_null() {
+            return Err(Error::NullPointer);
+        }
+        for (i, shard) in encoded.into_iter().enumerate() {
+            shards.add(i).write(KomodoBuffer::new(shard));
+        }
+        Ok(())
+    })
+}
+
+/// prove the serialized shards of some data and turn them into blocks, one per shard
+///
+/// # Safety
+/// `setup` should be a live handle, `data` should be valid for `len` reads, `shards` should be
+/// valid for `nb_shards` reads and `blocks` should be valid for `nb_shards` writes.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_prove(
+    setup: *const KomodoSetup,
+    data: *const u8,
+    len: usize,
+    shards: *const KomodoBuffer,
+    nb_shards: usize,
+    blocks: *mut *mut KomodoBlock,
+) -> u32 {
+    status(|| {
+        let powers = &as_ref(setup)?.0;
+        let bytes = as_slice(data, len)?;
+        let shards = as_slice(shards, nb_shards)?
+            .iter()
+            .map(|s| deserialize::<Shard<F>>(s.data, s.len))
+            .collect::<Result<Vec<_>, _>>()?;
+        let Some(k) = shards.first().map(|s| s.k as usize) else {
+            return Ok(());
+        };
+
+        let proof = semi_avid::prove::<F, G, P>(bytes, powers, k)?;
+        if blocks.is_null() {
+            return Err(Error::NullPointer);
+        }
+        for (i, block) in semi_avid::build::<F, G, P>(&shards, &proof)
+            .into_iter()
+            .enumerate()
+        {
+            blocks
+                .add(i)
+                .write(Box::into_raw(Box::new(KomodoBlock(block))));
+        }
+        Ok(())
+    })
+}
+
+/// verify a single block against a trusted setup
+///
+/// # Safety
+/// `setup` and `block` should be live handles and `valid` should be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_verify(
+    setup: *const KomodoSetup,
+    block: *const KomodoBlock,
+    valid: *mut bool,
+) -> u32 {
+    status(|| {
+        let res = semi_avid::verify::<F, G, P>(&as_ref(block)?.0, &as_ref(setup)?.0)?;
+        write(valid, res)
+    })
+}
+
+/// decode the original data from at least $k$ blocks
+///
+/// # Safety
+/// `blocks` should be valid for `nb_blocks` reads of live handles and `out` should be valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_decode(
+    blocks: *const *const KomodoBlock,
+    nb_blocks: usize,
+    out: *mut KomodoBuffer,
+) -> u32 {
+    status(|| {
+        let shards = as_slice(blocks, nb_blocks)?
+            .iter()
+            .map(|b| as_ref(*b).map(|b| b.0.shard.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        write(out, KomodoBuffer::new(fec::decode(shards)?))
+    })
+}
+
+/// load a block from its serialized bytes
+///
+/// # Safety
+/// `data` should be valid for `len` reads and `out` should be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_block_from_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut KomodoBlock,
+) -> u32 {
+    status(|| {
+        let block = deserialize(data, len)?;
+        write(out, Box::into_raw(Box::new(KomodoBlock(block))))
+    })
+}
+
+/// serialize a block, e.g. to send it over the network
+///
+/// # Safety
+/// `block` should be a live handle and `out` should be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_block_to_bytes(
+    block: *const KomodoBlock,
+    out: *mut KomodoBuffer,
+) -> u32 {
+    status(|| write(out, KomodoBuffer::new(serialize(&as_ref(block)?.0)?)))
+}
+
+/// free a block
+///
+/// # Safety
+/// `block` should be null or a live handle, which is not live anymore afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn komodo_block_free(block: *mut KomodoBlock) {
+    if !block.is_null() {
+        drop(Box::from_raw(block));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::CStr, ptr};
+
+    use super::*;
+
+    fn bytes() -> Vec<u8> {
+        include_bytes!("../../../assets/dragoon_32x32.png").to_vec()
+    }
+
+    #[test]
+    fn end_to_end() {
+        let bytes = bytes();
+        let (k, n) = (3, 5);
+
+        unsafe {
+            let mut setup = ptr::null_mut();
+            assert_eq!(komodo_setup_new(bytes.len(), &mut setup), KOMODO_OK);
+
+            let mut shards: Vec<KomodoBuffer> = Vec::with_capacity(n);
+            let status = komodo_encode(bytes.as_ptr(), bytes.len(), k, n, shards.as_mut_ptr());
+            assert_eq!(status, KOMODO_OK);
+            shards.set_len(n);
+
+            let mut blocks = vec![ptr::null_mut(); n];
+            let status = komodo_prove(
+                setup,
+                bytes.as_ptr(),
+                bytes.len(),
+                shards.as_ptr(),
+                n,
+                blocks.as_mut_ptr(),
+            );
+            assert_eq!(status, KOMODO_OK);
+
+            // NOTE: the blocks go through their serialized form, as if sent over the network
+            let mut received = vec![];
+            for block in &blocks {
+                let mut buffer = KomodoBuffer {
+                    data: ptr::null_mut(),
+                    len: 0,
+                };
+                assert_eq!(komodo_block_to_bytes(*block, &mut buffer), KOMODO_OK);
+                let mut r = ptr::null_mut();
+                assert_eq!(
+                    komodo_block_from_bytes(buffer.data, buffer.len, &mut r),
+                    KOMODO_OK
+                );
+                komodo_buffer_free(buffer);
+
+                let mut valid = false;
+                assert_eq!(komodo_verify(setup, r, &mut valid), KOMODO_OK);
+                assert!(valid);
+                received.push(r as *const KomodoBlock);
+            }
+
+            let mut decoded = KomodoBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(
+                komodo_decode(received[n - k..].as_ptr(), k, &mut decoded),
+                KOMODO_OK
+            );
+            assert_eq!(slice::from_raw_parts(decoded.data, decoded.len), bytes);
+            komodo_buffer_free(decoded);
+
+            for shard in shards {
+                komodo_buffer_free(shard);
+            }
+            for block in blocks
+                .into_iter()
+                .chain(received.into_iter().map(|b| b as _))
+            {
+                komodo_block_free(block);
+            }
+            komodo_setup_free(setup);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        unsafe {
+            let mut setup = ptr::null_mut();
+            assert_eq!(komodo_setup_new(0, &mut setup), 301);
+            assert_eq!(komodo_setup_new(1024, ptr::null_mut()), KOMODO_NULL_POINTER);
+            assert_eq!(
+                CStr::from_ptr(komodo_last_error()).to_str().unwrap(),
+                "unexpected null pointer"
+            );
+
+            let invalid = [1u8, 2, 3];
+            let status = komodo_setup_from_bytes(invalid.as_ptr(), invalid.len(), &mut setup);
+            assert_eq!(status, 400);
+            assert!(setup.is_null());
+
+            let mut decoded = KomodoBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(komodo_decode(ptr::null(), 0, &mut decoded), 200);
+            assert!(CStr::from_ptr(komodo_last_error())
+                .to_str()
+                .unwrap()
+                .starts_with("Expected at least"));
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! JS bindings of Komodo, to run Semi-AVID in browsers and JS runtimes
+//!
+//! all the values cross the boundary as byte arrays, i.e. the compressed serialization of
+//! _arkworks_, which makes it possible to verify blocks client-side, e.g. blocks that have been
+//! proven by a native Komodo node:
+//! - the trusted setup, as given by [`setup`]
+//! - one array per shard, as given by [`encode`]
+//! - one array per block, as given by [`prove`]
+//!
+//! > **Note**
+//! >
+//! > the curve is BLS12-381 and the encoding matrix is random.
+//!
+//! # Example
+//! ```js
+//! import init, { setup, encode, prove, verify, decode } from "./pkg/komodo_wasm.js";
+//!
+//! await init();
+//! const bytes = new TextEncoder().encode("hello world");
+//! const powers = setup(bytes.length);
+//! const blocks = prove(bytes, encode(bytes, 3, 5), powers);
+//! console.assert(blocks.every((b) => verify(b, powers)));
+//! console.assert(new TextDecoder().decode(decode(blocks.slice(2))) === "hello world");
+//! ```
+use ark_bls12_381::{Fr, G1Projective};
+use ark_poly::univariate::DensePolynomial;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use js_sys::Uint8Array;
+use komodo::{
+    algebra::linalg::Matrix,
+    error::KomodoError,
+    fec::{self, Shard},
+    semi_avid::{self, Block},
+    zk::{self, Powers},
+};
+use rand::rngs::OsRng;
+use wasm_bindgen::prelude::*;
+
+type F = Fr;
+type G = G1Projective;
+type P = DensePolynomial<F>;
+
+fn ser<T: CanonicalSerialize>(value: &T) -> Result<Vec<u8>, KomodoError> {
+    let mut bytes = vec![];
+    value.serialize_compressed(&mut bytes)?;
+    Ok(bytes)
+}
+
+// NOTE: the values come from untrusted JS code, they are always validated
+fn de<T: CanonicalDeserialize>(bytes: &[u8]) -> Result<T, KomodoError> {
+    Ok(T::deserialize_compressed(bytes)?)
+}
+
+fn to_js(arrays: Vec<Vec<u8>>) -> Vec<Uint8Array> {
+    arrays
+        .iter()
+        .map(|a| Uint8Array::from(a.as_slice()))
+        .collect()
+}
+
+fn from_js(arrays: Vec<Uint8Array>) -> Vec<Vec<u8>> {
+    arrays.iter().map(Uint8Array::to_vec).collect()
+}
+
+mod api {
+    use super::*;
+
+    pub(super) fn setup(nb_bytes: usize) -> Result<Vec<u8>, KomodoError> {
+        ser(&zk::setup_secure::<F, G>(
+            zk::nb_elements_in_setup::<F>(nb_bytes),
+            &mut OsRng,
+        )?)
+    }
+
+    pub(super) fn encode(bytes: &[u8], k: usize, n: usize) -> Result<Vec<Vec<u8>>, KomodoError> {
+        fec::encode::<F>(bytes, &Matrix::random(k, n, &mut OsRng))?
+            .iter()
+            .map(ser)
+            .collect()
+    }
+
+    pub(super) fn prove(
+        bytes: &[u8],
+        shards: &[Vec<u8>],
+        powers: &[u8],
+    ) -> Result<Vec<Vec<u8>>, KomodoError> {
+        let shards = shards
+            .iter()
+            .map(|s| de::<Shard<F>>(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let Some(k) = shards.first().map(|s| s.k as usize) else {
+            return Ok(vec![]);
+        };
+
+        let proof = semi_avid::prove::<F, G, P>(bytes, &de::<Powers<F, G>>(powers)?, k)?;
+        semi_avid::build::<F, G, P>(&shards, &proof)
+            .iter()
+            .map(ser)
+            .collect()
+    }
+
+    pub(super) fn verify(block: &[u8], powers: &[u8]) -> Result<bool, KomodoError> {
+        semi_avid::verify::<F, G, P>(&de::<Block<F, G>>(block)?, &de::<Powers<F, G>>(powers)?)
+    }
+
+    pub(super) fn decode(blocks: &[Vec<u8>]) -> Result<Vec<u8>, KomodoError> {
+        fec::decode::<F>(
+            blocks
+                .iter()
+                .map(|b| de::<Block<F, G>>(b).map(|b| b.shard))
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    }
+}
+
+/// build a random trusted setup, large enough to prove `nb_bytes` of data
+#[wasm_bindgen]
+pub fn setup(nb_bytes: usize) -> Result<Vec<u8>, JsError> {
+    Ok(api::setup(nb_bytes)?)
+}
+
+/// encode some data into $n$ shards, any $k$ of which are enough to decode it
+#[wasm_bindgen]
+pub fn encode(bytes: &[u8], k: usize, n: usize) -> Result<Vec<Uint8Array>, JsError> {
+    Ok(to_js(api::encode(bytes, k, n)?))
+}
+
+/// prove the shards of some data and turn them into blocks
+#[wasm_bindgen]
+pub fn prove(
+    bytes: &[u8],
+    shards: Vec<Uint8Array>,
+    powers: &[u8],
+) -> Result<Vec<Uint8Array>, JsError> {
+    Ok(to_js(api::prove(bytes, &from_js(shards), powers)?))
+}
+
+/// verify a single block against the trusted setup
+#[wasm_bindgen]
+pub fn verify(block: &[u8], powers: &[u8]) -> Result<bool, JsError> {
+    Ok(api::verify(block, powers)?)
+}
+
+/// decode the original data from at least $k$ blocks
+#[wasm_bindgen]
+pub fn decode(blocks: Vec<Uint8Array>) -> Result<Vec<u8>, JsError> {
+    Ok(api::decode(&from_js(blocks))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::api::{decode, encode, prove, setup, verify};
+
+    #[test]
+    fn end_to_end() {
+        let bytes = include_bytes!("../../../assets/dragoon_32x32.png").to_vec();
+        let (k, n) = (3, 5);
+
+        let powers = setup(bytes.len()).unwrap();
+        let blocks = prove(&bytes, &encode(&bytes, k, n).unwrap(), &powers).unwrap();
+        assert_eq!(blocks.len(), n);
+        for block in &blocks {
+            assert!(verify(block, &powers).unwrap());
+        }
+        assert_eq!(decode(&blocks[n - k..]).unwrap(), bytes);
+
+        assert!(verify(&blocks[0][1..], &powers).is_err());
+    }
+}
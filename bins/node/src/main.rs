@@ -0,0 +1,107 @@
+//! a Komodo node, see `komodo::network`
+//!
+//! the node stores the Semi-AVID blocks pushed to it, serves them to the other nodes and, with
+//! `--replicate`, fetches all the missing blocks of the files announced on the network.
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::Result;
+use ark_bls12_381::{Fr, G1Projective};
+use ark_ff::One;
+use ark_poly::univariate::DensePolynomial;
+use ark_serialize::{Compress, Validate};
+use clap::Parser;
+use libp2p::{identity::Keypair, Multiaddr};
+use tracing::{info, warn};
+
+use komodo::{
+    algebra::linalg::Matrix,
+    fs,
+    network::{Config, Event, Node},
+    semi_avid::SemiAvid,
+    zk::Powers,
+};
+
+const COMPRESS: Compress = Compress::Yes;
+const VALIDATE: Validate = Validate::Yes;
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    /// the address to listen on
+    #[arg(long, default_value = "/ip4/0.0.0.0/tcp/0")]
+    listen: Multiaddr,
+    /// the addresses of the nodes to connect to when starting
+    #[arg(long, num_args = 0..)]
+    peers: Vec<Multiaddr>,
+
+    /// the trusted setup of Semi-AVID, e.g. the one of SACLIN
+    #[arg(long)]
+    setup: PathBuf,
+    /// where to store the blocks and the manifests
+    #[arg(long)]
+    home: PathBuf,
+
+    /// fetch all the missing blocks of the files announced by the other nodes
+    #[arg(long)]
+    replicate: bool,
+    /// how long a connection without any request is kept open, in seconds
+    #[arg(long, default_value_t = 60)]
+    idle_timeout: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+
+    let cli = Cli::parse();
+
+    let setup: Powers<Fr, G1Projective> =
+        fs::read(&cli.setup, COMPRESS, VALIDATE, fs::Encryption::None)?;
+    let config = Config {
+        block_dir: cli.home.join("blocks"),
+        manifest_dir: cli.home.join("manifests"),
+        layout: fs::Layout::Flat,
+        header: fs::Header::new(
+            "semi-avid",
+            "BLS12-381",
+            &fs::Manifest::setup_digest(&setup, COMPRESS)?,
+        ),
+        compress: COMPRESS,
+        validate: VALIDATE,
+        idle_timeout: Duration::from_secs(cli.idle_timeout),
+    };
+    // NOTE: the node only verifies blocks, it never encodes any data
+    let scheme = SemiAvid::<Fr, G1Projective, DensePolynomial<Fr>>::new(
+        Matrix::from_vec_vec_unchecked(vec![vec![Fr::one()]]),
+    );
+
+    let mut node = Node::new(Keypair::generate_ed25519(), scheme, setup, config)?;
+    info!("peer ID: {}", node.peer_id());
+    node.listen(cli.listen)?;
+    for peer in cli.peers {
+        node.dial(peer)?;
+    }
+
+    loop {
+        match node.next_event().await {
+            Event::Listening(addr) => {
+                // NOTE: the full address, to be given to the other nodes with `--peers`
+                println!("{}/p2p/{}", addr, node.peer_id());
+            }
+            Event::Connected(peer) => info!("connected to {}", peer),
+            Event::Announced { peer, manifest } if cli.replicate => {
+                let ids = node.fetch_missing(peer, &manifest)?;
+                info!("fetching {} blocks from {}", ids.len(), peer);
+            }
+            Event::Announced { .. } => {}
+            Event::Fetched { peer, hash, valid } if valid => {
+                info!("block {} fetched from {}", hash, peer)
+            }
+            Event::Fetched { peer, hash, .. } => warn!("block {} from {} is invalid", hash, peer),
+            Event::Response { peer, response, .. } => info!("{:?} from {}", response, peer),
+            Event::Failed { peer, error, .. } => warn!("request to {} failed: {}", peer, error),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! the aPlonK pipeline of SACLIN, see [`komodo::aplonk`]
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_ec::AffineRepr;
+use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial};
+use tracing::info;
+
+use komodo::{
+    algebra,
+    aplonk::{self, SetupParams},
+    fec::EncodedChunk,
+    zk::trim,
+};
+
+use crate::{
+    evaluation_point, evaluation_points,
+    progress::Progress,
+    read_blocks_batched,
+    setup::{self, TrustedSetup},
+    verify_in_parallel,
+};
+
+pub(crate) const PROTOCOL: &str = "aplonk";
+pub(crate) const SETUP_FILENAME: &str = "aplonk-setup";
+
+type P = DensePolynomial<Fr>;
+pub(crate) type Block = aplonk::Block<Bls12_381>;
+
+/// the polynomials of the data, one per row of $k$ elements
+fn polynomials(bytes: &[u8], k: usize) -> Vec<P> {
+    algebra::split_data_into_field_elements::<Fr>(bytes, k)
+        .chunks(k)
+        .map(|c| P::from_coefficients_vec(c.to_vec()))
+        .collect()
+}
+
+/// the setup of aPlonK, as created by [`generate`]
+pub(crate) type Setup = SetupParams<Bls12_381>;
+
+impl TrustedSetup for Setup {
+    fn nb_powers(&self) -> usize {
+        self.kzg.powers_of_g.len()
+    }
+
+    fn trim(mut self, degree: usize) -> Result<Self> {
+        // NOTE: only the KZG part is trimmed, the IPA part depends on the number of polynomials
+        setup::check_degree(degree, self.kzg.powers_of_g.len())?;
+        self.kzg.powers_of_g.truncate(degree + 1);
+        self.kzg.powers_of_gamma_g.retain(|i, _| *i <= degree);
+        Ok(self)
+    }
+}
+
+pub(crate) fn read_setup(powers_dir: &Path) -> Result<Setup> {
+    setup::read(&powers_dir.join(SETUP_FILENAME))
+}
+
+/// create a trusted setup for any data up to `nb_bytes` encoded with `k` source shards
+///
+/// > **Note**
+/// >
+/// > unlike the other protocols, the setup of aPlonK depends on $k$.
+pub(crate) fn generate(nb_bytes: usize, k: usize, powers_dir: &Path) -> Result<()> {
+    if k < 2 {
+        return Err(anyhow!("aPlonK requires k >= 2, found {}", k));
+    }
+    info!("generating new aPlonK setup");
+    let nb_polynomials = polynomials(&vec![0; nb_bytes], k).len();
+    let params = aplonk::setup::<Bls12_381, P>(k - 1, nb_polynomials)?;
+
+    setup::dump(&params, &powers_dir.join(SETUP_FILENAME), PROTOCOL)?;
+
+    Ok(())
+}
+
+/// prove the shards of a chunk with aPlonK, the chunk has to be encoded with
+/// [`evaluation_points`](crate::evaluation_points)
+pub(crate) fn prove(chunk: &EncodedChunk<Fr>, k: usize, params: &Setup) -> Result<Vec<Block>> {
+    if k < 2 {
+        return Err(anyhow!("aPlonK requires k >= 2, found {}", k));
+    }
+
+    let polynomials = polynomials(&chunk.data, k);
+    if !polynomials.len().is_power_of_two() {
+        return Err(anyhow!(
+            "aPlonK requires a power of 2 number of polynomials, found {}",
+            polynomials.len()
+        ));
+    }
+    let commit = aplonk::commit(polynomials.clone(), params.clone())?;
+
+    let points = evaluation_points(chunk.shards.len());
+    Ok(aplonk::prove::<Bls12_381, P>(
+        commit,
+        polynomials,
+        chunk.shards.clone(),
+        points,
+        params.clone(),
+    )?)
+}
+
+/// verify any number of aPlonK blocks against the setup of `powers_dir`, with `jobs` threads
+pub(crate) fn verify(
+    block_hashes: &[String],
+    block_dir: &Path,
+    powers_dir: &Path,
+    jobs: usize,
+    progress: &Progress,
+) -> Result<Vec<(String, bool)>> {
+    let params = read_setup(powers_dir)?;
+    let (_, vk_psi) = trim(params.kzg.clone(), 0);
+
+    let blocks = read_blocks_batched::<Block>(block_hashes, block_dir, jobs);
+    Ok(verify_in_parallel(&blocks, jobs, progress, |b| {
+        Ok(aplonk::verify::<Bls12_381, P>(
+            b,
+            evaluation_point(&b.shard)?,
+            &vk_psi,
+            params.ipa.tau_1,
+            params.kzg.powers_of_g[0].into_group(),
+            params.kzg.h.into_group(),
+        )?)
+    }))
+}
@@ -0,0 +1,261 @@
+//! measure the main operations of Komodo on this machine, see the `bench` command
+//!
+//! the field operations, the trusted setup and the commitments, the erasure code and the recoding
+//! are the suites of [`benchmarks::suites`], restricted to the curve of SACLIN. the end-to-end
+//! proving and verifying of a protocol is specific to SACLIN as it runs its own commands.
+//!
+//! each measure is a record with the `label` of the operation, the `name` of the curve and the
+//! `times` of all the measurements in nanoseconds, i.e. the format of PLNK, so that the results
+//! can be read by the `benchmarks` Nushell library, together with the `suite` and the `mean` time.
+//!
+//! > **Note**
+//! >
+//! > the end-to-end suite runs the `encode` and `verify` commands against a temporary store, so
+//! > the times include writing and reading the blocks.
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use ark_bls12_381::{Fr, G1Projective};
+use ark_std::rand::RngCore;
+use benchmarks::suites::{self, random_bytes, Bencher as _};
+use clap::ValueEnum;
+use tracing::{info, warn};
+
+use crate::{
+    fri_unsupported, output::Value, progress::Progress, EncodingMethod, Protocol, Store, CURVE,
+};
+
+/// a group of measures
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Suite {
+    /// the arithmetic of the scalar field
+    Field,
+    /// the trusted setup and the commitment of a polynomial
+    Commit,
+    /// the encoding and the decoding of some data
+    Fec,
+    /// the recoding of two shards
+    Recoding,
+    /// the encoding, proving and verifying of `--protocol`, end to end
+    Protocol,
+}
+
+impl Suite {
+    /// the number of measures of the suite
+    fn nb_measures(&self) -> u64 {
+        match self {
+            Suite::Field => 9,
+            Suite::Commit => 2,
+            Suite::Fec => 2,
+            Suite::Recoding => 1,
+            Suite::Protocol => 2,
+        }
+    }
+}
+
+/// the parameters of the measures
+pub(crate) struct Params {
+    pub(crate) nb_bytes: usize,
+    pub(crate) k: usize,
+    pub(crate) n: usize,
+    pub(crate) nb_measurements: usize,
+}
+
+/// the collected measures of a run
+struct Bencher<'a> {
+    suite: Suite,
+    nb_measurements: usize,
+    progress: &'a Progress,
+    measures: Vec<Value>,
+}
+
+impl suites::Bencher for Bencher<'_> {
+    /// repeat `f` and record its times
+    fn bench(&mut self, label: &str, mut f: impl FnMut() -> Result<Duration>) -> Result<()> {
+        let mut times = vec![];
+        for _ in 0..self.nb_measurements {
+            times.push(f()?.as_nanos() as usize);
+        }
+        let mean = times.iter().sum::<usize>() as f64 / times.len().max(1) as f64;
+        info!("{:?} {}: {:.0}ns", self.suite, label, mean);
+
+        self.measures.push(Value::Record(vec![
+            ("suite", format!("{:?}", self.suite).to_lowercase().into()),
+            ("label", label.into()),
+            ("name", CURVE.into()),
+            ("times", times.into()),
+            ("mean", mean.into()),
+        ]));
+        self.progress.inc(1);
+        Ok(())
+    }
+}
+
+/// encode and verify some data with `store`, whose setup is generated first
+fn end_to_end(
+    b: &mut Bencher,
+    store: &Store,
+    (nb_bytes, k, n): (usize, usize, usize),
+    rng: &mut impl RngCore,
+) -> Result<()> {
+    store.generate_setup(nb_bytes, Some(k), rng)?;
+    let name = store.protocol_name()?;
+
+    let mut blocks = vec![];
+    b.bench(&format!("{} encode {} bytes", name, nb_bytes), || {
+        let bytes = random_bytes(nb_bytes, rng);
+        let start = Instant::now();
+        let summary = store.encode(
+            Cursor::new(bytes),
+            (k, n),
+            EncodingMethod::Random,
+            None,
+            rng,
+            &Progress::hidden(),
+        )?;
+        let elapsed = start.elapsed();
+
+        let Value::Record(fields) = summary else {
+            return Err(anyhow!("unexpected summary of `encode`"));
+        };
+        for (key, value) in fields {
+            if let ("blocks", Value::List(hashes)) = (key, value) {
+                blocks.extend(hashes.into_iter().filter_map(|h| match h {
+                    Value::String(h) => Some(h),
+                    _ => None,
+                }));
+            }
+        }
+        Ok(elapsed)
+    })?;
+
+    let mut next = blocks.iter().cycle();
+    b.bench(&format!("{} verify block", name), || {
+        let hash = next.next().ok_or_else(|| anyhow!("no block to verify"))?;
+        let start = Instant::now();
+        let res = store.verify(std::slice::from_ref(hash), 1, &Progress::hidden())?;
+        let elapsed = start.elapsed();
+        if !res.iter().all(|(_, valid)| *valid) {
+            return Err(anyhow!("block {} is invalid", hash));
+        }
+        Ok(elapsed)
+    })
+}
+
+/// run [`end_to_end`] with a temporary store, which is removed afterwards
+fn temporary_end_to_end(
+    b: &mut Bencher,
+    protocol: Protocol,
+    data: (usize, usize, usize),
+    rng: &mut impl RngCore,
+) -> Result<()> {
+    if protocol == Protocol::Fri {
+        return Err(fri_unsupported());
+    }
+
+    let home = std::env::temp_dir().join(format!("saclin-bench-{:016x}", rng.next_u64()));
+    info!("measuring in the temporary store {:?}", home);
+    let store = Store {
+        protocol,
+        block_dir: home.join("blocks/"),
+        manifest_dir: home.join("manifests/"),
+        powers_dir: home.clone(),
+        powers_file: home.join("powers"),
+    };
+
+    let res = end_to_end(b, &store, data, rng);
+    if let Err(e) = std::fs::remove_dir_all(&home) {
+        warn!("could not remove the temporary store {:?}: {}", home, e);
+    }
+    res
+}
+
+/// run the `suites` of measures, all of them if empty, with `protocol` for the end-to-end one
+pub(crate) fn run(
+    protocol: Protocol,
+    suites: &[Suite],
+    params: &Params,
+    quiet: bool,
+) -> Result<Value> {
+    if params.nb_bytes == 0 {
+        return Err(anyhow!("the data is empty, there is nothing to measure"));
+    }
+    if params.k < 2 || params.n < params.k {
+        return Err(anyhow!(
+            "expected 2 <= k <= n, found k = {} and n = {}",
+            params.k,
+            params.n
+        ));
+    }
+    let suites = if suites.is_empty() {
+        Suite::value_variants()
+    } else {
+        suites
+    };
+
+    let mut rng = rand::thread_rng();
+    let progress = Progress::new(
+        "measuring",
+        Some(suites.iter().map(Suite::nb_measures).sum()),
+        "measures",
+        quiet,
+    );
+    let mut measures = vec![];
+    for suite in suites {
+        let mut b = Bencher {
+            suite: *suite,
+            nb_measurements: params.nb_measurements,
+            progress: &progress,
+            measures: vec![],
+        };
+        let data = (params.nb_bytes, params.k, params.n);
+        match suite {
+            Suite::Field => suites::field::<Fr>(&mut b, &mut rng),
+            Suite::Commit => suites::commit::<Fr, G1Projective>(&mut b, params.nb_bytes, &mut rng),
+            Suite::Fec => suites::fec::<Fr>(&mut b, data, &mut rng),
+            Suite::Recoding => suites::recoding::<Fr>(&mut b, data, &mut rng),
+            Suite::Protocol => temporary_end_to_end(&mut b, protocol, data, &mut rng),
+        }
+        .map_err(|e| anyhow!("suite {:?} failed: {}", suite, e))?;
+        measures.extend(b.measures);
+    }
+
+    Ok(Value::List(measures))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run, Params, Suite};
+    use crate::{output::Value, Protocol};
+
+    #[test]
+    fn measures() {
+        let params = Params {
+            nb_bytes: 100,
+            k: 2,
+            n: 3,
+            nb_measurements: 2,
+        };
+        let Value::List(measures) = run(
+            Protocol::SemiAvid,
+            &[Suite::Fec, Suite::Recoding, Suite::Protocol],
+            &params,
+            true,
+        )
+        .unwrap() else {
+            panic!("expected a list of measures");
+        };
+        assert_eq!(measures.len(), 5);
+        for measure in measures {
+            let Value::Record(fields) = measure else {
+                panic!("expected a record");
+            };
+            let keys: Vec<&str> = fields.iter().map(|(k, _)| *k).collect();
+            assert_eq!(keys, ["suite", "label", "name", "times", "mean"]);
+            assert!(matches!(&fields[3].1, Value::List(times) if times.len() == 2));
+        }
+
+        assert!(run(Protocol::SemiAvid, &[], &Params { k: 1, ..params }, true).is_err());
+    }
+}
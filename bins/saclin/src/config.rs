@@ -0,0 +1,175 @@
+//! the configuration file of SACLIN, to avoid passing the same options to every command
+//!
+//! the configuration is a flat subset of TOML, one `key = value` per line, where values are
+//! either quoted strings or integers, e.g.
+//! ```toml
+//! # where the setup, the blocks and the manifests are stored
+//! home = "/var/lib/saclin"
+//! protocol = "semi-avid"
+//! curve = "bls12-381"
+//! k = 3
+//! n = 5
+//! powers = "/var/lib/saclin/setups"
+//! log_level = "info"
+//! log_format = "json"
+//! log_filter = "komodo::fs=debug"
+//! ```
+//!
+//! > **Note**
+//! >
+//! > the options given on the command line always take precedence, then the `KOMODO_*`
+//! > environment variables and finally the configuration file.
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+/// the defaults of SACLIN, all optional
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Config {
+    /// the home directory, see `--home`
+    pub(crate) home: Option<PathBuf>,
+    /// the protocol of the setup and the blocks, see `--protocol`
+    pub(crate) protocol: Option<String>,
+    /// the elliptic curve, see `--curve`
+    pub(crate) curve: Option<String>,
+    /// the number of source shards
+    pub(crate) k: Option<usize>,
+    /// the number of encoded shards
+    pub(crate) n: Option<usize>,
+    /// the directory of the trusted setups, see `--powers`
+    pub(crate) powers: Option<PathBuf>,
+    /// the default level of the logs, see `--log-level`
+    pub(crate) log_level: Option<String>,
+    /// the format of the logs, see `--log-format`
+    pub(crate) log_format: Option<String>,
+    /// the per-module filters of the logs, see `--log-filter`
+    pub(crate) log_filter: Option<String>,
+}
+
+enum ConfigValue {
+    String(String),
+    Int(usize),
+}
+
+/// parse a quoted string or an integer, followed by an optional comment
+fn parse_value(value: &str) -> Option<ConfigValue> {
+    let value = value.trim();
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut s = String::new();
+        let mut chars = rest.chars();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => match chars.next()? {
+                    '"' => s.push('"'),
+                    '\\' => s.push('\\'),
+                    'n' => s.push('\n'),
+                    't' => s.push('\t'),
+                    _ => return None,
+                },
+                c => s.push(c),
+            }
+        }
+        let rest = chars.as_str().trim();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return None;
+        }
+        Some(ConfigValue::String(s))
+    } else {
+        let value = value.split('#').next().unwrap_or_default().trim();
+        value.replace('_', "").parse().ok().map(ConfigValue::Int)
+    }
+}
+
+impl Config {
+    pub(crate) fn parse(content: &str) -> Result<Self> {
+        let mut config = Config::default();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                anyhow!("line {}: expected `key = value`, found {:?}", i + 1, line)
+            })?;
+            let key = key.trim();
+            let value = parse_value(value)
+                .ok_or_else(|| anyhow!("line {}: invalid value for `{}`", i + 1, key))?;
+
+            match (key, value) {
+                ("home", ConfigValue::String(s)) => config.home = Some(PathBuf::from(s)),
+                ("protocol", ConfigValue::String(s)) => config.protocol = Some(s),
+                ("curve", ConfigValue::String(s)) => config.curve = Some(s),
+                ("k", ConfigValue::Int(x)) => config.k = Some(x),
+                ("n", ConfigValue::Int(x)) => config.n = Some(x),
+                ("powers", ConfigValue::String(s)) => config.powers = Some(PathBuf::from(s)),
+                ("log_level", ConfigValue::String(s)) => config.log_level = Some(s),
+                ("log_format", ConfigValue::String(s)) => config.log_format = Some(s),
+                ("log_filter", ConfigValue::String(s)) => config.log_filter = Some(s),
+                (
+                    "home" | "protocol" | "curve" | "powers" | "log_level" | "log_format"
+                    | "log_filter",
+                    _,
+                ) => return Err(anyhow!("line {}: `{}` should be a string", i + 1, key)),
+                ("k" | "n", _) => {
+                    return Err(anyhow!("line {}: `{}` should be an integer", i + 1, key))
+                }
+                _ => return Err(anyhow!("line {}: unknown key `{}`", i + 1, key)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    pub(crate) fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("could not read config {:?}: {}", path, e))?;
+        Self::parse(&content).map_err(|e| anyhow!("invalid config {:?}: {}", path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::Config;
+
+    #[test]
+    fn parse() {
+        let config = Config::parse(
+            r#"
+# a comment
+home = "/tmp/saclin" # another comment
+protocol = "kzg"
+k = 3
+n = 1_000
+log_format = "json"
+
+powers = "a \"quoted\" dir"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                home: Some(PathBuf::from("/tmp/saclin")),
+                protocol: Some("kzg".to_string()),
+                curve: None,
+                k: Some(3),
+                n: Some(1000),
+                powers: Some(PathBuf::from("a \"quoted\" dir")),
+                log_level: None,
+                log_format: Some("json".to_string()),
+                log_filter: None,
+            }
+        );
+
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert!(Config::parse("k = \"3\"").is_err());
+        assert!(Config::parse("home = 3").is_err());
+        assert!(Config::parse("foo = 3").is_err());
+        assert!(Config::parse("home = \"unterminated").is_err());
+        assert!(Config::parse("[section]").is_err());
+    }
+}
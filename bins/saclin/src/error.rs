@@ -0,0 +1,116 @@
+//! the exit codes of SACLIN and the errors printed before exiting
+//!
+//! every failure has a kind, which gives the exit code of the process, see [`ErrorKind`], and
+//! ends with a single JSON object on the last line of _stderr_, e.g.
+//! ```json
+//! {"error": "verification", "code": 4, "message": "1 invalid blocks out of 3: [...]"}
+//! ```
+//!
+//! > **Note**
+//! >
+//! > the exit codes are part of the interface of SACLIN and will not change, new kinds of errors
+//! > will only get new codes.
+use std::process::exit;
+
+use crate::output::{Output, Value};
+
+/// the exit codes, as shown by `--help`
+pub(crate) const EXIT_CODES: &str = "\
+Exit codes:
+  0  success
+  1  any other failure
+  2  invalid arguments, environment variables or config
+  3  a file could not be read or written
+  4  at least one block is invalid
+  5  the blocks could not be decoded or recoded
+  6  the trusted setup is not the expected one";
+
+/// the kind of a failure, which gives the exit code of SACLIN
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ErrorKind {
+    /// any other failure
+    Failure,
+    /// invalid arguments, environment variables or config, as with `clap`
+    Usage,
+    /// a file could not be read or written
+    Io,
+    /// at least one block is invalid
+    Verification,
+    /// the blocks could not be decoded or recoded
+    Decode,
+    /// the trusted setup is not the expected one
+    SetupMismatch,
+}
+
+impl ErrorKind {
+    /// the exit code of the kind
+    pub(crate) fn code(&self) -> i32 {
+        match self {
+            ErrorKind::Failure => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::Io => 3,
+            ErrorKind::Verification => 4,
+            ErrorKind::Decode => 5,
+            ErrorKind::SetupMismatch => 6,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::Failure => "failure",
+            ErrorKind::Usage => "usage",
+            ErrorKind::Io => "io",
+            ErrorKind::Verification => "verification",
+            ErrorKind::Decode => "decode",
+            ErrorKind::SetupMismatch => "setup_mismatch",
+        }
+    }
+
+    /// the kind of an error, [`ErrorKind::Io`] if it comes from an IO error and `default`
+    /// otherwise
+    pub(crate) fn of(e: &anyhow::Error, default: ErrorKind) -> ErrorKind {
+        if e.chain().any(|e| e.is::<std::io::Error>()) {
+            ErrorKind::Io
+        } else {
+            default
+        }
+    }
+}
+
+/// the final JSON object of a failure, see the module documentation
+fn payload(kind: ErrorKind, message: &str) -> String {
+    Value::Record(vec![
+        ("error", kind.name().into()),
+        ("code", (kind.code() as usize).into()),
+        ("message", message.into()),
+    ])
+    .render(Output::Json)
+}
+
+/// print an error on _stderr_ and exit with the code of its kind
+pub(crate) fn throw_error(kind: ErrorKind, message: &str) -> ! {
+    eprintln!("{}", payload(kind, message));
+    exit(kind.code());
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::{payload, ErrorKind};
+
+    #[test]
+    fn kinds() {
+        assert_eq!(
+            payload(ErrorKind::Verification, "block \"a\" is invalid"),
+            r#"{"error": "verification", "code": 4, "message": "block \"a\" is invalid"}"#
+        );
+
+        let io = anyhow::Error::from(std::io::Error::other("nope")).context("could not read");
+        assert_eq!(ErrorKind::of(&io, ErrorKind::Decode), ErrorKind::Io);
+        assert_eq!(
+            ErrorKind::of(&anyhow!("nope"), ErrorKind::Decode),
+            ErrorKind::Decode
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! estimate the storage and the proof overhead of some data, without touching any data nor setup
+//!
+//! the sizes are exact, they are computed from the sizes of the serialized field elements and
+//! curve points, but the headers of the files, see [`komodo::fs::Header`], are not counted.
+//!
+//! the times are derived from a built-in calibration of the underlying operations, e.g. a pairing
+//! or a multi-scalar multiplication, measured on a single core of a reference machine.
+//!
+//! > **Note**
+//! >
+//! > the times are only orders of magnitude, on another machine they should be off by a
+//! > constant factor.
+use anyhow::{anyhow, Result};
+#[cfg(feature = "aplonk")]
+use ark_bls12_381::{Bls12_381, G2Projective};
+use ark_bls12_381::{Fr, G1Projective};
+#[cfg(feature = "aplonk")]
+use ark_ec::pairing::PairingOutput;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use ark_std::Zero;
+
+use crate::{output::Value, Protocol, COMPRESS};
+
+/// the time of a multiplication and an addition of two elements of $\mathbb{F}_r$
+const FIELD_MUL_ADD: f64 = 60e-9;
+/// the time of a multi-scalar multiplication in $\mathbb{G}_1$, per point
+const G1_MSM: f64 = 50e-6;
+/// the time of a multi-scalar multiplication in $\mathbb{G}_2$, per point
+#[cfg(feature = "aplonk")]
+const G2_MSM: f64 = 150e-6;
+/// the time of a scalar multiplication in $\mathbb{G}_2$
+#[cfg(feature = "aplonk")]
+const G2_MUL: f64 = 1.3e-3;
+/// the time of a pairing
+#[cfg(any(feature = "kzg", feature = "aplonk"))]
+const PAIRING: f64 = 2.6e-3;
+
+/// the size of a vector of `len` elements of `size` bytes once serialized
+fn vec_size(len: usize, size: usize) -> usize {
+    // NOTE: vectors are prefixed with their length, as a `u64`
+    8 + len * size
+}
+
+/// the overhead of some data for a protocol
+#[derive(Debug, Clone, PartialEq)]
+struct Estimate {
+    /// the number of polynomials, i.e. the number of elements in each shard
+    nb_polynomials: usize,
+    /// the number of powers of the trusted setup required to prove the data
+    setup_powers: usize,
+    shard_size: usize,
+    proof_size: usize,
+    prove_seconds: f64,
+    verify_block_seconds: f64,
+}
+
+fn estimate_protocol(protocol: Protocol, nb_bytes: usize, k: usize, n: usize) -> Result<Estimate> {
+    if nb_bytes == 0 {
+        return Err(anyhow!("the data is empty, there is nothing to estimate"));
+    }
+    if k == 0 || n < k {
+        return Err(anyhow!(
+            "expected 0 < k <= n, found k = {} and n = {}",
+            k,
+            n
+        ));
+    }
+
+    // NOTE: see `komodo::algebra::split_data_into_field_elements`
+    let bytes_per_element = (Fr::MODULUS_BIT_SIZE as usize - 1) / 8;
+    let m = nb_bytes.div_ceil(bytes_per_element).div_ceil(k);
+
+    let fr = Fr::zero().serialized_size(COMPRESS);
+    let g1 = G1Projective::zero().serialized_size(COMPRESS);
+    // NOTE: a KZG proof is a point and an optional field element, which is always absent
+    #[cfg(any(feature = "kzg", feature = "aplonk"))]
+    let kzg_proof = g1 + 1;
+
+    // NOTE: see `komodo::fec::Shard`, the hash is a SHA-256
+    let shard_size = 4 + vec_size(k, fr) + vec_size(32, 1) + vec_size(m, fr) + 8;
+    let encode = (n * k * m) as f64 * FIELD_MUL_ADD;
+
+    let estimate = match protocol {
+        Protocol::SemiAvid => Estimate {
+            nb_polynomials: m,
+            setup_powers: m,
+            shard_size,
+            // NOTE: the $k$ columns of the data are committed
+            proof_size: vec_size(k, g1),
+            prove_seconds: encode + (k * m) as f64 * G1_MSM,
+            // NOTE: the shard is committed and compared to the combination of the commitments
+            verify_block_seconds: (k + m) as f64 * G1_MSM,
+        },
+        #[cfg(feature = "kzg")]
+        Protocol::Kzg => {
+            if k < 2 {
+                return Err(anyhow!("KZG+ requires k >= 2, found {}", k));
+            }
+            Estimate {
+                nb_polynomials: m,
+                setup_powers: k,
+                shard_size,
+                proof_size: vec_size(m, g1) + kzg_proof,
+                prove_seconds: encode
+                    + (m * k) as f64 * G1_MSM
+                    + n as f64 * ((m * k) as f64 * FIELD_MUL_ADD + k as f64 * G1_MSM),
+                verify_block_seconds: m as f64 * G1_MSM + 2.0 * PAIRING,
+            }
+        }
+        #[cfg(feature = "aplonk")]
+        Protocol::Aplonk => {
+            if k < 2 {
+                return Err(anyhow!("aPlonK requires k >= 2, found {}", k));
+            }
+            if !m.is_power_of_two() {
+                return Err(anyhow!(
+                    "aPlonK requires a power of 2 number of polynomials, found {}",
+                    m
+                ));
+            }
+            let g2 = G2Projective::zero().serialized_size(COMPRESS);
+            let gt = PairingOutput::<Bls12_381>::zero().serialized_size(COMPRESS);
+            let log_m = m.trailing_zeros() as usize;
+            let ipa_proof = 2 * vec_size(log_m, gt) + 2 * vec_size(log_m, g1) + g1 + g2;
+            Estimate {
+                nb_polynomials: m,
+                setup_powers: k,
+                shard_size,
+                proof_size: gt + fr + g1 + kzg_proof + ipa_proof + g2,
+                prove_seconds: encode
+                    + (m * k) as f64 * G1_MSM
+                    + m as f64 * PAIRING
+                    + n as f64
+                        * ((m * k) as f64 * FIELD_MUL_ADD
+                            + k as f64 * G1_MSM
+                            + 2.0 * m as f64 * PAIRING
+                            + m as f64 * G2_MUL),
+                verify_block_seconds: (2 * log_m + 4) as f64 * PAIRING + m as f64 * G2_MSM,
+            }
+        }
+        Protocol::Fri => return Err(anyhow!("FRI is not supported by `estimate`")),
+    };
+
+    Ok(estimate)
+}
+
+/// estimate the overhead of `nb_bytes` of data encoded with $(k, n)$ and proven with `protocol`
+///
+/// the blow-up is the ratio between the size of all the blocks and the size of the data.
+pub(crate) fn run(protocol: Protocol, nb_bytes: usize, k: usize, n: usize) -> Result<Value> {
+    let e = estimate_protocol(protocol, nb_bytes, k, n)?;
+    let block_size = e.shard_size + e.proof_size;
+
+    Ok(Value::Record(vec![
+        ("nb_bytes", nb_bytes.into()),
+        ("k", k.into()),
+        ("n", n.into()),
+        ("polynomials", e.nb_polynomials.into()),
+        ("setup_powers", e.setup_powers.into()),
+        ("shard_size", e.shard_size.into()),
+        ("proof_size", e.proof_size.into()),
+        ("block_size", block_size.into()),
+        ("total_size", (n * block_size).into()),
+        ("blowup", ((n * block_size) as f64 / nb_bytes as f64).into()),
+        ("prove_seconds", e.prove_seconds.into()),
+        ("verify_block_seconds", e.verify_block_seconds.into()),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_serialize::CanonicalSerialize;
+
+    use komodo::{
+        algebra::linalg::Matrix,
+        fec,
+        semi_avid::{build, prove},
+        zk,
+    };
+
+    use super::estimate_protocol;
+    use crate::{Protocol, COMPRESS};
+
+    #[test]
+    fn semi_avid_sizes() {
+        let mut rng = ark_std::test_rng();
+        let bytes = include_bytes!("../../../assets/dragoon_32x32.png").to_vec();
+        let (k, n) = (3, 5);
+
+        let powers = zk::setup::<Fr, G1Projective>(bytes.len(), &mut rng).unwrap();
+        let shards = fec::encode(&bytes, &Matrix::random(k, n, &mut rng)).unwrap();
+        let proof = prove::<Fr, G1Projective, DensePolynomial<Fr>>(&bytes, &powers, k).unwrap();
+        let blocks = build::<Fr, G1Projective, DensePolynomial<Fr>>(&shards, &proof);
+
+        let estimate = estimate_protocol(Protocol::SemiAvid, bytes.len(), k, n).unwrap();
+        assert_eq!(
+            estimate.shard_size,
+            blocks[0].shard.serialized_size(COMPRESS)
+        );
+        assert_eq!(
+            estimate.shard_size + estimate.proof_size,
+            blocks[0].serialized_size(COMPRESS)
+        );
+
+        assert!(estimate_protocol(Protocol::SemiAvid, 0, k, n).is_err());
+        assert!(estimate_protocol(Protocol::SemiAvid, 10, 3, 2).is_err());
+    }
+}
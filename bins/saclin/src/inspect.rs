@@ -0,0 +1,220 @@
+//! describe the blocks of the store, one record per block, see the `inspect` command
+//!
+//! the summary of a block comes from its header, see [`fs::Header`], and from its shard, e.g. the
+//! sparsity of its linear combination tells whether it is a source shard, i.e. a single non-zero
+//! coefficient, or the result of an encoding or a recoding.
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use ark_bls12_381::{Fr, G1Projective};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::Zero;
+
+use komodo::{fec::Shard, fs, semi_avid::Block};
+
+use crate::{
+    fri_unsupported, output, output::Value, setup::hex, Protocol, COMPRESS, ENCRYPTION, LAYOUT,
+    VALIDATE,
+};
+
+/// a condition on the fields of the summary of a block, given as `key=value`
+///
+/// a string field matches when it starts with the value, e.g. `file_hash=abcd`, any other field
+/// when it is equal to it, e.g. `k=3` or `source=true`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Filter {
+    key: String,
+    value: String,
+}
+
+/// parse a `key=value` filter
+pub(crate) fn parse_filter(s: &str) -> Result<Filter, String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok(Filter {
+            key: key.trim().to_string(),
+            value: value.trim().to_string(),
+        }),
+        _ => Err(format!("expected `key=value`, found {:?}", s)),
+    }
+}
+
+impl Filter {
+    fn matches(&self, summary: &Value) -> Result<bool> {
+        let Value::Record(fields) = summary else {
+            return Ok(false);
+        };
+        let (_, field) = fields.iter().find(|(k, _)| *k == self.key).ok_or_else(|| {
+            anyhow!(
+                "cannot filter on `{}`, expected one of {}",
+                self.key,
+                fields
+                    .iter()
+                    .map(|(k, _)| format!("`{}`", k))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+
+        Ok(match field {
+            Value::String(s) => s.starts_with(&self.value),
+            Value::Bool(b) => b.to_string() == self.value,
+            Value::Int(x) => x.to_string() == self.value,
+            _ => false,
+        })
+    }
+}
+
+/// the summary of a block, and its full content if `full` is set
+fn describe<B: CanonicalSerialize>(
+    hash: &str,
+    block: &B,
+    shard: &Shard<Fr>,
+    block_dir: &Path,
+    full: Option<Vec<(&'static str, Value)>>,
+) -> Result<Value> {
+    let path = LAYOUT.find(block_dir, hash);
+    let header = fs::Header::read_from(&path)?.unwrap_or_default();
+
+    let block_size = block.serialized_size(COMPRESS);
+    let shard_size = shard.serialized_size(COMPRESS);
+    let nb_non_zero = shard
+        .linear_combination
+        .iter()
+        .filter(|c| !c.is_zero())
+        .count();
+
+    let mut fields = vec![
+        ("block", hash.into()),
+        ("protocol", header.protocol.into()),
+        ("curve", header.curve.into()),
+        ("k", shard.k.into()),
+        ("file_hash", hex(&shard.hash).into()),
+        ("setup_digest", hex(&header.setup_digest).into()),
+        ("elements", shard.data.len().into()),
+        ("shard_size", shard_size.into()),
+        ("proof_size", (block_size - shard_size).into()),
+        ("size", (std::fs::metadata(&path)?.len() as usize).into()),
+        ("non_zero_coefficients", nb_non_zero.into()),
+        (
+            "sparsity",
+            (1.0 - nb_non_zero as f64 / shard.linear_combination.len().max(1) as f64).into(),
+        ),
+        ("source", (nb_non_zero == 1).into()),
+    ];
+    if let Some(full) = full {
+        fields.extend(full);
+    }
+
+    Ok(Value::Record(fields))
+}
+
+/// describe the blocks of a protocol with [`describe`]
+fn describe_all<B: CanonicalDeserialize + CanonicalSerialize>(
+    block_hashes: &[String],
+    block_dir: &Path,
+    shard: impl Fn(&B) -> &Shard<Fr>,
+    full: impl Fn(&B) -> Vec<(&'static str, Value)>,
+    with_content: bool,
+) -> Result<Vec<Value>> {
+    fs::read_blocks::<B>(
+        block_hashes,
+        block_dir,
+        LAYOUT,
+        COMPRESS,
+        VALIDATE,
+        ENCRYPTION,
+    )?
+    .iter()
+    .map(|(h, b)| {
+        let shard = shard(b);
+        let content = with_content.then(|| {
+            let mut content = vec![("shard", output::shard(shard))];
+            content.extend(full(b));
+            content
+        });
+        describe(h, b, shard, block_dir, content)
+    })
+    .collect()
+}
+
+/// describe any number of blocks of `protocol`, keeping only the ones matching all the filters
+///
+/// the content of the shards and the commitments are only shown with `full`.
+pub(crate) fn run(
+    protocol: Protocol,
+    block_hashes: &[String],
+    block_dir: &Path,
+    filters: &[Filter],
+    full: bool,
+) -> Result<Value> {
+    let summaries = match protocol {
+        Protocol::SemiAvid => describe_all::<Block<Fr, G1Projective>>(
+            block_hashes,
+            block_dir,
+            |b| &b.shard,
+            |b| {
+                vec![(
+                    "commits",
+                    Value::List(b.commits().iter().map(|c| c.0.to_string().into()).collect()),
+                )]
+            },
+            full,
+        )?,
+        #[cfg(feature = "kzg")]
+        Protocol::Kzg => describe_all::<crate::kzg::Block>(
+            block_hashes,
+            block_dir,
+            |b| &b.shard,
+            |_| vec![],
+            full,
+        )?,
+        #[cfg(feature = "aplonk")]
+        Protocol::Aplonk => describe_all::<crate::aplonk::Block>(
+            block_hashes,
+            block_dir,
+            |b| &b.shard,
+            |_| vec![],
+            full,
+        )?,
+        Protocol::Fri => return Err(fri_unsupported()),
+    };
+
+    let mut kept = vec![];
+    for summary in summaries {
+        let mut keep = true;
+        for filter in filters {
+            keep &= filter.matches(&summary)?;
+        }
+        if keep {
+            kept.push(summary);
+        }
+    }
+
+    Ok(Value::List(kept))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_filter;
+    use crate::output::Value;
+
+    #[test]
+    fn filter() {
+        let summary = Value::Record(vec![
+            ("file_hash", "abcdef".into()),
+            ("k", 3usize.into()),
+            ("source", false.into()),
+        ]);
+
+        let matches = |f: &str| parse_filter(f).unwrap().matches(&summary).unwrap();
+        assert!(matches("file_hash=abc"));
+        assert!(!matches("file_hash=bc"));
+        assert!(matches("k=3"));
+        assert!(!matches("k=4"));
+        assert!(matches("source = false"));
+
+        assert!(parse_filter("k").is_err());
+        assert!(parse_filter("=3").is_err());
+        assert!(parse_filter("foo=3").unwrap().matches(&summary).is_err());
+    }
+}
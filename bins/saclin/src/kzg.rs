@@ -0,0 +1,127 @@
+//! the KZG+ pipeline of SACLIN, see [`komodo::kzg`]
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_ff::PrimeField;
+use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial};
+use ark_poly_commit::kzg10::{UniversalParams, KZG10};
+use ark_std::rand::RngCore;
+use tracing::info;
+
+use komodo::{algebra, fec::EncodedChunk, kzg, zk::trim};
+
+use crate::{
+    evaluation_point, evaluation_points,
+    progress::Progress,
+    read_blocks_batched,
+    setup::{self, TrustedSetup},
+    verify_in_parallel,
+};
+
+pub(crate) const PROTOCOL: &str = "kzg";
+pub(crate) const SETUP_FILENAME: &str = "kzg-setup";
+
+type P = DensePolynomial<Fr>;
+pub(crate) type Block = kzg::Block<Bls12_381>;
+
+/// the setup of KZG+, as created by [`generate`]
+pub(crate) type Setup = UniversalParams<Bls12_381>;
+
+impl TrustedSetup for Setup {
+    fn nb_powers(&self) -> usize {
+        self.powers_of_g.len()
+    }
+
+    fn trim(mut self, degree: usize) -> Result<Self> {
+        setup::check_degree(degree, self.powers_of_g.len())?;
+        self.powers_of_g.truncate(degree + 1);
+        self.powers_of_gamma_g.retain(|i, _| *i <= degree);
+        Ok(self)
+    }
+}
+
+pub(crate) fn read_setup(powers_dir: &Path) -> Result<Setup> {
+    setup::read(&powers_dir.join(SETUP_FILENAME))
+}
+
+/// create a random trusted setup for any data up to `nb_bytes`
+pub(crate) fn generate(nb_bytes: usize, powers_dir: &Path, rng: &mut impl RngCore) -> Result<()> {
+    info!("generating new KZG+ setup");
+    // NOTE: `k` is not known yet, the setup is large enough for data in a single polynomial
+    let degree = nb_bytes / (Fr::MODULUS_BIT_SIZE as usize / 8);
+    let params = KZG10::<Bls12_381, P>::setup(degree, false, rng)?;
+
+    setup::dump(&params, &powers_dir.join(SETUP_FILENAME), PROTOCOL)?;
+
+    Ok(())
+}
+
+/// prove the shards of a chunk with KZG+, the chunk has to be encoded with
+/// [`evaluation_points`](crate::evaluation_points)
+pub(crate) fn prove(chunk: &EncodedChunk<Fr>, k: usize, params: &Setup) -> Result<Vec<Block>> {
+    if k < 2 {
+        return Err(anyhow!("KZG+ requires k >= 2, found {}", k));
+    }
+    if params.powers_of_g.len() < k {
+        return Err(anyhow!(
+            "the KZG+ setup is too small, expected at least {} powers, found {}",
+            k,
+            params.powers_of_g.len()
+        ));
+    }
+    let (powers, _) = trim(params.clone(), k - 1);
+
+    let polynomials = algebra::split_data_into_field_elements::<Fr>(&chunk.data, k)
+        .chunks(k)
+        .map(|c| P::from_coefficients_vec(c.to_vec()))
+        .collect::<Vec<_>>();
+    let (commits, _) = kzg::commit(&powers, &polynomials)?;
+
+    let points = evaluation_points(chunk.shards.len());
+    Ok(kzg::prove::<Bls12_381, P>(
+        commits,
+        polynomials,
+        chunk.shards.clone(),
+        points,
+        powers,
+    )?)
+}
+
+/// verify any number of KZG+ blocks against the setup of `powers_dir`, with `jobs` threads
+///
+/// all the blocks are first verified at once with [`kzg::batch_verify`], which is much cheaper,
+/// and then one by one only if the batch is invalid, to find the invalid blocks.
+pub(crate) fn verify(
+    block_hashes: &[String],
+    block_dir: &Path,
+    powers_dir: &Path,
+    jobs: usize,
+    progress: &Progress,
+) -> Result<Vec<(String, bool)>> {
+    let (_, verifier_key) = trim(read_setup(powers_dir)?, 0);
+
+    let blocks = read_blocks_batched::<Block>(block_hashes, block_dir, jobs);
+    let batch = blocks
+        .iter()
+        .map(|(_, b)| b.as_ref().ok().cloned())
+        .collect::<Option<Vec<_>>>()
+        .filter(|batch| !batch.is_empty());
+    if let Some(batch) = batch {
+        let pts = batch
+            .iter()
+            .map(|b| evaluation_point(&b.shard))
+            .collect::<Result<Vec<_>>>();
+        if let Ok(pts) = pts {
+            if kzg::batch_verify::<Bls12_381, P>(&batch, &pts, &verifier_key)? {
+                progress.inc(block_hashes.len() as u64);
+                return Ok(block_hashes.iter().map(|h| (h.clone(), true)).collect());
+            }
+        }
+    }
+
+    Ok(verify_in_parallel(&blocks, jobs, progress, |b| {
+        let pt = evaluation_point(&b.shard)?;
+        Ok(kzg::verify::<Bls12_381, P>(b, pt, &verifier_key))
+    }))
+}
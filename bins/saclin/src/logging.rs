@@ -0,0 +1,252 @@
+//! the logs of SACLIN, always written to _stderr_
+//!
+//! the verbosity is controlled with a default level, e.g. `--log-level debug`, and per-module
+//! filters, e.g. `--log-filter komodo::fs=trace,saclin=warn`, with the syntax of
+//! [`tracing_subscriber::filter::Targets`].
+//!
+//! > **Note**
+//! >
+//! > the JSON logs have one object per line, with the `timestamp`, the `level`, the `target`, the
+//! > `spans` and all the `fields` of the event, so that they can be ingested by any log
+//! > aggregator.
+use std::fmt;
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use tracing::{
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    Event, Subscriber,
+};
+use tracing_subscriber::{
+    filter::Targets,
+    fmt::{
+        format::Writer,
+        time::{FormatTime, SystemTime},
+        FmtContext, FormatEvent, FormatFields,
+    },
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    Layer, Registry,
+};
+
+use crate::output::quote;
+
+/// the format of the logs
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LogFormat {
+    /// one line per event, colored when _stderr_ is a terminal
+    Text,
+    /// multiple lines per event, with the source location
+    Pretty,
+    /// one JSON object per line
+    Json,
+}
+
+/// the level of the logs that are not matched by a filter
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// the fields of an event, already formatted as JSON values
+#[derive(Default)]
+struct JsonFields(Vec<(String, String)>);
+
+impl Visit for JsonFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .push((field.name().to_string(), quote(&format!("{:?}", value))));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_string(), quote(value)));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        let value = if value.is_finite() {
+            value.to_string()
+        } else {
+            "null".to_string()
+        };
+        self.0.push((field.name().to_string(), value));
+    }
+}
+
+/// format the events as JSON objects, see the module documentation
+struct Json;
+
+impl<S, N> FormatEvent<S, N> for Json
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+
+        let spans = ctx
+            .event_scope()
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| quote(span.name()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        writeln!(
+            writer,
+            "{{\"timestamp\": {}, \"level\": {}, \"target\": {}, \"spans\": [{}], \"fields\": {{{}}}}}",
+            quote(&timestamp),
+            quote(&event.metadata().level().to_string()),
+            quote(event.metadata().target()),
+            spans.join(", "),
+            fields
+                .0
+                .iter()
+                .map(|(k, v)| format!("{}: {}", quote(k), v))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+}
+
+/// the filter of the logs, from a default level and optional per-module filters
+///
+/// a default level in the filters, e.g. `debug` in `debug,komodo=trace`, takes precedence over
+/// `level`.
+pub(crate) fn targets(level: LogLevel, filter: Option<&str>) -> Result<Targets> {
+    let targets = match filter {
+        Some(filter) => Targets::from_str(filter)
+            .map_err(|e| anyhow!("invalid log filter {:?}: {}", filter, e))?,
+        None => Targets::new(),
+    };
+
+    Ok(match targets.default_level() {
+        Some(_) => targets,
+        None => targets.with_default(level),
+    })
+}
+
+/// install the global logger, writing the logs to _stderr_
+pub(crate) fn init(format: LogFormat, targets: Targets) -> Result<()> {
+    let ansi = std::io::stderr().is_terminal();
+    let layer: Box<dyn Layer<Registry> + Send + Sync> = match format {
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_ansi(ansi)
+            .boxed(),
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .pretty()
+            .with_writer(std::io::stderr)
+            .with_ansi(ansi)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .event_format(Json)
+            .with_writer(std::io::stderr)
+            .boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(layer.with_filter(targets))
+        .try_init()
+        .map_err(|e| anyhow!("cannot init logger: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::{info, warn};
+    use tracing_subscriber::{layer::SubscriberExt, Layer};
+
+    use super::{targets, Json, LogLevel};
+
+    #[test]
+    fn json() {
+        let logs = Arc::new(Mutex::new(vec![]));
+        let writer = {
+            let logs = logs.clone();
+            move || WriteTo(logs.clone())
+        };
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .event_format(Json)
+                .with_writer(writer)
+                .with_filter(targets(LogLevel::Warn, Some("saclin::logging=info")).unwrap()),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("encode").entered();
+            info!(n = 3, ok = true, "a \"quoted\" message");
+            warn!(target: "komodo", "kept");
+            info!(target: "komodo", "filtered out");
+        });
+
+        let logs = String::from_utf8(logs.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = logs.lines().collect();
+        assert_eq!(lines.len(), 2, "{}", logs);
+        assert!(lines[0].starts_with("{\"timestamp\": \""));
+        assert!(lines[0].ends_with(
+            "\"level\": \"INFO\", \"target\": \"saclin::logging::tests\", \"spans\": [\"encode\"], \
+            \"fields\": {\"message\": \"a \\\"quoted\\\" message\", \"n\": 3, \"ok\": true}}"
+        ));
+        assert!(lines[1].contains("\"level\": \"WARN\", \"target\": \"komodo\""));
+
+        assert!(targets(LogLevel::Info, Some("komodo=nope")).is_err());
+    }
+
+    struct WriteTo(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for WriteTo {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+}
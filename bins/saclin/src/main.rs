@@ -1,341 +1,1411 @@
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::exit;
+use std::str::FromStr;
 
 use ark_bls12_381::{Fr, G1Projective};
-use ark_ec::CurveGroup;
 use ark_ff::PrimeField;
-use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial};
-use ark_serialize::{CanonicalDeserialize, Compress, Validate};
-use ark_std::ops::Div;
+use ark_poly::univariate::DensePolynomial;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use ark_std::rand::RngCore;
+use clap::{Parser, Subcommand, ValueEnum};
+use rand::{rngs::StdRng, SeedableRng};
 use tracing::{info, warn};
 
 use komodo::{
     algebra::linalg::Matrix,
-    error::KomodoError,
-    fec::{self, decode, Shard},
+    fec::{self, decode, EncodedChunk, Shard},
     fs,
-    semi_avid::{build, prove, recode, verify, Block},
+    semi_avid::{build, prove, recode, recode_with_coeffs, verify, Block},
     zk::{self, Powers},
 };
 
+#[cfg(feature = "aplonk")]
+mod aplonk;
+mod bench;
+mod config;
+mod error;
+mod estimate;
+mod inspect;
+#[cfg(feature = "kzg")]
+mod kzg;
+mod logging;
+mod output;
+mod progress;
+mod serve;
+mod setup;
+
+use config::Config;
+use error::{throw_error, ErrorKind};
+use logging::{LogFormat, LogLevel};
+use output::{Output, Value};
+use progress::Progress;
+
 const COMPRESS: Compress = Compress::Yes;
+const CURVE: &str = "BLS12-381";
+const PROTOCOL: &str = "semi-avid";
+const LAYOUT: fs::Layout = fs::Layout::Flat;
+const COMPRESSION: fs::Compression = fs::Compression::None;
+const ENCRYPTION: fs::Encryption = fs::Encryption::None;
 const VALIDATE: Validate = Validate::Yes;
 
-#[allow(clippy::type_complexity)]
-fn parse_args() -> (
-    Vec<u8>,
-    usize,
-    usize,
-    bool,
-    String,
-    bool,
-    bool,
-    bool,
-    bool,
-    usize,
-    String,
-    Vec<String>,
-) {
-    let bytes_path = std::env::args()
-        .nth(1)
-        .expect("expected path to bytes as first positional argument");
-    let bytes = if bytes_path.is_empty() {
-        vec![]
-    } else {
-        std::fs::read(bytes_path).unwrap()
-    };
-    let k: usize = std::env::args()
-        .nth(2)
-        .expect("expected k as second positional argument")
-        .parse()
-        .expect("could not parse k as an int");
-    let n: usize = std::env::args()
-        .nth(3)
-        .expect("expected n as third positional argument")
-        .parse()
-        .expect("could not parse n as an int");
-    let do_generate_powers: bool = std::env::args()
-        .nth(4)
-        .expect("expected do_generate_powers as fourth positional argument")
-        .parse()
-        .expect("could not parse do_generate_powers as a bool");
-    let home_dir = std::env::args()
-        .nth(5)
-        .expect("expected home_dir as fifth positional argument");
-    let do_reconstruct_data: bool = std::env::args()
-        .nth(6)
-        .expect("expected do_reconstruct_data as sixth positional argument")
-        .parse()
-        .expect("could not parse do_reconstruct_data as a bool");
-    let do_verify_blocks: bool = std::env::args()
-        .nth(7)
-        .expect("expected do_verify_blocks as seventh positional argument")
-        .parse()
-        .expect("could not parse do_verify_blocks as a bool");
-    let do_combine_blocks: bool = std::env::args()
-        .nth(8)
-        .expect("expected do_combine_blocks as eigth positional argument")
-        .parse()
-        .expect("could not parse do_combine_blocks as a bool");
-    let do_inspect_blocks: bool = std::env::args()
-        .nth(9)
-        .expect("expected do_inspect_blocks as ninth positional argument")
-        .parse()
-        .expect("could not parse do_inspect_blocks as a bool");
-    let nb_bytes: usize = std::env::args()
-        .nth(10)
-        .expect("expected nb_bytes as 10th positional argument")
-        .parse()
-        .expect("could not parse nb_bytes as a usize");
-    let encoding_method = std::env::args()
-        .nth(11)
-        .expect("expected encoding_method as 11th positional argument");
-    let block_hashes = std::env::args().skip(12).collect::<Vec<_>>();
-
-    (
-        bytes,
-        k,
-        n,
-        do_generate_powers,
-        home_dir,
-        do_reconstruct_data,
-        do_verify_blocks,
-        do_combine_blocks,
-        do_inspect_blocks,
-        nb_bytes,
-        encoding_method,
+/// the method used to build the encoding matrix
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum EncodingMethod {
+    Vandermonde,
+    Random,
+}
+
+/// the protocol used to prove and verify the blocks
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum Protocol {
+    SemiAvid,
+    #[cfg(feature = "kzg")]
+    Kzg,
+    #[cfg(feature = "aplonk")]
+    Aplonk,
+    Fri,
+}
+
+/// the elliptic curve of the setup and the blocks
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum Curve {
+    #[value(name = "bls12-381")]
+    Bls12_381,
+}
+
+/// SACLIN, the Semi-AVID CLI, to encode and prove data
+///
+/// all the options below, but `--output` and `--quiet`, can also be given with `KOMODO_*` environment variables
+/// or in a configuration file, see `--config`.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None, after_help = error::EXIT_CODES)]
+struct Cli {
+    /// a configuration file, with the defaults of the options below and of `-k` and `-n`
+    #[arg(long, env = "KOMODO_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// the directory where the trusted setup, the blocks and the manifests are stored
+    #[arg(long, env = "KOMODO_HOME")]
+    home: Option<PathBuf>,
+
+    /// the protocol of the setup and the blocks, defaults to Semi-AVID
+    #[arg(long, value_enum, env = "KOMODO_PROTOCOL", global = true)]
+    protocol: Option<Protocol>,
+
+    /// the elliptic curve of the setup and the blocks, only BLS12-381 is supported
+    #[arg(long, value_enum, env = "KOMODO_CURVE")]
+    curve: Option<Curve>,
+
+    /// the directory of the trusted setups, defaults to `--home`
+    #[arg(long, env = "KOMODO_POWERS")]
+    powers: Option<PathBuf>,
+
+    /// the format of the data printed to stdout, the logs and the errors always go to stderr
+    #[arg(long, value_enum, default_value = "plain")]
+    output: Output,
+
+    /// the level of the logs that are not matched by `--log-filter`, defaults to `info`
+    #[arg(long, value_enum, env = "KOMODO_LOG_LEVEL")]
+    log_level: Option<LogLevel>,
+
+    /// per-module filters of the logs, e.g. `komodo::fs=debug,saclin=warn`
+    #[arg(long, env = "RUST_LOG")]
+    log_filter: Option<String>,
+
+    /// the format of the logs, which always go to stderr, defaults to `text`
+    #[arg(long, value_enum, env = "KOMODO_LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+
+    /// do not show the progress bars of the long operations, which are only shown when stderr is a
+    /// terminal anyway
+    #[arg(short, long)]
+    quiet: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum SetupCommand {
+    /// create a random trusted setup for a given amount of data
+    Generate {
+        /// the size of the biggest expected data during the lifetime of the application
+        nb_bytes: usize,
+        /// the number of source shards, only required by aPlonK
+        #[arg(short, env = "KOMODO_K")]
+        k: Option<usize>,
+        /// generate a reproducible setup from a seed, e.g. for tests
+        ///
+        /// anyone knowing the seed can forge proofs, such a setup must never be used in
+        /// production
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// show the size, the degree and the digest of the trusted setup
+    Inspect,
+    /// import a trusted setup, e.g. the output of a ceremony, replacing the current one
+    Import {
+        /// a raw serialization of the setup or a file written by Komodo
+        path: PathBuf,
+    },
+    /// export the trusted setup as a raw serialization, without header
+    Export {
+        path: PathBuf,
+        /// trim the setup to polynomials of at most this degree
+        #[arg(long)]
+        degree: Option<usize>,
+    },
+    /// print the digest of the trusted setup, as found in the manifests and the blocks
+    Digest,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// manage the trusted setup of `--protocol`
+    Setup {
+        #[command(subcommand)]
+        command: SetupCommand,
+    },
+    /// encode and prove a file, the hashes of the new blocks are printed
+    Encode {
+        /// the file to encode and prove
+        input: PathBuf,
+        /// the number of source shards
+        #[arg(short, env = "KOMODO_K")]
+        k: Option<usize>,
+        /// the total number of shards
+        #[arg(short, env = "KOMODO_N")]
+        n: Option<usize>,
+        /// KZG+ and aPlonK only support the Vandermonde encoding
+        #[arg(long, value_enum, default_value = "random")]
+        encoding_method: EncodingMethod,
+        /// encode the file chunk by chunk, each chunk of `chunk_size` bytes being encoded and
+        /// proven independently, to process arbitrarily large files with a bounded memory
+        #[arg(long)]
+        chunk_size: Option<usize>,
+    },
+    /// verify the integrity of any number of blocks, concurrently, and exit with an error if any
+    /// of them is invalid
+    Verify {
+        #[arg(required_unless_present = "all")]
+        blocks: Vec<String>,
+        /// verify all the blocks of the store that belong to `--protocol`
+        #[arg(long, conflicts_with = "blocks")]
+        all: bool,
+        /// the number of threads used to read and verify the blocks, defaults to the number of
+        /// cores
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// the expected digest of the trusted setup, in hex, as printed by `setup digest`
+        ///
+        /// no block is verified if the digest of the local setup is not this one.
+        #[arg(long, env = "KOMODO_SETUP_DIGEST")]
+        setup_digest: Option<String>,
+    },
+    /// reconstruct the original data from a subset of blocks, chunk after chunk
+    Reconstruct { blocks: Vec<String> },
+    /// reconstruct the original data from a subset of blocks and write the raw bytes
+    Decode {
+        blocks: Vec<String>,
+        /// the file to write the data to
+        #[arg(short = 'o', long = "output", required_unless_present = "stdout")]
+        path: Option<PathBuf>,
+        /// write the raw data to _stdout_ instead of a file
+        #[arg(long, conflicts_with = "path")]
+        stdout: bool,
+    },
+    /// compute linear combinations of blocks into new blocks, Semi-AVID only
+    Recode {
+        blocks: Vec<String>,
+        /// the number of new blocks, each one with random coefficients
+        #[arg(long, default_value_t = 1)]
+        count: usize,
+        /// the coefficients of a new block, one per block and separated by commas, e.g.
+        /// `--coeffs 1,2,3`, can be repeated to create multiple blocks
+        #[arg(long, value_parser = parse_coeffs, conflicts_with = "count")]
+        coeffs: Vec<Coeffs>,
+    },
+    /// describe any number of blocks, e.g. their protocol, their size and their proof, see
+    /// `--output table` for a compact view
+    Inspect {
+        #[arg(required_unless_present = "all")]
+        blocks: Vec<String>,
+        /// inspect all the blocks of the store that belong to `--protocol`
+        #[arg(long, conflicts_with = "blocks")]
+        all: bool,
+        /// only show the blocks whose summary matches `key=value`, e.g. `k=3`, `source=true` or
+        /// `file_hash=<prefix>`, can be repeated
+        #[arg(long = "filter", value_parser = inspect::parse_filter)]
+        filters: Vec<inspect::Filter>,
+        /// show the content of the shards and the commitments as well
+        #[arg(long)]
+        full: bool,
+    },
+    /// serve the store over HTTP, to upload data, fetch and submit blocks and query manifests
+    ///
+    /// - `POST /data?k=<k>&n=<n>[&chunk_size=<size>][&encoding_method=<method>]`: encode and prove
+    ///   the body of the request, as `encode` does
+    /// - `GET /blocks` and `GET /blocks/<hash>`: list the blocks and download the raw file of a
+    ///   block
+    /// - `POST /blocks`: verify the raw file of a block and store it if it is valid
+    /// - `GET /manifests` and `GET /manifests/<hash>`: list the manifests and show a manifest,
+    ///   together with the status of its blocks
+    ///
+    /// all the responses but the raw blocks are JSON documents.
+    Serve {
+        /// the address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        address: String,
+    },
+    /// export the blocks of a file, and its manifest if any, to a single archive
+    Export {
+        /// the hash of the file, i.e. the name of its manifest, as given by `encode`
+        file_hash: String,
+        /// the archive to write
+        #[arg(long)]
+        to: PathBuf,
+    },
+    /// import the blocks, and the manifest if any, of an archive written by `export`
+    ///
+    /// the blocks are checked against their hash, and the ones that are already present are left
+    /// untouched.
+    Import { archive: PathBuf },
+    /// estimate the size of the blocks and the time to prove and verify them, for some data
+    /// encoded and proven with `--protocol`, without touching any data nor setup
+    Estimate {
+        /// the size of the data
+        #[arg(long)]
+        nb_bytes: usize,
+        /// the number of source shards
+        #[arg(short, env = "KOMODO_K")]
+        k: Option<usize>,
+        /// the total number of shards
+        #[arg(short, env = "KOMODO_N")]
+        n: Option<usize>,
+    },
+    /// measure the main operations of Komodo on this machine, to calibrate the parameters, without
+    /// touching the store
+    ///
+    /// the end-to-end measures use `--protocol` and a temporary store, with a new setup.
+    Bench {
+        /// the suites to run, all of them by default
+        #[arg(long, value_enum, value_delimiter = ',')]
+        suites: Vec<bench::Suite>,
+        /// the size of the data to encode, recode and prove
+        #[arg(long, default_value_t = 1024)]
+        nb_bytes: usize,
+        /// the number of source shards
+        #[arg(short, env = "KOMODO_K")]
+        k: Option<usize>,
+        /// the total number of shards
+        #[arg(short, env = "KOMODO_N")]
+        n: Option<usize>,
+        /// the number of times each operation is measured, larger values will reduce the variance
+        /// of the measurements
+        #[arg(long, default_value_t = 10)]
+        nb_measurements: usize,
+    },
+}
+
+/// the coefficients of a linear combination of blocks
+#[derive(Debug, Clone)]
+struct Coeffs(Vec<Fr>);
+
+/// parse a comma-separated list of field elements, in decimal
+fn parse_coeffs(s: &str) -> Result<Coeffs, String> {
+    s.split(',')
+        .map(|c| {
+            Fr::from_str(c.trim()).map_err(|_| format!("{:?} is not a valid field element", c))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Coeffs)
+}
+
+/// the code parameters $(k, n)$, either given on the command line or found in the config
+fn fec_params(k: Option<usize>, n: Option<usize>, config: &Config) -> (usize, usize) {
+    match (k.or(config.k), n.or(config.n)) {
+        (Some(k), Some(n)) => (k, n),
+        _ => throw_error(
+            ErrorKind::Usage,
+            "`-k` and `-n` are required, either on the command line, with `KOMODO_K` and \
+                `KOMODO_N` or in the config",
+        ),
+    }
+}
+
+/// the error of all the commands that would need to store FRI blocks
+fn fri_unsupported() -> anyhow::Error {
+    anyhow!("FRI blocks cannot be stored, their commitment and proofs are not serializable")
+}
+
+/// the $n$ evaluation points of a Vandermonde encoding
+fn evaluation_points(n: usize) -> Vec<Fr> {
+    (0..n)
+        .map(|i| Fr::from_le_bytes_mod_order(&i.to_le_bytes()))
+        .collect()
+}
+
+/// the point at which a shard of a Vandermonde encoding has been evaluated, i.e. the second
+/// element of its linear combination
+#[cfg(any(feature = "kzg", feature = "aplonk"))]
+fn evaluation_point(shard: &Shard<Fr>) -> Result<Fr> {
+    shard
+        .linear_combination
+        .get(1)
+        .copied()
+        .ok_or_else(|| anyhow!("cannot find the evaluation point of a shard with k < 2"))
+}
+
+/// read any number of blocks with `jobs` threads, see [`fs::read_blocks_batched`]
+fn read_blocks_batched<B: CanonicalDeserialize + Send>(
+    block_hashes: &[String],
+    block_dir: &Path,
+    jobs: usize,
+) -> Vec<(String, Result<B>)> {
+    fs::read_blocks_batched::<B>(
         block_hashes,
+        block_dir,
+        &fs::ReadOptions {
+            compress: COMPRESS,
+            validate: VALIDATE,
+            nb_threads: jobs,
+            layout: LAYOUT,
+            encryption: ENCRYPTION,
+        },
     )
 }
 
-fn throw_error(code: i32, message: &str) {
-    eprint!("{}", message);
-    exit(code);
+/// verify any number of blocks with `jobs` threads, in the same way as
+/// [`fs::read_blocks_batched`] reads them
+///
+/// a block that could not be read, or whose verification fails with an error, is invalid.
+fn verify_in_parallel<B: Sync>(
+    blocks: &[(String, Result<B>)],
+    jobs: usize,
+    progress: &Progress,
+    verify: impl Fn(&B) -> Result<bool> + Sync,
+) -> Vec<(String, bool)> {
+    if blocks.is_empty() {
+        return vec![];
+    }
+
+    let chunk_size = blocks.len().div_ceil(jobs.clamp(1, blocks.len()));
+    let verify = &verify;
+    std::thread::scope(|scope| {
+        let handles = blocks
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(h, b)| {
+                            let status = match b.as_ref() {
+                                Ok(b) => verify(b).unwrap_or_else(|e| {
+                                    warn!("could not verify block {}: {}", h, e);
+                                    false
+                                }),
+                                Err(e) => {
+                                    warn!("could not read block {}: {}", h, e);
+                                    false
+                                }
+                            };
+                            progress.inc(1);
+                            (h.clone(), status)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("a block verification thread panicked"))
+            .collect()
+    })
 }
 
-fn generate_random_powers<F, G, P>(
-    n: usize,
-    powers_dir: &Path,
-    powers_filename: Option<&str>,
-    rng: &mut impl RngCore,
-) -> Result<()>
-where
-    F: PrimeField,
-    G: CurveGroup<ScalarField = F>,
-    P: DenseUVPolynomial<F>,
-    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
-{
-    info!("generating new powers");
-    let powers = zk::setup::<F, G>(zk::nb_elements_in_setup::<F>(n), rng)?;
-
-    fs::dump(&powers, powers_dir, powers_filename, COMPRESS)?;
-
-    Ok(())
+fn read_blocks<B: CanonicalDeserialize>(
+    block_hashes: &[String],
+    block_dir: &Path,
+) -> Vec<(String, B)> {
+    fs::read_blocks::<B>(
+        block_hashes,
+        block_dir,
+        LAYOUT,
+        COMPRESS,
+        VALIDATE,
+        ENCRYPTION,
+    )
+    .unwrap_or_else(|e| throw_error(ErrorKind::Io, &format!("could not read blocks: {}", e)))
 }
 
-fn verify_blocks<F, G, P>(
-    blocks: &[(String, Block<F, G>)],
-    powers: Powers<F, G>,
-) -> Result<(), KomodoError>
-where
-    F: PrimeField,
-    G: CurveGroup<ScalarField = F>,
-    P: DenseUVPolynomial<F>,
-    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
-{
-    let res = blocks
-        .iter()
-        .map(|(f, b)| Ok((f, verify::<F, G, P>(b, &powers)?)))
-        .collect::<Result<Vec<(&String, bool)>, KomodoError>>()?;
+/// decode the shards of any number of chunks, in order, and check the integrity of each chunk
+/// against the hash of its shards, see [`fec::chunk_hash`]
+///
+/// consecutive shards with the same hash belong to the same chunk, as in [`fec::decode_chunks`].
+fn decode_checked(shards: &[Shard<Fr>]) -> Result<Vec<u8>> {
+    if shards.is_empty() {
+        return Err(anyhow!("no blocks to decode"));
+    }
 
-    eprint!("[");
-    for (f, v) in res {
-        eprint!("{{block: {:?}, status: {}}}", f, v);
+    let mut bytes = vec![];
+    for (i, chunk) in shards.chunk_by(|a, b| a.hash == b.hash).enumerate() {
+        let data = decode::<Fr>(chunk.to_vec())
+            .map_err(|e| anyhow!("could not decode chunk {}: {}", i, e))?;
+        if fec::chunk_hash(i, &data) != chunk[0].hash {
+            return Err(anyhow!(
+                "chunk {} does not match the hash of its shards, the blocks might be corrupted or \
+                not given in order",
+                i
+            ));
+        }
+        bytes.extend(data);
     }
-    eprint!("]");
-    Ok(())
+
+    Ok(bytes)
 }
 
-fn main() {
-    tracing_subscriber::fmt::try_init().expect("cannot init logger");
+/// read the shards of any number of blocks, whatever their protocol
+fn read_shards(
+    protocol: Protocol,
+    block_hashes: &[String],
+    block_dir: &Path,
+) -> Vec<(String, Shard<Fr>)> {
+    match protocol {
+        Protocol::SemiAvid => read_blocks::<Block<Fr, G1Projective>>(block_hashes, block_dir)
+            .into_iter()
+            .map(|(h, b)| (h, b.shard))
+            .collect(),
+        #[cfg(feature = "kzg")]
+        Protocol::Kzg => read_blocks::<kzg::Block>(block_hashes, block_dir)
+            .into_iter()
+            .map(|(h, b)| (h, b.shard))
+            .collect(),
+        #[cfg(feature = "aplonk")]
+        Protocol::Aplonk => read_blocks::<aplonk::Block>(block_hashes, block_dir)
+            .into_iter()
+            .map(|(h, b)| (h, b.shard))
+            .collect(),
+        Protocol::Fri => throw_error(
+            ErrorKind::Usage,
+            &format!("could not read blocks: {}", fri_unsupported()),
+        ),
+    }
+}
 
-    let mut rng = rand::thread_rng();
+/// the setup of a protocol and its digest, read once to prove all the chunks of a file
+enum Prover {
+    SemiAvid(Powers<Fr, G1Projective>, Vec<u8>),
+    #[cfg(feature = "kzg")]
+    Kzg(kzg::Setup, Vec<u8>),
+    #[cfg(feature = "aplonk")]
+    Aplonk(aplonk::Setup, Vec<u8>),
+}
+
+impl Prover {
+    /// the digest of the setup, see [`fs::Manifest::setup_digest`]
+    fn setup_digest(&self) -> &[u8] {
+        match self {
+            Prover::SemiAvid(_, digest) => digest,
+            #[cfg(feature = "kzg")]
+            Prover::Kzg(_, digest) => digest,
+            #[cfg(feature = "aplonk")]
+            Prover::Aplonk(_, digest) => digest,
+        }
+    }
+
+    /// prove the shards of a chunk and dump the blocks to `block_dir`
+    fn prove(&self, chunk: &EncodedChunk<Fr>, k: usize, block_dir: &Path) -> Result<fs::Manifest> {
+        match self {
+            Prover::SemiAvid(powers, digest) => {
+                let proof = prove::<Fr, G1Projective, DensePolynomial<Fr>>(&chunk.data, powers, k)
+                    .map_err(|e| anyhow!("could not prove: {}", e))?;
+                let blocks = build::<Fr, G1Projective, DensePolynomial<Fr>>(&chunk.shards, &proof);
+                dump_chunk(&blocks, chunk, k, PROTOCOL, digest, block_dir)
+            }
+            #[cfg(feature = "kzg")]
+            Prover::Kzg(setup, digest) => {
+                let blocks = kzg::prove(chunk, k, setup)?;
+                dump_chunk(&blocks, chunk, k, kzg::PROTOCOL, digest, block_dir)
+            }
+            #[cfg(feature = "aplonk")]
+            Prover::Aplonk(setup, digest) => {
+                let blocks = aplonk::prove(chunk, k, setup)?;
+                dump_chunk(&blocks, chunk, k, aplonk::PROTOCOL, digest, block_dir)
+            }
+        }
+    }
+}
+
+/// dump the blocks of a chunk to `block_dir` and describe them in a manifest
+fn dump_chunk<B: CanonicalSerialize>(
+    blocks: &[B],
+    chunk: &EncodedChunk<Fr>,
+    k: usize,
+    protocol: &str,
+    setup_digest: &[u8],
+    block_dir: &Path,
+) -> Result<fs::Manifest> {
+    let file_hash = chunk.shards[0].hash.clone();
+    let header = fs::Header::new(protocol, CURVE, setup_digest).for_blocks(k as u32, &file_hash);
+
+    let block_hashes = blocks
+        .iter()
+        .map(|b| {
+            fs::dump_block(
+                b,
+                block_dir,
+                LAYOUT,
+                &header,
+                COMPRESS,
+                COMPRESSION,
+                ENCRYPTION,
+            )
+        })
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| anyhow!("could not dump blocks: {}", e))?;
 
-    let (
-        bytes,
+    Ok(fs::Manifest {
+        file_hash,
+        size: chunk.data.len(),
         k,
-        n,
-        do_generate_powers,
-        home_dir,
-        do_reconstruct_data,
-        do_verify_blocks,
-        do_combine_blocks,
-        do_inspect_blocks,
-        nb_bytes,
-        encoding_method,
+        n: chunk.shards.len(),
+        curve: CURVE.to_string(),
+        setup_digest: setup_digest.to_vec(),
         block_hashes,
-    ) = parse_args();
-
-    let home_dir = PathBuf::from(&home_dir);
-    let block_dir = home_dir.join("blocks/");
-    let powers_dir = home_dir;
-    let powers_filename = "powers";
-    let powers_file = powers_dir.join(powers_filename);
+        sealing: None,
+    })
+}
 
-    if do_generate_powers {
-        generate_random_powers::<Fr, G1Projective, DensePolynomial<Fr>>(
-            nb_bytes,
-            &powers_dir,
-            Some(powers_filename),
-            &mut rng,
-        )
-        .unwrap_or_else(|e| throw_error(1, &format!("could not generate powers: {}", e)));
+/// where the blocks, the manifests and the trusted setups of a protocol are stored
+struct Store {
+    protocol: Protocol,
+    block_dir: PathBuf,
+    manifest_dir: PathBuf,
+    #[cfg_attr(not(any(feature = "kzg", feature = "aplonk")), allow(dead_code))]
+    powers_dir: PathBuf,
+    powers_file: PathBuf,
+}
 
-        exit(0);
+impl Store {
+    /// the name of the protocol, as found in the headers of the blocks
+    fn protocol_name(&self) -> Result<&'static str> {
+        match self.protocol {
+            Protocol::SemiAvid => Ok(PROTOCOL),
+            #[cfg(feature = "kzg")]
+            Protocol::Kzg => Ok(kzg::PROTOCOL),
+            #[cfg(feature = "aplonk")]
+            Protocol::Aplonk => Ok(aplonk::PROTOCOL),
+            Protocol::Fri => Err(fri_unsupported()),
+        }
     }
 
-    if do_reconstruct_data {
-        let blocks: Vec<Shard<Fr>> =
-            fs::read_blocks::<Fr, G1Projective>(&block_hashes, &block_dir, COMPRESS, VALIDATE)
-                .unwrap_or_else(|e| {
-                    throw_error(1, &format!("could not read blocks: {}", e));
-                    unreachable!()
-                })
-                .iter()
-                .cloned()
-                .map(|b| b.1.shard)
+    /// export the blocks of a file to an archive, see [`fs::export_archive`]
+    ///
+    /// the blocks are the ones of the manifest of the file, or the ones whose header refers to
+    /// the file if there is no manifest, e.g. after importing blocks without their manifest.
+    fn export(&self, file_hash: &str, to: &Path) -> Result<Value> {
+        if file_hash.is_empty() || !file_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!("{:?} is not a valid file hash", file_hash));
+        }
+        let file_hash = file_hash.to_lowercase();
+
+        let manifest_file = self.manifest_dir.join(&file_hash);
+        let (manifest, block_hashes) = if manifest_file.is_file() {
+            let manifest = fs::Manifest::load(&file_hash, &self.manifest_dir, COMPRESS, VALIDATE)?;
+            let status = manifest.verify(&self.block_dir, LAYOUT)?;
+            for (blocks, what) in [
+                (&status.missing, "missing"),
+                (&status.corrupted, "corrupted"),
+            ] {
+                if !blocks.is_empty() {
+                    warn!(
+                        "{} blocks are {} and will not be exported: {}",
+                        blocks.len(),
+                        what,
+                        fs::format_hashes(blocks)
+                    );
+                }
+            }
+            (Some(manifest_file), status.present)
+        } else {
+            let blocks = fs::list_blocks(&self.block_dir)?
+                .into_iter()
+                .filter(|b| setup::hex(&b.file_hash) == file_hash)
+                .map(|b| b.hash)
                 .collect();
-        eprintln!(
-            "{:?}",
-            decode::<Fr>(blocks).unwrap_or_else(|e| {
-                throw_error(1, &format!("could not decode: {}", e));
-                unreachable!()
-            })
+            (None, blocks)
+        };
+        if block_hashes.is_empty() {
+            return Err(anyhow!("no blocks of file {} in the store", file_hash));
+        }
+
+        let res = std::fs::File::create(to)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| {
+                fs::export_archive(
+                    &block_hashes,
+                    &self.block_dir,
+                    LAYOUT,
+                    manifest.as_deref(),
+                    std::io::BufWriter::new(file),
+                )
+            });
+        if let Err(e) = res {
+            let _ = std::fs::remove_file(to);
+            return Err(e);
+        }
+        info!("{} blocks exported to {:?}", block_hashes.len(), to);
+
+        Ok(Value::Record(vec![
+            ("archive", to.display().to_string().into()),
+            ("manifest", manifest.is_some().then_some(file_hash).into()),
+            ("blocks", block_hashes.into()),
+        ]))
+    }
+
+    /// import an archive written by [`Store::export`], see [`fs::import_archive`]
+    fn import(&self, archive: &Path) -> Result<Value> {
+        let content = fs::import_archive(
+            std::io::BufReader::new(std::fs::File::open(archive)?),
+            &self.block_dir,
+            LAYOUT,
+            &self.manifest_dir,
+        )?;
+        info!(
+            "{} blocks imported from {:?}",
+            content.blocks.len(),
+            archive
         );
 
-        exit(0);
+        Ok(Value::Record(vec![
+            ("manifest", content.manifest.into()),
+            ("blocks", content.blocks.into()),
+        ]))
     }
 
-    if do_combine_blocks {
-        let blocks =
-            fs::read_blocks::<Fr, G1Projective>(&block_hashes, &block_dir, COMPRESS, VALIDATE)
-                .unwrap_or_else(|e| {
-                    throw_error(1, &format!("could not read blocks: {}", e));
-                    unreachable!()
-                });
+    /// the hashes of all the blocks of the protocol
+    fn list_blocks(&self) -> Result<Vec<String>> {
+        let protocol = self.protocol_name()?;
+        // NOTE: blocks without a header are assumed to be of the requested protocol
+        Ok(fs::list_blocks(&self.block_dir)?
+            .into_iter()
+            .filter(|b| b.protocol == protocol || b.protocol.is_empty())
+            .map(|b| b.hash)
+            .collect())
+    }
 
-        let formatted_output = fs::dump_blocks(
-            &[recode(
-                &blocks.iter().map(|(_, b)| b).cloned().collect::<Vec<_>>(),
-                &mut rng,
-            )
-            .unwrap_or_else(|e| {
-                throw_error(1, &format!("could not encode block: {}", e));
-                unreachable!()
-            })
-            .unwrap_or_else(|| {
-                throw_error(1, "could not recode block (list of blocks is likely empty)");
-                unreachable!()
-            })],
-            &block_dir,
+    /// read the setup of the protocol, once for all the chunks to prove
+    fn read_prover(&self) -> Result<Prover> {
+        match self.protocol {
+            Protocol::SemiAvid => {
+                let powers = setup::read(&self.powers_file)?;
+                let digest = fs::Manifest::setup_digest(&powers, COMPRESS)?;
+                Ok(Prover::SemiAvid(powers, digest))
+            }
+            #[cfg(feature = "kzg")]
+            Protocol::Kzg => {
+                let setup = kzg::read_setup(&self.powers_dir)?;
+                let digest = fs::Manifest::setup_digest(&setup, COMPRESS)?;
+                Ok(Prover::Kzg(setup, digest))
+            }
+            #[cfg(feature = "aplonk")]
+            Protocol::Aplonk => {
+                let setup = aplonk::read_setup(&self.powers_dir)?;
+                let digest = fs::Manifest::setup_digest(&setup, COMPRESS)?;
+                Ok(Prover::Aplonk(setup, digest))
+            }
+            Protocol::Fri => Err(fri_unsupported()),
+        }
+    }
+
+    /// generate a new trusted setup for `nb_bytes` of data and dump it to the store
+    ///
+    /// only aPlonK needs $k$ to build its setup.
+    #[cfg_attr(not(feature = "aplonk"), allow(unused_variables))]
+    fn generate_setup(
+        &self,
+        nb_bytes: usize,
+        k: Option<usize>,
+        rng: &mut impl RngCore,
+    ) -> Result<()> {
+        match self.protocol {
+            Protocol::SemiAvid => {
+                let powers =
+                    zk::setup::<Fr, G1Projective>(zk::nb_elements_in_setup::<Fr>(nb_bytes), rng)?;
+                setup::dump(&powers, &self.powers_file, PROTOCOL).map(|_| ())
+            }
+            #[cfg(feature = "kzg")]
+            Protocol::Kzg => kzg::generate(nb_bytes, &self.powers_dir, rng),
+            #[cfg(feature = "aplonk")]
+            Protocol::Aplonk => k
+                .ok_or_else(|| anyhow!("aPlonK requires `-k` to build its setup"))
+                .and_then(|k| aplonk::generate(nb_bytes, k, &self.powers_dir)),
+            Protocol::Fri => Err(anyhow!("FRI is transparent and does not need any setup")),
+        }
+    }
+
+    /// encode, prove and store some data read chunk by chunk, see [`fec::encode_chunks`], and
+    /// save one manifest per chunk
+    ///
+    /// a summary of the new chunks and blocks is returned.
+    fn encode(
+        &self,
+        reader: impl std::io::Read,
+        (k, n): (usize, usize),
+        encoding_method: EncodingMethod,
+        chunk_size: Option<usize>,
+        rng: &mut impl RngCore,
+        progress: &Progress,
+    ) -> Result<Value> {
+        let prover = self
+            .read_prover()
+            .map_err(|e| anyhow!("could not read setup: {}", e))?;
+
+        let encoding_mat = match (self.protocol, encoding_method) {
+            (Protocol::SemiAvid, EncodingMethod::Random) => Matrix::random(k, n, rng),
+            (Protocol::SemiAvid, EncodingMethod::Vandermonde) => {
+                Matrix::vandermonde_unchecked(&evaluation_points(n), k)
+            }
+            (protocol, method) => {
+                if matches!(method, EncodingMethod::Random) {
+                    warn!("{:?} always uses a Vandermonde encoding", protocol);
+                }
+                Matrix::vandermonde_unchecked(&evaluation_points(n), k)
+            }
+        };
+
+        let mut size = 0;
+        let mut chunks = vec![];
+        let mut block_hashes = vec![];
+        for chunk in fec::encode_chunks(reader, chunk_size.unwrap_or(usize::MAX), &encoding_mat) {
+            let chunk = chunk.map_err(|e| anyhow!("could not encode: {}", e))?;
+            info!(
+                "proving chunk {} of {} bytes",
+                chunk.index,
+                chunk.data.len()
+            );
+            let manifest = prover.prove(&chunk, k, &self.block_dir)?;
+            progress.inc(chunk.data.len() as u64);
+            let manifest_filename = manifest
+                .save(&self.manifest_dir, COMPRESS)
+                .map_err(|e| anyhow!("could not save manifest: {}", e))?;
+            info!(
+                "manifest saved to `{:?}`",
+                self.manifest_dir.join(&manifest_filename)
+            );
+
+            size += manifest.size;
+            block_hashes.extend(manifest.block_hashes.clone());
+            chunks.push(Value::Record(vec![
+                (
+                    "hash",
+                    manifest
+                        .file_hash
+                        .iter()
+                        .map(|x| format!("{:x}", x))
+                        .collect::<String>()
+                        .into(),
+                ),
+                ("size", manifest.size.into()),
+                ("manifest", manifest_filename.into()),
+                ("blocks", manifest.block_hashes.into()),
+            ]));
+        }
+        if chunks.is_empty() {
+            return Err(anyhow!("the data is empty, there is nothing to encode"));
+        }
+
+        Ok(Value::Record(vec![
+            ("size", size.into()),
+            ("k", k.into()),
+            ("n", n.into()),
+            ("chunks", Value::List(chunks)),
+            ("blocks", block_hashes.into()),
+        ]))
+    }
+
+    /// make sure the setup of the protocol is the expected one, given as a hex digest
+    fn check_setup_digest(&self, expected: &str) -> Result<()> {
+        let prover = self.read_prover()?;
+        let digest = setup::hex(prover.setup_digest());
+        if !digest.eq_ignore_ascii_case(expected.trim()) {
+            return Err(anyhow!(
+                "the digest of the setup is {}, expected {}",
+                digest,
+                expected
+            ));
+        }
+        Ok(())
+    }
+
+    /// verify any number of stored blocks with `jobs` threads
+    fn verify(
+        &self,
+        block_hashes: &[String],
+        jobs: usize,
+        progress: &Progress,
+    ) -> Result<Vec<(String, bool)>> {
+        match self.protocol {
+            Protocol::SemiAvid => {
+                let blocks = read_blocks_batched::<Block<Fr, G1Projective>>(
+                    block_hashes,
+                    &self.block_dir,
+                    jobs,
+                );
+                let powers = setup::read(&self.powers_file)?;
+
+                Ok(verify_in_parallel(&blocks, jobs, progress, |b| {
+                    Ok(verify::<Fr, G1Projective, DensePolynomial<Fr>>(b, &powers)?)
+                }))
+            }
+            #[cfg(feature = "kzg")]
+            Protocol::Kzg => kzg::verify(
+                block_hashes,
+                &self.block_dir,
+                &self.powers_dir,
+                jobs,
+                progress,
+            ),
+            #[cfg(feature = "aplonk")]
+            Protocol::Aplonk => aplonk::verify(
+                block_hashes,
+                &self.block_dir,
+                &self.powers_dir,
+                jobs,
+                progress,
+            ),
+            Protocol::Fri => Err(fri_unsupported()),
+        }
+    }
+
+    /// store a block received from someone else, read from `file`, which must have a header
+    fn store_block<B: CanonicalSerialize + CanonicalDeserialize>(
+        &self,
+        file: &Path,
+    ) -> Result<String> {
+        let header = fs::Header::read_from(file)?
+            .ok_or_else(|| anyhow!("blocks without a header cannot be stored"))?;
+        let protocol = self.protocol_name()?;
+        if header.protocol != protocol {
+            return Err(anyhow!(
+                "expected a {} block, found a {} block",
+                protocol,
+                header.protocol
+            ));
+        }
+
+        let block: B = fs::read(file, COMPRESS, VALIDATE, ENCRYPTION)?;
+        fs::dump_block(
+            &block,
+            &self.block_dir,
+            LAYOUT,
+            &header,
             COMPRESS,
+            COMPRESSION,
+            ENCRYPTION,
         )
-        .unwrap_or_else(|e| {
-            throw_error(1, &format!("could not dump block: {}", e));
-            unreachable!()
-        });
+    }
 
-        eprint!("{}", formatted_output);
+    /// verify the block of `file` and store it, an invalid block is never kept in the store
+    ///
+    /// the hash of the block and its status are returned.
+    fn submit(&self, file: &Path) -> Result<(String, bool)> {
+        let hash = match self.protocol {
+            Protocol::SemiAvid => self.store_block::<Block<Fr, G1Projective>>(file)?,
+            #[cfg(feature = "kzg")]
+            Protocol::Kzg => self.store_block::<kzg::Block>(file)?,
+            #[cfg(feature = "aplonk")]
+            Protocol::Aplonk => self.store_block::<aplonk::Block>(file)?,
+            Protocol::Fri => return Err(fri_unsupported()),
+        };
+
+        let valid = self
+            .verify(std::slice::from_ref(&hash), 1, &Progress::hidden())
+            .map(|res| res.first().is_some_and(|(_, v)| *v));
+        if !matches!(valid, Ok(true)) {
+            fs::delete_block(&hash, &self.block_dir, LAYOUT)?;
+        }
 
-        exit(0);
+        Ok((hash, valid?))
     }
+}
 
-    if do_inspect_blocks {
-        let blocks =
-            fs::read_blocks::<Fr, G1Projective>(&block_hashes, &block_dir, COMPRESS, VALIDATE)
-                .unwrap_or_else(|e| {
-                    throw_error(1, &format!("could not read blocks: {}", e));
-                    unreachable!()
-                });
-        eprint!("[");
-        for (_, block) in &blocks {
-            eprint!("{},", block);
+fn main() {
+    let cli = Cli::try_parse().unwrap_or_else(|e| {
+        // NOTE: `--help` and `--version` are not errors
+        if !e.use_stderr() {
+            e.exit();
         }
-        eprintln!("]");
+        let _ = e.print();
+        let message = e.render().to_string();
+        let message = message.lines().next().unwrap_or_default();
+        throw_error(ErrorKind::Usage, message.trim_start_matches("error: "))
+    });
 
-        exit(0);
-    }
+    let config = cli
+        .config
+        .as_ref()
+        .map(|path| Config::read(path))
+        .transpose()
+        .unwrap_or_else(|e| throw_error(ErrorKind::of(&e, ErrorKind::Usage), &e.to_string()))
+        .unwrap_or_default();
 
-    info!("reading powers from file `{:?}`", powers_file);
-    let powers = if let Ok(serialized) = std::fs::read(&powers_file) {
-        info!("deserializing the powers from `{:?}`", powers_file);
-        Powers::<Fr, G1Projective>::deserialize_with_mode(&serialized[..], COMPRESS, VALIDATE)
-            .unwrap_or_else(|e| {
+    let log_level = cli
+        .log_level
+        .or_else(|| {
+            config.log_level.as_ref().map(|l| {
+                LogLevel::from_str(l, true).unwrap_or_else(|_| {
+                    throw_error(
+                        ErrorKind::Usage,
+                        &format!("invalid log level {:?} in the config", l),
+                    )
+                })
+            })
+        })
+        .unwrap_or(LogLevel::Info);
+    let log_format = cli
+        .log_format
+        .or_else(|| {
+            config.log_format.as_ref().map(|f| {
+                LogFormat::from_str(f, true).unwrap_or_else(|_| {
+                    throw_error(
+                        ErrorKind::Usage,
+                        &format!("invalid log format {:?} in the config", f),
+                    )
+                })
+            })
+        })
+        .unwrap_or(LogFormat::Text);
+    logging::targets(
+        log_level,
+        cli.log_filter.as_deref().or(config.log_filter.as_deref()),
+    )
+    .and_then(|targets| logging::init(log_format, targets))
+    .unwrap_or_else(|e| throw_error(ErrorKind::Usage, &e.to_string()));
+
+    let protocol = cli
+        .protocol
+        .or_else(|| {
+            config.protocol.as_ref().map(|p| {
+                Protocol::from_str(p, true).unwrap_or_else(|_| {
+                    throw_error(
+                        ErrorKind::Usage,
+                        &format!("invalid protocol {:?} in the config", p),
+                    )
+                })
+            })
+        })
+        .unwrap_or(Protocol::SemiAvid);
+    if cli.curve.is_none() {
+        if let Some(curve) = &config.curve {
+            if Curve::from_str(curve, true).is_err() {
                 throw_error(
-                    1,
-                    &format!("could not deserialize powers from {:?}: {}", powers_file, e),
+                    ErrorKind::Usage,
+                    &format!("invalid curve {:?} in the config", curve),
                 );
-                unreachable!()
-            })
-    } else {
-        warn!("could not read powers from `{:?}`", powers_file);
-        info!("regenerating temporary powers");
-        zk::setup::<Fr, G1Projective>(zk::nb_elements_in_setup::<Fr>(nb_bytes), &mut rng)
+            }
+        }
+    }
+
+    // NOTE: estimating and measuring do not need any store
+    if let Command::Estimate { nb_bytes, k, n } = cli.command {
+        let (k, n) = fec_params(k, n, &config);
+        estimate::run(protocol, nb_bytes, k, n)
+            .map(|value| println!("{}", value.render(cli.output)))
             .unwrap_or_else(|e| {
-                throw_error(1, &format!("could not generate powers: {}", e));
-                unreachable!()
-            })
-    };
+                throw_error(ErrorKind::Usage, &format!("could not estimate: {}", e))
+            });
+        return;
+    }
+    if let Command::Bench {
+        suites,
+        nb_bytes,
+        k,
+        n,
+        nb_measurements,
+    } = cli.command
+    {
+        let (k, n) = fec_params(k, n, &config);
+        let params = bench::Params {
+            nb_bytes,
+            k,
+            n,
+            nb_measurements,
+        };
+        bench::run(protocol, &suites, &params, cli.quiet)
+            .map(|value| println!("{}", value.render(cli.output)))
+            .unwrap_or_else(|e| {
+                throw_error(
+                    ErrorKind::of(&e, ErrorKind::Failure),
+                    &format!("could not measure: {}", e),
+                )
+            });
+        return;
+    }
 
-    if do_verify_blocks {
-        verify_blocks::<Fr, G1Projective, DensePolynomial<Fr>>(
-            &fs::read_blocks::<Fr, G1Projective>(&block_hashes, &block_dir, COMPRESS, VALIDATE)
-                .unwrap_or_else(|e| {
-                    throw_error(1, &format!("could not read blocks: {}", e));
-                    unreachable!()
-                }),
-            powers,
+    let home = cli.home.or(config.home.clone()).unwrap_or_else(|| {
+        throw_error(
+            ErrorKind::Usage,
+            "no home directory, please use `--home`, `KOMODO_HOME` or `home` in the config",
         )
-        .unwrap_or_else(|e| {
-            throw_error(1, &format!("Failed to verify blocks: {}", e));
-            unreachable!()
-        });
+    });
+
+    let mut rng = rand::thread_rng();
+
+    let block_dir = home.join("blocks/");
+    let manifest_dir = home.join("manifests/");
+    let powers_dir = cli
+        .powers
+        .or(config.powers.clone())
+        .unwrap_or_else(|| home.clone());
+    let powers_filename = "powers";
+    let powers_file = powers_dir.join(powers_filename);
+
+    let store = Store {
+        protocol,
+        block_dir: block_dir.clone(),
+        manifest_dir: manifest_dir.clone(),
+        powers_dir: powers_dir.clone(),
+        powers_file: powers_file.clone(),
+    };
 
-        exit(0);
+    for dir in [&powers_dir, &block_dir] {
+        if let Err(e) = fs::remove_partial_files(dir) {
+            warn!("could not clean up partial files in `{:?}`: {}", dir, e);
+        }
     }
 
-    let encoding_mat = match encoding_method.as_str() {
-        "vandermonde" => {
-            let points: Vec<Fr> = (0..n)
-                .map(|i| Fr::from_le_bytes_mod_order(&i.to_le_bytes()))
+    match cli.command {
+        Command::Setup { command } => {
+            if let SetupCommand::Generate { nb_bytes, k, seed } = command {
+                let k = k.or(config.k);
+                let mut rng: Box<dyn RngCore> = match seed {
+                    Some(seed) => {
+                        warn!(
+                            "the setup is generated from a seed, it must not be used in production"
+                        );
+                        Box::new(StdRng::seed_from_u64(seed))
+                    }
+                    None => Box::new(rng),
+                };
+                if protocol == Protocol::Fri {
+                    info!("FRI is transparent and does not need any setup");
+                    println!("{}", Value::Record(vec![]).render(cli.output));
+                    return;
+                }
+                #[cfg(feature = "aplonk")]
+                if protocol == Protocol::Aplonk && seed.is_some() {
+                    warn!("the setup of aPlonK cannot be seeded, the seed is ignored");
+                }
+                let progress = Progress::new("generating setup", None, "", cli.quiet);
+                let res = store.generate_setup(nb_bytes, k, &mut rng);
+                drop(progress);
+                res.unwrap_or_else(|e| {
+                    throw_error(
+                        ErrorKind::of(&e, ErrorKind::Failure),
+                        &format!("could not generate setup: {}", e),
+                    )
+                });
+            }
+
+            match protocol {
+                Protocol::SemiAvid => {
+                    setup::run::<Powers<Fr, G1Projective>>(&command, &powers_file, PROTOCOL)
+                }
+                #[cfg(feature = "kzg")]
+                Protocol::Kzg => setup::run::<kzg::Setup>(
+                    &command,
+                    &powers_dir.join(kzg::SETUP_FILENAME),
+                    kzg::PROTOCOL,
+                ),
+                #[cfg(feature = "aplonk")]
+                Protocol::Aplonk => setup::run::<aplonk::Setup>(
+                    &command,
+                    &powers_dir.join(aplonk::SETUP_FILENAME),
+                    aplonk::PROTOCOL,
+                ),
+                Protocol::Fri => Err(anyhow!("FRI is transparent and does not have any setup")),
+            }
+            .map(|value| println!("{}", value.render(cli.output)))
+            .unwrap_or_else(|e| throw_error(ErrorKind::of(&e, ErrorKind::Failure), &e.to_string()))
+        }
+        Command::Reconstruct { blocks } => {
+            let shards: Vec<Shard<Fr>> = read_shards(protocol, &blocks, &block_dir)
+                .into_iter()
+                .map(|(_, s)| s)
                 .collect();
-            Matrix::vandermonde_unchecked(&points, k)
+            let bytes = decode_checked(&shards)
+                .unwrap_or_else(|e| throw_error(ErrorKind::Decode, &e.to_string()));
+            println!("{}", Value::from(bytes).render(cli.output));
         }
-        "random" => Matrix::random(k, n, &mut rng),
-        m => {
-            throw_error(1, &format!("invalid encoding method: {}", m));
-            unreachable!()
+        Command::Decode {
+            blocks,
+            path,
+            stdout,
+        } => {
+            let shards: Vec<Shard<Fr>> = read_shards(protocol, &blocks, &block_dir)
+                .into_iter()
+                .map(|(_, s)| s)
+                .collect();
+            let bytes = decode_checked(&shards)
+                .unwrap_or_else(|e| throw_error(ErrorKind::Decode, &e.to_string()));
+
+            if stdout {
+                let mut out = std::io::stdout().lock();
+                out.write_all(&bytes)
+                    .and_then(|_| out.flush())
+                    .unwrap_or_else(|e| {
+                        throw_error(ErrorKind::Io, &format!("could not write to stdout: {}", e))
+                    });
+            } else if let Some(path) = path {
+                std::fs::write(&path, &bytes).unwrap_or_else(|e| {
+                    throw_error(
+                        ErrorKind::Io,
+                        &format!("could not write to {:?}: {}", path, e),
+                    )
+                });
+                info!("{} bytes written to {:?}", bytes.len(), path);
+                let summary = Value::Record(vec![
+                    ("path", path.display().to_string().into()),
+                    ("size", bytes.len().into()),
+                ]);
+                println!("{}", summary.render(cli.output));
+            }
         }
-    };
+        Command::Recode {
+            blocks: block_hashes,
+            count,
+            coeffs,
+        } => {
+            if protocol != Protocol::SemiAvid {
+                throw_error(
+                    ErrorKind::Usage,
+                    &format!("blocks of protocol {:?} cannot be recoded", protocol),
+                );
+            }
+            let blocks = read_blocks::<Block<Fr, G1Projective>>(&block_hashes, &block_dir)
+                .into_iter()
+                .map(|(_, b)| b)
+                .collect::<Vec<_>>();
 
-    let shards = fec::encode::<Fr>(&bytes, &encoding_mat).unwrap_or_else(|e| {
-        throw_error(1, &format!("could not encode: {}", e));
-        unreachable!()
-    });
-    let proof =
-        prove::<Fr, G1Projective, DensePolynomial<Fr>>(&bytes, &powers, k).unwrap_or_else(|e| {
-            throw_error(1, &format!("could not prove: {}", e));
-            unreachable!()
-        });
-    let blocks = build::<Fr, G1Projective, DensePolynomial<Fr>>(&shards, &proof);
-
-    let formatted_output = fs::dump_blocks(&blocks, &block_dir, COMPRESS).unwrap_or_else(|e| {
-        throw_error(1, &format!("could not dump blocks: {}", e));
-        unreachable!()
-    });
+            // the recoded blocks come from the same data and setup as the blocks they combine
+            let header = block_hashes
+                .first()
+                .and_then(|h| fs::Header::read_from(&LAYOUT.find(&block_dir, h)).ok())
+                .flatten()
+                .unwrap_or_else(|| fs::Header::new(PROTOCOL, CURVE, &[]));
+
+            let recoded = if coeffs.is_empty() {
+                (0..count)
+                    .map(|_| recode(&blocks, &mut rng))
+                    .collect::<Result<Vec<_>, _>>()
+            } else {
+                coeffs
+                    .iter()
+                    .map(|c| recode_with_coeffs(&blocks, &c.0))
+                    .collect::<Result<Vec<_>, _>>()
+            }
+            .unwrap_or_else(|e| {
+                throw_error(
+                    ErrorKind::Decode,
+                    &format!("could not recode blocks: {}", e),
+                )
+            })
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_else(|| {
+                throw_error(
+                    ErrorKind::Decode,
+                    &format!(
+                        "could not recode blocks, the list of blocks is likely empty or there \
+                        are not {} coefficients",
+                        blocks.len()
+                    ),
+                )
+            });
 
-    eprint!("{}", formatted_output);
+            let block_hashes = recoded
+                .iter()
+                .map(|b| {
+                    fs::dump_block(
+                        b,
+                        &block_dir,
+                        LAYOUT,
+                        &header,
+                        COMPRESS,
+                        COMPRESSION,
+                        ENCRYPTION,
+                    )
+                })
+                .collect::<Result<Vec<_>>>()
+                .unwrap_or_else(|e| {
+                    throw_error(ErrorKind::Io, &format!("could not dump blocks: {}", e))
+                });
+
+            println!("{}", Value::from(block_hashes).render(cli.output));
+        }
+        Command::Inspect {
+            blocks,
+            all,
+            filters,
+            full,
+        } => {
+            let blocks = if all {
+                store.list_blocks().unwrap_or_else(|e| {
+                    throw_error(ErrorKind::Io, &format!("could not list blocks: {}", e))
+                })
+            } else {
+                blocks
+            };
+            inspect::run(protocol, &blocks, &block_dir, &filters, full)
+                .map(|value| println!("{}", value.render(cli.output)))
+                .unwrap_or_else(|e| {
+                    throw_error(
+                        ErrorKind::of(&e, ErrorKind::Failure),
+                        &format!("could not inspect blocks: {}", e),
+                    )
+                });
+        }
+        Command::Verify {
+            blocks,
+            all,
+            jobs,
+            setup_digest,
+        } => {
+            if let Some(expected) = setup_digest {
+                store.check_setup_digest(&expected).unwrap_or_else(|e| {
+                    throw_error(
+                        ErrorKind::of(&e, ErrorKind::SetupMismatch),
+                        &format!("refusing to verify blocks: {}", e),
+                    )
+                });
+            }
+
+            let jobs =
+                jobs.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+            let blocks = if all {
+                store.list_blocks().unwrap_or_else(|e| {
+                    throw_error(ErrorKind::Io, &format!("could not list blocks: {}", e))
+                })
+            } else {
+                blocks
+            };
+
+            let start = std::time::Instant::now();
+            let progress =
+                Progress::new("verifying", Some(blocks.len() as u64), "blocks", cli.quiet);
+            let res = store.verify(&blocks, jobs, &progress);
+            drop(progress);
+            let res = res.unwrap_or_else(|e| {
+                throw_error(
+                    ErrorKind::of(&e, ErrorKind::Failure),
+                    &format!("could not verify blocks: {}", e),
+                )
+            });
+            let seconds = start.elapsed().as_secs_f64();
+
+            let invalid = res
+                .iter()
+                .filter(|(_, v)| !v)
+                .map(|(f, _)| f.clone())
+                .collect::<Vec<_>>();
+            let summary = Value::Record(vec![
+                (
+                    "blocks",
+                    Value::List(
+                        res.iter()
+                            .map(|(f, v)| {
+                                Value::Record(vec![
+                                    ("block", f.clone().into()),
+                                    ("status", (*v).into()),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                ),
+                ("valid", (res.len() - invalid.len()).into()),
+                ("invalid", invalid.len().into()),
+                ("seconds", seconds.into()),
+                ("blocks_per_second", (res.len() as f64 / seconds).into()),
+            ]);
+            println!("{}", summary.render(cli.output));
+
+            if !invalid.is_empty() {
+                throw_error(
+                    ErrorKind::Verification,
+                    &format!(
+                        "{} invalid blocks out of {}: {}",
+                        invalid.len(),
+                        res.len(),
+                        fs::format_hashes(&invalid)
+                    ),
+                );
+            }
+        }
+        Command::Export { file_hash, to } => store
+            .export(&file_hash, &to)
+            .map(|value| println!("{}", value.render(cli.output)))
+            .unwrap_or_else(|e| {
+                throw_error(
+                    ErrorKind::of(&e, ErrorKind::Failure),
+                    &format!("could not export {}: {}", file_hash, e),
+                )
+            }),
+        Command::Import { archive } => store
+            .import(&archive)
+            .map(|value| println!("{}", value.render(cli.output)))
+            .unwrap_or_else(|e| {
+                throw_error(
+                    ErrorKind::of(&e, ErrorKind::Failure),
+                    &format!("could not import {:?}: {}", archive, e),
+                )
+            }),
+        Command::Estimate { .. } | Command::Bench { .. } => unreachable!(),
+        Command::Serve { address } => {
+            serve::run(&store, &address, (config.k, config.n)).unwrap_or_else(|e| {
+                throw_error(
+                    ErrorKind::of(&e, ErrorKind::Failure),
+                    &format!("could not serve: {}", e),
+                )
+            });
+        }
+        Command::Encode {
+            input,
+            k,
+            n,
+            encoding_method,
+            chunk_size,
+        } => {
+            let (k, n) = fec_params(k, n, &config);
+            let file = std::fs::File::open(&input).unwrap_or_else(|e| {
+                throw_error(ErrorKind::Io, &format!("could not read {:?}: {}", input, e))
+            });
+
+            let progress = Progress::new(
+                "encoding and proving",
+                file.metadata().ok().map(|m| m.len()),
+                "bytes",
+                cli.quiet,
+            );
+            let summary = store.encode(
+                file,
+                (k, n),
+                encoding_method,
+                chunk_size,
+                &mut rng,
+                &progress,
+            );
+            drop(progress);
+            let summary = summary.unwrap_or_else(|e| {
+                throw_error(
+                    ErrorKind::of(&e, ErrorKind::Failure),
+                    &format!("could not encode {:?}: {}", input, e),
+                )
+            });
+            println!("{}", summary.render(cli.output));
+        }
+    }
 }
@@ -0,0 +1,369 @@
+//! the data printed by SACLIN, to _stdout_, in one of the [`Output`] formats
+//!
+//! > **Note**
+//! >
+//! > all the logs and the errors go to _stderr_, so that _stdout_ always holds a single and
+//! > well-formed document.
+use ark_bls12_381::Fr;
+use ark_std::Zero;
+use clap::ValueEnum;
+
+use komodo::fec::Shard;
+
+/// the format of the data printed by SACLIN
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Output {
+    /// standard JSON
+    Json,
+    /// the _Nushell Object Notation_, see [NUON](https://www.nushell.sh/book/loading_data.html#nuon)
+    Nuon,
+    /// human-readable text, one value per line
+    Plain,
+    /// an aligned table, with one row per record of a list or per field of a record
+    ///
+    /// anything else is rendered as [`Output::Plain`] and nested values are rendered as NUON.
+    Table,
+}
+
+/// a document to print
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    /// the absence of a value, `null` in JSON and NUON and nothing in plain text
+    Null,
+    Bool(bool),
+    Int(u64),
+    Float(f64),
+    String(String),
+    List(Vec<Value>),
+    Record(Vec<(&'static str, Value)>),
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(x: usize) -> Self {
+        Value::Int(x as u64)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(x: u32) -> Self {
+        Value::Int(x as u64)
+    }
+}
+
+impl From<u8> for Value {
+    fn from(x: u8) -> Self {
+        Value::Int(x as u64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Value::Float(x)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(x: Option<T>) -> Self {
+        x.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(xs: Vec<T>) -> Self {
+        Value::List(xs.into_iter().map(Into::into).collect())
+    }
+}
+
+/// a field element as a decimal string
+///
+/// > **Note**
+/// >
+/// > the zero element is displayed as an empty string by `ark-ff`.
+pub(crate) fn element(x: &Fr) -> Value {
+    if x.is_zero() {
+        Value::from("0")
+    } else {
+        Value::String(x.to_string())
+    }
+}
+
+/// a shard, with all its field elements as decimal strings, see [`element`]
+pub(crate) fn shard(shard: &Shard<Fr>) -> Value {
+    Value::Record(vec![
+        ("k", shard.k.into()),
+        (
+            "comb",
+            Value::List(shard.linear_combination.iter().map(element).collect()),
+        ),
+        (
+            "bytes",
+            Value::List(shard.data.iter().map(element).collect()),
+        ),
+        (
+            "hash",
+            shard
+                .hash
+                .iter()
+                .map(|x| format!("{:x}", x))
+                .collect::<String>()
+                .into(),
+        ),
+        ("size", shard.size.into()),
+    ])
+}
+
+/// a JSON string literal, which is also a valid NUON string
+pub(crate) fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// a float with a fixed precision, which is also valid JSON and NUON
+///
+/// > **Note**
+/// >
+/// > JSON has no infinite nor NaN numbers, they are rendered as `null`.
+fn float(x: f64) -> String {
+    if x.is_finite() {
+        format!("{:.3}", x)
+    } else {
+        "null".to_string()
+    }
+}
+
+impl Value {
+    fn is_scalar(&self) -> bool {
+        !matches!(self, Value::List(_) | Value::Record(_))
+    }
+
+    /// JSON and NUON only differ in the keys of the records, which are not quoted in NUON
+    fn structured(&self, quote_keys: bool) -> String {
+        match self {
+            Value::Null => "null".to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Int(x) => x.to_string(),
+            Value::Float(x) => float(*x),
+            Value::String(s) => quote(s),
+            Value::List(xs) => format!(
+                "[{}]",
+                xs.iter()
+                    .map(|x| x.structured(quote_keys))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Value::Record(fields) => format!(
+                "{{{}}}",
+                fields
+                    .iter()
+                    .map(|(k, v)| {
+                        let key = if quote_keys { quote(k) } else { k.to_string() };
+                        format!("{}: {}", key, v.structured(quote_keys))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    fn plain(&self, indent: usize, lines: &mut Vec<String>) {
+        let pad = " ".repeat(indent);
+        match self {
+            Value::Null => lines.push(pad),
+            Value::Bool(b) => lines.push(format!("{}{}", pad, b)),
+            Value::Int(x) => lines.push(format!("{}{}", pad, x)),
+            Value::Float(x) => lines.push(format!("{}{}", pad, float(*x))),
+            Value::String(s) => lines.push(format!("{}{}", pad, s)),
+            Value::List(xs) => {
+                for x in xs {
+                    if x.is_scalar() {
+                        x.plain(indent, lines);
+                    } else {
+                        lines.push(format!("{}-", pad));
+                        x.plain(indent + 2, lines);
+                    }
+                }
+            }
+            Value::Record(fields) => {
+                for (k, v) in fields {
+                    if v.is_scalar() {
+                        let mut value = vec![];
+                        v.plain(0, &mut value);
+                        lines.push(format!("{}{}: {}", pad, k, value.join("")));
+                    } else {
+                        lines.push(format!("{}{}:", pad, k));
+                        v.plain(indent + 2, lines);
+                    }
+                }
+            }
+        }
+    }
+
+    /// a scalar as in [`Output::Plain`], anything else as NUON
+    fn cell(&self) -> String {
+        if self.is_scalar() {
+            let mut cell = vec![];
+            self.plain(0, &mut cell);
+            cell.join("")
+        } else {
+            self.structured(false)
+        }
+    }
+
+    /// the rows of a table, with the header first, or `None` if this is not tabular data
+    fn rows(&self) -> Option<Vec<Vec<String>>> {
+        match self {
+            Value::List(xs) if !xs.is_empty() => {
+                let mut columns: Vec<&'static str> = vec![];
+                for x in xs {
+                    let Value::Record(fields) = x else {
+                        return None;
+                    };
+                    for (k, _) in fields {
+                        if !columns.contains(k) {
+                            columns.push(k);
+                        }
+                    }
+                }
+
+                let mut rows = vec![columns.iter().map(|c| c.to_string()).collect()];
+                for x in xs {
+                    let Value::Record(fields) = x else {
+                        unreachable!()
+                    };
+                    rows.push(
+                        columns
+                            .iter()
+                            .map(|c| {
+                                fields
+                                    .iter()
+                                    .find(|(k, _)| k == c)
+                                    .map(|(_, v)| v.cell())
+                                    .unwrap_or_default()
+                            })
+                            .collect(),
+                    );
+                }
+                Some(rows)
+            }
+            Value::Record(fields) => {
+                let mut rows = vec![vec!["key".to_string(), "value".to_string()]];
+                rows.extend(fields.iter().map(|(k, v)| vec![k.to_string(), v.cell()]));
+                Some(rows)
+            }
+            _ => None,
+        }
+    }
+
+    fn table(&self) -> Option<String> {
+        let rows = self.rows()?;
+        let widths: Vec<usize> = (0..rows[0].len())
+            .map(|i| rows.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+            .collect();
+
+        let line = |row: &Vec<String>| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, w)| format!("{:<w$}", cell, w = w))
+                .collect::<Vec<_>>()
+                .join(" | ")
+                .trim_end()
+                .to_string()
+        };
+        let separator = widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("-+-");
+
+        let mut lines = vec![line(&rows[0]), separator];
+        lines.extend(rows[1..].iter().map(line));
+        Some(lines.join("\n"))
+    }
+
+    pub(crate) fn render(&self, output: Output) -> String {
+        match output {
+            Output::Json => self.structured(true),
+            Output::Nuon => self.structured(false),
+            Output::Plain => {
+                let mut lines = vec![];
+                self.plain(0, &mut lines);
+                lines.join("\n")
+            }
+            Output::Table => self.table().unwrap_or_else(|| self.render(Output::Plain)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Output, Value};
+
+    #[test]
+    fn render() {
+        let value = Value::List(vec![Value::Record(vec![
+            ("block", "a\"b".into()),
+            ("status", true.into()),
+            ("sizes", vec![1usize, 2].into()),
+            ("speed", 1.5.into()),
+            ("manifest", None::<String>.into()),
+        ])]);
+
+        assert_eq!(
+            value.render(Output::Json),
+            r#"[{"block": "a\"b", "status": true, "sizes": [1, 2], "speed": 1.500, "manifest": null}]"#
+        );
+        assert_eq!(
+            value.render(Output::Nuon),
+            r#"[{block: "a\"b", status: true, sizes: [1, 2], speed: 1.500, manifest: null}]"#
+        );
+        assert_eq!(
+            value.render(Output::Plain),
+            "-\n  block: a\"b\n  status: true\n  sizes:\n    1\n    2\n  speed: 1.500\n  manifest: "
+        );
+
+        let value = Value::List(vec![
+            Value::Record(vec![("block", "aa".into()), ("sizes", vec![1usize].into())]),
+            Value::Record(vec![("block", "b".into()), ("k", 3usize.into())]),
+        ]);
+        assert_eq!(
+            value.render(Output::Table),
+            "block | sizes | k\n------+-------+--\naa    | [1]   |\nb     |       | 3"
+        );
+        assert_eq!(
+            Value::Record(vec![("k", 3usize.into())]).render(Output::Table),
+            "key | value\n----+------\nk   | 3"
+        );
+        assert_eq!(Value::from(vec![1usize, 2]).render(Output::Table), "1\n2");
+    }
+}
@@ -0,0 +1,168 @@
+//! the progress bars of the long operations of SACLIN, e.g. encoding a big file or verifying a
+//! whole store
+//!
+//! a bar is redrawn on _stderr_ by a background thread, so that the elapsed time keeps increasing
+//! even when a single step takes minutes, e.g. the generation of a trusted setup.
+//!
+//! > **Note**
+//! >
+//! > bars are only shown when _stderr_ is a terminal and `--quiet` is not used, so that logs
+//! > redirected to a file or captured by a script are never polluted.
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const WIDTH: usize = 30;
+const REFRESH: Duration = Duration::from_millis(100);
+
+struct State {
+    label: String,
+    total: Option<u64>,
+    unit: &'static str,
+    done: AtomicU64,
+    finished: AtomicBool,
+    start: Instant,
+}
+
+impl State {
+    fn draw(&self, last: bool) {
+        let line = line(
+            &self.label,
+            self.done.load(Ordering::Relaxed),
+            self.total,
+            self.unit,
+            self.start.elapsed(),
+        );
+        let mut stderr = std::io::stderr().lock();
+        // NOTE: `\x1b[K` clears the end of a previous and longer line
+        let _ = write!(stderr, "\r{}\x1b[K{}", line, if last { "\n" } else { "" });
+        let _ = stderr.flush();
+    }
+}
+
+/// a progress bar, which is finished when dropped
+pub(crate) struct Progress {
+    state: Arc<State>,
+    ticker: Option<JoinHandle<()>>,
+}
+
+impl Progress {
+    /// start a progress bar towards `total` steps of `unit`, or a spinner if `total` is unknown
+    pub(crate) fn new(label: &str, total: Option<u64>, unit: &'static str, quiet: bool) -> Self {
+        let state = Arc::new(State {
+            label: label.to_string(),
+            total,
+            unit,
+            done: AtomicU64::new(0),
+            finished: AtomicBool::new(false),
+            start: Instant::now(),
+        });
+
+        let ticker = (!quiet && std::io::stderr().is_terminal()).then(|| {
+            let state = state.clone();
+            std::thread::spawn(move || {
+                while !state.finished.load(Ordering::Relaxed) {
+                    state.draw(false);
+                    std::thread::sleep(REFRESH);
+                }
+            })
+        });
+
+        Self { state, ticker }
+    }
+
+    /// a progress bar that is never shown, e.g. for the requests of `serve`
+    pub(crate) fn hidden() -> Self {
+        Self::new("", None, "", true)
+    }
+
+    /// advance the progress by `n` steps, from any thread
+    pub(crate) fn inc(&self, n: u64) {
+        self.state.done.fetch_add(n, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.state.finished.store(true, Ordering::Relaxed);
+        if let Some(ticker) = self.ticker.take() {
+            let _ = ticker.join();
+            self.state.draw(true);
+        }
+    }
+}
+
+/// a duration, rounded to the second, e.g. `1h02m03s`
+fn duration(d: Duration) -> String {
+    let s = d.as_secs();
+    match (s / 3600, s / 60 % 60, s % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
+}
+
+/// one line of a progress bar, with the estimated remaining time when the total is known
+fn line(label: &str, done: u64, total: Option<u64>, unit: &str, elapsed: Duration) -> String {
+    let unit = if unit.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", unit)
+    };
+
+    match total {
+        Some(total) => {
+            let ratio = if total == 0 {
+                1.0
+            } else {
+                (done as f64 / total as f64).min(1.0)
+            };
+            let filled = (ratio * WIDTH as f64) as usize;
+            let eta = if done == 0 || done >= total {
+                String::new()
+            } else {
+                let remaining = elapsed.as_secs_f64() * (total - done) as f64 / done as f64;
+                format!(", ETA {}", duration(Duration::from_secs_f64(remaining)))
+            };
+            format!(
+                "{} [{}{}] {}/{}{} ({:.0}%) in {}{}",
+                label,
+                "#".repeat(filled),
+                "-".repeat(WIDTH - filled),
+                done,
+                total,
+                unit,
+                ratio * 100.0,
+                duration(elapsed),
+                eta,
+            )
+        }
+        None if done == 0 => format!("{}... {}", label, duration(elapsed)),
+        None => format!("{}... {}{} in {}", label, done, unit, duration(elapsed)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::line;
+
+    #[test]
+    fn render() {
+        assert_eq!(
+            line("verifying", 5, Some(20), "blocks", Duration::from_secs(30)),
+            "verifying [#######-----------------------] 5/20 blocks (25%) in 30s, ETA 1m30s"
+        );
+        assert_eq!(
+            line("encoding", 10, Some(10), "bytes", Duration::from_secs(3725)),
+            "encoding [##############################] 10/10 bytes (100%) in 1h02m05s"
+        );
+        assert_eq!(
+            line("generating setup", 0, None, "", Duration::from_secs(2)),
+            "generating setup... 2s"
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! a small HTTP daemon exposing the store of SACLIN, see the `serve` command
+//!
+//! requests are handled one after the other, which is enough for a single node and keeps the
+//! store consistent without any locking.
+//!
+//! > **Note**
+//! >
+//! > the errors are JSON documents of the form `{"error": "<message>"}`, with a status code that
+//! > is $400$ for bad requests, $404$ for missing blocks and manifests, $422$ for invalid blocks
+//! > and $500$ otherwise.
+use std::io::Read;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use ark_std::rand::RngCore;
+use clap::ValueEnum;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{info, warn};
+
+use komodo::fs;
+
+use crate::{
+    output::{Output, Value},
+    progress::Progress,
+    EncodingMethod, Store, COMPRESS, LAYOUT, VALIDATE,
+};
+
+/// the answer to a request
+enum Reply {
+    Json(u16, Value),
+    Bytes(Vec<u8>),
+}
+
+/// an error, together with its HTTP status code
+struct HttpError(u16, String);
+
+impl From<anyhow::Error> for HttpError {
+    fn from(e: anyhow::Error) -> Self {
+        HttpError(500, e.to_string())
+    }
+}
+
+impl From<std::io::Error> for HttpError {
+    fn from(e: std::io::Error) -> Self {
+        HttpError(500, e.to_string())
+    }
+}
+
+fn bad_request(message: String) -> HttpError {
+    HttpError(400, message)
+}
+
+fn not_found(message: String) -> HttpError {
+    HttpError(404, message)
+}
+
+/// the `key=value` pairs of the query string of a URL, in order
+fn parse_query(query: &str) -> Vec<(&str, &str)> {
+    query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| p.split_once('=').unwrap_or((p, "")))
+        .collect()
+}
+
+/// the value of `key` in a query, parsed
+fn query_param<T: FromStr>(query: &[(&str, &str)], key: &str) -> Result<Option<T>, HttpError> {
+    query
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| {
+            v.parse()
+                .map_err(|_| bad_request(format!("invalid value {:?} for `{}`", v, key)))
+        })
+        .transpose()
+}
+
+/// make sure a name taken from a URL is a single path component, e.g. a hash
+fn check_name(name: &str) -> Result<&str, HttpError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(bad_request(format!("{:?} is not a valid hash", name)));
+    }
+    Ok(name)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+/// a manifest and the status of its blocks, see [`fs::Manifest::verify`]
+fn manifest(store: &Store, name: &str) -> Result<Value, HttpError> {
+    if !store.manifest_dir.join(name).is_file() {
+        return Err(not_found(format!("no manifest {}", name)));
+    }
+    let manifest = fs::Manifest::load(name, &store.manifest_dir, COMPRESS, VALIDATE)?;
+    let status = manifest.verify(&store.block_dir, LAYOUT)?;
+
+    Ok(Value::Record(vec![
+        ("hash", hex(&manifest.file_hash).into()),
+        ("size", manifest.size.into()),
+        ("k", manifest.k.into()),
+        ("n", manifest.n.into()),
+        ("curve", manifest.curve.into()),
+        ("setup_digest", hex(&manifest.setup_digest).into()),
+        ("blocks", manifest.block_hashes.into()),
+        (
+            "status",
+            Value::Record(vec![
+                ("present", status.present.into()),
+                ("missing", status.missing.into()),
+                ("corrupted", status.corrupted.into()),
+                ("needed", status.nb_needed.into()),
+            ]),
+        ),
+    ]))
+}
+
+/// the names of the manifests of the store
+fn manifests(store: &Store) -> Result<Value, HttpError> {
+    if !store.manifest_dir.exists() {
+        return Ok(Value::List(vec![]));
+    }
+
+    let mut names = vec![];
+    for entry in std::fs::read_dir(&store.manifest_dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if path.is_file() && !name.starts_with('.') {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    Ok(names.into())
+}
+
+/// verify a block sent in the body of a request and store it if it is valid
+fn submit(store: &Store, body: &mut dyn Read, rng: &mut impl RngCore) -> Result<Reply, HttpError> {
+    // NOTE: the name of the temporary file is the one of a partial file, so that it is cleaned up
+    // on startup if the daemon crashes before removing it
+    std::fs::create_dir_all(&store.block_dir)?;
+    let file: PathBuf = store
+        .block_dir
+        .join(format!(".submitted-{:016x}.partial", rng.next_u64()));
+    std::io::copy(body, &mut std::fs::File::create(&file)?)?;
+
+    let res = store.submit(&file);
+    if let Err(e) = std::fs::remove_file(&file) {
+        warn!("could not remove `{:?}`: {}", file, e);
+    }
+
+    match res {
+        Ok((hash, true)) => Ok(Reply::Json(
+            201,
+            Value::Record(vec![("block", hash.into()), ("status", true.into())]),
+        )),
+        Ok((hash, false)) => Err(HttpError(422, format!("block {} is invalid", hash))),
+        Err(e) => Err(HttpError(422, format!("could not store block: {}", e))),
+    }
+}
+
+/// encode and prove the data sent in the body of a request
+fn upload(
+    store: &Store,
+    body: &mut dyn Read,
+    query: &[(&str, &str)],
+    (k, n): (Option<usize>, Option<usize>),
+    rng: &mut impl RngCore,
+) -> Result<Reply, HttpError> {
+    let (k, n) = match (
+        query_param(query, "k")?.or(k),
+        query_param(query, "n")?.or(n),
+    ) {
+        (Some(k), Some(n)) => (k, n),
+        _ => return Err(bad_request("`k` and `n` are required".to_string())),
+    };
+    let encoding_method = match query.iter().find(|(k, _)| *k == "encoding_method") {
+        Some((_, m)) => EncodingMethod::from_str(m, true)
+            .map_err(|_| bad_request(format!("invalid encoding method {:?}", m)))?,
+        None => EncodingMethod::Random,
+    };
+    let chunk_size = query_param(query, "chunk_size")?;
+
+    let summary = store
+        .encode(
+            body,
+            (k, n),
+            encoding_method,
+            chunk_size,
+            rng,
+            &Progress::hidden(),
+        )
+        .map_err(|e| bad_request(format!("could not encode: {}", e)))?;
+    Ok(Reply::Json(201, summary))
+}
+
+/// route a request to its handler
+fn handle(
+    store: &Store,
+    request: &mut Request,
+    defaults: (Option<usize>, Option<usize>),
+    rng: &mut impl RngCore,
+) -> Result<Reply, HttpError> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let query = parse_query(query);
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (request.method(), segments.as_slice()) {
+        (Method::Post, ["data"]) => upload(store, request.as_reader(), &query, defaults, rng),
+        (Method::Get, ["blocks"]) => Ok(Reply::Json(200, store.list_blocks()?.into())),
+        (Method::Get, ["blocks", hash]) => {
+            let path = LAYOUT.find(&store.block_dir, check_name(hash)?);
+            if !path.is_file() {
+                return Err(not_found(format!("no block {}", hash)));
+            }
+            Ok(Reply::Bytes(std::fs::read(path)?))
+        }
+        (Method::Post, ["blocks"]) => submit(store, request.as_reader(), rng),
+        (Method::Get, ["manifests"]) => Ok(Reply::Json(200, manifests(store)?)),
+        (Method::Get, ["manifests", name]) => {
+            Ok(Reply::Json(200, manifest(store, check_name(name)?)?))
+        }
+        (method, _) => Err(not_found(format!("no route for {} {}", method, path))),
+    }
+}
+
+/// serve the store on `address` until the process is killed
+///
+/// `defaults` are the values of $k$ and $n$ when they are not given to `POST /data`.
+pub(crate) fn run(
+    store: &Store,
+    address: &str,
+    defaults: (Option<usize>, Option<usize>),
+) -> Result<()> {
+    let server =
+        Server::http(address).map_err(|e| anyhow!("could not listen on {}: {}", address, e))?;
+    info!("listening on http://{}", address);
+
+    let mut rng = rand::thread_rng();
+    let json = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    let bytes =
+        Header::from_bytes("Content-Type", "application/octet-stream").expect("valid header");
+
+    for mut request in server.incoming_requests() {
+        let reply =
+            handle(store, &mut request, defaults, &mut rng).unwrap_or_else(|HttpError(code, e)| {
+                warn!("{} {}: {}", request.method(), request.url(), e);
+                Reply::Json(code, Value::Record(vec![("error", e.into())]))
+            });
+
+        let res = match reply {
+            Reply::Json(code, value) => {
+                info!("{} {}: {}", request.method(), request.url(), code);
+                request.respond(
+                    Response::from_string(value.render(Output::Json))
+                        .with_status_code(code)
+                        .with_header(json.clone()),
+                )
+            }
+            Reply::Bytes(b) => {
+                info!("{} {}: 200", request.method(), request.url());
+                request.respond(Response::from_data(b).with_header(bytes.clone()))
+            }
+        };
+        if let Err(e) = res {
+            warn!("could not respond: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_name, parse_query};
+
+    #[test]
+    fn query() {
+        assert_eq!(
+            parse_query("k=3&n=5&&chunk_size"),
+            vec![("k", "3"), ("n", "5"), ("chunk_size", "")]
+        );
+        assert!(parse_query("").is_empty());
+
+        assert!(check_name("abc123").is_ok());
+        assert!(check_name("").is_err());
+        assert!(check_name("..").is_err());
+        assert!(check_name("a/b").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+//! the management of the trusted setups of SACLIN, whatever their protocol
+//!
+//! a setup is stored in the home directory of SACLIN, with a [`fs::Header`], and is identified by
+//! its digest, i.e. the [`fs::Manifest::setup_digest`] found in the manifests and the headers of
+//! all the blocks proven with it.
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use ark_bls12_381::{Fr, G1Projective};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use tracing::info;
+
+use komodo::{fs, zk::Powers};
+
+use crate::{output::Value, SetupCommand, COMPRESS, COMPRESSION, CURVE, ENCRYPTION, VALIDATE};
+
+/// a trusted setup that can be managed by SACLIN
+pub(crate) trait TrustedSetup: CanonicalSerialize + CanonicalDeserialize + Sized {
+    /// the number of powers of the secret, i.e. the maximum degree of the setup plus one
+    fn nb_powers(&self) -> usize;
+    /// specialize the setup to polynomials of degree at most `degree`
+    fn trim(self, degree: usize) -> Result<Self>;
+}
+
+impl TrustedSetup for Powers<Fr, G1Projective> {
+    fn nb_powers(&self) -> usize {
+        self.len()
+    }
+
+    fn trim(mut self, degree: usize) -> Result<Self> {
+        check_degree(degree, self.len())?;
+        self.truncate(degree + 1);
+        Ok(self)
+    }
+}
+
+/// make sure a setup with `nb_powers` powers can be trimmed to `degree`
+pub(crate) fn check_degree(degree: usize, nb_powers: usize) -> Result<()> {
+    if degree + 1 > nb_powers {
+        return Err(anyhow!(
+            "cannot trim a setup with {} powers to degree {}",
+            nb_powers,
+            degree
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+/// read the setup of `file`
+///
+/// > **Note**
+/// >
+/// > a missing setup is an error, SACLIN never generates temporary setups because the blocks
+/// > proven with them could not be verified afterwards.
+pub(crate) fn read<S: TrustedSetup>(file: &Path) -> Result<S> {
+    if !file.exists() {
+        return Err(anyhow!(
+            "no trusted setup at {:?}, please run `setup generate` or `setup import` first",
+            file
+        ));
+    }
+    info!("reading trusted setup from `{:?}`", file);
+    fs::read(file, COMPRESS, VALIDATE, ENCRYPTION)
+}
+
+/// dump a setup to `file`, with a header for `protocol`, and return its digest
+pub(crate) fn dump<S: TrustedSetup>(setup: &S, file: &Path, protocol: &str) -> Result<Vec<u8>> {
+    let (dir, filename) = match (file.parent(), file.file_name()) {
+        (Some(dir), Some(filename)) => (dir, filename.to_string_lossy()),
+        _ => return Err(anyhow!("{:?} is not a valid setup file", file)),
+    };
+    std::fs::create_dir_all(dir)?;
+    let digest = fs::Manifest::setup_digest(setup, COMPRESS)?;
+    let header = fs::Header::new(protocol, CURVE, &digest);
+    fs::dump(
+        setup,
+        dir,
+        Some(&filename),
+        &header,
+        COMPRESS,
+        COMPRESSION,
+        ENCRYPTION,
+    )?;
+
+    Ok(digest)
+}
+
+/// describe the setup of `file`
+pub(crate) fn inspect<S: TrustedSetup>(file: &Path, protocol: &str) -> Result<Value> {
+    let setup: S = read(file)?;
+    let nb_powers = setup.nb_powers();
+
+    Ok(Value::Record(vec![
+        ("protocol", protocol.into()),
+        ("file", file.display().to_string().into()),
+        ("size", (std::fs::metadata(file)?.len() as usize).into()),
+        ("powers", nb_powers.into()),
+        ("degree", nb_powers.saturating_sub(1).into()),
+        (
+            "digest",
+            hex(&fs::Manifest::setup_digest(&setup, COMPRESS)?).into(),
+        ),
+    ]))
+}
+
+/// the digest of the setup of `file`, as a hex string
+pub(crate) fn digest<S: TrustedSetup>(file: &Path) -> Result<String> {
+    Ok(hex(&fs::Manifest::setup_digest(
+        &read::<S>(file)?,
+        COMPRESS,
+    )?))
+}
+
+/// import the setup of `from`, e.g. the output of a ceremony, to `file`
+///
+/// the setup is validated and can either be a raw serialization or a file written by
+/// [`komodo::fs`].
+pub(crate) fn import<S: TrustedSetup>(from: &Path, file: &Path, protocol: &str) -> Result<String> {
+    info!("importing trusted setup from `{:?}`", from);
+    let setup: S = fs::read(from, COMPRESS, VALIDATE, ENCRYPTION)
+        .map_err(|e| anyhow!("could not import {:?}: {}", from, e))?;
+    if setup.nb_powers() == 0 {
+        return Err(anyhow!("{:?} is an empty setup", from));
+    }
+
+    Ok(hex(&dump(&setup, file, protocol)?))
+}
+
+/// export the setup of `file` to `to`, as a raw serialization without header, optionally trimmed
+/// to `degree`
+pub(crate) fn export<S: TrustedSetup>(
+    file: &Path,
+    to: &Path,
+    degree: Option<usize>,
+) -> Result<String> {
+    let mut setup: S = read(file)?;
+    if let Some(degree) = degree {
+        setup = setup.trim(degree)?;
+    }
+
+    let mut bytes = vec![];
+    setup.serialize_with_mode(&mut bytes, COMPRESS)?;
+    fs::write_atomically(to, &bytes)?;
+    info!("trusted setup exported to `{:?}`", to);
+
+    Ok(hex(&fs::Manifest::setup_digest(&setup, COMPRESS)?))
+}
+
+/// run any setup command on the setup of `file`, but the generation, which depends on the protocol
+///
+/// a generated or imported setup is described with [`inspect`].
+pub(crate) fn run<S: TrustedSetup>(
+    command: &SetupCommand,
+    file: &Path,
+    protocol: &str,
+) -> Result<Value> {
+    match command {
+        SetupCommand::Generate { .. } | SetupCommand::Inspect => inspect::<S>(file, protocol),
+        SetupCommand::Import { path } => {
+            import::<S>(path, file, protocol)?;
+            inspect::<S>(file, protocol)
+        }
+        SetupCommand::Export { path, degree } => Ok(Value::Record(vec![
+            ("path", path.display().to_string().into()),
+            ("digest", export::<S>(file, path, *degree)?.into()),
+        ])),
+        SetupCommand::Digest => Ok(digest::<S>(file)?.into()),
+    }
+}
@@ -17,6 +17,7 @@ use dragoonfri::{
 use komodo::error::KomodoError;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
+use report::{Format, Param, Report, Row, Unit};
 
 /// measure the time it takes to apply a function on a set of arguments and returns the result of
 /// the call
@@ -44,34 +45,35 @@ macro_rules! timeit {
     }};
 }
 
-/// same as [`timeit`] but prints a name and the time at the end directly
-///
-/// ```rust
-/// fn add(a: i32, b: i32) { a + b }
-/// let res = timeit_and_print!("addition", add, 1, 2);
-/// ```
-/// will be the same as
-/// ```rust
-/// fn add(a: i32, b: i32) { a + b }
-/// let res = {
-///     print!("addition: ");
-///     let start = Instant::now();
-///     let res = add(1, 2);
-///     let time = start.elapsed();
-///     println!("{}", time.as_nanos());
-///     res
-/// };
-/// ```
-macro_rules! timeit_and_print {
-    ($name: expr, $func:expr, $( $args:expr ),*) => {{
-        print!("{}: ", $name);
-        let (res, time) = timeit!($func, $($args),*);
-        println!("{}", time.as_nanos());
-        res
-    }};
+/// the context of the rows written by [`run`]
+struct Ctx<'a> {
+    report: Report<std::io::Stdout>,
+    curve: &'a str,
+    params: Vec<(&'a str, Param)>,
+}
+
+impl Ctx<'_> {
+    fn write(&mut self, metric: &str, value: u128, unit: Unit) {
+        self.report
+            .write(&Row {
+                protocol: "fri",
+                curve: self.curve,
+                params: &self.params,
+                metric,
+                value,
+                unit,
+            })
+            .unwrap();
+    }
+
+    fn time(&mut self, metric: &str, time: std::time::Duration) {
+        self.write(metric, time.as_nanos(), Unit::Nanoseconds);
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run<const N: usize, F: PrimeField, H: Hasher, P>(
+    ctx: &mut Ctx,
     bytes: &[u8],
     k: usize,
     n: usize,
@@ -84,13 +86,14 @@ where
     for<'a, 'b> &'a P: Div<&'b P, Output = P>,
     <H as rs_merkle::Hasher>::Hash: AsRef<[u8]> + CanonicalSerialize,
 {
-    let evaluations = timeit_and_print!("evaluating", komodo::fri::evaluate::<F>, &bytes, k, n);
+    let (evaluations, time) = timeit!(komodo::fri::evaluate::<F>, &bytes, k, n);
+    ctx.time("evaluating", time);
 
     let evals = evaluations.clone();
-    let shards = timeit_and_print!("encoding", komodo::fri::encode::<F>, &bytes, evals, k);
+    let (shards, time) = timeit!(komodo::fri::encode::<F>, &bytes, evals, k);
+    ctx.time("encoding", time);
 
-    let blocks = timeit_and_print!(
-        "proving",
+    let (blocks, time) = timeit!(
         komodo::fri::prove::<N, F, H, P>,
         evaluations,
         shards,
@@ -98,15 +101,15 @@ where
         rpo,
         q
     );
+    ctx.time("proving", time);
 
     let blocks = blocks.unwrap();
 
     let proofs: usize = blocks.iter().map(|b| b.proof.compressed_size()).sum();
     let commits: usize = blocks.iter().map(|b| b.commit.compressed_size()).sum();
-    println!("proofs: {}", proofs);
-    println!("commits: {}", commits);
+    ctx.write("proofs", proofs as u128, Unit::Bytes);
+    ctx.write("commits", commits as u128, Unit::Bytes);
 
-    print!("verifying: ");
     let time: std::time::Duration = blocks
         .iter()
         .cloned()
@@ -116,14 +119,10 @@ where
             time
         })
         .sum();
-    println!("{}", time.as_nanos());
+    ctx.time("verifying", time);
 
-    let decoded = timeit_and_print!(
-        "decoding",
-        komodo::fri::decode::<F, H>,
-        blocks[0..k].to_vec(),
-        n
-    );
+    let (decoded, time) = timeit!(komodo::fri::decode::<F, H>, blocks[0..k].to_vec(), n);
+    ctx.time("decoding", time);
 
     assert_eq!(hex::encode(bytes), hex::encode(decoded));
 
@@ -168,6 +167,10 @@ struct Args {
     hash: Hash,
     #[arg(long)]
     finite_field: FiniteField,
+
+    /// the format of the rows, see `benchmarks/report/src/lib.rs`
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
 }
 
 macro_rules! foo {
@@ -187,7 +190,7 @@ fn main() {
     let args = Args::parse();
 
     let bytes = generate_data(args.data_size, args.seed);
-    println!("loaded {} bytes of data", bytes.len());
+    eprintln!("loaded {} bytes of data", bytes.len());
 
     let ff = args.folding_factor;
     let f = match args.finite_field {
@@ -202,7 +205,23 @@ fn main() {
             Hash::SHA3_512 => foo!(ff, F_BLS12_381, Sha3_512),
         },
     };
+    let hash = args.hash.to_possible_value().unwrap();
+    let curve = args.finite_field.to_possible_value().unwrap();
+    let mut ctx = Ctx {
+        report: Report::stdout(args.format),
+        curve: curve.get_name(),
+        params: vec![
+            ("bytes", args.data_size.into()),
+            ("k", args.k.into()),
+            ("bf", args.blowup_factor.into()),
+            ("rpo", args.remainder_degree_plus_one.into()),
+            ("q", args.nb_queries.into()),
+            ("n", args.folding_factor.into()),
+            ("hash", hash.get_name().into()),
+        ],
+    };
     f(
+        &mut ctx,
         &bytes,
         args.k,
         args.k * args.blowup_factor,
@@ -2,11 +2,35 @@
 //!
 //! this module mainly contains an implementation of matrices over a finite
 //! field.
+//!
+//! > **Note**
+//! >
+//! > the most expensive operations, e.g. multiplication, inversion and
+//! > transposition, iterate over rows with the `cfg_*` macros from
+//! > [`ark_std`], which means they run in parallel when the `parallel` feature
+//! > is enabled and serially otherwise.
 use ark_ff::Field;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
 use ark_std::rand::{Rng, RngCore};
+use ark_std::{
+    cfg_chunks_mut, cfg_iter_mut, fmt, format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use crate::error::KomodoError;
+#[cfg(feature = "arbitrary")]
+use crate::fuzzing;
+
+/// the minimum size of all the dimensions of a product for [`Matrix::mul`] to
+/// use the cache-blocked multiplication
+pub const BLOCKED_MUL_THRESHOLD: usize = 64;
+/// the size of the square tiles used by the cache-blocked multiplication
+const MUL_BLOCK_SIZE: usize = 32;
 
 /// a matrix defined over a finite field
 ///
@@ -143,6 +167,7 @@ impl<T: Field> Matrix<T> {
     }
 
     /// build a completely random matrix of shape $n \times m$
+    #[cfg(any(feature = "prover", not(feature = "verify-only")))]
     pub fn random<R: RngCore>(n: usize, m: usize, rng: &mut R) -> Self {
         Self {
             elements: (0..(n * m)).map(|_| T::from(rng.gen::<u128>())).collect(),
@@ -151,6 +176,121 @@ impl<T: Field> Matrix<T> {
         }
     }
 
+    /// draw a random non-zero element
+    #[cfg(any(feature = "prover", not(feature = "verify-only")))]
+    fn random_non_zero<R: RngCore>(rng: &mut R) -> T {
+        loop {
+            let x = T::from(rng.gen::<u128>());
+            if !x.is_zero() {
+                return x;
+            }
+        }
+    }
+
+    /// build a sparse random matrix of shape $n \times m$
+    ///
+    /// each element is non-zero with probability `density` and, when non-zero, drawn uniformly at
+    /// random. To keep the matrix usable as an encoding matrix, i.e. to make sure no encoded shard
+    /// is full of zeros, any column that would end up being all zeros gets a single random non-zero
+    /// element.
+    ///
+    /// `density` should be in $(0, 1]$, otherwise [`KomodoError::InvalidMatrixElements`] is
+    /// returned. A density of $1$ is equivalent to [`Self::random`], up to the zero elements.
+    ///
+    /// > **Note**
+    /// >
+    /// > for reproducible matrices, give a seeded random number generator, e.g.
+    /// > `rand::rngs::StdRng::seed_from_u64(seed)`.
+    #[cfg(any(feature = "prover", not(feature = "verify-only")))]
+    pub fn random_sparse<R: RngCore>(
+        n: usize,
+        m: usize,
+        density: f64,
+        rng: &mut R,
+    ) -> Result<Self, KomodoError> {
+        if !(density > 0.0 && density <= 1.0) {
+            return Err(KomodoError::InvalidMatrixElements(format!(
+                "density should be in (0, 1], found {}",
+                density
+            )));
+        }
+
+        let mut matrix = Self {
+            elements: vec![T::zero(); n * m],
+            height: n,
+            width: m,
+        };
+
+        for j in 0..m {
+            for i in 0..n {
+                if rng.gen_bool(density) {
+                    matrix.set(i, j, Self::random_non_zero(rng));
+                }
+            }
+
+            if n > 0 && (0..n).all(|i| matrix.get(i, j).is_zero()) {
+                let i = rng.gen_range(0..n);
+                matrix.set(i, j, Self::random_non_zero(rng));
+            }
+        }
+
+        Ok(matrix)
+    }
+
+    /// build a random band matrix of shape $n \times m$ with a given bandwidth
+    ///
+    /// column $j$ has exactly `bandwidth` random non-zero elements, on the rows
+    /// $j, j + 1, ..., j + \text{bandwidth} - 1$, wrapping around modulo $n$. The band is cyclic so
+    /// that every column, and thus every encoded shard, depends on `bandwidth` source shards, even
+    /// when $m > n$.
+    ///
+    /// `bandwidth` should be in $[1, n]$, otherwise [`KomodoError::InvalidMatrixElements`] is
+    /// returned. A bandwidth of $n$ gives a dense random matrix.
+    ///
+    /// # Example
+    /// with $n = 4$, $m = 6$ and a bandwidth of $2$, the non-zero elements, denoted by `*`, are
+    /// ```text
+    /// [
+    ///     [*, 0, 0, *, *, 0],
+    ///     [*, *, 0, 0, *, *],
+    ///     [0, *, *, 0, 0, *],
+    ///     [0, 0, *, *, 0, 0],
+    /// ]
+    /// ```
+    ///
+    /// > **Note**
+    /// >
+    /// > for reproducible matrices, give a seeded random number generator, e.g.
+    /// > `rand::rngs::StdRng::seed_from_u64(seed)`.
+    #[cfg(any(feature = "prover", not(feature = "verify-only")))]
+    pub fn random_band<R: RngCore>(
+        n: usize,
+        m: usize,
+        bandwidth: usize,
+        rng: &mut R,
+    ) -> Result<Self, KomodoError> {
+        if bandwidth == 0 || bandwidth > n {
+            return Err(KomodoError::InvalidMatrixElements(format!(
+                "bandwidth should be in [1, {}], found {}",
+                n, bandwidth
+            )));
+        }
+
+        let mut matrix = Self {
+            elements: vec![T::zero(); n * m],
+            height: n,
+            width: m,
+        };
+
+        for j in 0..m {
+            for b in 0..bandwidth {
+                matrix.set((j + b) % n, j, Self::random_non_zero(rng));
+            }
+        }
+
+        Ok(matrix)
+    }
+
     /// build a matrix from a "_matrix_" of elements
     ///
     /// > **Note**  
@@ -238,7 +378,7 @@ impl<T: Field> Matrix<T> {
         }
     }
 
-    fn get(&self, i: usize, j: usize) -> T {
+    pub(crate) fn get(&self, i: usize, j: usize) -> T {
         self.elements[i * self.width + j]
     }
 
@@ -260,20 +400,9 @@ impl<T: Field> Matrix<T> {
 
     // compute _row / value_
     fn divide_row_by(&mut self, row: usize, value: T) {
-        for j in 0..self.width {
-            self.set(row, j, self.get(row, j) / value);
-        }
-    }
-
-    // compute _destination = destination + source * value_
-    fn multiply_row_by_and_add_to_row(&mut self, source: usize, value: T, destination: usize) {
-        for j in 0..self.width {
-            self.set(
-                destination,
-                j,
-                self.get(destination, j) + self.get(source, j) * value,
-            );
-        }
+        let inverse = value.inverse().unwrap();
+        cfg_iter_mut!(self.elements[(row * self.width)..((row + 1) * self.width)])
+            .for_each(|x| *x *= inverse);
     }
 
     /// compute the inverse of the matrix
@@ -291,21 +420,41 @@ impl<T: Field> Matrix<T> {
         let mut matrix = self.clone();
 
         for i in 0..matrix.height {
-            let pivot = matrix.get(i, i);
-            if pivot.is_zero() {
-                return Err(KomodoError::NonInvertibleMatrix(i));
+            // look for a non-zero pivot in the i-th column, which is required for sparse
+            // matrices whose diagonal might contain zeros
+            match (i..matrix.height).find(|&k| !matrix.get(k, i).is_zero()) {
+                Some(k) if k != i => {
+                    matrix.swap_rows(i, k);
+                    inverse.swap_rows(i, k);
+                }
+                Some(_) => {}
+                None => return Err(KomodoError::NonInvertibleMatrix(i)),
             }
+            let pivot = matrix.get(i, i);
 
             inverse.divide_row_by(i, pivot);
             matrix.divide_row_by(i, pivot);
 
-            for k in 0..matrix.height {
-                if k != i {
-                    let factor = matrix.get(k, i);
-                    inverse.multiply_row_by_and_add_to_row(i, -factor, k);
-                    matrix.multiply_row_by_and_add_to_row(i, -factor, k);
-                }
-            }
+            let width = matrix.width;
+            let pivot_row = matrix.elements[(i * width)..((i + 1) * width)].to_vec();
+            let inverse_pivot_row = inverse.elements[(i * width)..((i + 1) * width)].to_vec();
+
+            cfg_chunks_mut!(matrix.elements, width)
+                .zip(cfg_chunks_mut!(inverse.elements, width))
+                .enumerate()
+                .filter(|(k, _)| *k != i)
+                .for_each(|(_, (row, inverse_row))| {
+                    let factor = row[i];
+                    if factor.is_zero() {
+                        return;
+                    }
+                    for (x, p) in row.iter_mut().zip(pivot_row.iter()) {
+                        *x -= factor * p;
+                    }
+                    for (x, p) in inverse_row.iter_mut().zip(inverse_pivot_row.iter()) {
+                        *x -= factor * p;
+                    }
+                });
         }
 
         Ok(inverse)
@@ -371,6 +520,42 @@ impl<T: Field> Matrix<T> {
         nb_non_zero_rows
     }
 
+    /// check if a row is a linear combination of the rows of the matrix
+    ///
+    /// this is useful to know if a new vector, e.g. the linear combination of
+    /// an incoming recoded shard, would bring any new information to a set of
+    /// vectors, here the rows of the matrix.
+    ///
+    /// > **Note**
+    /// > a row whose length is not the width of the matrix is never in its row
+    /// > space.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use komodo::algebra::linalg::Matrix;
+    /// # type T = ark_bls12_381::Fr;
+    /// let matrix = Matrix::<T>::from_vec_vec(vec![
+    ///     vec![T::from(1), T::from(0), T::from(0)],
+    ///     vec![T::from(0), T::from(1), T::from(0)],
+    /// ])
+    /// .unwrap();
+    ///
+    /// assert!(matrix.is_in_row_space(&[T::from(2), T::from(3), T::from(0)]));
+    /// assert!(!matrix.is_in_row_space(&[T::from(2), T::from(3), T::from(1)]));
+    /// ```
+    pub fn is_in_row_space(&self, row: &[T]) -> bool {
+        if row.len() != self.width {
+            return false;
+        }
+
+        let mut basis = RowEchelon::new();
+        for i in 0..self.height {
+            basis.insert(&self.elements[(i * self.width)..((i + 1) * self.width)]);
+        }
+
+        basis.reduce(row).iter().all(|x| x.is_zero())
+    }
+
     /// compute the matrix multiplication with another matrix
     ///
     /// if `mat` represents a matrix $A$ and `rhs` is the representation of
@@ -379,6 +564,10 @@ impl<T: Field> Matrix<T> {
     /// > **Note**
     /// > both matrices should have compatible shapes, i.e. if `self` has shape
     /// > `(n, m)` and `rhs` has shape `(p, q)`, then `m == p`.
+    ///
+    /// when all the dimensions of the product are at least
+    /// [`BLOCKED_MUL_THRESHOLD`], a cache-blocked multiplication is used
+    /// instead of the naive triple loop. Both give the exact same result.
     pub fn mul(&self, rhs: &Self) -> Result<Self, KomodoError> {
         if self.width != rhs.height {
             return Err(KomodoError::IncompatibleMatrixShapes(
@@ -389,24 +578,105 @@ impl<T: Field> Matrix<T> {
             ));
         }
 
+        if self.height.min(self.width).min(rhs.width) >= BLOCKED_MUL_THRESHOLD {
+            Ok(self.mul_blocked(rhs))
+        } else {
+            Ok(self.mul_naive(rhs))
+        }
+    }
+
+    /// the naive $O(n^3)$ multiplication, well suited for small matrices
+    ///
+    /// > **Note**
+    /// > the shapes are assumed to be compatible, see [`Self::mul`]
+    fn mul_naive(&self, rhs: &Self) -> Self {
         let height = self.height;
         let width = rhs.width;
         let common = self.width;
 
         let mut elements = Vec::new();
         elements.resize(height * width, T::zero());
+        if width == 0 {
+            return Self {
+                elements,
+                height,
+                width,
+            };
+        }
 
-        for i in 0..height {
-            for j in 0..width {
-                elements[i * width + j] = (0..common).map(|k| self.get(i, k) * rhs.get(k, j)).sum();
-            }
+        cfg_chunks_mut!(elements, width)
+            .enumerate()
+            .for_each(|(i, row)| {
+                for (j, x) in row.iter_mut().enumerate() {
+                    *x = (0..common).map(|k| self.get(i, k) * rhs.get(k, j)).sum();
+                }
+            });
+
+        Self {
+            elements,
+            height,
+            width,
         }
+    }
 
-        Ok(Self {
+    /// a cache-blocked multiplication, well suited for large matrices
+    ///
+    /// the three matrices are cut into square tiles of size [`MUL_BLOCK_SIZE`]
+    /// and the product is accumulated tile by tile, walking the rows of both
+    /// `self` and `rhs` contiguously, so that the tiles being worked on stay
+    /// in cache.
+    ///
+    /// > **Note**
+    /// > the shapes are assumed to be compatible, see [`Self::mul`]
+    fn mul_blocked(&self, rhs: &Self) -> Self {
+        let height = self.height;
+        let width = rhs.width;
+        let common = self.width;
+
+        let mut elements = Vec::new();
+        elements.resize(height * width, T::zero());
+        if width == 0 {
+            return Self {
+                elements,
+                height,
+                width,
+            };
+        }
+
+        // each horizontal band of tiles of the output is computed independently
+        cfg_chunks_mut!(elements, MUL_BLOCK_SIZE * width)
+            .enumerate()
+            .for_each(|(b, band)| {
+                let ii = b * MUL_BLOCK_SIZE;
+                let nb_rows = band.len() / width;
+                for kk in (0..common).step_by(MUL_BLOCK_SIZE) {
+                    let k_end = (kk + MUL_BLOCK_SIZE).min(common);
+                    for jj in (0..width).step_by(MUL_BLOCK_SIZE) {
+                        let j_end = (jj + MUL_BLOCK_SIZE).min(width);
+
+                        for di in 0..nb_rows {
+                            let i = ii + di;
+                            let row = &mut band[(di * width + jj)..(di * width + j_end)];
+                            for k in kk..k_end {
+                                let a = self.get(i, k);
+                                if a.is_zero() {
+                                    continue;
+                                }
+                                let rhs_row = &rhs.elements[(k * width + jj)..(k * width + j_end)];
+                                for (x, b) in row.iter_mut().zip(rhs_row) {
+                                    *x += a * b;
+                                }
+                            }
+                        }
+                    }
+                }
+            });
+
+        Self {
             elements,
             height,
             width,
-        })
+        }
     }
 
     /// compute the transpose of the matrix
@@ -419,13 +689,22 @@ impl<T: Field> Matrix<T> {
 
         let mut elements = Vec::new();
         elements.resize(height * width, T::zero());
-
-        for i in 0..height {
-            for j in 0..width {
-                elements[i * width + j] = self.get(j, i);
-            }
+        if width == 0 {
+            return Self {
+                elements,
+                height,
+                width,
+            };
         }
 
+        cfg_chunks_mut!(elements, width)
+            .enumerate()
+            .for_each(|(i, row)| {
+                for (j, x) in row.iter_mut().enumerate() {
+                    *x = self.get(j, i);
+                }
+            });
+
         Self {
             elements,
             height,
@@ -438,7 +717,7 @@ impl<T: Field> Matrix<T> {
     /// # Example
     /// if a matrix has shape `(10, 11)` and is truncated to `(5, 7)`, the 5
     /// bottom rows and 4 right columns will be removed.
-    pub(crate) fn truncate(&self, rows: Option<usize>, cols: Option<usize>) -> Self {
+    pub fn truncate(&self, rows: Option<usize>, cols: Option<usize>) -> Self {
         let width = if let Some(w) = cols {
             self.width - w
         } else {
@@ -466,9 +745,131 @@ impl<T: Field> Matrix<T> {
             width,
         }
     }
+
+    /// turn a $k \times n$ encoding matrix into a _systematic_ one, i.e. whose first $k$ columns are
+    /// the identity, spanning the same codes
+    ///
+    /// the matrix is multiplied by the inverse of its first $k$ columns, e.g. the systematic form
+    /// of a Vandermonde matrix is a systematic Reed-Solomon code.
+    ///
+    /// > **Note**
+    /// >
+    /// > the first $k$ columns should be linearly independent, otherwise
+    /// > [`KomodoError::NonInvertibleMatrix`] is returned, and the matrix should be at least as
+    /// > wide as it is high, otherwise [`KomodoError::IncompatibleMatrixShapes`] is returned.
+    pub fn systematic(&self) -> Result<Self, KomodoError> {
+        if self.width < self.height {
+            return Err(KomodoError::IncompatibleMatrixShapes(
+                self.height,
+                self.height,
+                self.height,
+                self.width,
+            ));
+        }
+
+        self.truncate(None, Some(self.width - self.height))
+            .invert()?
+            .mul(self)
+    }
+}
+
+/// a set of linearly independent rows in _row echelon form_, built incrementally
+///
+/// each row of the basis is stored with the index of its pivot, i.e. its first
+/// non-zero element, which is normalized to $1$. Every row is zero on the
+/// pivots of the rows that were inserted before it, which allows to reduce any
+/// vector by all the rows of the basis in a single pass.
+pub(crate) struct RowEchelon<T: Field> {
+    rows: Vec<(usize, Vec<T>)>,
+}
+
+impl<T: Field> RowEchelon<T> {
+    pub(crate) fn new() -> Self {
+        Self { rows: vec![] }
+    }
+
+    /// remove from `row` its components along the rows of the basis
+    ///
+    /// rows of different lengths are padded with zeros.
+    fn reduce(&self, row: &[T]) -> Vec<T> {
+        let mut row = row.to_vec();
+        for (pivot, b) in &self.rows {
+            if row.len() < b.len() {
+                row.resize(b.len(), T::zero());
+            }
+            let factor = row[*pivot];
+            if !factor.is_zero() {
+                for (x, y) in row.iter_mut().zip(b.iter()) {
+                    *x -= factor * y;
+                }
+            }
+        }
+
+        row
+    }
+
+    /// try to add a row to the basis, returns `true` iif it was linearly
+    /// independent from the rows already in the basis
+    pub(crate) fn insert(&mut self, row: &[T]) -> bool {
+        let mut row = self.reduce(row);
+        match row.iter().position(|x| !x.is_zero()) {
+            Some(pivot) => {
+                let inverse = row[pivot].inverse().unwrap();
+                for x in row.iter_mut() {
+                    *x *= inverse;
+                }
+                self.rows.push((pivot, row));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// find a maximal subset of linearly independent rows
+///
+/// the rows are considered in order and a row is selected when it is linearly
+/// independent from all the rows that have been selected before. The search
+/// stops as soon as `k` rows have been selected, e.g. when the rows are the
+/// linear combinations of shards and $k$ is the code parameter, these are
+/// exactly the shards that are needed to decode.
+///
+/// the output is the list of the indices of the selected rows, in increasing
+/// order, and has length $\min(k, r)$ where $r$ is the rank of the rows.
+///
+/// > **Note**
+/// > all the rows are expected to have the same length.
+///
+/// # Example
+/// ```rust
+/// # use komodo::algebra::linalg::independent_subset;
+/// # type T = ark_bls12_381::Fr;
+/// let rows = vec![
+///     vec![T::from(1), T::from(0)],
+///     vec![T::from(2), T::from(0)],
+///     vec![T::from(0), T::from(1)],
+/// ];
+///
+/// assert_eq!(independent_subset(&rows, 2), vec![0, 2]);
+/// assert_eq!(independent_subset(&rows, 1), vec![0]);
+/// ```
+pub fn independent_subset<T: Field>(rows: &[Vec<T>], k: usize) -> Vec<usize> {
+    let mut basis = RowEchelon::new();
+    let mut indices = vec![];
+
+    for (i, row) in rows.iter().enumerate() {
+        if indices.len() >= k {
+            break;
+        }
+        if basis.insert(row) {
+            indices.push(i);
+        }
+    }
+
+    indices
 }
 
-impl<T: Field> std::fmt::Display for Matrix<T> {
+impl<T: Field> fmt::Display for Matrix<T> {
     /// an example matrix with the identity of order 3
     /// ```text
     /// /1 0 0\
@@ -511,7 +912,7 @@ impl<T: Field> std::fmt::Display for Matrix<T> {
     /// |0     0     0     *    |
     /// \0     0     0     *    /
     /// ```
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for i in 0..self.height {
             let start = if i == 0 {
                 "/"
@@ -571,10 +972,24 @@ impl<T: Field> std::fmt::Display for Matrix<T> {
     }
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a, T: ark_ff::PrimeField> arbitrary::Arbitrary<'a> for Matrix<T> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let height = fuzzing::dimension(u)?;
+        let width = fuzzing::dimension(u)?;
+
+        Ok(Self {
+            elements: fuzzing::field_elements(u, height * width)?,
+            height,
+            width,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ark_bls12_381::Fr;
-    use ark_ff::Field;
+    use ark_ff::{Field, Zero};
 
     use super::{KomodoError, Matrix};
 
@@ -666,6 +1081,39 @@ mod tests {
         assert_eq!(product, expected);
     }
 
+    #[test]
+    fn blocked_multiplication() {
+        let mut rng = ark_std::test_rng();
+
+        for (n, m, p) in [
+            (1, 1, 1),
+            (3, 5, 7),
+            (32, 32, 32),
+            (37, 70, 45),
+            (65, 64, 66),
+        ] {
+            let a = Matrix::<Fr>::random(n, m, &mut rng);
+            let b = Matrix::<Fr>::random(m, p, &mut rng);
+            assert_eq!(
+                a.mul_blocked(&b),
+                a.mul_naive(&b),
+                "TEST | shapes: ({} x {}) and ({} x {})",
+                n,
+                m,
+                m,
+                p
+            );
+        }
+
+        let a = Matrix::<Fr>::random_sparse(70, 80, 0.2, &mut rng).unwrap();
+        let b = Matrix::<Fr>::random(80, 90, &mut rng);
+        assert_eq!(a.mul(&b).unwrap(), a.mul_naive(&b));
+
+        let a = Matrix::<Fr>::random(70, 70, &mut rng);
+        let inverse = a.invert().unwrap();
+        assert_eq!(a.mul(&inverse).unwrap(), Matrix::<Fr>::identity(70));
+    }
+
     #[test]
     fn random() {
         let mut rng = ark_std::test_rng();
@@ -680,6 +1128,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn random_sparse() {
+        let mut rng = ark_std::test_rng();
+
+        assert!(Matrix::<Fr>::random_sparse(3, 5, 0.0, &mut rng).is_err());
+        assert!(Matrix::<Fr>::random_sparse(3, 5, 1.5, &mut rng).is_err());
+        assert!(Matrix::<Fr>::random_sparse(3, 5, f64::NAN, &mut rng).is_err());
+
+        let mat = Matrix::<Fr>::random_sparse(3, 5, 1.0, &mut rng).unwrap();
+        assert!(mat.elements.iter().all(|x| !x.is_zero()));
+
+        for density in [0.01, 0.1, 0.5] {
+            let mat = Matrix::<Fr>::random_sparse(10, 20, density, &mut rng).unwrap();
+            assert_eq!((mat.height, mat.width), (10, 20));
+            for j in 0..mat.width {
+                assert!(
+                    mat.get_col(j).unwrap().iter().any(|x| !x.is_zero()),
+                    "column {} should not be all zeros with density {}",
+                    j,
+                    density
+                );
+            }
+        }
+
+        let mat = Matrix::<Fr>::random_sparse(50, 50, 0.1, &mut rng).unwrap();
+        let nb_non_zeros = mat.elements.iter().filter(|x| !x.is_zero()).count();
+        assert!(
+            nb_non_zeros < 2 * 50 * 50 / 10,
+            "too many non-zero elements: {}",
+            nb_non_zeros
+        );
+
+        let a = Matrix::<Fr>::random_sparse(7, 9, 0.3, &mut ark_std::test_rng()).unwrap();
+        let b = Matrix::<Fr>::random_sparse(7, 9, 0.3, &mut ark_std::test_rng()).unwrap();
+        assert_eq!(a, b, "same seed should give the same matrix");
+    }
+
+    #[test]
+    fn random_band() {
+        let mut rng = ark_std::test_rng();
+
+        assert!(Matrix::<Fr>::random_band(3, 5, 0, &mut rng).is_err());
+        assert!(Matrix::<Fr>::random_band(3, 5, 4, &mut rng).is_err());
+
+        let mat = Matrix::<Fr>::random_band(4, 6, 2, &mut rng).unwrap();
+        #[rustfmt::skip]
+        let pattern = [
+            vec![1, 0, 0, 1, 1, 0],
+            vec![1, 1, 0, 0, 1, 1],
+            vec![0, 1, 1, 0, 0, 1],
+            vec![0, 0, 1, 1, 0, 0],
+        ];
+        for (i, row) in pattern.iter().enumerate() {
+            for (j, &p) in row.iter().enumerate() {
+                assert_eq!(!mat.get(i, j).is_zero(), p == 1, "at ({}, {})", i, j);
+            }
+        }
+
+        let mat = Matrix::<Fr>::random_band(5, 5, 5, &mut rng).unwrap();
+        assert!(mat.elements.iter().all(|x| !x.is_zero()));
+
+        for n in 1..10 {
+            let mat = Matrix::<Fr>::random_band(n, n, 2.min(n), &mut rng).unwrap();
+            assert_eq!(mat.rank(), n);
+        }
+    }
+
     #[test]
     fn inverse() {
         let mut rng = ark_std::test_rng();
@@ -710,6 +1225,16 @@ mod tests {
             KomodoError::NonSquareMatrix(..)
         ));
 
+        let matrix = Matrix::<Fr>::from_vec_vec(mat_to_elements(vec![
+            vec![0, 1, 0],
+            vec![0, 0, 1],
+            vec![1, 0, 0],
+        ]))
+        .unwrap();
+        let inverse = matrix.invert().unwrap();
+        assert_eq!(matrix.mul(&inverse).unwrap(), Matrix::<Fr>::identity(3));
+        assert_eq!(inverse.mul(&matrix).unwrap(), Matrix::<Fr>::identity(3));
+
         let inverse = Matrix::<Fr>::from_diagonal(vec_to_elements(vec![0, 3, 4])).invert();
         assert!(inverse.is_err());
         assert!(matches!(
@@ -787,6 +1312,25 @@ mod tests {
         assert_eq!(matrix.truncate(Some(1), Some(2)), truncated);
     }
 
+    #[test]
+    fn systematic() {
+        let matrix = Matrix::<Fr>::vandermonde_unchecked(&vec_to_elements(vec![1, 2, 3, 4, 5]), 3);
+        let systematic = matrix.systematic().unwrap();
+        assert_eq!(systematic.truncate(None, Some(2)), Matrix::identity(3));
+        assert_eq!(systematic.rank(), 3);
+
+        assert!(matches!(
+            matrix.transpose().systematic(),
+            Err(KomodoError::IncompatibleMatrixShapes(..))
+        ));
+        assert!(matches!(
+            Matrix::<Fr>::from_vec_vec(mat_to_elements(vec![vec![1, 1, 2], vec![1, 1, 3]]))
+                .unwrap()
+                .systematic(),
+            Err(KomodoError::NonInvertibleMatrix(..))
+        ));
+    }
+
     #[test]
     fn get_cols() {
         let matrix = Matrix::<Fr>::from_vec_vec(mat_to_elements(vec![
@@ -802,6 +1346,65 @@ mod tests {
         assert_eq!(matrix.get_col(3), Some(vec_to_elements(vec![10, 11, 12])));
     }
 
+    #[test]
+    fn row_space() {
+        let matrix = Matrix::<Fr>::from_vec_vec(mat_to_elements(vec![
+            vec![1, 2, 3, 4],
+            vec![0, 1, 0, 1],
+            vec![1, 3, 3, 5],
+        ]))
+        .unwrap();
+
+        assert!(matrix.is_in_row_space(&vec_to_elements(vec![0, 0, 0, 0])));
+        assert!(matrix.is_in_row_space(&vec_to_elements(vec![1, 2, 3, 4])));
+        assert!(matrix.is_in_row_space(&vec_to_elements(vec![2, 5, 6, 9])));
+        assert!(!matrix.is_in_row_space(&vec_to_elements(vec![0, 0, 1, 0])));
+        assert!(!matrix.is_in_row_space(&vec_to_elements(vec![1, 2, 3])));
+
+        let mut rng = ark_std::test_rng();
+        let matrix = Matrix::<Fr>::random(5, 5, &mut rng);
+        assert!(matrix.is_in_row_space(&Matrix::<Fr>::random(1, 5, &mut rng).elements));
+
+        let matrix = Matrix::<Fr>::random(4, 5, &mut rng);
+        let combination = Matrix::<Fr>::random(1, 4, &mut rng).mul(&matrix).unwrap();
+        assert!(matrix.is_in_row_space(&combination.elements));
+    }
+
+    #[test]
+    fn independent_subset() {
+        let rows = mat_to_elements::<Fr>(vec![
+            vec![0, 0, 0],
+            vec![1, 2, 3],
+            vec![2, 4, 6],
+            vec![0, 1, 0],
+            vec![1, 3, 3],
+            vec![0, 0, 1],
+            vec![5, 5, 5],
+        ]);
+
+        assert_eq!(super::independent_subset(&rows, 3), vec![1, 3, 5]);
+        assert_eq!(super::independent_subset(&rows, 2), vec![1, 3]);
+        assert_eq!(super::independent_subset(&rows, 10), vec![1, 3, 5]);
+        assert_eq!(super::independent_subset(&rows, 0), Vec::<usize>::new());
+        assert_eq!(super::independent_subset::<Fr>(&[], 3), Vec::<usize>::new());
+
+        // NOTE: rows of different lengths are padded with zeros
+        let ragged = mat_to_elements::<Fr>(vec![vec![1, 1], vec![1], vec![1, 1, 0], vec![0, 0, 1]]);
+        assert_eq!(super::independent_subset(&ragged, 4), vec![0, 1, 3]);
+
+        let mut rng = ark_std::test_rng();
+        for _ in 0..10 {
+            let m = Matrix::<Fr>::random(7, 4, &mut rng);
+            let rows: Vec<Vec<Fr>> = m.elements.chunks(4).map(|r| r.to_vec()).collect();
+            let indices = super::independent_subset(&rows, 4);
+            assert_eq!(indices.len(), m.rank());
+
+            let selected =
+                Matrix::from_vec_vec(indices.iter().map(|&i| rows[i].clone()).collect()).unwrap();
+            assert_eq!(selected.rank(), indices.len());
+        }
+    }
+
     #[test]
     fn rank() {
         let mut rng = ark_std::test_rng();
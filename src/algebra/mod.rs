@@ -7,9 +7,14 @@ use ark_ff::{BigInteger, PrimeField};
 #[cfg(any(feature = "kzg", feature = "aplonk"))]
 use ark_poly::DenseUVPolynomial;
 #[cfg(any(feature = "kzg", feature = "aplonk"))]
-use ark_std::One;
+use ark_std::ops::{Div, Mul};
 #[cfg(any(feature = "kzg", feature = "aplonk"))]
-use std::ops::{Div, Mul};
+use ark_std::One;
+use ark_std::{cfg_chunks, cfg_iter, format, vec::Vec};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::error::KomodoError;
 
 pub mod linalg;
 
@@ -69,10 +74,9 @@ pub mod linalg;
 pub fn split_data_into_field_elements<F: PrimeField>(bytes: &[u8], modulus: usize) -> Vec<F> {
     let bytes_per_element = (F::MODULUS_BIT_SIZE as usize - 1) / 8;
 
-    let mut elements = Vec::new();
-    for chunk in bytes.chunks(bytes_per_element) {
-        elements.push(F::from_le_bytes_mod_order(chunk));
-    }
+    let mut elements = cfg_chunks!(bytes, bytes_per_element)
+        .map(F::from_le_bytes_mod_order)
+        .collect::<Vec<_>>();
 
     if elements.len() % modulus != 0 {
         elements.resize((elements.len() / modulus + 1) * modulus, F::one());
@@ -84,15 +88,107 @@ pub fn split_data_into_field_elements<F: PrimeField>(bytes: &[u8], modulus: usiz
 /// merges elliptic curve elements back into a sequence of bytes
 ///
 /// this is the inverse operation of [`split_data_into_field_elements`].
-pub(crate) fn merge_elements_into_bytes<F: PrimeField>(elements: &[F]) -> Vec<u8> {
-    let mut bytes = vec![];
-    for e in elements {
-        let mut b = e.into_bigint().to_bytes_le();
-        b.pop();
-        bytes.append(&mut b);
+///
+/// > **Note**
+/// >
+/// > the output might contain trailing padding bytes, i.e. the caller should truncate it to the
+/// > size of the original data if it is known.
+pub fn merge_elements_into_bytes<F: PrimeField>(elements: &[F]) -> Vec<u8> {
+    let bytes_per_element = (F::MODULUS_BIT_SIZE as usize - 1) / 8;
+
+    cfg_iter!(elements)
+        .map(|e| {
+            let mut b = e.into_bigint().to_bytes_le();
+            b.truncate(bytes_per_element);
+            b
+        })
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+/// convert elements from a finite field $\mathbb{F}_1$ to another finite field $\mathbb{F}_2$
+///
+/// each element is interpreted as its canonical integer representative in $[0, p_1)$ and mapped to
+/// the element of $\mathbb{F}_2$ with the same representative, which means that the conversion is
+/// lossless and the inverse of [`convert`] from $\mathbb{F}_2$ to $\mathbb{F}_1$.
+///
+/// > **Note**
+/// >
+/// > if the modulus of $\mathbb{F}_2$ is smaller than the one of $\mathbb{F}_1$, some elements
+/// > might not fit in $\mathbb{F}_2$. Instead of silently reducing them modulo $p_2$, this
+/// > function returns [`KomodoError::ElementTooLargeForField`] with the index of the first
+/// > offending element.
+/// >
+/// > use [`reencode`] to move _data_ from a field to another regardless of their sizes.
+///
+/// # Example
+/// ```
+/// # #[derive(ark_ff::MontConfig)]
+/// # #[modulus = "65537"]
+/// # #[generator = "3"]
+/// # struct FpConfig_;
+/// # type Fp = ark_ff::Fp64<ark_ff::MontBackend<FpConfig_, 1>>;
+/// #
+/// # use ark_bls12_381::Fr;
+/// # use komodo::algebra::convert;
+/// # fn main() {
+/// assert_eq!(
+///     convert::<Fp, Fr>(&[Fp::from(2), Fp::from(3)]).unwrap(),
+///     vec![Fr::from(2), Fr::from(3)],
+/// );
+/// assert!(convert::<Fr, Fp>(&[Fr::from(2), Fr::from(65537)]).is_err());
+/// # }
+/// ```
+pub fn convert<F1: PrimeField, F2: PrimeField>(elements: &[F1]) -> Result<Vec<F2>, KomodoError> {
+    elements
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            let bytes = e.into_bigint().to_bytes_le();
+            let converted = F2::from_le_bytes_mod_order(&bytes);
+
+            if !has_same_representative(&bytes, &converted.into_bigint().to_bytes_le()) {
+                return Err(KomodoError::ElementTooLargeForField(i, format!("{}", e)));
+            }
+
+            Ok(converted)
+        })
+        .collect()
+}
+
+/// compare two little-endian integers, ignoring their trailing zero bytes
+fn has_same_representative(lhs: &[u8], rhs: &[u8]) -> bool {
+    fn strip(x: &[u8]) -> &[u8] {
+        let len = x.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+        &x[..len]
     }
 
-    bytes
+    strip(lhs) == strip(rhs)
+}
+
+/// re-encode data that has been split into elements of $\mathbb{F}_1$ into elements of
+/// $\mathbb{F}_2$
+///
+/// this is a byte-level bridge between two finite fields: the elements are first merged back
+/// into the `nb_bytes` bytes of the original data (see [`merge_elements_into_bytes`]) and then
+/// split again into elements of the new field (see [`split_data_into_field_elements`]), with the
+/// same `modulus` semantics for padding.
+///
+/// in opposition to [`convert`], this never fails, whatever the sizes of the two fields are, but
+/// the number of output elements might be different from the number of input elements.
+///
+/// > **Note**
+/// >
+/// > `nb_bytes` is usually the size of the original data, e.g. [`crate::fec::Shard::size`].
+pub fn reencode<F1: PrimeField, F2: PrimeField>(
+    elements: &[F1],
+    nb_bytes: usize,
+    modulus: usize,
+) -> Vec<F2> {
+    let mut bytes = merge_elements_into_bytes(elements);
+    bytes.resize(nb_bytes, 0);
+
+    split_data_into_field_elements(&bytes, modulus)
 }
 
 #[cfg(any(feature = "kzg", feature = "aplonk"))]
@@ -202,6 +298,8 @@ mod tests {
     #[cfg(any(feature = "kzg", feature = "aplonk"))]
     use ark_std::{test_rng, UniformRand};
 
+    use crate::error::KomodoError;
+
     fn bytes() -> Vec<u8> {
         include_bytes!("../../assets/dragoon_32x32.png").to_vec()
     }
@@ -270,6 +368,54 @@ mod tests {
         split_and_merge_template::<Fr>(&bytes(), 4096);
     }
 
+    #[derive(ark_ff::MontConfig)]
+    #[modulus = "65537"]
+    #[generator = "3"]
+    struct FpConfig_;
+    type Fp = ark_ff::Fp64<ark_ff::MontBackend<FpConfig_, 1>>;
+
+    #[test]
+    fn convert() {
+        let small: Vec<Fp> = (0..100u64).map(Fp::from).collect();
+        let big: Vec<Fr> = super::convert(&small).unwrap();
+        assert_eq!(big, (0..100u64).map(Fr::from).collect::<Vec<_>>());
+        assert_eq!(super::convert::<Fr, Fp>(&big).unwrap(), small);
+
+        assert_eq!(
+            super::convert::<Fr, Fp>(&[Fr::from(65536), Fr::from(65537), Fr::from(1)]),
+            Err(KomodoError::ElementTooLargeForField(1, "65537".to_string()))
+        );
+        assert!(super::convert::<Fr, Fp>(&[-Fr::from(1)]).is_err());
+        assert_eq!(super::convert::<Fr, Fp>(&[]).unwrap(), vec![]);
+    }
+
+    fn reencode_template<F1: PrimeField, F2: PrimeField>(bytes: &[u8], modulus: usize) {
+        let elements: Vec<F1> = super::split_data_into_field_elements(bytes, modulus);
+        let reencoded: Vec<F2> = super::reencode(&elements, bytes.len(), modulus);
+        assert_eq!(
+            reencoded,
+            super::split_data_into_field_elements::<F2>(bytes, modulus),
+            "TEST | modulus: {modulus}"
+        );
+
+        let back: Vec<F1> = super::reencode(&reencoded, bytes.len(), modulus);
+        assert_eq!(back, elements, "TEST | modulus: {modulus}");
+    }
+
+    #[test]
+    fn reencode() {
+        reencode_template::<Fr, Fp>(&bytes(), 1);
+        reencode_template::<Fr, Fp>(&bytes(), 8);
+        reencode_template::<Fp, Fr>(&bytes(), 1);
+        reencode_template::<Fp, Fr>(&bytes(), 8);
+    }
+
+    #[test]
+    fn split_and_merge_small_field() {
+        split_and_merge_template::<Fp>(&bytes(), 1);
+        split_and_merge_template::<Fp>(&bytes(), 8);
+    }
+
     #[cfg(any(feature = "kzg", feature = "aplonk"))]
     fn powers_of_template<E: Pairing>() {
         let rng = &mut test_rng();
@@ -9,7 +9,9 @@ use crate::algebra::{
 };
 use crate::aplonk::polynomial;
 use crate::aplonk::transcript;
-use crate::error::KomodoError;
+use crate::error::{KomodoError, Source};
+#[cfg(feature = "arbitrary")]
+use crate::fuzzing;
 
 /// holds the setup parameters of the IPA stage of [aPlonk from [Ambrona et al.]][aPlonK]
 ///
@@ -47,6 +49,41 @@ pub(super) struct Proof<E: Pairing> {
     pub ck_tau_0: E::G2,
 }
 
+#[cfg(feature = "arbitrary")]
+impl<E: Pairing> Proof<E> {
+    /// an arbitrary proof with `kappa` rounds, see [`crate::fuzzing`]
+    pub(super) fn arbitrary(
+        u: &mut arbitrary::Unstructured,
+        kappa: usize,
+    ) -> arbitrary::Result<Self> {
+        let g1s = |u: &mut arbitrary::Unstructured| {
+            (0..kappa)
+                .map(|_| fuzzing::group_element::<E::G1>(u))
+                .collect::<arbitrary::Result<Vec<_>>>()
+        };
+        let (l_r, r_r) = (g1s(u)?, g1s(u)?);
+
+        // NOTE: the elements of the target group are pairings, which are expensive, they are all
+        // multiples of a single one
+        let gt = <PairingOutput<E> as ark_ec::Group>::generator();
+        let gts = |u: &mut arbitrary::Unstructured| {
+            (0..kappa)
+                .map(|_| Ok(gt * fuzzing::field_element::<E::ScalarField>(u)?))
+                .collect::<arbitrary::Result<Vec<_>>>()
+        };
+        let (l_g, r_g) = (gts(u)?, gts(u)?);
+
+        Ok(Self {
+            l_g,
+            r_g,
+            l_r,
+            r_r,
+            mu_0: fuzzing::group_element(u)?,
+            ck_tau_0: fuzzing::group_element(u)?,
+        })
+    }
+}
+
 // compute if a number is a power of two
 //
 // generated by ChatGPT
@@ -86,10 +123,7 @@ pub(super) fn prove<E: Pairing>(
     mu: &[E::G1],
 ) -> Result<(Proof<E>, Vec<E::ScalarField>), KomodoError> {
     if !is_power_of_two(k) {
-        return Err(KomodoError::Other(format!(
-            "PolynomialCountIpaError: expected $k$ to be a power of 2, found {}",
-            k
-        )));
+        return Err(KomodoError::InvalidPolynomialCount(k));
     }
     let kappa = f64::log2(k as f64) as usize;
     let mut l_g = vector::zero::<PairingOutput<E>>(kappa);
@@ -104,7 +138,7 @@ pub(super) fn prove<E: Pairing>(
     let mut ck_tau = ck_tau.to_vec();
     let mut ts = match transcript::initialize(c_g, r, p) {
         Ok(transcript) => transcript,
-        Err(error) => return Err(KomodoError::Other(format!("SerializationError: {}", error))),
+        Err(error) => return Err(KomodoError::Transcript(Source::new(error))),
     };
 
     // 2.
@@ -124,20 +158,17 @@ pub(super) fn prove<E: Pairing>(
         // 5.
         u[j] = match transcript::hash(l_g[j], r_g[j], l_r[j], r_r[j], &ts) {
             Ok(hash) => hash,
-            Err(error) => return Err(KomodoError::Other(format!("SerializationError: {}", error))),
+            Err(error) => return Err(KomodoError::Transcript(Source::new(error))),
         };
         ts = match transcript::reset::<E>(u[j]) {
             Ok(transcript) => transcript,
-            Err(error) => return Err(KomodoError::Other(format!("SerializationError: {}", error))),
+            Err(error) => return Err(KomodoError::Transcript(Source::new(error))),
         };
 
         let u_j_inv = if let Some(inverse) = u[j].inverse() {
             inverse
         } else {
-            return Err(KomodoError::Other(format!(
-                "EllipticInverseError: could not inverse {:?}",
-                u[j],
-            )));
+            return Err(KomodoError::NonInvertibleElement(format!("{:?}", u[j])));
         };
 
         // 6.
@@ -214,15 +245,12 @@ where
     for<'a, 'b> &'a P: Div<&'b P, Output = P>,
 {
     if !is_power_of_two(k) {
-        return Err(KomodoError::Other(format!(
-            "PolynomialCountIpaError: expected $k$ to be a power of 2, found {}",
-            k,
-        )));
+        return Err(KomodoError::InvalidPolynomialCount(k));
     }
     let kappa = f64::log2(k as f64) as usize;
     let mut ts = match transcript::initialize(c_g, r, p) {
         Ok(transcript) => transcript,
-        Err(error) => return Err(KomodoError::Other(format!("SerializationError: {}", error))),
+        Err(error) => return Err(KomodoError::Transcript(Source::new(error))),
     };
     let mut u = vector::zero::<E::ScalarField>(kappa);
 
@@ -239,11 +267,11 @@ where
         // 3.
         u[j] = match transcript::hash(l_g[j], r_g[j], l_r[j], r_r[j], &ts) {
             Ok(hash) => hash,
-            Err(error) => return Err(KomodoError::Other(format!("SerializationError: {}", error))),
+            Err(error) => return Err(KomodoError::Transcript(Source::new(error))),
         };
         ts = match transcript::reset::<E>(u[j]) {
             Ok(transcript) => transcript,
-            Err(error) => return Err(KomodoError::Other(format!("SerializationError: {}", error))),
+            Err(error) => return Err(KomodoError::Transcript(Source::new(error))),
         };
     }
 
@@ -252,10 +280,7 @@ where
         if let Some(inverse) = u_i.inverse() {
             u_inv.push(inverse)
         } else {
-            return Err(KomodoError::Other(format!(
-                "EllipticInverseError: could not inverse {:?}",
-                u_i,
-            )));
+            return Err(KomodoError::NonInvertibleElement(format!("{:?}", u_i)));
         }
     }
 
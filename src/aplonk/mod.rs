@@ -13,16 +13,26 @@ use ark_poly_commit::{
     PCRandomness,
 };
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress};
-use ark_std::{test_rng, One, UniformRand};
+use ark_std::{
+    cfg_iter,
+    rand::{CryptoRng, RngCore},
+    test_rng, One, UniformRand,
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use rs_merkle::algorithms::Sha256;
 use rs_merkle::Hasher;
 use std::marker::PhantomData;
 use std::ops::{Div, Mul};
 
+#[cfg(feature = "arbitrary")]
+use crate::fuzzing;
 use crate::{
-    algebra,
-    error::KomodoError,
-    fec::Shard,
+    algebra::{self, linalg::Matrix},
+    error::{KomodoError, Source},
+    fec::{self, Shard},
+    pipeline::ProvingScheme,
+    telemetry::operation,
     zk::{ark_commit, trim},
 };
 
@@ -41,6 +51,36 @@ pub struct Block<E: Pairing> {
     aplonk_proof: E::G2,
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a, E: Pairing> arbitrary::Arbitrary<'a> for Block<E> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let shard = Shard::arbitrary(u)?;
+        // NOTE: the IPA proof has $\kappa = \log_2(m)$ rounds, with $m$ the number of polynomials,
+        // i.e. the number of elements of the shard
+        let kappa = f64::log2(shard.data.len() as f64) as usize;
+
+        Ok(Self {
+            shard,
+            com_f: fuzzing::group_element(u)?,
+            v_hat: fuzzing::field_element(u)?,
+            mu_hat: fuzzing::group_element(u)?,
+            kzg_proof: kzg10::Proof {
+                w: fuzzing::group_element::<E::G1>(u)?.into(),
+                random_v: None,
+            },
+            ipa_proof: ipa::Proof::arbitrary(u, kappa)?,
+            aplonk_proof: fuzzing::group_element(u)?,
+        })
+    }
+}
+
+impl<E: Pairing> Block<E> {
+    /// the commitment of all the polynomials of the data at once, see [`commit`]
+    pub fn commit(&self) -> &PairingOutput<E> {
+        &self.com_f
+    }
+}
+
 /// /!\ [`Commitment`] is not [`CanonicalDeserialize`] because `P` is not [`Send`].
 #[derive(Debug, Clone, Default, PartialEq, CanonicalSerialize)]
 pub struct Commitment<E, P>
@@ -74,6 +114,11 @@ pub struct VerifierKey<E: Pairing> {
 /// > this is an almost perfect translation of the *Setup* algorithm in page
 /// > **13** of [aPlonk from [Ambrona et al.]][aPlonK]
 ///
+/// > **Note**  
+/// > the secrets of the setup are drawn from [`test_rng`], i.e. they are the same every time and
+/// > the setup is not secure, which is only useful for tests and benchmarks, see
+/// > [`setup_secure`].
+///
 /// [aPlonk]: https://eprint.iacr.org/2022/1352.pdf
 pub fn setup<E, P>(
     degree_bound: usize,
@@ -84,7 +129,33 @@ where
     P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
     for<'a, 'b> &'a P: Div<&'b P, Output = P>,
 {
-    let rng = &mut test_rng();
+    setup_with::<E, P>(degree_bound, nb_polynomials, &mut test_rng())
+}
+
+/// the same as [`setup`], with secrets drawn from a cryptographically secure source of randomness
+pub fn setup_secure<E, P>(
+    degree_bound: usize,
+    nb_polynomials: usize,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<SetupParams<E>, ark_poly_commit::Error>
+where
+    E: Pairing,
+    P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    setup_with::<E, P>(degree_bound, nb_polynomials, rng)
+}
+
+fn setup_with<E, P>(
+    degree_bound: usize,
+    nb_polynomials: usize,
+    rng: &mut impl RngCore,
+) -> Result<SetupParams<E>, ark_poly_commit::Error>
+where
+    E: Pairing,
+    P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
     let params = KZG10::<E, P>::setup(degree_bound, true, rng)?;
 
     let g_1 = params.powers_of_g[0];
@@ -117,27 +188,25 @@ where
     let supported_degree = polynomials.iter().map(|p| p.degree()).max().unwrap_or(0);
 
     if setup.ipa.ck_tau.len() < polynomials.len() {
-        return Err(KomodoError::Other(format!(
-            "setup error: expected at least {} powers of ck_tau for IPA, found {}",
-            polynomials.len(),
+        return Err(KomodoError::TooFewPowersInIpaSetup(
             setup.ipa.ck_tau.len(),
-        )));
+            polynomials.len(),
+        ));
     }
 
     let (powers, _) = trim(setup.kzg, supported_degree);
 
     if powers.powers_of_g.len() <= supported_degree {
-        return Err(KomodoError::Other(format!(
-            "setup error: expected at least {} powers of g for KZG, found {}",
-            supported_degree,
+        return Err(KomodoError::TooFewPowersInTrustedSetup(
             powers.powers_of_g.len(),
-        )));
+            supported_degree + 1,
+        ));
     }
 
     // commit.1.
     let mu = match ark_commit(&powers, &polynomials) {
         Ok((mu, _)) => mu,
-        Err(error) => return Err(KomodoError::Other(format!("commit error: {}", error))),
+        Err(error) => return Err(KomodoError::KzgCommit(Source::new(error))),
     };
     let mu: Vec<E::G1> = mu.iter().map(|c| c.0.into_group()).collect();
 
@@ -171,114 +240,109 @@ where
         points.len()
     );
 
+    let op = operation!("prove", "aplonk", shards = shards.len());
+
     let (mu, com_f) = commit;
 
     let supported_degree = polynomials.iter().map(|p| p.degree()).max().unwrap_or(0);
     let (powers, _) = trim(params.kzg, supported_degree);
 
     // open
-    let mut proofs = Vec::new();
-    for (s, pt) in shards.iter().zip(points.iter()) {
-        let v_hat_elements = polynomials
-            .iter()
-            .map(|p| p.evaluate(pt))
-            .collect::<Vec<E::ScalarField>>();
-
-        // open.3.1.
-        let mut r_bytes = vec![];
-        if let Err(error) = com_f.serialize_with_mode(&mut r_bytes, Compress::Yes) {
-            return Err(KomodoError::Other(format!("serialization: {}", error)));
-        }
-        if let Err(error) = pt.serialize_with_mode(&mut r_bytes, Compress::Yes) {
-            return Err(KomodoError::Other(format!("serialization: {}", error)));
-        }
-        // FIXME: hash *com_v* here
-        let hash = Sha256::hash(r_bytes.as_slice());
-        let r = E::ScalarField::from_le_bytes_mod_order(&hash);
-
-        // open.3.2.
-        let r_vec = algebra::powers_of::<E>(r, polynomials.len());
-        // open.3.3
-        let f = algebra::scalar_product_polynomial::<E, P>(&r_vec, &polynomials);
-        // open.3.4.
-        let mu_hat: E::G1 = algebra::scalar_product_g1::<E>(&mu, &r_vec);
-        // open.3.5.
-        let v_hat: E::ScalarField = algebra::scalar_product::<E>(&v_hat_elements, &r_vec);
-
-        // open.4.
-        let kzg_proof = match KZG10::<E, P>::open(
-            &powers,
-            &f,
-            *pt,
-            &Randomness::<E::ScalarField, P>::empty(),
-        ) {
-            Ok(proof) => proof,
-            Err(error) => return Err(KomodoError::Other(format!("kzg open error: {}", error))),
-        };
-
-        // open.5.
-        // we do no need this step as we already share the shards on the network
-
-        // open.6.
-        let (ipa_proof, u) =
-            match ipa::prove(polynomials.len(), &params.ipa.ck_tau, com_f, r, mu_hat, &mu) {
-                Ok(proof) => proof,
-                Err(error) => return Err(error),
-            };
-        let mut u_inv = Vec::new();
-        for u_i in &u {
-            if let Some(inverse) = u_i.inverse() {
-                u_inv.push(inverse)
-            } else {
-                return Err(KomodoError::Other(format!(
-                    "EllipticInverseError: could not inverse {:?}",
-                    u_i
-                )));
-            }
-        }
-
-        // open.7.1.
-        let kappa = f64::log2(polynomials.len() as f64) as usize;
-        let g = polynomial::compute_g::<E, P>(polynomials.len(), kappa, &u, &u_inv);
-        // open.7.2.
-        let mut rho_bytes = vec![];
-        if let Err(error) = ipa_proof.serialize_with_mode(&mut rho_bytes, Compress::Yes) {
-            return Err(KomodoError::Other(format!("SerializationError: {}", error)));
-        }
-        let rho = E::ScalarField::from_le_bytes_mod_order(&Sha256::hash(rho_bytes.as_slice()));
-        // open.7.3.
-        // implicit in the computation of the witness polynomial
-
-        // open.8.1.
-        let h = match KZG10::<E, P>::compute_witness_polynomial(
-            &g,
-            rho,
-            &Randomness::<E::ScalarField, P>::empty(),
-        ) {
-            Ok((h, _)) => h,
-            Err(error) => return Err(KomodoError::Other(format!("kzg witness error: {}", error))),
-        };
-        // open.8.2.
-        let aplonk_proof = h
-            .coeffs()
-            .iter()
-            .enumerate()
-            .map(|(i, hi)| params.ipa.ck_tau[i].mul(hi))
-            .sum();
-
-        // open.9.
-        proofs.push(Block {
-            shard: s.clone(),
-            com_f,
-            v_hat,
-            mu_hat,
-            kzg_proof,
-            ipa_proof,
-            aplonk_proof,
-        });
-    }
-
-    Ok(proofs)
+    //
+    // NOTE: the shards are proven in parallel with the `parallel` feature
+    op.done(
+        cfg_iter!(shards)
+            .zip(cfg_iter!(points))
+            .map(|(s, pt)| {
+                let v_hat_elements = polynomials
+                    .iter()
+                    .map(|p| p.evaluate(pt))
+                    .collect::<Vec<E::ScalarField>>();
+
+                // open.3.1.
+                let mut r_bytes = vec![];
+                com_f.serialize_with_mode(&mut r_bytes, Compress::Yes)?;
+                pt.serialize_with_mode(&mut r_bytes, Compress::Yes)?;
+                // FIXME: hash *com_v* here
+                let hash = Sha256::hash(r_bytes.as_slice());
+                let r = E::ScalarField::from_le_bytes_mod_order(&hash);
+
+                // open.3.2.
+                let r_vec = algebra::powers_of::<E>(r, polynomials.len());
+                // open.3.3
+                let f = algebra::scalar_product_polynomial::<E, P>(&r_vec, &polynomials);
+                // open.3.4.
+                let mu_hat: E::G1 = algebra::scalar_product_g1::<E>(&mu, &r_vec);
+                // open.3.5.
+                let v_hat: E::ScalarField = algebra::scalar_product::<E>(&v_hat_elements, &r_vec);
+
+                // open.4.
+                let kzg_proof = match KZG10::<E, P>::open(
+                    &powers,
+                    &f,
+                    *pt,
+                    &Randomness::<E::ScalarField, P>::empty(),
+                ) {
+                    Ok(proof) => proof,
+                    Err(error) => return Err(KomodoError::KzgOpen(Source::new(error))),
+                };
+
+                // open.5.
+                // we do no need this step as we already share the shards on the network
+
+                // open.6.
+                let (ipa_proof, u) =
+                    ipa::prove(polynomials.len(), &params.ipa.ck_tau, com_f, r, mu_hat, &mu)?;
+                let mut u_inv = Vec::new();
+                for u_i in &u {
+                    if let Some(inverse) = u_i.inverse() {
+                        u_inv.push(inverse)
+                    } else {
+                        return Err(KomodoError::NonInvertibleElement(format!("{:?}", u_i)));
+                    }
+                }
+
+                // open.7.1.
+                let kappa = f64::log2(polynomials.len() as f64) as usize;
+                let g = polynomial::compute_g::<E, P>(polynomials.len(), kappa, &u, &u_inv);
+                // open.7.2.
+                let mut rho_bytes = vec![];
+                ipa_proof.serialize_with_mode(&mut rho_bytes, Compress::Yes)?;
+                let rho =
+                    E::ScalarField::from_le_bytes_mod_order(&Sha256::hash(rho_bytes.as_slice()));
+                // open.7.3.
+                // implicit in the computation of the witness polynomial
+
+                // open.8.1.
+                let h = match KZG10::<E, P>::compute_witness_polynomial(
+                    &g,
+                    rho,
+                    &Randomness::<E::ScalarField, P>::empty(),
+                ) {
+                    Ok((h, _)) => h,
+                    Err(error) => return Err(KomodoError::KzgOpen(Source::new(error))),
+                };
+                // open.8.2.
+                let aplonk_proof = h
+                    .coeffs()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, hi)| params.ipa.ck_tau[i].mul(hi))
+                    .sum();
+
+                // open.9.
+                Ok(Block {
+                    shard: s.clone(),
+                    com_f,
+                    v_hat,
+                    mu_hat,
+                    kzg_proof,
+                    ipa_proof,
+                    aplonk_proof,
+                })
+            })
+            .collect(),
+    )
 }
 
 pub fn verify<E, P>(
@@ -294,14 +358,12 @@ where
     P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
     for<'a, 'b> &'a P: Div<&'b P, Output = P>,
 {
+    let op = operation!("verify", "aplonk", elements = block.shard.data.len());
+
     // check.1.
     let mut bytes = vec![];
-    if let Err(error) = block.com_f.serialize_with_mode(&mut bytes, Compress::Yes) {
-        return Err(KomodoError::Other(format!("SerializationError: {}", error)));
-    }
-    if let Err(error) = pt.serialize_with_mode(&mut bytes, Compress::Yes) {
-        return Err(KomodoError::Other(format!("SerializationError: {}", error)));
-    }
+    block.com_f.serialize_with_mode(&mut bytes, Compress::Yes)?;
+    pt.serialize_with_mode(&mut bytes, Compress::Yes)?;
     // FIXME: hash *com_v* here
     let hash = Sha256::hash(bytes.as_slice());
     let r = E::ScalarField::from_le_bytes_mod_order(&hash);
@@ -310,7 +372,7 @@ where
     let p1 = block.mu_hat - vk_psi.g.mul(block.v_hat);
     let inner = vk_psi.beta_h.into_group() - vk_psi.h.mul(&pt);
     if E::pairing(p1, vk_psi.h) != E::pairing(block.kzg_proof.w, inner) {
-        return Ok(false);
+        return Ok(op.verified(false));
     }
 
     // TODO: missing part of the aplonk algorithm
@@ -329,24 +391,21 @@ where
         block.mu_hat,
         &block.ipa_proof,
     )? {
-        return Ok(false);
+        return Ok(op.verified(false));
     }
 
     // check.5.1.
     let mut bytes = vec![];
-    if let Err(error) = block
+    block
         .ipa_proof
-        .serialize_with_mode(&mut bytes, Compress::Yes)
-    {
-        return Err(KomodoError::Other(format!("SerializationError: {}", error)));
-    }
+        .serialize_with_mode(&mut bytes, Compress::Yes)?;
     let hash = Sha256::hash(bytes.as_slice());
     let rho = E::ScalarField::from_le_bytes_mod_order(&hash);
 
     let kappa = f64::log2(nb_polynomials as f64) as usize;
     let mut ts = match transcript::initialize(block.com_f, r, block.mu_hat) {
         Ok(transcript) => transcript,
-        Err(error) => return Err(KomodoError::Other(format!("SerializationError: {}", error))),
+        Err(error) => return Err(KomodoError::Transcript(Source::new(error))),
     };
     let mut u = algebra::vector::zero::<E::ScalarField>(kappa);
     for j in (0..kappa).rev() {
@@ -358,11 +417,11 @@ where
             &ts,
         ) {
             Ok(hash) => hash,
-            Err(error) => return Err(KomodoError::Other(format!("SerializationError: {}", error))),
+            Err(error) => return Err(KomodoError::Transcript(Source::new(error))),
         };
         ts = match transcript::reset::<E>(u[j]) {
             Ok(transcript) => transcript,
-            Err(error) => return Err(KomodoError::Other(format!("SerializationError: {}", error))),
+            Err(error) => return Err(KomodoError::Transcript(Source::new(error))),
         };
     }
 
@@ -371,10 +430,7 @@ where
         if let Some(inverse) = u_i.inverse() {
             u_inv.push(inverse)
         } else {
-            return Err(KomodoError::Other(format!(
-                "EllipticInverseError: could not inverse {:?}",
-                u_i
-            )));
+            return Err(KomodoError::NonInvertibleElement(format!("{:?}", u_i)));
         }
     }
 
@@ -395,7 +451,146 @@ where
     //     - b_psi has passed in check.2.
     //     - b_v is skipped for now
     //     - b_IPA has passed in check.4.
-    Ok(b_tau)
+    Ok(op.verified(b_tau))
+}
+
+/// aPlonK as a [`ProvingScheme`], with $k$ and the $n$ evaluation points of the encoding
+///
+/// > **Note**
+/// >
+/// > as with [`crate::kzg::Kzg`], the shards are encoded with the Vandermonde matrix of the
+/// > points.
+pub struct Aplonk<E: Pairing, P> {
+    points: Vec<E::ScalarField>,
+    k: usize,
+    _phantom: PhantomData<P>,
+}
+
+impl<E: Pairing, P> Aplonk<E, P> {
+    /// $n$ is the number of `points`
+    pub fn new(points: Vec<E::ScalarField>, k: usize) -> Self {
+        Self {
+            points,
+            k,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn polynomials(&self, bytes: &[u8]) -> Vec<P>
+    where
+        P: DenseUVPolynomial<E::ScalarField>,
+    {
+        algebra::split_data_into_field_elements::<E::ScalarField>(bytes, self.k)
+            .chunks(self.k)
+            .map(|c| P::from_coefficients_vec(c.to_vec()))
+            .collect()
+    }
+}
+
+/// the trusted setup of [`Aplonk`], with its verifier key, for `nb_bytes` of data
+fn scheme_setup<E, P>(
+    k: usize,
+    nb_bytes: usize,
+    rng: &mut impl RngCore,
+) -> Result<(SetupParams<E>, VerifierKey<E>), KomodoError>
+where
+    E: Pairing,
+    P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    let bytes_per_element = (E::ScalarField::MODULUS_BIT_SIZE as usize - 1) / 8;
+    let nb_polynomials = nb_bytes.div_ceil(bytes_per_element).div_ceil(k);
+
+    let params = setup_with::<E, P>(k - 1, nb_polynomials, rng)
+        .map_err(|e| KomodoError::KzgCommit(Source::new(e)))?;
+    let (_, vk_psi) = trim(params.kzg.clone(), k - 1);
+    let vk = VerifierKey {
+        vk_psi,
+        tau_1: params.ipa.tau_1,
+        g1: params.kzg.powers_of_g[0].into_group(),
+        g2: params.kzg.h.into_group(),
+    };
+
+    Ok((params, vk))
+}
+
+impl<E, P> ProvingScheme for Aplonk<E, P>
+where
+    E: Pairing,
+    P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    type Field = E::ScalarField;
+    type SetupParams = (SetupParams<E>, VerifierKey<E>);
+    type Proof = Vec<Block<E>>;
+    type Block = Block<E>;
+
+    fn setup<R: RngCore>(
+        &self,
+        nb_bytes: usize,
+        rng: &mut R,
+    ) -> Result<Self::SetupParams, KomodoError> {
+        crate::randomness::check("aplonk::setup")?;
+        scheme_setup::<E, P>(self.k, nb_bytes, rng)
+    }
+
+    fn setup_secure<R: RngCore + CryptoRng>(
+        &self,
+        nb_bytes: usize,
+        rng: &mut R,
+    ) -> Result<Self::SetupParams, KomodoError> {
+        scheme_setup::<E, P>(self.k, nb_bytes, rng)
+    }
+
+    fn encode(&self, bytes: &[u8]) -> Result<Vec<Shard<E::ScalarField>>, KomodoError> {
+        fec::encode(bytes, &Matrix::vandermonde_unchecked(&self.points, self.k))
+    }
+
+    fn encode_with_hash(
+        &self,
+        bytes: &[u8],
+        hash: &[u8],
+    ) -> Result<Vec<Shard<E::ScalarField>>, KomodoError> {
+        let encoding_mat = Matrix::vandermonde_unchecked(&self.points, self.k);
+        fec::encode_with_hash(bytes, &encoding_mat, hash.to_vec())
+    }
+
+    fn prove(
+        &self,
+        bytes: &[u8],
+        shards: &[Shard<E::ScalarField>],
+        (params, _): &Self::SetupParams,
+    ) -> Result<Vec<Block<E>>, KomodoError> {
+        let polynomials = self.polynomials(bytes);
+        let commit = commit(polynomials.clone(), params.clone())?;
+        prove::<E, P>(
+            commit,
+            polynomials,
+            shards.to_vec(),
+            self.points.clone(),
+            params.clone(),
+        )
+    }
+
+    // NOTE: the blocks are built at the same time as the proofs
+    fn build(&self, _shards: Vec<Shard<E::ScalarField>>, proof: Vec<Block<E>>) -> Vec<Block<E>> {
+        proof
+    }
+
+    fn verify(&self, block: &Block<E>, (_, vk): &Self::SetupParams) -> Result<bool, KomodoError> {
+        verify::<E, P>(
+            block,
+            block.shard.linear_combination[1],
+            &vk.vk_psi,
+            vk.tau_1,
+            vk.g1,
+            vk.g2,
+        )
+    }
+
+    fn shard(block: &Block<E>) -> &Shard<E::ScalarField> {
+        &block.shard
+    }
 }
 
 #[cfg(test)]
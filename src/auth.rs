@@ -0,0 +1,225 @@
+//! signatures of blocks and manifests by the nodes that produce them
+//!
+//! a node is identified by an Ed25519 key pair, see [`keypair`], and signs the values it sends,
+//! e.g. the blocks it has proven or recoded, with [`sign`]. A receiver can then check with
+//! [`Signed::verify`] that a value comes from the node with the public key [`Signed::signer`], e.g.
+//! to attribute invalid blocks to the node that sent them and implement slashing or reputation.
+//!
+//! the signature is computed over the canonical serialization of the value, prefixed with the
+//! protocol and the curve the value belongs to, e.g. `"semi-avid"` and `"BLS12-381"`, so that a
+//! signature cannot be replayed on a value of another protocol that would serialize the same.
+//!
+//! > **Note**
+//! >
+//! > the signature proves who sent a value, not that the value is correct, which is still the job
+//! > of the proving schemes, e.g. [`crate::pipeline::ProvingScheme::verify`].
+//!
+//! # Example
+//! ```
+//! # use ark_bls12_381::{Fr as F, G1Projective as G};
+//! # use ark_poly::univariate::DensePolynomial as DP;
+//! use komodo::{
+//!     algebra::linalg::Matrix,
+//!     auth,
+//!     pipeline::{self, ProvingScheme},
+//!     semi_avid::SemiAvid,
+//! };
+//!
+//! # fn main() {
+//! let mut rng = ark_std::test_rng();
+//! let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+//!
+//! let scheme = SemiAvid::<F, G, DP<F>>::new(Matrix::random(3, 6, &mut rng));
+//! let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+//! let blocks = pipeline::disperse(&scheme, &bytes, &setup).unwrap();
+//!
+//! let (secret_key, public_key) = auth::keypair(&mut rng).unwrap();
+//! let signed = auth::sign(blocks[0].clone(), "semi-avid", "BLS12-381", &secret_key).unwrap();
+//!
+//! assert_eq!(signed.signer, public_key);
+//! assert!(signed.verify("semi-avid", "BLS12-381").unwrap());
+//! assert!(!signed.verify("kzg", "BLS12-381").unwrap());
+//! # }
+//! ```
+use anyhow::{anyhow, Result};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{CryptoRng, RngCore};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+/// the domain of the signatures, so that they cannot be mistaken for signatures of something else
+const DOMAIN: &[u8] = b"komodo/auth/1";
+
+/// a value together with the signature of the node that produced it, see [`sign`]
+///
+/// > **Note**
+/// >
+/// > the value is serialized last, so that values that must be the last of the data they are
+/// > deserialized from, e.g. a [`crate::fs::Manifest`], can be signed as well.
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Signed<T: CanonicalSerialize + CanonicalDeserialize> {
+    /// the Ed25519 public key of the node that signed the value
+    pub signer: [u8; 32],
+    /// the Ed25519 signature of the value
+    pub signature: [u8; 64],
+    /// the signed value, e.g. a block or a manifest
+    pub value: T,
+}
+
+/// generate an Ed25519 key pair for a node, as `(secret key, public key)`
+///
+/// > **Note**
+/// >
+/// > `rng` is subject to the [`crate::randomness::RandomnessPolicy`], see [`keypair_secure`].
+pub fn keypair<R: RngCore>(rng: &mut R) -> Result<([u8; 32], [u8; 32])> {
+    crate::randomness::check("auth::keypair")?;
+    Ok(keypair_unchecked(rng))
+}
+
+/// the same as [`keypair`], with a cryptographically secure source of randomness
+pub fn keypair_secure<R: RngCore + CryptoRng>(rng: &mut R) -> ([u8; 32], [u8; 32]) {
+    keypair_unchecked(rng)
+}
+
+fn keypair_unchecked<R: RngCore>(rng: &mut R) -> ([u8; 32], [u8; 32]) {
+    let mut secret_key = [0; 32];
+    rng.fill_bytes(&mut secret_key);
+    let public_key = SigningKey::from_bytes(&secret_key)
+        .verifying_key()
+        .to_bytes();
+    (secret_key, public_key)
+}
+
+/// the message that is actually signed for a value
+fn message<T: CanonicalSerialize>(value: &T, protocol: &str, curve: &str) -> Result<Vec<u8>> {
+    let mut message = DOMAIN.to_vec();
+    protocol.to_string().serialize_compressed(&mut message)?;
+    curve.to_string().serialize_compressed(&mut message)?;
+    value.serialize_compressed(&mut message)?;
+    Ok(message)
+}
+
+/// sign a value of a given protocol and curve with the secret key of a node, see [`keypair`]
+pub fn sign<T: CanonicalSerialize + CanonicalDeserialize>(
+    value: T,
+    protocol: &str,
+    curve: &str,
+    secret_key: &[u8; 32],
+) -> Result<Signed<T>> {
+    let signing_key = SigningKey::from_bytes(secret_key);
+    let signature = signing_key.sign(&message(&value, protocol, curve)?);
+
+    Ok(Signed {
+        signer: signing_key.verifying_key().to_bytes(),
+        signature: signature.to_bytes(),
+        value,
+    })
+}
+
+impl<T: CanonicalSerialize + CanonicalDeserialize> Signed<T> {
+    /// whether the value has been signed by [`Signed::signer`] for a given protocol and curve
+    ///
+    /// it is an error if the public key of the signer is not a valid Ed25519 public key.
+    pub fn verify(&self, protocol: &str, curve: &str) -> Result<bool> {
+        let verifying_key = VerifyingKey::from_bytes(&self.signer)
+            .map_err(|e| anyhow!("invalid public key of signer: {}", e))?;
+        let message = message(&self.value, protocol, curve)?;
+
+        Ok(verifying_key
+            .verify(&message, &Signature::from_bytes(&self.signature))
+            .is_ok())
+    }
+
+    /// whether the value has been signed by a given node, see [`Signed::verify`]
+    pub fn verify_from(&self, signer: &[u8; 32], protocol: &str, curve: &str) -> Result<bool> {
+        Ok(&self.signer == signer && self.verify(protocol, curve)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::test_rng;
+
+    use super::{keypair, sign, Signed};
+    #[cfg(feature = "fs")]
+    use crate::fs::Manifest;
+    use crate::{
+        algebra::linalg::Matrix,
+        pipeline::{self, ProvingScheme},
+        semi_avid::{Block, SemiAvid},
+    };
+
+    type S = SemiAvid<Fr, G1Projective, DensePolynomial<Fr>>;
+
+    #[test]
+    fn sign_and_verify() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        let scheme = S::new(Matrix::random(3, 5, &mut rng));
+        let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let blocks = pipeline::disperse(&scheme, &bytes, &setup).unwrap();
+
+        let (secret_key, public_key) = keypair(&mut rng).unwrap();
+        let (_, other) = keypair(&mut rng).unwrap();
+
+        let signed = sign(blocks[0].clone(), "semi-avid", "BLS12-381", &secret_key).unwrap();
+        assert_eq!(signed.signer, public_key);
+        assert!(signed.verify("semi-avid", "BLS12-381").unwrap());
+        assert!(signed
+            .verify_from(&public_key, "semi-avid", "BLS12-381")
+            .unwrap());
+        assert!(!signed
+            .verify_from(&other, "semi-avid", "BLS12-381")
+            .unwrap());
+        assert!(!signed.verify("kzg", "BLS12-381").unwrap());
+        assert!(!signed.verify("semi-avid", "BN254").unwrap());
+
+        let mut serialized = vec![];
+        signed.serialize_compressed(&mut serialized).unwrap();
+        let deserialized =
+            Signed::<Block<Fr, G1Projective>>::deserialize_compressed(&serialized[..]).unwrap();
+        assert_eq!(deserialized, signed);
+
+        let mut tampered = signed.clone();
+        tampered.value = blocks[1].clone();
+        assert!(!tampered.verify("semi-avid", "BLS12-381").unwrap());
+        let mut tampered = signed.clone();
+        tampered.signer = other;
+        assert!(!tampered.verify("semi-avid", "BLS12-381").unwrap());
+        let mut tampered = signed.clone();
+        tampered.signature[0] ^= 1;
+        assert!(!tampered.verify("semi-avid", "BLS12-381").unwrap());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn manifest() {
+        let mut rng = test_rng();
+        let manifest = Manifest {
+            file_hash: vec![1, 2, 3],
+            size: 3,
+            k: 3,
+            n: 5,
+            curve: "BLS12-381".to_string(),
+            setup_digest: vec![],
+            block_hashes: vec!["a".to_string(), "b".to_string()],
+            sealing: None,
+        };
+
+        let (secret_key, _) = keypair(&mut rng).unwrap();
+        let signed = sign(manifest.clone(), "semi-avid", &manifest.curve, &secret_key).unwrap();
+        assert!(signed.verify("semi-avid", "BLS12-381").unwrap());
+
+        let mut serialized = vec![];
+        signed.serialize_compressed(&mut serialized).unwrap();
+        let deserialized = Signed::<Manifest>::deserialize_compressed(&serialized[..]).unwrap();
+        assert_eq!(deserialized, signed);
+
+        let mut tampered = signed.clone();
+        tampered.value.block_hashes.pop();
+        assert!(!tampered.verify("semi-avid", "BLS12-381").unwrap());
+    }
+}
@@ -0,0 +1,329 @@
+//! primitives for data-availability sampling, e.g. for blockchains
+//!
+//! a producer encodes some data into $n$ shards, the columns of the encoded matrix, and publishes a
+//! commitment to the whole matrix, with [`commit`]. Light clients then make sure that the data is
+//! _available_, i.e. that enough shards can be downloaded to decode it, without downloading it:
+//! each client draws a few random shard indices, with [`sample_indices`], asks the network for
+//! them, gets [`Sample`]s built with [`open`], and checks each one against the commitment with
+//! [`verify_sample`]. A [`Verifier`] accumulates the results into a _confidence_ that the data is
+//! available.
+//!
+//! the commitment is the one of [Semi-AVID](crate::semi_avid), i.e. one commitment per source
+//! shard, which makes any shard, whatever its linear combination, verifiable on its own.
+//!
+//! > **Note**
+//! >
+//! > the data is extended in a single dimension, i.e. this is the 1D construction: an adversary
+//! > has to withhold at least $n - k + 1$ shards to make the data unavailable.
+//!
+//! # Example
+//! ```
+//! # use ark_bls12_381::{Fr as F, G1Projective as G};
+//! # use ark_poly::univariate::DensePolynomial as DP;
+//! use komodo::{algebra::linalg::Matrix, das, zk};
+//!
+//! # fn main() {
+//! let mut rng = ark_std::test_rng();
+//! let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+//! let (k, n) = (4, 16);
+//!
+//! let encoding_mat = Matrix::random(k, n, &mut rng);
+//! let powers = zk::setup::<F, G>(zk::nb_elements_in_setup::<F>(bytes.len()), &mut rng).unwrap();
+//! let (shards, commitment) = das::commit::<F, G, DP<F>>(&bytes, &encoding_mat, &powers).unwrap();
+//!
+//! let mut verifier = das::Verifier::new(k, n);
+//! for sample in das::open(&shards, &das::sample_indices(b"some seed", n, 8)) {
+//!     let valid =
+//!         das::verify_sample::<F, G, DP<F>>(&sample, &commitment, &encoding_mat, &powers).unwrap();
+//!     verifier.record(sample.index, valid);
+//! }
+//! assert!(verifier.confidence() > 0.99);
+//! # }
+//! ```
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_poly::DenseUVPolynomial;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{ops::Div, vec::Vec};
+use rs_merkle::{algorithms::Sha256, Hasher};
+
+use crate::{
+    algebra::linalg::Matrix,
+    error::KomodoError,
+    fec::Shard,
+    semi_avid::{self, Block},
+    zk::{Commitment, Powers},
+};
+
+/// a shard of the encoded data together with its index, i.e. its column in the encoding matrix
+#[derive(Debug, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Sample<F: PrimeField> {
+    pub index: usize,
+    pub shard: Shard<F>,
+}
+
+/// encode some data and commit to the whole encoded matrix
+///
+/// the shards are given in the order of the columns of the encoding matrix, i.e. shard $j$ is the
+/// one with index $j$.
+#[allow(clippy::type_complexity)]
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn commit<F, G, P>(
+    bytes: &[u8],
+    encoding_mat: &Matrix<F>,
+    powers: &Powers<F, G>,
+) -> Result<(Vec<Shard<F>>, Vec<Commitment<F, G>>), KomodoError>
+where
+    F: PrimeField,
+    G: CurveGroup<ScalarField = F>,
+    P: DenseUVPolynomial<F>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    let shards = crate::fec::encode(bytes, encoding_mat)?;
+    let commitment = semi_avid::prove::<F, G, P>(bytes, powers, encoding_mat.height)?;
+    Ok((shards, commitment))
+}
+
+/// draw `nb_samples` distinct indices of shards, out of $n$, from a seed
+///
+/// the indices are derived from the seed only, so that anyone can check that a client has not
+/// chosen them, e.g. with a seed taken from a block of a blockchain.
+///
+/// > **Note**
+/// >
+/// > at most $n$ indices are drawn.
+pub fn sample_indices(seed: &[u8], n: usize, nb_samples: usize) -> Vec<usize> {
+    let nb_samples = nb_samples.min(n);
+
+    let mut indices = Vec::with_capacity(nb_samples);
+    let mut counter: u64 = 0;
+    while indices.len() < nb_samples {
+        let mut preimage = seed.to_vec();
+        preimage.extend(counter.to_le_bytes());
+        let hash = Sha256::hash(&preimage);
+
+        let mut word = [0; 8];
+        word.copy_from_slice(&hash[..8]);
+        let index = (u64::from_le_bytes(word) % n as u64) as usize;
+        if !indices.contains(&index) {
+            indices.push(index);
+        }
+        counter += 1;
+    }
+
+    indices
+}
+
+/// the samples of some shards at given indices, the indices out of bounds are skipped
+pub fn open<F: PrimeField>(shards: &[Shard<F>], indices: &[usize]) -> Vec<Sample<F>> {
+    indices
+        .iter()
+        .filter_map(|&index| {
+            shards.get(index).map(|shard| Sample {
+                index,
+                shard: shard.clone(),
+            })
+        })
+        .collect()
+}
+
+/// verify that a sample is the shard at its index of the data that has been committed to
+///
+/// a sample is valid if its linear combination is the column of the encoding matrix at its index
+/// and if it is valid with respect to the commitment, see [`semi_avid::verify`].
+pub fn verify_sample<F, G, P>(
+    sample: &Sample<F>,
+    commitment: &[Commitment<F, G>],
+    encoding_mat: &Matrix<F>,
+    powers: &Powers<F, G>,
+) -> Result<bool, KomodoError>
+where
+    F: PrimeField,
+    G: CurveGroup<ScalarField = F>,
+    P: DenseUVPolynomial<F>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    if encoding_mat.get_col(sample.index).as_ref() != Some(&sample.shard.linear_combination)
+        || commitment.len() != encoding_mat.height
+    {
+        return Ok(false);
+    }
+
+    semi_avid::verify::<F, G, P>(
+        &Block {
+            shard: sample.shard.clone(),
+            proof: commitment.to_vec(),
+        },
+        powers,
+    )
+}
+
+/// the probability that `nb_samples` distinct samples all succeed while the data is not available,
+/// i.e. when only $k - 1$ of the $n$ shards are
+fn false_positive(k: usize, n: usize, nb_samples: usize) -> f64 {
+    (0..nb_samples)
+        .map(|i| (k.saturating_sub(1 + i)) as f64 / (n - i) as f64)
+        .product()
+}
+
+/// the number of distinct samples required to reach a given confidence, see
+/// [`Verifier::confidence`]
+///
+/// > **Note**
+/// >
+/// > this is at most $k$, because $k$ valid samples are enough to decode the data.
+pub fn nb_samples_for(k: usize, n: usize, confidence: f64) -> usize {
+    (0..=k.min(n))
+        .find(|&s| 1.0 - false_positive(k, n, s) >= confidence)
+        .unwrap_or(k.min(n))
+}
+
+/// accumulates the samples of a light client into a confidence that the data is available
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verifier {
+    k: usize,
+    n: usize,
+    sampled: Vec<usize>,
+    unavailable: bool,
+}
+
+impl Verifier {
+    pub fn new(k: usize, n: usize) -> Self {
+        Self {
+            k,
+            n,
+            sampled: Vec::new(),
+            unavailable: false,
+        }
+    }
+
+    /// record the outcome of a sample, `valid` being `false` when the sample is invalid or could
+    /// not be downloaded
+    ///
+    /// > **Note**
+    /// >
+    /// > samples at the same index are only counted once.
+    pub fn record(&mut self, index: usize, valid: bool) {
+        if !valid {
+            self.unavailable = true;
+        } else if index < self.n && !self.sampled.contains(&index) {
+            self.sampled.push(index);
+        }
+    }
+
+    /// the number of distinct valid samples
+    pub fn nb_samples(&self) -> usize {
+        self.sampled.len()
+    }
+
+    /// whether a sample has failed, in which case the data is considered unavailable
+    pub fn has_failed(&self) -> bool {
+        self.unavailable
+    }
+
+    /// the probability that the data is available, given the samples so far
+    ///
+    /// this is $1 - \prod\limits_{i = 0}^{s - 1} \frac{k - 1 - i}{n - i}$, with $s$ the number of
+    /// distinct valid samples, i.e. $1$ minus the probability that all the samples succeed while
+    /// the adversary withholds the minimum number of shards, $n - k + 1$, to make the data
+    /// unavailable, and $0$ as soon as a sample has failed.
+    pub fn confidence(&self) -> f64 {
+        if self.unavailable {
+            return 0.0;
+        }
+        1.0 - false_positive(self.k, self.n, self.sampled.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_std::test_rng;
+
+    use super::{commit, nb_samples_for, open, sample_indices, verify_sample, Verifier};
+    use crate::{algebra::linalg::Matrix, zk};
+
+    type P = DensePolynomial<Fr>;
+
+    #[test]
+    fn indices() {
+        let indices = sample_indices(b"seed", 16, 8);
+        assert_eq!(indices, sample_indices(b"seed", 16, 8));
+        assert_ne!(indices, sample_indices(b"other seed", 16, 8));
+        assert_eq!(indices.len(), 8);
+        assert!(indices.iter().all(|&i| i < 16));
+        assert!(indices
+            .iter()
+            .all(|i| indices.iter().filter(|j| *j == i).count() == 1));
+
+        let mut all = sample_indices(b"seed", 16, 100);
+        all.sort();
+        assert_eq!(all, (0..16).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn sampling() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+        let (k, n) = (4, 16);
+
+        let encoding_mat = Matrix::random(k, n, &mut rng);
+        let powers =
+            zk::setup::<Fr, G1Projective>(zk::nb_elements_in_setup::<Fr>(bytes.len()), &mut rng)
+                .unwrap();
+        let (shards, commitment) = commit::<_, _, P>(&bytes, &encoding_mat, &powers).unwrap();
+
+        let indices = sample_indices(b"seed", n, 3);
+        let samples = open(&shards, &indices);
+        assert_eq!(samples.len(), 3);
+
+        let mut verifier = Verifier::new(k, n);
+        let mut confidence = verifier.confidence();
+        assert_eq!(confidence, 0.0);
+        for sample in &samples {
+            assert!(verify_sample::<_, _, P>(sample, &commitment, &encoding_mat, &powers).unwrap());
+            verifier.record(sample.index, true);
+            verifier.record(sample.index, true);
+            assert!(verifier.confidence() > confidence);
+            confidence = verifier.confidence();
+        }
+        assert_eq!(verifier.nb_samples(), 3);
+
+        let mut tampered = samples[0].clone();
+        tampered.shard.data[0] += Fr::from(1u64);
+        assert!(!verify_sample::<_, _, P>(&tampered, &commitment, &encoding_mat, &powers).unwrap());
+        // NOTE: a valid shard presented at the wrong index
+        let mut moved = samples[0].clone();
+        moved.index = (moved.index + 1) % n;
+        assert!(!verify_sample::<_, _, P>(&moved, &commitment, &encoding_mat, &powers).unwrap());
+        moved.index = n;
+        assert!(!verify_sample::<_, _, P>(&moved, &commitment, &encoding_mat, &powers).unwrap());
+
+        verifier.record(tampered.index, false);
+        assert!(verifier.has_failed());
+        assert_eq!(verifier.confidence(), 0.0);
+    }
+
+    #[test]
+    fn confidence() {
+        let (k, n) = (4, 16);
+
+        let mut verifier = Verifier::new(k, n);
+        for i in 0..k {
+            verifier.record(i, true);
+        }
+        // NOTE: $k$ valid shards are enough to decode
+        assert_eq!(verifier.confidence(), 1.0);
+
+        let s = nb_samples_for(k, n, 0.99);
+        let mut verifier = Verifier::new(k, n);
+        (0..s).for_each(|i| verifier.record(i, true));
+        assert!(verifier.confidence() >= 0.99);
+        let mut verifier = Verifier::new(k, n);
+        (0..s - 1).for_each(|i| verifier.record(i, true));
+        assert!(verifier.confidence() < 0.99);
+
+        assert_eq!(nb_samples_for(k, n, 1.0), k);
+    }
+}
@@ -1,7 +1,104 @@
 //! Komodo-specific errors
 //!
 //! there are a few linear algebra errors and some related to ZK.
-use thiserror::Error;
+//!
+//! all the errors implement [`Error`], where the errors of the dependencies of Komodo are given by
+//! [`Error::source`], and have a stable numeric code, see [`KomodoError::code`].
+//!
+//! > **Note**
+//! >
+//! > [`Error`] is [`std::error::Error`] when the `std` feature is enabled, which is the default,
+//! > and a trait of `ark-std` with the same interface otherwise.
+//!
+//! an error can also be sent across process boundaries, e.g. by a service exposing Komodo over
+//! HTTP, as a [`WireError`], which can be serialized with `serde` when the `serde` feature is
+//! enabled.
+use ark_serialize::SerializationError;
+use ark_std::{
+    error::Error,
+    fmt, format,
+    ops::Deref,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+/// the underlying error of a dependency, e.g. of `ark-serialize` or `ark-poly-commit`
+///
+/// the error is shared, so that [`KomodoError`] can still be cloned and compared, and can be
+/// recovered with [`Source::downcast_ref`] or as the [`Error::source`] of the [`KomodoError`].
+///
+/// > **Note**
+/// >
+/// > two sources are equal when their messages are, whatever their types.
+#[derive(Debug, Clone)]
+pub struct Source(Arc<dyn Error + Send + Sync + 'static>);
+
+impl Source {
+    /// wrap an arbitrary error as the source of a [`KomodoError`]
+    pub fn new(error: impl Error + Send + Sync + 'static) -> Self {
+        Self(Arc::new(error))
+    }
+
+    /// the underlying error, if it has type `E`
+    ///
+    /// > **Note**
+    /// >
+    /// > this requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        self.0.downcast_ref()
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// NOTE: `Source` is not an error itself but derefs to the underlying error, so that the source
+// of a `KomodoError` is the error of the dependency, not this wrapper
+impl Deref for Source {
+    type Target = dyn Error + Send + Sync + 'static;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl PartialEq for Source {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+/// the shards involved in a failed decoding, see [`crate::fec::decode`]
+///
+/// this tells which shards are useful, i.e. linearly independent, and how many more are needed,
+/// so that the missing ones can be requested, e.g. from the network.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct DecodeContext {
+    /// the hash of the original data, see [`crate::fec::Shard::hash`]
+    pub hash: Vec<u8>,
+    /// the indices, among the given shards, of a maximal set of linearly independent shards
+    pub independent: Vec<usize>,
+    /// the indices of all the other shards, which are linear combinations of the independent
+    /// ones and thus useless to decode
+    pub dependent: Vec<usize>,
+}
+
+impl DecodeContext {
+    /// the rank of the given shards, i.e. the number of linearly independent shards
+    pub fn rank(&self) -> usize {
+        self.independent.len()
+    }
+}
 
 /// An error that Komodo could end up producing.
 ///
@@ -9,43 +106,580 @@ use thiserror::Error;
 /// - related to _linear algebra_
 /// - related to FEC
 /// - related to proving the shards
-#[derive(Clone, Debug, Error, PartialEq)]
+/// - coming from a dependency, which is then given as a [`Source`]
+/// - related to the headers of the serialized artifacts, see [`crate::header`]
+///
+/// > **Note**
+/// >
+/// > new variants might be added without a major release, see [`KomodoError::code`] to identify
+/// > errors across versions.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum KomodoError {
     /// `{0}` is a custom error message when a matrix is invalid.
-    #[error("Invalid matrix elements: {0}")]
     InvalidMatrixElements(String),
     /// `{0}` and `{1}` are the shape of the rectangular matrix.
-    #[error("Matrix is not a square, ({0} x {1})")]
     NonSquareMatrix(usize, usize),
     /// `{0}` is the ID of the row where the matrix inversion failed.
-    #[error("Matrix is not invertible at row {0}")]
     NonInvertibleMatrix(usize),
     /// `{0}` and `{1}` are the shape of the left matrix and `{2}` and `{3}` are the shape of the
     /// right matrix.
-    #[error("Matrices don't have compatible shapes: ({0} x {1}) and ({2} x {3})")]
     IncompatibleMatrixShapes(usize, usize, usize, usize),
     /// `{0}` and `{1}` are the IDs of the non-distinct _Vandermonde_ points and `{2}` is the list
     /// of all the _Vandermonde_ points.
-    #[error(
-        "Seed points of a Vandermonde matrix should be distinct: {0} and {1} are the same ({2})"
-    )]
     InvalidVandermonde(usize, usize, String),
-    /// `{0}` is the actual number of shards and `{1}` is the expected amount.
-    #[error("Expected at least {1} shards, got {0}")]
-    TooFewShards(usize, usize),
+    /// `{0}` is the actual number of shards, `{1}` is the expected amount and `{2}` describes the
+    /// shards that have been given.
+    TooFewShards(usize, usize, DecodeContext),
+    /// `{0}` is the expected number of linearly independent shards and `{1}` describes the shards
+    /// that have been given, see [`DecodeContext::rank`].
+    LinearlyDependentShards(usize, DecodeContext),
     /// `{0}` is a custom error message when shards are incompatible.
-    #[error("Shards are incompatible: {0}")]
     IncompatibleShards(String),
+    /// `{0}` is the number of source shards and `{1}` the number of encoded shards, for which
+    /// there is no radix-2 evaluation domain, i.e. $k \gt n$ or the field is not large enough.
+    InvalidEvaluationDomain(usize, usize),
+    /// `{0}` is the maximum number of corrupted shards that can be corrected, i.e.
+    /// $\lfloor (m - k) / 2 \rfloor$ with $m$ shards, and there are more.
+    UncorrectableShards(usize),
+    /// `{0}..{1}` is the requested range of bytes and `{2}` is the size of the data.
+    InvalidByteRange(usize, usize, usize),
+    /// `{0}` is a custom error message when the padding of the data is invalid.
+    InvalidPadding(String),
     /// `{0}` is a custom error message when blocks are incompatible.
-    #[error("Blocks are incompatible: {0}")]
     IncompatibleBlocks(String),
-    #[error("Degree is zero")]
+    /// `{0}` is the index of the element that could not be converted and `{1}` is its value.
+    ElementTooLargeForField(usize, String),
     DegreeIsZero,
     /// `{0}` is the supported degree of the trusted setup and `{1}` is the actual requested
     /// polynomnial degree
-    #[error("too many coefficients: max is {0}, found {0}")]
     TooFewPowersInTrustedSetup(usize, usize),
-    /// `{0}` is a custom error message.
-    #[error("Another error: {0}")]
-    Other(String),
+    /// `{0}` is the number of powers of $\tau$ in the IPA part of the trusted setup and `{1}` is
+    /// the actual number of polynomials
+    TooFewPowersInIpaSetup(usize, usize),
+    /// `{0}` is the number of polynomials, which should be a power of $2$ for IPA.
+    InvalidPolynomialCount(usize),
+    /// `{0}` is the element, formatted, that does not have an inverse in its field.
+    NonInvertibleElement(String),
+    /// something could not be serialized.
+    Serialization(Source),
+    /// the transcript of IPA could not be built.
+    Transcript(Source),
+    /// polynomials could not be committed with KZG.
+    KzgCommit(Source),
+    /// a polynomial could not be opened with KZG, i.e. its witness could not be computed.
+    KzgOpen(Source),
+    /// some data could not be read or written.
+    Io(Source),
+    /// a task could not run to completion, e.g. it panicked or its runtime has been shut down.
+    Task(Source),
+    /// `{0}` is the latest version supported by the reader and `{1}` is the version of the
+    /// artifact, $0$ meaning that the artifact has no header.
+    VersionMismatch(u32, u32),
+    /// `{0}` is the expected protocol and `{1}` is the protocol of the artifact.
+    ProtocolMismatch(String, String),
+    /// `{0}` is the expected curve and `{1}` is the curve of the artifact.
+    CurveMismatch(String, String),
+    /// `{0}` is the digest of the expected trusted setup and `{1}` is the one of the artifact,
+    /// both in hexadecimal.
+    SetupMismatch(String, String),
+    /// `{0}` is the operation that has been given a source of randomness that is not known to be
+    /// cryptographically secure, see [`crate::randomness::RandomnessPolicy`].
+    WeakRandomness(String),
+}
+
+impl KomodoError {
+    /// a stable numeric code for the error, e.g. for FFI or CLI consumers
+    ///
+    /// the codes are grouped by family and never change nor get reused:
+    /// - `1xx`: linear algebra
+    /// - `2xx`: FEC
+    /// - `3xx`: proofs and trusted setups
+    /// - `4xx`: errors of the dependencies
+    /// - `5xx`: headers of the serialized artifacts
+    pub fn code(&self) -> u32 {
+        match self {
+            Self::InvalidMatrixElements(..) => 100,
+            Self::NonSquareMatrix(..) => 101,
+            Self::NonInvertibleMatrix(..) => 102,
+            Self::IncompatibleMatrixShapes(..) => 103,
+            Self::InvalidVandermonde(..) => 104,
+            Self::ElementTooLargeForField(..) => 105,
+            Self::NonInvertibleElement(..) => 106,
+            Self::TooFewShards(..) => 200,
+            Self::IncompatibleShards(..) => 201,
+            Self::LinearlyDependentShards(..) => 202,
+            Self::InvalidEvaluationDomain(..) => 203,
+            Self::UncorrectableShards(..) => 204,
+            Self::InvalidByteRange(..) => 205,
+            Self::InvalidPadding(..) => 206,
+            Self::IncompatibleBlocks(..) => 300,
+            Self::DegreeIsZero => 301,
+            Self::TooFewPowersInTrustedSetup(..) => 302,
+            Self::TooFewPowersInIpaSetup(..) => 303,
+            Self::InvalidPolynomialCount(..) => 304,
+            Self::WeakRandomness(..) => 305,
+            Self::Serialization(..) => 400,
+            Self::Transcript(..) => 401,
+            Self::KzgCommit(..) => 402,
+            Self::KzgOpen(..) => 403,
+            Self::Io(..) => 404,
+            Self::Task(..) => 405,
+            Self::VersionMismatch(..) => 500,
+            Self::ProtocolMismatch(..) => 501,
+            Self::CurveMismatch(..) => 502,
+            Self::SetupMismatch(..) => 503,
+        }
+    }
+
+    /// whether the error comes from an artifact that has not been produced with the same version,
+    /// protocol, curve or trusted setup as the reader, see [`crate::header::Header::check`]
+    pub fn is_mismatch(&self) -> bool {
+        matches!(
+            self,
+            Self::VersionMismatch(..)
+                | Self::ProtocolMismatch(..)
+                | Self::CurveMismatch(..)
+                | Self::SetupMismatch(..)
+        )
+    }
+}
+
+impl fmt::Display for KomodoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMatrixElements(s) => write!(f, "Invalid matrix elements: {}", s),
+            Self::NonSquareMatrix(h, w) => write!(f, "Matrix is not a square, ({} x {})", h, w),
+            Self::NonInvertibleMatrix(i) => write!(f, "Matrix is not invertible at row {}", i),
+            Self::IncompatibleMatrixShapes(h1, w1, h2, w2) => write!(
+                f,
+                "Matrices don't have compatible shapes: ({} x {}) and ({} x {})",
+                h1, w1, h2, w2
+            ),
+            Self::InvalidVandermonde(i, j, points) => write!(
+                f,
+                "Seed points of a Vandermonde matrix should be distinct: {} and {} are the same ({})",
+                i, j, points
+            ),
+            Self::TooFewShards(actual, expected, _) => {
+                write!(f, "Expected at least {} shards, got {}", expected, actual)
+            }
+            Self::LinearlyDependentShards(expected, context) => write!(
+                f,
+                "Expected {} linearly independent shards, got {}",
+                expected,
+                context.rank()
+            ),
+            Self::IncompatibleShards(s) => write!(f, "Shards are incompatible: {}", s),
+            Self::InvalidPadding(s) => write!(f, "Invalid padding: {}", s),
+            Self::InvalidByteRange(start, end, size) => write!(
+                f,
+                "Invalid range of bytes {}..{} for data of {} bytes",
+                start, end, size
+            ),
+            Self::UncorrectableShards(e) => write!(
+                f,
+                "Too many corrupted shards, at most {} can be corrected",
+                e
+            ),
+            Self::InvalidEvaluationDomain(k, n) => write!(
+                f,
+                "No radix-2 evaluation domain to encode {} source shards into {}",
+                k, n
+            ),
+            Self::IncompatibleBlocks(s) => write!(f, "Blocks are incompatible: {}", s),
+            Self::ElementTooLargeForField(i, value) => write!(
+                f,
+                "Element {} does not fit in the target field: {}",
+                i, value
+            ),
+            Self::DegreeIsZero => write!(f, "Degree is zero"),
+            Self::TooFewPowersInTrustedSetup(max, found) => write!(
+                f,
+                "too many coefficients: max is {}, found {}",
+                max, found
+            ),
+            Self::TooFewPowersInIpaSetup(max, found) => write!(
+                f,
+                "too many polynomials for IPA: max is {}, found {}",
+                max, found
+            ),
+            Self::InvalidPolynomialCount(n) => write!(
+                f,
+                "Expected the number of polynomials to be a power of 2, found {}",
+                n
+            ),
+            Self::NonInvertibleElement(e) => write!(f, "Could not invert field element {}", e),
+            Self::WeakRandomness(operation) => write!(
+                f,
+                "{} requires a cryptographically secure source of randomness",
+                operation
+            ),
+            Self::Serialization(source) => write!(f, "Serialization error: {}", source),
+            Self::Transcript(source) => write!(f, "Transcript error: {}", source),
+            Self::KzgCommit(source) => write!(f, "KZG commit error: {}", source),
+            Self::KzgOpen(source) => write!(f, "KZG open error: {}", source),
+            Self::Io(source) => write!(f, "I/O error: {}", source),
+            Self::Task(source) => write!(f, "Task error: {}", source),
+            Self::VersionMismatch(expected, 0) => write!(
+                f,
+                "Version mismatch: expected a header up to version {}, found none",
+                expected
+            ),
+            Self::VersionMismatch(expected, found) => write!(
+                f,
+                "Version mismatch: expected up to version {}, found version {}",
+                expected, found
+            ),
+            Self::ProtocolMismatch(expected, found) => write!(
+                f,
+                "Protocol mismatch: expected {:?}, found {:?}",
+                expected, found
+            ),
+            Self::CurveMismatch(expected, found) => write!(
+                f,
+                "Curve mismatch: expected {:?}, found {:?}",
+                expected, found
+            ),
+            Self::SetupMismatch(expected, found) => write!(
+                f,
+                "Trusted setup mismatch: expected {}, found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl Error for KomodoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Serialization(source)
+            | Self::Transcript(source)
+            | Self::KzgCommit(source)
+            | Self::KzgOpen(source)
+            | Self::Io(source)
+            | Self::Task(source) => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+/// an error that has been received from another process, see [`KomodoError::from_wire`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteError(pub String);
+
+impl fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for RemoteError {}
+
+/// a self-contained representation of a [`KomodoError`], to transmit it to another process
+///
+/// the fields of the error are kept, formatted as strings, together with the messages of all its
+/// sources, so that the receiver can still react to the exact error with
+/// [`KomodoError::from_wire`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WireError {
+    /// see [`KomodoError::code`]
+    pub code: u32,
+    /// the message of the error, as given by its [`fmt::Display`] implementation
+    pub message: String,
+    /// the fields of the error, in order
+    pub fields: Vec<String>,
+    /// the messages of the chain of sources of the error, starting with the direct source
+    pub sources: Vec<String>,
+}
+
+impl KomodoError {
+    /// convert the error to its [`WireError`] representation
+    pub fn to_wire(&self) -> WireError {
+        let fields = match self {
+            Self::InvalidMatrixElements(s)
+            | Self::IncompatibleShards(s)
+            | Self::InvalidPadding(s)
+            | Self::IncompatibleBlocks(s)
+            | Self::NonInvertibleElement(s)
+            | Self::WeakRandomness(s) => vec![s.clone()],
+            Self::NonSquareMatrix(a, b)
+            | Self::InvalidEvaluationDomain(a, b)
+            | Self::TooFewPowersInTrustedSetup(a, b)
+            | Self::TooFewPowersInIpaSetup(a, b) => vec![a.to_string(), b.to_string()],
+            Self::NonInvertibleMatrix(a)
+            | Self::UncorrectableShards(a)
+            | Self::InvalidPolynomialCount(a) => vec![a.to_string()],
+            Self::IncompatibleMatrixShapes(a, b, c, d) => {
+                vec![a.to_string(), b.to_string(), c.to_string(), d.to_string()]
+            }
+            Self::InvalidVandermonde(a, b, s) => vec![a.to_string(), b.to_string(), s.clone()],
+            Self::VersionMismatch(a, b) => vec![a.to_string(), b.to_string()],
+            Self::InvalidByteRange(a, b, c) => vec![a.to_string(), b.to_string(), c.to_string()],
+            Self::ProtocolMismatch(a, b)
+            | Self::CurveMismatch(a, b)
+            | Self::SetupMismatch(a, b) => vec![a.clone(), b.clone()],
+            Self::ElementTooLargeForField(a, s) => vec![a.to_string(), s.clone()],
+            Self::TooFewShards(a, b, context) => [
+                vec![a.to_string(), b.to_string()],
+                context_to_fields(context),
+            ]
+            .concat(),
+            Self::LinearlyDependentShards(a, context) => {
+                [vec![a.to_string()], context_to_fields(context)].concat()
+            }
+            Self::DegreeIsZero
+            | Self::Serialization(_)
+            | Self::Transcript(_)
+            | Self::KzgCommit(_)
+            | Self::KzgOpen(_)
+            | Self::Io(_)
+            | Self::Task(_) => vec![],
+        };
+
+        let mut sources = vec![];
+        let mut source = Error::source(self);
+        while let Some(error) = source {
+            sources.push(error.to_string());
+            source = error.source();
+        }
+
+        WireError {
+            code: self.code(),
+            message: self.to_string(),
+            fields,
+            sources,
+        }
+    }
+
+    /// rebuild an error from its [`WireError`] representation, see [`KomodoError::to_wire`]
+    ///
+    /// the source of the error, if any, becomes a [`RemoteError`] with the message of the
+    /// original source. `None` is returned if the code is unknown, e.g. because the error comes
+    /// from a more recent version of Komodo, or if the fields are invalid.
+    pub fn from_wire(wire: &WireError) -> Option<Self> {
+        let string = |i: usize| wire.fields.get(i).cloned();
+        let number = |i: usize| wire.fields.get(i)?.parse::<usize>().ok();
+        let source = || {
+            Source::new(RemoteError(
+                wire.sources.first().cloned().unwrap_or_default(),
+            ))
+        };
+
+        let error = match wire.code {
+            100 => Self::InvalidMatrixElements(string(0)?),
+            101 => Self::NonSquareMatrix(number(0)?, number(1)?),
+            102 => Self::NonInvertibleMatrix(number(0)?),
+            103 => Self::IncompatibleMatrixShapes(number(0)?, number(1)?, number(2)?, number(3)?),
+            104 => Self::InvalidVandermonde(number(0)?, number(1)?, string(2)?),
+            105 => Self::ElementTooLargeForField(number(0)?, string(1)?),
+            106 => Self::NonInvertibleElement(string(0)?),
+            200 => Self::TooFewShards(
+                number(0)?,
+                number(1)?,
+                context_from_fields(wire.fields.get(2..)?)?,
+            ),
+            201 => Self::IncompatibleShards(string(0)?),
+            202 => Self::LinearlyDependentShards(
+                number(0)?,
+                context_from_fields(wire.fields.get(1..)?)?,
+            ),
+            203 => Self::InvalidEvaluationDomain(number(0)?, number(1)?),
+            204 => Self::UncorrectableShards(number(0)?),
+            205 => Self::InvalidByteRange(number(0)?, number(1)?, number(2)?),
+            206 => Self::InvalidPadding(string(0)?),
+            300 => Self::IncompatibleBlocks(string(0)?),
+            301 => Self::DegreeIsZero,
+            302 => Self::TooFewPowersInTrustedSetup(number(0)?, number(1)?),
+            303 => Self::TooFewPowersInIpaSetup(number(0)?, number(1)?),
+            304 => Self::InvalidPolynomialCount(number(0)?),
+            305 => Self::WeakRandomness(string(0)?),
+            400 => Self::Serialization(source()),
+            401 => Self::Transcript(source()),
+            402 => Self::KzgCommit(source()),
+            403 => Self::KzgOpen(source()),
+            404 => Self::Io(source()),
+            405 => Self::Task(source()),
+            500 => Self::VersionMismatch(string(0)?.parse().ok()?, string(1)?.parse().ok()?),
+            501 => Self::ProtocolMismatch(string(0)?, string(1)?),
+            502 => Self::CurveMismatch(string(0)?, string(1)?),
+            503 => Self::SetupMismatch(string(0)?, string(1)?),
+            _ => return None,
+        };
+
+        Some(error)
+    }
+}
+
+/// the hash in hexadecimal and the independent and dependent indices as comma-separated lists
+fn context_to_fields(context: &DecodeContext) -> Vec<String> {
+    let indices = |indices: &[usize]| {
+        indices
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    vec![
+        context.hash.iter().map(|x| format!("{:02x}", x)).collect(),
+        indices(&context.independent),
+        indices(&context.dependent),
+    ]
+}
+
+fn context_from_fields(fields: &[String]) -> Option<DecodeContext> {
+    let [hash, independent, dependent] = fields else {
+        return None;
+    };
+    let indices = |indices: &str| {
+        indices
+            .split(',')
+            .filter(|i| !i.is_empty())
+            .map(|i| i.parse().ok())
+            .collect::<Option<Vec<usize>>>()
+    };
+
+    Some(DecodeContext {
+        hash: (0..hash.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hash.get(i..i + 2)?, 16).ok())
+            .collect::<Option<_>>()?,
+        independent: indices(independent)?,
+        dependent: indices(dependent)?,
+    })
+}
+
+impl From<SerializationError> for KomodoError {
+    fn from(error: SerializationError) -> Self {
+        Self::Serialization(Source::new(error))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for KomodoError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(Source::new(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_serialize::SerializationError;
+
+    use super::{DecodeContext, KomodoError, RemoteError, Source, WireError};
+
+    #[test]
+    fn typed_source() {
+        let err: KomodoError = SerializationError::NotEnoughSpace.into();
+        assert_eq!(err.clone(), err);
+
+        let KomodoError::Serialization(source) = &err else {
+            panic!("expected a serialization error, found {:?}", err);
+        };
+        assert!(matches!(
+            source.downcast_ref::<SerializationError>(),
+            Some(SerializationError::NotEnoughSpace)
+        ));
+        assert!(source.downcast_ref::<std::io::Error>().is_none());
+
+        // the source is the error of the dependency itself
+        assert!(err
+            .source()
+            .unwrap()
+            .downcast_ref::<SerializationError>()
+            .is_some());
+        assert_eq!(err.code(), 400);
+    }
+
+    fn all_errors() -> Vec<KomodoError> {
+        vec![
+            KomodoError::InvalidMatrixElements(String::new()),
+            KomodoError::NonSquareMatrix(0, 0),
+            KomodoError::NonInvertibleMatrix(0),
+            KomodoError::IncompatibleMatrixShapes(0, 0, 0, 0),
+            KomodoError::InvalidVandermonde(0, 0, String::new()),
+            KomodoError::ElementTooLargeForField(0, String::new()),
+            KomodoError::NonInvertibleElement(String::new()),
+            KomodoError::TooFewShards(0, 0, DecodeContext::default()),
+            KomodoError::LinearlyDependentShards(
+                3,
+                DecodeContext {
+                    hash: vec![0, 1, 255],
+                    independent: vec![0, 2],
+                    dependent: vec![1],
+                },
+            ),
+            KomodoError::IncompatibleShards(String::new()),
+            KomodoError::InvalidEvaluationDomain(0, 0),
+            KomodoError::UncorrectableShards(0),
+            KomodoError::InvalidByteRange(0, 1, 0),
+            KomodoError::InvalidPadding(String::new()),
+            KomodoError::IncompatibleBlocks(String::new()),
+            KomodoError::DegreeIsZero,
+            KomodoError::TooFewPowersInTrustedSetup(0, 0),
+            KomodoError::TooFewPowersInIpaSetup(0, 0),
+            KomodoError::InvalidPolynomialCount(0),
+            KomodoError::WeakRandomness(String::new()),
+            SerializationError::InvalidData.into(),
+            KomodoError::Transcript(Source::new(SerializationError::InvalidData)),
+            KomodoError::KzgCommit(Source::new(SerializationError::InvalidData)),
+            KomodoError::KzgOpen(Source::new(SerializationError::InvalidData)),
+            std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into(),
+            KomodoError::Task(Source::new(SerializationError::InvalidData)),
+            KomodoError::VersionMismatch(1, 2),
+            KomodoError::ProtocolMismatch(String::new(), String::new()),
+            KomodoError::CurveMismatch(String::new(), String::new()),
+            KomodoError::SetupMismatch(String::new(), String::new()),
+        ]
+    }
+
+    #[test]
+    fn unique_codes() {
+        let errors = all_errors();
+        let codes = errors
+            .iter()
+            .map(|e| e.code())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(codes.len(), errors.len());
+    }
+
+    #[test]
+    fn wire() {
+        for error in all_errors() {
+            let wire = error.to_wire();
+            assert_eq!(wire.code, error.code());
+            assert_eq!(wire.message, error.to_string());
+            assert_eq!(KomodoError::from_wire(&wire), Some(error));
+        }
+
+        let wire = KomodoError::IncompatibleMatrixShapes(1, 2, 3, 4).to_wire();
+        assert_eq!(wire.fields, vec!["1", "2", "3", "4"]);
+        assert!(wire.sources.is_empty());
+
+        let wire = KomodoError::from(SerializationError::NotEnoughSpace).to_wire();
+        assert_eq!(
+            wire.sources,
+            vec![SerializationError::NotEnoughSpace.to_string()]
+        );
+        let Some(KomodoError::Serialization(source)) = KomodoError::from_wire(&wire) else {
+            panic!("expected a serialization error");
+        };
+        assert!(source.downcast_ref::<RemoteError>().is_some());
+
+        // unknown codes and invalid fields are rejected
+        let unknown = WireError {
+            code: 999,
+            ..wire.clone()
+        };
+        assert_eq!(KomodoError::from_wire(&unknown), None);
+        let invalid = WireError {
+            code: 101,
+            fields: vec!["a".to_string(), "1".to_string()],
+            ..wire
+        };
+        assert_eq!(KomodoError::from_wire(&invalid), None);
+    }
 }
@@ -0,0 +1,574 @@
+//! a batteries-included entry point to store files with Komodo
+//!
+//! storing a file with Komodo means encoding it, proving the shards, storing the blocks and
+//! keeping track of them in a [`Manifest`], and getting it back means reading the manifest and
+//! the blocks, verifying them and decoding the file. [`Komodo`] does all of this, with any
+//! [`ProvingScheme`] and any [`BlockStore`], once configured with
+//! - a proving scheme, which holds the code parameters, e.g. the encoding matrix of
+//!   [`crate::semi_avid::SemiAvid`]
+//! - the trusted setup of the scheme and the name of its curve
+//! - a store for the blocks
+//! - a store for the manifests, see [`BlockStore::dump_manifest`]
+//!
+//! the files are identified by their [`FileId`], given by [`Komodo::store`] or, for many small
+//! files at once, [`Komodo::store_batch`].
+//!
+//! # Example
+//! ```
+//! # use ark_bls12_381::{Fr as F, G1Projective as G};
+//! # use ark_poly::univariate::DensePolynomial as DP;
+//! use komodo::{
+//!     algebra::linalg::Matrix, fs::store::MemoryStore, semi_avid::SemiAvid, Komodo,
+//!     ProvingScheme,
+//! };
+//!
+//! # fn main() {
+//! let mut rng = ark_std::test_rng();
+//! let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+//!
+//! let scheme = SemiAvid::<F, G, DP<F>>::new(Matrix::random(3, 6, &mut rng));
+//! let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+//! let mut komodo = Komodo::new(scheme, setup, "BLS12-381", MemoryStore::new(), MemoryStore::new())
+//!     .unwrap()
+//!     .with_protocol("semi-avid");
+//!
+//! let id = komodo.store(&bytes).unwrap();
+//! assert!(komodo.audit(&id).unwrap().is_complete());
+//! assert_eq!(komodo.fetch(&id).unwrap(), bytes);
+//! # }
+//! ```
+use std::fmt;
+
+use anyhow::Result;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+
+use crate::{
+    error::KomodoError,
+    fs::{store::BlockStore, Header, Manifest, ManifestStatus},
+    header,
+    pipeline::{self, ProvingScheme},
+};
+
+/// the identifier of a file stored with [`Komodo::store`], i.e. its [`Manifest::filename`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(pub String);
+
+impl fmt::Display for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// a proving scheme, its trusted setup and the stores of the blocks and the manifests, see the
+/// [module-level documentation](self)
+pub struct Komodo<S: ProvingScheme, B: BlockStore, M: BlockStore> {
+    scheme: S,
+    setup: S::SetupParams,
+    header: Header,
+    blocks: B,
+    manifests: M,
+    compress: Compress,
+}
+
+impl<S, B, M> Komodo<S, B, M>
+where
+    S: ProvingScheme,
+    S::Block: CanonicalSerialize + CanonicalDeserialize,
+    B: BlockStore,
+    M: BlockStore,
+{
+    /// configure Komodo once and for all
+    ///
+    /// the blocks are compressed, see [`Komodo::with_compress`], and their protocol is `"komodo"`,
+    /// see [`Komodo::with_protocol`].
+    pub fn new(
+        scheme: S,
+        setup: S::SetupParams,
+        curve: &str,
+        blocks: B,
+        manifests: M,
+    ) -> Result<Self>
+    where
+        S::SetupParams: CanonicalSerialize,
+    {
+        let setup_digest = Manifest::setup_digest(&setup, Compress::Yes)?;
+        Ok(Self {
+            scheme,
+            setup,
+            header: Header::new("komodo", curve, &setup_digest),
+            blocks,
+            manifests,
+            compress: Compress::Yes,
+        })
+    }
+
+    /// the same, with another protocol in the headers of the blocks, e.g. `"semi-avid"`
+    pub fn with_protocol(self, protocol: &str) -> Self {
+        Self {
+            header: Header {
+                artifact: header::Header {
+                    protocol: protocol.into(),
+                    ..self.header.artifact
+                },
+                ..self.header
+            },
+            ..self
+        }
+    }
+
+    /// the same, with another compression of the blocks and the manifests
+    pub fn with_compress(self, compress: Compress) -> Self {
+        Self { compress, ..self }
+    }
+
+    pub fn scheme(&self) -> &S {
+        &self.scheme
+    }
+
+    pub fn setup(&self) -> &S::SetupParams {
+        &self.setup
+    }
+
+    pub fn blocks(&self) -> &B {
+        &self.blocks
+    }
+
+    /// the store of the blocks, e.g. to simulate the loss of some of them
+    pub fn blocks_mut(&mut self) -> &mut B {
+        &mut self.blocks
+    }
+
+    pub fn manifests(&self) -> &M {
+        &self.manifests
+    }
+
+    /// disperse a file, store its blocks and its manifest and return its identifier
+    pub fn store(&mut self, bytes: &[u8]) -> Result<FileId> {
+        let blocks = pipeline::disperse(&self.scheme, bytes, &self.setup)?;
+        self.store_blocks(bytes.len(), &blocks)
+    }
+
+    /// disperse a batch of files at once and return their identifiers, in the same order
+    ///
+    /// the files share the trusted setup and are proven together, see [`pipeline::disperse_batch`],
+    /// which is cheaper than storing them one by one when they are small. Each file still has its
+    /// own manifest and can be fetched on its own.
+    pub fn store_batch(&mut self, files: &[&[u8]]) -> Result<Vec<FileId>> {
+        let batch = pipeline::disperse_batch(&self.scheme, files, &self.setup)?;
+        files
+            .iter()
+            .zip(batch)
+            .map(|(bytes, blocks)| self.store_blocks(bytes.len(), &blocks))
+            .collect()
+    }
+
+    fn store_blocks(&mut self, size: usize, blocks: &[S::Block]) -> Result<FileId> {
+        let (k, file_hash) = match blocks.first() {
+            Some(block) => {
+                let shard = S::shard(block);
+                (shard.k, shard.hash.clone())
+            }
+            None => (0, vec![]),
+        };
+        let header = self.header.clone().for_blocks(k, &file_hash);
+        let block_hashes = self.blocks.dump_blocks(blocks, &header, self.compress)?;
+
+        let manifest = Manifest {
+            file_hash,
+            size,
+            k: k as usize,
+            n: blocks.len(),
+            curve: self.header.artifact.curve.clone(),
+            setup_digest: self.header.artifact.setup_digest.clone(),
+            block_hashes,
+            sealing: None,
+        };
+        let filename = self.manifests.dump_manifest(&manifest, self.compress)?;
+
+        Ok(FileId(filename))
+    }
+
+    /// the manifest of a file
+    ///
+    /// it is an error if the file has been stored on another curve or with another trusted setup,
+    /// see [`BlockStore::read_block_as`].
+    pub fn manifest(&self, id: &FileId) -> Result<Manifest> {
+        let expected = Header::new(
+            "manifest",
+            &self.header.artifact.curve,
+            &self.header.artifact.setup_digest,
+        );
+        self.manifests
+            .read_block_as(&id.0, &expected, self.compress, Validate::Yes)
+    }
+
+    /// read a block of a file, `None` if it cannot be read or does not belong to the file
+    ///
+    /// blocks from another protocol, curve or trusted setup are errors, see
+    /// [`KomodoError::is_mismatch`].
+    fn read_block(&self, hash: &str, manifest: &Manifest) -> Result<Option<S::Block>> {
+        match self.blocks.read_block_as::<S::Block>(
+            hash,
+            &self.header,
+            self.compress,
+            Validate::Yes,
+        ) {
+            Ok(block) if S::shard(&block).hash == manifest.file_hash => Ok(Some(block)),
+            Ok(_) => Ok(None),
+            Err(e) if e.downcast_ref().is_some_and(KomodoError::is_mismatch) => Err(e),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// get a file back from its blocks
+    ///
+    /// > **Note**
+    /// >
+    /// > the blocks that are missing, that cannot be read or that do not belong to the file are
+    /// > skipped and the invalid ones are discarded, see [`pipeline::collect`], the file can be
+    /// > fetched as long as $k$ of them are left.
+    /// >
+    /// > however, a block from another protocol, curve or trusted setup means that the stores are
+    /// > shared with an incompatible configuration, which is an error, see
+    /// > [`KomodoError::is_mismatch`].
+    pub fn fetch(&self, id: &FileId) -> Result<Vec<u8>> {
+        let manifest = self.manifest(id)?;
+        let mut blocks = vec![];
+        for hash in &manifest.block_hashes {
+            if let Some(block) = self.read_block(hash, &manifest)? {
+                blocks.push(block);
+            }
+        }
+        Ok(pipeline::collect(&self.scheme, blocks, &self.setup)?)
+    }
+
+    /// check all the blocks of a file
+    ///
+    /// the blocks are read and verified, a block is
+    /// - _missing_ if it is not in the store anymore
+    /// - _corrupted_ if it cannot be read, if it does not belong to the file or if it is not valid
+    ///   with respect to the trusted setup
+    /// - _present_ otherwise
+    ///
+    /// as for [`Komodo::fetch`], blocks from an incompatible configuration are errors.
+    pub fn audit(&self, id: &FileId) -> Result<ManifestStatus> {
+        let manifest = self.manifest(id)?;
+
+        let mut status = ManifestStatus::default();
+        for hash in &manifest.block_hashes {
+            if !self.blocks.contains(hash)? {
+                status.missing.push(hash.clone());
+                continue;
+            }
+
+            let valid = match self.read_block(hash, &manifest)? {
+                Some(block) => self.scheme.verify(&block, &self.setup)?,
+                None => false,
+            };
+            if valid {
+                status.present.push(hash.clone());
+            } else {
+                status.corrupted.push(hash.clone());
+            }
+        }
+        status.nb_needed = manifest.k.saturating_sub(status.present.len());
+
+        Ok(status)
+    }
+
+    /// re-prove all the stored files under a new trusted setup and use it from now on, e.g. to
+    /// retire a compromised or deprecated ceremony without uploading the files again
+    ///
+    /// every file listed in the store of the manifests is fetched with the current setup and
+    /// re-proven with the new one, see [`pipeline::rotate_setup`], and the identifiers of the
+    /// rotated files are returned.
+    ///
+    /// > **Note**
+    /// >
+    /// > the manifests are only replaced once all the files have been re-proven, i.e. if any of
+    /// > them cannot be fetched, the new blocks are deleted and nothing changes. Then, each
+    /// > manifest is replaced at once and the old blocks are deleted last, so that a manifest never
+    /// > points to missing blocks.
+    pub fn rotate_setup(&mut self, setup: S::SetupParams) -> Result<Vec<FileId>>
+    where
+        S::SetupParams: CanonicalSerialize,
+    {
+        let header = Header {
+            artifact: header::Header {
+                setup_digest: Manifest::setup_digest(&setup, Compress::Yes)?,
+                ..self.header.artifact.clone()
+            },
+            ..self.header.clone()
+        };
+
+        let ids = self
+            .manifests
+            .list()?
+            .into_iter()
+            .map(FileId)
+            .collect::<Vec<_>>();
+        let mut rotated: Vec<(Manifest, Vec<String>)> = vec![];
+        for id in &ids {
+            match self.rotate_file(id, &setup, &header) {
+                Ok(r) => rotated.push(r),
+                Err(e) => {
+                    let old = rotated.iter().flat_map(|(_, old)| old).collect::<Vec<_>>();
+                    for (manifest, _) in &rotated {
+                        for hash in &manifest.block_hashes {
+                            if !old.contains(&hash) {
+                                self.blocks.delete(hash)?;
+                            }
+                        }
+                    }
+                    return Err(e.context(format!("could not rotate the setup of file {}", id)));
+                }
+            }
+        }
+
+        for (manifest, _) in &rotated {
+            self.manifests.dump_manifest(manifest, self.compress)?;
+        }
+        self.setup = setup;
+        self.header = header;
+
+        let new = rotated
+            .iter()
+            .flat_map(|(manifest, _)| &manifest.block_hashes)
+            .collect::<Vec<_>>();
+        for hash in rotated.iter().flat_map(|(_, old)| old) {
+            if !new.contains(&hash) && self.blocks.contains(hash)? {
+                self.blocks.delete(hash)?;
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// re-prove a file under a new setup and store its new blocks, returning its new manifest and
+    /// the hashes of its old blocks
+    fn rotate_file(
+        &mut self,
+        id: &FileId,
+        setup: &S::SetupParams,
+        header: &Header,
+    ) -> Result<(Manifest, Vec<String>)> {
+        let manifest = self.manifest(id)?;
+        let mut blocks = vec![];
+        for hash in &manifest.block_hashes {
+            if let Some(block) = self.read_block(hash, &manifest)? {
+                blocks.push(block);
+            }
+        }
+
+        let blocks = pipeline::rotate_setup(&self.scheme, &self.setup, setup, blocks)?;
+        let header = header
+            .clone()
+            .for_blocks(manifest.k as u32, &manifest.file_hash);
+        let block_hashes = self.blocks.dump_blocks(&blocks, &header, self.compress)?;
+
+        Ok((
+            Manifest {
+                setup_digest: header.artifact.setup_digest,
+                block_hashes,
+                ..manifest.clone()
+            },
+            manifest.block_hashes,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_serialize::Compress;
+    use ark_std::test_rng;
+
+    use super::{FileId, Komodo};
+    use crate::{
+        algebra::linalg::Matrix,
+        error::KomodoError,
+        fs::{store::BlockStore, store::MemoryStore, Header},
+        pipeline::ProvingScheme,
+        semi_avid::SemiAvid,
+    };
+
+    type S = SemiAvid<Fr, G1Projective, DensePolynomial<Fr>>;
+
+    #[test]
+    fn store_fetch_and_audit() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        let scheme = S::new(Matrix::random(3, 5, &mut rng));
+        let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let mut komodo = Komodo::new(
+            scheme,
+            setup,
+            "BLS12-381",
+            MemoryStore::new(),
+            MemoryStore::new(),
+        )
+        .unwrap()
+        .with_protocol("semi-avid");
+
+        let id = komodo.store(&bytes).unwrap();
+        let other = komodo.store(b"komodo").unwrap();
+        assert_ne!(id, other);
+
+        let manifest = komodo.manifest(&id).unwrap();
+        assert_eq!((manifest.k, manifest.n, manifest.size), (3, 5, bytes.len()));
+        assert!(komodo.audit(&id).unwrap().is_complete());
+        assert_eq!(komodo.fetch(&id).unwrap(), bytes);
+        assert_eq!(komodo.fetch(&other).unwrap(), b"komodo");
+
+        let hashes = manifest.block_hashes;
+        komodo.blocks_mut().delete(&hashes[0]).unwrap();
+        komodo.blocks_mut().put(&hashes[1], b"garbage").unwrap();
+        let status = komodo.audit(&id).unwrap();
+        assert_eq!(status.missing, vec![hashes[0].clone()]);
+        assert_eq!(status.corrupted, vec![hashes[1].clone()]);
+        assert_eq!(status.present, hashes[2..].to_vec());
+        assert!(status.is_complete());
+        assert_eq!(komodo.fetch(&id).unwrap(), bytes);
+
+        // NOTE: a block of another file is not a block of this one
+        let other_block = komodo
+            .blocks()
+            .get(&komodo.manifest(&other).unwrap().block_hashes[0]);
+        komodo
+            .blocks_mut()
+            .put(&hashes[2], &other_block.unwrap())
+            .unwrap();
+        let status = komodo.audit(&id).unwrap();
+        assert_eq!(status.corrupted, vec![hashes[1].clone(), hashes[2].clone()]);
+        assert_eq!(status.nb_needed, 1);
+        assert!(komodo.fetch(&id).is_err());
+
+        assert!(komodo.fetch(&FileId("unknown".to_string())).is_err());
+    }
+
+    #[test]
+    fn batch() {
+        let mut rng = test_rng();
+        let files: [&[u8]; 3] = [
+            include_bytes!("../assets/dragoon_32x32.png"),
+            b"komodo",
+            &[1, 2, 3, 4, 5],
+        ];
+
+        let scheme = S::new(Matrix::random(3, 5, &mut rng));
+        let setup = scheme.setup(files[0].len(), &mut rng).unwrap();
+        let mut komodo = Komodo::new(
+            scheme,
+            setup,
+            "BLS12-381",
+            MemoryStore::new(),
+            MemoryStore::new(),
+        )
+        .unwrap();
+
+        let ids = komodo.store_batch(&files).unwrap();
+        assert_eq!(ids.len(), files.len());
+        for (id, bytes) in ids.iter().zip(files) {
+            assert_eq!(komodo.manifest(id).unwrap().size, bytes.len());
+            assert_eq!(komodo.fetch(id).unwrap(), bytes);
+        }
+        assert_eq!(komodo.store(files[1]).unwrap(), ids[1]);
+    }
+
+    #[test]
+    fn setup_rotation() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        let scheme = S::new(Matrix::random(3, 5, &mut rng));
+        let old = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let new = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let mut komodo = Komodo::new(
+            scheme,
+            old.clone(),
+            "BLS12-381",
+            MemoryStore::new(),
+            MemoryStore::new(),
+        )
+        .unwrap();
+
+        let id = komodo.store(&bytes).unwrap();
+        let other = komodo.store(b"komodo").unwrap();
+        let old_manifest = komodo.manifest(&id).unwrap();
+
+        let mut ids = komodo.rotate_setup(new.clone()).unwrap();
+        ids.sort();
+        let mut expected = vec![id.clone(), other.clone()];
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        let manifest = komodo.manifest(&id).unwrap();
+        assert_ne!(manifest.setup_digest, old_manifest.setup_digest);
+        for hash in &old_manifest.block_hashes {
+            assert!(!komodo.blocks().contains(hash).unwrap());
+        }
+        assert!(komodo.audit(&id).unwrap().is_complete());
+        assert_eq!(komodo.fetch(&id).unwrap(), bytes);
+        assert_eq!(komodo.fetch(&other).unwrap(), b"komodo");
+
+        // NOTE: a file that cannot be fetched anymore leaves everything as it was
+        let manifest = komodo.manifest(&other).unwrap();
+        for hash in &manifest.block_hashes[..3] {
+            komodo.blocks_mut().delete(hash).unwrap();
+        }
+        let nb_blocks = komodo.blocks().list().unwrap().len();
+        assert!(komodo.rotate_setup(old).is_err());
+        assert_eq!(komodo.blocks().list().unwrap().len(), nb_blocks);
+        assert_eq!(komodo.fetch(&id).unwrap(), bytes);
+    }
+
+    #[test]
+    fn incompatible_configurations() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        let matrix = Matrix::random(3, 5, &mut rng);
+        let scheme = S::new(matrix.clone());
+        let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let mut komodo = Komodo::new(
+            scheme,
+            setup.clone(),
+            "BLS12-381",
+            MemoryStore::new(),
+            MemoryStore::new(),
+        )
+        .unwrap();
+        let id = komodo.store(&bytes).unwrap();
+
+        // NOTE: the same stores, with another curve
+        let (blocks, manifests) = (komodo.blocks().clone(), komodo.manifests().clone());
+        let other = Komodo::new(S::new(matrix), setup, "BN254", blocks, manifests).unwrap();
+        let err = other.fetch(&id).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<KomodoError>(),
+            Some(KomodoError::CurveMismatch(..))
+        ));
+
+        // NOTE: a block of another protocol among the blocks of the file
+        let mut foreign = MemoryStore::new();
+        let key = foreign
+            .dump_block(&42u64, &Header::new("kzg", "BLS12-381", &[]), Compress::Yes)
+            .unwrap();
+        let hashes = komodo.manifest(&id).unwrap().block_hashes;
+        komodo
+            .blocks_mut()
+            .put(&hashes[0], &foreign.get(&key).unwrap())
+            .unwrap();
+        let err = komodo.fetch(&id).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<KomodoError>(),
+            Some(&KomodoError::ProtocolMismatch(
+                "komodo".to_string(),
+                "kzg".to_string()
+            ))
+        );
+        assert!(komodo.audit(&id).is_err());
+    }
+}
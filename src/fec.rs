@@ -1,12 +1,29 @@
 //! a module to encode, recode and decode shards of data with FEC methods.
 
 use ark_ff::PrimeField;
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_std::rand::RngCore;
-
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use ark_std::{
+    cfg_chunks,
+    rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng},
+};
+use ark_std::{collections::BTreeMap, format, ops::Range, string::ToString, vec, vec::Vec};
+
+#[cfg(feature = "parallel")]
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use rayon::prelude::*;
 use rs_merkle::{algorithms::Sha256, Hasher};
 
-use crate::{algebra, algebra::linalg::Matrix, error::KomodoError};
+#[cfg(feature = "arbitrary")]
+use crate::fuzzing;
+use crate::{
+    algebra,
+    algebra::linalg::{self, Matrix, RowEchelon},
+    error::{DecodeContext, KomodoError},
+    telemetry::operation,
+};
 
 /// representation of a FEC shard of data.
 #[derive(Debug, Default, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
@@ -31,6 +48,33 @@ pub struct Shard<F: PrimeField> {
 }
 
 impl<F: PrimeField> Shard<F> {
+    /// a digest of the shard alone, i.e. the [`Sha256`] of its [`Shard::linear_combination`]
+    /// followed by its [`Shard::data`], serialized and compressed
+    ///
+    /// the digest of a shard can be kept next to it, e.g. in a manifest or in the message that
+    /// carries it, and checked with [`Shard::check_integrity`] to detect a shard that has been
+    /// corrupted on disk or in transit before trying to decode it.
+    ///
+    /// > **Note**
+    /// >
+    /// > the digest is not part of the shard itself, i.e. the serialization of the shards does not
+    /// > change. Unlike [`Shard::hash`], which is the same for all the shards of the same data, it
+    /// > is different for each shard, and unlike the proofs of the blocks it does not need any
+    /// > setup, but it does not protect against a malicious shard whose digest is forged too.
+    pub fn checksum(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.linear_combination
+            .serialize_compressed(&mut bytes)
+            .and_then(|_| self.data.serialize_compressed(&mut bytes))
+            .expect("serialization into a vector cannot fail");
+        Sha256::hash(&bytes).to_vec()
+    }
+
+    /// check that the shard still matches a digest computed with [`Shard::checksum`]
+    pub fn check_integrity(&self, checksum: &[u8]) -> bool {
+        self.checksum() == checksum
+    }
+
     /// compute the linear combination between two [`Shard`]s
     ///
     /// if we denote the [`Shard`] itself and the other [`Shard`] by $s$ and $o$ respectively, the
@@ -61,6 +105,87 @@ impl<F: PrimeField> Shard<F> {
             size: self.size,
         }
     }
+
+    /// the same as [`Shard::recode_with`], in place, i.e. without allocating a new [`Shard`]
+    ///
+    /// the [`Shard`] becomes $\alpha s + \beta o$.
+    pub fn recode_assign(&mut self, alpha: F, other: &Self, beta: F) {
+        for (l, r) in self
+            .linear_combination
+            .iter_mut()
+            .zip(other.linear_combination.iter())
+        {
+            *l = *l * alpha + *r * beta;
+        }
+        for (es, eo) in self.data.iter_mut().zip(other.data.iter()) {
+            *es = *es * alpha + *eo * beta;
+        }
+    }
+}
+
+/// the same as [`recode_with_coeffs`], accumulating the linear combination into an existing
+/// [`Shard`]
+///
+/// the buffers of `out` are reused, i.e. no memory is allocated once they are large enough, which
+/// allows to recode many shards in a row with a single output shard.
+///
+/// > **Note**
+/// >
+/// > returns `false`, and leaves `out` untouched, if the number of shards is not the same as the
+/// > number of coefficients or if no shards are provided.
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{algebra::linalg::Matrix, fec};
+///
+/// # fn main() {
+/// let mut rng = ark_std::test_rng();
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let shards = fec::encode::<F>(&bytes, &Matrix::random(3, 5, &mut rng)).unwrap();
+///
+/// let mut recoded = fec::Shard::default();
+/// for coeffs in [[1, 2, 3, 4, 5], [5, 4, 3, 2, 1]] {
+///     let coeffs = coeffs.map(F::from);
+///     assert!(fec::recode_with_coeffs_into(&shards, &coeffs, &mut recoded));
+///     assert_eq!(Some(&recoded), fec::recode_with_coeffs(&shards, &coeffs).as_ref());
+/// }
+/// # }
+/// ```
+pub fn recode_with_coeffs_into<F: PrimeField>(
+    shards: &[Shard<F>],
+    coeffs: &[F],
+    out: &mut Shard<F>,
+) -> bool {
+    if shards.len() != coeffs.len() || shards.is_empty() {
+        return false;
+    }
+
+    let first = &shards[0];
+    out.k = first.k;
+    out.size = first.size;
+    out.hash.clear();
+    out.hash.extend_from_slice(&first.hash);
+    out.linear_combination.clear();
+    out.linear_combination
+        .resize(first.linear_combination.len(), F::zero());
+    out.data.clear();
+    out.data.resize(first.data.len(), F::zero());
+
+    for (s, c) in shards.iter().zip(coeffs) {
+        for (o, l) in out
+            .linear_combination
+            .iter_mut()
+            .zip(s.linear_combination.iter())
+        {
+            *o += *l * c;
+        }
+        for (o, e) in out.data.iter_mut().zip(s.data.iter()) {
+            *o += *e * c;
+        }
+    }
+
+    true
 }
 
 /// compute the linear combination between an arbitrary number of [`Shard`]s
@@ -104,10 +229,64 @@ pub fn recode_with_coeffs<F: PrimeField>(shards: &[Shard<F>], coeffs: &[F]) -> O
 /// > **Note**
 /// >
 /// > this is a wrapper around [`recode_with_coeffs`].
+/// >
+/// > `rng` is subject to the [`crate::randomness::RandomnessPolicy`], see
+/// > [`recode_random_secure`].
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
 pub fn recode_random<F: PrimeField>(
     shards: &[Shard<F>],
     rng: &mut impl RngCore,
 ) -> Result<Option<Shard<F>>, KomodoError> {
+    crate::randomness::check("fec::recode_random")?;
+    Ok(recode_random_unchecked(shards, rng)?.map(|(shard, _)| shard))
+}
+
+/// the same as [`recode_random`], also returning the coefficients that have been drawn
+///
+/// the coefficients can be logged, e.g. to audit the recoding, and the recoded shard can be
+/// reproduced, and thus verified, later with [`recode_with_coeffs`].
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{algebra::linalg::Matrix, fec};
+///
+/// # fn main() {
+/// let mut rng = ark_std::test_rng();
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let shards = fec::encode::<F>(&bytes, &Matrix::random(3, 5, &mut rng)).unwrap();
+///
+/// let (recoded, coeffs) = fec::recode_random_with_coeffs(&shards, &mut rng)
+///     .unwrap()
+///     .unwrap();
+/// assert_eq!(fec::recode_with_coeffs(&shards, &coeffs), Some(recoded));
+/// # }
+/// ```
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+#[allow(clippy::type_complexity)]
+pub fn recode_random_with_coeffs<F: PrimeField>(
+    shards: &[Shard<F>],
+    rng: &mut impl RngCore,
+) -> Result<Option<(Shard<F>, Vec<F>)>, KomodoError> {
+    crate::randomness::check("fec::recode_random_with_coeffs")?;
+    recode_random_unchecked(shards, rng)
+}
+
+/// the same as [`recode_random`], with a cryptographically secure source of randomness
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn recode_random_secure<F: PrimeField>(
+    shards: &[Shard<F>],
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<Option<Shard<F>>, KomodoError> {
+    Ok(recode_random_unchecked(shards, rng)?.map(|(shard, _)| shard))
+}
+
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+#[allow(clippy::type_complexity)]
+fn recode_random_unchecked<F: PrimeField>(
+    shards: &[Shard<F>],
+    rng: &mut impl RngCore,
+) -> Result<Option<(Shard<F>, Vec<F>)>, KomodoError> {
     for (i, (s1, s2)) in shards.iter().zip(shards.iter().skip(1)).enumerate() {
         if s1.k != s2.k {
             return Err(KomodoError::IncompatibleShards(format!(
@@ -130,7 +309,14 @@ pub fn recode_random<F: PrimeField>(
     }
 
     let coeffs = shards.iter().map(|_| F::rand(rng)).collect::<Vec<_>>();
-    Ok(recode_with_coeffs(shards, &coeffs))
+    Ok(recode_with_coeffs(shards, &coeffs).map(|shard| (shard, coeffs)))
+}
+
+/// the hash of some data, as put in the [`Shard::hash`] of its shards by [`encode`]
+///
+/// this is the [`Sha256`] of the data, see [`encode_with_hasher`] for other algorithms.
+pub fn content_hash(data: &[u8]) -> Vec<u8> {
+    Sha256::hash(data).to_vec()
 }
 
 /// applies a given encoding matrix to some data to generate encoded shards
@@ -143,148 +329,1734 @@ pub fn recode_random<F: PrimeField>(
 /// Padding might be applied depending on the size of the data compared to the size of the encoding
 /// matrix. (see [`algebra::split_data_into_field_elements`])
 ///
+/// the shards are tagged with the [`content_hash`] of the data, see [`encode_with_hash`] to avoid
+/// hashing the data again when its hash is already known.
+///
 /// This is the inverse of [`decode`].
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
 pub fn encode<F: PrimeField>(
     data: &[u8],
     encoding_mat: &Matrix<F>,
 ) -> Result<Vec<Shard<F>>, KomodoError> {
-    let hash = Sha256::hash(data).to_vec();
+    encode_with_hash(data, encoding_mat, content_hash(data))
+}
+
+/// an explicit padding of the data, see [`encode_padded`]
+///
+/// the data is padded up to a whole number of rows of source elements, i.e. it is not padded
+/// again by [`algebra::split_data_into_field_elements`], in a way that can be checked and removed
+/// without knowing the [`Shard::size`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    /// the size of the data, as a `u64` in little endian, followed by the data and zeros
+    LengthPrefix,
+    /// the data followed by a single `0x80` byte and zeros, as in ISO/IEC 7816-4
+    Iso7816,
+}
+
+/// pad data for $k$ source shards, see [`Padding`]
+///
+/// # Panics
+/// if `k` is $0$.
+pub fn pad<F: PrimeField>(data: &[u8], k: usize, padding: Padding) -> Vec<u8> {
+    assert!(k > 0, "data should be padded for at least one source shard");
+    let bytes_per_row = k * ((F::MODULUS_BIT_SIZE as usize - 1) / 8);
+
+    let mut padded = match padding {
+        Padding::LengthPrefix => [&(data.len() as u64).to_le_bytes()[..], data].concat(),
+        Padding::Iso7816 => [data, &[0x80]].concat(),
+    };
+    padded.resize(padded.len().div_ceil(bytes_per_row) * bytes_per_row, 0);
+    padded
+}
+
+/// check and remove the padding of some data, see [`pad`]
+///
+/// [`KomodoError::InvalidPadding`] is returned if the data does not have the structure of the
+/// padding, e.g. if the declared size does not match or if the padding bytes are not zeros.
+pub fn unpad(padded: &[u8], padding: Padding) -> Result<Vec<u8>, KomodoError> {
+    match padding {
+        Padding::LengthPrefix => {
+            let Some((prefix, rest)) = padded.split_first_chunk::<8>() else {
+                return Err(KomodoError::InvalidPadding(format!(
+                    "no length prefix in {} bytes",
+                    padded.len()
+                )));
+            };
+            let size = u64::from_le_bytes(*prefix);
+            if size > rest.len() as u64 {
+                return Err(KomodoError::InvalidPadding(format!(
+                    "declared size {} larger than the {} bytes of data",
+                    size,
+                    rest.len()
+                )));
+            }
+            let (data, zeros) = rest.split_at(size as usize);
+            if zeros.iter().any(|&b| b != 0) {
+                return Err(KomodoError::InvalidPadding(format!(
+                    "non-zero padding after {} bytes of data",
+                    size
+                )));
+            }
+            Ok(data.to_vec())
+        }
+        Padding::Iso7816 => match padded.iter().rposition(|&b| b != 0) {
+            Some(i) if padded[i] == 0x80 => Ok(padded[..i].to_vec()),
+            _ => Err(KomodoError::InvalidPadding(
+                "no 0x80 byte at the end of the data".to_string(),
+            )),
+        },
+    }
+}
+
+/// the same as [`encode`] with an explicit [`Padding`] of the data
+///
+/// > **Note**
+/// >
+/// > the [`Shard::hash`] and [`Shard::size`] are the ones of the padded data.
+/// >
+/// > [`KomodoError::InvalidMatrixElements`] is returned if the encoding matrix has no row, i.e. if
+/// > there is no source shard to pad the data for.
+///
+/// This is the inverse of [`decode_padded`].
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{
+///     algebra::linalg::Matrix,
+///     fec::{self, Padding},
+/// };
+///
+/// # fn main() {
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let encoding_mat = Matrix::random(3, 5, &mut ark_std::test_rng());
+///
+/// let shards = fec::encode_padded::<F>(&bytes, &encoding_mat, Padding::Iso7816).unwrap();
+/// assert_eq!(fec::decode_padded(shards, Padding::Iso7816).unwrap(), bytes);
+/// # }
+/// ```
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_padded<F: PrimeField>(
+    data: &[u8],
+    encoding_mat: &Matrix<F>,
+    padding: Padding,
+) -> Result<Vec<Shard<F>>, KomodoError> {
+    if encoding_mat.height == 0 {
+        return Err(KomodoError::InvalidMatrixElements(
+            "the encoding matrix should have at least one row to pad the data".to_string(),
+        ));
+    }
+    encode(&pad::<F>(data, encoding_mat.height, padding), encoding_mat)
+}
+
+/// the same as [`encode`] with the data hashed by any [`Hasher`] instead of [`Sha256`]
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_with_hasher<F: PrimeField, H: Hasher>(
+    data: &[u8],
+    encoding_mat: &Matrix<F>,
+) -> Result<Vec<Shard<F>>, KomodoError> {
+    encode_with_hash(data, encoding_mat, H::hash(data).into())
+}
+
+/// the same as [`encode`] with a precomputed hash of the data, which is not hashed again
+///
+/// this allows to hash large data only once across a whole pipeline, e.g. with a streaming hasher
+/// while the data is read from disk.
+///
+/// > **Note**
+/// >
+/// > the hash is trusted as is, it is up to the caller to make sure it is the hash of `data`,
+/// > e.g. its [`content_hash`].
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_with_hash<F: PrimeField>(
+    data: &[u8],
+    encoding_mat: &Matrix<F>,
+    hash: Vec<u8>,
+) -> Result<Vec<Shard<F>>, KomodoError> {
+    let op = operation!(
+        "encode",
+        "fec",
+        bytes = data.len(),
+        k = encoding_mat.height,
+        n = encoding_mat.width
+    );
+
+    let elements = algebra::split_data_into_field_elements(data, encoding_mat.height);
+    op.done(encode_rows(&elements, encoding_mat, hash, data.len()))
+}
 
+/// encode source elements, whose number is a multiple of $k$, row by row
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+fn encode_rows<F: PrimeField>(
+    elements: &[F],
+    encoding_mat: &Matrix<F>,
+    hash: Vec<u8>,
+    size: usize,
+) -> Result<Vec<Shard<F>>, KomodoError> {
     let k = encoding_mat.height;
 
-    let source_shards = Matrix::from_vec_vec(
-        algebra::split_data_into_field_elements(data, k)
-            .chunks(k)
-            .map(|c| c.to_vec())
-            .collect(),
-    )?;
+    let source_shards = Matrix::from_vec_vec(elements.chunks(k).map(|c| c.to_vec()).collect())?;
 
-    Ok(source_shards
-        .mul(encoding_mat)?
-        .transpose()
-        .elements
-        .chunks(source_shards.height)
+    let shards = source_shards.mul(encoding_mat)?.transpose().elements;
+
+    Ok(cfg_chunks!(shards, source_shards.height)
         .enumerate()
         .map(|(j, s)| Shard {
             k: k as u32,
             linear_combination: encoding_mat.get_col(j).unwrap(),
             hash: hash.clone(),
             data: s.to_vec(),
-            size: data.len(),
+            size,
         })
         .collect())
 }
 
-/// reconstruct the original data from a set of encoded, possibly recoded, shards
+/// the [`Shard::hash`] of the shards of [`encode_elements`], i.e. the [`content_hash`] of the
+/// compressed serialization of the elements
+pub fn elements_hash<F: PrimeField>(elements: &[F]) -> Vec<u8> {
+    let mut bytes = vec![];
+    elements
+        .serialize_compressed(&mut bytes)
+        .expect("serialization into a vector cannot fail");
+    content_hash(&bytes)
+}
+
+/// encode field elements directly, without going through bytes
+///
+/// this is the same as [`encode`] for applications that already hold field elements, e.g. the
+/// outputs of another protocol, which do not need to be converted to bytes and back. The elements
+/// are the source elements themselves, row after row.
 ///
 /// > **Note**
 /// >
-/// > this function might fail in a variety of cases
-/// > - if there are too few shards
-/// > - if there are linear dependencies between shards
+/// > the [`Shard::size`] of the shards is the number of elements and the last row is padded with
+/// > zeros if the number of elements is not a multiple of $k$, which [`decode_elements`] removes.
 ///
-/// This is the inverse of [`encode`].
-pub fn decode<F: PrimeField>(shards: Vec<Shard<F>>) -> Result<Vec<u8>, KomodoError> {
-    if shards.is_empty() {
-        return Err(KomodoError::TooFewShards(0, 0));
+/// This is the inverse of [`decode_elements`].
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{algebra::linalg::Matrix, fec};
+///
+/// # fn main() {
+/// let elements = (0..100u64).map(F::from).collect::<Vec<_>>();
+/// let shards = fec::encode_elements(&elements, &Matrix::random(3, 5, &mut ark_std::test_rng()))
+///     .unwrap();
+///
+/// assert_eq!(fec::decode_elements(&shards[2..]).unwrap(), elements);
+/// # }
+/// ```
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_elements<F: PrimeField>(
+    elements: &[F],
+    encoding_mat: &Matrix<F>,
+) -> Result<Vec<Shard<F>>, KomodoError> {
+    let op = operation!(
+        "encode_elements",
+        "fec",
+        elements = elements.len(),
+        k = encoding_mat.height,
+        n = encoding_mat.width
+    );
+
+    let k = encoding_mat.height;
+    let mut source_elements = elements.to_vec();
+    source_elements.resize(elements.len().div_ceil(k) * k, F::zero());
+
+    op.done(encode_rows(
+        &source_elements,
+        encoding_mat,
+        elements_hash(elements),
+        elements.len(),
+    ))
+}
+
+/// the same as [`encode`], yielding the shards one at a time
+///
+/// only the data, as field elements, and the shard being computed are held in memory, instead of
+/// all the $n$ shards at once, which allows to encode large data and, e.g., send each shard to its
+/// storage node before the next one is computed.
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_iter<'a, F: PrimeField>(
+    data: &[u8],
+    encoding_mat: &'a Matrix<F>,
+) -> impl Iterator<Item = Shard<F>> + 'a {
+    let k = encoding_mat.height;
+    let hash = content_hash(data);
+    let size = data.len();
+    let elements = algebra::split_data_into_field_elements::<F>(data, k);
+
+    (0..encoding_mat.width).map(move |j| {
+        let linear_combination = encoding_mat.get_col(j).unwrap();
+        let data = cfg_chunks!(elements, k)
+            .map(|row| {
+                row.iter()
+                    .zip(&linear_combination)
+                    .map(|(x, a)| *x * a)
+                    .sum()
+            })
+            .collect();
+
+        Shard {
+            k: k as u32,
+            linear_combination,
+            hash: hash.clone(),
+            data,
+            size,
+        }
+    })
+}
+
+/// the same as [`encode`], with the data read from a reader instead of a slice of bytes
+///
+/// the data is read, hashed and encoded by batches of rows of at most about $1$ MiB, i.e. the
+/// whole data is never held in memory, only the shards being built, which allows to encode a
+/// file or a socket directly. The shards are exactly the ones of [`encode`].
+///
+/// > **Note**
+/// >
+/// > the $n$ shards are still returned at once, see [`encode_chunks`] to encode large data in
+/// > independent chunks with a bounded memory.
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{algebra::linalg::Matrix, fec};
+///
+/// # fn main() {
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let encoding_mat = Matrix::random(3, 5, &mut ark_std::test_rng());
+///
+/// let shards = fec::encode_from_reader::<F, _>(&bytes[..], &encoding_mat).unwrap();
+/// assert_eq!(shards, fec::encode(&bytes, &encoding_mat).unwrap());
+/// # }
+/// ```
+#[cfg(feature = "std")]
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_from_reader<F: PrimeField, R: std::io::Read>(
+    reader: R,
+    encoding_mat: &Matrix<F>,
+) -> Result<Vec<Shard<F>>, KomodoError> {
+    use sha2::Digest;
+
+    let op = operation!(
+        "encode_from_reader",
+        "fec",
+        k = encoding_mat.height,
+        n = encoding_mat.width,
+        bytes = tracing::field::Empty
+    );
+
+    let (k, n) = (encoding_mat.height, encoding_mat.width);
+    // NOTE: the batches are made of whole rows, so that only the last one is padded
+    let bytes_per_row = k * ((F::MODULUS_BIT_SIZE as usize - 1) / 8);
+    let batch_size = (BATCH_SIZE / bytes_per_row).max(1) * bytes_per_row;
+
+    let mut hasher = sha2::Sha256::new();
+    let mut size = 0;
+    let mut shards = vec![vec![]; n];
+    for batch in Chunks::new(reader, batch_size) {
+        let batch = batch?;
+        hasher.update(&batch);
+        size += batch.len();
+
+        let source_shards = Matrix::from_vec_vec(
+            algebra::split_data_into_field_elements(&batch, k)
+                .chunks(k)
+                .map(|c| c.to_vec())
+                .collect(),
+        )?;
+        let encoded = source_shards.mul(encoding_mat)?.transpose().elements;
+        for (shard, s) in shards.iter_mut().zip(encoded.chunks(source_shards.height)) {
+            shard.extend_from_slice(s);
+        }
     }
+    let hash = hasher.finalize().to_vec();
 
-    let k = shards[0].k;
-    let np = shards.len();
+    if size == 0 {
+        // NOTE: empty data fails exactly as with `encode`
+        return encode_with_hash(&[], encoding_mat, hash);
+    }
+
+    op.record("bytes", size);
+    op.done(Ok(shards
+        .into_iter()
+        .enumerate()
+        .map(|(j, data)| Shard {
+            k: k as u32,
+            linear_combination: encoding_mat.get_col(j).unwrap(),
+            hash: hash.clone(),
+            data,
+            size,
+        })
+        .collect()))
+}
+
+/// a rateless generator of shards, i.e. a fountain code
+///
+/// instead of a fixed number $n$ of shards, a [`Fountain`] produces an unbounded stream of shards
+/// on demand, each one being a random linear combination of the $k$ source shards, which fits the
+/// dissemination to an unknown number of receivers. Any $k$ shards of the stream can be decoded
+/// with [`decode`], with an overwhelming probability on the large fields of Komodo.
+///
+/// the coefficients are drawn from a [`StdRng`] seeded with the seed of the fountain, i.e. two
+/// fountains with the same data and the same seed produce the same stream.
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::fec::{self, Fountain};
+///
+/// # fn main() {
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+///
+/// let mut fountain = Fountain::<F>::new(&bytes, 3, 42);
+/// let shards = fountain.by_ref().skip(1000).take(3).collect();
+/// assert_eq!(fec::decode(shards).unwrap(), bytes);
+/// # }
+/// ```
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub struct Fountain<F: PrimeField> {
+    k: usize,
+    elements: Vec<F>,
+    hash: Vec<u8>,
+    size: usize,
+    rng: StdRng,
+}
 
-    if np < k as usize {
-        return Err(KomodoError::TooFewShards(np, k as usize));
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+impl<F: PrimeField> Fountain<F> {
+    /// build a fountain for some data, split into $k$ source shards
+    pub fn new(data: &[u8], k: usize, seed: u64) -> Self {
+        Self {
+            k,
+            elements: algebra::split_data_into_field_elements(data, k),
+            hash: content_hash(data),
+            size: data.len(),
+            rng: StdRng::seed_from_u64(seed),
+        }
     }
 
-    let encoding_mat = Matrix::from_vec_vec(
-        shards
-            .iter()
-            .map(|b| b.linear_combination.clone())
-            .collect(),
-    )?
-    .truncate(Some(np - k as usize), None);
+    /// produce the next shard of the stream
+    pub fn next_shard(&mut self) -> Shard<F> {
+        let linear_combination = (0..self.k)
+            .map(|_| F::rand(&mut self.rng))
+            .collect::<Vec<_>>();
+        let data = cfg_chunks!(self.elements, self.k)
+            .map(|row| {
+                row.iter()
+                    .zip(&linear_combination)
+                    .map(|(x, a)| *x * a)
+                    .sum()
+            })
+            .collect();
 
-    let shard_mat = Matrix::from_vec_vec(
-        shards
-            .iter()
-            .take(k as usize)
-            .map(|b| b.data.clone())
-            .collect(),
-    )?;
+        Shard {
+            k: self.k as u32,
+            linear_combination,
+            hash: self.hash.clone(),
+            data,
+            size: self.size,
+        }
+    }
+}
 
-    let source_shards = encoding_mat.invert()?.mul(&shard_mat)?.transpose().elements;
+/// the stream never ends, i.e. [`Iterator::next`] always returns a shard
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+impl<F: PrimeField> Iterator for Fountain<F> {
+    type Item = Shard<F>;
 
-    let mut bytes = algebra::merge_elements_into_bytes(&source_shards);
-    bytes.resize(shards[0].size, 0);
-    Ok(bytes)
+    fn next(&mut self) -> Option<Shard<F>> {
+        Some(self.next_shard())
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use ark_bls12_381::Fr;
-    use ark_ff::PrimeField;
+/// encode data into $n$ shards with a Reed-Solomon code evaluated by FFT
+///
+/// each row of $k$ source elements is seen as the coefficients of a polynomial, which is evaluated
+/// on the first $n$ points of a radix-2 evaluation domain in $O(n \log n)$ instead of the
+/// $O(kn)$ of a dense matrix multiplication. The shards are exactly the ones of [`encode`] with
+/// the Vandermonde matrix of these points, i.e. their linear combinations are the powers of the
+/// points and they can be decoded, recoded and proven as any other shards.
+///
+/// > **Note**
+/// >
+/// > $k$ should not be greater than $n$ and the field should have a radix-2 evaluation domain of
+/// > size at least $n$, otherwise [`KomodoError::InvalidEvaluationDomain`] is returned.
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_fft<F: PrimeField>(
+    data: &[u8],
+    k: usize,
+    n: usize,
+) -> Result<Vec<Shard<F>>, KomodoError> {
+    let op = operation!("encode_fft", "fec", bytes = data.len(), k = k, n = n);
 
-    use crate::{
-        algebra,
-        algebra::linalg::Matrix,
-        fec::{decode, encode, recode_random, Shard},
-    };
+    let domain = Radix2EvaluationDomain::<F>::new(n)
+        .filter(|_| k > 0 && k <= n)
+        .ok_or(KomodoError::InvalidEvaluationDomain(k, n))?;
 
-    use itertools::Itertools;
-    use rand::seq::SliceRandom;
+    let hash = content_hash(data);
 
-    use super::recode_with_coeffs;
+    let elements = algebra::split_data_into_field_elements::<F>(data, k);
+    let evaluations = cfg_chunks!(elements, k)
+        .map(|row| domain.fft(row))
+        .collect::<Vec<_>>();
 
-    type LC = Vec<usize>;
-    type LCExclusion = Vec<usize>;
+    op.done(Ok(domain
+        .elements()
+        .take(n)
+        .enumerate()
+        .map(|(j, point)| Shard {
+            k: k as u32,
+            linear_combination: (0..k as u64).map(|i| point.pow([i])).collect(),
+            hash: hash.clone(),
+            data: evaluations.iter().map(|e| e[j]).collect(),
+            size: data.len(),
+        })
+        .collect()))
+}
 
-    fn bytes() -> Vec<u8> {
-        include_bytes!("../assets/dragoon_32x32.png").to_vec()
-    }
+/// the same as [`encode`] with the _systematic_ form of the encoding matrix, see
+/// [`Matrix::systematic`]
+///
+/// the first $k$ shards are verbatim copies of the source shards, i.e. of the data, and the
+/// $n - k$ other ones are parity shards. Decoding from the $k$ first shards does not need any
+/// matrix inversion, see [`decode`], and, with a Vandermonde matrix, this is a classic systematic
+/// Reed-Solomon code.
+///
+/// > **Note**
+/// >
+/// > the first $k$ columns of the encoding matrix should be linearly independent.
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_systematic<F: PrimeField>(
+    data: &[u8],
+    encoding_mat: &Matrix<F>,
+) -> Result<Vec<Shard<F>>, KomodoError> {
+    encode(data, &encoding_mat.systematic()?)
+}
 
-    fn to_curve<F: PrimeField>(n: u128) -> F {
-        F::from_le_bytes_mod_order(&n.to_le_bytes())
+/// the position of the source shard that a shard is a verbatim copy of, if any, i.e. the position
+/// of the only non-zero coefficient of its linear combination when that coefficient is $1$
+fn systematic_position<F: PrimeField>(shard: &Shard<F>) -> Option<usize> {
+    let mut non_zeros = shard
+        .linear_combination
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| !x.is_zero());
+    match (non_zeros.next(), non_zeros.next()) {
+        (Some((i, x)), None) if x.is_one() => Some(i),
+        _ => None,
     }
+}
 
-    /// `contains_one_of(x, set)` is true iif `x` fully contains one of the lists from `set`
-    ///
-    /// > **Note**  
-    /// >
-    /// > see [`containment`] for some example
-    fn contains_one_of(x: &[usize], set: &[Vec<usize>]) -> bool {
-        set.iter().any(|y| y.iter().all(|z| x.contains(z)))
-    }
+/// find a maximal set of linearly independent shards, in order, from their linear combinations
+pub(crate) fn decode_context<F: PrimeField>(shards: &[Shard<F>]) -> DecodeContext {
+    let mut basis = RowEchelon::new();
+    let mut context = DecodeContext {
+        hash: shards.first().map(|s| s.hash.clone()).unwrap_or_default(),
+        ..Default::default()
+    };
 
-    #[test]
-    fn containment() {
-        assert!(contains_one_of(&[1, 2, 3], &[vec![1, 2, 3]]));
-        assert!(contains_one_of(
-            &[3, 6, 8],
-            &[vec![2, 4, 6], vec![1, 3, 7], vec![6, 7, 8], vec![3, 6, 8]],
-        ));
-        assert!(!contains_one_of(
-            &[1, 6, 8],
-            &[vec![2, 4, 6], vec![1, 3, 7], vec![6, 7, 8], vec![3, 6, 8]],
-        ));
-        assert!(contains_one_of(
-            &[3, 6, 8, 9, 10],
-            &[vec![2, 4, 6], vec![1, 3, 7], vec![6, 7, 8], vec![3, 6, 8]],
-        ));
-        assert!(contains_one_of(&[0, 4, 5], &[vec![2, 3, 5], vec![4, 5]]));
+    for (i, shard) in shards.iter().enumerate() {
+        if basis.insert(&shard.linear_combination) {
+            context.independent.push(i);
+        } else {
+            context.dependent.push(i);
+        }
     }
 
-    fn try_all_decoding_combinations<F: PrimeField>(
-        data: &[u8],
-        shards: &[Shard<F>],
-        k: usize,
-        n: usize,
-        test_case: &str,
-        limit: Option<usize>,
-        should_not_be_decodable: Vec<LCExclusion>,
-    ) {
-        let there_are_recoded_shards = shards.len() > n;
+    context
+}
 
-        for c in shards
+/// the rank of some shards, i.e. the number of linearly independent shards among them
+///
+/// this only looks at the linear combinations of the shards, i.e. it is much cheaper than trying
+/// to [`decode`] them.
+pub fn rank_of<F: PrimeField>(shards: &[Shard<F>]) -> usize {
+    decode_context(shards).rank()
+}
+
+/// whether some shards are enough to [`decode`], i.e. whether $k$ of them are linearly independent
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{algebra::linalg::Matrix, fec};
+///
+/// # fn main() {
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let shards = fec::encode::<F>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+///
+/// let mut pool = vec![shards[0].clone(), shards[0].clone(), shards[1].clone()];
+/// assert_eq!(fec::rank_of(&pool), 2);
+/// assert!(!fec::is_decodable(&pool));
+///
+/// assert!(!fec::increases_rank(&pool, &shards[1]));
+/// assert!(fec::increases_rank(&pool, &shards[4]));
+/// pool.push(shards[4].clone());
+/// assert!(fec::is_decodable(&pool));
+/// # }
+/// ```
+pub fn is_decodable<F: PrimeField>(shards: &[Shard<F>]) -> bool {
+    shards
+        .first()
+        .is_some_and(|s| rank_of(shards) >= s.k as usize)
+}
+
+/// whether a shard would increase the rank of some shards, i.e. whether it would be useful to
+/// [`decode`] them, see [`rank_of`]
+///
+/// > **Note**
+/// >
+/// > a shard whose linear combination does not have as many coefficients as the ones of the
+/// > other shards never increases their rank.
+pub fn increases_rank<F: PrimeField>(shards: &[Shard<F>], shard: &Shard<F>) -> bool {
+    let width = shard.linear_combination.len();
+    if shards.iter().any(|s| s.linear_combination.len() != width) {
+        return false;
+    }
+
+    let mut basis = RowEchelon::new();
+    for s in shards {
+        basis.insert(&s.linear_combination);
+    }
+    basis.insert(&shard.linear_combination)
+}
+
+/// reconstruct the original data from a set of encoded, possibly recoded, shards
+///
+/// > **Note**
+/// >
+/// > this function might fail in a variety of cases
+/// > - if there are too few shards, see [`KomodoError::TooFewShards`]
+/// > - if there are linear dependencies between shards such that less than $k$ of them are
+/// >   linearly independent, see [`KomodoError::LinearlyDependentShards`]
+/// > - if the shards claim to have no source shard at all, i.e. $k = 0$, see
+/// >   [`KomodoError::IncompatibleShards`]
+/// >
+/// > in the first two cases, the error tells which shards are useful and how many more are needed.
+/// >
+/// > if more than $k$ shards are given, the first $k$ linearly independent ones are used, i.e. the
+/// > decoding succeeds as soon as any $k$ of the shards are linearly independent, wherever they
+/// > are in the input.
+/// >
+/// > when these $k$ shards are copies of the source shards, e.g. the first $k$ shards of
+/// > [`encode_systematic`], they are simply put back in order without any matrix inversion.
+///
+/// This is the inverse of [`encode`].
+pub fn decode<F: PrimeField>(shards: Vec<Shard<F>>) -> Result<Vec<u8>, KomodoError> {
+    let op = operation!(
+        "decode",
+        "fec",
+        shards = shards.len(),
+        bytes = tracing::field::Empty
+    );
+
+    let shards = independent_shards(&shards)?;
+    let source_shards =
+        Decoding::new(&shards)?.source_elements(&shards, 0..shards[0].data.len())?;
+
+    let mut bytes = algebra::merge_elements_into_bytes(&source_shards);
+    bytes.resize(shards[0].size, 0);
+    op.record("bytes", bytes.len());
+    op.done(Ok(bytes))
+}
+
+/// the first $k$ linearly independent shards, see [`decode`] for the errors
+pub(crate) fn independent_shards<F: PrimeField>(
+    shards: &[Shard<F>],
+) -> Result<Vec<&Shard<F>>, KomodoError> {
+    if shards.is_empty() {
+        return Err(KomodoError::TooFewShards(0, 0, DecodeContext::default()));
+    }
+
+    let k = shards[0].k as usize;
+    if k == 0 {
+        return Err(KomodoError::IncompatibleShards(
+            "shards should have at least one source shard, found k = 0".to_string(),
+        ));
+    }
+    let np = shards.len();
+    if np < k {
+        return Err(KomodoError::TooFewShards(np, k, decode_context(shards)));
+    }
+
+    let rows: Vec<Vec<F>> = shards
+        .iter()
+        .map(|s| s.linear_combination.clone())
+        .collect();
+    let indices = linalg::independent_subset(&rows, k);
+    if indices.len() < k {
+        return Err(KomodoError::LinearlyDependentShards(
+            k,
+            decode_context(shards),
+        ));
+    }
+
+    Ok(indices.into_iter().map(|i| &shards[i]).collect())
+}
+
+/// how to get the source elements back from $k$ linearly independent shards
+pub(crate) enum Decoding<F: PrimeField> {
+    /// the shards are copies of the source shards, at these positions
+    Systematic(Vec<usize>),
+    /// the inverse of the matrix of the linear combinations of the shards
+    Inverse(Matrix<F>),
+}
+
+impl<F: PrimeField> Decoding<F> {
+    pub(crate) fn new(shards: &[&Shard<F>]) -> Result<Self, KomodoError> {
+        let positions = shards
+            .iter()
+            .map(|s| systematic_position(s).filter(|&i| i < shards.len()))
+            .collect::<Option<Vec<_>>>();
+        if let Some(positions) = positions {
+            return Ok(Self::Systematic(positions));
+        }
+
+        let encoding_mat = Matrix::from_vec_vec(
+            shards
+                .iter()
+                .map(|b| b.linear_combination.clone())
+                .collect(),
+        )?;
+        Ok(Self::Inverse(encoding_mat.invert()?))
+    }
+
+    /// the source elements of some rows of the data, row after row
+    ///
+    /// it is an error if one of the shards does not have all the rows.
+    pub(crate) fn source_elements(
+        &self,
+        shards: &[&Shard<F>],
+        rows: Range<usize>,
+    ) -> Result<Vec<F>, KomodoError> {
+        let rows_of = |shard: &Shard<F>| {
+            shard
+                .data
+                .get(rows.clone())
+                .map(|r| r.to_vec())
+                .ok_or_else(|| {
+                    KomodoError::IncompatibleShards(format!(
+                        "shard has {} elements, rows {:?} are missing",
+                        shard.data.len(),
+                        rows
+                    ))
+                })
+        };
+
+        match self {
+            Self::Systematic(positions) => {
+                // NOTE: the shards are linearly independent, i.e. the positions are all distinct
+                let mut source_shards = vec![vec![]; shards.len()];
+                for (shard, &i) in shards.iter().zip(positions) {
+                    source_shards[i] = rows_of(shard)?;
+                }
+                Ok(Matrix::from_vec_vec(source_shards)?.transpose().elements)
+            }
+            Self::Inverse(inverse) => {
+                let shard_mat = Matrix::from_vec_vec(
+                    shards
+                        .iter()
+                        .map(|b| rows_of(b))
+                        .collect::<Result<_, _>>()?,
+                )?;
+                Ok(inverse.mul(&shard_mat)?.transpose().elements)
+            }
+        }
+    }
+}
+
+/// the number of bytes decoded or encoded at once by [`decode_into`] and [`encode_from_reader`]
+#[cfg(feature = "std")]
+const BATCH_SIZE: usize = 1 << 20;
+
+/// reconstruct the original data from a set of shards, as [`decode`] does, and write it into a
+/// writer
+///
+/// the data is decoded and written by batches of rows of at most about $1$ MiB, i.e. the whole
+/// output is never allocated, which allows to decode very large data to disk. The number of bytes
+/// written is returned.
+///
+/// > **Note**
+/// >
+/// > this fails exactly as [`decode`] does, in which case nothing has been written, or with the
+/// > I/O error of the writer, in which case part of the data might have been written already.
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{algebra::linalg::Matrix, fec};
+///
+/// # fn main() {
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let shards = fec::encode::<F>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+///
+/// let mut decoded = vec![];
+/// assert_eq!(fec::decode_into(&shards[2..], &mut decoded).unwrap(), bytes.len());
+/// assert_eq!(decoded, bytes);
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn decode_into<F: PrimeField, W: std::io::Write>(
+    shards: &[Shard<F>],
+    mut writer: W,
+) -> Result<usize, KomodoError> {
+    let op = operation!("decode_into", "fec", shards = shards.len());
+
+    let shards = independent_shards(shards)?;
+    let decoding = Decoding::new(&shards)?;
+
+    let (k, size, nb_rows) = (shards[0].k as usize, shards[0].size, shards[0].data.len());
+    let bytes_per_row = k * ((F::MODULUS_BIT_SIZE as usize - 1) / 8);
+    let rows_per_batch = (BATCH_SIZE / bytes_per_row).max(1);
+
+    let mut written = 0;
+    for start in (0..nb_rows).step_by(rows_per_batch) {
+        if written >= size {
+            break;
+        }
+        let rows = start..(start + rows_per_batch).min(nb_rows);
+        let bytes = algebra::merge_elements_into_bytes(&decoding.source_elements(&shards, rows)?);
+        let bytes = &bytes[..bytes.len().min(size - written)];
+        writer.write_all(bytes)?;
+        written += bytes.len();
+    }
+    if written < size {
+        // NOTE: the shards are too short for the size of the data, which decode pads with zeros
+        let padding = (size - written) as u64;
+        std::io::copy(
+            &mut std::io::Read::take(std::io::repeat(0), padding),
+            &mut writer,
+        )?;
+        written = size;
+    }
+
+    op.done(Ok(written))
+}
+
+/// reconstruct the original data from a set of shards built with [`encode_padded`] and check its
+/// padding
+///
+/// > **Note**
+/// >
+/// > this fails exactly as [`decode`] does, and with [`KomodoError::InvalidPadding`] if the size
+/// > of the shards is not a whole number of rows or if the padding is invalid, see [`unpad`].
+///
+/// This is the inverse of [`encode_padded`].
+pub fn decode_padded<F: PrimeField>(
+    shards: Vec<Shard<F>>,
+    padding: Padding,
+) -> Result<Vec<u8>, KomodoError> {
+    let (k, size) = shards
+        .first()
+        .map(|s| (s.k as usize, s.size))
+        .unwrap_or_default();
+    let padded = decode(shards)?;
+
+    let bytes_per_row = k * ((F::MODULUS_BIT_SIZE as usize - 1) / 8);
+    if size % bytes_per_row != 0 {
+        return Err(KomodoError::InvalidPadding(format!(
+            "size {} is not a whole number of rows of {} bytes",
+            size, bytes_per_row
+        )));
+    }
+
+    unpad(&padded, padding)
+}
+
+/// reconstruct the original field elements from a set of shards built with [`encode_elements`]
+///
+/// > **Note**
+/// >
+/// > this fails exactly as [`decode`] does.
+///
+/// This is the inverse of [`encode_elements`].
+pub fn decode_elements<F: PrimeField>(shards: &[Shard<F>]) -> Result<Vec<F>, KomodoError> {
+    let op = operation!("decode_elements", "fec", shards = shards.len());
+
+    let shards = independent_shards(shards)?;
+    let mut elements = Decoding::new(&shards)?.source_elements(&shards, 0..shards[0].data.len())?;
+    elements.truncate(shards[0].size);
+
+    op.done(Ok(elements))
+}
+
+/// regenerate the shard of a given linear combination from any $k$ valid shards, e.g. to replace
+/// a lost shard of a storage system
+///
+/// the coefficients $c$ such that the target linear combination $t$ is $c \cdot L$, where $L$ is
+/// the matrix of the linear combinations of $k$ independent shards, are solved for and the new
+/// shard is the same combination of the shards, i.e. the original data is never reconstructed nor
+/// returned to the caller.
+///
+/// > **Note**
+/// >
+/// > this fails exactly as [`decode`] does and with [`KomodoError::IncompatibleShards`] if the
+/// > target does not have $k$ coefficients.
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{algebra::linalg::Matrix, fec};
+///
+/// # fn main() {
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let points = (1..=5).map(F::from).collect::<Vec<_>>();
+/// let shards = fec::encode::<F>(&bytes, &Matrix::vandermonde(&points, 3).unwrap()).unwrap();
+///
+/// let lost = &shards[1];
+/// let repaired = fec::repair(&shards[2..], &lost.linear_combination).unwrap();
+/// assert_eq!(&repaired, lost);
+/// # }
+/// ```
+pub fn repair<F: PrimeField>(shards: &[Shard<F>], target: &[F]) -> Result<Shard<F>, KomodoError> {
+    let op = operation!("repair", "fec", shards = shards.len());
+
+    let shards = independent_shards(shards)?;
+    let k = shards[0].k as usize;
+    if target.len() != k {
+        return Err(KomodoError::IncompatibleShards(format!(
+            "expected {} coefficients in the target linear combination, found {}",
+            k,
+            target.len()
+        )));
+    }
+
+    let linear_combinations = Matrix::from_vec_vec(
+        shards
+            .iter()
+            .map(|s| s.linear_combination.clone())
+            .collect(),
+    )?;
+    let coeffs = Matrix::from_vec_vec(vec![target.to_vec()])?
+        .mul(&linear_combinations.invert()?)?
+        .elements;
+
+    let shards = shards.into_iter().cloned().collect::<Vec<_>>();
+    // NOTE: there are as many shards as coefficients and at least one of each
+    op.done(Ok(recode_with_coeffs(&shards, &coeffs).unwrap()))
+}
+
+/// reconstruct only a range of bytes of the original data, e.g. to access a large object randomly
+///
+/// only the rows of source elements that cover the range are decoded, i.e. the cost is
+/// proportional to the size of the range instead of the size of the data. This fails exactly as
+/// [`decode`] does and with [`KomodoError::InvalidByteRange`] if the range is not in the data.
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{algebra::linalg::Matrix, fec};
+///
+/// # fn main() {
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let shards = fec::encode::<F>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+///
+/// assert_eq!(fec::decode_range(&shards[2..], 1000..1234).unwrap(), &bytes[1000..1234]);
+/// # }
+/// ```
+pub fn decode_range<F: PrimeField>(
+    shards: &[Shard<F>],
+    range: Range<usize>,
+) -> Result<Vec<u8>, KomodoError> {
+    let op = operation!(
+        "decode_range",
+        "fec",
+        shards = shards.len(),
+        bytes = range.len()
+    );
+
+    let shards = independent_shards(shards)?;
+    let (k, size) = (shards[0].k as usize, shards[0].size);
+    if range.start > range.end || range.end > size {
+        return Err(KomodoError::InvalidByteRange(range.start, range.end, size));
+    }
+    if range.is_empty() {
+        return op.done(Ok(vec![]));
+    }
+
+    let bytes_per_element = (F::MODULUS_BIT_SIZE as usize - 1) / 8;
+    let nb_rows = shards[0].data.len();
+    // NOTE: the size comes from the shards, i.e. the range might go past the data, which is then
+    // padded with zeros, as in [`decode`]
+    let first_row = (range.start / bytes_per_element / k).min(nb_rows);
+    let last_row = ((range.end - 1) / bytes_per_element / k).min(nb_rows.saturating_sub(1));
+    let source_shards = Decoding::new(&shards)?
+        .source_elements(&shards, first_row..(last_row + 1).max(first_row))?;
+
+    let offset = first_row * k * bytes_per_element;
+    let mut bytes = algebra::merge_elements_into_bytes(&source_shards);
+    bytes.resize(range.end - offset, 0);
+    op.done(Ok(bytes.split_off(range.start - offset)))
+}
+
+/// the evaluation points of some shards of a Reed-Solomon code, i.e. shards whose linear
+/// combinations are the successive powers of distinct points, if any
+fn reed_solomon_points<F: PrimeField>(shards: &[Shard<F>], k: usize) -> Option<Vec<F>> {
+    if k < 2 {
+        return None;
+    }
+
+    let mut points: Vec<F> = Vec::with_capacity(shards.len());
+    for shard in shards {
+        let x = *shard.linear_combination.get(1)?;
+        let mut power = F::one();
+        for c in &shard.linear_combination {
+            if *c != power {
+                return None;
+            }
+            power *= x;
+        }
+        if shard.linear_combination.len() != k || points.contains(&x) {
+            return None;
+        }
+        points.push(x);
+    }
+
+    Some(points)
+}
+
+/// evaluate a polynomial, given by its coefficients, at some point
+fn evaluate<F: PrimeField>(coeffs: &[F], x: F) -> F {
+    coeffs.iter().rev().fold(F::zero(), |acc, c| acc * x + c)
+}
+
+/// find any solution of a linear system given as the rows of its augmented matrix, with the free
+/// variables set to $0$, [`None`] if the system is inconsistent
+fn solve<F: PrimeField>(mut rows: Vec<Vec<F>>, nb_unknowns: usize) -> Option<Vec<F>> {
+    let mut pivots = vec![];
+    for col in 0..nb_unknowns {
+        let r = pivots.len();
+        let Some(p) = (r..rows.len()).find(|&i| !rows[i][col].is_zero()) else {
+            continue;
+        };
+        rows.swap(r, p);
+
+        let inverse = rows[r][col].inverse().expect("pivot is not zero");
+        rows[r].iter_mut().for_each(|x| *x *= inverse);
+        let pivot_row = rows[r].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            let factor = row[col];
+            if i != r && !factor.is_zero() {
+                row.iter_mut()
+                    .zip(&pivot_row)
+                    .for_each(|(x, p)| *x -= factor * p);
+            }
+        }
+        pivots.push(col);
+    }
+
+    if rows[pivots.len()..]
+        .iter()
+        .any(|row| !row[nb_unknowns].is_zero())
+    {
+        return None;
+    }
+
+    let mut solution = vec![F::zero(); nb_unknowns];
+    for (r, col) in pivots.into_iter().enumerate() {
+        solution[col] = rows[r][nb_unknowns];
+    }
+    Some(solution)
+}
+
+/// run Berlekamp-Welch on a single row of the shards, i.e. find the polynomial of degree less than
+/// $k$ that agrees with all but at most $e$ of the values
+fn berlekamp_welch<F: PrimeField>(
+    points: &[F],
+    values: &[F],
+    k: usize,
+    e: usize,
+) -> Option<Vec<F>> {
+    // NOTE: the unknowns are the $k + e$ coefficients of $Q$ and the $e$ first coefficients of the
+    // monic error locator $E$, such that $Q(x_j) = y_j E(x_j)$ for all $j$
+    let rows = points
+        .iter()
+        .zip(values)
+        .map(|(&x, &y)| {
+            let powers = (0..=k + e)
+                .scan(F::one(), |p, _| {
+                    let current = *p;
+                    *p *= x;
+                    Some(current)
+                })
+                .collect::<Vec<_>>();
+            let mut row = powers[..k + e].to_vec();
+            row.extend(powers[..e].iter().map(|p| -y * p));
+            row.push(y * powers[e]);
+            row
+        })
+        .collect();
+    let solution = solve(rows, k + 2 * e)?;
+
+    // NOTE: $P = Q / E$, where the division is exact if there are at most $e$ errors
+    let mut remainder = solution[..k + e].to_vec();
+    let mut locator = solution[k + e..].to_vec();
+    locator.push(F::one());
+    let mut quotient = vec![F::zero(); k];
+    for i in (0..k).rev() {
+        let c = remainder[i + e];
+        quotient[i] = c;
+        for (j, l) in locator.iter().enumerate() {
+            remainder[i + j] -= c * l;
+        }
+    }
+    if remainder.iter().any(|r| !r.is_zero()) {
+        return None;
+    }
+
+    Some(quotient)
+}
+
+/// reconstruct the original data from the shards of a Reed-Solomon code, correcting the shards
+/// that have been silently corrupted
+///
+/// with $m$ shards, up to $\lfloor (m - k) / 2 \rfloor$ corrupted shards are corrected with the
+/// algorithm of Berlekamp and Welch, row by row, and their indices are returned together with the
+/// data. The rows that agree with the first $k$ shards are decoded directly, only the other ones
+/// go through the error correction.
+///
+/// > **Note**
+/// >
+/// > the shards should come from a Reed-Solomon code, e.g. [`encode`] with a Vandermonde matrix or
+/// > [`encode_fft`], otherwise [`KomodoError::IncompatibleShards`] is returned. Only the data of
+/// > the shards is corrected, their other fields are trusted.
+/// >
+/// > [`KomodoError::UncorrectableShards`] is returned when the corruption is detected but cannot
+/// > be corrected.
+pub fn decode_with_errors<F: PrimeField>(
+    shards: Vec<Shard<F>>,
+) -> Result<(Vec<u8>, Vec<usize>), KomodoError> {
+    let op = operation!("decode_with_errors", "fec", shards = shards.len());
+
+    if shards.is_empty() {
+        return Err(KomodoError::TooFewShards(0, 0, DecodeContext::default()));
+    }
+
+    let k = shards[0].k as usize;
+    let m = shards.len();
+    if m < k {
+        return Err(KomodoError::TooFewShards(m, k, decode_context(&shards)));
+    }
+
+    let points = reed_solomon_points(&shards, k).ok_or_else(|| {
+        KomodoError::IncompatibleShards(
+            "error correction needs the shards of a Reed-Solomon code".to_string(),
+        )
+    })?;
+    let e = (m - k) / 2;
+
+    // NOTE: the candidate decoding of each row from the $k$ first shards
+    let inverse = Matrix::from_vec_vec(
+        shards[..k]
+            .iter()
+            .map(|s| s.linear_combination.clone())
+            .collect(),
+    )?
+    .invert()?;
+
+    let mut source_shards = Vec::with_capacity(k * shards[0].data.len());
+    let mut corrupted = vec![false; m];
+    for r in 0..shards[0].data.len() {
+        let values = shards
+            .iter()
+            .map(|s| s.data.get(r).copied().unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        let candidate = (0..k)
+            .map(|i| (0..k).map(|j| inverse.get(i, j) * values[j]).sum())
+            .collect::<Vec<F>>();
+        let row = if points
+            .iter()
+            .zip(&values)
+            .all(|(&x, &y)| evaluate(&candidate, x) == y)
+        {
+            candidate
+        } else {
+            berlekamp_welch(&points, &values, k, e).ok_or(KomodoError::UncorrectableShards(e))?
+        };
+
+        for (j, (&x, &y)) in points.iter().zip(&values).enumerate() {
+            if evaluate(&row, x) != y {
+                corrupted[j] = true;
+            }
+        }
+        source_shards.extend(row);
+    }
+
+    let corrupted = (0..m).filter(|&j| corrupted[j]).collect::<Vec<_>>();
+    if corrupted.len() > e {
+        return Err(KomodoError::UncorrectableShards(e));
+    }
+
+    let mut bytes = algebra::merge_elements_into_bytes(&source_shards);
+    bytes.resize(shards[0].size, 0);
+    op.done(Ok((bytes, corrupted)))
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, F: PrimeField> arbitrary::Arbitrary<'a> for Shard<F> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let k = fuzzing::dimension(u)?;
+        let nb_elements = fuzzing::dimension(u)?;
+        let bytes_per_element = (F::MODULUS_BIT_SIZE as usize - 1) / 8;
+
+        Ok(Self {
+            k: k as u32,
+            linear_combination: fuzzing::field_elements(u, k)?,
+            hash: u.bytes(32).map(|h| h.to_vec()).unwrap_or(vec![0; 32]),
+            data: fuzzing::field_elements(u, nb_elements)?,
+            size: u.int_in_range(0..=k * nb_elements * bytes_per_element)?,
+        })
+    }
+}
+
+/// the chunks of some data, read one after the other, see [`encode_chunks`]
+///
+/// all the chunks have `chunk_size` bytes, except the last one which might be smaller, and an
+/// empty reader gives no chunk at all.
+#[cfg(feature = "std")]
+pub struct Chunks<R: std::io::Read> {
+    reader: R,
+    chunk_size: usize,
+    done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Chunks<R> {
+    /// split the data of `reader` into chunks of `chunk_size` bytes
+    ///
+    /// the iterator yields the chunks in order, or the error of the reader, after which it stops.
+    /// A `chunk_size` of $0$ gives no chunk at all.
+    pub fn new(reader: R, chunk_size: usize) -> Self {
+        Self {
+            reader,
+            chunk_size,
+            done: chunk_size == 0,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Iterator for Chunks<R> {
+    type Item = Result<Vec<u8>, KomodoError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use std::io::Read;
+
+        if self.done {
+            return None;
+        }
+
+        let mut chunk = vec![];
+        if let Err(e) = (&mut self.reader)
+            .take(self.chunk_size as u64)
+            .read_to_end(&mut chunk)
+        {
+            self.done = true;
+            return Some(Err(e.into()));
+        }
+        if chunk.len() < self.chunk_size {
+            self.done = true;
+        }
+        if chunk.is_empty() {
+            return None;
+        }
+
+        Some(Ok(chunk))
+    }
+}
+
+/// one chunk of data and its shards, see [`encode_chunks`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedChunk<F: PrimeField> {
+    /// the position of the chunk in the data
+    pub index: usize,
+    /// the bytes of the chunk, e.g. to prove the shards
+    pub data: Vec<u8>,
+    /// the shards of the chunk alone, whose [`Shard::hash`] is the [`chunk_hash`] of the chunk,
+    /// i.e. any $k$ of them decode to `data`
+    pub shards: Vec<Shard<F>>,
+}
+
+/// the [`Shard::hash`] of the shards of the chunk at position `index`, see [`encode_chunk`]
+///
+/// all the chunks but the first one are hashed with their index, as a `u64` in little endian,
+/// followed by their bytes, so that two identical chunks have different shards. The first chunk
+/// is hashed exactly as [`encode`] would, i.e. data that fits in a single chunk does not depend on
+/// the chunking.
+pub fn chunk_hash(index: usize, data: &[u8]) -> Vec<u8> {
+    if index == 0 {
+        content_hash(data)
+    } else {
+        Sha256::hash(&[&(index as u64).to_le_bytes()[..], data].concat()).to_vec()
+    }
+}
+
+/// encode a single chunk, see [`encode_chunks`] and [`chunk_hash`]
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_chunk<F: PrimeField>(
+    index: usize,
+    data: Vec<u8>,
+    encoding_mat: &Matrix<F>,
+) -> Result<EncodedChunk<F>, KomodoError> {
+    let shards = encode_with_hash(&data, encoding_mat, chunk_hash(index, &data))?;
+
+    Ok(EncodedChunk {
+        index,
+        data,
+        shards,
+    })
+}
+
+/// encode data from a reader chunk by chunk, each chunk being encoded independently with
+/// [`encode_chunk`]
+///
+/// only one chunk is read at a time, which allows to encode arbitrarily large data with a bounded
+/// memory.
+///
+/// This is the inverse of [`decode_chunks`].
+#[cfg(feature = "std")]
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_chunks<'a, F: PrimeField, R: std::io::Read + 'a>(
+    reader: R,
+    chunk_size: usize,
+    encoding_mat: &'a Matrix<F>,
+) -> impl Iterator<Item = Result<EncodedChunk<F>, KomodoError>> + 'a {
+    Chunks::new(reader, chunk_size)
+        .enumerate()
+        .map(move |(i, chunk)| encode_chunk(i, chunk?, encoding_mat))
+}
+
+/// reconstruct data encoded with [`encode_chunks`] from the shards of all its chunks, in order
+///
+/// consecutive shards with the same [`Shard::hash`] belong to the same chunk and are decoded
+/// together with [`decode`].
+pub fn decode_chunks<F: PrimeField>(shards: Vec<Shard<F>>) -> Result<Vec<u8>, KomodoError> {
+    if shards.is_empty() {
+        return decode(shards);
+    }
+
+    let mut bytes = vec![];
+    let mut chunk: Vec<Shard<F>> = vec![];
+    for shard in shards {
+        if chunk.last().is_some_and(|s| s.hash != shard.hash) {
+            bytes.extend(decode(ark_std::mem::take(&mut chunk))?);
+        }
+        chunk.push(shard);
+    }
+    bytes.extend(decode(chunk)?);
+
+    Ok(bytes)
+}
+
+/// a shard of one stripe of some data, see [`encode_striped`]
+#[derive(Debug, Default, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct StripedShard<F: PrimeField> {
+    /// the position of the stripe in the data
+    pub stripe: usize,
+    /// the total number of stripes of the data, the same for all its shards, i.e. `stripe` is
+    /// always smaller
+    pub nb_stripes: usize,
+    /// the shard of the stripe, hashed as the chunk at position `stripe`, see [`chunk_hash`]
+    pub shard: Shard<F>,
+}
+
+/// encode data stripe by stripe, all the stripes being encoded with the same matrix
+///
+/// the data is split into stripes of at most `max_stripe_size` bytes and the shards of a stripe
+/// are only computed when the iterator reaches it, i.e. the size of the shards is bounded by the
+/// size of the stripes instead of the size of the data and the shards of the first stripes can be
+/// sent while the next ones are being encoded.
+///
+/// This is the inverse of [`decode_striped`].
+///
+/// > **Note**
+/// >
+/// > empty data has no stripe at all.
+///
+/// # Panics
+/// if `max_stripe_size` is $0$.
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{algebra::linalg::Matrix, fec};
+///
+/// # fn main() {
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let encoding_mat = Matrix::random(3, 5, &mut ark_std::test_rng());
+///
+/// let shards = fec::encode_striped::<F>(&bytes, 1024, &encoding_mat)
+///     // NOTE: any 3 shards of each stripe are enough
+///     .flat_map(|stripe| stripe.unwrap().into_iter().skip(2))
+///     .collect();
+/// assert_eq!(fec::decode_striped(shards).unwrap(), bytes);
+/// # }
+/// ```
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode_striped<'a, F: PrimeField>(
+    data: &'a [u8],
+    max_stripe_size: usize,
+    encoding_mat: &'a Matrix<F>,
+) -> impl Iterator<Item = Result<Vec<StripedShard<F>>, KomodoError>> + 'a {
+    assert!(max_stripe_size > 0, "stripes should not be empty");
+
+    let nb_stripes = data.len().div_ceil(max_stripe_size);
+    (0..nb_stripes).map(move |i| {
+        let start = (i * max_stripe_size).min(data.len());
+        let stripe = &data[start..(start + max_stripe_size).min(data.len())];
+        let shards = encode_with_hash(stripe, encoding_mat, chunk_hash(i, stripe))?;
+
+        Ok(shards
+            .into_iter()
+            .map(|shard| StripedShard {
+                stripe: i,
+                nb_stripes,
+                shard,
+            })
+            .collect())
+    })
+}
+
+/// reconstruct data encoded with [`encode_striped`] from the shards of all its stripes, in any
+/// order
+///
+/// the shards are grouped by stripe and each stripe is decoded with [`decode`].
+///
+/// > **Note**
+/// >
+/// > [`KomodoError::TooFewShards`] is returned when a whole stripe is missing and
+/// > [`KomodoError::IncompatibleShards`] when the shards disagree on the number of stripes.
+pub fn decode_striped<F: PrimeField>(shards: Vec<StripedShard<F>>) -> Result<Vec<u8>, KomodoError> {
+    let op = operation!("decode_striped", "fec", shards = shards.len());
+
+    let Some(nb_stripes) = shards.first().map(|s| s.nb_stripes) else {
+        return decode::<F>(vec![]);
+    };
+
+    // NOTE: the number of stripes comes from the shards, i.e. it cannot be trusted to allocate
+    let mut stripes: BTreeMap<usize, Vec<Shard<F>>> = BTreeMap::new();
+    for s in shards {
+        if s.nb_stripes != nb_stripes || s.stripe >= nb_stripes {
+            return Err(KomodoError::IncompatibleShards(format!(
+                "stripe {} of {} is not part of data of {} stripes",
+                s.stripe, s.nb_stripes, nb_stripes
+            )));
+        }
+        stripes.entry(s.stripe).or_default().push(s.shard);
+    }
+
+    let mut bytes = vec![];
+    for i in 0..nb_stripes {
+        bytes.extend(decode(stripes.remove(&i).unwrap_or_default())?);
+    }
+
+    op.record("bytes", bytes.len());
+    op.done(Ok(bytes))
+}
+
+/// the maximum number of field elements of the data encoded in a single pass by [`encode_large`]
+pub const MAX_STRIPE_ELEMENTS: usize = 1 << 20;
+
+/// how some data has been split into stripes by [`encode_large`], required by [`decode_large`]
+#[derive(Debug, Default, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct StripingManifest {
+    /// the size of the whole data
+    pub size: usize,
+    /// the size of all the stripes but the last one, which might be smaller
+    pub stripe_size: usize,
+    /// the [`Shard::hash`] of the shards of each stripe, in order, see [`chunk_hash`]
+    pub hashes: Vec<Vec<u8>>,
+}
+
+impl StripingManifest {
+    /// the number of stripes of the data, i.e. the number of stripes expected by
+    /// [`decode_large`], $0$ for empty data
+    pub fn nb_stripes(&self) -> usize {
+        self.hashes.len()
+    }
+}
+
+/// encode data that might be too large to be encoded at once, stripe by stripe
+///
+/// the data is split into stripes of at most [`MAX_STRIPE_ELEMENTS`] field elements, made of whole
+/// rows, which are encoded with [`encode_striped`]. Data that fits in a single stripe gives a
+/// single stripe whose shards are exactly the ones of [`encode`] and empty data has no stripe at
+/// all.
+///
+/// This is the inverse of [`decode_large`].
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{algebra::linalg::Matrix, fec};
+///
+/// # fn main() {
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let encoding_mat = Matrix::random(3, 5, &mut ark_std::test_rng());
+///
+/// let (stripes, manifest) = fec::encode_large::<F>(&bytes, &encoding_mat).unwrap();
+/// assert_eq!(manifest.nb_stripes(), 1);
+///
+/// let stripes = stripes.into_iter().map(|s| s[2..].to_vec()).collect();
+/// assert_eq!(fec::decode_large(stripes, &manifest).unwrap(), bytes);
+/// # }
+/// ```
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+#[allow(clippy::type_complexity)]
+pub fn encode_large<F: PrimeField>(
+    data: &[u8],
+    encoding_mat: &Matrix<F>,
+) -> Result<(Vec<Vec<Shard<F>>>, StripingManifest), KomodoError> {
+    encode_large_with_max_elements(data, encoding_mat, MAX_STRIPE_ELEMENTS)
+}
+
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+#[allow(clippy::type_complexity)]
+fn encode_large_with_max_elements<F: PrimeField>(
+    data: &[u8],
+    encoding_mat: &Matrix<F>,
+    max_elements: usize,
+) -> Result<(Vec<Vec<Shard<F>>>, StripingManifest), KomodoError> {
+    let op = operation!("encode_large", "fec", bytes = data.len());
+
+    let k = encoding_mat.height;
+    let bytes_per_element = (F::MODULUS_BIT_SIZE as usize - 1) / 8;
+    let stripe_size = (max_elements / k).max(1) * k * bytes_per_element;
+
+    let stripes = encode_striped(data, stripe_size, encoding_mat)
+        .map(|stripe| Ok(stripe?.into_iter().map(|s| s.shard).collect::<Vec<_>>()))
+        .collect::<Result<Vec<_>, KomodoError>>()?;
+    let manifest = StripingManifest {
+        size: data.len(),
+        stripe_size,
+        hashes: stripes.iter().map(|s| s[0].hash.clone()).collect(),
+    };
+
+    op.done(Ok((stripes, manifest)))
+}
+
+/// reconstruct data encoded with [`encode_large`] from the shards of each of its stripes, in the
+/// order of the [`StripingManifest`]
+///
+/// each stripe is decoded with [`decode`] and the stripes are stitched back together.
+///
+/// > **Note**
+/// >
+/// > [`KomodoError::IncompatibleShards`] is returned if the stripes do not match the manifest,
+/// > i.e. if there are not as many stripes or if the shards of a stripe are not the shards of the
+/// > expected stripe.
+pub fn decode_large<F: PrimeField>(
+    stripes: Vec<Vec<Shard<F>>>,
+    manifest: &StripingManifest,
+) -> Result<Vec<u8>, KomodoError> {
+    let op = operation!("decode_large", "fec", stripes = stripes.len());
+
+    if stripes.len() != manifest.nb_stripes() {
+        return Err(KomodoError::IncompatibleShards(format!(
+            "expected {} stripes, found {}",
+            manifest.nb_stripes(),
+            stripes.len()
+        )));
+    }
+
+    let mut bytes = Vec::with_capacity(manifest.size);
+    for (i, (stripe, hash)) in stripes.into_iter().zip(&manifest.hashes).enumerate() {
+        if let Some(shard) = stripe.iter().find(|s| &s.hash != hash) {
+            return Err(KomodoError::IncompatibleShards(format!(
+                "shard with hash {:?} in stripe {} of hash {:?}",
+                shard.hash, i, hash
+            )));
+        }
+        bytes.extend(decode(stripe)?);
+    }
+
+    if bytes.len() != manifest.size {
+        return Err(KomodoError::IncompatibleShards(format!(
+            "decoded {} bytes, expected {}",
+            bytes.len(),
+            manifest.size
+        )));
+    }
+
+    op.done(Ok(bytes))
+}
+
+/// optional information about a shard, which is not needed to decode it, see [`AnnotatedShard`]
+#[derive(Debug, Default, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ShardMetadata {
+    /// the index of the column of the encoding matrix the shard has been computed from, e.g. the
+    /// position of its Vandermonde point, which is meaningless for a recoded shard
+    pub index: Option<u64>,
+    /// an identifier of the object, or of the generation of the object, the shard belongs to
+    pub object_id: Option<Vec<u8>>,
+    /// the time at which the shard has been created, in seconds since the Unix epoch
+    pub created_at: Option<u64>,
+}
+
+impl ShardMetadata {
+    /// the same, with the index of the shard
+    pub fn with_index(self, index: u64) -> Self {
+        Self {
+            index: Some(index),
+            ..self
+        }
+    }
+
+    /// the same, with the identifier of the object
+    pub fn with_object_id(self, object_id: &[u8]) -> Self {
+        Self {
+            object_id: Some(object_id.to_vec()),
+            ..self
+        }
+    }
+
+    /// the same, with the time of creation of the shard
+    pub fn with_created_at(self, created_at: u64) -> Self {
+        Self {
+            created_at: Some(created_at),
+            ..self
+        }
+    }
+
+    /// the same, created now according to the clock of the system
+    #[cfg(feature = "std")]
+    pub fn created_now(self) -> Self {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        self.with_created_at(now.as_secs())
+    }
+}
+
+/// a shard and its [`ShardMetadata`], serialized together
+///
+/// > **Note**
+/// >
+/// > the metadata is a wrapper around the shard instead of a part of it, i.e. the serialization of
+/// > [`Shard`] does not change and the shards that do not need any metadata do not pay for it.
+#[derive(Debug, Default, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AnnotatedShard<F: PrimeField> {
+    /// the shard itself, serialized as any other [`Shard`]
+    pub shard: Shard<F>,
+    /// the metadata attached to the shard, which play no role in the decoding
+    pub metadata: ShardMetadata,
+}
+
+/// attach the same metadata to all the shards of an encoding, with the position of each shard as
+/// its [`ShardMetadata::index`]
+///
+/// # Example
+/// ```
+/// # use ark_bls12_381::Fr as F;
+/// use komodo::{
+///     algebra::linalg::Matrix,
+///     fec::{self, ShardMetadata},
+/// };
+///
+/// # fn main() {
+/// let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+/// let shards = fec::encode::<F>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+///
+/// let shards = fec::annotate(shards, ShardMetadata::default().with_object_id(b"dragoon"));
+/// assert_eq!(shards[4].metadata.index, Some(4));
+/// assert_eq!(shards[4].metadata.object_id, Some(b"dragoon".to_vec()));
+/// # }
+/// ```
+pub fn annotate<F: PrimeField>(
+    shards: Vec<Shard<F>>,
+    metadata: ShardMetadata,
+) -> Vec<AnnotatedShard<F>> {
+    shards
+        .into_iter()
+        .enumerate()
+        .map(|(i, shard)| AnnotatedShard {
+            shard,
+            metadata: metadata.clone().with_index(i as u64),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr;
+    use ark_ff::{PrimeField, UniformRand};
+
+    use crate::{
+        algebra,
+        algebra::linalg::Matrix,
+        error::KomodoError,
+        fec::{
+            annotate, chunk_hash, content_hash, decode, decode_chunks, decode_elements,
+            decode_into, decode_large, decode_padded, decode_range, decode_striped,
+            decode_with_errors, encode, encode_chunks, encode_elements, encode_fft,
+            encode_from_reader, encode_iter, encode_large, encode_padded, encode_striped,
+            encode_systematic, encode_with_hash, encode_with_hasher, increases_rank, is_decodable,
+            pad, rank_of, recode_random, recode_random_with_coeffs, recode_with_coeffs_into,
+            repair, unpad, AnnotatedShard, Fountain, Padding, Shard, ShardMetadata,
+        },
+    };
+
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use itertools::Itertools;
+    use rand::{seq::SliceRandom, Rng};
+
+    use super::{encode_large_with_max_elements, recode_with_coeffs};
+
+    type LC = Vec<usize>;
+    type LCExclusion = Vec<usize>;
+
+    fn bytes() -> Vec<u8> {
+        include_bytes!("../assets/dragoon_32x32.png").to_vec()
+    }
+
+    fn to_curve<F: PrimeField>(n: u128) -> F {
+        F::from_le_bytes_mod_order(&n.to_le_bytes())
+    }
+
+    /// `contains_one_of(x, set)` is true iif `x` fully contains one of the lists from `set`
+    ///
+    /// > **Note**  
+    /// >
+    /// > see [`containment`] for some example
+    fn contains_one_of(x: &[usize], set: &[Vec<usize>]) -> bool {
+        set.iter().any(|y| y.iter().all(|z| x.contains(z)))
+    }
+
+    #[test]
+    fn containment() {
+        assert!(contains_one_of(&[1, 2, 3], &[vec![1, 2, 3]]));
+        assert!(contains_one_of(
+            &[3, 6, 8],
+            &[vec![2, 4, 6], vec![1, 3, 7], vec![6, 7, 8], vec![3, 6, 8]],
+        ));
+        assert!(!contains_one_of(
+            &[1, 6, 8],
+            &[vec![2, 4, 6], vec![1, 3, 7], vec![6, 7, 8], vec![3, 6, 8]],
+        ));
+        assert!(contains_one_of(
+            &[3, 6, 8, 9, 10],
+            &[vec![2, 4, 6], vec![1, 3, 7], vec![6, 7, 8], vec![3, 6, 8]],
+        ));
+        assert!(contains_one_of(&[0, 4, 5], &[vec![2, 3, 5], vec![4, 5]]));
+    }
+
+    fn try_all_decoding_combinations<F: PrimeField>(
+        data: &[u8],
+        shards: &[Shard<F>],
+        k: usize,
+        n: usize,
+        test_case: &str,
+        limit: Option<usize>,
+        should_not_be_decodable: Vec<LCExclusion>,
+    ) {
+        let there_are_recoded_shards = shards.len() > n;
+
+        for c in shards
             .iter()
             .cloned()
             .enumerate()
@@ -345,63 +2117,265 @@ mod tests {
         let mut rng = ark_std::test_rng();
         let test_case = format!("TEST | data: {} bytes, k: {}, n: {}", data.len(), k, n);
 
-        let shards = encode::<F>(data, &Matrix::random(k, n, &mut rng))
-            .unwrap_or_else(|_| panic!("could not encode {test_case}"));
+        let shards = encode::<F>(data, &Matrix::random(k, n, &mut rng))
+            .unwrap_or_else(|_| panic!("could not encode {test_case}"));
+
+        try_all_decoding_combinations(data, &shards, k, n, &test_case, None, vec![]);
+    }
+
+    fn end_to_end_with_recoding_template<F: PrimeField>(
+        data: &[u8],
+        k: usize,
+        n: usize,
+        recoding_steps: Vec<LC>,
+        should_not_be_decodable: Vec<LCExclusion>,
+        name: &str,
+    ) {
+        let mut rng = ark_std::test_rng();
+        let test_case = format!(
+            "TEST | data: {} bytes, k: {}, n: {}, scenario: {}",
+            data.len(),
+            k,
+            n,
+            name
+        );
+
+        let mut shards = encode::<F>(data, &Matrix::random(k, n, &mut rng))
+            .unwrap_or_else(|_| panic!("could not encode {test_case}"));
+
+        for step in recoding_steps {
+            let shards_to_recode: Vec<_> = shards
+                .iter()
+                .cloned()
+                .enumerate()
+                .filter_map(|(i, s)| if step.contains(&i) { Some(s) } else { None })
+                .collect();
+            shards.push(recode_random(&shards_to_recode, &mut rng).unwrap().unwrap());
+        }
+
+        try_all_decoding_combinations(
+            data,
+            &shards,
+            k,
+            n,
+            &test_case,
+            None,
+            should_not_be_decodable,
+        );
+    }
+
+    #[test]
+    fn end_to_end() {
+        let bytes = bytes();
+
+        let ks = [3, 5];
+        let n = 5;
+
+        for k in ks {
+            end_to_end_template::<Fr>(&bytes, k, n);
+        }
+    }
+
+    #[test]
+    fn end_to_end_with_structured_matrices() {
+        let bytes = bytes();
+        let mut rng = ark_std::test_rng();
+
+        for k in [3, 5, 8] {
+            let test_case = format!("TEST | data: {} bytes, k: {}", bytes.len(), k);
+
+            let band = Matrix::<Fr>::random_band(k, k, 2, &mut rng).unwrap();
+            let shards = encode(&bytes, &band).unwrap();
+            assert_eq!(bytes, decode(shards).unwrap(), "band matrix {test_case}");
+
+            let sparse = Matrix::<Fr>::random_sparse(k, k, 0.9, &mut rng).unwrap();
+            if sparse.rank() == k {
+                let shards = encode(&bytes, &sparse).unwrap();
+                assert_eq!(bytes, decode(shards).unwrap(), "sparse matrix {test_case}");
+            }
+        }
+    }
+
+    #[test]
+    fn precomputed_hash() {
+        let bytes = bytes();
+        let encoding_mat = Matrix::<Fr>::random(3, 5, &mut ark_std::test_rng());
+
+        let shards = encode(&bytes, &encoding_mat).unwrap();
+        assert_eq!(
+            encode_with_hash(&bytes, &encoding_mat, content_hash(&bytes)).unwrap(),
+            shards
+        );
+        assert_eq!(
+            encode_with_hasher::<Fr, rs_merkle::algorithms::Sha256>(&bytes, &encoding_mat).unwrap(),
+            shards
+        );
+
+        let shards = encode_with_hash(&bytes, &encoding_mat, vec![1, 2, 3]).unwrap();
+        assert!(shards.iter().all(|s| s.hash == vec![1, 2, 3]));
+        assert_eq!(decode(shards).unwrap(), bytes);
+    }
+
+    #[test]
+    fn iterator() {
+        let bytes = bytes();
+        let mut rng = ark_std::test_rng();
+
+        for (k, n) in [(3, 5), (5, 5), (4, 10)] {
+            let encoding_mat = Matrix::<Fr>::random(k, n, &mut rng);
+            let shards = encode_iter(&bytes, &encoding_mat).collect::<Vec<_>>();
+            assert_eq!(
+                shards,
+                encode(&bytes, &encoding_mat).unwrap(),
+                "k: {k}, n: {n}"
+            );
+            assert_eq!(decode(shards).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn fft() {
+        let bytes = bytes();
+
+        for (k, n) in [(3, 5), (4, 8), (8, 8), (2, 3)] {
+            let shards = encode_fft::<Fr>(&bytes, k, n).unwrap();
+            assert_eq!(shards.len(), n);
+
+            let points = shards
+                .iter()
+                .map(|s| s.linear_combination[1])
+                .collect::<Vec<_>>();
+            let encoding_mat = Matrix::vandermonde(&points, k).unwrap();
+            assert_eq!(
+                shards,
+                encode(&bytes, &encoding_mat).unwrap(),
+                "k: {k}, n: {n}"
+            );
+
+            assert_eq!(decode(shards[n - k..].to_vec()).unwrap(), bytes);
+        }
 
-        try_all_decoding_combinations(data, &shards, k, n, &test_case, None, vec![]);
+        assert!(matches!(
+            encode_fft::<Fr>(&bytes, 5, 3),
+            Err(KomodoError::InvalidEvaluationDomain(5, 3))
+        ));
+        assert!(matches!(
+            encode_fft::<Fr>(&bytes, 0, 3),
+            Err(KomodoError::InvalidEvaluationDomain(0, 3))
+        ));
     }
 
-    fn end_to_end_with_recoding_template<F: PrimeField>(
-        data: &[u8],
-        k: usize,
-        n: usize,
-        recoding_steps: Vec<LC>,
-        should_not_be_decodable: Vec<LCExclusion>,
-        name: &str,
-    ) {
-        let mut rng = ark_std::test_rng();
-        let test_case = format!(
-            "TEST | data: {} bytes, k: {}, n: {}, scenario: {}",
-            data.len(),
-            k,
-            n,
-            name
+    #[test]
+    fn error_correction() {
+        let bytes = bytes();
+        let (k, n) = (3, 8);
+        let points = (1..=n as u128).map(to_curve::<Fr>).collect::<Vec<_>>();
+        let shards = encode(&bytes, &Matrix::vandermonde_unchecked(&points, k)).unwrap();
+
+        assert_eq!(
+            decode_with_errors(shards.clone()).unwrap(),
+            (bytes.clone(), vec![])
         );
 
-        let mut shards = encode::<F>(data, &Matrix::random(k, n, &mut rng))
-            .unwrap_or_else(|_| panic!("could not encode {test_case}"));
+        // NOTE: with $n = 8$ and $k = 3$, up to $2$ shards can be corrected, whatever their rows
+        let mut corrupted = shards.clone();
+        corrupted[0].data[0] += Fr::from(1u64);
+        corrupted[0].data[5] += Fr::from(2u64);
+        corrupted[6].data[3] += Fr::from(3u64);
+        assert_eq!(
+            decode_with_errors(corrupted.clone()).unwrap(),
+            (bytes.clone(), vec![0, 6])
+        );
+        assert_ne!(decode(corrupted[..k].to_vec()).unwrap(), bytes);
 
-        for step in recoding_steps {
-            let shards_to_recode: Vec<_> = shards
-                .iter()
-                .cloned()
-                .enumerate()
-                .filter_map(|(i, s)| if step.contains(&i) { Some(s) } else { None })
-                .collect();
-            shards.push(recode_random(&shards_to_recode, &mut rng).unwrap().unwrap());
-        }
+        let mut corrupted = shards[..k + 1].to_vec();
+        corrupted[1].data[0] += Fr::from(1u64);
+        assert!(matches!(
+            decode_with_errors(corrupted),
+            Err(KomodoError::UncorrectableShards(0))
+        ));
 
-        try_all_decoding_combinations(
-            data,
-            &shards,
-            k,
-            n,
-            &test_case,
-            None,
-            should_not_be_decodable,
+        assert!(matches!(
+            decode_with_errors(shards[..k - 1].to_vec()),
+            Err(KomodoError::TooFewShards(2, 3, ..))
+        ));
+        let shards = encode(
+            &bytes,
+            &Matrix::<Fr>::random(k, n, &mut ark_std::test_rng()),
+        )
+        .unwrap();
+        assert!(matches!(
+            decode_with_errors(shards),
+            Err(KomodoError::IncompatibleShards(..))
+        ));
+    }
+
+    #[test]
+    fn fountain() {
+        let bytes = bytes();
+        let k = 4;
+
+        let shards = Fountain::<Fr>::new(&bytes, k, 0)
+            .take(20)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            shards,
+            Fountain::<Fr>::new(&bytes, k, 0)
+                .take(20)
+                .collect::<Vec<_>>()
         );
+        assert_ne!(shards[0], Fountain::<Fr>::new(&bytes, k, 1).next_shard());
+
+        for i in [0, 3, 16] {
+            assert_eq!(decode(shards[i..i + k].to_vec()).unwrap(), bytes);
+        }
+        assert!(matches!(
+            decode(shards[..k - 1].to_vec()),
+            Err(KomodoError::TooFewShards(3, 4, ..))
+        ));
     }
 
     #[test]
-    fn end_to_end() {
+    fn systematic() {
         let bytes = bytes();
+        let (k, n) = (3, 6);
+        let points = (1..=n as u128).map(to_curve::<Fr>).collect::<Vec<_>>();
+        let encoding_mat = Matrix::vandermonde_unchecked(&points, k);
 
-        let ks = [3, 5];
-        let n = 5;
+        let shards = encode_systematic(&bytes, &encoding_mat).unwrap();
+        assert_eq!(shards.len(), n);
 
-        for k in ks {
-            end_to_end_template::<Fr>(&bytes, k, n);
+        let elements = algebra::split_data_into_field_elements::<Fr>(&bytes, k);
+        for (i, shard) in shards.iter().take(k).enumerate() {
+            let source = elements
+                .iter()
+                .skip(i)
+                .step_by(k)
+                .cloned()
+                .collect::<Vec<_>>();
+            assert_eq!(shard.data, source, "shard {i} should be a copy of the data");
         }
+
+        assert_eq!(decode(shards[..k].to_vec()).unwrap(), bytes);
+        assert_eq!(decode(shards[k..].to_vec()).unwrap(), bytes);
+        assert_eq!(
+            decode(vec![
+                shards[4].clone(),
+                shards[0].clone(),
+                shards[2].clone()
+            ])
+            .unwrap(),
+            bytes
+        );
+        assert_eq!(
+            decode(vec![
+                shards[2].clone(),
+                shards[0].clone(),
+                shards[1].clone()
+            ])
+            .unwrap(),
+            bytes
+        );
     }
 
     #[test]
@@ -567,4 +2541,626 @@ mod tests {
     fn combine_shards() {
         combine_shards_template::<Fr>();
     }
+
+    #[test]
+    fn decode_failure_context() {
+        let bytes = bytes();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+        let recoded = recode_with_coeffs(&shards[..2], &[to_curve(2), to_curve(3)]).unwrap();
+
+        let err = decode(shards[..2].to_vec()).unwrap_err();
+        let KomodoError::TooFewShards(2, 3, context) = err else {
+            panic!("expected too few shards, found {:?}", err);
+        };
+        assert_eq!(context.hash, shards[0].hash);
+        assert_eq!(context.independent, vec![0, 1]);
+
+        let err = decode(vec![
+            shards[0].clone(),
+            shards[1].clone(),
+            recoded.clone(),
+            shards[0].clone(),
+        ])
+        .unwrap_err();
+        let KomodoError::LinearlyDependentShards(3, context) = err else {
+            panic!("expected linearly dependent shards, found {:?}", err);
+        };
+        assert_eq!(context.rank(), 2);
+        assert_eq!(context.independent, vec![0, 1]);
+        assert_eq!(context.dependent, vec![2, 3]);
+
+        // the useless shards are skipped as long as enough shards are linearly independent
+        assert_eq!(
+            decode(vec![
+                shards[0].clone(),
+                recoded,
+                shards[1].clone(),
+                shards[4].clone()
+            ])
+            .unwrap(),
+            bytes
+        );
+
+        // NOTE: $k$ comes from the shards, i.e. it cannot be trusted
+        let mut forged = shards.clone();
+        forged.iter_mut().for_each(|s| s.k = 0);
+        assert!(matches!(
+            decode(forged.clone()),
+            Err(KomodoError::IncompatibleShards(..))
+        ));
+        assert!(matches!(
+            decode_range(&forged, 0..1),
+            Err(KomodoError::IncompatibleShards(..))
+        ));
+    }
+
+    #[test]
+    fn checksum() {
+        let bytes = bytes();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+        let checksums = shards.iter().map(|s| s.checksum()).collect::<Vec<_>>();
+
+        assert!(shards
+            .iter()
+            .zip(&checksums)
+            .all(|(s, c)| s.check_integrity(c)));
+        assert_ne!(checksums[0], checksums[1]);
+
+        let mut corrupted = shards[0].clone();
+        corrupted.data[0] += Fr::from(1u64);
+        assert!(!corrupted.check_integrity(&checksums[0]));
+
+        let mut corrupted = shards[0].clone();
+        corrupted.linear_combination[2] = Fr::from(0u64);
+        assert!(!corrupted.check_integrity(&checksums[0]));
+    }
+
+    #[test]
+    fn large() {
+        let bytes = bytes();
+        let mut rng = ark_std::test_rng();
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+
+        let (stripes, manifest) = encode_large::<Fr>(&bytes, &encoding_mat).unwrap();
+        assert_eq!(stripes, vec![encode(&bytes, &encoding_mat).unwrap()]);
+        assert_eq!(decode_large(stripes, &manifest).unwrap(), bytes);
+
+        for max_elements in [1, 3, 10, 30] {
+            let (stripes, manifest) =
+                encode_large_with_max_elements::<Fr>(&bytes, &encoding_mat, max_elements).unwrap();
+            let elements_per_stripe = (max_elements / 3).max(1) * 3;
+            assert_eq!(manifest.stripe_size, elements_per_stripe * 31);
+            assert_eq!(
+                manifest.nb_stripes(),
+                bytes.len().div_ceil(manifest.stripe_size)
+            );
+            assert_eq!(stripes.len(), manifest.nb_stripes());
+            assert!(stripes.len() > 1);
+            assert!(stripes
+                .iter()
+                .all(|s| s[0].data.len() * 3 <= elements_per_stripe));
+
+            let stripes = stripes
+                .into_iter()
+                .map(|s| s.choose_multiple(&mut rng, 3).cloned().collect())
+                .collect::<Vec<_>>();
+            assert_eq!(decode_large(stripes.clone(), &manifest).unwrap(), bytes);
+
+            let mut swapped = stripes.clone();
+            swapped.swap(0, 1);
+            assert!(matches!(
+                decode_large(swapped, &manifest),
+                Err(KomodoError::IncompatibleShards(..))
+            ));
+            assert!(matches!(
+                decode_large(stripes[1..].to_vec(), &manifest),
+                Err(KomodoError::IncompatibleShards(..))
+            ));
+        }
+
+        let (stripes, manifest) = encode_large::<Fr>(&[], &encoding_mat).unwrap();
+        assert_eq!(manifest.nb_stripes(), 0);
+        assert_eq!(decode_large(stripes, &manifest).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn metadata() {
+        let bytes = bytes();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+
+        let metadata = ShardMetadata::default()
+            .with_object_id(b"komodo")
+            .with_created_at(1_700_000_000);
+        let annotated = annotate(shards.clone(), metadata);
+        for (i, (a, s)) in annotated.iter().zip(&shards).enumerate() {
+            assert_eq!(&a.shard, s);
+            assert_eq!(a.metadata.index, Some(i as u64));
+            assert_eq!(a.metadata.object_id, Some(b"komodo".to_vec()));
+            assert_eq!(a.metadata.created_at, Some(1_700_000_000));
+        }
+
+        for shard in annotated.into_iter().chain([AnnotatedShard {
+            shard: shards[0].clone(),
+            metadata: ShardMetadata::default(),
+        }]) {
+            let mut serialized = vec![];
+            shard.serialize_compressed(&mut serialized).unwrap();
+            assert_eq!(
+                AnnotatedShard::<Fr>::deserialize_compressed(&serialized[..]).unwrap(),
+                shard
+            );
+        }
+
+        assert!(ShardMetadata::default()
+            .created_now()
+            .created_at
+            .is_some_and(|t| t > 1_700_000_000));
+    }
+
+    #[test]
+    fn striping() {
+        let bytes = bytes();
+        let mut rng = ark_std::test_rng();
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+
+        for max_stripe_size in [1, 31, 100, bytes.len() - 1, bytes.len(), 10 * bytes.len()] {
+            let stripes = encode_striped::<Fr>(&bytes, max_stripe_size, &encoding_mat)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(stripes.len(), bytes.len().div_ceil(max_stripe_size));
+            for (i, stripe) in stripes.iter().enumerate() {
+                assert_eq!(stripe.len(), 5);
+                assert!(stripe.iter().all(|s| s.stripe == i));
+                assert!(stripe.iter().all(|s| s.shard.size <= max_stripe_size));
+            }
+
+            let mut shards = stripes
+                .into_iter()
+                .flat_map(|s| s.into_iter().take(3))
+                .collect::<Vec<_>>();
+            shards.shuffle(&mut rng);
+            assert_eq!(decode_striped(shards.clone()).unwrap(), bytes);
+
+            if max_stripe_size < bytes.len() {
+                shards.retain(|s| s.stripe != 1);
+                assert!(matches!(
+                    decode_striped(shards),
+                    Err(KomodoError::TooFewShards(0, 0, ..))
+                ));
+            }
+        }
+
+        assert_eq!(encode_striped::<Fr>(&[], 10, &encoding_mat).count(), 0);
+
+        // NOTE: the number of stripes is not trusted
+        let mut shards = encode_striped::<Fr>(&bytes, 100, &encoding_mat)
+            .flat_map(|s| s.unwrap())
+            .collect::<Vec<_>>();
+        shards[1].nb_stripes = usize::MAX;
+        assert!(matches!(
+            decode_striped(shards.clone()),
+            Err(KomodoError::IncompatibleShards(..))
+        ));
+        shards.iter_mut().for_each(|s| s.nb_stripes = usize::MAX);
+        assert!(matches!(
+            decode_striped(shards),
+            Err(KomodoError::TooFewShards(0, 0, ..))
+        ));
+    }
+
+    #[test]
+    fn repairing() {
+        let bytes = bytes();
+        let mut rng = ark_std::test_rng();
+        let (k, n) = (3, 6);
+        let points = (1..=n as u128).map(to_curve::<Fr>).collect::<Vec<_>>();
+        let shards = encode::<Fr>(&bytes, &Matrix::vandermonde_unchecked(&points, k)).unwrap();
+
+        for lost in 0..n {
+            let others = shards
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != lost)
+                .map(|(_, s)| s.clone())
+                .collect::<Vec<_>>();
+            for subset in others.iter().cloned().combinations(k) {
+                let repaired = repair(&subset, &shards[lost].linear_combination).unwrap();
+                assert_eq!(repaired, shards[lost], "lost: {lost}");
+            }
+        }
+
+        let recoded = (0..k)
+            .map(|_| recode_random(&shards, &mut rng).unwrap().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            repair(&recoded, &shards[0].linear_combination).unwrap(),
+            shards[0]
+        );
+
+        assert!(matches!(
+            repair(&shards, &[to_curve::<Fr>(1)]),
+            Err(KomodoError::IncompatibleShards(..))
+        ));
+        assert!(matches!(
+            repair(&shards[..k - 1], &shards[k].linear_combination),
+            Err(KomodoError::TooFewShards(2, 3, ..))
+        ));
+        assert!(matches!(
+            repair(&vec![shards[0].clone(); k], &shards[k].linear_combination),
+            Err(KomodoError::LinearlyDependentShards(3, ..))
+        ));
+    }
+
+    #[test]
+    fn rank() {
+        let mut rng = ark_std::test_rng();
+        let bytes = bytes();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 6, &mut rng)).unwrap();
+
+        assert_eq!(rank_of::<Fr>(&[]), 0);
+        assert!(!is_decodable::<Fr>(&[]));
+        assert!(increases_rank(&[], &shards[0]));
+
+        for m in 1..=6 {
+            for subset in shards.iter().cloned().combinations(m) {
+                assert_eq!(rank_of(&subset), m.min(3));
+                assert_eq!(is_decodable(&subset), m >= 3);
+                assert_eq!(is_decodable(&subset), decode(subset.clone()).is_ok());
+            }
+        }
+
+        let recoded = recode_with_coeffs(&shards[..2], &[Fr::from(2u64), Fr::from(3u64)]).unwrap();
+        let pool = vec![shards[0].clone(), shards[1].clone(), shards[0].clone()];
+        assert_eq!(rank_of(&pool), 2);
+        assert!(!increases_rank(&pool, &recoded));
+        assert!(!increases_rank(&pool, &shards[1]));
+        assert!(increases_rank(&pool, &shards[2]));
+        assert_eq!(rank_of(&[pool.clone(), vec![recoded]].concat()), 2);
+        assert!(is_decodable(
+            &[pool.clone(), vec![shards[5].clone()]].concat()
+        ));
+
+        let mut zero = shards[2].clone();
+        zero.linear_combination = vec![Fr::from(0u64); 3];
+        assert!(!increases_rank(&pool, &zero));
+        let mut short = shards[2].clone();
+        short.linear_combination.pop();
+        assert!(!increases_rank(&pool, &short));
+    }
+
+    #[test]
+    fn recoding_in_place() {
+        let mut rng = ark_std::test_rng();
+        let bytes = bytes();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut rng)).unwrap();
+
+        let (alpha, beta) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let mut recoded = shards[0].clone();
+        recoded.recode_assign(alpha, &shards[1], beta);
+        assert_eq!(recoded, shards[0].recode_with(alpha, &shards[1], beta));
+
+        let mut out = Shard::default();
+        for subset in [&shards[..], &shards[..2], &shards[3..], &shards[1..4]] {
+            let coeffs = subset
+                .iter()
+                .map(|_| Fr::rand(&mut rng))
+                .collect::<Vec<_>>();
+            assert!(recode_with_coeffs_into(subset, &coeffs, &mut out));
+            assert_eq!(Some(&out), recode_with_coeffs(subset, &coeffs).as_ref());
+        }
+
+        let other = encode::<Fr>(&bytes[..100], &Matrix::random(2, 3, &mut rng)).unwrap();
+        let coeffs = [Fr::rand(&mut rng), Fr::rand(&mut rng)];
+        assert!(recode_with_coeffs_into(&other[..2], &coeffs, &mut out));
+        assert_eq!(
+            Some(&out),
+            recode_with_coeffs(&other[..2], &coeffs).as_ref()
+        );
+
+        let before = out.clone();
+        assert!(!recode_with_coeffs_into(&shards, &coeffs, &mut out));
+        assert!(!recode_with_coeffs_into(&[], &[], &mut out));
+        assert_eq!(out, before);
+    }
+
+    #[test]
+    fn recoding_with_coeffs() {
+        let mut rng = ark_std::test_rng();
+        let bytes = bytes();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut rng)).unwrap();
+
+        let (recoded, coeffs) = recode_random_with_coeffs(&shards[1..], &mut rng)
+            .unwrap()
+            .unwrap();
+        assert_eq!(coeffs.len(), 4);
+        assert_eq!(
+            recode_with_coeffs(&shards[1..], &coeffs),
+            Some(recoded.clone())
+        );
+
+        let expected = (0..3)
+            .map(|i| {
+                shards[1..]
+                    .iter()
+                    .zip(&coeffs)
+                    .map(|(s, c)| s.linear_combination[i] * c)
+                    .sum::<Fr>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(recoded.linear_combination, expected);
+
+        assert_eq!(recode_random_with_coeffs::<Fr>(&[], &mut rng), Ok(None));
+    }
+
+    #[test]
+    fn padding() {
+        let mut rng = ark_std::test_rng();
+        let bytes = bytes();
+
+        for padding in [Padding::LengthPrefix, Padding::Iso7816] {
+            for k in [1, 3, 5] {
+                for size in [0, 1, 30, 31, 92, 93, bytes.len()] {
+                    let data = &bytes[..size];
+                    let padded = pad::<Fr>(data, k, padding);
+                    assert_eq!(padded.len() % (k * 31), 0);
+                    assert!(!padded.is_empty());
+                    assert_eq!(unpad(&padded, padding).unwrap(), data);
+
+                    let encoding_mat = Matrix::random(k, k + 2, &mut rng);
+                    let shards = encode_padded::<Fr>(data, &encoding_mat, padding).unwrap();
+                    assert_eq!(decode_padded(shards[2..].to_vec(), padding).unwrap(), data);
+                }
+            }
+        }
+
+        let padded = pad::<Fr>(&bytes, 3, Padding::LengthPrefix);
+        let mut corrupted = padded.clone();
+        *corrupted.last_mut().unwrap() = 1;
+        assert!(matches!(
+            unpad(&corrupted, Padding::LengthPrefix),
+            Err(KomodoError::InvalidPadding(..))
+        ));
+        let mut corrupted = padded.clone();
+        corrupted[..8].copy_from_slice(&(padded.len() as u64).to_le_bytes());
+        assert!(matches!(
+            unpad(&corrupted, Padding::LengthPrefix),
+            Err(KomodoError::InvalidPadding(..))
+        ));
+        assert!(matches!(
+            unpad(&[1, 2, 3], Padding::LengthPrefix),
+            Err(KomodoError::InvalidPadding(..))
+        ));
+        assert!(matches!(
+            unpad(&[1, 2, 3, 0, 0], Padding::Iso7816),
+            Err(KomodoError::InvalidPadding(..))
+        ));
+        assert!(matches!(
+            unpad(&[0, 0], Padding::Iso7816),
+            Err(KomodoError::InvalidPadding(..))
+        ));
+
+        assert!(matches!(
+            encode_padded::<Fr>(&bytes, &Matrix::random(0, 5, &mut rng), Padding::Iso7816),
+            Err(KomodoError::InvalidMatrixElements(..))
+        ));
+
+        // NOTE: data that has not been padded explicitly has an implicit padding
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+        let shards = encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        assert!(matches!(
+            decode_padded(shards, Padding::Iso7816),
+            Err(KomodoError::InvalidPadding(..))
+        ));
+    }
+
+    #[test]
+    fn elements() {
+        let mut rng = ark_std::test_rng();
+
+        for (nb_elements, k, n) in [(1, 1, 2), (99, 3, 5), (100, 3, 5), (101, 3, 5), (7, 8, 10)] {
+            let elements = (0..nb_elements)
+                .map(|_| Fr::rand(&mut rng))
+                .collect::<Vec<_>>();
+            let encoding_mat = Matrix::random(k, n, &mut rng);
+
+            let shards = encode_elements(&elements, &encoding_mat).unwrap();
+            assert_eq!(shards.len(), n);
+            assert!(shards.iter().all(|s| s.size == nb_elements));
+            assert!(shards
+                .iter()
+                .all(|s| s.data.len() == nb_elements.div_ceil(k)));
+
+            for subset in shards.iter().cloned().combinations(k) {
+                assert_eq!(decode_elements(&subset).unwrap(), elements);
+            }
+            assert!(matches!(
+                decode_elements(&shards[..k - 1]),
+                Err(KomodoError::TooFewShards(..))
+            ));
+        }
+
+        // NOTE: the source elements of some bytes are encoded exactly as the bytes themselves
+        let bytes = bytes();
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+        let elements = algebra::split_data_into_field_elements::<Fr>(&bytes, 3);
+        let shards = encode_elements(&elements, &encoding_mat).unwrap();
+        let expected = encode(&bytes, &encoding_mat).unwrap();
+        for (s, e) in shards.iter().zip(&expected) {
+            assert_eq!(s.data, e.data);
+        }
+    }
+
+    #[test]
+    fn encoding_from_reader() {
+        let mut rng = ark_std::test_rng();
+
+        // NOTE: the large data is read in several batches, the last one being padded
+        let large = (0..(3 << 20) + 17).map(|_| rng.gen()).collect::<Vec<u8>>();
+        for (bytes, k, n) in [(bytes(), 3, 5), (bytes(), 1, 2), (large, 4, 6)] {
+            let encoding_mat = Matrix::random(k, n, &mut rng);
+            let shards = encode_from_reader::<Fr, _>(&bytes[..], &encoding_mat).unwrap();
+            assert_eq!(shards, encode(&bytes, &encoding_mat).unwrap());
+            assert_eq!(decode(shards).unwrap(), bytes);
+        }
+
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+        assert_eq!(
+            encode_from_reader::<Fr, _>(&[][..], &encoding_mat),
+            encode(&[], &encoding_mat)
+        );
+    }
+
+    #[test]
+    fn decoding_into_writer() {
+        let mut rng = ark_std::test_rng();
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+
+        // NOTE: the large data is decoded in several batches
+        let large = (0..(3 << 20) + 17).map(|_| rng.gen()).collect::<Vec<u8>>();
+        for bytes in [bytes(), large] {
+            let shards = encode::<Fr>(&bytes, &encoding_mat).unwrap();
+
+            let mut decoded = vec![];
+            assert_eq!(
+                decode_into(&shards[1..4], &mut decoded).unwrap(),
+                bytes.len()
+            );
+            assert_eq!(decoded, bytes);
+        }
+
+        let shards = encode::<Fr>(&bytes(), &encoding_mat).unwrap();
+        let mut decoded = vec![];
+        assert!(matches!(
+            decode_into(&shards[..2], &mut decoded),
+            Err(KomodoError::TooFewShards(2, 3, ..))
+        ));
+        assert!(decoded.is_empty());
+    }
+
+    #[test]
+    fn range() {
+        let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+        let mut rng = ark_std::test_rng();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut rng)).unwrap();
+        let points = (1..=5).map(to_curve::<Fr>).collect::<Vec<_>>();
+        let systematic =
+            encode_systematic::<Fr>(&bytes, &Matrix::vandermonde_unchecked(&points, 3)).unwrap();
+
+        let size = bytes.len();
+        for range in [
+            0..size,
+            0..1,
+            30..31,
+            31..32,
+            92..94,
+            1000..5000,
+            size - 1..size,
+            42..42,
+        ] {
+            for shards in [
+                &shards[2..],
+                &shards[..],
+                &systematic[..3],
+                &systematic[1..4],
+            ] {
+                assert_eq!(
+                    decode_range(shards, range.clone()).unwrap(),
+                    &bytes[range.clone()],
+                    "{range:?}"
+                );
+            }
+        }
+
+        assert!(matches!(
+            decode_range(&shards, 0..size + 1),
+            Err(KomodoError::InvalidByteRange(0, s, _)) if s == size + 1
+        ));
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 10..5;
+        assert!(matches!(
+            decode_range(&shards, reversed),
+            Err(KomodoError::InvalidByteRange(10, 5, _))
+        ));
+        assert!(matches!(
+            decode_range(&shards[..2], 0..10),
+            Err(KomodoError::TooFewShards(2, 3, ..))
+        ));
+
+        // NOTE: the size comes from the shards, the data is padded with zeros as with `decode`
+        let mut inflated = shards.clone();
+        inflated.iter_mut().for_each(|s| s.size = 10 * size);
+        let padded = decode(inflated.clone()).unwrap();
+        for range in [0..10 * size, size - 10..size + 10, 5 * size..5 * size + 10] {
+            assert_eq!(
+                decode_range(&inflated, range.clone()).unwrap(),
+                &padded[range.clone()],
+                "{range:?}"
+            );
+        }
+
+        let mut truncated = shards[2..].to_vec();
+        truncated[1].data.pop();
+        assert!(matches!(
+            decode_range(&truncated, 0..size),
+            Err(KomodoError::IncompatibleShards(..))
+        ));
+    }
+
+    #[test]
+    fn independent_subset() {
+        let bytes = bytes();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+        let twice = recode_with_coeffs(&shards[..1], &[to_curve(2)]).unwrap();
+        let recoded = recode_with_coeffs(&shards[..2], &[to_curve(2), to_curve(3)]).unwrap();
+
+        // NOTE: the first $k$ shards only span a space of dimension $1$, then $2$
+        let mixed = vec![
+            shards[0].clone(),
+            twice.clone(),
+            shards[0].clone(),
+            shards[1].clone(),
+            recoded.clone(),
+            twice,
+            shards[1].clone(),
+            recoded,
+            shards[3].clone(),
+        ];
+        assert_eq!(decode(mixed.clone()).unwrap(), bytes);
+        assert!(matches!(
+            decode(mixed[..8].to_vec()),
+            Err(KomodoError::LinearlyDependentShards(3, ..))
+        ));
+    }
+
+    #[test]
+    fn chunks() {
+        let mut bytes = bytes();
+        bytes.truncate(bytes.len() / 100 * 100);
+        // two identical chunks at the end
+        bytes.extend(vec![0; 2 * 100]);
+        let (k, n) = (3, 5);
+        let matrix = Matrix::<Fr>::random(k, n, &mut ark_std::test_rng());
+
+        let chunks = encode_chunks(&bytes[..], 100, &matrix)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(chunks.len(), bytes.len().div_ceil(100));
+        assert_eq!(chunks[0].shards, encode(&bytes[..100], &matrix).unwrap());
+        let (a, b) = (&chunks[chunks.len() - 2], &chunks[chunks.len() - 1]);
+        assert_eq!(a.data, b.data);
+        assert_ne!(a.shards[0].hash, b.shards[0].hash);
+        for chunk in &chunks {
+            assert_eq!(chunk.shards[0].hash, chunk_hash(chunk.index, &chunk.data));
+        }
+
+        // any k shards of each chunk are enough
+        let shards = chunks
+            .iter()
+            .flat_map(|c| c.shards[n - k..].to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(decode_chunks(shards).unwrap(), bytes);
+
+        assert!(encode_chunks(&[][..], 100, &matrix).next().is_none());
+        assert!(decode_chunks::<Fr>(vec![]).is_err());
+    }
 }
@@ -1,12 +1,17 @@
 use ark_ff::PrimeField;
 use ark_poly::DenseUVPolynomial;
-use ark_std::ops::Div;
-use rs_merkle::algorithms::Sha256;
+use ark_std::{ops::Div, rand::RngCore};
 use rs_merkle::Hasher;
-use std::rc::Rc;
+use std::{marker::PhantomData, rc::Rc};
 use tracing::{debug, info};
 
-use crate::{algebra, error::KomodoError, fec};
+use crate::{
+    algebra,
+    error::{DecodeContext, KomodoError},
+    fec,
+    pipeline::ProvingScheme,
+    telemetry::operation,
+};
 use dragoonfri::{
     frida::{FridaBuilder, FridaCommitment},
     interpolation::interpolate_polynomials,
@@ -55,8 +60,16 @@ pub fn encode<F: PrimeField>(
     evaluations: Vec<Vec<F>>,
     k: usize,
 ) -> Vec<fec::Shard<F>> {
-    let hash = Sha256::hash(bytes).to_vec();
+    encode_with_hash(bytes, evaluations, k, fec::content_hash(bytes))
+}
 
+/// the same as [`encode`] with a precomputed hash of the data, see [`fec::encode_with_hash`]
+pub fn encode_with_hash<F: PrimeField>(
+    bytes: &[u8],
+    evaluations: Vec<Vec<F>>,
+    k: usize,
+    hash: Vec<u8>,
+) -> Vec<fec::Shard<F>> {
     let n = evaluations[0].len();
 
     let t = transpose(evaluations);
@@ -84,6 +97,8 @@ where
     for<'a, 'b> &'a P: Div<&'b P, Output = P>,
     <H as rs_merkle::Hasher>::Hash: AsRef<[u8]>,
 {
+    let op = operation!("prove", "fri", shards = shards.len());
+
     let builder = FridaBuilder::<F, H>::new::<N, _>(
         &evaluations,
         FriChallenger::<H>::default(),
@@ -94,7 +109,7 @@ where
 
     let commit = Rc::new(FridaCommitment::from(builder.clone()));
 
-    Ok(shards
+    op.done(Ok(shards
         .iter()
         .enumerate()
         .map(|(i, s)| Block {
@@ -103,7 +118,7 @@ where
             commit: commit.clone(),
             position: i,
         })
-        .collect())
+        .collect()))
 }
 
 pub fn verify<const N: usize, F: PrimeField, H: Hasher, P>(
@@ -116,6 +131,8 @@ where
     for<'a, 'b> &'a P: Div<&'b P, Output = P>,
     <H as rs_merkle::Hasher>::Hash: AsRef<[u8]>,
 {
+    let op = operation!("verify", "fri", elements = block.shard.data.len());
+
     block
         .commit
         .verify::<N, _>(
@@ -133,7 +150,7 @@ where
         domain_size,
     ));
 
-    Ok(())
+    op.done(Ok(()))
 }
 
 pub fn decode<F: PrimeField, H: Hasher>(blocks: Vec<Block<F, H>>, n: usize) -> Vec<u8> {
@@ -154,6 +171,115 @@ pub fn decode<F: PrimeField, H: Hasher>(blocks: Vec<Block<F, H>>, n: usize) -> V
     bytes
 }
 
+/// FRI as a [`ProvingScheme`], with a folding factor of $N$
+///
+/// > **Note**
+/// >
+/// > FRI is transparent, there is no setup, and the blocks are always decoded with [`decode`].
+pub struct Fri<const N: usize, F, H, P> {
+    k: usize,
+    n: usize,
+    blowup_factor: usize,
+    remainder_plus_one: usize,
+    nb_queries: usize,
+    _phantom: PhantomData<(F, H, P)>,
+}
+
+impl<const N: usize, F, H, P> Fri<N, F, H, P> {
+    /// $k$ and $n$ should be powers of $2$, the rest are the parameters of [`prove`]
+    pub fn new(
+        k: usize,
+        n: usize,
+        blowup_factor: usize,
+        remainder_plus_one: usize,
+        nb_queries: usize,
+    ) -> Self {
+        Self {
+            k,
+            n,
+            blowup_factor,
+            remainder_plus_one,
+            nb_queries,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<const N: usize, F: PrimeField, H: Hasher, P> ProvingScheme for Fri<N, F, H, P>
+where
+    P: DenseUVPolynomial<F>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+    <H as rs_merkle::Hasher>::Hash: AsRef<[u8]>,
+{
+    type Field = F;
+    type SetupParams = ();
+    type Proof = Vec<Block<F, H>>;
+    type Block = Block<F, H>;
+
+    fn setup<R: RngCore>(&self, _nb_bytes: usize, _rng: &mut R) -> Result<(), KomodoError> {
+        Ok(())
+    }
+
+    fn encode(&self, bytes: &[u8]) -> Result<Vec<fec::Shard<F>>, KomodoError> {
+        Ok(encode(bytes, evaluate(bytes, self.k, self.n), self.k))
+    }
+
+    fn encode_with_hash(
+        &self,
+        bytes: &[u8],
+        hash: &[u8],
+    ) -> Result<Vec<fec::Shard<F>>, KomodoError> {
+        let evaluations = evaluate(bytes, self.k, self.n);
+        Ok(encode_with_hash(bytes, evaluations, self.k, hash.to_vec()))
+    }
+
+    // NOTE: the evaluations are not part of the shards and need to be computed again
+    fn prove(
+        &self,
+        bytes: &[u8],
+        shards: &[fec::Shard<F>],
+        _: &(),
+    ) -> Result<Vec<Block<F, H>>, KomodoError> {
+        prove::<N, F, H, P>(
+            evaluate(bytes, self.k, self.n),
+            shards.to_vec(),
+            self.blowup_factor,
+            self.remainder_plus_one,
+            self.nb_queries,
+        )
+    }
+
+    // NOTE: the blocks are built at the same time as the proofs
+    fn build(&self, _shards: Vec<fec::Shard<F>>, proof: Vec<Block<F, H>>) -> Vec<Block<F, H>> {
+        proof
+    }
+
+    fn verify(&self, block: &Block<F, H>, _: &()) -> Result<bool, KomodoError> {
+        verify::<N, F, H, P>(block.clone(), self.n, self.nb_queries)?;
+        Ok(true)
+    }
+
+    fn shard(block: &Block<F, H>) -> &fec::Shard<F> {
+        &block.shard
+    }
+
+    fn decode(&self, blocks: Vec<Block<F, H>>) -> Result<Vec<u8>, KomodoError> {
+        if blocks.len() < self.k {
+            // NOTE: all the blocks of FRI are at distinct positions, i.e. independent
+            let context = DecodeContext {
+                hash: blocks
+                    .first()
+                    .map(|b| b.shard.hash.clone())
+                    .unwrap_or_default(),
+                independent: (0..blocks.len()).collect(),
+                dependent: vec![],
+            };
+            return Err(KomodoError::TooFewShards(blocks.len(), self.k, context));
+        }
+        Ok(decode(blocks, self.n))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ark_ff::PrimeField;
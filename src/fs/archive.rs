@@ -0,0 +1,346 @@
+//! move a whole set of blocks, and optionally their manifest, as a single archive
+//!
+//! an archive is a simple container of files, where the files are stored exactly as they are on
+//! the disk, i.e. with their [`super::Header`] and their compression or encryption, if any:
+//! ```text
+//! | magic (4 bytes) | version as a u32 LE (4 bytes) | entry | ... | entry | end (1 byte) |
+//! ```
+//! where each entry is
+//! ```text
+//! | kind (1 byte) | name | size as a u64 LE (8 bytes) | content (size bytes) |
+//! ```
+//! and the name is serialized with `ark-serialize`.
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use tracing::{info, warn};
+
+use super::{
+    decoded, encryption, format, index, write_atomically, Encryption, HashingWriter, Layout,
+};
+
+/// the magic number at the start of all the archives
+const ARCHIVE_MAGIC: [u8; 4] = *b"KMDA";
+/// the current version of the archive format
+const ARCHIVE_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: u8 = 0;
+const BLOCK_ENTRY: u8 = 1;
+const END: u8 = 0xff;
+
+/// what has been found in an archive by [`import_archive`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ArchiveContent {
+    /// the name of the manifest of the archive, if any
+    pub manifest: Option<String>,
+    /// the hashes of all the blocks of the archive, in order
+    pub blocks: Vec<String>,
+}
+
+fn write_entry(writer: &mut impl Write, kind: u8, name: &str, path: &Path) -> Result<()> {
+    let mut file = File::open(path)?;
+    writer.write_all(&[kind])?;
+    name.to_string()
+        .serialize_with_mode(&mut *writer, Compress::No)?;
+    writer.write_all(&file.metadata()?.len().to_le_bytes())?;
+    std::io::copy(&mut file, writer)?;
+    Ok(())
+}
+
+/// write the blocks of `block_dir` and, if given, a manifest into a single archive
+///
+/// > **Note**
+/// >
+/// > the blocks are copied as is, without being deserialized, see [`import_archive`] to get them
+/// > back.
+pub fn export_archive(
+    block_hashes: &[String],
+    block_dir: &Path,
+    layout: Layout,
+    manifest: Option<&Path>,
+    mut writer: impl Write,
+) -> Result<()> {
+    info!("exporting {} blocks to an archive", block_hashes.len());
+    writer.write_all(&ARCHIVE_MAGIC)?;
+    writer.write_all(&ARCHIVE_VERSION.to_le_bytes())?;
+
+    if let Some(path) = manifest {
+        let name = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("`{:?}` is not a manifest", path))?;
+        write_entry(&mut writer, MANIFEST_ENTRY, &name, path)?;
+    }
+    for block_hash in block_hashes {
+        let path = layout.find(block_dir, block_hash);
+        write_entry(&mut writer, BLOCK_ENTRY, block_hash, &path)
+            .map_err(|e| anyhow!("could not export block `{}`: {}", block_hash, e))?;
+    }
+
+    writer.write_all(&[END])?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// make sure that the content of a block matches its name
+///
+/// encrypted blocks cannot be checked without their key and are trusted.
+fn check_block(block_hash: &str, bytes: &[u8]) -> Result<()> {
+    let name = format!("block `{}`", block_hash);
+    let (header, mut reader) = format::open_reader(bytes, &name)?;
+    if encryption::is_encrypted(&mut reader)? {
+        warn!("{} is encrypted and cannot be checked", name);
+        return Ok(());
+    }
+
+    let naming = header
+        .as_ref()
+        .map(|h| h.extension.naming)
+        .unwrap_or_default();
+    let mut hasher = HashingWriter::new(std::io::sink(), naming.algorithm);
+    std::io::copy(
+        &mut decoded(reader, &header, Encryption::None)?,
+        &mut hasher,
+    )?;
+    let actual = naming.format(&hasher.into_parts().1);
+    if actual != block_hash {
+        return Err(anyhow!("{} is corrupted, its hash is {}", name, actual));
+    }
+
+    Ok(())
+}
+
+/// a name coming from an archive must be a plain file name, so that an archive can never write
+/// outside of the target directories
+fn check_name(name: &str) -> Result<()> {
+    let is_plain = !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\'])
+        && Path::new(name).file_name().is_some_and(|f| f == name);
+    if !is_plain {
+        return Err(anyhow!("invalid entry name in archive: {:?}", name));
+    }
+    Ok(())
+}
+
+/// extract an archive written by [`export_archive`]
+///
+/// - the blocks are checked against their hash and stored in `block_dir` according to `layout`,
+///   the blocks that are already present are left untouched
+/// - the manifest, if any, is stored in `manifest_dir`
+///
+/// the entries are imported one after the other: an invalid entry stops the import, but the
+/// entries before it are kept.
+pub fn import_archive(
+    mut reader: impl Read,
+    block_dir: &Path,
+    layout: Layout,
+    manifest_dir: &Path,
+) -> Result<ArchiveContent> {
+    info!("importing an archive into `{:?}`", block_dir);
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != ARCHIVE_MAGIC {
+        return Err(anyhow!("not a Komodo archive"));
+    }
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != ARCHIVE_VERSION {
+        return Err(anyhow!(
+            "archive has version {}, only version {} is supported",
+            version,
+            ARCHIVE_VERSION
+        ));
+    }
+
+    let mut content = ArchiveContent::default();
+    loop {
+        let mut kind = [0; 1];
+        reader.read_exact(&mut kind)?;
+        if kind[0] == END {
+            break;
+        }
+
+        let name = String::deserialize_with_mode(&mut reader, Compress::No, Validate::Yes)?;
+        check_name(&name)?;
+        let mut size = [0; 8];
+        reader.read_exact(&mut size)?;
+        let mut bytes = vec![];
+        (&mut reader)
+            .take(u64::from_le_bytes(size))
+            .read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != u64::from_le_bytes(size) {
+            return Err(anyhow!("archive is truncated"));
+        }
+
+        match kind[0] {
+            MANIFEST_ENTRY => {
+                std::fs::create_dir_all(manifest_dir)?;
+                write_atomically(&manifest_dir.join(&name), &bytes)?;
+                content.manifest = Some(name);
+            }
+            BLOCK_ENTRY => {
+                check_block(&name, &bytes)?;
+                if !layout.find(block_dir, &name).is_file() {
+                    let path = layout.path(block_dir, &name);
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    write_atomically(&path, &bytes)?;
+                    index::record(block_dir, layout, &name)?;
+                }
+                content.blocks.push(name);
+            }
+            k => return Err(anyhow!("unknown entry kind in archive: {}", k)),
+        }
+    }
+
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use ark_bls12_381::Fr;
+    use ark_serialize::{Compress, Validate};
+
+    use crate::{
+        algebra::linalg::Matrix,
+        fec::{encode, Shard},
+        fs::{dump_block, read_blocks, Compression, Encryption, Header, Layout, Manifest},
+    };
+
+    use super::{check_name, export_archive, import_archive};
+
+    #[test]
+    fn archive() {
+        let dir = test_dir("archive");
+        let (block_dir, manifest_dir) = (dir.join("blocks"), dir.join("manifests"));
+
+        let bytes = include_bytes!("../../assets/dragoon_32x32.png").to_vec();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+        let block_hashes = shards
+            .iter()
+            .map(|s| {
+                dump_block(
+                    s,
+                    &block_dir,
+                    Layout::Flat,
+                    &Header::new("test", "BLS12-381", &[]),
+                    Compress::Yes,
+                    Compression::None,
+                    Encryption::None,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let manifest = Manifest {
+            file_hash: shards[0].hash.clone(),
+            size: bytes.len(),
+            k: 3,
+            n: 5,
+            curve: "BLS12-381".to_string(),
+            setup_digest: vec![],
+            block_hashes: block_hashes.clone(),
+            sealing: None,
+        };
+        let manifest_name = manifest.save(&manifest_dir, Compress::Yes).unwrap();
+
+        let mut archive = vec![];
+        export_archive(
+            &block_hashes,
+            &block_dir,
+            Layout::Flat,
+            Some(&manifest_dir.join(&manifest_name)),
+            &mut archive,
+        )
+        .unwrap();
+
+        // the archive can be imported elsewhere, with another layout
+        let other = dir.join("other");
+        let layout = Layout::Sharded { depth: 1, width: 2 };
+        let content = import_archive(
+            &archive[..],
+            &other.join("blocks"),
+            layout,
+            &other.join("manifests"),
+        )
+        .unwrap();
+        assert_eq!(content.manifest, Some(manifest_name.clone()));
+        assert_eq!(content.blocks, block_hashes);
+        assert_eq!(
+            Manifest::load(
+                &manifest_name,
+                &other.join("manifests"),
+                Compress::Yes,
+                Validate::Yes
+            )
+            .unwrap(),
+            manifest
+        );
+        assert!(manifest
+            .verify(&other.join("blocks"), layout)
+            .unwrap()
+            .is_complete());
+        let actual = read_blocks::<Shard<Fr>>(
+            &block_hashes,
+            &other.join("blocks"),
+            layout,
+            Compress::Yes,
+            Validate::Yes,
+            Encryption::None,
+        )
+        .unwrap();
+        assert_eq!(
+            actual.into_iter().map(|(_, s)| s).collect::<Vec<_>>(),
+            shards
+        );
+
+        // importing twice is fine
+        import_archive(
+            &archive[..],
+            &other.join("blocks"),
+            layout,
+            &other.join("manifests"),
+        )
+        .unwrap();
+
+        // corrupted and truncated archives are rejected
+        let import = |archive: &[u8]| {
+            let target = dir.join("rejected");
+            let _ = std::fs::remove_dir_all(&target);
+            import_archive(archive, &target, Layout::Flat, &target)
+        };
+        let mut corrupted = archive.clone();
+        let n = corrupted.len();
+        corrupted[n - 2] ^= 1;
+        assert!(import(&corrupted).is_err());
+        assert!(import(&archive[..archive.len() - 1]).is_err());
+        assert!(import(&archive[4..]).is_err());
+
+        // unknown blocks cannot be exported
+        assert!(export_archive(
+            &["does-not-exist".to_string()],
+            &block_dir,
+            Layout::Flat,
+            None,
+            &mut vec![],
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn entry_names() {
+        assert!(check_name("aaaa").is_ok());
+        for name in ["", ".", "..", ".hidden", "../aaaa", "a/b", "/aaaa", "a\\b"] {
+            assert!(check_name(name).is_err(), "{:?}", name);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! optional encryption at rest of the payloads written by [`crate::fs`]
+//!
+//! when a file is encrypted, its payload, possibly compressed, is replaced by
+//! ```text
+//! | magic (4 bytes) | nonce (24 bytes) | ciphertext and tag |
+//! ```
+//! and the header of the file, which stays in clear so that files can still be listed, is used
+//! as the _associated data_ of the encryption, i.e. it cannot be tampered with either.
+//!
+//! > **Note**
+//! >
+//! > the name of an encrypted file is still the hash of its serialized bytes, so that an
+//! > encrypted block has the same name as its clear counterpart. Computing or checking that hash,
+//! > e.g. with [`super::scrub`] or [`super::Manifest::verify`], requires to decrypt the file,
+//! > which is not possible without the key.
+use std::io::{BufRead, Read};
+
+use anyhow::{anyhow, Result};
+
+use super::Header;
+
+/// the magic number at the start of all the encrypted payloads
+pub(super) const ENCRYPTION_MAGIC: [u8; 4] = *b"KMDE";
+
+/// a 256-bit secret key, provided by the caller
+///
+/// > **Note**
+/// >
+/// > the key is never printed, even in debug mode.
+#[cfg(feature = "encryption")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Key(pub [u8; 32]);
+
+#[cfg(feature = "encryption")]
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Key(<redacted>)")
+    }
+}
+
+/// how the payloads are encrypted on the disk
+///
+/// > **Note**
+/// >
+/// > encryption requires the whole payload to be held in memory, in opposition to
+/// > [`super::Compression`] which works on the fly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encryption {
+    /// the payloads are written in clear
+    #[default]
+    None,
+    /// the payloads are encrypted with XChaCha20-Poly1305, whose 192-bit nonces can safely be
+    /// drawn at random for every file
+    #[cfg(feature = "encryption")]
+    XChaCha20Poly1305(Key),
+}
+
+/// whether the payload at the start of `reader` is encrypted
+pub(super) fn is_encrypted(reader: &mut impl BufRead) -> Result<bool> {
+    Ok(reader.fill_buf()?.starts_with(&ENCRYPTION_MAGIC))
+}
+
+/// the associated data of the encryption, i.e. the serialized header
+#[cfg(feature = "encryption")]
+fn associated_data(header: &Header) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    header.write(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// encrypt a payload and prepend the magic number and the nonce
+#[cfg(feature = "encryption")]
+pub(super) fn encrypt(payload: &[u8], header: &Header, key: &Key) -> Result<Vec<u8>> {
+    use chacha20poly1305::{
+        aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+        XChaCha20Poly1305,
+    };
+
+    let cipher = XChaCha20Poly1305::new(&key.0.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: payload,
+                aad: &associated_data(header)?,
+            },
+        )
+        .map_err(|_| anyhow!("could not encrypt payload"))?;
+
+    Ok([&ENCRYPTION_MAGIC[..], nonce.as_slice(), &ciphertext[..]].concat())
+}
+
+/// decrypt the payload at the start of `reader`, which has been written by [`encrypt`]
+///
+/// it is an error if the key is wrong or if either the header or the payload have been tampered
+/// with.
+#[cfg_attr(not(feature = "encryption"), allow(unused_variables))]
+pub(super) fn decrypt(
+    mut reader: impl Read,
+    header: &Option<Header>,
+    encryption: Encryption,
+) -> Result<Vec<u8>> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+
+    match encryption {
+        Encryption::None => Err(anyhow!("payload is encrypted but no key has been given")),
+        #[cfg(feature = "encryption")]
+        Encryption::XChaCha20Poly1305(key) => {
+            use chacha20poly1305::{
+                aead::{Aead, KeyInit, Payload},
+                XChaCha20Poly1305, XNonce,
+            };
+
+            const NONCE_LEN: usize = 24;
+            let data = &data[ENCRYPTION_MAGIC.len()..];
+            if data.len() < NONCE_LEN {
+                return Err(anyhow!("encrypted payload is truncated"));
+            }
+            let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+
+            let header = header
+                .as_ref()
+                .ok_or_else(|| anyhow!("encrypted payload has no header"))?;
+            XChaCha20Poly1305::new(&key.0.into())
+                .decrypt(
+                    XNonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: &associated_data(header)?,
+                    },
+                )
+                .map_err(|_| anyhow!("could not decrypt payload, wrong key or tampered file"))
+        }
+    }
+}
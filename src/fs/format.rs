@@ -0,0 +1,547 @@
+//! the versioned on-disk format of the files written by [`crate::fs`]
+//!
+//! every file starts with a small uncompressed header, followed by the payload, i.e. the
+//! serialized object, which might be compressed, see [`super::Compression`]
+//! ```text
+//! | artifact header | extension version as a u32 LE (4 bytes) | k | file hash | naming (2 bytes) | payload |
+//! ```
+//! where the _artifact header_ is the [`crate::header::Header`] shared by all the artifacts of
+//! Komodo, i.e. with its magic number, its version, the protocol, the curve and the setup digest,
+//! and the other fields form the _extension_ of the files, serialized with `ark-serialize`.
+//!
+//! the versions of the [`Extension`] are
+//! - 1: no field, i.e. only the artifact header
+//! - 2: the $k$ and the file hash of blocks, so that the blocks of a directory can be listed
+//!   without deserializing them, see [`super::list_blocks`]
+//! - 3: the [`Naming`] of the file, i.e. how its name has been computed from its payload
+//!
+//! > **Note**
+//! >
+//! > the name of a file, i.e. its hash, only depends on its payload. This means that the header
+//! > of a file can be upgraded with [`migrate`] without changing its name nor invalidating any
+//! > [`super::Manifest`]. Whether a payload is encrypted is told by the payload itself, see
+//! > [`super::Encryption`].
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use tracing::{info, warn};
+
+use super::{encryption, walk_blocks, AtomicFile, Naming};
+use crate::{error::KomodoError, header};
+
+/// the current version of the [`Extension`]
+pub const EXTENSION_VERSION: u32 = 3;
+
+/// the fields of the header that are specific to the files written by [`crate::fs`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Extension {
+    /// the version of the extension, see [`EXTENSION_VERSION`]
+    pub version: u32,
+    /// the number of source shards if the payload is a block, $0$ otherwise
+    pub k: u32,
+    /// the hash of the original file if the payload is a block, empty otherwise, see
+    /// [`crate::fec::Shard::hash`]
+    pub file_hash: Vec<u8>,
+    /// how the name of the file is computed from its payload
+    pub naming: Naming,
+}
+
+/// the header of a file written by [`crate::fs`], describing its payload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// the protocol, the curve and the trusted setup of the payload
+    pub artifact: header::Header,
+    pub extension: Extension,
+}
+
+impl std::fmt::Display for Header {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}, extension version {}",
+            self.artifact, self.extension.version
+        )
+    }
+}
+
+impl Header {
+    /// a header with the current [`crate::header::VERSION`] and [`EXTENSION_VERSION`]
+    pub fn new(protocol: &str, curve: &str, setup_digest: &[u8]) -> Self {
+        Self {
+            artifact: header::Header::new(protocol.into(), curve, setup_digest),
+            extension: Extension {
+                version: EXTENSION_VERSION,
+                ..Extension::default()
+            },
+        }
+    }
+
+    /// the same header, for the blocks of a given file
+    pub fn for_blocks(self, k: u32, file_hash: &[u8]) -> Self {
+        Self {
+            extension: Extension {
+                k,
+                file_hash: file_hash.to_vec(),
+                ..self.extension
+            },
+            ..self
+        }
+    }
+
+    /// the same header, with another naming of the file
+    pub fn with_naming(self, naming: Naming) -> Self {
+        Self {
+            extension: Extension {
+                naming,
+                ..self.extension
+            },
+            ..self
+        }
+    }
+
+    /// check that the header of a file, i.e. `self`, has the protocol, the curve and the trusted
+    /// setup of the `expected` one, see [`crate::header::Header::check`]
+    ///
+    /// > **Note**
+    /// >
+    /// > the version of the extension is checked when the file is opened, because all the
+    /// > versions up to [`EXTENSION_VERSION`] can be read.
+    pub fn check(&self, expected: &Self) -> std::result::Result<(), KomodoError> {
+        self.artifact.check(&expected.artifact)
+    }
+
+    pub(super) fn write(&self, writer: &mut impl Write) -> Result<()> {
+        self.artifact.write(&mut *writer)?;
+        writer.write_all(&self.extension.version.to_le_bytes())?;
+        if self.extension.version >= 2 {
+            self.extension
+                .k
+                .serialize_with_mode(&mut *writer, Compress::No)?;
+            self.extension
+                .file_hash
+                .serialize_with_mode(&mut *writer, Compress::No)?;
+        }
+        if self.extension.version >= 3 {
+            writer.write_all(&self.extension.naming.to_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// consume the header at the start of `reader`, if any
+    ///
+    /// `None` means that the data has been written before the format was versioned.
+    pub(super) fn read(reader: &mut impl BufRead) -> Result<Option<Self>> {
+        if !reader.fill_buf()?.starts_with(&header::MAGIC) {
+            return Ok(None);
+        }
+        let artifact = header::Header::read(&mut *reader)?;
+
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        let (k, file_hash) = if version >= 2 {
+            <(u32, Vec<u8>)>::deserialize_with_mode(&mut *reader, Compress::No, Validate::Yes)?
+        } else {
+            (0, vec![])
+        };
+        let naming = if version >= 3 {
+            let mut naming = [0; 2];
+            reader.read_exact(&mut naming)?;
+            Naming::from_bytes(naming)?
+        } else {
+            Naming::default()
+        };
+
+        Ok(Some(Self {
+            artifact,
+            extension: Extension {
+                version,
+                k,
+                file_hash,
+                naming,
+            },
+        }))
+    }
+
+    /// read only the header of a file, `None` means that the file has no header
+    pub fn read_from(path: &Path) -> Result<Option<Self>> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+/// consume the header of some data and check that its version is supported
+///
+/// `name` is only used in the logs and errors.
+pub(super) fn open_reader<R: BufRead>(mut reader: R, name: &str) -> Result<(Option<Header>, R)> {
+    let header = Header::read(&mut reader)?;
+    match &header {
+        None => warn!(
+            "{} has no header, it has been written by an older version of Komodo, see `fs::migrate`",
+            name
+        ),
+        Some(h) if h.artifact.version > header::VERSION => {
+            return Err(anyhow::Error::new(KomodoError::VersionMismatch(
+                header::VERSION,
+                h.artifact.version,
+            ))
+            .context(format!("could not read {}", name)))
+        }
+        Some(h) if h.extension.version > EXTENSION_VERSION => {
+            return Err(anyhow::Error::new(KomodoError::VersionMismatch(
+                EXTENSION_VERSION,
+                h.extension.version,
+            ))
+            .context(format!("could not read {}", name)))
+        }
+        // NOTE: payloads never change from one version to the other, only headers do
+        Some(h) if h.extension.version < EXTENSION_VERSION => warn!(
+            "{} has been written with extension version {}, see `fs::migrate` to upgrade it to {}",
+            name, h.extension.version, EXTENSION_VERSION
+        ),
+        Some(_) => {}
+    }
+
+    Ok((header, reader))
+}
+
+/// open a file written by [`crate::fs`] and return its header together with a reader at the start
+/// of the payload
+pub(super) fn open(path: &Path) -> Result<(Option<Header>, BufReader<File>)> {
+    open_reader(BufReader::new(File::open(path)?), &format!("`{:?}`", path))
+}
+
+/// give some context to an error that happened while deserializing a payload
+pub(super) fn deserialization_error(
+    name: &str,
+    header: &Option<Header>,
+    error: impl std::fmt::Display,
+) -> anyhow::Error {
+    match header {
+        Some(header) => anyhow!("could not deserialize {} ({}): {}", name, header, error),
+        None => anyhow!(
+            "could not deserialize {} (no header, written by an older version of Komodo): {}",
+            name,
+            error
+        ),
+    }
+}
+
+/// a migration hook, upgrading the [`Extension`] of a file from version `v` to version `v + 1`
+///
+/// the hook is given the current header and the path to the file, to be able to read the payload
+/// if needed.
+///
+/// > **Note**
+/// >
+/// > hooks only upgrade headers, payloads are never rewritten so that the names of the files never
+/// > change.
+pub type MigrationHook = fn(Header, &Path) -> Result<Header>;
+
+/// the migration hooks, where the hook at index `i` upgrades from version `i + 1` to `i + 2`
+const MIGRATIONS: &[MigrationHook] = &[v1_to_v2, v2_to_v3];
+
+/// the $k$ and the file hash cannot be recovered without knowing the type of the payload, they
+/// are left unknown
+fn v1_to_v2(header: Header, _: &Path) -> Result<Header> {
+    Ok(Header {
+        extension: Extension {
+            version: 2,
+            k: 0,
+            file_hash: vec![],
+            ..header.extension
+        },
+        ..header
+    })
+}
+
+/// all the files written before version 3 have been named with the default [`Naming`]
+fn v2_to_v3(header: Header, _: &Path) -> Result<Header> {
+    Ok(Header {
+        extension: Extension {
+            version: 3,
+            naming: Naming::default(),
+            ..header.extension
+        },
+        ..header
+    })
+}
+
+/// upgrade all the files of a directory, and its subdirectories, to the current
+/// [`EXTENSION_VERSION`]
+///
+/// - files without any header, i.e. written before the format was versioned, get the `legacy`
+///   header, whose extension version is ignored
+/// - files with an older version go through the migration hooks one version after the other
+/// - files that are already up to date are left untouched
+/// - encrypted files are left untouched, because their header is authenticated, see
+///   [`super::Encryption`]
+///
+/// the number of files that have been rewritten is returned.
+pub fn migrate(dir: &Path, legacy: &Header) -> Result<usize> {
+    info!(
+        "migrating `{:?}` to extension version {}",
+        dir, EXTENSION_VERSION
+    );
+    let mut nb_migrated = 0;
+    for path in walk_blocks(dir)? {
+        let mut reader = BufReader::new(File::open(&path)?);
+        let mut header = match Header::read(&mut reader)? {
+            Some(h) if h.extension.version == EXTENSION_VERSION => continue,
+            Some(h) if h.extension.version > EXTENSION_VERSION => {
+                return Err(anyhow::Error::new(KomodoError::VersionMismatch(
+                    EXTENSION_VERSION,
+                    h.extension.version,
+                ))
+                .context(format!("could not migrate `{:?}`", path)))
+            }
+            Some(h) => h,
+            None => Header {
+                artifact: legacy.artifact.clone(),
+                extension: Extension {
+                    version: EXTENSION_VERSION,
+                    ..legacy.extension.clone()
+                },
+            },
+        };
+        if encryption::is_encrypted(&mut reader)? {
+            warn!(
+                "`{:?}` is encrypted, its header is authenticated and cannot be migrated",
+                path
+            );
+            continue;
+        }
+
+        while header.extension.version < EXTENSION_VERSION {
+            let hook = (header.extension.version as usize)
+                .checked_sub(1)
+                .and_then(|i| MIGRATIONS.get(i))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "no migration from extension version {}",
+                        header.extension.version
+                    )
+                })?;
+            header = hook(header, &path)?;
+        }
+
+        info!("migrating `{:?}`", path);
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut file = AtomicFile::create(dir)?;
+        header.write(&mut file)?;
+        std::io::copy(&mut reader, &mut file)?;
+        file.commit(&path)?;
+
+        nb_migrated += 1;
+    }
+
+    Ok(nb_migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use ark_serialize::{Compress, Validate};
+
+    use crate::{
+        error::KomodoError,
+        fs::{dump, hash_file, read, serialize, Compression, Encryption, Naming},
+    };
+
+    use super::{migrate, Extension, Header, EXTENSION_VERSION};
+    use crate::header;
+
+    #[test]
+    fn versioned_format() {
+        let dir = test_dir("format");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+
+        let header = Header::new("test", "BLS12-381", &[1, 2, 3]);
+        let value: Vec<u64> = vec![1, 2, 3, 4];
+
+        let filename = dump(
+            &value,
+            &dir,
+            None,
+            &header,
+            Compress::Yes,
+            Compression::None,
+            Encryption::None,
+        )
+        .unwrap();
+        // the name only depends on the payload
+        assert_eq!(
+            filename,
+            Naming::default().name(&serialize(&value, Compress::Yes).unwrap())
+        );
+        assert_eq!(hash_file(&dir.join(&filename)).unwrap(), filename);
+        assert_eq!(
+            Header::read_from(&dir.join(&filename)).unwrap(),
+            Some(header.clone())
+        );
+        assert_eq!(
+            read::<Vec<u64>>(
+                &dir.join(&filename),
+                Compress::Yes,
+                Validate::Yes,
+                Encryption::None
+            )
+            .unwrap(),
+            value
+        );
+
+        // the header tells whether the file can be used by a reader
+        let read_header = Header::read_from(&dir.join(&filename)).unwrap().unwrap();
+        assert!(read_header
+            .check(&Header::new("test", "BLS12-381", &[]))
+            .is_ok());
+        assert_eq!(
+            read_header.check(&Header::new("test", "BN254", &[1, 2, 3])),
+            Err(KomodoError::CurveMismatch(
+                "BN254".to_string(),
+                "BLS12-381".to_string()
+            ))
+        );
+        assert!(read_header
+            .check(&Header::new("kzg", "BLS12-381", &[1, 2, 3]))
+            .is_err());
+
+        // the header gives some context when the payload is not what is expected
+        let err = read::<(Vec<u64>, Vec<u64>)>(
+            &dir.join(&filename),
+            Compress::Yes,
+            Validate::Yes,
+            Encryption::None,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("protocol \"test\""), "{}", err);
+        assert!(err.contains("curve \"BLS12-381\""), "{}", err);
+
+        // files written by an older Komodo have no header, they can still be read...
+        let legacy = dir.join("sub").join(&filename);
+        std::fs::write(&legacy, serialize(&value, Compress::Yes).unwrap()).unwrap();
+        assert_eq!(Header::read_from(&legacy).unwrap(), None);
+        assert_eq!(
+            read::<Vec<u64>>(&legacy, Compress::Yes, Validate::Yes, Encryption::None).unwrap(),
+            value
+        );
+
+        // ... and migrated without changing their name
+        let legacy_header = Header::new("legacy", "BLS12-381", &[]);
+        assert_eq!(migrate(&dir, &legacy_header).unwrap(), 1);
+        assert_eq!(migrate(&dir, &legacy_header).unwrap(), 0);
+        assert_eq!(
+            Header::read_from(&legacy).unwrap(),
+            Some(legacy_header.clone())
+        );
+        assert_eq!(hash_file(&legacy).unwrap(), filename);
+        assert_eq!(
+            read::<Vec<u64>>(&legacy, Compress::Yes, Validate::Yes, Encryption::None).unwrap(),
+            value
+        );
+
+        // files with an older version can still be read and are migrated with the hooks
+        let mut v1 = header.clone().for_blocks(3, &[4, 5, 6]);
+        v1.extension.version = 1;
+        dump(
+            &value,
+            &dir,
+            Some("v1"),
+            &v1,
+            Compress::Yes,
+            Compression::None,
+            Encryption::None,
+        )
+        .unwrap();
+        v1.extension = Extension {
+            version: 1,
+            ..Extension::default()
+        };
+        assert_eq!(
+            Header::read_from(&dir.join("v1")).unwrap(),
+            Some(v1.clone())
+        );
+        assert_eq!(
+            read::<Vec<u64>>(
+                &dir.join("v1"),
+                Compress::Yes,
+                Validate::Yes,
+                Encryption::None
+            )
+            .unwrap(),
+            value
+        );
+        assert_eq!(migrate(&dir, &legacy_header).unwrap(), 1);
+        assert_eq!(
+            Header::read_from(&dir.join("v1")).unwrap(),
+            Some(Header {
+                extension: Extension {
+                    version: EXTENSION_VERSION,
+                    ..v1.extension
+                },
+                ..v1
+            })
+        );
+
+        // files from the future are rejected
+        let mut future = header.clone();
+        future.extension.version = EXTENSION_VERSION + 1;
+        dump(
+            &value,
+            &dir,
+            Some("future"),
+            &future,
+            Compress::Yes,
+            Compression::None,
+            Encryption::None,
+        )
+        .unwrap();
+        let err = read::<Vec<u64>>(
+            &dir.join("future"),
+            Compress::Yes,
+            Validate::Yes,
+            Encryption::None,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<KomodoError>(),
+            Some(&KomodoError::VersionMismatch(
+                EXTENSION_VERSION,
+                EXTENSION_VERSION + 1
+            ))
+        );
+        assert!(migrate(&dir, &future).is_err());
+
+        let mut future = header;
+        future.artifact.version = header::VERSION + 1;
+        dump(
+            &value,
+            &dir,
+            Some("future-artifact"),
+            &future,
+            Compress::Yes,
+            Compression::None,
+            Encryption::None,
+        )
+        .unwrap();
+        let err = read::<Vec<u64>>(
+            &dir.join("future-artifact"),
+            Compress::Yes,
+            Validate::Yes,
+            Encryption::None,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<KomodoError>(),
+            Some(&KomodoError::VersionMismatch(
+                header::VERSION,
+                header::VERSION + 1
+            ))
+        );
+    }
+}
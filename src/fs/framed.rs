@@ -0,0 +1,211 @@
+//! framed Semi-AVID blocks, where the shard and the proof can be read independently
+//!
+//! the payload of a framed block, i.e. after its [`Header`], is laid out as follows
+//! ```text
+//! | magic (4 bytes) | length of the shard section as a u64 LE (8 bytes) | shard | proof |
+//! ```
+//! which allows a decode-only workflow to only read the shard, with [`read_shard`], and a
+//! verify-only audit to only read the proof, with [`read_proof`], without parsing the other
+//! section.
+//!
+//! > **Note**
+//! >
+//! > framed blocks are never compressed on the disk, because that would prevent seeking to the
+//! > proof section.
+use std::{
+    fs::File,
+    io::{BufReader, Read, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use tracing::info;
+
+use super::{format, index, quota, AtomicFile, HashingWriter, Header, Layout};
+use crate::{fec::Shard, semi_avid::Block, zk::Commitment};
+
+/// the magic number at the start of all the framed blocks
+pub(super) const FRAME_MAGIC: [u8; 4] = *b"KMDF";
+
+/// dump a Semi-AVID block as a framed block and return its hash
+///
+/// > **Note**
+/// >
+/// > as with [`super::dump_block`], the block is named after the hash of its payload, i.e. of
+/// > the whole frame.
+pub fn dump_framed_block<F, G>(
+    block: &Block<F, G>,
+    block_dir: &Path,
+    layout: Layout,
+    header: &Header,
+    compress: Compress,
+) -> Result<String>
+where
+    F: PrimeField,
+    G: CurveGroup<ScalarField = F>,
+{
+    std::fs::create_dir_all(block_dir)?;
+    let mut file = AtomicFile::create(block_dir)?;
+    header.write(&mut file)?;
+    let mut writer = HashingWriter::new(&mut file, header.extension.naming.algorithm);
+
+    writer.write_all(&FRAME_MAGIC)?;
+    writer.write_all(&(block.shard.serialized_size(compress) as u64).to_le_bytes())?;
+    block.shard.serialize_with_mode(&mut writer, compress)?;
+    block.proof.serialize_with_mode(&mut writer, compress)?;
+    let hash = header.extension.naming.format(&writer.into_parts().1);
+
+    if layout.find(block_dir, &hash).is_file() {
+        info!("framed block `{}` is already present", hash);
+        return Ok(hash);
+    }
+
+    let path = layout.path(block_dir, &hash);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let lock = quota::reserve(block_dir, file.len()?)?;
+    info!("dumping framed block into `{:?}`", path);
+    file.commit(&path)?;
+    drop(lock);
+    index::record(block_dir, layout, &hash)?;
+
+    Ok(hash)
+}
+
+/// open a framed block and return a reader positioned at the start of the shard section,
+/// together with the length of that section
+fn open_frame(
+    block_hash: &str,
+    block_dir: &Path,
+    layout: Layout,
+) -> Result<(BufReader<File>, u64)> {
+    let path = layout.find(block_dir, block_hash);
+    let (_, mut reader) = format::open(&path)?;
+
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != FRAME_MAGIC {
+        return Err(anyhow!("`{:?}` is not a framed block", path));
+    }
+
+    let mut shard_len = [0; 8];
+    reader.read_exact(&mut shard_len)?;
+
+    Ok((reader, u64::from_le_bytes(shard_len)))
+}
+
+/// read only the shard of a framed block
+pub fn read_shard<F: PrimeField>(
+    block_hash: &str,
+    block_dir: &Path,
+    layout: Layout,
+    compress: Compress,
+    validate: Validate,
+) -> Result<Shard<F>> {
+    let (reader, shard_len) = open_frame(block_hash, block_dir, layout)?;
+    Ok(Shard::deserialize_with_mode(
+        reader.take(shard_len),
+        compress,
+        validate,
+    )?)
+}
+
+/// read only the proof of a framed block, i.e. the commitments
+pub fn read_proof<F, G>(
+    block_hash: &str,
+    block_dir: &Path,
+    layout: Layout,
+    compress: Compress,
+    validate: Validate,
+) -> Result<Vec<Commitment<F, G>>>
+where
+    F: PrimeField,
+    G: CurveGroup<ScalarField = F>,
+{
+    let (mut reader, shard_len) = open_frame(block_hash, block_dir, layout)?;
+    reader.seek_relative(shard_len as i64)?;
+    Ok(Vec::deserialize_with_mode(reader, compress, validate)?)
+}
+
+/// read a whole framed block
+pub fn read_framed_block<F, G>(
+    block_hash: &str,
+    block_dir: &Path,
+    layout: Layout,
+    compress: Compress,
+    validate: Validate,
+) -> Result<Block<F, G>>
+where
+    F: PrimeField,
+    G: CurveGroup<ScalarField = F>,
+{
+    let (mut reader, shard_len) = open_frame(block_hash, block_dir, layout)?;
+    let shard = Shard::deserialize_with_mode((&mut reader).take(shard_len), compress, validate)?;
+    let proof = Vec::deserialize_with_mode(reader, compress, validate)?;
+    Ok(Block { shard, proof })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_serialize::{Compress, Validate};
+
+    use crate::{
+        algebra::linalg::Matrix,
+        fec::encode,
+        fs::{dump_block, hash_file, Compression, Encryption, Header, Layout},
+        semi_avid::{build, prove},
+        zk::setup,
+    };
+
+    use super::{dump_framed_block, read_framed_block, read_proof, read_shard};
+
+    #[test]
+    fn partial_reads() {
+        let mut rng = ark_std::test_rng();
+        let bytes = include_bytes!("../../assets/dragoon_32x32.png").to_vec();
+        let dir = test_dir("framed");
+
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+        let shards = encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        let powers = setup::<Fr, G1Projective>(bytes.len(), &mut rng).unwrap();
+        let proof = prove::<Fr, G1Projective, DensePolynomial<Fr>>(&bytes, &powers, 3).unwrap();
+        let blocks = build::<Fr, G1Projective, DensePolynomial<Fr>>(&shards, &proof);
+
+        let layout = Layout::Sharded { depth: 1, width: 2 };
+        let header = Header::new("semi-avid", "BLS12-381", &[]);
+        for compress in [Compress::Yes, Compress::No] {
+            for block in &blocks {
+                let h = dump_framed_block(block, &dir, layout, &header, compress).unwrap();
+                assert_eq!(hash_file(&layout.path(&dir, &h)).unwrap(), h);
+
+                let shard = read_shard::<Fr>(&h, &dir, layout, compress, Validate::Yes).unwrap();
+                assert_eq!(shard, block.shard);
+                let actual =
+                    read_proof::<Fr, G1Projective>(&h, &dir, layout, compress, Validate::Yes);
+                assert_eq!(actual.unwrap(), block.proof);
+                let actual = read_framed_block(&h, &dir, layout, compress, Validate::Yes);
+                assert_eq!(&actual.unwrap(), block);
+            }
+        }
+
+        // a block that is not framed
+        let h = dump_block(
+            &blocks[0],
+            &dir,
+            layout,
+            &header,
+            Compress::Yes,
+            Compression::None,
+            Encryption::None,
+        )
+        .unwrap();
+        assert!(read_shard::<Fr>(&h, &dir, layout, Compress::Yes, Validate::Yes).is_err());
+    }
+}
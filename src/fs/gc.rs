@@ -0,0 +1,307 @@
+//! reclaim the space of blocks that are not needed anymore
+//!
+//! the blocks of a block directory are reference counted from the [`Manifest`]s of a manifest
+//! directory: a block that is listed by no manifest is an _orphan_, e.g. because the file it
+//! belongs to has been deleted or superseded, and can be collected by [`gc`]. Manifests can
+//! themselves expire after some time, see [`RetentionPolicy::ttl`].
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use ark_serialize::{Compress, Validate};
+use tracing::info;
+
+use super::{delete_block, walk_blocks, Layout, Manifest};
+
+/// how [`gc`] decides which blocks and manifests to keep
+#[derive(Clone, PartialEq)]
+pub struct RetentionPolicy {
+    /// the directory of the manifests that reference the blocks
+    pub manifest_dir: PathBuf,
+    /// the manifests older than this are deleted, and thus stop referencing their blocks, `None`
+    /// means that manifests never expire
+    pub ttl: Option<Duration>,
+    /// orphan blocks younger than this are kept, e.g. because they have just been dumped and the
+    /// manifest of their file has not been saved yet
+    pub grace_period: Duration,
+    /// only report what would be collected, without removing anything
+    pub dry_run: bool,
+    pub layout: Layout,
+    pub compress: Compress,
+    pub validate: Validate,
+}
+
+impl RetentionPolicy {
+    /// the default policy, where manifests never expire and orphan blocks are collected after
+    /// one hour
+    pub fn new(manifest_dir: &Path) -> Self {
+        Self {
+            manifest_dir: manifest_dir.to_path_buf(),
+            ttl: None,
+            grace_period: Duration::from_secs(60 * 60),
+            dry_run: false,
+            layout: Layout::default(),
+            compress: Compress::Yes,
+            validate: Validate::Yes,
+        }
+    }
+}
+
+/// the result of a [`gc`] pass
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GcReport {
+    /// the manifests that have expired
+    pub expired_manifests: Vec<String>,
+    /// the orphan blocks that have been collected
+    pub collected: Vec<String>,
+    /// the blocks that are still there, either referenced or in their grace period
+    pub kept: Vec<String>,
+    /// the number of bytes that have been reclaimed
+    pub reclaimed: u64,
+}
+
+fn age(path: &Path) -> Result<Duration> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    Ok(SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default())
+}
+
+/// count how many manifests of `manifest_dir` reference each block
+///
+/// > **Note**
+/// >
+/// > a manifest that cannot be loaded is an error, because the blocks it references would
+/// > otherwise be seen as orphans.
+pub fn refcounts(
+    manifest_dir: &Path,
+    compress: Compress,
+    validate: Validate,
+) -> Result<BTreeMap<String, usize>> {
+    let mut refcounts = BTreeMap::new();
+    for path in walk_blocks(manifest_dir)? {
+        let filename = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let manifest = Manifest::load(&filename, manifest_dir, compress, validate)?;
+        for block_hash in manifest.block_hashes {
+            *refcounts.entry(block_hash).or_insert(0) += 1;
+        }
+    }
+
+    Ok(refcounts)
+}
+
+/// collect the orphan blocks of `block_dir` according to a [`RetentionPolicy`]
+///
+/// - the manifests older than [`RetentionPolicy::ttl`] are deleted first
+/// - then the blocks of `block_dir` are reference counted from the remaining manifests, see
+///   [`refcounts`]
+/// - finally, the blocks that are referenced by no manifest and are older than
+///   [`RetentionPolicy::grace_period`] are deleted
+pub fn gc(block_dir: &Path, policy: &RetentionPolicy) -> Result<GcReport> {
+    info!("collecting garbage in `{:?}`", block_dir);
+    let mut report = GcReport::default();
+
+    if let Some(ttl) = policy.ttl {
+        for path in walk_blocks(&policy.manifest_dir)? {
+            if age(&path)? < ttl {
+                continue;
+            }
+            let filename = path
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_default();
+            info!("manifest `{}` has expired", filename);
+            if !policy.dry_run {
+                std::fs::remove_file(&path)?;
+            }
+            report.expired_manifests.push(filename);
+        }
+    }
+
+    let mut refcounts = refcounts(&policy.manifest_dir, policy.compress, policy.validate)?;
+    if policy.dry_run {
+        for filename in &report.expired_manifests {
+            let manifest = Manifest::load(
+                filename,
+                &policy.manifest_dir,
+                policy.compress,
+                policy.validate,
+            )?;
+            for block_hash in manifest.block_hashes {
+                if let Some(count) = refcounts.get_mut(&block_hash) {
+                    *count -= 1;
+                }
+            }
+        }
+    }
+
+    for path in walk_blocks(block_dir)? {
+        let block_hash = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let is_referenced = refcounts.get(&block_hash).is_some_and(|c| *c > 0);
+        if is_referenced || age(&path)? < policy.grace_period {
+            report.kept.push(block_hash);
+            continue;
+        }
+
+        info!("collecting orphan block `{}`", block_hash);
+        report.reclaimed += std::fs::metadata(&path)?.len();
+        if !policy.dry_run {
+            delete_block(&block_hash, block_dir, policy.layout)?;
+        }
+        report.collected.push(block_hash);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use std::time::Duration;
+
+    use ark_bls12_381::Fr;
+    use ark_serialize::{Compress, Validate};
+
+    use crate::{
+        algebra::linalg::Matrix,
+        fec::encode,
+        fs::{dump_block, Compression, Encryption, Header, Layout, Manifest},
+    };
+
+    use super::{gc, refcounts, RetentionPolicy};
+
+    #[test]
+    fn garbage_collection() {
+        let dir = test_dir("gc");
+        let block_dir = dir.join("blocks");
+        let manifest_dir = dir.join("manifests");
+        let layout = Layout::Sharded { depth: 1, width: 2 };
+
+        let store_file = |bytes: &[u8]| {
+            let shards =
+                encode::<Fr>(bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+            let block_hashes = shards
+                .iter()
+                .map(|s| {
+                    dump_block(
+                        s,
+                        &block_dir,
+                        layout,
+                        &Header::new("test", "BLS12-381", &[]),
+                        Compress::Yes,
+                        Compression::None,
+                        Encryption::None,
+                    )
+                    .unwrap()
+                })
+                .collect::<Vec<_>>();
+            let manifest = Manifest {
+                file_hash: shards[0].hash.clone(),
+                size: bytes.len(),
+                k: 3,
+                n: 5,
+                curve: "BLS12-381".to_string(),
+                setup_digest: vec![],
+                block_hashes: block_hashes.clone(),
+                sealing: None,
+            };
+            (
+                manifest.save(&manifest_dir, Compress::Yes).unwrap(),
+                block_hashes,
+            )
+        };
+
+        let bytes = include_bytes!("../../assets/dragoon_32x32.png").to_vec();
+        let (_, blocks_a) = store_file(&bytes);
+        let (manifest_b, blocks_b) = store_file(&bytes.iter().map(|b| !b).collect::<Vec<_>>());
+        // orphan blocks, e.g. from a file that has been deleted
+        let (manifest_c, blocks_c) = store_file(&bytes[..100]);
+        std::fs::remove_file(manifest_dir.join(manifest_c)).unwrap();
+
+        let refs = refcounts(&manifest_dir, Compress::Yes, Validate::Yes).unwrap();
+        assert_eq!(refs.len(), 10);
+        assert!(refs.values().all(|c| *c == 1));
+
+        let policy = RetentionPolicy {
+            layout,
+            ..RetentionPolicy::new(&manifest_dir)
+        };
+
+        // orphans are protected by the grace period
+        let report = gc(&block_dir, &policy).unwrap();
+        assert!(report.collected.is_empty());
+        assert_eq!(report.kept.len(), 15);
+
+        let policy = RetentionPolicy {
+            grace_period: Duration::ZERO,
+            ..policy
+        };
+
+        // a dry run does not remove anything
+        let report = gc(
+            &block_dir,
+            &RetentionPolicy {
+                dry_run: true,
+                ..policy.clone()
+            },
+        )
+        .unwrap();
+        let mut expected = blocks_c.clone();
+        expected.sort();
+        let mut collected = report.collected.clone();
+        collected.sort();
+        assert_eq!(collected, expected);
+        assert!(report.reclaimed > 0);
+        for h in &blocks_c {
+            assert!(layout.path(&block_dir, h).exists());
+        }
+
+        let report = gc(&block_dir, &policy).unwrap();
+        assert_eq!(report.collected.len(), 5);
+        assert_eq!(report.kept.len(), 10);
+        for h in &blocks_c {
+            assert!(!layout.path(&block_dir, h).exists());
+        }
+
+        // deleting a file is deleting its manifest
+        std::fs::remove_file(manifest_dir.join(manifest_b)).unwrap();
+        let report = gc(&block_dir, &policy).unwrap();
+        let mut collected = report.collected.clone();
+        collected.sort();
+        let mut expected = blocks_b.clone();
+        expected.sort();
+        assert_eq!(collected, expected);
+
+        // all the remaining manifests expire
+        let policy = RetentionPolicy {
+            ttl: Some(Duration::ZERO),
+            ..policy
+        };
+        let report = gc(
+            &block_dir,
+            &RetentionPolicy {
+                dry_run: true,
+                ..policy.clone()
+            },
+        )
+        .unwrap();
+        assert_eq!(report.expired_manifests.len(), 1);
+        assert_eq!(report.collected.len(), blocks_a.len());
+        assert!(manifest_dir.read_dir().unwrap().next().is_some());
+
+        let report = gc(&block_dir, &policy).unwrap();
+        assert_eq!(report.expired_manifests.len(), 1);
+        assert_eq!(report.collected.len(), blocks_a.len());
+        assert!(report.kept.is_empty());
+        assert!(manifest_dir.read_dir().unwrap().next().is_none());
+    }
+}
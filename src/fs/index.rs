@@ -0,0 +1,316 @@
+//! an inventory of the blocks of a block directory, built from their [`Header`] only
+//!
+//! [`list_blocks`] walks a directory and reads the header of every block, which is cheap but
+//! still touches every file. A node that needs to answer "what do I store for file X" often can
+//! opt in to a persistent [`Index`], which is then kept up to date by [`super::dump_block`],
+//! [`super::dump_framed_block`], [`super::delete_block`] and [`super::scrub`].
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{anyhow, Result};
+use tracing::info;
+
+use super::{walk_blocks, write_atomically, DirLock, Extension, Header, Layout};
+
+/// the name of the hidden file, inside the block directory, where the [`Index`] is persisted
+pub const INDEX_FILE: &str = ".index";
+
+/// the metadata of a stored block
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BlockInfo {
+    /// the hash of the block, i.e. its name on the disk
+    pub hash: String,
+    /// the size of the block on the disk, header included
+    pub size: u64,
+    /// the protocol of the block, empty if the block has no header
+    pub protocol: String,
+    /// the number of source shards, $0$ if unknown
+    pub k: u32,
+    /// the hash of the original file, empty if unknown
+    pub file_hash: Vec<u8>,
+}
+
+impl BlockInfo {
+    /// read the metadata of a block from its header, without reading its payload
+    pub fn read(path: &Path) -> Result<Self> {
+        let hash = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("`{:?}` is not a block", path))?;
+        let (protocol, extension) = match Header::read_from(path)? {
+            Some(header) => (header.artifact.protocol.to_string(), header.extension),
+            None => (String::new(), Extension::default()),
+        };
+
+        Ok(Self {
+            hash,
+            size: std::fs::metadata(path)?.len(),
+            protocol,
+            k: extension.k,
+            file_hash: extension.file_hash,
+        })
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.hash,
+            self.size,
+            self.k,
+            to_hex(&self.file_hash),
+            self.protocol
+        )
+    }
+
+    fn from_line(line: &str) -> Result<Self> {
+        let invalid = || anyhow!("invalid index entry: {:?}", line);
+        let fields = line.splitn(5, '\t').collect::<Vec<_>>();
+        let [hash, size, k, file_hash, protocol] = fields[..] else {
+            return Err(invalid());
+        };
+
+        Ok(Self {
+            hash: hash.to_string(),
+            size: size.parse().map_err(|_| invalid())?,
+            protocol: protocol.to_string(),
+            k: k.parse().map_err(|_| invalid())?,
+            file_hash: from_hex(file_hash).ok_or_else(invalid)?,
+        })
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// list the metadata of all the blocks of a block directory, whatever their layout, sorted by
+/// hash
+///
+/// > **Note**
+/// >
+/// > only the headers of the blocks are read.
+pub fn list_blocks(block_dir: &Path) -> Result<Vec<BlockInfo>> {
+    let mut blocks = walk_blocks(block_dir)?
+        .iter()
+        .map(|p| BlockInfo::read(p))
+        .collect::<Result<Vec<_>>>()?;
+    blocks.sort_by(|a, b| a.hash.cmp(&b.hash));
+
+    Ok(blocks)
+}
+
+/// a persistent index of the blocks of a block directory, stored in [`INDEX_FILE`]
+///
+/// the index is a simple text file with one block per line, see [`BlockInfo`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Index {
+    blocks: BTreeMap<String, BlockInfo>,
+}
+
+impl Index {
+    /// build the index of a block directory from scratch and persist it
+    ///
+    /// from now on, the index will be kept up to date by this module.
+    pub fn build(block_dir: &Path) -> Result<Self> {
+        info!("indexing `{:?}`", block_dir);
+        let _lock = DirLock::acquire(block_dir)?;
+        let index = Self {
+            blocks: list_blocks(block_dir)?
+                .into_iter()
+                .map(|b| (b.hash.clone(), b))
+                .collect(),
+        };
+        index.save(block_dir)?;
+
+        Ok(index)
+    }
+
+    /// load the index of a block directory, `None` means that the directory is not indexed
+    pub fn load(block_dir: &Path) -> Result<Option<Self>> {
+        let path = block_dir.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let blocks = std::fs::read_to_string(path)?
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(|l| BlockInfo::from_line(l).map(|b| (b.hash.clone(), b)))
+            .collect::<Result<_>>()?;
+
+        Ok(Some(Self { blocks }))
+    }
+
+    fn save(&self, block_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(block_dir)?;
+        let content = self
+            .blocks
+            .values()
+            .map(|b| format!("{}\n", b.to_line()))
+            .collect::<String>();
+        write_atomically(&block_dir.join(INDEX_FILE), content.as_bytes())
+    }
+
+    pub fn get(&self, block_hash: &str) -> Option<&BlockInfo> {
+        self.blocks.get(block_hash)
+    }
+
+    /// all the indexed blocks, sorted by hash
+    pub fn blocks(&self) -> impl Iterator<Item = &BlockInfo> {
+        self.blocks.values()
+    }
+
+    /// all the indexed blocks of a given file, see [`crate::fec::Shard::hash`]
+    pub fn blocks_of(&self, file_hash: &[u8]) -> Vec<&BlockInfo> {
+        self.blocks
+            .values()
+            .filter(|b| b.file_hash == file_hash)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+/// add a block that has just been written to the index of `block_dir`, if it is indexed
+pub(super) fn record(block_dir: &Path, layout: Layout, block_hash: &str) -> Result<()> {
+    if !block_dir.join(INDEX_FILE).exists() {
+        return Ok(());
+    }
+
+    let _lock = DirLock::acquire(block_dir)?;
+    if let Some(mut index) = Index::load(block_dir)? {
+        let info = BlockInfo::read(&layout.find(block_dir, block_hash))?;
+        index.blocks.insert(info.hash.clone(), info);
+        index.save(block_dir)?;
+    }
+    Ok(())
+}
+
+/// remove a block from the index of `block_dir`, if it is indexed
+pub(super) fn forget(block_dir: &Path, block_hash: &str) -> Result<()> {
+    if !block_dir.join(INDEX_FILE).exists() {
+        return Ok(());
+    }
+
+    let _lock = DirLock::acquire(block_dir)?;
+    if let Some(mut index) = Index::load(block_dir)? {
+        if index.blocks.remove(block_hash).is_some() {
+            index.save(block_dir)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use ark_bls12_381::Fr;
+    use ark_serialize::Compress;
+
+    use crate::{
+        algebra::linalg::Matrix,
+        fec::encode,
+        fs::{delete_block, dump_block, Compression, Encryption, Header, Layout},
+    };
+
+    use super::{from_hex, list_blocks, to_hex, BlockInfo, Index, INDEX_FILE};
+
+    #[test]
+    fn hex() {
+        let bytes = vec![0, 1, 15, 16, 255];
+        assert_eq!(to_hex(&bytes), "00010f10ff");
+        assert_eq!(from_hex(&to_hex(&bytes)), Some(bytes));
+        assert_eq!(from_hex(""), Some(vec![]));
+        assert_eq!(from_hex("0"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+
+    #[test]
+    fn block_index() {
+        let dir = test_dir("index");
+        let layout = Layout::Sharded { depth: 1, width: 2 };
+
+        let dump_file = |bytes: &[u8]| {
+            let shards =
+                encode::<Fr>(bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+            let header = Header::new("test", "BLS12-381", &[]).for_blocks(3, &shards[0].hash);
+            let hashes = shards
+                .iter()
+                .map(|s| {
+                    dump_block(
+                        s,
+                        &dir,
+                        layout,
+                        &header,
+                        Compress::Yes,
+                        Compression::None,
+                        Encryption::None,
+                    )
+                    .unwrap()
+                })
+                .collect::<Vec<_>>();
+            (shards[0].hash.clone(), hashes)
+        };
+
+        let bytes = include_bytes!("../../assets/dragoon_32x32.png").to_vec();
+        let (file_a, hashes_a) = dump_file(&bytes);
+
+        // listing only needs the headers
+        let blocks = list_blocks(&dir).unwrap();
+        assert_eq!(blocks.len(), 5);
+        for b in &blocks {
+            assert!(hashes_a.contains(&b.hash));
+            assert_eq!(b.protocol, "test");
+            assert_eq!(b.k, 3);
+            assert_eq!(b.file_hash, file_a);
+            assert_eq!(
+                b.size,
+                std::fs::metadata(layout.path(&dir, &b.hash)).unwrap().len()
+            );
+        }
+
+        // no index unless asked for
+        assert_eq!(Index::load(&dir).unwrap(), None);
+        let index = Index::build(&dir).unwrap();
+        assert!(dir.join(INDEX_FILE).exists());
+        assert_eq!(index.blocks().cloned().collect::<Vec<_>>(), blocks);
+        assert_eq!(Index::load(&dir).unwrap(), Some(index));
+
+        // the index follows the dumps and the deletions
+        let other = bytes.iter().map(|b| b.wrapping_add(1)).collect::<Vec<_>>();
+        let (file_b, hashes_b) = dump_file(&other);
+        delete_block(&hashes_a[0], &dir, layout).unwrap();
+
+        let index = Index::load(&dir).unwrap().unwrap();
+        assert_eq!(index.len(), 9);
+        assert_eq!(index.blocks_of(&file_a).len(), 4);
+        assert_eq!(index.blocks_of(&file_b).len(), 5);
+        assert!(index.get(&hashes_a[0]).is_none());
+        assert_eq!(index.get(&hashes_b[0]).map(|b| b.k), Some(3));
+        assert_eq!(
+            index.blocks().cloned().collect::<Vec<_>>(),
+            list_blocks(&dir).unwrap()
+        );
+
+        // the index file itself is not a block
+        assert!(list_blocks(&dir)
+            .unwrap()
+            .iter()
+            .all(|b: &BlockInfo| b.hash != INDEX_FILE));
+    }
+}
@@ -0,0 +1,417 @@
+//! content addressing compatible with [IPFS](https://ipfs.tech/), to reference and fetch the
+//! dispersed files through the existing IPFS infrastructure
+//!
+//! - any serialized object, e.g. a block or a [`Manifest`], has a [`Cid`], see [`cid`], which is
+//!   the name it gets when it is dumped with [`super::Naming::IPFS`]
+//! - the blocks of a file can be exported as an [IPLD](https://ipld.io/) DAG with [`export_dag`],
+//!   in a [CARv1](https://ipld.io/specs/transport/car/carv1/) file that can be imported with
+//!   `ipfs dag import`
+use std::{fmt, io::prelude::*, path::Path};
+
+use anyhow::{anyhow, Result};
+use ark_serialize::{CanonicalSerialize, Compress};
+use tracing::info;
+
+use super::{
+    decoded, format, naming::base32, serialize, Encryption, HashAlgorithm, Layout, Manifest,
+};
+
+/// a [CIDv1](https://github.com/multiformats/cid), i.e. a self-describing content identifier
+///
+/// a [`Cid`] is written as a lowercase base32 multibase string, e.g. `bafkrei...`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cid {
+    /// the [multicodec](https://github.com/multiformats/multicodec/blob/master/table.csv) of the
+    /// content, e.g. [`Cid::RAW`]
+    pub codec: u64,
+    /// the hash function of [`Cid::digest`]
+    pub algorithm: HashAlgorithm,
+    /// the hash of the content
+    pub digest: Vec<u8>,
+}
+
+const CID_VERSION: u64 = 0x01;
+
+impl Cid {
+    /// the multicodec of raw binary data
+    pub const RAW: u64 = 0x55;
+    /// the multicodec of DAG-CBOR
+    pub const DAG_CBOR: u64 = 0x71;
+
+    /// the CID of some content
+    pub fn new(codec: u64, algorithm: HashAlgorithm, bytes: &[u8]) -> Self {
+        Self {
+            codec,
+            algorithm,
+            digest: algorithm.digest(bytes),
+        }
+    }
+
+    /// the binary representation of the CID, i.e. its version, its codec and its multihash
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        varint(&mut bytes, CID_VERSION);
+        varint(&mut bytes, self.codec);
+        varint(&mut bytes, self.algorithm.multihash_code());
+        varint(&mut bytes, self.digest.len() as u64);
+        bytes.extend_from_slice(&self.digest);
+        bytes
+    }
+}
+
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "b{}", base32(&self.to_bytes()))
+    }
+}
+
+/// write an unsigned [varint](https://github.com/multiformats/unsigned-varint)
+fn varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// the CID of any serializable object, e.g. a block or a [`Manifest`], as a raw leaf hashed with
+/// SHA-256
+pub fn cid(serializable: &impl CanonicalSerialize, compress: Compress) -> Result<Cid> {
+    Ok(Cid::new(
+        Cid::RAW,
+        HashAlgorithm::Sha256,
+        &serialize(serializable, compress)?,
+    ))
+}
+
+/// the subset of the IPLD data model needed to describe a file
+enum Ipld {
+    Integer(u64),
+    Bytes(Vec<u8>),
+    String(String),
+    List(Vec<Ipld>),
+    Map(Vec<(&'static str, Ipld)>),
+    Link(Cid),
+}
+
+/// the CBOR tag of CIDs in DAG-CBOR
+const CID_TAG: u64 = 42;
+
+fn cbor_head(bytes: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    match value {
+        0..=23 => bytes.push(major | value as u8),
+        24..=0xff => bytes.extend_from_slice(&[major | 24, value as u8]),
+        0x100..=0xffff => {
+            bytes.push(major | 25);
+            bytes.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            bytes.push(major | 26);
+            bytes.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            bytes.push(major | 27);
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+impl Ipld {
+    /// encode the node in DAG-CBOR, i.e. canonical CBOR
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Ipld::Integer(i) => cbor_head(bytes, 0, *i),
+            Ipld::Bytes(b) => {
+                cbor_head(bytes, 2, b.len() as u64);
+                bytes.extend_from_slice(b);
+            }
+            Ipld::String(s) => {
+                cbor_head(bytes, 3, s.len() as u64);
+                bytes.extend_from_slice(s.as_bytes());
+            }
+            Ipld::List(l) => {
+                cbor_head(bytes, 4, l.len() as u64);
+                for node in l {
+                    node.encode(bytes);
+                }
+            }
+            Ipld::Map(m) => {
+                // NOTE: DAG-CBOR sorts the keys by length first, then bytewise
+                let mut entries = m.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(k, _)| (k.len(), *k));
+                cbor_head(bytes, 5, entries.len() as u64);
+                for (key, node) in entries {
+                    cbor_head(bytes, 3, key.len() as u64);
+                    bytes.extend_from_slice(key.as_bytes());
+                    node.encode(bytes);
+                }
+            }
+            Ipld::Link(cid) => {
+                cbor_head(bytes, 6, CID_TAG);
+                // NOTE: the binary CID is prefixed with the identity multibase, i.e. `0x00`
+                let cid = [&[0x00][..], &cid.to_bytes()].concat();
+                cbor_head(bytes, 2, cid.len() as u64);
+                bytes.extend_from_slice(&cid);
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.encode(&mut bytes);
+        bytes
+    }
+}
+
+/// read the payload of a file, decompressed
+fn read_payload(path: &Path) -> Result<Vec<u8>> {
+    let (header, reader) = format::open(path)?;
+    let mut payload = vec![];
+    decoded(reader, &header, Encryption::None)?.read_to_end(&mut payload)?;
+    Ok(payload)
+}
+
+/// write a section of a CAR file, i.e. a CID and its content, prefixed with their length
+fn write_section(writer: &mut impl Write, cid: &Cid, bytes: &[u8]) -> Result<()> {
+    let cid = cid.to_bytes();
+    let mut length = vec![];
+    varint(&mut length, (cid.len() + bytes.len()) as u64);
+    writer.write_all(&length)?;
+    writer.write_all(&cid)?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+/// export the blocks of a file from `block_dir` as an IPLD DAG, written in CARv1 to `writer`,
+/// and return the CID of its root
+///
+/// the CAR file contains the root node, the manifest, serialized with `compress`, and all the
+/// blocks of the manifest. The root is a [DAG-CBOR](https://ipld.io/specs/codecs/dag-cbor/spec/)
+/// node that describes the file and links to its manifest and to all its blocks, in order, as raw
+/// leaves:
+/// ```text
+/// {
+///     "k": k, "n": n, "size": size, "curve": curve,
+///     "blocks": [CID, ..., CID], "manifest": CID,
+///     "file_hash": bytes, "setup_digest": bytes,
+/// }
+/// ```
+///
+/// > **Note**
+/// >
+/// > the leaves are the serialized objects, i.e. the payloads of the files without their
+/// > [`Header`](super::Header) nor their compression. Encrypted blocks cannot be exported.
+/// >
+/// > the blocks are read twice, once to compute the root and once to export them, and are never
+/// > all held in memory at once.
+pub fn export_dag(
+    manifest: &Manifest,
+    block_dir: &Path,
+    layout: Layout,
+    compress: Compress,
+    mut writer: impl Write,
+) -> Result<Cid> {
+    info!(
+        "exporting {} blocks as an IPLD DAG",
+        manifest.block_hashes.len()
+    );
+    let payload = |block_hash: &String| {
+        read_payload(&layout.find(block_dir, block_hash))
+            .map_err(|e| anyhow!("could not export block `{}`: {}", block_hash, e))
+    };
+
+    let manifest_bytes = serialize(manifest, compress)?;
+    let manifest_cid = Cid::new(Cid::RAW, HashAlgorithm::Sha256, &manifest_bytes);
+    let block_cids = manifest
+        .block_hashes
+        .iter()
+        .map(|h| Ok(Cid::new(Cid::RAW, HashAlgorithm::Sha256, &payload(h)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let root = Ipld::Map(vec![
+        ("k", Ipld::Integer(manifest.k as u64)),
+        ("n", Ipld::Integer(manifest.n as u64)),
+        ("size", Ipld::Integer(manifest.size as u64)),
+        ("curve", Ipld::String(manifest.curve.clone())),
+        (
+            "blocks",
+            Ipld::List(block_cids.iter().cloned().map(Ipld::Link).collect()),
+        ),
+        ("manifest", Ipld::Link(manifest_cid.clone())),
+        ("file_hash", Ipld::Bytes(manifest.file_hash.clone())),
+        ("setup_digest", Ipld::Bytes(manifest.setup_digest.clone())),
+    ])
+    .to_bytes();
+    let root_cid = Cid::new(Cid::DAG_CBOR, HashAlgorithm::Sha256, &root);
+
+    let header = Ipld::Map(vec![
+        ("roots", Ipld::List(vec![Ipld::Link(root_cid.clone())])),
+        ("version", Ipld::Integer(1)),
+    ])
+    .to_bytes();
+    let mut length = vec![];
+    varint(&mut length, header.len() as u64);
+    writer.write_all(&length)?;
+    writer.write_all(&header)?;
+
+    write_section(&mut writer, &root_cid, &root)?;
+    write_section(&mut writer, &manifest_cid, &manifest_bytes)?;
+    // NOTE: the same block might be listed more than once, e.g. if two shards are equal
+    let mut written = std::collections::HashSet::new();
+    for (block_hash, cid) in manifest.block_hashes.iter().zip(block_cids) {
+        if written.insert(cid.clone()) {
+            write_section(&mut writer, &cid, &payload(block_hash)?)?;
+        }
+    }
+    writer.flush()?;
+
+    Ok(root_cid)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use ark_bls12_381::Fr;
+    use ark_serialize::Compress;
+
+    use crate::{
+        algebra::linalg::Matrix,
+        fec::encode,
+        fs::{
+            dump_block, Compression, Encryption, HashAlgorithm, Header, Layout, Manifest, Naming,
+        },
+    };
+
+    use super::{cid, export_dag, varint, Cid, Ipld};
+
+    fn read_varint(bytes: &mut &[u8]) -> u64 {
+        let (mut value, mut shift) = (0, 0);
+        loop {
+            let byte = bytes[0];
+            *bytes = &bytes[1..];
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    #[test]
+    fn encodings() {
+        for value in [0, 1, 0x7f, 0x80, 300, u64::MAX] {
+            let mut bytes = vec![];
+            varint(&mut bytes, value);
+            assert_eq!(read_varint(&mut &bytes[..]), value);
+        }
+        let mut bytes = vec![];
+        varint(&mut bytes, 300);
+        assert_eq!(bytes, [0xac, 0x02]);
+
+        assert_eq!(
+            Cid::new(Cid::RAW, HashAlgorithm::Sha256, b"").to_string(),
+            "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+        );
+        // NOTE: the CID of the empty DAG-CBOR map, as given by `ipfs dag put`
+        let empty = Ipld::Map(vec![]).to_bytes();
+        assert_eq!(empty, [0xa0]);
+        assert_eq!(
+            Cid::new(Cid::DAG_CBOR, HashAlgorithm::Sha256, &empty).to_string(),
+            "bafyreigbtj4x7ip5legnfznufuopl4sg4knzc2cof6duas4b3q2fy6swua"
+        );
+
+        assert_eq!(
+            Ipld::Map(vec![
+                ("bb", Ipld::Integer(500)),
+                ("a", Ipld::String("x".to_string())),
+                ("c", Ipld::List(vec![Ipld::Bytes(vec![1])])),
+            ])
+            .to_bytes(),
+            [
+                0xa3, 0x61, b'a', 0x61, b'x', 0x61, b'c', 0x81, 0x41, 1, 0x62, b'b', b'b', 0x19,
+                0x01, 0xf4
+            ]
+        );
+    }
+
+    #[test]
+    fn dag() {
+        let dir = test_dir("ipld");
+
+        let bytes = include_bytes!("../../assets/dragoon_32x32.png").to_vec();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+        let header = Header::new("test", "BLS12-381", &[]).with_naming(Naming::IPFS);
+        let block_hashes = shards
+            .iter()
+            .map(|s| {
+                dump_block(
+                    s,
+                    &dir,
+                    Layout::Flat,
+                    &header,
+                    Compress::Yes,
+                    Compression::None,
+                    Encryption::None,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        // NOTE: blocks named with the IPFS naming are named after their CID
+        for (shard, hash) in shards.iter().zip(&block_hashes) {
+            assert_eq!(&cid(shard, Compress::Yes).unwrap().to_string(), hash);
+        }
+        let manifest = Manifest {
+            file_hash: shards[0].hash.clone(),
+            size: bytes.len(),
+            k: 3,
+            n: 5,
+            curve: "BLS12-381".to_string(),
+            setup_digest: vec![],
+            block_hashes: block_hashes.clone(),
+            sealing: None,
+        };
+
+        let mut car = vec![];
+        let root = export_dag(&manifest, &dir, Layout::Flat, Compress::Yes, &mut car).unwrap();
+        assert_eq!(root.codec, Cid::DAG_CBOR);
+
+        let mut reader = &car[..];
+        let header_len = read_varint(&mut reader) as usize;
+        let header = Ipld::Map(vec![
+            ("roots", Ipld::List(vec![Ipld::Link(root.clone())])),
+            ("version", Ipld::Integer(1)),
+        ])
+        .to_bytes();
+        assert_eq!(&reader[..header_len], header);
+        reader = &reader[header_len..];
+
+        let mut cids = vec![];
+        while !reader.is_empty() {
+            let section_len = read_varint(&mut reader) as usize;
+            let (section, rest) = reader.split_at(section_len);
+            // NOTE: all the CIDs of the DAG are 36 bytes long, i.e. SHA-256 with small codecs
+            let (cid, content) = section.split_at(36);
+            let codec = if cids.is_empty() {
+                Cid::DAG_CBOR
+            } else {
+                Cid::RAW
+            };
+            assert_eq!(
+                cid,
+                Cid::new(codec, HashAlgorithm::Sha256, content).to_bytes()
+            );
+            cids.push(Cid::new(codec, HashAlgorithm::Sha256, content).to_string());
+            reader = rest;
+        }
+        assert_eq!(cids[0], root.to_string());
+        assert_eq!(cids[1], cid(&manifest, Compress::Yes).unwrap().to_string());
+        assert_eq!(cids[2..], block_hashes);
+
+        let corrupted = Manifest {
+            block_hashes: vec!["not-a-block".to_string()],
+            ..manifest
+        };
+        assert!(export_dag(&corrupted, &dir, Layout::Flat, Compress::Yes, vec![]).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! advisory locking of a directory between concurrent writers
+//!
+//! the lock is a hidden [`LOCK_FILE`] that is created with _create-new_ semantics, i.e. the
+//! creation fails if the file already exists, which is atomic on all the filesystems supported
+//! by the standard library, and removed when the lock is released.
+//!
+//! > **Note**
+//! >
+//! > the lock is only _advisory_: it protects the writers of this module against each other, not
+//! > against any other process touching the directory.
+use std::{
+    fs::OpenOptions,
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{anyhow, Result};
+use tracing::warn;
+
+/// the name of the hidden lock file, inside the locked directory
+pub const LOCK_FILE: &str = ".lock";
+
+/// a lock older than this is considered abandoned, e.g. because its owner has crashed, and is
+/// broken
+const STALE_AFTER: Duration = Duration::from_secs(60);
+/// how long [`DirLock::acquire`] waits for the lock before giving up
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// an exclusive lock on a directory, released when dropped
+#[derive(Debug)]
+pub struct DirLock {
+    path: PathBuf,
+}
+
+impl DirLock {
+    /// try to lock `dir` once, `None` means that the lock is already held
+    pub fn try_acquire(dir: &Path) -> Result<Option<Self>> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(LOCK_FILE);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                // NOTE: only useful to whoever needs to debug a lock
+                let _ = write!(file, "{}", std::process::id());
+                Ok(Some(Self { path }))
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let age = std::fs::metadata(&path)
+                    .and_then(|m| m.modified())
+                    .map(|m| SystemTime::now().duration_since(m).unwrap_or_default());
+                if age.is_ok_and(|a| a > STALE_AFTER) {
+                    warn!("breaking stale lock `{:?}`", path);
+                    let _ = std::fs::remove_file(&path);
+                }
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// lock `dir`, waiting for the current owner of the lock, if any, to release it
+    pub fn acquire(dir: &Path) -> Result<Self> {
+        let start = Instant::now();
+        let mut backoff = Duration::from_millis(1);
+        loop {
+            if let Some(lock) = Self::try_acquire(dir)? {
+                return Ok(lock);
+            }
+            if start.elapsed() > TIMEOUT {
+                return Err(anyhow!(
+                    "could not lock `{:?}` after {:?}",
+                    dir.join(LOCK_FILE),
+                    TIMEOUT
+                ));
+            }
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(Duration::from_millis(100));
+        }
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirLock, LOCK_FILE};
+    use crate::fs::test_dir;
+
+    #[test]
+    fn dir_lock() {
+        let dir = test_dir("lock");
+
+        let lock = DirLock::try_acquire(&dir).unwrap();
+        assert!(lock.is_some());
+        assert!(dir.join(LOCK_FILE).exists());
+        assert!(DirLock::try_acquire(&dir).unwrap().is_none());
+
+        drop(lock);
+        assert!(!dir.join(LOCK_FILE).exists());
+        let lock = DirLock::acquire(&dir).unwrap();
+
+        // the lock is released by another thread while this one waits for it
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                drop(lock);
+            });
+            let _lock = DirLock::acquire(&dir).unwrap();
+        });
+        assert!(!dir.join(LOCK_FILE).exists());
+    }
+}
@@ -0,0 +1,246 @@
+//! zero-copy reads of the shard of a block
+//!
+//! a [`ShardView`] sits on top of the raw bytes of a block, e.g. a memory-mapped file with
+//! [`map_shard`], and only deserializes the elements of the shard when they are used, instead of
+//! copying and deserializing the whole shard into heap buffers up front.
+//!
+//! > **Note**
+//! >
+//! > only the blocks whose payload starts with a shard can be viewed, i.e. plain blocks of
+//! > [`crate::semi_avid`] or [`crate::fec`] and framed blocks, see [`super::dump_framed_block`].
+//! > Compressed and encrypted blocks have to be read with [`super::read_blocks`].
+use std::{marker::PhantomData, ops::Deref};
+
+use anyhow::{anyhow, Result};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, Compress, Validate};
+
+use super::{encryption::ENCRYPTION_MAGIC, format, framed::FRAME_MAGIC, ZSTD_MAGIC};
+use crate::fec::Shard;
+
+/// a lazy view on the shard of a block, backed by the raw bytes of the block
+///
+/// the small fields of the shard are deserialized once, when the view is created, but the
+/// elements of [`Shard::data`] are only deserialized by [`ShardView::get`] and
+/// [`ShardView::iter`].
+pub struct ShardView<F: PrimeField, B: Deref<Target = [u8]>> {
+    bytes: B,
+    compress: Compress,
+    validate: Validate,
+    /// see [`Shard::k`]
+    pub k: u32,
+    /// see [`Shard::linear_combination`]
+    pub linear_combination: Vec<F>,
+    /// see [`Shard::hash`]
+    pub hash: Vec<u8>,
+    /// see [`Shard::size`]
+    pub size: usize,
+    /// the offset of the first element of the shard in `bytes`
+    offset: usize,
+    /// the number of elements in the shard
+    len: usize,
+    /// the size of a single serialized element
+    element_size: usize,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField, B: Deref<Target = [u8]>> ShardView<F, B> {
+    /// create a view on the raw bytes of a block, including its header
+    pub fn new(bytes: B, compress: Compress, validate: Validate) -> Result<Self> {
+        let (_, payload) = format::open_reader(&bytes[..], "mapped block")?;
+        let mut reader = if payload.starts_with(&FRAME_MAGIC) {
+            &payload[FRAME_MAGIC.len() + 8..]
+        } else if payload.starts_with(&ZSTD_MAGIC) || payload.starts_with(&ENCRYPTION_MAGIC) {
+            return Err(anyhow!("compressed or encrypted blocks cannot be mapped"));
+        } else {
+            payload
+        };
+
+        let (k, linear_combination, hash, len) =
+            <(u32, Vec<F>, Vec<u8>, u64)>::deserialize_with_mode(&mut reader, compress, validate)?;
+        let offset = bytes.len() - reader.len();
+        let element_size = F::zero().serialized_size(compress);
+        let end = (len as usize)
+            .checked_mul(element_size)
+            .and_then(|s| s.checked_add(offset))
+            .filter(|end| *end <= bytes.len())
+            .ok_or_else(|| anyhow!("mapped block is truncated"))?;
+        let size = usize::deserialize_with_mode(&bytes[end..], compress, validate)?;
+
+        Ok(Self {
+            bytes,
+            compress,
+            validate,
+            k,
+            linear_combination,
+            hash,
+            size,
+            offset,
+            len: len as usize,
+            element_size,
+            _field: PhantomData,
+        })
+    }
+
+    /// the number of elements in the shard
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// deserialize the element at `index` in the shard
+    pub fn get(&self, index: usize) -> Result<F> {
+        if index >= self.len {
+            return Err(anyhow!(
+                "index {} out of bounds for a shard of {} elements",
+                index,
+                self.len
+            ));
+        }
+
+        let start = self.offset + index * self.element_size;
+        Ok(F::deserialize_with_mode(
+            &self.bytes[start..start + self.element_size],
+            self.compress,
+            self.validate,
+        )?)
+    }
+
+    /// deserialize the elements of the shard one after the other
+    pub fn iter(&self) -> impl Iterator<Item = Result<F>> + '_ {
+        (0..self.len).map(|i| self.get(i))
+    }
+
+    /// deserialize the whole shard
+    pub fn to_shard(&self) -> Result<Shard<F>> {
+        Ok(Shard {
+            k: self.k,
+            linear_combination: self.linear_combination.clone(),
+            hash: self.hash.clone(),
+            data: self.iter().collect::<Result<_>>()?,
+            size: self.size,
+        })
+    }
+}
+
+/// map a block into memory and view its shard, see [`ShardView`]
+///
+/// > **Note**
+/// >
+/// > the block must not be modified while it is mapped, which is the case of all the blocks
+/// > written by [`crate::fs`], because they are never written in place, see
+/// > [`super::write_atomically`].
+#[cfg(feature = "mmap")]
+pub fn map_shard<F: PrimeField>(
+    block_hash: &str,
+    block_dir: &std::path::Path,
+    layout: super::Layout,
+    compress: Compress,
+    validate: Validate,
+) -> Result<ShardView<F, memmap2::Mmap>> {
+    let file = std::fs::File::open(layout.find(block_dir, block_hash))?;
+    // SAFETY: the files of a block directory are only ever replaced atomically, never modified
+    // in place
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    ShardView::new(map, compress, validate)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_serialize::{Compress, Validate};
+
+    use crate::{
+        algebra::linalg::Matrix,
+        fec::encode,
+        fs::{dump_block, dump_framed_block, Compression, Encryption, Header, Layout},
+        semi_avid::{build, prove},
+        zk::setup,
+    };
+
+    use super::ShardView;
+
+    #[test]
+    fn shard_view() {
+        let mut rng = ark_std::test_rng();
+        let bytes = include_bytes!("../../assets/dragoon_32x32.png").to_vec();
+        let dir = test_dir("mapped");
+
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut rng)).unwrap();
+        let powers = setup::<Fr, G1Projective>(bytes.len(), &mut rng).unwrap();
+        let proof = prove::<Fr, G1Projective, DensePolynomial<Fr>>(&bytes, &powers, 3).unwrap();
+        let blocks = build::<Fr, G1Projective, DensePolynomial<Fr>>(&shards, &proof);
+
+        let header = Header::new("test", "BLS12-381", &[]);
+        for compress in [Compress::Yes, Compress::No] {
+            let plain = dump_block(
+                &blocks[0],
+                &dir,
+                Layout::Flat,
+                &header,
+                compress,
+                Compression::None,
+                Encryption::None,
+            )
+            .unwrap();
+            let framed =
+                dump_framed_block(&blocks[0], &dir, Layout::Flat, &header, compress).unwrap();
+
+            for hash in [plain, framed] {
+                let content = std::fs::read(dir.join(hash)).unwrap();
+                let view = ShardView::<Fr, _>::new(content, compress, Validate::Yes).unwrap();
+                assert_eq!(view.len(), shards[0].data.len());
+                assert_eq!(view.get(1).unwrap(), shards[0].data[1]);
+                assert!(view.get(view.len()).is_err());
+                assert_eq!(view.to_shard().unwrap(), shards[0]);
+            }
+        }
+
+        // a truncated block cannot be viewed
+        let content = std::fs::read(
+            std::fs::read_dir(&dir)
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap()
+                .path(),
+        )
+        .unwrap();
+        assert!(ShardView::<Fr, _>::new(
+            &content[..content.len() / 2],
+            Compress::Yes,
+            Validate::Yes
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped_shard() {
+        use super::map_shard;
+
+        let dir = test_dir("mmap");
+
+        let bytes = include_bytes!("../../assets/dragoon_32x32.png").to_vec();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+        let layout = Layout::Sharded { depth: 1, width: 2 };
+        let hash = dump_block(
+            &shards[0],
+            &dir,
+            layout,
+            &Header::new("test", "BLS12-381", &[]),
+            Compress::Yes,
+            Compression::None,
+            Encryption::None,
+        )
+        .unwrap();
+
+        let view = map_shard::<Fr>(&hash, &dir, layout, Compress::Yes, Validate::Yes).unwrap();
+        assert_eq!(view.to_shard().unwrap(), shards[0]);
+    }
+}
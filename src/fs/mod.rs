@@ -0,0 +1,1883 @@
+//! interact with the filesystem, read from and write to it
+//!
+//! the functions of this module are generic over the type of the blocks, i.e. any type that is
+//! [`CanonicalSerialize`] and [`CanonicalDeserialize`] can be stored and loaded, e.g. the blocks
+//! of [`crate::semi_avid`], `kzg` or `aplonk`.
+//!
+//! a directory of blocks can be made self-describing with a [`Manifest`], the blocks can be
+//! spread across subdirectories with a sharded [`Layout`] and their integrity can be checked
+//! periodically with [`scrub`]. The blocks that are not referenced by any manifest anymore can be
+//! collected with [`gc`], a whole set of blocks can be moved at once with [`export_archive`] and
+//! the disk usage of a directory can be reported and capped, see [`usage`] and [`set_quota`].
+//! Semi-AVID blocks can also be stored as _framed_ blocks, see
+//! [`dump_framed_block`], to read only their shard or their proof, and the shard of a block can be
+//! read lazily, without copying it, with a [`ShardView`].
+//!
+//! all the files written by this module start with a versioned [`Header`], i.e. the
+//! [`crate::header::Header`] of all the artifacts followed by an [`Extension`], describing their
+//! content, and are named after the hash of their content, see [`Naming`] to use other hash
+//! functions and encodings, e.g. the CIDs of IPFS. The blocks of a file can also be exported as an
+//! IPLD DAG, to be served by IPFS, see [`export_dag`].
+//!
+//! the functions of this module work with the local filesystem only, see [`store`] to persist
+//! blocks with other storage backends.
+//!
+//! # Concurrency
+//! multiple threads or processes can dump blocks into the same directory at the same time:
+//! - all the files are written to unique temporary files first and then atomically renamed into
+//!   place, see [`write_atomically`], so no reader ever sees a partially written block
+//! - blocks are content-addressed, i.e. two writers racing on the same path write the same
+//!   block, and a block that is already present is never written again, see [`dump_block`]
+//! - the updates of the [`Index`] are serialized with a [`DirLock`]
+//!
+//! however, [`remove_partial_files`] and the maintenance passes, i.e. [`migrate`],
+//! [`migrate_layout`], [`scrub`] and [`gc`], assume that they are the only ones writing to the
+//! directory, which can be enforced by holding a [`DirLock`] on another directory shared by all
+//! the processes.
+use std::{
+    fs::File,
+    io::{prelude::*, BufWriter},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+
+use anyhow::{anyhow, Result};
+use tracing::{info, warn};
+
+mod archive;
+mod encryption;
+mod format;
+mod framed;
+mod gc;
+mod index;
+mod ipld;
+mod lock;
+mod mapped;
+mod naming;
+mod quota;
+mod scrub;
+pub mod store;
+
+pub use archive::{export_archive, import_archive, ArchiveContent};
+pub use encryption::Encryption;
+#[cfg(feature = "encryption")]
+pub use encryption::Key;
+pub use format::{migrate, Extension, Header, MigrationHook, EXTENSION_VERSION};
+pub use framed::{dump_framed_block, read_framed_block, read_proof, read_shard};
+pub use gc::{gc, refcounts, GcReport, RetentionPolicy};
+pub use index::{list_blocks, BlockInfo, Index, INDEX_FILE};
+pub use ipld::{cid, export_dag, Cid};
+pub use lock::{DirLock, LOCK_FILE};
+#[cfg(feature = "mmap")]
+pub use mapped::map_shard;
+pub use mapped::ShardView;
+use naming::Hasher;
+pub use naming::{Encoding, HashAlgorithm, Naming};
+pub use quota::{quota, set_quota, usage, QuotaExceeded, Usage, QUOTA_FILE};
+pub use scrub::{quarantined_path, scrub, ScrubIssue, ScrubReport, QUARANTINE_DIR};
+
+/// dump any serializable object to the disk
+///
+/// - `dumpable` can be anything that is _serializable_
+/// - if `filename` is provided, then it will be used as the filename as is
+/// - otherwise, the hash of the _dumpable_ will be computed and used as the
+///   filename
+///
+/// this function will return the name of the file the _dumpable_ has been
+/// dumped to.
+///
+/// > **Note**
+/// >
+/// > the _dumpable_ is serialized directly to the disk, it is never entirely held in memory.
+/// >
+/// > the hash is always the one of the serialized bytes, whatever the `compression`, the
+/// > `encryption` and the `header`, and is computed and formatted with the [`Extension::naming`].
+pub fn dump(
+    dumpable: &impl CanonicalSerialize,
+    dump_dir: &Path,
+    filename: Option<&str>,
+    header: &Header,
+    compress: Compress,
+    compression: Compression,
+    encryption: Encryption,
+) -> Result<String> {
+    info!("dumping dumpable into `{:?}`", dump_dir);
+    let mut file = AtomicFile::create(dump_dir)?;
+    let digest = write_file(
+        dumpable,
+        &mut file,
+        header,
+        compress,
+        compression,
+        encryption,
+    )?;
+
+    let filename = match filename {
+        Some(filename) => filename.to_string(),
+        None => header.extension.naming.format(&digest),
+    };
+
+    file.commit(&dump_dir.join(&filename))?;
+
+    Ok(filename)
+}
+
+/// read any deserializable object from the disk, e.g. one that has been written with [`dump`]
+///
+/// > **Note**
+/// >
+/// > the object is deserialized directly from the file, without reading all its bytes first.
+/// >
+/// > compressed files are detected and decompressed transparently, see [`Compression`].
+/// >
+/// > encrypted files are decrypted with the key of `encryption`, see [`Encryption`], and are read
+/// > entirely in memory first.
+pub fn read<T: CanonicalDeserialize>(
+    path: &Path,
+    compress: Compress,
+    validate: Validate,
+    encryption: Encryption,
+) -> Result<T> {
+    info!("reading `{:?}`", path);
+    let (header, reader) = format::open(path)?;
+    T::deserialize_with_mode(decoded(reader, &header, encryption)?, compress, validate)
+        .map_err(|e| format::deserialization_error(&format!("`{:?}`", path), &header, e))
+}
+
+/// how the serialized bytes are compressed on the disk
+///
+/// > **Note**
+/// >
+/// > this is not the same as [`Compress`], which compresses curve points when serializing them,
+/// > the two can be combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// the serialized bytes are written as is
+    #[default]
+    None,
+    /// the serialized bytes are compressed with [Zstandard](https://facebook.github.io/zstd/) at
+    /// the given level, e.g. `3` which is the default of `zstd`
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+/// the magic number at the start of any Zstandard frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// serialize into `writer`, compressing on the fly, and return the hash of the serialized bytes
+fn serialize_into(
+    serializable: &impl CanonicalSerialize,
+    writer: impl Write,
+    compress: Compress,
+    compression: Compression,
+    algorithm: HashAlgorithm,
+) -> Result<Vec<u8>> {
+    match compression {
+        Compression::None => {
+            let mut writer = HashingWriter::new(writer, algorithm);
+            serializable.serialize_with_mode(&mut writer, compress)?;
+            Ok(writer.into_parts().1)
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(level) => {
+            let encoder = zstd::stream::write::Encoder::new(writer, level)?;
+            let mut writer = HashingWriter::new(encoder, algorithm);
+            serializable.serialize_with_mode(&mut writer, compress)?;
+            let (encoder, digest) = writer.into_parts();
+            encoder.finish()?;
+            Ok(digest)
+        }
+    }
+}
+
+/// write the header of a file followed by its payload, and return the hash of the serialized
+/// bytes
+fn write_file(
+    serializable: &impl CanonicalSerialize,
+    writer: &mut impl Write,
+    header: &Header,
+    compress: Compress,
+    compression: Compression,
+    encryption: Encryption,
+) -> Result<Vec<u8>> {
+    header.write(writer)?;
+    match encryption {
+        Encryption::None => serialize_into(
+            serializable,
+            writer,
+            compress,
+            compression,
+            header.extension.naming.algorithm,
+        ),
+        #[cfg(feature = "encryption")]
+        Encryption::XChaCha20Poly1305(key) => {
+            let mut payload = vec![];
+            let digest = serialize_into(
+                serializable,
+                &mut payload,
+                compress,
+                compression,
+                header.extension.naming.algorithm,
+            )?;
+            writer.write_all(&encryption::encrypt(&payload, header, &key)?)?;
+            Ok(digest)
+        }
+    }
+}
+
+/// wrap the reader of a payload such that it is decrypted and decompressed, if needed
+fn decoded<'a, R: BufRead + 'a>(
+    mut reader: R,
+    header: &Option<Header>,
+    encryption: Encryption,
+) -> Result<Box<dyn Read + 'a>> {
+    if encryption::is_encrypted(&mut reader)? {
+        let payload = encryption::decrypt(reader, header, encryption)?;
+        return decompressed(std::io::Cursor::new(payload));
+    }
+
+    decompressed(reader)
+}
+
+/// wrap a reader such that compressed data is decompressed on the fly
+///
+/// the compression is detected with the magic number at the start of the data, anything else is
+/// read as is.
+fn decompressed<'a, R: BufRead + 'a>(mut reader: R) -> Result<Box<dyn Read + 'a>> {
+    if reader.fill_buf()?.starts_with(&ZSTD_MAGIC) {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(zstd::stream::read::Decoder::with_buffer(reader)?));
+        #[cfg(not(feature = "zstd"))]
+        return Err(anyhow!(
+            "data is compressed with Zstandard but the `zstd` feature is not enabled"
+        ));
+    }
+
+    Ok(Box::new(reader))
+}
+
+fn serialize(serializable: &impl CanonicalSerialize, compress: Compress) -> Result<Vec<u8>> {
+    let mut serialized = vec![0; serializable.serialized_size(compress)];
+    serializable.serialize_with_mode(&mut serialized[..], compress)?;
+    Ok(serialized)
+}
+
+/// compute the hash of the content of a file, without reading it entirely in memory
+///
+/// > **Note**
+/// >
+/// > only the payload is hashed, and compressed files are hashed as if they were not. Encrypted
+/// > files cannot be hashed.
+/// >
+/// > the hash is computed and formatted with the [`Naming`] of the file.
+fn hash_file(path: &Path) -> Result<String> {
+    let (header, reader) = format::open(path)?;
+    let naming = header
+        .as_ref()
+        .map(|h| h.extension.naming)
+        .unwrap_or_default();
+    let mut reader = decoded(reader, &header, Encryption::None)?;
+    let mut writer = HashingWriter::new(std::io::sink(), naming.algorithm);
+    std::io::copy(&mut reader, &mut writer)?;
+    Ok(naming.format(&writer.into_parts().1))
+}
+
+/// a writer that hashes everything that goes through it
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Hasher,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W, algorithm: HashAlgorithm) -> Self {
+        Self {
+            inner,
+            hasher: algorithm.hasher(),
+        }
+    }
+
+    /// get back the inner writer and the hash of everything that has been written
+    fn into_parts(self) -> (W, Vec<u8>) {
+        (self.inner, self.hasher.finalize().to_vec())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// the extension of the temporary files that are written to before being renamed into place
+const PARTIAL_EXTENSION: &str = "partial";
+
+/// a file that is written to a hidden temporary location and only appears at its final path
+/// once [`AtomicFile::commit`] has been called
+///
+/// the temporary file is removed if the [`AtomicFile`] is dropped without being committed.
+struct AtomicFile {
+    partial: PathBuf,
+    writer: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    /// create a new temporary file in `dir`, which should be on the same filesystem as the final
+    /// path of the file
+    fn create(dir: &Path) -> Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let partial = dir.join(format!(
+            ".{}-{}.{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            PARTIAL_EXTENSION
+        ));
+        let file = File::create(&partial)?;
+
+        Ok(Self {
+            partial,
+            writer: Some(BufWriter::new(file)),
+        })
+    }
+
+    /// the number of bytes written so far
+    fn len(&mut self) -> Result<u64> {
+        let writer = self
+            .writer
+            .as_mut()
+            .expect("writer is only taken on commit");
+        writer.flush()?;
+        Ok(writer.get_ref().metadata()?.len())
+    }
+
+    /// sync the file to the disk, move it to `path` and sync the parent directory
+    fn commit(mut self, path: &Path) -> Result<()> {
+        let file = self
+            .writer
+            .take()
+            .expect("writer is only taken on commit")
+            .into_inner()?;
+        file.sync_all()?;
+        drop(file);
+
+        std::fs::rename(&self.partial, path)?;
+
+        #[cfg(unix)]
+        if let Some(dir) = path.parent() {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            File::open(dir)?.sync_all()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer
+            .as_mut()
+            .expect("writer is only taken on commit")
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer
+            .as_mut()
+            .expect("writer is only taken on commit")
+            .flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() {
+            let _ = std::fs::remove_file(&self.partial);
+        }
+    }
+}
+
+/// write some bytes to a file such that a crash can never leave a partially written file behind
+///
+/// the bytes are first written to a hidden temporary file in the same directory, this file is
+/// synced to the disk and then renamed to `path`, which is atomic on POSIX filesystems. Finally,
+/// the parent directory is synced as well, so that the rename itself is durable.
+///
+/// > **Note**
+/// >
+/// > if the process crashes in the middle of this function, only the temporary file might remain,
+/// > see [`remove_partial_files`] to clean them up.
+pub fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut file = AtomicFile::create(dir)?;
+    file.write_all(bytes)?;
+    file.commit(path)
+}
+
+/// remove all the temporary files that a crash during [`write_atomically`] might have left
+/// behind in a directory
+///
+/// this should typically be called on startup, before reading anything from `dir`. The list of
+/// the files that have been removed is returned and an absent directory is not an error.
+pub fn remove_partial_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut removed = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_partial = path.is_file()
+            && path
+                .file_name()
+                .map(|f| f.to_string_lossy())
+                .is_some_and(|f| {
+                    f.starts_with('.') && f.ends_with(&format!(".{PARTIAL_EXTENSION}"))
+                });
+
+        if is_partial {
+            warn!("removing partially written file `{:?}`", path);
+            std::fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+
+    Ok(removed)
+}
+
+/// where blocks are stored inside a block directory
+///
+/// # Example
+/// a block whose hash is `abcdef` will be stored
+/// - at `blocks/abcdef` with [`Layout::Flat`]
+/// - at `blocks/ab/cd/abcdef` with `Layout::Sharded { depth: 2, width: 2 }`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// all the blocks are directly inside the block directory
+    #[default]
+    Flat,
+    /// the blocks are spread across `depth` levels of subdirectories, each one named after the
+    /// next `width` characters of the hash of the block
+    Sharded { depth: usize, width: usize },
+}
+
+impl Layout {
+    /// the path where a block should be stored in `block_dir` with this layout
+    pub fn path(&self, block_dir: &Path, block_hash: &str) -> PathBuf {
+        let mut path = block_dir.to_path_buf();
+        if let Layout::Sharded { depth, width } = *self {
+            for i in 0..depth {
+                match block_hash.get(i * width..(i + 1) * width) {
+                    Some(prefix) if width > 0 => path.push(prefix),
+                    _ => break,
+                }
+            }
+        }
+        path.join(block_hash)
+    }
+
+    /// find the path of a block in `block_dir`
+    ///
+    /// the block is first looked up with this layout and then as a flat block, so that stores
+    /// that have not been migrated yet, see [`migrate_layout`], can still be read transparently.
+    pub fn find(&self, block_dir: &Path, block_hash: &str) -> PathBuf {
+        let path = self.path(block_dir, block_hash);
+        if path.exists() {
+            return path;
+        }
+
+        let flat = Layout::Flat.path(block_dir, block_hash);
+        if flat.exists() {
+            flat
+        } else {
+            path
+        }
+    }
+}
+
+/// all the non-hidden files of `dir` and its subdirectories
+fn walk_blocks(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_hidden = path
+            .file_name()
+            .is_some_and(|f| f.to_string_lossy().starts_with('.'));
+        if is_hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            files.extend(walk_blocks(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// move all the blocks of `block_dir` to where they belong with the `to` layout
+///
+/// this works with any existing layout, e.g. an old flat store or even a partially migrated
+/// one, and the subdirectories that are left empty are removed. The number of blocks that have
+/// been moved is returned.
+pub fn migrate_layout(block_dir: &Path, to: Layout) -> Result<usize> {
+    info!("migrating blocks in `{:?}` to {:?}", block_dir, to);
+    let mut nb_moved = 0;
+    for path in walk_blocks(block_dir)? {
+        let block_hash = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let target = to.path(block_dir, &block_hash);
+        if path != target {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::rename(&path, &target)?;
+            nb_moved += 1;
+        }
+    }
+
+    remove_empty_dirs(block_dir)?;
+
+    Ok(nb_moved)
+}
+
+/// remove all the empty subdirectories of `dir`, but not `dir` itself
+fn remove_empty_dirs(dir: &Path) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            remove_empty_dirs(&path)?;
+            if std::fs::read_dir(&path)?.next().is_none() {
+                std::fs::remove_dir(&path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// dump a bunch of blocks to the disk and return a JSON / NUON compatible list
+/// of all the hashes that have been dumped
+///
+/// the blocks are named after their hash, as with [`dump`], and stored according to `layout`.
+///
+/// # Example
+/// let's say we give three blocks to [`dump_blocks`] and their hashes are `aaaa`, `bbbb` and
+/// `cccc` respectively, then this function will return
+/// ```json
+/// '["aaaa", "bbbb", "cccc"]'
+/// ```
+pub fn dump_blocks<B: CanonicalSerialize>(
+    blocks: &[B],
+    block_dir: &Path,
+    layout: Layout,
+    header: &Header,
+    compress: Compress,
+    compression: Compression,
+    encryption: Encryption,
+) -> Result<String> {
+    info!("dumping blocks to `{:?}`", block_dir);
+    let hashes = blocks
+        .iter()
+        .map(|b| {
+            dump_block(
+                b,
+                block_dir,
+                layout,
+                header,
+                compress,
+                compression,
+                encryption,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(format_hashes(&hashes))
+}
+
+/// dump a single block to the disk, according to `layout`, and return its hash
+///
+/// > **Note**
+/// >
+/// > this is idempotent: if the block is already present, whatever its layout, compression or
+/// > encryption, it is left untouched.
+/// >
+/// > a new block is not written if it would exceed the quota of `block_dir`, see [`set_quota`].
+pub fn dump_block<B: CanonicalSerialize>(
+    block: &B,
+    block_dir: &Path,
+    layout: Layout,
+    header: &Header,
+    compress: Compress,
+    compression: Compression,
+    encryption: Encryption,
+) -> Result<String> {
+    std::fs::create_dir_all(block_dir)?;
+    let mut file = AtomicFile::create(block_dir)?;
+    let hash = header.extension.naming.format(&write_file(
+        block,
+        &mut file,
+        header,
+        compress,
+        compression,
+        encryption,
+    )?);
+
+    if layout.find(block_dir, &hash).is_file() {
+        info!("block `{}` is already present", hash);
+        return Ok(hash);
+    }
+
+    let path = layout.path(block_dir, &hash);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let lock = quota::reserve(block_dir, file.len()?)?;
+    info!("dumping block into `{:?}`", path);
+    file.commit(&path)?;
+    drop(lock);
+    index::record(block_dir, layout, &hash)?;
+
+    Ok(hash)
+}
+
+/// remove a block from the disk, whatever its layout, does nothing if the block does not exist
+pub fn delete_block(block_hash: &str, block_dir: &Path, layout: Layout) -> Result<()> {
+    let path = layout.find(block_dir, block_hash);
+    if path.is_file() {
+        info!("deleting block `{:?}`", path);
+        std::fs::remove_file(path)?;
+    }
+    index::forget(block_dir, block_hash)
+}
+
+/// format a list of hashes as a JSON / NUON compatible list, as done by [`dump_blocks`]
+pub fn format_hashes(hashes: &[String]) -> String {
+    let mut formatted_output = String::from("[");
+    for hash in hashes {
+        formatted_output.push_str(&format!("{:?},", hash));
+    }
+    formatted_output.push(']');
+
+    formatted_output
+}
+
+/// read blocks from a list of block hashes
+///
+/// > **Note**
+/// >
+/// > this is a basically the inverse of [`dump_blocks`]
+///
+/// # Example
+/// let's say we have three blocks `A`, `B` and `C` whose hashes are `aaaa`, `bbbb` and `cccc`
+/// respectively.
+/// if one calls [`read_blocks`] with `aaaa` and `cccc` as the queried block hashes, the output of
+/// this function will be
+/// ```ignore
+/// Ok(vec![("aaaa", A), ("cccc", C)])
+/// ```
+///
+/// > **Note**
+/// >
+/// > the type of the blocks can be inferred most of the time, or given explicitely, e.g.
+/// > `read_blocks::<semi_avid::Block<F, G>>(...)`
+pub fn read_blocks<B: CanonicalDeserialize>(
+    block_hashes: &[String],
+    block_dir: &Path,
+    layout: Layout,
+    compress: Compress,
+    validate: Validate,
+    encryption: Encryption,
+) -> Result<Vec<(String, B)>> {
+    block_hashes
+        .iter()
+        .map(|f| {
+            Ok((
+                f.clone(),
+                read_block(f, block_dir, layout, compress, validate, encryption)?,
+            ))
+        })
+        .collect()
+}
+
+fn read_block<B: CanonicalDeserialize>(
+    block_hash: &str,
+    block_dir: &Path,
+    layout: Layout,
+    compress: Compress,
+    validate: Validate,
+    encryption: Encryption,
+) -> Result<B> {
+    read(
+        &layout.find(block_dir, block_hash),
+        compress,
+        validate,
+        encryption,
+    )
+}
+
+/// options to read a batch of blocks with [`read_blocks_batched`]
+#[derive(Clone, Copy)]
+pub struct ReadOptions {
+    pub compress: Compress,
+    /// blocks coming from a trusted local storage can skip the validation with [`Validate::No`],
+    /// which is much faster for curve points
+    pub validate: Validate,
+    /// the number of threads used to read and deserialize blocks concurrently
+    pub nb_threads: usize,
+    pub layout: Layout,
+    pub encryption: Encryption,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            compress: Compress::Yes,
+            validate: Validate::Yes,
+            nb_threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            layout: Layout::Flat,
+            encryption: Encryption::None,
+        }
+    }
+}
+
+/// read a batch of blocks in parallel
+///
+/// the blocks are split evenly between [`ReadOptions::nb_threads`] threads, each one doing the
+/// file IO and the deserialization of its blocks.
+///
+/// > **Note**
+/// >
+/// > in opposition to [`read_blocks`], a block that cannot be read does not make the whole
+/// > batch fail: each block comes with its own result, in the same order as `block_hashes`.
+pub fn read_blocks_batched<B: CanonicalDeserialize + Send>(
+    block_hashes: &[String],
+    block_dir: &Path,
+    options: &ReadOptions,
+) -> Vec<(String, Result<B>)> {
+    if block_hashes.is_empty() {
+        return vec![];
+    }
+
+    let nb_threads = options.nb_threads.clamp(1, block_hashes.len());
+    let chunk_size = block_hashes.len().div_ceil(nb_threads);
+    info!(
+        "reading {} blocks from `{:?}` with {} threads",
+        block_hashes.len(),
+        block_dir,
+        nb_threads
+    );
+
+    std::thread::scope(|scope| {
+        let handles = block_hashes
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|f| {
+                            let block = read_block(
+                                f,
+                                block_dir,
+                                options.layout,
+                                options.compress,
+                                options.validate,
+                                options.encryption,
+                            );
+                            (f.clone(), block)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("a block reading thread panicked"))
+            .collect()
+    })
+}
+
+/// describes a dispersed file and ties it to all the blocks that have been generated from it
+///
+/// a manifest makes a directory of blocks self-describing: given a manifest, one knows which
+/// file the blocks belong to, which code and trusted setup have been used to generate them and,
+/// with [`Manifest::verify`], which blocks are still missing and how many of them are required
+/// to be able to reconstruct the file.
+///
+/// > **Note**
+/// >
+/// > the [`Manifest::sealing`] is only serialized when there is one, so that the manifests of
+/// > files in clear are the same as before it existed. This means that a manifest must always be
+/// > the last value of the data it is deserialized from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    /// the hash of the original file, the same as [`crate::fec::Shard::hash`]
+    pub file_hash: Vec<u8>,
+    /// the size of the original file in bytes
+    pub size: usize,
+    /// the number of source shards, i.e. the minimum number of blocks to reconstruct the file
+    pub k: usize,
+    /// the total number of encoded blocks
+    pub n: usize,
+    /// the name of the curve that has been used, e.g. `"BLS12-381"`
+    pub curve: String,
+    /// the hash of the serialized trusted setup that has been used to prove the blocks
+    pub setup_digest: Vec<u8>,
+    /// the hashes of all the blocks, as returned by [`dump_blocks`]
+    pub block_hashes: Vec<String>,
+    /// how the file has been encrypted before being encoded, if it has been sealed
+    pub sealing: Option<Sealing>,
+}
+
+/// how the data of a file has been encrypted before being encoded, e.g. by the `sealed` module
+///
+/// > **Note**
+/// >
+/// > with a sealed file, [`Manifest::file_hash`] and [`Manifest::size`] are the ones of the
+/// > encrypted data, i.e. what the blocks encode.
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Sealing {
+    /// the nonce of the encryption
+    pub nonce: Vec<u8>,
+    /// the key encapsulated with HPKE for the recipient, empty if the key has been shared
+    /// directly
+    pub encapsulated_key: Vec<u8>,
+}
+
+/// the byte announcing a [`Sealing`] at the end of a serialized [`Manifest`]
+const SEALED: u8 = 1;
+
+impl CanonicalSerialize for Manifest {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.file_hash.serialize_with_mode(&mut writer, compress)?;
+        self.size.serialize_with_mode(&mut writer, compress)?;
+        self.k.serialize_with_mode(&mut writer, compress)?;
+        self.n.serialize_with_mode(&mut writer, compress)?;
+        self.curve.serialize_with_mode(&mut writer, compress)?;
+        self.setup_digest
+            .serialize_with_mode(&mut writer, compress)?;
+        self.block_hashes
+            .serialize_with_mode(&mut writer, compress)?;
+        if let Some(sealing) = &self.sealing {
+            writer.write_all(&[SEALED])?;
+            sealing.serialize_with_mode(&mut writer, compress)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.file_hash.serialized_size(compress)
+            + self.size.serialized_size(compress)
+            + self.k.serialized_size(compress)
+            + self.n.serialized_size(compress)
+            + self.curve.serialized_size(compress)
+            + self.setup_digest.serialized_size(compress)
+            + self.block_hashes.serialized_size(compress)
+            + self
+                .sealing
+                .as_ref()
+                .map_or(0, |s| 1 + s.serialized_size(compress))
+    }
+}
+
+impl Valid for Manifest {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for Manifest {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let file_hash = Vec::deserialize_with_mode(&mut reader, compress, validate)?;
+        let size = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let k = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let n = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let curve = String::deserialize_with_mode(&mut reader, compress, validate)?;
+        let setup_digest = Vec::deserialize_with_mode(&mut reader, compress, validate)?;
+        let block_hashes = Vec::deserialize_with_mode(&mut reader, compress, validate)?;
+
+        let mut tag = [0; 1];
+        let sealing = match reader.read(&mut tag)? {
+            0 => None,
+            _ if tag[0] == SEALED => Some(Sealing::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?),
+            _ => return Err(SerializationError::InvalidData),
+        };
+
+        Ok(Self {
+            file_hash,
+            size,
+            k,
+            n,
+            curve,
+            setup_digest,
+            block_hashes,
+            sealing,
+        })
+    }
+}
+
+/// the state of a directory of blocks with respect to a [`Manifest`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ManifestStatus {
+    /// the blocks of the manifest that are present and intact
+    pub present: Vec<String>,
+    /// the blocks of the manifest that cannot be found
+    pub missing: Vec<String>,
+    /// the blocks of the manifest whose content does not match their hash
+    pub corrupted: Vec<String>,
+    /// the number of blocks that are still needed to be able to reconstruct the file
+    pub nb_needed: usize,
+}
+
+impl ManifestStatus {
+    /// whether enough intact blocks are present to reconstruct the file
+    pub fn is_complete(&self) -> bool {
+        self.nb_needed == 0
+    }
+}
+
+impl Manifest {
+    /// compute the digest of a trusted setup to put in [`Manifest::setup_digest`]
+    pub fn setup_digest(setup: &impl CanonicalSerialize, compress: Compress) -> Result<Vec<u8>> {
+        serialize_into(
+            setup,
+            std::io::sink(),
+            compress,
+            Compression::None,
+            HashAlgorithm::Sha256,
+        )
+    }
+
+    /// the name of the manifest on the disk, derived from [`Manifest::file_hash`]
+    pub fn filename(&self) -> String {
+        self.file_hash
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect()
+    }
+
+    /// save the manifest to `manifest_dir`, creating it if necessary, and return the name of the
+    /// file
+    ///
+    /// > **Note**
+    /// >
+    /// > the manifest is named after the file it describes, see [`Manifest::filename`], so that
+    /// > it can be retrieved from the hash of the file only.
+    pub fn save(&self, manifest_dir: &Path, compress: Compress) -> Result<String> {
+        std::fs::create_dir_all(manifest_dir)?;
+        dump(
+            self,
+            manifest_dir,
+            Some(&self.filename()),
+            &Header::new("manifest", &self.curve, &self.setup_digest),
+            compress,
+            Compression::None,
+            Encryption::None,
+        )
+    }
+
+    /// load a manifest that has been saved with [`Manifest::save`]
+    pub fn load(
+        filename: &str,
+        manifest_dir: &Path,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self> {
+        read(
+            &manifest_dir.join(filename),
+            compress,
+            validate,
+            Encryption::None,
+        )
+    }
+
+    /// check the blocks of the manifest against the content of `block_dir`
+    ///
+    /// the content of each block file is hashed again to make sure it has not been corrupted.
+    pub fn verify(&self, block_dir: &Path, layout: Layout) -> Result<ManifestStatus> {
+        if self.block_hashes.len() != self.n {
+            return Err(anyhow!(
+                "manifest lists {} blocks but n = {}",
+                self.block_hashes.len(),
+                self.n
+            ));
+        }
+
+        let mut status = ManifestStatus::default();
+        for block_hash in &self.block_hashes {
+            match hash_file(&layout.find(block_dir, block_hash)) {
+                Ok(h) if &h == block_hash => status.present.push(block_hash.clone()),
+                Ok(_) => status.corrupted.push(block_hash.clone()),
+                Err(e)
+                    if e.downcast_ref::<std::io::Error>()
+                        .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) =>
+                {
+                    status.missing.push(block_hash.clone())
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        status.nb_needed = self.k.saturating_sub(status.present.len());
+
+        Ok(status)
+    }
+}
+
+/// a fresh and empty directory for a test, removed when it is dropped, see [`test_dir`]
+#[cfg(test)]
+pub(crate) struct TestDir(PathBuf);
+
+#[cfg(test)]
+impl std::ops::Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// a fresh and empty directory for a given test
+///
+/// the directory is removed when the returned [`TestDir`] is dropped, even if the test fails, so
+/// that no other run inherits its content.
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> TestDir {
+    let dir = std::env::temp_dir().join(format!("komodo-fs-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    TestDir(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_serialize::{Compress, Validate};
+
+    use crate::{
+        algebra::linalg::Matrix,
+        fec::{encode, Shard},
+        semi_avid::{build, prove, Block},
+        zk::{setup, Powers},
+    };
+
+    use super::{
+        dump, dump_blocks, hash_file, list_blocks, migrate_layout, read, read_blocks,
+        read_blocks_batched, remove_partial_files, serialize, test_dir, write_atomically,
+        AtomicFile, Compression, Encoding, Encryption, HashAlgorithm, Header, Index, Layout,
+        Manifest, Naming, ReadOptions, LOCK_FILE,
+    };
+
+    fn bytes() -> Vec<u8> {
+        include_bytes!("../../assets/dragoon_32x32.png").to_vec()
+    }
+
+    fn header() -> Header {
+        Header::new("test", "BLS12-381", &[])
+    }
+
+    fn hashes(formatted_output: &str) -> Vec<String> {
+        formatted_output
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(',')
+            .filter(|h| !h.is_empty())
+            .map(|h| h.trim_matches('"').to_string())
+            .collect()
+    }
+
+    #[test]
+    fn atomic_writes() {
+        let dir = test_dir("atomic-writes");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("foo");
+        write_atomically(&path, &[1, 2, 3]).unwrap();
+        write_atomically(&path, &[4, 5]).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), vec![4, 5]);
+
+        let filename = dump(
+            &[6u8, 7, 8].to_vec(),
+            &dir,
+            None,
+            &header(),
+            Compress::Yes,
+            Compression::None,
+            Encryption::None,
+        )
+        .unwrap();
+        assert!(dir.join(filename).exists());
+
+        // no temporary file should remain after successful writes
+        assert_eq!(remove_partial_files(&dir).unwrap(), Vec::<PathBuf>::new());
+
+        // simulate a crash in the middle of a write
+        let partial = dir.join(".bar.partial");
+        std::fs::write(&partial, [1, 2]).unwrap();
+        assert_eq!(remove_partial_files(&dir).unwrap(), vec![partial.clone()]);
+        assert!(!partial.exists());
+        assert!(path.exists());
+
+        assert_eq!(
+            remove_partial_files(&dir.join("does-not-exist")).unwrap(),
+            Vec::<PathBuf>::new()
+        );
+    }
+
+    #[test]
+    fn dump_and_read_blocks() {
+        let mut rng = ark_std::test_rng();
+        let bytes = bytes();
+        let dir = test_dir("dump-and-read-blocks");
+
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+        let shards = encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        let powers = setup::<Fr, G1Projective>(bytes.len(), &mut rng).unwrap();
+        let proof = prove::<Fr, G1Projective, DensePolynomial<Fr>>(&bytes, &powers, 3).unwrap();
+        let blocks = build::<Fr, G1Projective, DensePolynomial<Fr>>(&shards, &proof);
+
+        let block_hashes = hashes(
+            &dump_blocks(
+                &blocks,
+                &dir,
+                Layout::Flat,
+                &header(),
+                Compress::Yes,
+                Compression::None,
+                Encryption::None,
+            )
+            .unwrap(),
+        );
+        assert_eq!(block_hashes.len(), blocks.len());
+        let actual: Vec<(String, Block<Fr, G1Projective>)> = read_blocks(
+            &block_hashes,
+            &dir,
+            Layout::Flat,
+            Compress::Yes,
+            Validate::Yes,
+            Encryption::None,
+        )
+        .unwrap();
+        assert_eq!(
+            actual.into_iter().map(|(_, b)| b).collect::<Vec<_>>(),
+            blocks
+        );
+
+        // any serializable type can be stored, not only Semi-AVID blocks
+        let shard_hashes = hashes(
+            &dump_blocks(
+                &shards,
+                &dir,
+                Layout::Flat,
+                &header(),
+                Compress::No,
+                Compression::None,
+                Encryption::None,
+            )
+            .unwrap(),
+        );
+        let actual = read_blocks::<Shard<Fr>>(
+            &shard_hashes,
+            &dir,
+            Layout::Flat,
+            Compress::No,
+            Validate::Yes,
+            Encryption::None,
+        )
+        .unwrap();
+        assert_eq!(
+            actual.into_iter().map(|(_, s)| s).collect::<Vec<_>>(),
+            shards
+        );
+    }
+
+    #[test]
+    fn configurable_naming() {
+        let bytes = bytes();
+        let dir = test_dir("configurable-naming");
+        let layout = Layout::Sharded { depth: 1, width: 2 };
+
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+        for naming in [
+            Naming::IPFS,
+            Naming {
+                algorithm: HashAlgorithm::Sha512,
+                encoding: Encoding::Base32,
+            },
+        ] {
+            let header = header().with_naming(naming);
+            let block_hashes = hashes(
+                &dump_blocks(
+                    &shards,
+                    &dir,
+                    layout,
+                    &header,
+                    Compress::Yes,
+                    Compression::None,
+                    Encryption::None,
+                )
+                .unwrap(),
+            );
+            for (shard, block_hash) in shards.iter().zip(&block_hashes) {
+                assert_eq!(
+                    *block_hash,
+                    naming.name(&serialize(shard, Compress::Yes).unwrap())
+                );
+                let path = layout.find(&dir, block_hash);
+                assert_eq!(hash_file(&path).unwrap(), *block_hash);
+                assert_eq!(Header::read_from(&path).unwrap(), Some(header.clone()));
+            }
+            if naming == Naming::IPFS {
+                assert!(block_hashes.iter().all(|h| h.starts_with("bafkrei")));
+            }
+
+            let actual = read_blocks::<Shard<Fr>>(
+                &block_hashes,
+                &dir,
+                layout,
+                Compress::Yes,
+                Validate::Yes,
+                Encryption::None,
+            )
+            .unwrap();
+            assert_eq!(
+                actual.into_iter().map(|(_, s)| s).collect::<Vec<_>>(),
+                shards
+            );
+
+            let manifest = Manifest {
+                file_hash: shards[0].hash.clone(),
+                size: bytes.len(),
+                k: 3,
+                n: 5,
+                curve: "BLS12-381".to_string(),
+                setup_digest: vec![],
+                block_hashes,
+                sealing: None,
+            };
+            let status = manifest.verify(&dir, layout).unwrap();
+            assert!(status.corrupted.is_empty() && status.is_complete());
+        }
+    }
+
+    #[test]
+    fn manifest() {
+        let mut rng = ark_std::test_rng();
+        let bytes = bytes();
+        let dir = test_dir("manifest");
+        let (k, n) = (3, 5);
+
+        let encoding_mat = Matrix::random(k, n, &mut rng);
+        let shards = encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        let powers = setup::<Fr, G1Projective>(bytes.len(), &mut rng).unwrap();
+        let proof = prove::<Fr, G1Projective, DensePolynomial<Fr>>(&bytes, &powers, k).unwrap();
+        let blocks = build::<Fr, G1Projective, DensePolynomial<Fr>>(&shards, &proof);
+        let block_dir = dir.join("blocks");
+        let block_hashes = hashes(
+            &dump_blocks(
+                &blocks,
+                &block_dir,
+                Layout::Flat,
+                &header(),
+                Compress::Yes,
+                Compression::None,
+                Encryption::None,
+            )
+            .unwrap(),
+        );
+
+        let manifest = Manifest {
+            file_hash: shards[0].hash.clone(),
+            size: bytes.len(),
+            k,
+            n,
+            curve: "BLS12-381".to_string(),
+            setup_digest: Manifest::setup_digest(&powers, Compress::Yes).unwrap(),
+            block_hashes: block_hashes.clone(),
+            sealing: None,
+        };
+
+        let manifest_dir = dir.join("manifests");
+        let filename = manifest.save(&manifest_dir, Compress::Yes).unwrap();
+        assert_eq!(filename, hex::encode(&shards[0].hash));
+        let loaded = Manifest::load(&filename, &manifest_dir, Compress::Yes, Validate::Yes);
+        assert_eq!(loaded.unwrap(), manifest);
+
+        let status = manifest.verify(&block_dir, Layout::Flat).unwrap();
+        assert_eq!(status.present, block_hashes);
+        assert!(status.missing.is_empty() && status.corrupted.is_empty());
+        assert!(status.is_complete());
+
+        // lose a block and corrupt another one
+        std::fs::remove_file(block_dir.join(&block_hashes[0])).unwrap();
+        std::fs::write(block_dir.join(&block_hashes[1]), [0, 1, 2]).unwrap();
+        let status = manifest.verify(&block_dir, Layout::Flat).unwrap();
+        assert_eq!(status.present, block_hashes[2..].to_vec());
+        assert_eq!(status.missing, vec![block_hashes[0].clone()]);
+        assert_eq!(status.corrupted, vec![block_hashes[1].clone()]);
+        assert!(status.is_complete());
+
+        std::fs::remove_file(block_dir.join(&block_hashes[2])).unwrap();
+        let status = manifest.verify(&block_dir, Layout::Flat).unwrap();
+        assert_eq!(status.nb_needed, 1);
+        assert!(!status.is_complete());
+
+        let invalid = Manifest {
+            n: n + 1,
+            ..manifest
+        };
+        assert!(invalid.verify(&block_dir, Layout::Flat).is_err());
+    }
+
+    #[test]
+    fn concurrent_dumps() {
+        let mut rng = ark_std::test_rng();
+        let dir = test_dir("concurrent-dumps");
+        let layout = Layout::Sharded { depth: 1, width: 2 };
+        let shards = encode::<Fr>(&bytes(), &Matrix::random(3, 8, &mut rng)).unwrap();
+        Index::build(&dir).unwrap();
+
+        // all the writers race on the same blocks, in different orders
+        let results = std::thread::scope(|s| {
+            let handles = (0..4)
+                .map(|i| {
+                    let (dir, shards) = (&dir, &shards);
+                    s.spawn(move || {
+                        let mut shards = shards.clone();
+                        shards.rotate_left(i);
+                        dump_blocks(
+                            &shards,
+                            dir,
+                            layout,
+                            &header(),
+                            Compress::Yes,
+                            Compression::None,
+                            Encryption::None,
+                        )
+                        .map(|h| {
+                            let mut h = hashes(&h);
+                            h.sort();
+                            h
+                        })
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert!(results.iter().all(|r| r == &results[0]));
+
+        let expected = results[0].clone();
+        let listed = list_blocks(&dir).unwrap();
+        assert_eq!(
+            listed.iter().map(|b| b.hash.clone()).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(
+            Index::load(&dir)
+                .unwrap()
+                .unwrap()
+                .blocks()
+                .cloned()
+                .collect::<Vec<_>>(),
+            listed
+        );
+        assert!(remove_partial_files(&dir).unwrap().is_empty());
+        assert!(!dir.join(LOCK_FILE).exists());
+
+        // dumping again is a no-op
+        let path = layout.path(&dir, &expected[0]);
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        dump_blocks(
+            &shards,
+            &dir,
+            layout,
+            &header(),
+            Compress::Yes,
+            Compression::None,
+            Encryption::None,
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().modified().unwrap(),
+            modified
+        );
+    }
+
+    #[test]
+    fn read_blocks_in_batch() {
+        let mut rng = ark_std::test_rng();
+        let bytes = bytes();
+        let dir = test_dir("read-blocks-in-batch");
+
+        let encoding_mat = Matrix::random(3, 7, &mut rng);
+        let shards = encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        let powers = setup::<Fr, G1Projective>(bytes.len(), &mut rng).unwrap();
+        let proof = prove::<Fr, G1Projective, DensePolynomial<Fr>>(&bytes, &powers, 3).unwrap();
+        let blocks = build::<Fr, G1Projective, DensePolynomial<Fr>>(&shards, &proof);
+
+        let mut block_hashes = hashes(
+            &dump_blocks(
+                &blocks,
+                &dir,
+                Layout::Flat,
+                &header(),
+                Compress::Yes,
+                Compression::None,
+                Encryption::None,
+            )
+            .unwrap(),
+        );
+        block_hashes.insert(2, "does-not-exist".to_string());
+
+        for nb_threads in [0, 1, 3, 100] {
+            for validate in [Validate::Yes, Validate::No] {
+                let options = ReadOptions {
+                    compress: Compress::Yes,
+                    validate,
+                    nb_threads,
+                    layout: Layout::Flat,
+                    encryption: Encryption::None,
+                };
+                let actual =
+                    read_blocks_batched::<Block<Fr, G1Projective>>(&block_hashes, &dir, &options);
+
+                assert_eq!(
+                    actual.iter().map(|(h, _)| h.clone()).collect::<Vec<_>>(),
+                    block_hashes
+                );
+                assert!(actual[2].1.is_err());
+                let actual = actual
+                    .into_iter()
+                    .filter_map(|(_, b)| b.ok())
+                    .collect::<Vec<_>>();
+                assert_eq!(actual, blocks);
+            }
+        }
+
+        assert!(
+            read_blocks_batched::<Block<Fr, G1Projective>>(&[], &dir, &ReadOptions::default())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn sharded_layout() {
+        let layout = Layout::Sharded { depth: 2, width: 2 };
+        let dir = PathBuf::from("blocks");
+        assert_eq!(layout.path(&dir, "abcdef"), dir.join("ab/cd/abcdef"));
+        assert_eq!(layout.path(&dir, "abc"), dir.join("ab/abc"));
+        assert_eq!(Layout::Flat.path(&dir, "abcdef"), dir.join("abcdef"));
+
+        let mut rng = ark_std::test_rng();
+        let bytes = bytes();
+        let dir = test_dir("sharded-layout");
+
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+        let shards = encode::<Fr>(&bytes, &encoding_mat).unwrap();
+
+        // an old flat store...
+        let flat_hashes = hashes(
+            &dump_blocks(
+                &shards[..3],
+                &dir,
+                Layout::Flat,
+                &header(),
+                Compress::Yes,
+                Compression::None,
+                Encryption::None,
+            )
+            .unwrap(),
+        );
+        // ... where new blocks are sharded
+        let sharded_hashes = hashes(
+            &dump_blocks(
+                &shards[3..],
+                &dir,
+                layout,
+                &header(),
+                Compress::Yes,
+                Compression::None,
+                Encryption::None,
+            )
+            .unwrap(),
+        );
+        for h in &sharded_hashes {
+            assert!(layout.path(&dir, h).exists());
+        }
+
+        let mut all_hashes = [flat_hashes.clone(), sharded_hashes.clone()].concat();
+        all_hashes.sort();
+        let list_hashes = |dir| {
+            list_blocks(dir)
+                .unwrap()
+                .into_iter()
+                .map(|b| b.hash)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(list_hashes(&dir), all_hashes);
+
+        // flat blocks can still be read with the sharded layout
+        let block_hashes = [flat_hashes.clone(), sharded_hashes.clone()].concat();
+        let actual = read_blocks::<Shard<Fr>>(
+            &block_hashes,
+            &dir,
+            layout,
+            Compress::Yes,
+            Validate::Yes,
+            Encryption::None,
+        )
+        .unwrap();
+        assert_eq!(
+            actual.into_iter().map(|(_, s)| s).collect::<Vec<_>>(),
+            shards
+        );
+
+        assert_eq!(migrate_layout(&dir, layout).unwrap(), flat_hashes.len());
+        assert_eq!(migrate_layout(&dir, layout).unwrap(), 0);
+        for h in &block_hashes {
+            assert!(layout.path(&dir, h).exists());
+        }
+        assert_eq!(list_hashes(&dir), all_hashes);
+
+        assert_eq!(
+            migrate_layout(&dir, Layout::Flat).unwrap(),
+            block_hashes.len()
+        );
+        for h in &block_hashes {
+            assert!(Layout::Flat.path(&dir, h).exists());
+        }
+        // no empty shard directory should remain
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), block_hashes.len());
+    }
+
+    #[test]
+    fn streaming_dump_and_read() {
+        let mut rng = ark_std::test_rng();
+        let dir = test_dir("streaming-dump-and-read");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let powers = setup::<Fr, G1Projective>(bytes().len(), &mut rng).unwrap();
+        for compress in [Compress::Yes, Compress::No] {
+            // the name of the file is the hash of the serialized bytes, as if it was not streamed
+            let filename = dump(
+                &powers,
+                &dir,
+                None,
+                &header(),
+                compress,
+                Compression::None,
+                Encryption::None,
+            )
+            .unwrap();
+            assert_eq!(
+                filename,
+                Naming::default().name(&serialize(&powers, compress).unwrap())
+            );
+            assert_eq!(
+                read::<Powers<Fr, G1Projective>>(
+                    &dir.join(&filename),
+                    compress,
+                    Validate::Yes,
+                    Encryption::None
+                )
+                .unwrap(),
+                powers
+            );
+        }
+
+        // an aborted write does not leave anything behind
+        let mut file = AtomicFile::create(&dir).unwrap();
+        std::io::Write::write_all(&mut file, &[1, 2, 3]).unwrap();
+        drop(file);
+        assert!(remove_partial_files(&dir).unwrap().is_empty());
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn compressed_without_zstd() {
+        let dir = test_dir("compressed-without-zstd");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("compressed");
+        std::fs::write(&path, [0x28, 0xb5, 0x2f, 0xfd, 0, 0, 0, 0]).unwrap();
+        assert!(read::<Vec<u8>>(&path, Compress::Yes, Validate::Yes, Encryption::None).is_err());
+    }
+
+    #[test]
+    fn encrypted_without_key() {
+        let dir = test_dir("encrypted-without-key");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("encrypted");
+        let mut bytes = vec![];
+        header().write(&mut bytes).unwrap();
+        bytes.extend(b"KMDE");
+        bytes.extend([0; 64]);
+        std::fs::write(&path, bytes).unwrap();
+
+        let err = read::<Vec<u8>>(&path, Compress::Yes, Validate::Yes, Encryption::None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no key"), "{}", err);
+        assert!(hash_file(&path).is_err());
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn encryption() {
+        use super::{migrate, Key};
+
+        let mut rng = ark_std::test_rng();
+        let dir = test_dir("encryption");
+        let shards = encode::<Fr>(&bytes(), &Matrix::random(3, 5, &mut rng)).unwrap();
+
+        let key = Encryption::XChaCha20Poly1305(Key([42; 32]));
+        let wrong_key = Encryption::XChaCha20Poly1305(Key([43; 32]));
+        let dump = |dir: &std::path::Path, encryption| {
+            hashes(
+                &dump_blocks(
+                    &shards,
+                    dir,
+                    Layout::Flat,
+                    &header(),
+                    Compress::Yes,
+                    Compression::None,
+                    encryption,
+                )
+                .unwrap(),
+            )
+        };
+        let plain = dump(&dir.join("plain"), Encryption::None);
+        let encrypted_dir = dir.join("encrypted");
+        let encrypted = dump(&encrypted_dir, key);
+        // blocks have the same hash whether they are encrypted or not
+        assert_eq!(plain, encrypted);
+
+        // the header stays in clear but the payload does not
+        let path = encrypted_dir.join(&encrypted[0]);
+        assert_eq!(Header::read_from(&path).unwrap(), Some(header()));
+        let clear = serialize(&shards[0], Compress::Yes).unwrap();
+        let content = std::fs::read(&path).unwrap();
+        assert!(!content.windows(clear.len()).any(|w| w == clear));
+
+        let read = |encryption| {
+            read_blocks::<Shard<Fr>>(
+                &encrypted,
+                &encrypted_dir,
+                Layout::Flat,
+                Compress::Yes,
+                Validate::Yes,
+                encryption,
+            )
+        };
+        assert_eq!(
+            read(key)
+                .unwrap()
+                .into_iter()
+                .map(|(_, s)| s)
+                .collect::<Vec<_>>(),
+            shards
+        );
+        assert!(read(wrong_key).is_err());
+        assert!(read(Encryption::None).is_err());
+
+        // the header is authenticated
+        let tampered = Header::new("tampered", "BLS12-381", &[]);
+        let mut bytes = vec![];
+        tampered.write(&mut bytes).unwrap();
+        let mut reader = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+        Header::read(&mut reader).unwrap();
+        std::io::copy(&mut reader, &mut bytes).unwrap();
+        write_atomically(&path, &bytes).unwrap();
+        assert!(read(key).is_err());
+
+        // encrypted files are never migrated
+        assert_eq!(migrate(&encrypted_dir, &header()).unwrap(), 0);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_compression() {
+        let mut rng = ark_std::test_rng();
+        let bytes = bytes();
+        let dir = test_dir("zstd-compression");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // NOTE: the powers of a trusted setup are random points that cannot be compressed, hence
+        // the size is checked on a repetitive payload
+        let payload = bytes.repeat(10);
+        let plain = dump(
+            &payload,
+            &dir,
+            Some("plain-payload"),
+            &header(),
+            Compress::No,
+            Compression::None,
+            Encryption::None,
+        )
+        .unwrap();
+        let compressed = dump(
+            &payload,
+            &dir,
+            Some("zstd-payload"),
+            &header(),
+            Compress::No,
+            Compression::Zstd(3),
+            Encryption::None,
+        )
+        .unwrap();
+
+        let size = |f: &str| std::fs::metadata(dir.join(f)).unwrap().len();
+        assert!(size(&compressed) < size(&plain));
+        assert_eq!(
+            read::<Vec<u8>>(
+                &dir.join(compressed),
+                Compress::No,
+                Validate::Yes,
+                Encryption::None
+            )
+            .unwrap(),
+            payload
+        );
+
+        let powers = setup::<Fr, G1Projective>(bytes.len(), &mut rng).unwrap();
+        let compressed = dump(
+            &powers,
+            &dir,
+            Some("zstd"),
+            &header(),
+            Compress::No,
+            Compression::Zstd(3),
+            Encryption::None,
+        )
+        .unwrap();
+        assert_eq!(
+            read::<Powers<Fr, G1Projective>>(
+                &dir.join(compressed),
+                Compress::No,
+                Validate::Yes,
+                Encryption::None
+            )
+            .unwrap(),
+            powers
+        );
+
+        // blocks have the same hash whether they are compressed or not
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+        let shards = encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        let block_dir = dir.join("blocks");
+        let plain = hashes(
+            &dump_blocks(
+                &shards,
+                &block_dir,
+                Layout::Flat,
+                &header(),
+                Compress::Yes,
+                Compression::None,
+                Encryption::None,
+            )
+            .unwrap(),
+        );
+        let compressed_dir = dir.join("compressed-blocks");
+        let compressed = hashes(
+            &dump_blocks(
+                &shards,
+                &compressed_dir,
+                Layout::Flat,
+                &header(),
+                Compress::Yes,
+                Compression::Zstd(19),
+                Encryption::None,
+            )
+            .unwrap(),
+        );
+        assert_eq!(plain, compressed);
+
+        let actual = read_blocks::<Shard<Fr>>(
+            &compressed,
+            &compressed_dir,
+            Layout::Flat,
+            Compress::Yes,
+            Validate::Yes,
+            Encryption::None,
+        )
+        .unwrap();
+        assert_eq!(
+            actual.into_iter().map(|(_, s)| s).collect::<Vec<_>>(),
+            shards
+        );
+
+        let manifest = Manifest {
+            file_hash: shards[0].hash.clone(),
+            size: bytes.len(),
+            k: 3,
+            n: 5,
+            curve: "BLS12-381".to_string(),
+            setup_digest: Manifest::setup_digest(&powers, Compress::Yes).unwrap(),
+            block_hashes: compressed,
+            sealing: None,
+        };
+        assert!(manifest
+            .verify(&compressed_dir, Layout::Flat)
+            .unwrap()
+            .corrupted
+            .is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+//! how the files written by [`crate::fs`] are named after their content
+//!
+//! the name of a file is the hash of its payload, see [`super::dump`], computed with a
+//! [`HashAlgorithm`] and written with an [`Encoding`]. The [`Naming`] of a file is part of its
+//! [`super::Header`], so that its name can always be checked again, e.g. by [`super::scrub`],
+//! without any other configuration.
+//!
+//! # Example
+//! the names of the empty payload are
+//! - `e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855` with the default [`Naming`]
+//! - `bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku` with [`Naming::IPFS`], which is
+//!   the CID IPFS gives to the same raw bytes
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256, Sha512};
+
+use super::Cid;
+
+/// the hash function used to name files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+/// how a digest is written in a file name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// hexadecimal where each byte is written without its leading zero, e.g. `0x0a` is `a`
+    ///
+    /// > **Note**
+    /// >
+    /// > this is how all the files were named before the naming was configurable, it is kept as
+    /// > the default to remain compatible with existing stores.
+    #[default]
+    CompactHex,
+    /// standard hexadecimal, two lowercase characters per byte
+    Hex,
+    /// lowercase base32 from [RFC 4648](https://www.rfc-editor.org/rfc/rfc4648), without padding
+    Base32,
+    /// a [CIDv1](https://github.com/multiformats/cid) with the `raw` codec, written as a base32
+    /// multibase string, e.g. `bafkrei...`, see [`super::Cid`]
+    ///
+    /// > **Note**
+    /// >
+    /// > all the CIDs of a given [`HashAlgorithm`] share the same prefix, which makes the first
+    /// > levels of a [`super::Layout::Sharded`] layout useless.
+    Cid,
+}
+
+/// the hash function and the encoding used to name files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Naming {
+    pub algorithm: HashAlgorithm,
+    pub encoding: Encoding,
+}
+
+/// an incremental hasher for any [`HashAlgorithm`]
+pub(super) enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    pub(super) fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Sha256(h) => h.update(bytes),
+            Hasher::Sha512(h) => h.update(bytes),
+        }
+    }
+
+    pub(super) fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Sha256(h) => h.finalize().to_vec(),
+            Hasher::Sha512(h) => h.finalize().to_vec(),
+        }
+    }
+}
+
+impl HashAlgorithm {
+    pub(super) fn hasher(self) -> Hasher {
+        match self {
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+        }
+    }
+
+    pub fn digest(self, bytes: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(bytes);
+        hasher.finalize()
+    }
+
+    /// the code of the hash function in the
+    /// [multicodec table](https://github.com/multiformats/multicodec/blob/master/table.csv)
+    pub(super) fn multihash_code(self) -> u64 {
+        match self {
+            HashAlgorithm::Sha256 => 0x12,
+            HashAlgorithm::Sha512 => 0x13,
+        }
+    }
+}
+
+pub(super) fn base32(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+    let mut encoded = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let (mut buffer, mut nb_bits) = (0u16, 0);
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u16;
+        nb_bits += 8;
+        while nb_bits >= 5 {
+            nb_bits -= 5;
+            encoded.push(ALPHABET[((buffer >> nb_bits) & 0x1f) as usize] as char);
+        }
+    }
+    if nb_bits > 0 {
+        encoded.push(ALPHABET[((buffer << (5 - nb_bits)) & 0x1f) as usize] as char);
+    }
+
+    encoded
+}
+
+impl Naming {
+    /// the same naming as IPFS for raw blocks, i.e. CIDv1 with SHA-256
+    pub const IPFS: Naming = Naming {
+        algorithm: HashAlgorithm::Sha256,
+        encoding: Encoding::Cid,
+    };
+
+    /// write a digest computed with [`Naming::algorithm`] as a file name
+    pub fn format(&self, digest: &[u8]) -> String {
+        match self.encoding {
+            Encoding::CompactHex => digest.iter().map(|x| format!("{:x}", x)).collect(),
+            Encoding::Hex => digest.iter().map(|x| format!("{:02x}", x)).collect(),
+            Encoding::Base32 => base32(digest),
+            Encoding::Cid => Cid {
+                codec: Cid::RAW,
+                algorithm: self.algorithm,
+                digest: digest.to_vec(),
+            }
+            .to_string(),
+        }
+    }
+
+    /// the name of some bytes
+    pub fn name(&self, bytes: &[u8]) -> String {
+        self.format(&self.algorithm.digest(bytes))
+    }
+
+    pub(super) fn to_bytes(self) -> [u8; 2] {
+        let algorithm = match self.algorithm {
+            HashAlgorithm::Sha256 => 0,
+            HashAlgorithm::Sha512 => 1,
+        };
+        let encoding = match self.encoding {
+            Encoding::CompactHex => 0,
+            Encoding::Hex => 1,
+            Encoding::Base32 => 2,
+            Encoding::Cid => 3,
+        };
+        [algorithm, encoding]
+    }
+
+    pub(super) fn from_bytes(bytes: [u8; 2]) -> Result<Self> {
+        let algorithm = match bytes[0] {
+            0 => HashAlgorithm::Sha256,
+            1 => HashAlgorithm::Sha512,
+            a => return Err(anyhow!("unknown hash algorithm: {}", a)),
+        };
+        let encoding = match bytes[1] {
+            0 => Encoding::CompactHex,
+            1 => Encoding::Hex,
+            2 => Encoding::Base32,
+            3 => Encoding::Cid,
+            e => return Err(anyhow!("unknown encoding: {}", e)),
+        };
+        Ok(Self {
+            algorithm,
+            encoding,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base32, Encoding, HashAlgorithm, Naming};
+
+    #[test]
+    fn rfc4648_base32() {
+        for (bytes, expected) in [
+            ("", ""),
+            ("f", "my"),
+            ("fo", "mzxq"),
+            ("foo", "mzxw6"),
+            ("foob", "mzxw6yq"),
+            ("fooba", "mzxw6ytb"),
+            ("foobar", "mzxw6ytboi"),
+        ] {
+            assert_eq!(base32(bytes.as_bytes()), expected);
+        }
+    }
+
+    #[test]
+    fn names() {
+        let naming = |algorithm, encoding| Naming {
+            algorithm,
+            encoding,
+        };
+
+        assert_eq!(
+            Naming::default().name(b"abc"),
+            "ba7816bf8f1cfea414140de5dae2223b0361a396177a9cb410ff61f2015ad"
+        );
+        assert_eq!(
+            naming(HashAlgorithm::Sha256, Encoding::Hex).name(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            Naming::IPFS.name(b""),
+            "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+        );
+        assert_eq!(
+            naming(HashAlgorithm::Sha256, Encoding::Base32).name(b"komodo"),
+            "k2lxwwxdgafkiftbr5p2y2n3l7ff7u4uz43vyn46ucg7ivta6tyq"
+        );
+        assert_eq!(
+            naming(HashAlgorithm::Sha512, Encoding::Hex).name(b"komodo"),
+            "d0a4807dc0cdc4158d62bb8dd3204a0f8a334a453ce2bcdffc354f15aa94ddf8\
+             0f46aa1d2490860631aa4757d4bbd9d5d9258ed5b44aea6a454dd5377545e716"
+        );
+        assert_eq!(
+            naming(HashAlgorithm::Sha512, Encoding::Cid).name(b"komodo"),
+            "bafkrgqgqusah3qgnyqky2yv3rxjsasqprizuurj44k6n77bvj4k2vfg57ahunkq5esiimbrrvjdvpvf33hk5\
+             sjmo2w2ev2tkivg5kn3vixtrm"
+        );
+
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Sha512] {
+            for encoding in [
+                Encoding::CompactHex,
+                Encoding::Hex,
+                Encoding::Base32,
+                Encoding::Cid,
+            ] {
+                let naming = naming(algorithm, encoding);
+                assert_eq!(Naming::from_bytes(naming.to_bytes()).unwrap(), naming);
+            }
+        }
+        assert!(Naming::from_bytes([2, 0]).is_err());
+        assert!(Naming::from_bytes([0, 4]).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! disk usage of a block directory and optional quota on it
+//!
+//! a block directory can be given a quota with [`set_quota`], which is then enforced by
+//! [`super::dump_block`], [`super::dump_framed_block`] and [`super::store::LocalStore`]: a new
+//! block that would make the directory exceed its quota is not written and a [`QuotaExceeded`]
+//! error is returned instead, which can be recovered with [`anyhow::Error::downcast_ref`].
+//!
+//! > **Note**
+//! >
+//! > only the blocks themselves are accounted for, not the hidden files of the directory, e.g.
+//! > the [`super::Index`] or the partial files.
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{anyhow, Result};
+use thiserror::Error;
+
+use super::{list_blocks, write_atomically, DirLock, Index};
+
+/// the name of the hidden file, inside the block directory, where the quota is persisted
+pub const QUOTA_FILE: &str = ".quota";
+
+/// the disk usage of a block directory, see [`usage`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Usage {
+    /// the total size of all the blocks, in bytes
+    pub total: u64,
+    /// the number of blocks
+    pub nb_blocks: usize,
+    /// the size of the blocks of each original file, see [`crate::fec::Shard::hash`]
+    ///
+    /// the blocks without any file hash, e.g. written before version 2 of the format, are
+    /// accounted for under an empty hash.
+    pub per_file: BTreeMap<Vec<u8>, u64>,
+    /// the size of the blocks of each protocol, see [`crate::header::Header::protocol`]
+    pub per_protocol: BTreeMap<String, u64>,
+}
+
+/// a block could not be written because its directory would have exceeded its quota
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("quota exceeded: {used} bytes used out of {quota}, cannot write {needed} more bytes")]
+pub struct QuotaExceeded {
+    /// the quota of the directory, in bytes
+    pub quota: u64,
+    /// the current usage of the directory, in bytes
+    pub used: u64,
+    /// the size of the block that could not be written, in bytes
+    pub needed: u64,
+}
+
+/// compute the disk usage of a block directory, whatever its layout
+///
+/// > **Note**
+/// >
+/// > if the directory is indexed, see [`Index`], the usage is computed from the index only,
+/// > otherwise the headers of all the blocks are read.
+pub fn usage(block_dir: &Path) -> Result<Usage> {
+    let blocks = match Index::load(block_dir)? {
+        Some(index) => index.blocks().cloned().collect(),
+        None => list_blocks(block_dir)?,
+    };
+
+    let mut usage = Usage::default();
+    for block in blocks {
+        usage.total += block.size;
+        usage.nb_blocks += 1;
+        *usage.per_file.entry(block.file_hash).or_default() += block.size;
+        *usage.per_protocol.entry(block.protocol).or_default() += block.size;
+    }
+
+    Ok(usage)
+}
+
+/// set the quota of a block directory, in bytes, or remove it with `None`
+///
+/// > **Note**
+/// >
+/// > setting a quota below the current usage does not remove any block, it only prevents new
+/// > blocks from being written.
+pub fn set_quota(block_dir: &Path, quota: Option<u64>) -> Result<()> {
+    let path = block_dir.join(QUOTA_FILE);
+    match quota {
+        Some(quota) => {
+            std::fs::create_dir_all(block_dir)?;
+            write_atomically(&path, quota.to_string().as_bytes())
+        }
+        None if path.exists() => Ok(std::fs::remove_file(path)?),
+        None => Ok(()),
+    }
+}
+
+/// the quota of a block directory, in bytes, `None` means that there is no quota
+pub fn quota(block_dir: &Path) -> Result<Option<u64>> {
+    let path = block_dir.join(QUOTA_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(&path)?;
+    let quota = content
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("invalid quota in `{:?}`: {:?}", path, content))?;
+    Ok(Some(quota))
+}
+
+/// make sure that `size` more bytes can be written to `block_dir`
+///
+/// if the directory has a quota, the returned lock must be held until the block has been
+/// written, so that concurrent writers cannot exceed the quota together.
+pub(super) fn reserve(block_dir: &Path, size: u64) -> Result<Option<DirLock>> {
+    if quota(block_dir)?.is_none() {
+        return Ok(None);
+    }
+
+    let lock = DirLock::acquire(block_dir)?;
+    // NOTE: the quota is read again now that the directory is locked
+    if let Some(quota) = quota(block_dir)? {
+        let used = usage(block_dir)?.total;
+        if used.saturating_add(size) > quota {
+            return Err(QuotaExceeded {
+                quota,
+                used,
+                needed: size,
+            }
+            .into());
+        }
+    }
+
+    Ok(Some(lock))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use ark_bls12_381::Fr;
+    use ark_serialize::Compress;
+
+    use crate::{
+        algebra::linalg::Matrix,
+        fec::encode,
+        fs::{dump_block, Compression, Encryption, Header, Index, Layout},
+    };
+
+    use super::{quota, set_quota, usage, QuotaExceeded};
+
+    #[test]
+    fn quota_and_usage() {
+        let dir = test_dir("quota");
+        let layout = Layout::Sharded { depth: 1, width: 2 };
+
+        let bytes = include_bytes!("../../assets/dragoon_32x32.png").to_vec();
+        let shards = encode::<Fr>(&bytes, &Matrix::random(3, 5, &mut ark_std::test_rng())).unwrap();
+        let dump = |i: usize, protocol: &str| {
+            dump_block(
+                &shards[i],
+                &dir,
+                layout,
+                &Header::new(protocol, "BLS12-381", &[]).for_blocks(3, &shards[i].hash),
+                Compress::Yes,
+                Compression::None,
+                Encryption::None,
+            )
+        };
+
+        assert_eq!(usage(&dir).unwrap().total, 0);
+        assert_eq!(quota(&dir).unwrap(), None);
+
+        dump(0, "a").unwrap();
+        dump(1, "b").unwrap();
+        let current = usage(&dir).unwrap();
+        assert_eq!(current.nb_blocks, 2);
+        assert_eq!(current.per_protocol.len(), 2);
+        assert_eq!(current.per_file[&shards[0].hash], current.total);
+        assert_eq!(current.per_protocol.values().sum::<u64>(), current.total);
+
+        // the index gives the same usage
+        Index::build(&dir).unwrap();
+        assert_eq!(usage(&dir).unwrap(), current);
+
+        // a new block does not fit...
+        set_quota(&dir, Some(current.total + 1)).unwrap();
+        assert_eq!(quota(&dir).unwrap(), Some(current.total + 1));
+        let err = dump(2, "a").unwrap_err();
+        let exceeded = err.downcast_ref::<QuotaExceeded>().unwrap();
+        assert_eq!(exceeded.used, current.total);
+        assert_eq!(exceeded.quota, current.total + 1);
+        assert_eq!(usage(&dir).unwrap(), current);
+
+        // ... but existing ones can still be dumped again
+        dump(0, "a").unwrap();
+
+        set_quota(&dir, None).unwrap();
+        dump(2, "a").unwrap();
+        assert_eq!(usage(&dir).unwrap().nb_blocks, 3);
+    }
+}
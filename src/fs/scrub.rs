@@ -0,0 +1,266 @@
+//! periodic integrity verification of a directory of Semi-AVID blocks
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_poly::DenseUVPolynomial;
+use ark_serialize::{Compress, Validate};
+use ark_std::ops::Div;
+use tracing::{info, warn};
+
+use super::{hash_file, index, read, walk_blocks, Encryption, Layout};
+use crate::{
+    semi_avid::{verify, Block},
+    zk::Powers,
+};
+
+/// the name of the hidden directory, inside the block directory, where corrupt blocks are moved
+pub const QUARANTINE_DIR: &str = ".quarantine";
+
+/// the reason why a block has been quarantined by [`scrub`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScrubIssue {
+    /// the content of the block does not match its hash anymore, e.g. because of bit rot
+    HashMismatch(String),
+    /// the block cannot be deserialized, the error is attached
+    Unreadable(String),
+    /// the proof of the block does not verify against the trusted setup
+    InvalidProof,
+}
+
+impl std::fmt::Display for ScrubIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScrubIssue::HashMismatch(actual) => write!(f, "content hash is {}", actual),
+            ScrubIssue::Unreadable(e) => write!(f, "block cannot be read: {}", e),
+            ScrubIssue::InvalidProof => write!(f, "proof does not verify"),
+        }
+    }
+}
+
+/// the result of a [`scrub`] pass
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScrubReport {
+    /// the blocks that have passed all the checks
+    pub healthy: Vec<String>,
+    /// the blocks that have been moved to [`QUARANTINE_DIR`], with the reason why
+    pub quarantined: Vec<(String, ScrubIssue)>,
+}
+
+impl ScrubReport {
+    pub fn nb_blocks(&self) -> usize {
+        self.healthy.len() + self.quarantined.len()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.quarantined.is_empty()
+    }
+
+    /// format the report as JSON / NUON, e.g.
+    /// ```json
+    /// {"healthy": ["aaaa"], "quarantined": [{"block": "bbbb", "issue": "proof does not verify"}]}
+    /// ```
+    pub fn to_json(&self) -> String {
+        let healthy = self
+            .healthy
+            .iter()
+            .map(|h| format!("{:?}", h))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let quarantined = self
+            .quarantined
+            .iter()
+            .map(|(h, i)| format!("{{\"block\": {:?}, \"issue\": {:?}}}", h, i.to_string()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            "{{\"healthy\": [{}], \"quarantined\": [{}]}}",
+            healthy, quarantined
+        )
+    }
+}
+
+fn quarantine(block_dir: &Path, path: &Path, block_hash: &str) -> Result<()> {
+    let quarantine_dir = block_dir.join(QUARANTINE_DIR);
+    std::fs::create_dir_all(&quarantine_dir)?;
+    std::fs::rename(path, quarantine_dir.join(block_hash))?;
+    index::forget(block_dir, block_hash)
+}
+
+/// walk a directory of Semi-AVID blocks, whatever its [`Layout`], and check every block
+///
+/// for each block
+/// - the hash of its content is compared to its filename
+/// - it is deserialized
+/// - its proof is verified against `powers`
+///
+/// the blocks that fail any of these checks are moved to [`QUARANTINE_DIR`], so that they are
+/// not used anymore and can be repaired or inspected later, and a [`ScrubReport`] is returned.
+pub fn scrub<F, G, P>(
+    block_dir: &Path,
+    powers: &Powers<F, G>,
+    compress: Compress,
+    validate: Validate,
+) -> Result<ScrubReport>
+where
+    F: PrimeField,
+    G: CurveGroup<ScalarField = F>,
+    P: DenseUVPolynomial<F>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    info!("scrubbing `{:?}`", block_dir);
+    let mut report = ScrubReport::default();
+    for path in walk_blocks(block_dir)? {
+        let block_hash = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let actual = hash_file(&path)?;
+        let issue = if actual != block_hash {
+            Some(ScrubIssue::HashMismatch(actual))
+        } else {
+            match read::<Block<F, G>>(&path, compress, validate, Encryption::None) {
+                Ok(block) => match verify::<F, G, P>(&block, powers) {
+                    Ok(true) => None,
+                    _ => Some(ScrubIssue::InvalidProof),
+                },
+                Err(e) => Some(ScrubIssue::Unreadable(e.to_string())),
+            }
+        };
+
+        match issue {
+            Some(issue) => {
+                warn!("quarantining `{}`: {}", block_hash, issue);
+                quarantine(block_dir, &path, &block_hash)?;
+                report.quarantined.push((block_hash, issue));
+            }
+            None => report.healthy.push(block_hash),
+        }
+    }
+
+    Ok(report)
+}
+
+/// where a block that has been quarantined by [`scrub`] now lives
+pub fn quarantined_path(block_dir: &Path, block_hash: &str) -> PathBuf {
+    Layout::Flat.path(&block_dir.join(QUARANTINE_DIR), block_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_serialize::{Compress, Validate};
+
+    use crate::{
+        algebra::linalg::Matrix,
+        fec::encode,
+        fs::{dump_block, write_atomically, Compression, Encryption, Header, Layout, Naming},
+        semi_avid::{build, prove},
+        zk::setup,
+    };
+
+    use super::{quarantined_path, scrub, ScrubIssue};
+
+    #[test]
+    fn scrub_block_dir() {
+        let mut rng = ark_std::test_rng();
+        let bytes = include_bytes!("../../assets/dragoon_32x32.png").to_vec();
+        let dir = test_dir("scrub");
+
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+        let shards = encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        let powers = setup::<Fr, G1Projective>(bytes.len(), &mut rng).unwrap();
+        let proof = prove::<Fr, G1Projective, DensePolynomial<Fr>>(&bytes, &powers, 3).unwrap();
+        let mut blocks = build::<Fr, G1Projective, DensePolynomial<Fr>>(&shards, &proof);
+
+        // a block whose shard has been tampered with before being stored
+        blocks[1].shard.data[0] += Fr::from(1u8);
+
+        let layout = Layout::Sharded { depth: 1, width: 2 };
+        let header = Header::new("semi-avid", "BLS12-381", &[]);
+        let block_hashes = blocks
+            .iter()
+            .map(|b| {
+                dump_block(
+                    b,
+                    &dir,
+                    layout,
+                    &header,
+                    Compress::Yes,
+                    Compression::None,
+                    Encryption::None,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // bit rot on a stored block
+        write_atomically(&layout.path(&dir, &block_hashes[2]), &[0, 1, 2]).unwrap();
+
+        // a file that is named after its content but is not a block
+        let garbage = [3, 4, 5];
+        let garbage_hash = Naming::default().name(&garbage);
+        write_atomically(&dir.join(&garbage_hash), &garbage).unwrap();
+
+        let scrub = |dir| {
+            scrub::<Fr, G1Projective, DensePolynomial<Fr>>(
+                dir,
+                &powers,
+                Compress::Yes,
+                Validate::Yes,
+            )
+            .unwrap()
+        };
+
+        let report = scrub(&dir);
+        assert_eq!(report.nb_blocks(), 6);
+        assert!(!report.is_clean());
+        let mut healthy = vec![
+            block_hashes[0].clone(),
+            block_hashes[3].clone(),
+            block_hashes[4].clone(),
+        ];
+        healthy.sort();
+        let mut actual_healthy = report.healthy.clone();
+        actual_healthy.sort();
+        assert_eq!(actual_healthy, healthy);
+
+        let issue = |h: &String| {
+            report
+                .quarantined
+                .iter()
+                .find(|(q, _)| q == h)
+                .map(|(_, i)| i.clone())
+        };
+        assert_eq!(issue(&block_hashes[1]), Some(ScrubIssue::InvalidProof));
+        assert!(matches!(
+            issue(&block_hashes[2]),
+            Some(ScrubIssue::HashMismatch(_))
+        ));
+        assert!(matches!(
+            issue(&garbage_hash),
+            Some(ScrubIssue::Unreadable(_))
+        ));
+        for h in &block_hashes[1..=2] {
+            assert!(quarantined_path(&dir, h).exists());
+            assert!(!layout.path(&dir, h).exists());
+        }
+
+        let json = report.to_json();
+        assert!(json.starts_with("{\"healthy\": [\""));
+        assert!(json.contains(&format!(
+            "{{\"block\": {:?}, \"issue\": \"proof does not verify\"}}",
+            block_hashes[1]
+        )));
+
+        // the quarantined blocks are not scrubbed again
+        let report = scrub(&dir);
+        assert!(report.is_clean());
+        assert_eq!(report.nb_blocks(), 3);
+    }
+}
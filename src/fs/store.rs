@@ -0,0 +1,472 @@
+//! pluggable storage backends for blocks
+//!
+//! all the backends implement [`BlockStore`], which only knows how to store raw bytes under a
+//! key. The serialization of the blocks, with their [`Header`], and their content-addressing,
+//! i.e. the key of a block is the hash of its serialized bytes, are provided on top of that by
+//! the trait itself.
+//!
+//! the available backends are
+//! - [`LocalStore`]: the local filesystem, with the same behaviour as [`super::dump_blocks`] and
+//!   [`super::read_blocks`]
+//! - [`MemoryStore`]: a simple in-memory map, e.g. for tests
+//! - `ObjectStore`: any object storage such as S3, behind the `object-store` feature
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use tracing::info;
+
+use super::{
+    decoded, delete_block, format, index, list_blocks, quota, serialize, write_atomically,
+    Encryption, Header, Layout, Manifest,
+};
+
+/// a place where blocks can be persisted
+///
+/// implementors only have to provide the raw operations on bytes, the other methods will take
+/// care of the serialization.
+pub trait BlockStore {
+    /// store `bytes` under `key`, overwriting any previous value
+    fn put(&mut self, key: &str, bytes: &[u8]) -> Result<()>;
+    /// get the bytes stored under `key`, it is an error if the key does not exist
+    fn get(&self, key: &str) -> Result<Vec<u8>>;
+    fn contains(&self, key: &str) -> Result<bool>;
+    /// remove the value stored under `key`, does nothing if the key does not exist
+    fn delete(&mut self, key: &str) -> Result<()>;
+    /// list all the keys of the store, in lexicographic order
+    fn list(&self) -> Result<Vec<String>>;
+
+    /// serialize a block, store it under its hash and return the hash
+    fn dump_block<B: CanonicalSerialize>(
+        &mut self,
+        block: &B,
+        header: &Header,
+        compress: Compress,
+    ) -> Result<String>
+    where
+        Self: Sized,
+    {
+        let serialized = serialize(block, compress)?;
+        let key = header.extension.naming.name(&serialized);
+
+        let mut bytes = vec![];
+        header.write(&mut bytes)?;
+        bytes.extend(serialized);
+        self.put(&key, &bytes)?;
+
+        Ok(key)
+    }
+
+    /// store all the blocks and return their hashes in the same order
+    fn dump_blocks<B: CanonicalSerialize>(
+        &mut self,
+        blocks: &[B],
+        header: &Header,
+        compress: Compress,
+    ) -> Result<Vec<String>>
+    where
+        Self: Sized,
+    {
+        blocks
+            .iter()
+            .map(|b| self.dump_block(b, header, compress))
+            .collect()
+    }
+
+    /// serialize a manifest, store it under its [`Manifest::filename`] and return the name
+    ///
+    /// the manifest can be read back with [`BlockStore::read_block`].
+    ///
+    /// > **Note**
+    /// >
+    /// > a manifest is named after the file it describes and not after its content, it should be
+    /// > stored apart from the blocks, e.g. not in a [`LocalStore`] that is scrubbed.
+    fn dump_manifest(&mut self, manifest: &Manifest, compress: Compress) -> Result<String>
+    where
+        Self: Sized,
+    {
+        let key = manifest.filename();
+
+        let mut bytes = vec![];
+        Header::new("manifest", &manifest.curve, &manifest.setup_digest).write(&mut bytes)?;
+        bytes.extend(serialize(manifest, compress)?);
+        self.put(&key, &bytes)?;
+
+        Ok(key)
+    }
+
+    /// read and deserialize the block stored under `block_hash`
+    fn read_block<B: CanonicalDeserialize>(
+        &self,
+        block_hash: &str,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<B>
+    where
+        Self: Sized,
+    {
+        deserialize_block(&self.get(block_hash)?, block_hash, None, compress, validate)
+    }
+
+    /// read a block like [`BlockStore::read_block`], after checking that its header matches the
+    /// `expected` one, see [`Header::check`]
+    ///
+    /// a block computed by another protocol, on another curve or with another trusted setup is
+    /// rejected with a typed error, e.g. [`crate::error::KomodoError::CurveMismatch`], that can be
+    /// recovered with [`anyhow::Error::downcast_ref`].
+    ///
+    /// > **Note**
+    /// >
+    /// > blocks without any header, i.e. written by an older version of Komodo, are not checked.
+    fn read_block_as<B: CanonicalDeserialize>(
+        &self,
+        block_hash: &str,
+        expected: &Header,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<B>
+    where
+        Self: Sized,
+    {
+        deserialize_block(
+            &self.get(block_hash)?,
+            block_hash,
+            Some(expected),
+            compress,
+            validate,
+        )
+    }
+
+    /// read a bunch of blocks, see [`super::read_blocks`]
+    fn read_blocks<B: CanonicalDeserialize>(
+        &self,
+        block_hashes: &[String],
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Vec<(String, B)>>
+    where
+        Self: Sized,
+    {
+        block_hashes
+            .iter()
+            .map(|h| Ok((h.clone(), self.read_block(h, compress, validate)?)))
+            .collect()
+    }
+}
+
+/// deserialize the bytes of a block, checking its header against the `expected` one if any
+fn deserialize_block<B: CanonicalDeserialize>(
+    bytes: &[u8],
+    block_hash: &str,
+    expected: Option<&Header>,
+    compress: Compress,
+    validate: Validate,
+) -> Result<B> {
+    let name = format!("block `{}`", block_hash);
+    let (header, reader) = format::open_reader(bytes, &name)?;
+    if let (Some(header), Some(expected)) = (&header, expected) {
+        header
+            .check(expected)
+            .with_context(|| format!("could not read {}", name))?;
+    }
+    B::deserialize_with_mode(
+        decoded(reader, &header, Encryption::None)?,
+        compress,
+        validate,
+    )
+    .map_err(|e| format::deserialization_error(&name, &header, e))
+}
+
+/// a store in a directory of the local filesystem
+///
+/// > **Note**
+/// >
+/// > the writes are atomic, see [`write_atomically`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalStore {
+    pub dir: PathBuf,
+    pub layout: Layout,
+}
+
+impl LocalStore {
+    pub fn new(dir: PathBuf, layout: Layout) -> Self {
+        Self { dir, layout }
+    }
+}
+
+impl BlockStore for LocalStore {
+    fn put(&mut self, key: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.layout.path(&self.dir, key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let lock = if self.contains(key)? {
+            None
+        } else {
+            quota::reserve(&self.dir, bytes.len() as u64)?
+        };
+        info!("storing `{}` into `{:?}`", key, path);
+        write_atomically(&path, bytes)?;
+        drop(lock);
+        index::record(&self.dir, self.layout, key)
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(std::fs::read(self.layout.find(&self.dir, key))?)
+    }
+
+    fn contains(&self, key: &str) -> Result<bool> {
+        Ok(self.layout.find(&self.dir, key).is_file())
+    }
+
+    fn delete(&mut self, key: &str) -> Result<()> {
+        delete_block(key, &self.dir, self.layout)
+    }
+
+    fn list(&self) -> Result<Vec<String>> {
+        Ok(list_blocks(&self.dir)?
+            .into_iter()
+            .map(|b| b.hash)
+            .collect())
+    }
+}
+
+/// a store that lives in memory only
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryStore {
+    blocks: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl BlockStore for MemoryStore {
+    fn put(&mut self, key: &str, bytes: &[u8]) -> Result<()> {
+        self.blocks.insert(key.to_string(), bytes.to_vec());
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        self.blocks
+            .get(key)
+            .cloned()
+            .ok_or_else(|| anyhow!("no such block: {}", key))
+    }
+
+    fn contains(&self, key: &str) -> Result<bool> {
+        Ok(self.blocks.contains_key(key))
+    }
+
+    fn delete(&mut self, key: &str) -> Result<()> {
+        self.blocks.remove(key);
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<String>> {
+        let mut keys = self.blocks.keys().cloned().collect::<Vec<_>>();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// the minimal set of operations of an object storage service, e.g. S3
+///
+/// Komodo does not depend on any particular client: wrapping the client of one's infrastructure,
+/// e.g. a bucket of the `rust-s3` crate, in this trait is enough to get an [`ObjectStore`].
+#[cfg(feature = "object-store")]
+pub trait ObjectClient {
+    fn put_object(&mut self, key: &str, bytes: &[u8]) -> Result<()>;
+    /// get an object, `None` means that the object does not exist
+    fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>>;
+    fn delete_object(&mut self, key: &str) -> Result<()>;
+    /// list the keys of all the objects that start with `prefix`
+    fn list_objects(&self, prefix: &str) -> Result<Vec<String>>;
+}
+
+/// a store on top of an object storage service
+///
+/// all the blocks are stored under `prefix`, e.g. `komodo/blocks/`, which allows to share a
+/// single bucket between multiple applications.
+#[cfg(feature = "object-store")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectStore<C: ObjectClient> {
+    pub client: C,
+    pub prefix: String,
+}
+
+#[cfg(feature = "object-store")]
+impl<C: ObjectClient> ObjectStore<C> {
+    pub fn new(client: C, prefix: &str) -> Self {
+        Self {
+            client,
+            prefix: prefix.to_string(),
+        }
+    }
+
+    fn object_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+#[cfg(feature = "object-store")]
+impl<C: ObjectClient> BlockStore for ObjectStore<C> {
+    fn put(&mut self, key: &str, bytes: &[u8]) -> Result<()> {
+        let key = self.object_key(key);
+        info!("storing object `{}`", key);
+        self.client.put_object(&key, bytes)
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>> {
+        self.client
+            .get_object(&self.object_key(key))?
+            .ok_or_else(|| anyhow!("no such block: {}", key))
+    }
+
+    fn contains(&self, key: &str) -> Result<bool> {
+        Ok(self.client.get_object(&self.object_key(key))?.is_some())
+    }
+
+    fn delete(&mut self, key: &str) -> Result<()> {
+        self.client.delete_object(&self.object_key(key))
+    }
+
+    fn list(&self) -> Result<Vec<String>> {
+        let mut keys = self
+            .client
+            .list_objects(&self.prefix)?
+            .into_iter()
+            .filter_map(|k| k.strip_prefix(&self.prefix).map(|k| k.to_string()))
+            .collect::<Vec<_>>();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use ark_bls12_381::Fr;
+    use ark_serialize::{Compress, Validate};
+
+    use crate::{algebra::linalg::Matrix, error::KomodoError, fec::encode, fec::Shard};
+
+    use super::{BlockStore, LocalStore, MemoryStore};
+    use crate::fs::{Header, Layout};
+
+    fn bytes() -> Vec<u8> {
+        include_bytes!("../../assets/dragoon_32x32.png").to_vec()
+    }
+
+    fn run<S: BlockStore>(store: &mut S) {
+        let mut rng = ark_std::test_rng();
+        let shards = encode::<Fr>(&bytes(), &Matrix::random(3, 5, &mut rng)).unwrap();
+
+        assert!(store.list().unwrap().is_empty());
+
+        let header = Header::new("test", "BLS12-381", &[]);
+        let hashes = store.dump_blocks(&shards, &header, Compress::Yes).unwrap();
+        let mut sorted_hashes = hashes.clone();
+        sorted_hashes.sort();
+        assert_eq!(store.list().unwrap(), sorted_hashes);
+        assert!(store.contains(&hashes[0]).unwrap());
+
+        let actual = store
+            .read_blocks::<Shard<Fr>>(&hashes, Compress::Yes, Validate::Yes)
+            .unwrap();
+        assert_eq!(
+            actual.into_iter().map(|(_, s)| s).collect::<Vec<_>>(),
+            shards
+        );
+
+        assert_eq!(
+            store
+                .read_block_as::<Shard<Fr>>(&hashes[0], &header, Compress::Yes, Validate::Yes)
+                .unwrap(),
+            shards[0]
+        );
+        let err = store
+            .read_block_as::<Shard<Fr>>(
+                &hashes[0],
+                &Header::new("test", "BN254", &[]),
+                Compress::Yes,
+                Validate::Yes,
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<KomodoError>(),
+            Some(&KomodoError::CurveMismatch(
+                "BN254".to_string(),
+                "BLS12-381".to_string()
+            ))
+        );
+
+        store.delete(&hashes[0]).unwrap();
+        assert!(!store.contains(&hashes[0]).unwrap());
+        assert!(store
+            .read_block::<Shard<Fr>>(&hashes[0], Compress::Yes, Validate::Yes)
+            .is_err());
+        // deleting twice is fine
+        store.delete(&hashes[0]).unwrap();
+        assert_eq!(store.list().unwrap().len(), shards.len() - 1);
+    }
+
+    #[test]
+    fn memory_store() {
+        run(&mut MemoryStore::new());
+    }
+
+    #[test]
+    fn local_store() {
+        for layout in [Layout::Flat, Layout::Sharded { depth: 2, width: 2 }] {
+            let dir = test_dir(&format!("local-store-{:?}", layout));
+            run(&mut LocalStore::new(dir.to_path_buf(), layout));
+        }
+    }
+
+    #[cfg(feature = "object-store")]
+    #[test]
+    fn object_store() {
+        use std::collections::BTreeMap;
+
+        use anyhow::Result;
+
+        use super::{ObjectClient, ObjectStore};
+
+        /// a fake bucket
+        #[derive(Default)]
+        struct Bucket(BTreeMap<String, Vec<u8>>);
+
+        impl ObjectClient for Bucket {
+            fn put_object(&mut self, key: &str, bytes: &[u8]) -> Result<()> {
+                self.0.insert(key.to_string(), bytes.to_vec());
+                Ok(())
+            }
+            fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>> {
+                Ok(self.0.get(key).cloned())
+            }
+            fn delete_object(&mut self, key: &str) -> Result<()> {
+                self.0.remove(key);
+                Ok(())
+            }
+            fn list_objects(&self, prefix: &str) -> Result<Vec<String>> {
+                Ok(self
+                    .0
+                    .keys()
+                    .filter(|k| k.starts_with(prefix))
+                    .cloned()
+                    .collect())
+            }
+        }
+
+        let mut bucket = Bucket::default();
+        bucket.put_object("other/app", &[1, 2, 3]).unwrap();
+
+        let mut store = ObjectStore::new(bucket, "komodo/blocks/");
+        run(&mut store);
+        assert!(store.client.0.contains_key("other/app"));
+    }
+}
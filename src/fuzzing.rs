@@ -0,0 +1,246 @@
+//! support for fuzzing and property testing of protocols built on Komodo
+//!
+//! with the `arbitrary` feature, the core types implement [`arbitrary::Arbitrary`], e.g.
+//! [`crate::fec::Shard`], [`crate::algebra::linalg::Matrix`] and the blocks of all the proving schemes but FRI. The
+//! instances are _structurally_ valid, i.e. they have consistent dimensions, e.g. a shard has as
+//! many coefficients in its linear combination as its $k$ and a Semi-AVID block has one commitment
+//! per source shard, but they are not valid from a cryptographic point of view, i.e. an arbitrary
+//! block is not expected to verify.
+//!
+//! the dimensions are kept small, at most [`MAX_DIMENSION`], so that the operations on arbitrary
+//! instances stay fast enough to be run many times.
+//!
+//! this module also defines the invariants that should hold for any input, e.g. that decoding
+//! encoded data gives the data back, to be checked by property tests or fuzz targets.
+//!
+//! # Example
+//! ```
+//! # use ark_bls12_381::Fr;
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use komodo::{algebra::linalg::Matrix, fec::Shard, fuzzing};
+//!
+//! # fn main() {
+//! let raw = (0..4096).map(|i| (i * 7 + 3) as u8).collect::<Vec<_>>();
+//! let mut u = Unstructured::new(&raw);
+//!
+//! let shard = Shard::<Fr>::arbitrary(&mut u).unwrap();
+//! assert_eq!(shard.linear_combination.len(), shard.k as usize);
+//! assert!(fuzzing::serialization_round_trip(&shard));
+//!
+//! let bytes = Vec::<u8>::arbitrary(&mut u).unwrap();
+//! let points = (1..=5u64).map(Fr::from).collect::<Vec<_>>();
+//! let encoding_mat = Matrix::vandermonde(&points, 3).unwrap();
+//! assert!(fuzzing::encoding_round_trip(&bytes, &encoding_mat).unwrap());
+//! # }
+//! ```
+use arbitrary::Unstructured;
+use ark_ec::Group;
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use ark_std::rand::RngCore;
+
+use crate::{
+    algebra::linalg::Matrix,
+    error::KomodoError,
+    fec,
+    pipeline::{self, ProvingScheme},
+};
+
+/// the maximum size of the dimensions of the arbitrary instances, e.g. $k$ or the number of
+/// elements in a shard
+pub const MAX_DIMENSION: usize = 8;
+
+/// an arbitrary dimension, between $1$ and [`MAX_DIMENSION`]
+pub fn dimension(u: &mut Unstructured) -> arbitrary::Result<usize> {
+    u.int_in_range(1..=MAX_DIMENSION)
+}
+
+/// an arbitrary element of a prime field
+///
+/// > **Note**
+/// >
+/// > the element is built from as many bytes as the modulus, reduced modulo the modulus, i.e. all
+/// > the elements can be generated, and zero when there is no data left.
+pub fn field_element<F: PrimeField>(u: &mut Unstructured) -> arbitrary::Result<F> {
+    let mut bytes = vec![0; (F::MODULUS_BIT_SIZE as usize + 7) / 8];
+    u.fill_buffer(&mut bytes)?;
+    Ok(F::from_le_bytes_mod_order(&bytes))
+}
+
+/// `len` arbitrary elements of a prime field, see [`field_element`]
+pub fn field_elements<F: PrimeField>(
+    u: &mut Unstructured,
+    len: usize,
+) -> arbitrary::Result<Vec<F>> {
+    (0..len).map(|_| field_element(u)).collect()
+}
+
+/// an arbitrary element of a group, as a multiple of its generator, see [`field_element`]
+pub fn group_element<G: Group>(u: &mut Unstructured) -> arbitrary::Result<G> {
+    Ok(G::generator() * field_element::<G::ScalarField>(u)?)
+}
+
+/// whether a value is the same after being serialized and deserialized, with and without
+/// compression
+pub fn serialization_round_trip<T>(value: &T) -> bool
+where
+    T: CanonicalSerialize + CanonicalDeserialize + PartialEq,
+{
+    [Compress::Yes, Compress::No].into_iter().all(|compress| {
+        let mut bytes = vec![];
+        if value.serialize_with_mode(&mut bytes, compress).is_err() {
+            return false;
+        }
+        T::deserialize_with_mode(&bytes[..], compress, Validate::Yes).is_ok_and(|v| &v == value)
+    })
+}
+
+/// whether decoding all the shards of some encoded data gives the data back
+///
+/// the errors are the ones of [`fec::encode`] and [`fec::decode`], e.g. when the data is empty or
+/// when the encoding matrix does not have full rank.
+pub fn encoding_round_trip<F: PrimeField>(
+    bytes: &[u8],
+    encoding_mat: &Matrix<F>,
+) -> Result<bool, KomodoError> {
+    let shards = fec::encode(bytes, encoding_mat)?;
+    Ok(fec::decode(shards)? == bytes)
+}
+
+/// whether decoding as many random recodings of the shards of some encoded data as there are
+/// shards gives the data back, see [`encoding_round_trip`]
+pub fn recoding_round_trip<F: PrimeField, R: RngCore>(
+    bytes: &[u8],
+    encoding_mat: &Matrix<F>,
+    rng: &mut R,
+) -> Result<bool, KomodoError> {
+    let shards = fec::encode(bytes, encoding_mat)?;
+    let recoded = (0..shards.len())
+        .filter_map(|_| fec::recode_random(&shards, rng).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(fec::decode(recoded)? == bytes)
+}
+
+/// whether all the blocks of some dispersed data are valid and collecting them gives the data
+/// back, see [`pipeline::disperse`] and [`pipeline::collect`]
+pub fn proving_round_trip<S: ProvingScheme>(
+    scheme: &S,
+    bytes: &[u8],
+    params: &S::SetupParams,
+) -> Result<bool, KomodoError> {
+    let blocks = pipeline::disperse(scheme, bytes, params)?;
+    for block in &blocks {
+        if !scheme.verify(block, params)? {
+            return Ok(false);
+        }
+    }
+    Ok(pipeline::collect(scheme, blocks, params)? == bytes)
+}
+
+/// an arbitrary square matrix with full rank, i.e. that can be inverted, of size $k$
+///
+/// > **Note**
+/// >
+/// > the matrix is the Vandermonde matrix of $k$ distinct arbitrary points.
+pub fn invertible_matrix<F: PrimeField>(
+    u: &mut Unstructured,
+    k: usize,
+) -> arbitrary::Result<Matrix<F>> {
+    encoding_matrix(u, k, k)
+}
+
+/// an arbitrary $k \times n$ encoding matrix whose $k$ first columns, and any $k$ of its columns,
+/// are linearly independent, i.e. any $k$ shards are enough to decode
+///
+/// > **Note**
+/// >
+/// > the matrix is the Vandermonde matrix of $n$ distinct arbitrary points.
+pub fn encoding_matrix<F: PrimeField>(
+    u: &mut Unstructured,
+    k: usize,
+    n: usize,
+) -> arbitrary::Result<Matrix<F>> {
+    let mut points: Vec<F> = Vec::with_capacity(n);
+    while points.len() < n {
+        let mut point = field_element::<F>(u)?;
+        // NOTE: a point is made distinct from the others by incrementing it
+        while points.contains(&point) {
+            point += F::one();
+        }
+        points.push(point);
+    }
+
+    Matrix::vandermonde(&points, k).map_err(|_| arbitrary::Error::IncorrectFormat)
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_std::{rand::RngCore, test_rng};
+
+    use super::{encoding_matrix, encoding_round_trip, recoding_round_trip};
+    use super::{invertible_matrix, serialization_round_trip};
+    use crate::{algebra::linalg::Matrix, fec::Shard, semi_avid};
+
+    fn raw(size: usize) -> Vec<u8> {
+        let mut raw = vec![0; size];
+        test_rng().fill_bytes(&mut raw);
+        raw
+    }
+
+    #[test]
+    fn structure() {
+        let raw = raw(1 << 16);
+        let mut u = Unstructured::new(&raw);
+
+        for _ in 0..20 {
+            let shard = Shard::<Fr>::arbitrary(&mut u).unwrap();
+            assert_eq!(shard.linear_combination.len(), shard.k as usize);
+            assert!(serialization_round_trip(&shard));
+
+            let matrix = Matrix::<Fr>::arbitrary(&mut u).unwrap();
+            assert_eq!(matrix.elements.len(), matrix.height * matrix.width);
+
+            let block = semi_avid::Block::<Fr, G1Projective>::arbitrary(&mut u).unwrap();
+            assert_eq!(block.proof.len(), block.shard.k as usize);
+            assert!(serialization_round_trip(&block));
+
+            #[cfg(feature = "kzg")]
+            assert!(serialization_round_trip(
+                &crate::kzg::Block::<ark_bls12_381::Bls12_381>::arbitrary(&mut u).unwrap()
+            ));
+            #[cfg(feature = "aplonk")]
+            assert!(serialization_round_trip(
+                &crate::aplonk::Block::<ark_bls12_381::Bls12_381>::arbitrary(&mut u).unwrap()
+            ));
+        }
+
+        // NOTE: there is no data left, the instances are still valid
+        let mut u = Unstructured::new(&[]);
+        let shard = Shard::<Fr>::arbitrary(&mut u).unwrap();
+        assert_eq!(shard.linear_combination.len(), shard.k as usize);
+    }
+
+    #[test]
+    fn invariants() {
+        let mut rng = test_rng();
+        let raw = raw(1 << 16);
+        let mut u = Unstructured::new(&raw);
+
+        for _ in 0..20 {
+            let k = super::dimension(&mut u).unwrap();
+            let n = k + u.int_in_range(0..=4).unwrap();
+            let bytes = Vec::<u8>::arbitrary(&mut u).unwrap();
+
+            let encoding_mat = encoding_matrix::<Fr>(&mut u, k, n).unwrap();
+            if bytes.is_empty() {
+                assert!(encoding_round_trip(&bytes, &encoding_mat).is_err());
+                continue;
+            }
+            assert!(invertible_matrix::<Fr>(&mut u, k).unwrap().invert().is_ok());
+            assert!(encoding_round_trip(&bytes, &encoding_mat).unwrap());
+            assert!(recoding_round_trip(&bytes, &encoding_mat, &mut rng).unwrap());
+        }
+    }
+}
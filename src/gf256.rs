@@ -0,0 +1,457 @@
+//! erasure coding over the small field $GF(2^8)$, for applications that do not need the proofs
+//!
+//! the [`fec`](crate::fec) module works over the large prime fields of the elliptic curves, which
+//! is required to prove the shards but is slow when only plain erasure coding is needed. This
+//! module exposes the same API, i.e. [`encode`], [`recode_random`] and [`decode`], over
+//! $GF(2^8)$, whose arithmetic is a few table lookups and where each byte of data is exactly one
+//! element, i.e. there is no conversion nor padding between bytes and elements.
+//!
+//! > **Note**
+//! >
+//! > the field is built with the polynomial $x^8 + x^4 + x^3 + x^2 + 1$, i.e. `0x11d`, the one of
+//! > most Reed-Solomon deployments, and has $256$ elements, i.e. a [`Matrix::vandermonde`] can
+//! > have at most $256$ columns.
+//!
+//! # Example
+//! ```
+//! use komodo::gf256::{decode, encode, Matrix};
+//!
+//! # fn main() {
+//! let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+//!
+//! let shards = encode(&bytes, &Matrix::vandermonde(3, 6).unwrap()).unwrap();
+//! assert_eq!(decode(shards[2..5].to_vec()).unwrap(), bytes);
+//! # }
+//! ```
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use ark_std::{
+    format,
+    rand::{Rng, RngCore},
+};
+use ark_std::{string::ToString, vec, vec::Vec};
+
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use crate::fec::content_hash;
+use crate::{
+    error::{DecodeContext, KomodoError},
+    telemetry::operation,
+};
+
+/// the reduction polynomial of the field, $x^8 + x^4 + x^3 + x^2 + 1$
+const POLYNOMIAL: u16 = 0x11d;
+
+/// the powers of the generator $x$, twice, so that the sum of two logarithms can be used as is
+const EXP: [u8; 512] = {
+    let mut exp = [0; 512];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        exp[i + 255] = x as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= POLYNOMIAL;
+        }
+        i += 1;
+    }
+    exp
+};
+
+/// the logarithms in base $x$ of the non-zero elements
+const LOG: [u8; 256] = {
+    let mut log = [0; 256];
+    let mut i = 0;
+    while i < 255 {
+        log[EXP[i] as usize] = i as u8;
+        i += 1;
+    }
+    log
+};
+
+/// multiply two elements of $GF(2^8)$
+///
+/// > **Note**
+/// >
+/// > the addition is simply the XOR of the two elements.
+pub fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+}
+
+/// the inverse of an element of $GF(2^8)$, [`None`] for $0$
+pub fn inv(a: u8) -> Option<u8> {
+    if a == 0 {
+        return None;
+    }
+    Some(EXP[255 - LOG[a as usize] as usize])
+}
+
+/// compute `acc += c * x` element-wise
+fn mul_add(acc: &mut [u8], c: u8, x: &[u8]) {
+    if c == 0 {
+        return;
+    }
+    for (a, x) in acc.iter_mut().zip(x) {
+        *a ^= mul(c, *x);
+    }
+}
+
+/// a $k \times n$ encoding matrix over $GF(2^8)$, see [`crate::algebra::linalg::Matrix`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    pub elements: Vec<u8>,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Matrix {
+    /// build a Vandermonde matrix on the points $0, 1, \ldots, n - 1$, any $k$ columns of which
+    /// are linearly independent
+    ///
+    /// > **Note**
+    /// >
+    /// > there are only $256$ distinct points, i.e. [`KomodoError::InvalidVandermonde`] is returned
+    /// > if `width` is greater than $256$.
+    pub fn vandermonde(height: usize, width: usize) -> Result<Self, KomodoError> {
+        if width > 256 {
+            return Err(KomodoError::InvalidVandermonde(0, 256, "0".to_string()));
+        }
+
+        let mut elements = vec![0; height * width];
+        for j in 0..width {
+            let mut x = 1;
+            for i in 0..height {
+                elements[i * width + j] = x;
+                x = mul(x, j as u8);
+            }
+        }
+
+        Ok(Self {
+            elements,
+            height,
+            width,
+        })
+    }
+
+    /// build a completely random matrix of shape $k \times n$
+    #[cfg(any(feature = "prover", not(feature = "verify-only")))]
+    pub fn random<R: RngCore>(height: usize, width: usize, rng: &mut R) -> Self {
+        Self {
+            elements: (0..(height * width)).map(|_| rng.gen()).collect(),
+            height,
+            width,
+        }
+    }
+
+    #[cfg(any(feature = "prover", not(feature = "verify-only")))]
+    fn get_col(&self, j: usize) -> Vec<u8> {
+        (0..self.height)
+            .map(|i| self.elements[i * self.width + j])
+            .collect()
+    }
+}
+
+/// representation of a FEC shard of data over $GF(2^8)$, see [`crate::fec::Shard`]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Shard {
+    /// the code parameter, required to decode
+    pub k: u32,
+    /// the coefficients of the shard with respect to the $k$ source shards
+    pub linear_combination: Vec<u8>,
+    /// the hash of the original data, used for validation
+    pub hash: Vec<u8>,
+    /// the shard itself
+    pub data: Vec<u8>,
+    /// the size of the original data, used for padding
+    pub size: usize,
+}
+
+/// applies a given encoding matrix to some data to generate encoded shards, see
+/// [`crate::fec::encode`]
+///
+/// the data is padded with zeros up to a multiple of $k$ bytes, the $k$ source shards being the
+/// bytes at positions $i$, $i + k$, $i + 2k$, and so on.
+///
+/// This is the inverse of [`decode`].
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn encode(data: &[u8], encoding_mat: &Matrix) -> Result<Vec<Shard>, KomodoError> {
+    let op = operation!(
+        "encode",
+        "gf256",
+        bytes = data.len(),
+        k = encoding_mat.height,
+        n = encoding_mat.width
+    );
+
+    let k = encoding_mat.height;
+    if k == 0 {
+        return Err(KomodoError::IncompatibleMatrixShapes(
+            1,
+            0,
+            0,
+            encoding_mat.width,
+        ));
+    }
+
+    let hash = content_hash(data);
+    let nb_rows = data.len().div_ceil(k);
+    let mut source_shards = vec![vec![0; nb_rows]; k];
+    for (r, row) in data.chunks(k).enumerate() {
+        for (i, x) in row.iter().enumerate() {
+            source_shards[i][r] = *x;
+        }
+    }
+
+    op.done(Ok((0..encoding_mat.width)
+        .map(|j| {
+            let linear_combination = encoding_mat.get_col(j);
+            let mut data_j = vec![0; nb_rows];
+            for (c, s) in linear_combination.iter().zip(&source_shards) {
+                mul_add(&mut data_j, *c, s);
+            }
+
+            Shard {
+                k: k as u32,
+                linear_combination,
+                hash: hash.clone(),
+                data: data_j,
+                size: data.len(),
+            }
+        })
+        .collect()))
+}
+
+/// compute the linear combination of some shards, see [`crate::fec::recode_with_coeffs`]
+///
+/// returns [`None`] if the number of shards is not the same as the number of coefficients or if
+/// no shards are provided.
+pub fn recode_with_coeffs(shards: &[Shard], coeffs: &[u8]) -> Option<Shard> {
+    if shards.len() != coeffs.len() || shards.is_empty() {
+        return None;
+    }
+
+    let mut shard = Shard {
+        linear_combination: vec![0; shards[0].linear_combination.len()],
+        data: vec![0; shards[0].data.len()],
+        ..shards[0].clone()
+    };
+    for (s, c) in shards.iter().zip(coeffs) {
+        mul_add(&mut shard.linear_combination, *c, &s.linear_combination);
+        mul_add(&mut shard.data, *c, &s.data);
+    }
+
+    Some(shard)
+}
+
+/// compute a random linear combination of some shards, see [`crate::fec::recode_random`]
+///
+/// > **Note**
+/// >
+/// > the shards should come from the same data, otherwise
+/// > [`KomodoError::IncompatibleShards`] is returned.
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn recode_random<R: RngCore>(
+    shards: &[Shard],
+    rng: &mut R,
+) -> Result<Option<Shard>, KomodoError> {
+    for (i, s) in shards.iter().enumerate().skip(1) {
+        let first = &shards[0];
+        if (s.k, &s.hash, s.size) != (first.k, &first.hash, first.size) {
+            return Err(KomodoError::IncompatibleShards(format!(
+                "shard {} does not come from the same data as shard 0",
+                i
+            )));
+        }
+    }
+
+    let coeffs = shards.iter().map(|_| rng.gen()).collect::<Vec<u8>>();
+    Ok(recode_with_coeffs(shards, &coeffs))
+}
+
+/// find a maximal set of linearly independent shards, in order, see [`crate::fec::decode`]
+fn decode_context(shards: &[Shard]) -> DecodeContext {
+    let mut basis: Vec<(usize, Vec<u8>)> = vec![];
+    let mut context = DecodeContext {
+        hash: shards.first().map(|s| s.hash.clone()).unwrap_or_default(),
+        ..Default::default()
+    };
+
+    for (i, shard) in shards.iter().enumerate() {
+        let mut row = shard.linear_combination.clone();
+        for (pivot, basis_row) in &basis {
+            let factor = row.get(*pivot).copied().unwrap_or_default();
+            mul_add(&mut row, factor, basis_row);
+        }
+
+        match row.iter().position(|x| *x != 0) {
+            Some(pivot) => {
+                let inverse = inv(row[pivot]).expect("pivot is not zero");
+                row.iter_mut().for_each(|x| *x = mul(*x, inverse));
+                basis.push((pivot, row));
+                context.independent.push(i);
+            }
+            None => context.dependent.push(i),
+        }
+    }
+
+    context
+}
+
+/// reconstruct the original data from a set of encoded, possibly recoded, shards
+///
+/// > **Note**
+/// >
+/// > this fails exactly as [`crate::fec::decode`] does, with [`KomodoError::TooFewShards`] or
+/// > [`KomodoError::LinearlyDependentShards`].
+///
+/// This is the inverse of [`encode`].
+pub fn decode(shards: Vec<Shard>) -> Result<Vec<u8>, KomodoError> {
+    let op = operation!("decode", "gf256", shards = shards.len());
+
+    if shards.is_empty() {
+        return Err(KomodoError::TooFewShards(0, 0, DecodeContext::default()));
+    }
+
+    let k = shards[0].k as usize;
+    let np = shards.len();
+
+    let context = decode_context(&shards);
+    if np < k {
+        return Err(KomodoError::TooFewShards(np, k, context));
+    }
+    if context.rank() < k {
+        return Err(KomodoError::LinearlyDependentShards(k, context));
+    }
+
+    // NOTE: a Gauss-Jordan elimination on the linear combinations, carried over to the data,
+    // turns the shards into the source shards
+    let mut rows = context
+        .independent
+        .iter()
+        .take(k)
+        .map(|&i| (shards[i].linear_combination.clone(), shards[i].data.clone()))
+        .collect::<Vec<_>>();
+    for col in 0..k {
+        let pivot = (col..k)
+            .find(|&r| rows[r].0[col] != 0)
+            .ok_or(KomodoError::NonInvertibleMatrix(col))?;
+        rows.swap(col, pivot);
+
+        let inverse = inv(rows[col].0[col]).expect("pivot is not zero");
+        let (lc, data) = &mut rows[col];
+        lc.iter_mut().for_each(|x| *x = mul(*x, inverse));
+        data.iter_mut().for_each(|x| *x = mul(*x, inverse));
+
+        let (lc, data) = rows[col].clone();
+        for (r, (other_lc, other_data)) in rows.iter_mut().enumerate() {
+            if r != col {
+                let factor = other_lc[col];
+                mul_add(other_lc, factor, &lc);
+                mul_add(other_data, factor, &data);
+            }
+        }
+    }
+
+    let nb_rows = rows[0].1.len();
+    let mut bytes = Vec::with_capacity(nb_rows * k);
+    for r in 0..nb_rows {
+        bytes.extend(rows.iter().map(|(_, data)| data[r]));
+    }
+    bytes.truncate(shards[0].size);
+
+    op.done(Ok(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::KomodoError;
+
+    use super::{decode, encode, inv, mul, recode_random, recode_with_coeffs, Matrix};
+
+    fn bytes() -> Vec<u8> {
+        include_bytes!("../assets/dragoon_32x32.png").to_vec()
+    }
+
+    #[test]
+    fn field() {
+        for a in 1..=255u8 {
+            assert_eq!(mul(a, inv(a).unwrap()), 1, "{a}");
+            assert_eq!(mul(a, 1), a);
+            assert_eq!(mul(a, 0), 0);
+        }
+        assert_eq!(inv(0), None);
+        // NOTE: $x \cdot x^7 = x^8 = x^4 + x^3 + x^2 + 1$
+        assert_eq!(mul(0x02, 0x80), 0x1d);
+    }
+
+    #[test]
+    fn end_to_end() {
+        let bytes = bytes();
+        let mut rng = ark_std::test_rng();
+
+        for (k, n) in [(3, 5), (5, 5), (4, 10), (1, 3)] {
+            let shards = encode(&bytes, &Matrix::vandermonde(k, n).unwrap()).unwrap();
+            assert_eq!(shards.len(), n);
+            assert_eq!(
+                decode(shards[..k].to_vec()).unwrap(),
+                bytes,
+                "k: {k}, n: {n}"
+            );
+            assert_eq!(
+                decode(shards[n - k..].to_vec()).unwrap(),
+                bytes,
+                "k: {k}, n: {n}"
+            );
+
+            let shards = encode(&bytes, &Matrix::random(k, n, &mut rng)).unwrap();
+            if let Ok(decoded) = decode(shards) {
+                assert_eq!(decoded, bytes);
+            }
+        }
+
+        assert_eq!(
+            decode(encode(&[], &Matrix::vandermonde(3, 5).unwrap()).unwrap()).unwrap(),
+            Vec::<u8>::new()
+        );
+        assert!(Matrix::vandermonde(3, 257).is_err());
+    }
+
+    #[test]
+    fn recoding() {
+        let bytes = bytes();
+        let mut rng = ark_std::test_rng();
+
+        let shards = encode(&bytes, &Matrix::vandermonde(3, 5).unwrap()).unwrap();
+
+        let recoded = recode_with_coeffs(&shards[..2], &[1, 1]).unwrap();
+        assert!(matches!(
+            decode(vec![shards[0].clone(), shards[1].clone(), recoded.clone()]),
+            Err(KomodoError::LinearlyDependentShards(3, ..))
+        ));
+        assert_eq!(
+            decode(vec![shards[0].clone(), shards[2].clone(), recoded]).unwrap(),
+            bytes
+        );
+
+        let recoded = (0..3)
+            .map(|_| recode_random(&shards, &mut rng).unwrap().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(decode(recoded).unwrap(), bytes);
+
+        assert!(recode_with_coeffs(&shards, &[1]).is_none());
+        assert!(recode_random(&[], &mut rng).unwrap().is_none());
+
+        let mut other = shards[1].clone();
+        other.size += 1;
+        assert!(matches!(
+            recode_random(&[shards[0].clone(), other], &mut rng),
+            Err(KomodoError::IncompatibleShards(..))
+        ));
+        assert!(matches!(
+            decode(shards[..2].to_vec()),
+            Err(KomodoError::TooFewShards(2, 3, ..))
+        ));
+    }
+}
@@ -0,0 +1,360 @@
+//! versioned identifiers of the serialized artifacts of Komodo
+//!
+//! blocks, shards and manifests are only sequences of field elements and curve points once they
+//! have been serialized: deserializing the bytes of a block computed on another curve, with
+//! another trusted setup or by another protocol either fails with an obscure serialization error
+//! or, worse, succeeds and gives garbage. All the artifacts that leave a process are thus prefixed
+//! with a [`Header`]
+//! ```text
+//! | magic (4 bytes) | version as a u32 LE (4 bytes) | protocol | curve | setup digest | payload |
+//! ```
+//! where the protocol, the curve and the setup digest are serialized with `ark-serialize`, and the
+//! header is checked against the expected one, i.e. the one of the reader, before the payload is
+//! deserialized. A mismatch is a typed error, e.g. [`KomodoError::VersionMismatch`] or
+//! [`KomodoError::CurveMismatch`], see [`KomodoError::is_mismatch`].
+//!
+//! [`to_bytes`] and [`from_bytes`] do all of this for any serializable object, [`wrap`] and
+//! [`unwrap`] for already serialized bytes. The files written by `fs` start with the same header,
+//! followed by a versioned extension of their own.
+//!
+//! # Example
+//! ```
+//! # use ark_bls12_381::Fr;
+//! # use ark_serialize::{Compress, Validate};
+//! use komodo::{
+//!     error::KomodoError,
+//!     header::{self, Header, Protocol},
+//! };
+//!
+//! # fn main() {
+//! let header = Header::new(Protocol::SemiAvid, "BLS12-381", &[]);
+//! let bytes = header::to_bytes(&Fr::from(42u64), &header, Compress::Yes).unwrap();
+//!
+//! let x: Fr = header::from_bytes(&bytes, &header, Compress::Yes, Validate::Yes).unwrap();
+//! assert_eq!(x, Fr::from(42u64));
+//!
+//! let other = Header::new(Protocol::SemiAvid, "BN254", &[]);
+//! assert!(matches!(
+//!     header::from_bytes::<Fr>(&bytes, &other, Compress::Yes, Validate::Yes),
+//!     Err(KomodoError::CurveMismatch(..))
+//! ));
+//! # }
+//! ```
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate,
+};
+use ark_std::{
+    fmt,
+    io::{Read, Write},
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::error::KomodoError;
+
+/// the current version of the [`Header`]
+pub const VERSION: u32 = 1;
+
+/// the magic number at the start of all the artifacts
+pub(crate) const MAGIC: [u8; 4] = *b"KMDA";
+
+/// the protocol an artifact belongs to
+///
+/// protocols are identified by their name, see [`Protocol::name`], i.e. the protocols that are
+/// not part of Komodo can be identified with [`Protocol::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    /// the shards of [`crate::fec`]
+    Fec,
+    /// the blocks of [`crate::semi_avid`]
+    SemiAvid,
+    /// the blocks of `kzg`
+    Kzg,
+    /// the blocks of `aplonk`
+    Aplonk,
+    /// the blocks of `fri`
+    Fri,
+    /// the manifests of the files stored with `fs`
+    Manifest,
+    /// any other protocol, e.g. one built on top of Komodo
+    Other(String),
+}
+
+impl Protocol {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Fec => "fec",
+            Self::SemiAvid => "semi-avid",
+            Self::Kzg => "kzg",
+            Self::Aplonk => "aplonk",
+            Self::Fri => "fri",
+            Self::Manifest => "manifest",
+            Self::Other(name) => name,
+        }
+    }
+}
+
+impl From<&str> for Protocol {
+    fn from(name: &str) -> Self {
+        match name {
+            "fec" => Self::Fec,
+            "semi-avid" => Self::SemiAvid,
+            "kzg" => Self::Kzg,
+            "aplonk" => Self::Aplonk,
+            "fri" => Self::Fri,
+            "manifest" => Self::Manifest,
+            name => Self::Other(name.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// the header of an artifact, see the [module-level documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// the version of the header, see [`VERSION`]
+    pub version: u32,
+    pub protocol: Protocol,
+    /// the curve the artifact has been computed on, e.g. `"BLS12-381"`
+    pub curve: String,
+    /// the digest of the trusted setup the artifact has been computed with, empty if there is none
+    pub setup_digest: Vec<u8>,
+}
+
+impl Header {
+    /// a header with the current [`VERSION`]
+    pub fn new(protocol: Protocol, curve: &str, setup_digest: &[u8]) -> Self {
+        Self {
+            version: VERSION,
+            protocol,
+            curve: curve.to_string(),
+            setup_digest: setup_digest.to_vec(),
+        }
+    }
+
+    /// check that the header of an artifact, i.e. `self`, is compatible with the `expected` one
+    ///
+    /// the artifact should
+    /// - not be more recent than the reader, i.e. its version should be at most the expected one
+    /// - have the expected protocol and curve
+    /// - have been computed with the expected trusted setup, if the expected digest is not empty
+    pub fn check(&self, expected: &Self) -> Result<(), KomodoError> {
+        if self.version > expected.version {
+            return Err(KomodoError::VersionMismatch(expected.version, self.version));
+        }
+        if self.protocol != expected.protocol {
+            return Err(KomodoError::ProtocolMismatch(
+                expected.protocol.to_string(),
+                self.protocol.to_string(),
+            ));
+        }
+        if self.curve != expected.curve {
+            return Err(KomodoError::CurveMismatch(
+                expected.curve.clone(),
+                self.curve.clone(),
+            ));
+        }
+        if !expected.setup_digest.is_empty() && self.setup_digest != expected.setup_digest {
+            return Err(KomodoError::SetupMismatch(
+                hex(&expected.setup_digest),
+                hex(&self.setup_digest),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), KomodoError> {
+        // NOTE: the errors of `ark_std::io` are only `std::io::Error` with `std`
+        writer
+            .write_all(&MAGIC)
+            .and_then(|_| writer.write_all(&self.version.to_le_bytes()))
+            .map_err(SerializationError::from)?;
+        self.protocol
+            .name()
+            .to_string()
+            .serialize_with_mode(&mut writer, Compress::No)?;
+        self.curve.serialize_with_mode(&mut writer, Compress::No)?;
+        self.setup_digest
+            .serialize_with_mode(&mut writer, Compress::No)?;
+        Ok(())
+    }
+
+    /// consume the header at the start of `reader`
+    ///
+    /// data without any header, e.g. written by an older version of Komodo, has version $0$, i.e.
+    /// this fails with [`KomodoError::VersionMismatch`].
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, KomodoError> {
+        let mut magic = [0; 4];
+        if reader.read_exact(&mut magic).is_err() || magic != MAGIC {
+            return Err(KomodoError::VersionMismatch(VERSION, 0));
+        }
+
+        let mut version = [0; 4];
+        reader
+            .read_exact(&mut version)
+            .map_err(SerializationError::from)?;
+        let (protocol, curve, setup_digest) = <(String, String, Vec<u8>)>::deserialize_with_mode(
+            &mut reader,
+            Compress::No,
+            Validate::Yes,
+        )?;
+
+        Ok(Self {
+            version: u32::from_le_bytes(version),
+            protocol: Protocol::from(protocol.as_str()),
+            curve,
+            setup_digest,
+        })
+    }
+}
+
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "version {}, protocol {:?}, curve {:?}, setup {}",
+            self.version,
+            self.protocol.name(),
+            self.curve,
+            hex(&self.setup_digest)
+        )
+    }
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest
+        .iter()
+        .map(|x| ark_std::format!("{:02x}", x))
+        .collect()
+}
+
+/// serialize an object, prefixed with a header
+pub fn to_bytes<T: CanonicalSerialize>(
+    value: &T,
+    header: &Header,
+    compress: Compress,
+) -> Result<Vec<u8>, KomodoError> {
+    let mut bytes = Vec::new();
+    header.write(&mut bytes)?;
+    value.serialize_with_mode(&mut bytes, compress)?;
+    Ok(bytes)
+}
+
+/// deserialize an object serialized with [`to_bytes`], after checking its header, see
+/// [`Header::check`]
+pub fn from_bytes<T: CanonicalDeserialize>(
+    bytes: &[u8],
+    expected: &Header,
+    compress: Compress,
+    validate: Validate,
+) -> Result<T, KomodoError> {
+    Ok(T::deserialize_with_mode(
+        unwrap(bytes, expected)?,
+        compress,
+        validate,
+    )?)
+}
+
+/// prefix some already serialized bytes with a header
+pub fn wrap(payload: &[u8], header: &Header) -> Result<Vec<u8>, KomodoError> {
+    let mut bytes = Vec::with_capacity(payload.len());
+    header.write(&mut bytes)?;
+    bytes.extend_from_slice(payload);
+    Ok(bytes)
+}
+
+/// check the header of some bytes built with [`wrap`] or [`to_bytes`] and give the payload back
+pub fn unwrap<'a>(bytes: &'a [u8], expected: &Header) -> Result<&'a [u8], KomodoError> {
+    let mut reader = bytes;
+    Header::read(&mut reader)?.check(expected)?;
+    Ok(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr;
+    use ark_serialize::{CanonicalSerialize, Compress, Validate};
+
+    use super::{from_bytes, to_bytes, unwrap, wrap, Header, Protocol, VERSION};
+    use crate::error::KomodoError;
+
+    #[test]
+    fn protocols() {
+        for protocol in [
+            Protocol::Fec,
+            Protocol::SemiAvid,
+            Protocol::Kzg,
+            Protocol::Aplonk,
+            Protocol::Fri,
+            Protocol::Manifest,
+            Protocol::Other("my-protocol".to_string()),
+        ] {
+            assert_eq!(Protocol::from(protocol.name()), protocol);
+        }
+    }
+
+    #[test]
+    fn mismatches() {
+        let header = Header::new(Protocol::SemiAvid, "BLS12-381", &[1, 2, 3]);
+        let bytes = to_bytes(&Fr::from(42u64), &header, Compress::Yes).unwrap();
+        let read =
+            |expected: &Header| from_bytes::<Fr>(&bytes, expected, Compress::Yes, Validate::Yes);
+
+        assert_eq!(read(&header), Ok(Fr::from(42u64)));
+        // NOTE: the setup is not checked when the reader does not know it
+        assert!(read(&Header::new(Protocol::SemiAvid, "BLS12-381", &[])).is_ok());
+
+        let older = Header {
+            version: VERSION - 1,
+            ..header.clone()
+        };
+        assert_eq!(
+            read(&older),
+            Err(KomodoError::VersionMismatch(VERSION - 1, VERSION))
+        );
+        assert_eq!(
+            read(&Header::new(Protocol::Kzg, "BLS12-381", &[1, 2, 3])),
+            Err(KomodoError::ProtocolMismatch(
+                "kzg".to_string(),
+                "semi-avid".to_string()
+            ))
+        );
+        assert_eq!(
+            read(&Header::new(Protocol::SemiAvid, "BN254", &[1, 2, 3])),
+            Err(KomodoError::CurveMismatch(
+                "BN254".to_string(),
+                "BLS12-381".to_string()
+            ))
+        );
+        assert_eq!(
+            read(&Header::new(Protocol::SemiAvid, "BLS12-381", &[4])),
+            Err(KomodoError::SetupMismatch(
+                "04".to_string(),
+                "010203".to_string()
+            ))
+        );
+
+        // NOTE: data without any header
+        let mut raw = vec![];
+        Fr::from(42u64)
+            .serialize_with_mode(&mut raw, Compress::Yes)
+            .unwrap();
+        assert_eq!(
+            from_bytes::<Fr>(&raw, &header, Compress::Yes, Validate::Yes),
+            Err(KomodoError::VersionMismatch(VERSION, 0))
+        );
+        assert_eq!(
+            unwrap(&[], &header),
+            Err(KomodoError::VersionMismatch(VERSION, 0))
+        );
+
+        let wrapped = wrap(&raw, &header).unwrap();
+        assert_eq!(wrapped, bytes);
+        assert_eq!(unwrap(&wrapped, &header).unwrap(), &raw[..]);
+    }
+}
@@ -42,13 +42,26 @@ use ark_ff::PrimeField;
 use ark_poly::DenseUVPolynomial;
 use ark_poly_commit::{kzg10, PCRandomness};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError};
-use ark_std::{ops::Div, Zero};
+use ark_std::{
+    cfg_iter,
+    ops::Div,
+    rand::{CryptoRng, RngCore},
+    Zero,
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use rs_merkle::{algorithms::Sha256, Hasher};
+use std::marker::PhantomData;
 use std::ops::{AddAssign, Mul};
 
-use crate::algebra;
-use crate::error::KomodoError;
-use crate::fec::Shard;
+use crate::algebra::{self, linalg::Matrix};
+use crate::error::{KomodoError, Source};
+use crate::fec::{self, Shard};
+#[cfg(feature = "arbitrary")]
+use crate::fuzzing;
+use crate::pipeline::ProvingScheme;
+use crate::telemetry::operation;
+use crate::zk::trim;
 
 pub use crate::zk::ark_commit as commit;
 
@@ -63,6 +76,38 @@ pub struct Block<E: Pairing> {
     proof: kzg10::Proof<E>,
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a, E: Pairing> arbitrary::Arbitrary<'a> for Block<E> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let shard = Shard::arbitrary(u)?;
+        // NOTE: there is one commitment per polynomial, i.e. per element of the shard
+        let commit = (0..shard.data.len())
+            .map(|_| {
+                Ok(kzg10::Commitment(
+                    fuzzing::group_element::<E::G1>(u)?.into(),
+                ))
+            })
+            .collect::<arbitrary::Result<_>>()?;
+        let proof = kzg10::Proof {
+            w: fuzzing::group_element::<E::G1>(u)?.into(),
+            random_v: None,
+        };
+
+        Ok(Self {
+            shard,
+            commit,
+            proof,
+        })
+    }
+}
+
+impl<E: Pairing> Block<E> {
+    /// the commitments of the polynomials of the data, one per row, see [`prove`]
+    pub fn commits(&self) -> &[kzg10::Commitment<E>] {
+        &self.commit
+    }
+}
+
 /// proves $n$ encoded shards by computing one proof for each of them and attaching the commitment
 pub fn prove<E, P>(
     commits: Vec<kzg10::Commitment<E>>,
@@ -84,46 +129,50 @@ where
         points.len()
     );
 
+    let op = operation!("prove", "kzg", shards = shards.len());
+
     // step 3. each shard will contain an evaluation of each polynomial
     // in i (the alpha corresponding to the matrix column)
     // and the commit of each polynomials
     // compute a random combination of the polynomials and compute a proof for this polynomial
-    let mut proofs = Vec::new();
-    for (s, pt) in shards.iter().zip(points.iter()) {
-        let mut eval_bytes = vec![];
-        for p in &polynomials {
-            let elt = p.evaluate(pt);
-            if let Err(error) = elt.serialize_with_mode(&mut eval_bytes, Compress::Yes) {
-                return Err(KomodoError::Other(format!("Serialization: {}", error)));
-            }
-        }
-
-        let mut compressed_bytes = Vec::new();
-        for el in &s.data {
-            el.serialize_uncompressed(&mut compressed_bytes).unwrap();
-        }
-        let hash = Sha256::hash(&compressed_bytes);
-        let r = E::ScalarField::from_le_bytes_mod_order(&hash);
-
-        let r_vec = algebra::powers_of::<E>(r, polynomials.len());
-        let poly_q = algebra::scalar_product_polynomial::<E, P>(&r_vec, &polynomials);
-
-        match kzg10::KZG10::<E, P>::open(
-            &powers,
-            &poly_q,
-            *pt,
-            &kzg10::Randomness::<E::ScalarField, P>::empty(),
-        ) {
-            Ok(proof) => proofs.push(Block {
-                shard: s.clone(),
-                commit: commits.clone(),
-                proof,
-            }),
-            Err(error) => return Err(KomodoError::Other(format!("kzg open error: {}", error))),
-        };
-    }
-
-    Ok(proofs)
+    //
+    // NOTE: the shards are proven in parallel with the `parallel` feature
+    op.done(
+        cfg_iter!(shards)
+            .zip(cfg_iter!(points))
+            .map(|(s, pt)| {
+                let mut eval_bytes = vec![];
+                for p in &polynomials {
+                    let elt = p.evaluate(pt);
+                    elt.serialize_with_mode(&mut eval_bytes, Compress::Yes)?;
+                }
+
+                let mut compressed_bytes = Vec::new();
+                for el in &s.data {
+                    el.serialize_uncompressed(&mut compressed_bytes).unwrap();
+                }
+                let hash = Sha256::hash(&compressed_bytes);
+                let r = E::ScalarField::from_le_bytes_mod_order(&hash);
+
+                let r_vec = algebra::powers_of::<E>(r, polynomials.len());
+                let poly_q = algebra::scalar_product_polynomial::<E, P>(&r_vec, &polynomials);
+
+                match kzg10::KZG10::<E, P>::open(
+                    &powers,
+                    &poly_q,
+                    *pt,
+                    &kzg10::Randomness::<E::ScalarField, P>::empty(),
+                ) {
+                    Ok(proof) => Ok(Block {
+                        shard: s.clone(),
+                        commit: commits.clone(),
+                        proof,
+                    }),
+                    Err(error) => Err(KomodoError::KzgOpen(Source::new(error))),
+                }
+            })
+            .collect(),
+    )
 }
 
 fn compute_data_for_one_shard<E, P>(block: &Block<E>) -> (E::ScalarField, E::G1)
@@ -171,12 +220,14 @@ where
     P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
     for<'a, 'b> &'a P: Div<&'b P, Output = P>,
 {
+    let op = operation!("verify", "kzg", elements = block.shard.data.len());
+
     let (y, c) = compute_data_for_one_shard(block);
 
     let p1 = c - verifier_key.g.mul(y);
     let inner = verifier_key.beta_h.into_group() - verifier_key.h.mul(&pt);
 
-    E::pairing(p1, verifier_key.h) == E::pairing(block.proof.w, inner)
+    op.verified(E::pairing(p1, verifier_key.h) == E::pairing(block.proof.w, inner))
 }
 
 /// verify a bunch of blocks at once using a single elliptic pairing.
@@ -209,6 +260,8 @@ where
     P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
     for<'a, 'b> &'a P: Div<&'b P, Output = P>,
 {
+    let op = operation!("verify", "kzg", blocks = blocks.len());
+
     let mut data = Vec::new();
     for b in blocks {
         b.proof.w.serialize_with_mode(&mut data, Compress::Yes)?
@@ -229,8 +282,132 @@ where
     );
 
     // e(sum(r^i * proof_i, T * g2) = e(sum(r^i * (commit_i  - y_i * g1 + alpha_i * proof_i)),g2)
-    Ok(E::pairing(proof_agg, verifier_key.beta_h)
-        == E::pairing(inner_agg, verifier_key.h.into_group()))
+    Ok(op.verified(
+        E::pairing(proof_agg, verifier_key.beta_h)
+            == E::pairing(inner_agg, verifier_key.h.into_group()),
+    ))
+}
+
+/// KZG+ as a [`ProvingScheme`], with $k$ and the $n$ evaluation points of the encoding
+///
+/// > **Note**
+/// >
+/// > the shards are encoded with the Vandermonde matrix of the points, so that the evaluation
+/// > point of a block can be read back from its linear combination.
+pub struct Kzg<E: Pairing, P> {
+    points: Vec<E::ScalarField>,
+    k: usize,
+    _phantom: PhantomData<P>,
+}
+
+impl<E: Pairing, P> Kzg<E, P> {
+    /// $n$ is the number of `points`
+    pub fn new(points: Vec<E::ScalarField>, k: usize) -> Self {
+        Self {
+            points,
+            k,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// the trusted setup of [`Kzg`], for polynomials of degree $k - 1$
+fn scheme_setup<E, P>(
+    k: usize,
+    rng: &mut impl RngCore,
+) -> Result<(kzg10::Powers<'static, E>, kzg10::VerifierKey<E>), KomodoError>
+where
+    E: Pairing,
+    P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    let params = kzg10::KZG10::<E, P>::setup(k - 1, false, rng)
+        .map_err(|e| KomodoError::KzgCommit(Source::new(e)))?;
+    Ok(trim(params, k - 1))
+}
+
+impl<E, P> ProvingScheme for Kzg<E, P>
+where
+    E: Pairing,
+    P: DenseUVPolynomial<E::ScalarField, Point = E::ScalarField>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    type Field = E::ScalarField;
+    type SetupParams = (kzg10::Powers<'static, E>, kzg10::VerifierKey<E>);
+    type Proof = Vec<Block<E>>;
+    type Block = Block<E>;
+
+    fn setup<R: RngCore>(
+        &self,
+        _nb_bytes: usize,
+        rng: &mut R,
+    ) -> Result<Self::SetupParams, KomodoError> {
+        crate::randomness::check("kzg::setup")?;
+        scheme_setup::<E, P>(self.k, rng)
+    }
+
+    fn setup_secure<R: RngCore + CryptoRng>(
+        &self,
+        _nb_bytes: usize,
+        rng: &mut R,
+    ) -> Result<Self::SetupParams, KomodoError> {
+        scheme_setup::<E, P>(self.k, rng)
+    }
+
+    fn encode(&self, bytes: &[u8]) -> Result<Vec<Shard<E::ScalarField>>, KomodoError> {
+        fec::encode(bytes, &Matrix::vandermonde_unchecked(&self.points, self.k))
+    }
+
+    fn encode_with_hash(
+        &self,
+        bytes: &[u8],
+        hash: &[u8],
+    ) -> Result<Vec<Shard<E::ScalarField>>, KomodoError> {
+        let encoding_mat = Matrix::vandermonde_unchecked(&self.points, self.k);
+        fec::encode_with_hash(bytes, &encoding_mat, hash.to_vec())
+    }
+
+    fn prove(
+        &self,
+        bytes: &[u8],
+        shards: &[Shard<E::ScalarField>],
+        (powers, _): &Self::SetupParams,
+    ) -> Result<Vec<Block<E>>, KomodoError> {
+        let polynomials = algebra::split_data_into_field_elements::<E::ScalarField>(bytes, self.k)
+            .chunks(self.k)
+            .map(|c| P::from_coefficients_vec(c.to_vec()))
+            .collect::<Vec<_>>();
+        let (commits, _) =
+            commit(powers, &polynomials).map_err(|e| KomodoError::KzgCommit(Source::new(e)))?;
+        prove::<E, P>(
+            commits,
+            polynomials,
+            shards.to_vec(),
+            self.points.clone(),
+            powers.clone(),
+        )
+    }
+
+    // NOTE: the blocks are built at the same time as the proofs
+    fn build(&self, _shards: Vec<Shard<E::ScalarField>>, proof: Vec<Block<E>>) -> Vec<Block<E>> {
+        proof
+    }
+
+    fn verify(
+        &self,
+        block: &Block<E>,
+        (_, verifier_key): &Self::SetupParams,
+    ) -> Result<bool, KomodoError> {
+        Ok(verify::<E, P>(
+            block,
+            block.shard.linear_combination[1],
+            verifier_key,
+        ))
+    }
+
+    fn shard(block: &Block<E>) -> &Shard<E::ScalarField> {
+        &block.shard
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,8 @@
 //! Komodo: Cryptographically-proven Erasure Coding
 //!
 //! Komodo provides an easy-to-use Rust library and ecosystem that is composed of two main parts:
-//! - support for FEC encoding and decoding with the [`fec`] submodule
+//! - support for FEC encoding and decoding with the [`fec`] submodule, and over the small field
+//!   $GF(2^8)$ with [`gf256`]* when the shards do not need to be proven
 //! - support for proving and verifying shards of encoded data with the [`semi_avid`], [`kzg`]* and
 //! [`aplonk`]* submodules
 //!
@@ -10,6 +11,22 @@
 //! > modules marked with an `*`, e.g. [`kzg`]*, are hidden behind a _Cargo_ feature with the same
 //! > name
 //!
+//! all the proving schemes implement [`ProvingScheme`], see [`pipeline`] to write code that works
+//! with any of them, [`sealed`]* to encrypt the data before dispersing it and [`threshold`] to make
+//! $k$ an access-control threshold on top of a durability parameter, and [`das`] provides the
+//! primitives of data-availability sampling. The blocks can be signed by the nodes that produce
+//! them with [`auth`]* and [`nonblocking`]* runs all the CPU-heavy operations from async code.
+//! Finally, [`Komodo`], from [`facade`] and behind the `fs` feature, wires everything together to
+//! store files and fetch them back in a few lines, while [`repair`], behind the same feature, plans
+//! and drives the repair of the blocks that have been lost.
+//!
+//! The heavy operations are instrumented with [`tracing`] spans and, with the `metrics` feature,
+//! metrics, see [`telemetry`]. Protocols built on Komodo can be fuzzed and property-tested with
+//! arbitrary instances of the core types, see [`fuzzing`]*, and other implementations can be
+//! checked against deterministic [`testvectors`]*. The artifacts that leave a process are
+//! prefixed with a versioned [`header`], so that incompatible nodes fail with a typed error, and
+//! [`randomness`] makes sure that the secrets are drawn from secure sources of randomness.
+//!
 //! Other submodules define several fundamental building blocks to Komodo, but which are not
 //! mandatory to explore to understand the protocols.
 //!
@@ -43,19 +60,168 @@
 //! ```ignore
 //! assert_eq!(bytes, fec::decode(blocks[0..k]));
 //! ```
+//!
+//! # API stability
+//! the public API of Komodo comes in three tiers:
+//! - **stable**: the items re-exported at the root of the crate, i.e. [`encode`], [`decode`],
+//!   [`recode`], [`prove`], [`build`], [`verify`], [`Block`], [`Shard`] and [`KomodoError`] from
+//!   Semi-AVID and [`fec`], together with [`ProvingScheme`], [`pipeline`] and [`Komodo`]*. These
+//!   paths follow semantic versioning, i.e. they only break with a new major version, whatever
+//!   happens to the modules behind them.
+//! - **per protocol**: the modules of the proving schemes, e.g. [`semi_avid`], [`kzg`]*,
+//!   [`aplonk`]* and `fri`*, which all expose their own `prove`, `verify` and `Block`. Their
+//!   paths are stable but the signatures of their functions might change in minor versions when a
+//!   protocol evolves, [`ProvingScheme`] being the stable way to use them.
+//! - **building blocks**: all the other modules, e.g. [`algebra`] or [`zk`], which are public to
+//!   be reused and explored but might be reorganized in any minor version.
+//!
+//! downstream code that only needs to encode, prove, verify and decode should thus import from the
+//! root of the crate:
+//! ```
+//! # use ark_bls12_381::{Fr as F, G1Projective as G};
+//! # use ark_poly::univariate::DensePolynomial as DP;
+//! use komodo::{algebra::linalg::Matrix, build, decode, encode, prove, verify, zk};
+//!
+//! # fn main() {
+//! # let mut rng = ark_std::test_rng();
+//! # let (k, n) = (3, 6_usize);
+//! # let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+//! let powers = zk::setup::<F, G>(bytes.len(), &mut rng).unwrap();
+//!
+//! let shards = encode(&bytes, &Matrix::random(k, n, &mut rng)).unwrap();
+//! let proof = prove::<F, G, DP<F>>(&bytes, &powers, k).unwrap();
+//! let blocks = build::<F, G, DP<F>>(&shards, &proof);
+//!
+//! assert!(verify::<F, G, DP<F>>(&blocks[0], &powers).unwrap());
+//! let shards = blocks[..k].iter().map(|b| b.shard.clone()).collect();
+//! assert_eq!(decode(shards).unwrap(), bytes);
+//! # }
+//! ```
+//!
+//! # `no_std`
+//! the [`error`], [`algebra`], [`fec`], [`zk`], [`semi_avid`], [`pipeline`], [`threshold`],
+//! [`das`], [`header`], [`randomness`], [`telemetry`] and [`gf256`]* modules only require
+//! `alloc`, i.e. Komodo can be used without the standard library, e.g. in embedded verifiers or
+//! WASM runtimes, by disabling the default `std` feature. All the other features require `std`.
+//!
+//! # Verification only
+//! light clients, e.g. wallets, that only verify blocks produced by others can leave the provers
+//! out of their build with the `verify-only` feature, together with `default-features = false`:
+//! ```toml
+//! komodo = { version = "1.0.1", default-features = false, features = ["verify-only"] }
+//! ```
+//! everything that proves, encodes or recodes data, or that requires a source of randomness, is
+//! then not compiled, e.g. [`fec::encode`], [`zk::setup`], [`semi_avid::prove`], [`pipeline`] and
+//! [`threshold`], while the verification paths remain, e.g. [`semi_avid::verify`],
+//! [`das::verify_sample`] and [`fec::decode`], and the dependency tree is reduced to _arkworks_,
+//! `rs_merkle` and `tracing`.
+//!
+//! > **Note**
+//! >
+//! > Cargo features are additive: the provers are compiled back as soon as the `prover` feature is
+//! > enabled, e.g. by another crate of the dependency graph or by any feature that needs them,
+//! > such as `fs` or `kzg`. The tests and the examples require the provers.
+//!
+//! # WASM
+//! the same modules compile to `wasm32-unknown-unknown`, with or without `std`, the randomness
+//! then coming from the JS runtime. `bindings/wasm` exposes Semi-AVID to JS with `wasm-bindgen`.
+//!
+//! > **Note**
+//! >
+//! > there is no filesystem on `wasm32-unknown-unknown`, i.e. the `fs` feature is not supported.
+//!
+//! # Parallelism
+//! the `parallel` feature turns on the `parallel` features of the _arkworks_ dependencies and makes
+//! the heavy loops of Komodo run on [_rayon_](https://docs.rs/rayon), through the `cfg_*` macros of
+//! [`ark_std`], which fall back to serial iterators without the feature:
+//! - [`algebra`]: conversions between bytes and field elements
+//! - [`algebra::linalg`] and thus [`fec`]: multiplication, inversion and transposition of matrices
+//! - [`fec`]: the shards of [`fec::encode`] and [`fec::encode_iter`] are built row by row, the
+//!   FFTs of [`fec::encode_fft`] run one per row
+//! - [`zk`]: commitment of polynomials with [`zk::batch_commit`]
+//! - [`kzg`]* and [`aplonk`]*: proofs of the shards, one per thread
+//!
+//! the thread pool is the global one of _rayon_, whose size defaults to the number of CPUs and can
+//! be set with the `RAYON_NUM_THREADS` environment variable. The operations can also run on a
+//! dedicated pool, e.g. to isolate them from the rest of an application, by calling them inside
+//! [`rayon::ThreadPool::install`](https://docs.rs/rayon/latest/rayon/struct.ThreadPool.html#method.install).
+//!
+//! > **Note**
+//! >
+//! > `parallel` requires `std` and the results are the same with and without it.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(all(feature = "fs", target_arch = "wasm32", target_os = "unknown"))]
+compile_error!(
+    "the `fs` feature is not supported on `wasm32-unknown-unknown`, there is no filesystem"
+);
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
 pub mod algebra;
 #[cfg(feature = "aplonk")]
 pub mod aplonk;
+#[cfg(feature = "auth")]
+pub mod auth;
 #[cfg(test)]
 #[cfg(any(feature = "kzg", feature = "aplonk"))]
 mod conversions;
+pub mod das;
 pub mod error;
+#[cfg(feature = "fs")]
+pub mod facade;
 pub mod fec;
 #[cfg(feature = "fri")]
 pub mod fri;
 #[cfg(feature = "fs")]
 pub mod fs;
+#[cfg(feature = "arbitrary")]
+pub mod fuzzing;
+#[cfg(feature = "gf256")]
+pub mod gf256;
+pub mod header;
 #[cfg(feature = "kzg")]
 pub mod kzg;
+#[cfg(feature = "network")]
+pub mod network;
+#[cfg(feature = "tokio")]
+pub mod nonblocking;
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub mod pipeline;
+pub mod randomness;
+#[cfg(feature = "fs")]
+pub mod repair;
+#[cfg(feature = "sealed")]
+pub mod sealed;
 pub mod semi_avid;
+pub mod telemetry;
+#[cfg(feature = "testvectors")]
+pub mod testvectors;
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub mod threshold;
 pub mod zk;
+
+// NOTE: the stable API, see the "API stability" section above
+pub use error::KomodoError;
+#[cfg(feature = "fs")]
+pub use facade::Komodo;
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub use fec::encode;
+pub use fec::{decode, Shard};
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub use pipeline::ProvingScheme;
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub use semi_avid::{build, prove, recode};
+pub use semi_avid::{verify, Block};
+
+/// the legacy location of [`algebra::linalg`]
+///
+/// > **Note**
+/// >
+/// > this is only an alias to keep older imports working, [`algebra::linalg`] is the one and only
+/// > implementation of linear algebra in Komodo.
+#[deprecated(note = "use `komodo::algebra::linalg` instead")]
+pub mod linalg {
+    pub use crate::algebra::linalg::*;
+}
@@ -0,0 +1,683 @@
+//! disseminate blocks between Komodo nodes with [libp2p](https://libp2p.io/)
+//!
+//! a [`Node`] stores blocks in a directory, as [`crate::fs`] would do, and speaks two protocols:
+//! - a _gossip_ topic, [`MANIFEST_TOPIC`], on which the [`Manifest`]s of the dispersed files are
+//!   announced
+//! - a request-response protocol, [`PROTOCOL`], see [`Request`], to push blocks to a node, to
+//!   fetch blocks from it, to ask it which blocks of a file it is missing and to ask it to verify
+//!   one of its blocks
+//!
+//! the blocks are verified with a [`ProvingScheme`] before being stored, whether they have been
+//! pushed by a peer or fetched from it, and the inbound requests are answered by the node itself:
+//! [`Node::next_event`] only returns what the application needs to know about.
+//!
+//! > **Note**
+//! >
+//! > the blocks travel as their serialized bytes, i.e. the payload of the block files, so their
+//! > hashes are the same on the wire and on the disk.
+//! >
+//! > the blocks and the manifests are prefixed with a [`header::Header`] on the wire, built from
+//! > [`Config::header`], so that nodes with another protocol, curve or trusted setup are rejected
+//! > with a typed error, see [`crate::error::KomodoError::is_mismatch`], instead of sending
+//! > garbage to each other. The hash of a block is still the one of its payload only.
+//!
+//! # Example
+//! ```ignore
+//! let mut node = Node::new(Keypair::generate_ed25519(), scheme, setup, config)?;
+//! node.listen("/ip4/0.0.0.0/tcp/4001".parse()?)?;
+//! loop {
+//!     if let Event::Announced { peer, manifest } = node.next_event().await {
+//!         node.fetch_missing(peer, &manifest)?;
+//!     }
+//! }
+//! ```
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use anyhow::{anyhow, Result};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use futures::StreamExt;
+use libp2p::{
+    gossipsub,
+    identity::Keypair,
+    noise,
+    request_response::{self, OutboundRequestId, ProtocolSupport},
+    swarm::{NetworkBehaviour, SwarmEvent},
+    tcp, yamux, Multiaddr, PeerId, StreamProtocol, Swarm,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    fs::{self, Encryption, Header, Layout, Manifest},
+    header::{self, Protocol},
+    pipeline::ProvingScheme,
+};
+
+/// the request-response protocol of the blocks
+pub const PROTOCOL: &str = "/komodo/blocks/2.0.0";
+/// the gossip topic of the manifests
+pub const MANIFEST_TOPIC: &str = "komodo/manifests/2";
+
+/// a request to another node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Request {
+    /// store a block, given as its serialized bytes
+    Push(Vec<u8>),
+    /// get the serialized bytes of a block from its hash
+    Get(String),
+    /// the blocks of a file that are missing, from the hash of the file, see
+    /// [`Manifest::file_hash`]
+    Missing(Vec<u8>),
+    /// verify a block from its hash
+    Verify(String),
+}
+
+/// the answer of a node to a [`Request`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Response {
+    /// the hash of the block that has been stored
+    Stored(String),
+    /// the serialized bytes of a block
+    Block(Vec<u8>),
+    /// the blocks of a file that are missing or corrupted and how many more blocks are needed to
+    /// reconstruct it, see [`fs::ManifestStatus`]
+    Missing {
+        missing: Vec<String>,
+        nb_needed: usize,
+    },
+    /// whether a block is valid
+    Verified(bool),
+    /// the request could not be answered
+    Error(String),
+}
+
+/// where and how a [`Node`] stores its blocks and manifests
+#[derive(Clone)]
+pub struct Config {
+    pub block_dir: PathBuf,
+    pub manifest_dir: PathBuf,
+    pub layout: Layout,
+    /// the header of the stored blocks, completed for each block with [`Header::for_blocks`]
+    pub header: Header,
+    pub compress: Compress,
+    pub validate: Validate,
+    /// how long a connection without any request is kept open
+    pub idle_timeout: Duration,
+}
+
+/// what happened on the network
+#[derive(Debug)]
+pub enum Event {
+    /// the node listens on a new address
+    Listening(Multiaddr),
+    /// a connection has been established with a peer
+    Connected(PeerId),
+    /// a peer has announced a file, its manifest has been saved
+    Announced { peer: PeerId, manifest: Manifest },
+    /// a block requested with [`Node::fetch`] has arrived, it has been stored if it is valid
+    Fetched {
+        peer: PeerId,
+        hash: String,
+        valid: bool,
+    },
+    /// the answer to any other request
+    Response {
+        peer: PeerId,
+        id: OutboundRequestId,
+        response: Response,
+    },
+    /// a request could not reach a peer or has not been answered
+    Failed {
+        peer: PeerId,
+        id: OutboundRequestId,
+        error: String,
+    },
+}
+
+#[derive(NetworkBehaviour)]
+struct Behaviour {
+    gossipsub: gossipsub::Behaviour,
+    blocks: request_response::cbor::Behaviour<Request, Response>,
+}
+
+/// a Komodo node, see the [module-level documentation](self)
+pub struct Node<S: ProvingScheme> {
+    swarm: Swarm<Behaviour>,
+    scheme: S,
+    params: S::SetupParams,
+    config: Config,
+    /// the hashes of the blocks requested with [`Node::fetch`]
+    fetching: HashMap<OutboundRequestId, String>,
+}
+
+/// make sure a name received from the network is a single path component, e.g. a hash
+fn check_name(name: &str) -> Result<&str> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(anyhow!("invalid name {:?}", name));
+    }
+    Ok(name)
+}
+
+impl<S> Node<S>
+where
+    S: ProvingScheme,
+    S::Block: CanonicalSerialize + CanonicalDeserialize,
+{
+    /// build a node, with the identity `keypair`, which does not listen on any address yet
+    pub fn new(
+        keypair: Keypair,
+        scheme: S,
+        params: S::SetupParams,
+        config: Config,
+    ) -> Result<Self> {
+        let mut swarm = libp2p::SwarmBuilder::with_existing_identity(keypair)
+            .with_tokio()
+            .with_tcp(
+                tcp::Config::default(),
+                noise::Config::new,
+                yamux::Config::default,
+            )?
+            .with_behaviour(|key| {
+                let gossipsub = gossipsub::Behaviour::new(
+                    gossipsub::MessageAuthenticity::Signed(key.clone()),
+                    gossipsub::ConfigBuilder::default()
+                        .validation_mode(gossipsub::ValidationMode::Strict)
+                        .build()?,
+                )?;
+                let blocks = request_response::cbor::Behaviour::new(
+                    [(StreamProtocol::new(PROTOCOL), ProtocolSupport::Full)],
+                    request_response::Config::default(),
+                );
+                Ok(Behaviour { gossipsub, blocks })
+            })?
+            .with_swarm_config(|c| c.with_idle_connection_timeout(config.idle_timeout))
+            .build();
+
+        swarm
+            .behaviour_mut()
+            .gossipsub
+            .subscribe(&gossipsub::IdentTopic::new(MANIFEST_TOPIC))?;
+
+        Ok(Self {
+            swarm,
+            scheme,
+            params,
+            config,
+            fetching: HashMap::new(),
+        })
+    }
+
+    pub fn peer_id(&self) -> PeerId {
+        *self.swarm.local_peer_id()
+    }
+
+    pub fn listen(&mut self, addr: Multiaddr) -> Result<()> {
+        self.swarm.listen_on(addr)?;
+        Ok(())
+    }
+
+    pub fn dial(&mut self, addr: Multiaddr) -> Result<()> {
+        self.swarm.dial(addr)?;
+        Ok(())
+    }
+
+    /// save a manifest locally and announce it to the other nodes
+    ///
+    /// > **Note**
+    /// >
+    /// > this fails if no other node is subscribed to [`MANIFEST_TOPIC`] yet.
+    pub fn announce(&mut self, manifest: &Manifest) -> Result<()> {
+        manifest.save(&self.config.manifest_dir, self.config.compress)?;
+
+        let bytes = header::to_bytes(manifest, &self.manifest_header(), self.config.compress)?;
+        self.swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(gossipsub::IdentTopic::new(MANIFEST_TOPIC), bytes)?;
+        Ok(())
+    }
+
+    /// send any request to a peer, its answer will come as an [`Event::Response`]
+    pub fn request(&mut self, peer: PeerId, request: Request) -> OutboundRequestId {
+        self.swarm
+            .behaviour_mut()
+            .blocks
+            .send_request(&peer, request)
+    }
+
+    /// push a block to a peer, which will answer with its hash
+    pub fn push(&mut self, peer: PeerId, block: &S::Block) -> Result<OutboundRequestId> {
+        let bytes = header::to_bytes(block, &self.block_header(), self.config.compress)?;
+        Ok(self.request(peer, Request::Push(bytes)))
+    }
+
+    /// fetch a block from a peer, it will come as an [`Event::Fetched`]
+    pub fn fetch(&mut self, peer: PeerId, hash: &str) -> OutboundRequestId {
+        let id = self.request(peer, Request::Get(hash.to_string()));
+        self.fetching.insert(id, hash.to_string());
+        id
+    }
+
+    /// fetch all the blocks of a manifest that are missing or corrupted locally
+    pub fn fetch_missing(
+        &mut self,
+        peer: PeerId,
+        manifest: &Manifest,
+    ) -> Result<Vec<OutboundRequestId>> {
+        let status = manifest.verify(&self.config.block_dir, self.config.layout)?;
+        Ok(status
+            .missing
+            .iter()
+            .chain(status.corrupted.iter())
+            .map(|hash| self.fetch(peer, hash))
+            .collect())
+    }
+
+    /// drive the node until something happens, answering the inbound requests on the way
+    pub async fn next_event(&mut self) -> Event {
+        loop {
+            let event = match self.swarm.select_next_some().await {
+                SwarmEvent::NewListenAddr { address, .. } => Some(Event::Listening(address)),
+                SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                    Some(Event::Connected(peer_id))
+                }
+                SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(gossipsub::Event::Message {
+                    propagation_source,
+                    message,
+                    ..
+                })) => self.on_announcement(propagation_source, &message.data),
+                SwarmEvent::Behaviour(BehaviourEvent::Blocks(event)) => self.on_blocks(event),
+                _ => None,
+            };
+            if let Some(event) = event {
+                return event;
+            }
+        }
+    }
+
+    fn on_announcement(&mut self, peer: PeerId, data: &[u8]) -> Option<Event> {
+        let manifest = match header::from_bytes::<Manifest>(
+            data,
+            &self.manifest_header(),
+            self.config.compress,
+            self.config.validate,
+        ) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                warn!("invalid manifest from {}: {}", peer, e);
+                return None;
+            }
+        };
+        if let Err(e) = manifest.save(&self.config.manifest_dir, self.config.compress) {
+            warn!("could not save manifest from {}: {}", peer, e);
+            return None;
+        }
+
+        info!("file {} announced by {}", manifest.filename(), peer);
+        Some(Event::Announced { peer, manifest })
+    }
+
+    fn on_blocks(&mut self, event: request_response::Event<Request, Response>) -> Option<Event> {
+        match event {
+            request_response::Event::Message { peer, message } => match message {
+                request_response::Message::Request {
+                    request, channel, ..
+                } => {
+                    let response = self.answer(request).unwrap_or_else(|e| {
+                        warn!("request from {} failed: {}", peer, e);
+                        Response::Error(e.to_string())
+                    });
+                    if self
+                        .swarm
+                        .behaviour_mut()
+                        .blocks
+                        .send_response(channel, response)
+                        .is_err()
+                    {
+                        warn!("could not answer {}, the connection is closed", peer);
+                    }
+                    None
+                }
+                request_response::Message::Response {
+                    request_id,
+                    response,
+                } => match (self.fetching.remove(&request_id), response) {
+                    (Some(hash), Response::Block(bytes)) => {
+                        let valid = self.store_fetched(&hash, &bytes).unwrap_or_else(|e| {
+                            warn!("block {} from {} is invalid: {}", hash, peer, e);
+                            false
+                        });
+                        Some(Event::Fetched { peer, hash, valid })
+                    }
+                    (_, response) => Some(Event::Response {
+                        peer,
+                        id: request_id,
+                        response,
+                    }),
+                },
+            },
+            request_response::Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+            } => {
+                self.fetching.remove(&request_id);
+                Some(Event::Failed {
+                    peer,
+                    id: request_id,
+                    error: error.to_string(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn answer(&self, request: Request) -> Result<Response> {
+        match request {
+            Request::Push(bytes) => {
+                let block = self.deserialize(header::unwrap(&bytes, &self.block_header())?)?;
+                if !self.scheme.verify(&block, &self.params)? {
+                    return Err(anyhow!("invalid block"));
+                }
+                Ok(Response::Stored(self.store(&block)?))
+            }
+            Request::Get(hash) => {
+                let block = self.read(check_name(&hash)?)?;
+                Ok(Response::Block(header::to_bytes(
+                    &block,
+                    &self.block_header(),
+                    self.config.compress,
+                )?))
+            }
+            Request::Missing(file_hash) => {
+                // NOTE: the manifests are named after the hash of their file, see
+                // [`Manifest::filename`]
+                let filename: String = file_hash.iter().map(|x| format!("{:02x}", x)).collect();
+                let manifest = Manifest::load(
+                    &filename,
+                    &self.config.manifest_dir,
+                    self.config.compress,
+                    self.config.validate,
+                )?;
+                let status = manifest.verify(&self.config.block_dir, self.config.layout)?;
+                Ok(Response::Missing {
+                    missing: [status.missing, status.corrupted].concat(),
+                    nb_needed: status.nb_needed,
+                })
+            }
+            Request::Verify(hash) => {
+                let block = self.read(check_name(&hash)?)?;
+                Ok(Response::Verified(
+                    self.scheme.verify(&block, &self.params)?,
+                ))
+            }
+        }
+    }
+
+    /// the header of the blocks on the wire
+    fn block_header(&self) -> header::Header {
+        self.config.header.artifact.clone()
+    }
+
+    /// the header of the manifests on the wire
+    fn manifest_header(&self) -> header::Header {
+        header::Header {
+            protocol: Protocol::Manifest,
+            ..self.block_header()
+        }
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Result<S::Block> {
+        Ok(S::Block::deserialize_with_mode(
+            bytes,
+            self.config.compress,
+            self.config.validate,
+        )?)
+    }
+
+    fn read(&self, hash: &str) -> Result<S::Block> {
+        let mut blocks = fs::read_blocks::<S::Block>(
+            &[hash.to_string()],
+            &self.config.block_dir,
+            self.config.layout,
+            self.config.compress,
+            self.config.validate,
+            Encryption::None,
+        )?;
+        Ok(blocks.remove(0).1)
+    }
+
+    fn store(&self, block: &S::Block) -> Result<String> {
+        let shard = S::shard(block);
+        fs::dump_block(
+            block,
+            &self.config.block_dir,
+            self.config.layout,
+            &self.config.header.clone().for_blocks(shard.k, &shard.hash),
+            self.config.compress,
+            fs::Compression::None,
+            Encryption::None,
+        )
+    }
+
+    /// store a fetched block if it is the one that has been requested and it is valid
+    fn store_fetched(&self, hash: &str, bytes: &[u8]) -> Result<bool> {
+        let bytes = header::unwrap(bytes, &self.block_header())?;
+        if self.config.header.extension.naming.name(bytes) != hash {
+            return Ok(false);
+        }
+        let block = self.deserialize(bytes)?;
+        if !self.scheme.verify(&block, &self.params)? {
+            return Ok(false);
+        }
+        self.store(&block)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::test_dir;
+    use std::time::Duration;
+
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_serialize::{Compress, Validate};
+    use ark_std::test_rng;
+    use libp2p::identity::Keypair;
+    use tokio::sync::mpsc;
+
+    use super::{check_name, Config, Event, Node, Request, Response};
+    use crate::{
+        algebra::linalg::Matrix,
+        fs::{Header, Layout, Manifest},
+        pipeline::{self, ProvingScheme},
+        semi_avid::SemiAvid,
+    };
+
+    type S = SemiAvid<Fr, G1Projective, DensePolynomial<Fr>>;
+
+    fn config(dir: &std::path::Path) -> Config {
+        Config {
+            block_dir: dir.join("blocks"),
+            manifest_dir: dir.join("manifests"),
+            layout: Layout::Flat,
+            header: Header::new("semi-avid", "BLS12-381", &[]),
+            compress: Compress::Yes,
+            validate: Validate::Yes,
+            idle_timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// the next event of `node` that is not about the connections
+    async fn next(node: &mut Node<S>) -> Event {
+        loop {
+            match node.next_event().await {
+                Event::Listening(_) | Event::Connected(_) => continue,
+                event => return event,
+            }
+        }
+    }
+
+    #[test]
+    fn names_from_the_network() {
+        assert!(check_name("0123abcd").is_ok());
+        for name in ["", ".", "..", "../blocks", "a/b", "a\\b"] {
+            assert!(check_name(name).is_err(), "{:?} should be rejected", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn dissemination() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+        let (k, n) = (3, 5);
+
+        let scheme = S::new(Matrix::random(k, n, &mut rng));
+        let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let blocks = pipeline::disperse(&scheme, &bytes, &setup).unwrap();
+
+        let dir = test_dir("network");
+
+        // NOTE: the storage node runs in the background, the events of interest are forwarded
+        let mut storage = Node::new(
+            Keypair::generate_ed25519(),
+            S::new(Matrix::random(k, n, &mut rng)),
+            setup.clone(),
+            config(&dir.join("storage")),
+        )
+        .unwrap();
+        storage
+            .listen("/ip4/127.0.0.1/tcp/0".parse().unwrap())
+            .unwrap();
+        let storage_id = storage.peer_id();
+        let addr = match storage.next_event().await {
+            Event::Listening(addr) => addr,
+            event => panic!("expected the storage to listen, found {:?}", event),
+        };
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let event = storage.next_event().await;
+                if let Event::Announced { manifest, .. } = event {
+                    tx.send(manifest).unwrap();
+                }
+            }
+        });
+
+        let mut client = Node::new(
+            Keypair::generate_ed25519(),
+            scheme,
+            setup,
+            config(&dir.join("client")),
+        )
+        .unwrap();
+        client.dial(addr).unwrap();
+        loop {
+            if let Event::Connected(peer) = client.next_event().await {
+                assert_eq!(peer, storage_id);
+                break;
+            }
+        }
+
+        let mut hashes = vec![];
+        for block in &blocks {
+            client.push(storage_id, block).unwrap();
+            match next(&mut client).await {
+                Event::Response {
+                    peer,
+                    response: Response::Stored(hash),
+                    ..
+                } => {
+                    assert_eq!(peer, storage_id);
+                    hashes.push(hash);
+                }
+                event => panic!("expected a block to be stored, found {:?}", event),
+            }
+        }
+
+        client.request(storage_id, Request::Verify(hashes[0].clone()));
+        assert!(matches!(
+            next(&mut client).await,
+            Event::Response {
+                response: Response::Verified(true),
+                ..
+            }
+        ));
+
+        client.request(storage_id, Request::Get("../manifests".to_string()));
+        assert!(matches!(
+            next(&mut client).await,
+            Event::Response {
+                response: Response::Error(_),
+                ..
+            }
+        ));
+
+        // NOTE: an invalid block is refused
+        let mut invalid = blocks[0].clone();
+        invalid.shard.data[0] += Fr::from(1u64);
+        client.push(storage_id, &invalid).unwrap();
+        assert!(matches!(
+            next(&mut client).await,
+            Event::Response {
+                response: Response::Error(_),
+                ..
+            }
+        ));
+
+        let manifest = Manifest {
+            file_hash: blocks[0].shard.hash.clone(),
+            size: bytes.len(),
+            k,
+            n,
+            curve: "BLS12-381".to_string(),
+            setup_digest: vec![],
+            block_hashes: hashes.clone(),
+            sealing: None,
+        };
+        // NOTE: the subscription of the storage to the gossip topic can take a moment to arrive
+        let announce = async {
+            while client.announce(&manifest).is_err() {
+                tokio::select! {
+                    _ = client.next_event() => {},
+                    _ = tokio::time::sleep(Duration::from_millis(100)) => {},
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), announce)
+            .await
+            .expect("the manifest could not be announced");
+        let received = tokio::select! {
+            manifest = rx.recv() => manifest.unwrap(),
+            _ = async { loop { client.next_event().await; } } => unreachable!(),
+        };
+        assert_eq!(received, manifest);
+
+        client.request(storage_id, Request::Missing(manifest.file_hash.clone()));
+        assert_eq!(
+            match next(&mut client).await {
+                Event::Response { response, .. } => response,
+                event => panic!("expected the missing blocks, found {:?}", event),
+            },
+            Response::Missing {
+                missing: vec![],
+                nb_needed: 0
+            }
+        );
+
+        let ids = client.fetch_missing(storage_id, &manifest).unwrap();
+        assert_eq!(ids.len(), n);
+        for _ in 0..n {
+            assert!(matches!(
+                next(&mut client).await,
+                Event::Fetched { valid: true, .. }
+            ));
+        }
+        let status = manifest
+            .verify(&dir.join("client").join("blocks"), Layout::Flat)
+            .unwrap();
+        assert!(status.is_complete());
+        assert!(status.missing.is_empty());
+    }
+}
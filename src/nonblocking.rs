@@ -0,0 +1,304 @@
+//! async wrappers around the CPU-heavy operations of Komodo, for services running on _Tokio_
+//!
+//! encoding, proving, verifying and decoding take from milliseconds to seconds and would block the
+//! executor of a network service if they were called directly from async code. A [`Pool`] runs
+//! them on the blocking threads of _Tokio_, with [`tokio::task::spawn_blocking`], and bounds how
+//! many of them run at the same time, e.g. so that a flood of blocks to verify does not starve the
+//! rest of the service.
+//!
+//! > **Note**
+//! >
+//! > all the futures can be cancelled, e.g. with a timeout or `tokio::select!`, by dropping them.
+//! > An operation that has not started yet, i.e. that is still waiting for a slot in the pool,
+//! > never starts, but an operation that has already started runs to completion on its blocking
+//! > thread, and keeps its slot until then, before its result is discarded.
+//!
+//! # Example
+//! ```
+//! # use std::sync::Arc;
+//! # use ark_bls12_381::{Fr as F, G1Projective as G};
+//! # use ark_poly::univariate::DensePolynomial as DP;
+//! use komodo::{
+//!     algebra::linalg::Matrix, nonblocking::Pool, pipeline::ProvingScheme, semi_avid::SemiAvid,
+//! };
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let mut rng = ark_std::test_rng();
+//! let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+//!
+//! let scheme = Arc::new(SemiAvid::<F, G, DP<F>>::new(Matrix::random(3, 6, &mut rng)));
+//! let setup = Arc::new(scheme.setup(bytes.len(), &mut rng).unwrap());
+//!
+//! let pool = Pool::new(4);
+//! let blocks = pool
+//!     .disperse(scheme.clone(), bytes.clone(), setup.clone())
+//!     .await
+//!     .unwrap();
+//! let valid = pool
+//!     .verify_all(scheme.clone(), blocks.clone(), setup.clone())
+//!     .await
+//!     .unwrap();
+//! assert!(valid.iter().all(|&v| v));
+//! assert_eq!(
+//!     pool.collect(scheme, blocks[3..].to_vec(), setup).await.unwrap(),
+//!     bytes
+//! );
+//! # }
+//! ```
+use std::sync::Arc;
+
+use ark_std::rand::RngCore;
+use tokio::sync::Semaphore;
+
+use crate::{
+    error::{KomodoError, Source},
+    pipeline::{self, ProvingScheme},
+};
+
+/// a pool of blocking threads running at most a given number of operations at the same time
+///
+/// a pool is cheap to clone and all the clones share the same slots.
+#[derive(Debug, Clone)]
+pub struct Pool {
+    permits: Arc<Semaphore>,
+    max_concurrency: usize,
+}
+
+impl Pool {
+    /// a pool running at most `max_concurrency` operations at the same time, and at least one
+    pub fn new(max_concurrency: usize) -> Self {
+        let max_concurrency = max_concurrency.max(1);
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrency)),
+            max_concurrency,
+        }
+    }
+
+    /// the maximum number of operations running at the same time
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+
+    /// the number of operations that could start right now
+    pub fn available(&self) -> usize {
+        self.permits.available_permits()
+    }
+
+    /// run any blocking operation in the pool, see the [module-level documentation](self)
+    ///
+    /// it is a [`KomodoError::Task`] if the operation panics.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, KomodoError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, KomodoError> + Send + 'static,
+    {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| KomodoError::Task(Source::new(e)))?;
+
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            f()
+        })
+        .await
+        .map_err(|e| KomodoError::Task(Source::new(e)))?
+    }
+
+    /// see [`pipeline::disperse`]
+    pub async fn disperse<S>(
+        &self,
+        scheme: Arc<S>,
+        bytes: Vec<u8>,
+        params: Arc<S::SetupParams>,
+    ) -> Result<Vec<S::Block>, KomodoError>
+    where
+        S: ProvingScheme + Send + Sync + 'static,
+        S::SetupParams: Send + Sync + 'static,
+        S::Block: Send + 'static,
+    {
+        self.run(move || pipeline::disperse(&*scheme, &bytes, &params))
+            .await
+    }
+
+    /// see [`ProvingScheme::verify`]
+    pub async fn verify<S>(
+        &self,
+        scheme: Arc<S>,
+        block: S::Block,
+        params: Arc<S::SetupParams>,
+    ) -> Result<bool, KomodoError>
+    where
+        S: ProvingScheme + Send + Sync + 'static,
+        S::SetupParams: Send + Sync + 'static,
+        S::Block: Send + 'static,
+    {
+        self.run(move || scheme.verify(&block, &params)).await
+    }
+
+    /// verify blocks concurrently, as many at a time as the pool allows, see [`Pool::verify`]
+    ///
+    /// the results are in the same order as the blocks.
+    pub async fn verify_all<S>(
+        &self,
+        scheme: Arc<S>,
+        blocks: Vec<S::Block>,
+        params: Arc<S::SetupParams>,
+    ) -> Result<Vec<bool>, KomodoError>
+    where
+        S: ProvingScheme + Send + Sync + 'static,
+        S::SetupParams: Send + Sync + 'static,
+        S::Block: Send + 'static,
+    {
+        let tasks = blocks
+            .into_iter()
+            .map(|block| {
+                let (pool, scheme, params) = (self.clone(), scheme.clone(), params.clone());
+                tokio::spawn(async move { pool.verify(scheme, block, params).await })
+            })
+            .collect::<Vec<_>>();
+
+        let mut valid = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            valid.push(
+                task.await
+                    .map_err(|e| KomodoError::Task(Source::new(e)))??,
+            );
+        }
+        Ok(valid)
+    }
+
+    /// see [`ProvingScheme::recode`]
+    pub async fn recode<S, R>(
+        &self,
+        scheme: Arc<S>,
+        blocks: Vec<S::Block>,
+        mut rng: R,
+    ) -> Result<Option<S::Block>, KomodoError>
+    where
+        S: ProvingScheme + Send + Sync + 'static,
+        S::Block: Send + 'static,
+        R: RngCore + Send + 'static,
+    {
+        self.run(move || scheme.recode(&blocks, &mut rng)).await
+    }
+
+    /// see [`pipeline::collect`]
+    pub async fn collect<S>(
+        &self,
+        scheme: Arc<S>,
+        blocks: Vec<S::Block>,
+        params: Arc<S::SetupParams>,
+    ) -> Result<Vec<u8>, KomodoError>
+    where
+        S: ProvingScheme + Send + Sync + 'static,
+        S::SetupParams: Send + Sync + 'static,
+        S::Block: Send + 'static,
+    {
+        self.run(move || pipeline::collect(&*scheme, blocks, &params))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc,
+        },
+        time::Duration,
+    };
+
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_std::test_rng;
+
+    use super::Pool;
+    use crate::{
+        algebra::linalg::Matrix, error::KomodoError, pipeline::ProvingScheme, semi_avid::SemiAvid,
+    };
+
+    type S = SemiAvid<Fr, G1Projective, DensePolynomial<Fr>>;
+
+    #[tokio::test]
+    async fn pipeline() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        let scheme = Arc::new(S::new(Matrix::random(3, 5, &mut rng)));
+        let setup = Arc::new(scheme.setup(bytes.len(), &mut rng).unwrap());
+
+        let pool = Pool::new(2);
+        let mut blocks = pool
+            .disperse(scheme.clone(), bytes.clone(), setup.clone())
+            .await
+            .unwrap();
+        let recoded = pool
+            .recode(scheme.clone(), blocks[..3].to_vec(), test_rng())
+            .await
+            .unwrap()
+            .unwrap();
+        blocks.push(recoded);
+
+        blocks[1].shard.data[0] += Fr::from(1u64);
+        let valid = pool
+            .verify_all(scheme.clone(), blocks.clone(), setup.clone())
+            .await
+            .unwrap();
+        assert_eq!(valid, vec![true, false, true, true, true, true]);
+
+        // NOTE: the invalid block is discarded
+        assert!(matches!(
+            pool.collect(scheme, blocks[1..4].to_vec(), setup).await,
+            Err(KomodoError::TooFewShards(2, 3, _))
+        ));
+        assert_eq!(pool.available(), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrency_and_cancellation() {
+        let pool = Pool::new(0);
+        assert_eq!(pool.max_concurrency(), 1);
+
+        let (tx, rx) = mpsc::channel::<()>();
+        let busy = tokio::spawn({
+            let pool = pool.clone();
+            async move {
+                pool.run(move || {
+                    rx.recv().unwrap();
+                    Ok(())
+                })
+                .await
+            }
+        });
+        while pool.available() > 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        // NOTE: the operation is dropped while it is waiting for the busy one, it never starts
+        let ran = Arc::new(AtomicBool::new(false));
+        let waiting = pool.run({
+            let ran = ran.clone();
+            move || {
+                ran.store(true, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+        assert!(tokio::time::timeout(Duration::from_millis(50), waiting)
+            .await
+            .is_err());
+
+        tx.send(()).unwrap();
+        busy.await.unwrap().unwrap();
+        assert!(!ran.load(Ordering::SeqCst));
+        assert_eq!(pool.available(), 1);
+
+        let panicked = pool.run(|| -> Result<(), KomodoError> { panic!("oops") });
+        assert!(matches!(panicked.await, Err(KomodoError::Task(_))));
+        assert_eq!(pool.available(), 1);
+    }
+}
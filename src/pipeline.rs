@@ -0,0 +1,347 @@
+//! a common interface to all the proving schemes and a pipeline that works with any of them
+//!
+//! all the proving schemes of Komodo follow the same steps, see the [crate-level
+//! example](crate): the data is encoded into shards, the shards are proven and turned into
+//! blocks, the blocks are verified one by one and any $k$ of them can be decoded. [`ProvingScheme`]
+//! captures these steps, so that an application can be written once, with [`disperse`] and
+//! [`collect`], and then run with any of the schemes:
+//! - [`crate::semi_avid::SemiAvid`]
+//! - [`crate::kzg::Kzg`]*
+//! - [`crate::aplonk::Aplonk`]*
+//! - [`crate::fri::Fri`]*
+//!
+//! # Example
+//! ```
+//! # use ark_bls12_381::{Fr as F, G1Projective as G};
+//! # use ark_poly::univariate::DensePolynomial as DP;
+//! use komodo::{
+//!     algebra::linalg::Matrix,
+//!     pipeline::{self, ProvingScheme},
+//!     semi_avid::SemiAvid,
+//! };
+//!
+//! # fn main() {
+//! let mut rng = ark_std::test_rng();
+//! let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+//!
+//! let scheme = SemiAvid::<F, G, DP<F>>::new(Matrix::random(3, 6, &mut rng));
+//! let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+//!
+//! let blocks = pipeline::disperse(&scheme, &bytes, &setup).unwrap();
+//! assert_eq!(pipeline::collect(&scheme, blocks[3..].to_vec(), &setup).unwrap(), bytes);
+//! # }
+//! ```
+use ark_ff::PrimeField;
+use ark_std::{
+    rand::{CryptoRng, RngCore},
+    vec::Vec,
+};
+
+use crate::{error::KomodoError, fec::Shard};
+
+/// a scheme to prove encoded shards, e.g. [Semi-AVID](crate::semi_avid)
+///
+/// > **Note**
+/// >
+/// > the parameters of the encoding, e.g. $k$, $n$ or the encoding matrix, are the ones of the
+/// > value implementing the trait, the rest, e.g. the trusted setup, is given to each step.
+/// >
+/// > the steps that need randomness come in two versions, the `_secure` ones only accepting
+/// > cryptographically secure sources, see [`crate::randomness`].
+pub trait ProvingScheme {
+    /// the finite field of the shards
+    type Field: PrimeField;
+    /// what is needed to prove and verify the blocks, e.g. a trusted setup
+    type SetupParams;
+    /// the proof of the shards, as computed by [`ProvingScheme::prove`]
+    type Proof;
+    /// a shard together with its proof
+    type Block: Clone;
+
+    /// whether [`ProvingScheme::recode`] is supported
+    const RECODABLE: bool = false;
+
+    /// build the setup needed to prove `nb_bytes` of data
+    fn setup<R: RngCore>(
+        &self,
+        nb_bytes: usize,
+        rng: &mut R,
+    ) -> Result<Self::SetupParams, KomodoError>;
+
+    /// the same as [`ProvingScheme::setup`], with a cryptographically secure source of randomness
+    ///
+    /// the default implementation calls [`ProvingScheme::setup`], the schemes of Komodo bypass
+    /// the [`crate::randomness::RandomnessPolicy`].
+    fn setup_secure<R: RngCore + CryptoRng>(
+        &self,
+        nb_bytes: usize,
+        rng: &mut R,
+    ) -> Result<Self::SetupParams, KomodoError> {
+        self.setup(nb_bytes, rng)
+    }
+
+    /// encode the data into $n$ shards
+    fn encode(&self, bytes: &[u8]) -> Result<Vec<Shard<Self::Field>>, KomodoError>;
+
+    /// the same as [`ProvingScheme::encode`] with a precomputed hash of the data, see
+    /// [`crate::fec::encode_with_hash`]
+    ///
+    /// the default implementation encodes the data and then replaces the hash of the shards, the
+    /// schemes of Komodo never hash the data.
+    fn encode_with_hash(
+        &self,
+        bytes: &[u8],
+        hash: &[u8],
+    ) -> Result<Vec<Shard<Self::Field>>, KomodoError> {
+        let mut shards = self.encode(bytes)?;
+        for shard in shards.iter_mut() {
+            shard.hash = hash.to_vec();
+        }
+        Ok(shards)
+    }
+
+    /// compute the proof of the shards of some data
+    fn prove(
+        &self,
+        bytes: &[u8],
+        shards: &[Shard<Self::Field>],
+        params: &Self::SetupParams,
+    ) -> Result<Self::Proof, KomodoError>;
+
+    /// compute the proofs of the shards of a batch of files, one set of shards per file, in the
+    /// same order as the files
+    ///
+    /// the files are proven one after the other by default, a scheme can amortize the fixed costs
+    /// of the proofs across the files, e.g. [`crate::semi_avid::SemiAvid`].
+    fn prove_batch(
+        &self,
+        files: &[&[u8]],
+        shards: &[Vec<Shard<Self::Field>>],
+        params: &Self::SetupParams,
+    ) -> Result<Vec<Self::Proof>, KomodoError> {
+        files
+            .iter()
+            .zip(shards)
+            .map(|(bytes, shards)| self.prove(bytes, shards, params))
+            .collect()
+    }
+
+    /// attach the proof to the shards
+    fn build(&self, shards: Vec<Shard<Self::Field>>, proof: Self::Proof) -> Vec<Self::Block>;
+
+    /// verify a single block
+    fn verify(&self, block: &Self::Block, params: &Self::SetupParams) -> Result<bool, KomodoError>;
+
+    /// combine some blocks into a new one, [`None`] if there are no blocks or if the scheme is not
+    /// [`ProvingScheme::RECODABLE`]
+    fn recode<R: RngCore>(
+        &self,
+        _blocks: &[Self::Block],
+        _rng: &mut R,
+    ) -> Result<Option<Self::Block>, KomodoError> {
+        Ok(None)
+    }
+
+    /// the same as [`ProvingScheme::recode`], with a cryptographically secure source of
+    /// randomness, see [`ProvingScheme::setup_secure`]
+    fn recode_secure<R: RngCore + CryptoRng>(
+        &self,
+        blocks: &[Self::Block],
+        rng: &mut R,
+    ) -> Result<Option<Self::Block>, KomodoError> {
+        self.recode(blocks, rng)
+    }
+
+    /// the shard of a block
+    fn shard(block: &Self::Block) -> &Shard<Self::Field>;
+
+    /// decode the original data from at least $k$ blocks
+    fn decode(&self, blocks: Vec<Self::Block>) -> Result<Vec<u8>, KomodoError> {
+        crate::fec::decode(blocks.iter().map(|b| Self::shard(b).clone()).collect())
+    }
+}
+
+/// encode, prove and build the blocks of some data, ready to be sent to the storage nodes
+pub fn disperse<S: ProvingScheme>(
+    scheme: &S,
+    bytes: &[u8],
+    params: &S::SetupParams,
+) -> Result<Vec<S::Block>, KomodoError> {
+    let shards = scheme.encode(bytes)?;
+    let proof = scheme.prove(bytes, &shards, params)?;
+    Ok(scheme.build(shards, proof))
+}
+
+/// the same as [`disperse`] with a precomputed hash of the data, e.g. computed with a streaming
+/// hasher while reading a large file, see [`ProvingScheme::encode_with_hash`]
+pub fn disperse_with_hash<S: ProvingScheme>(
+    scheme: &S,
+    bytes: &[u8],
+    hash: &[u8],
+    params: &S::SetupParams,
+) -> Result<Vec<S::Block>, KomodoError> {
+    let shards = scheme.encode_with_hash(bytes, hash)?;
+    let proof = scheme.prove(bytes, &shards, params)?;
+    Ok(scheme.build(shards, proof))
+}
+
+/// the same as [`disperse`] for a batch of files, with the same setup, see
+/// [`ProvingScheme::prove_batch`]
+///
+/// the blocks of each file are in the same order as the files.
+pub fn disperse_batch<S: ProvingScheme>(
+    scheme: &S,
+    files: &[&[u8]],
+    params: &S::SetupParams,
+) -> Result<Vec<Vec<S::Block>>, KomodoError> {
+    let shards = files
+        .iter()
+        .map(|bytes| scheme.encode(bytes))
+        .collect::<Result<Vec<_>, _>>()?;
+    let proofs = scheme.prove_batch(files, &shards, params)?;
+
+    Ok(shards
+        .into_iter()
+        .zip(proofs)
+        .map(|(shards, proof)| scheme.build(shards, proof))
+        .collect())
+}
+
+/// re-prove the blocks of some data under a new trusted setup, e.g. to retire a compromised one
+///
+/// the blocks are verified with the `old` setup and decoded, see [`collect`], then the data is
+/// dispersed again with the `new` one, see [`disperse`]. When the data is still at hand, it can
+/// simply be dispersed again with the new setup.
+///
+/// > **Note**
+/// >
+/// > the shards are the same, only their proofs change, i.e. the new blocks can replace the old
+/// > ones one for one.
+pub fn rotate_setup<S: ProvingScheme>(
+    scheme: &S,
+    old: &S::SetupParams,
+    new: &S::SetupParams,
+    blocks: Vec<S::Block>,
+) -> Result<Vec<S::Block>, KomodoError> {
+    let bytes = collect(scheme, blocks, old)?;
+    disperse(scheme, &bytes, new)
+}
+
+/// verify some blocks and decode the original data from the valid ones
+///
+/// > **Note**
+/// >
+/// > the invalid blocks are discarded, the decoding fails if less than $k$ are left.
+pub fn collect<S: ProvingScheme>(
+    scheme: &S,
+    blocks: Vec<S::Block>,
+    params: &S::SetupParams,
+) -> Result<Vec<u8>, KomodoError> {
+    let mut valid = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        if scheme.verify(&block, params)? {
+            valid.push(block);
+        }
+    }
+    scheme.decode(valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_std::test_rng;
+
+    use super::{
+        collect, disperse, disperse_batch, disperse_with_hash, rotate_setup, ProvingScheme,
+    };
+    use crate::{algebra::linalg::Matrix, error::KomodoError, fec, semi_avid::SemiAvid};
+
+    type S = SemiAvid<Fr, G1Projective, DensePolynomial<Fr>>;
+
+    #[test]
+    fn semi_avid() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        let scheme = S::new(Matrix::random(3, 6, &mut rng));
+        let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let mut blocks = disperse(&scheme, &bytes, &setup).unwrap();
+        assert_eq!(blocks.len(), 6);
+
+        let recoded = scheme.recode(&blocks[..3], &mut rng).unwrap().unwrap();
+        assert!(scheme.verify(&recoded, &setup).unwrap());
+
+        // NOTE: an invalid block is discarded, the two left and the recoded one are enough
+        blocks[4].shard.data[0] += Fr::from(1u64);
+        let kept = vec![
+            blocks[3].clone(),
+            blocks[4].clone(),
+            blocks[5].clone(),
+            recoded,
+        ];
+        assert_eq!(collect(&scheme, kept, &setup).unwrap(), bytes);
+
+        assert!(matches!(
+            collect(&scheme, blocks[3..5].to_vec(), &setup),
+            Err(KomodoError::TooFewShards(..))
+        ));
+    }
+
+    #[test]
+    fn batch() {
+        let mut rng = test_rng();
+        let files: [&[u8]; 3] = [
+            include_bytes!("../assets/dragoon_32x32.png"),
+            b"a small file",
+            &[42; 1000],
+        ];
+
+        let scheme = S::new(Matrix::random(3, 5, &mut rng));
+        let setup = scheme.setup(files[0].len(), &mut rng).unwrap();
+        let batch = disperse_batch(&scheme, &files, &setup).unwrap();
+        assert_eq!(batch.len(), files.len());
+
+        for (blocks, bytes) in batch.into_iter().zip(files) {
+            assert_eq!(blocks, disperse(&scheme, bytes, &setup).unwrap());
+            assert_eq!(collect(&scheme, blocks, &setup).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn precomputed_hash() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        let scheme = S::new(Matrix::random(3, 5, &mut rng));
+        let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+
+        let hash = fec::content_hash(&bytes);
+        assert_eq!(
+            disperse_with_hash(&scheme, &bytes, &hash, &setup).unwrap(),
+            disperse(&scheme, &bytes, &setup).unwrap()
+        );
+    }
+
+    #[test]
+    fn rotation() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        let scheme = S::new(Matrix::random(3, 5, &mut rng));
+        let old = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let new = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let blocks = disperse(&scheme, &bytes, &old).unwrap();
+
+        let rotated = rotate_setup(&scheme, &old, &new, blocks[2..].to_vec()).unwrap();
+        assert_eq!(rotated.len(), 5);
+        for (block, old_block) in rotated.iter().zip(blocks.iter()) {
+            assert_eq!(S::shard(block), S::shard(old_block));
+            assert!(scheme.verify(block, &new).unwrap());
+            assert!(!scheme.verify(block, &old).unwrap());
+        }
+        assert_eq!(collect(&scheme, rotated, &new).unwrap(), bytes);
+
+        assert!(rotate_setup(&scheme, &new, &old, blocks).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! control over the sources of randomness given to Komodo
+//!
+//! some operations of Komodo need randomness that must remain unpredictable for the protocols to
+//! be secure, e.g. the secret $\tau$ of a trusted setup, the coefficients of the random linear
+//! combinations of recoding or the random parts of the shards of a secret, see
+//! [`crate::threshold`]. Feeding them with a weak or a test generator, e.g.
+//! [`ark_std::test_rng`], compiles and runs just fine but silently breaks the security.
+//!
+//! there are thus two versions of each of these operations:
+//! - the ones bounded by [`CryptoRng`](ark_std::rand::CryptoRng), which are the only ones that a
+//!   security-sensitive application should use, e.g. [`crate::zk::setup_secure`],
+//!   [`crate::fec::recode_random_secure`], [`crate::semi_avid::recode_secure`],
+//!   [`crate::threshold::share_secure`], [`crate::ProvingScheme::setup_secure`], and the keys,
+//!   nonces and ephemeral keys of `sealed::keypair_secure`*, `sealed::seal_secure`*,
+//!   `sealed::disperse_secure`*, `sealed::disperse_threshold_secure`* and
+//!   `auth::keypair_secure`*
+//! - the ones that accept any [`RngCore`](ark_std::rand::RngCore), e.g. [`crate::zk::setup`],
+//!   which are kept for experiments and benchmarks and are subject to the [`RandomnessPolicy`] of
+//!   the process
+//!
+//! an application can forbid the latter once and for all with [`set_policy`], in which case they
+//! fail with [`crate::error::KomodoError::WeakRandomness`].
+//!
+//! > **Note**
+//! >
+//! > the random encoding matrices, e.g. [`crate::algebra::linalg::Matrix::random`], are public and
+//! > do not need to be unpredictable, hence they are not subject to the policy.
+//!
+//! # Example
+//! ```
+//! # use ark_bls12_381::{Fr as F, G1Projective as G};
+//! use komodo::{
+//!     error::KomodoError,
+//!     randomness::{self, RandomnessPolicy},
+//!     zk,
+//! };
+//! use rand::rngs::OsRng;
+//!
+//! # fn main() {
+//! randomness::set_policy(RandomnessPolicy::CryptoOnly);
+//! assert!(matches!(
+//!     zk::setup::<F, G>(10, &mut ark_std::test_rng()),
+//!     Err(KomodoError::WeakRandomness(..))
+//! ));
+//! assert!(zk::setup_secure::<F, G>(10, &mut OsRng).is_ok());
+//! # randomness::set_policy(RandomnessPolicy::Any);
+//! # }
+//! ```
+use core::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use ark_std::string::ToString;
+
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use crate::error::KomodoError;
+
+/// which sources of randomness are accepted by the operations that need unpredictable randomness
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RandomnessPolicy {
+    /// any [`RngCore`](ark_std::rand::RngCore) is accepted, which is the default
+    #[default]
+    Any,
+    /// only the operations bounded by [`CryptoRng`](ark_std::rand::CryptoRng) are allowed
+    CryptoOnly,
+}
+
+static POLICY: AtomicU8 = AtomicU8::new(0);
+
+/// set the [`RandomnessPolicy`] of the whole process
+pub fn set_policy(policy: RandomnessPolicy) {
+    let policy = match policy {
+        RandomnessPolicy::Any => 0,
+        RandomnessPolicy::CryptoOnly => 1,
+    };
+    POLICY.store(policy, Ordering::SeqCst);
+}
+
+/// the current [`RandomnessPolicy`] of the process, see [`set_policy`]
+pub fn policy() -> RandomnessPolicy {
+    match POLICY.load(Ordering::SeqCst) {
+        0 => RandomnessPolicy::Any,
+        _ => RandomnessPolicy::CryptoOnly,
+    }
+}
+
+/// make sure that an operation can be given any source of randomness under the current policy
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub(crate) fn check(operation: &str) -> Result<(), KomodoError> {
+    // NOTE: the unit tests run in parallel, they wait for the ones that change the policy, see
+    // `tests::PolicyGuard`
+    #[cfg(all(test, feature = "std"))]
+    let _owner = tests::wait_for_policy();
+
+    match policy() {
+        RandomnessPolicy::Any => Ok(()),
+        RandomnessPolicy::CryptoOnly => Err(KomodoError::WeakRandomness(operation.to_string())),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+mod tests {
+    use std::{
+        sync::{Condvar, Mutex, MutexGuard},
+        thread::{self, ThreadId},
+    };
+
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_std::test_rng;
+    use rand::rngs::OsRng;
+
+    use super::{policy, set_policy, RandomnessPolicy};
+    use crate::{
+        algebra::linalg::Matrix, error::KomodoError, fec, pipeline::ProvingScheme, semi_avid,
+        semi_avid::SemiAvid, threshold, zk,
+    };
+
+    type S = SemiAvid<Fr, G1Projective, DensePolynomial<Fr>>;
+
+    /// the test that is currently changing the policy, if any
+    static OWNER: Mutex<Option<ThreadId>> = Mutex::new(None);
+    static RELEASED: Condvar = Condvar::new();
+
+    /// wait for the test that is changing the policy, if any and if it is not the current one
+    ///
+    /// the policy cannot change until the returned lock is dropped.
+    pub(super) fn wait_for_policy() -> MutexGuard<'static, Option<ThreadId>> {
+        let me = thread::current().id();
+        RELEASED
+            .wait_while(OWNER.lock().unwrap_or_else(|e| e.into_inner()), |owner| {
+                owner.is_some_and(|owner| owner != me)
+            })
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// a test that changes the policy of the whole process, the other tests wait for it to be
+    /// dropped, which resets the policy to [`RandomnessPolicy::Any`]
+    struct PolicyGuard;
+
+    impl PolicyGuard {
+        fn set(policy: RandomnessPolicy) -> Self {
+            let mut owner = wait_for_policy();
+            *owner = Some(thread::current().id());
+            set_policy(policy);
+            Self
+        }
+    }
+
+    impl Drop for PolicyGuard {
+        fn drop(&mut self) {
+            let mut owner = OWNER.lock().unwrap_or_else(|e| e.into_inner());
+            set_policy(RandomnessPolicy::Any);
+            *owner = None;
+            RELEASED.notify_all();
+        }
+    }
+
+    fn is_weak<T: core::fmt::Debug>(result: Result<T, KomodoError>) -> bool {
+        matches!(result, Err(KomodoError::WeakRandomness(..)))
+    }
+
+    #[test]
+    fn crypto_only() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+        let scheme = S::new(encoding_mat.clone());
+        let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let shards = fec::encode::<Fr>(&bytes, &encoding_mat).unwrap();
+        let blocks = crate::pipeline::disperse(&scheme, &bytes, &setup).unwrap();
+
+        let _guard = PolicyGuard::set(RandomnessPolicy::CryptoOnly);
+        assert_eq!(policy(), RandomnessPolicy::CryptoOnly);
+
+        assert!(is_weak(zk::setup::<Fr, G1Projective>(10, &mut rng)));
+        assert!(is_weak(scheme.setup(bytes.len(), &mut rng)));
+        assert!(is_weak(fec::recode_random(&shards, &mut rng)));
+        assert!(is_weak(fec::recode_random_with_coeffs(&shards, &mut rng)));
+        assert!(is_weak(semi_avid::recode(&blocks, &mut rng)));
+        assert!(is_weak(threshold::share(
+            b"secret",
+            &encoding_mat,
+            &mut rng
+        )));
+
+        assert!(zk::setup_secure::<Fr, G1Projective>(10, &mut OsRng).is_ok());
+        assert!(scheme.setup_secure(bytes.len(), &mut OsRng).is_ok());
+        assert!(fec::recode_random_secure(&shards, &mut OsRng).is_ok());
+        assert!(semi_avid::recode_secure(&blocks, &mut OsRng).is_ok());
+        assert!(threshold::share_secure(b"secret", &encoding_mat, &mut OsRng).is_ok());
+
+        set_policy(RandomnessPolicy::Any);
+        assert!(zk::setup::<Fr, G1Projective>(10, &mut rng).is_ok());
+        assert!(fec::recode_random(&shards, &mut rng).is_ok());
+    }
+
+    #[cfg(feature = "sealed")]
+    #[test]
+    fn crypto_only_sealed() {
+        use crate::sealed::{self, SealingKey};
+
+        let is_weak = |e: anyhow::Error| {
+            matches!(
+                e.downcast_ref::<KomodoError>(),
+                Some(KomodoError::WeakRandomness(..))
+            )
+        };
+
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+        let encoding_mat = Matrix::random(3, 5, &mut rng);
+        let scheme = S::new(encoding_mat.clone());
+        let setup = scheme
+            .setup(bytes.len() + sealed::OVERHEAD, &mut rng)
+            .unwrap();
+        let (_, public_key) = sealed::keypair(&mut rng).unwrap();
+        let key = SealingKey::Recipient(public_key);
+
+        let _guard = PolicyGuard::set(RandomnessPolicy::CryptoOnly);
+
+        assert!(is_weak(sealed::keypair(&mut rng).unwrap_err()));
+        assert!(is_weak(sealed::seal(&bytes, &key, &mut rng).unwrap_err()));
+        assert!(is_weak(
+            sealed::disperse(&scheme, &bytes, &setup, &key, &mut rng).unwrap_err()
+        ));
+        assert!(is_weak(
+            sealed::disperse_threshold(&scheme, &bytes, &setup, &encoding_mat, &mut rng)
+                .unwrap_err()
+        ));
+
+        sealed::keypair_secure(&mut OsRng);
+        assert!(sealed::seal_secure(&bytes, &key, &mut OsRng).is_ok());
+        assert!(sealed::disperse_secure(&scheme, &bytes, &setup, &key, &mut OsRng).is_ok());
+        assert!(sealed::disperse_threshold_secure(
+            &scheme,
+            &bytes,
+            &setup,
+            &encoding_mat,
+            &mut OsRng
+        )
+        .is_ok());
+    }
+
+    #[cfg(feature = "auth")]
+    #[test]
+    fn crypto_only_auth() {
+        let _guard = PolicyGuard::set(RandomnessPolicy::CryptoOnly);
+
+        let error = crate::auth::keypair(&mut test_rng()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<KomodoError>(),
+            Some(KomodoError::WeakRandomness(..))
+        ));
+        let (secret_key, public_key) = crate::auth::keypair_secure(&mut OsRng);
+        assert_ne!(secret_key, public_key);
+    }
+}
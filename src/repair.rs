@@ -0,0 +1,418 @@
+//! repair the blocks of a file across nodes
+//!
+//! when some blocks of a file are lost, a node in charge of the file has to
+//! 1. find out which blocks it is missing, e.g. with [`Manifest::verify`] or
+//!    [`crate::Komodo::audit`]
+//! 2. ask the reachable peers which blocks they have, i.e. their _inventories_
+//! 3. [`plan`] the downloads: a missing block that a peer still has is simply copied from it, and
+//!    the missing blocks that no peer has anymore are regenerated, which requires $k$ blocks of
+//!    the file to decode it
+//! 4. download the blocks of the [`RepairPlan`], e.g. with `network::Node::fetch`
+//! 5. [`regenerate`] the blocks that no peer has, by decoding the file, encoding it again and
+//!    proving the new shards, and store them
+//!
+//! the plan is _minimal_, i.e. it downloads as few blocks as possible, and spreads the downloads
+//! across the peers that have the blocks.
+//!
+//! > **Note**
+//! >
+//! > any $k$ blocks are assumed to be enough to decode the file, which is the case with the
+//! > Vandermonde encoding matrices of KZG and aPlonK and, with high probability, with random ones.
+//! >
+//! > a block can only be regenerated exactly, i.e. with the same hash, if it has been computed
+//! > directly from the encoding matrix of the [`ProvingScheme`], and not recoded.
+//!
+//! # Example
+//! ```
+//! # use std::collections::BTreeMap;
+//! # use ark_bls12_381::{Fr as F, G1Projective as G};
+//! # use ark_poly::univariate::DensePolynomial as DP;
+//! # use ark_serialize::{Compress, Validate};
+//! use komodo::{
+//!     algebra::linalg::Matrix,
+//!     fs::{store::{BlockStore, MemoryStore}, Header, ManifestStatus, Naming},
+//!     repair,
+//!     semi_avid::{Block, SemiAvid},
+//!     Komodo, ProvingScheme,
+//! };
+//!
+//! # fn main() {
+//! let mut rng = ark_std::test_rng();
+//! let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+//!
+//! let scheme = SemiAvid::<F, G, DP<F>>::new(Matrix::random(3, 6, &mut rng));
+//! let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+//! let mut komodo =
+//!     Komodo::new(scheme, setup, "BLS12-381", MemoryStore::new(), MemoryStore::new()).unwrap();
+//! let id = komodo.store(&bytes).unwrap();
+//! let manifest = komodo.manifest(&id).unwrap();
+//! let hashes = manifest.block_hashes.clone();
+//!
+//! // NOTE: all the blocks are lost locally and a peer only has the first three
+//! let peer = komodo.blocks().clone();
+//! for hash in &hashes {
+//!     komodo.blocks_mut().delete(hash).unwrap();
+//! }
+//! let status = ManifestStatus { missing: hashes.clone(), nb_needed: 3, ..Default::default() };
+//! let inventories = BTreeMap::from([("peer", hashes[..3].to_vec())]);
+//!
+//! let plan = repair::plan(&manifest, &status, &inventories).unwrap();
+//! assert_eq!(plan.nb_downloads(), 3);
+//! assert_eq!(plan.to_regenerate, hashes[3..].to_vec());
+//!
+//! let downloaded = plan
+//!     .by_peer()
+//!     .values()
+//!     .flatten()
+//!     .map(|hash| peer.read_block::<Block<F, G>>(hash, Compress::Yes, Validate::Yes).unwrap())
+//!     .collect();
+//! let regenerated = repair::regenerate(
+//!     komodo.scheme(),
+//!     komodo.setup(),
+//!     &manifest,
+//!     downloaded,
+//!     &plan.to_regenerate,
+//!     Naming::default(),
+//!     Compress::Yes,
+//! )
+//! .unwrap();
+//!
+//! let header = Header::new("komodo", "BLS12-381", &manifest.setup_digest)
+//!     .for_blocks(manifest.k as u32, &manifest.file_hash);
+//! let stored = komodo.blocks_mut().dump_blocks(&regenerated, &header, Compress::Yes).unwrap();
+//! assert_eq!(stored, hashes[3..].to_vec());
+//! # }
+//! ```
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use ark_serialize::{CanonicalSerialize, Compress};
+use tracing::info;
+
+use crate::{
+    fs::{Manifest, ManifestStatus, Naming},
+    pipeline::{self, ProvingScheme},
+};
+
+/// what to download, and from whom, to repair a file, see [`plan`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepairPlan<P> {
+    /// the missing blocks that a peer still has, to be copied as is, with the peer to get them
+    /// from
+    pub copies: Vec<(String, P)>,
+    /// the blocks to download, on top of the copies, to be able to decode the file, with the peer
+    /// to get them from
+    pub downloads: Vec<(String, P)>,
+    /// the missing blocks that no peer has anymore, to be regenerated with [`regenerate`]
+    pub to_regenerate: Vec<String>,
+}
+
+impl<P: Clone + Ord> RepairPlan<P> {
+    /// the total number of blocks to download
+    pub fn nb_downloads(&self) -> usize {
+        self.copies.len() + self.downloads.len()
+    }
+
+    /// the blocks to download from each peer, copies included
+    pub fn by_peer(&self) -> BTreeMap<P, Vec<String>> {
+        let mut by_peer: BTreeMap<P, Vec<String>> = BTreeMap::new();
+        for (hash, peer) in self.copies.iter().chain(self.downloads.iter()) {
+            by_peer.entry(peer.clone()).or_default().push(hash.clone());
+        }
+        by_peer
+    }
+
+    /// whether there is nothing to repair
+    pub fn is_empty(&self) -> bool {
+        self.copies.is_empty() && self.to_regenerate.is_empty()
+    }
+}
+
+/// compute a minimal download plan to repair the blocks of a file that are missing or corrupted
+/// locally
+///
+/// `status` is the local state of the blocks of the file, e.g. given by [`Manifest::verify`], and
+/// `inventories` gives the blocks that each reachable peer has. The blocks are downloaded from the
+/// peer that has the fewest downloads so far among the ones that have them, ties being broken by
+/// the order of the peers.
+///
+/// it is an error if some blocks have to be regenerated and fewer than $k$ blocks of the file are
+/// available, locally and from the peers.
+pub fn plan<P: Clone + Ord>(
+    manifest: &Manifest,
+    status: &ManifestStatus,
+    inventories: &BTreeMap<P, Vec<String>>,
+) -> Result<RepairPlan<P>> {
+    let mut load: BTreeMap<&P, usize> = inventories.keys().map(|p| (p, 0)).collect();
+    let mut pick = |hash: &String| -> Option<P> {
+        let peer = inventories
+            .iter()
+            .filter(|(_, blocks)| blocks.contains(hash))
+            .min_by_key(|(peer, _)| load[peer])
+            .map(|(peer, _)| peer)?;
+        *load.get_mut(peer).unwrap() += 1;
+        Some(peer.clone())
+    };
+
+    let mut plan = RepairPlan {
+        copies: vec![],
+        downloads: vec![],
+        to_regenerate: vec![],
+    };
+    for hash in status.missing.iter().chain(status.corrupted.iter()) {
+        match pick(hash) {
+            Some(peer) => plan.copies.push((hash.clone(), peer)),
+            None => plan.to_regenerate.push(hash.clone()),
+        }
+    }
+    if plan.to_regenerate.is_empty() {
+        return Ok(plan);
+    }
+
+    let mut nb_available = status.present.len() + plan.copies.len();
+    for hash in &manifest.block_hashes {
+        if nb_available >= manifest.k {
+            break;
+        }
+        if status.present.contains(hash) || plan.copies.iter().any(|(h, _)| h == hash) {
+            continue;
+        }
+        if let Some(peer) = pick(hash) {
+            plan.downloads.push((hash.clone(), peer));
+            nb_available += 1;
+        }
+    }
+    if nb_available < manifest.k {
+        return Err(anyhow!(
+            "cannot regenerate {} blocks, only {} blocks out of the {} required are available",
+            plan.to_regenerate.len(),
+            nb_available,
+            manifest.k
+        ));
+    }
+
+    info!(
+        "repair plan: {} copies, {} downloads, {} blocks to regenerate",
+        plan.copies.len(),
+        plan.downloads.len(),
+        plan.to_regenerate.len()
+    );
+    Ok(plan)
+}
+
+/// regenerate some blocks of a file from at least $k$ of its blocks
+///
+/// the file is decoded from `blocks`, the invalid ones being discarded, see
+/// [`pipeline::collect`], then encoded and proven again, see [`pipeline::disperse`], and the blocks
+/// at the positions of the `missing` ones in [`Manifest::block_hashes`] are returned, in the same
+/// order as `missing`. They can then be stored with
+/// [`BlockStore::dump_blocks`](crate::fs::store::BlockStore::dump_blocks), `naming` and `compress`
+/// being the ones of the store.
+///
+/// it is an error if a regenerated block does not have the hash of the block it replaces, e.g.
+/// because the original block had been recoded.
+pub fn regenerate<S>(
+    scheme: &S,
+    setup: &S::SetupParams,
+    manifest: &Manifest,
+    blocks: Vec<S::Block>,
+    missing: &[String],
+    naming: Naming,
+    compress: Compress,
+) -> Result<Vec<S::Block>>
+where
+    S: ProvingScheme,
+    S::Block: CanonicalSerialize + Clone,
+{
+    let bytes = pipeline::collect(scheme, blocks, setup)?;
+    let all = pipeline::disperse(scheme, &bytes, setup)?;
+    if all.len() != manifest.block_hashes.len() {
+        return Err(anyhow!(
+            "the proving scheme gives {} blocks, the manifest has {}",
+            all.len(),
+            manifest.block_hashes.len()
+        ));
+    }
+
+    missing
+        .iter()
+        .map(|hash| {
+            let i = manifest
+                .block_hashes
+                .iter()
+                .position(|h| h == hash)
+                .ok_or_else(|| anyhow!("block {} is not a block of the file", hash))?;
+
+            let mut serialized = vec![];
+            all[i].serialize_with_mode(&mut serialized, compress)?;
+            let name = naming.name(&serialized);
+            if &name != hash {
+                return Err(anyhow!(
+                    "block {} has been regenerated as {}, it might have been recoded",
+                    hash,
+                    name
+                ));
+            }
+            Ok(all[i].clone())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_serialize::{Compress, Validate};
+    use ark_std::test_rng;
+
+    use super::{plan, regenerate};
+    use crate::{
+        algebra::linalg::Matrix,
+        fs::{
+            store::{BlockStore, MemoryStore},
+            Header, Manifest, ManifestStatus, Naming,
+        },
+        pipeline::{self, ProvingScheme},
+        semi_avid::{Block, SemiAvid},
+    };
+
+    type S = SemiAvid<Fr, G1Projective, DensePolynomial<Fr>>;
+
+    fn manifest(k: usize, block_hashes: &[&str]) -> Manifest {
+        Manifest {
+            file_hash: vec![],
+            size: 0,
+            k,
+            n: block_hashes.len(),
+            curve: String::new(),
+            setup_digest: vec![],
+            block_hashes: block_hashes.iter().map(|h| h.to_string()).collect(),
+            sealing: None,
+        }
+    }
+
+    fn strings(hashes: &[&str]) -> Vec<String> {
+        hashes.iter().map(|h| h.to_string()).collect()
+    }
+
+    #[test]
+    fn planning() {
+        let manifest = manifest(3, &["a", "b", "c", "d", "e", "f"]);
+        let status = ManifestStatus {
+            present: strings(&["a"]),
+            missing: strings(&["b", "c"]),
+            corrupted: strings(&["d"]),
+            nb_needed: 2,
+        };
+
+        // NOTE: all the missing blocks can be copied, nothing has to be decoded
+        let inventories = BTreeMap::from([
+            (1, strings(&["b", "c", "d", "e"])),
+            (2, strings(&["b", "c", "d", "f"])),
+        ]);
+        let repair = plan(&manifest, &status, &inventories).unwrap();
+        assert_eq!(
+            repair.copies,
+            vec![
+                ("b".to_string(), 1),
+                ("c".to_string(), 2),
+                ("d".to_string(), 1)
+            ]
+        );
+        assert!(repair.downloads.is_empty() && repair.to_regenerate.is_empty());
+        assert_eq!(repair.by_peer()[&1], strings(&["b", "d"]));
+
+        // NOTE: `b` is lost everywhere, `c` and `d` are copied, which makes $k$ blocks with `a`
+        let inventories = BTreeMap::from([(1, strings(&["c", "e"])), (2, strings(&["d", "f"]))]);
+        let repair = plan(&manifest, &status, &inventories).unwrap();
+        assert_eq!(repair.to_regenerate, strings(&["b"]));
+        assert_eq!(repair.nb_downloads(), 2);
+
+        // NOTE: one more block has to be downloaded to decode
+        let inventories = BTreeMap::from([(1, strings(&["c", "e"])), (2, strings(&["f"]))]);
+        let repair = plan(&manifest, &status, &inventories).unwrap();
+        assert_eq!(repair.to_regenerate, strings(&["b", "d"]));
+        assert_eq!(repair.copies, vec![("c".to_string(), 1)]);
+        assert_eq!(repair.downloads, vec![("e".to_string(), 1)]);
+
+        assert!(plan(&manifest, &status, &BTreeMap::from([(1, strings(&["e"]))])).is_err());
+        assert!(plan::<u8>(
+            &manifest,
+            &ManifestStatus {
+                present: manifest.block_hashes.clone(),
+                ..Default::default()
+            },
+            &BTreeMap::new()
+        )
+        .unwrap()
+        .is_empty());
+    }
+
+    #[test]
+    fn regeneration() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        let scheme = S::new(Matrix::random(3, 5, &mut rng));
+        let setup = scheme.setup(bytes.len(), &mut rng).unwrap();
+        let blocks = pipeline::disperse(&scheme, &bytes, &setup).unwrap();
+
+        let header = Header::new("semi-avid", "BLS12-381", &[]);
+        let mut store = MemoryStore::new();
+        let hashes = store.dump_blocks(&blocks, &header, Compress::Yes).unwrap();
+        let manifest = Manifest {
+            block_hashes: hashes.clone(),
+            ..manifest(3, &[])
+        };
+
+        let remaining = store
+            .read_blocks::<Block<Fr, G1Projective>>(&hashes[2..], Compress::Yes, Validate::Yes)
+            .unwrap()
+            .into_iter()
+            .map(|(_, block)| block)
+            .collect::<Vec<_>>();
+        let regenerated = regenerate(
+            &scheme,
+            &setup,
+            &manifest,
+            remaining.clone(),
+            &hashes[..2],
+            Naming::default(),
+            Compress::Yes,
+        )
+        .unwrap();
+        assert_eq!(regenerated, blocks[..2].to_vec());
+
+        // NOTE: a recoded block cannot be regenerated
+        let recoded = scheme.recode(&blocks, &mut rng).unwrap().unwrap();
+        let recoded_hash = store.dump_block(&recoded, &header, Compress::Yes).unwrap();
+        let mut block_hashes = hashes.clone();
+        block_hashes[0] = recoded_hash.clone();
+        assert!(regenerate(
+            &scheme,
+            &setup,
+            &Manifest {
+                block_hashes,
+                ..manifest.clone()
+            },
+            remaining.clone(),
+            &[recoded_hash],
+            Naming::default(),
+            Compress::Yes,
+        )
+        .is_err());
+
+        assert!(regenerate(
+            &scheme,
+            &setup,
+            &manifest,
+            remaining,
+            &["not a block".to_string()],
+            Naming::default(),
+            Compress::Yes,
+        )
+        .is_err());
+    }
+}
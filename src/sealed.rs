@@ -0,0 +1,674 @@
+//! end-to-end encryption of the data before it is encoded
+//!
+//! a _sealed_ file is encrypted with XChaCha20-Poly1305 before being encoded and proven, i.e. the
+//! shards and the blocks only ever contain encrypted data and the storage nodes never see the
+//! file in clear. The blocks are still proven and verified as usual, [`disperse`] and [`collect`]
+//! simply wrap the ones of [`crate::pipeline`].
+//!
+//! the key of the encryption is either
+//! - shared beforehand by the parties, see [`SealingKey::Shared`]
+//! - derived for a single recipient from its X25519 public key with
+//!   [HPKE](https://www.rfc-editor.org/rfc/rfc9180), see [`SealingKey::Recipient`], in which case
+//!   only the recipient, with its secret key, can open the file
+//!
+//! everything else that is needed to open the file, i.e. the nonce and the encapsulated key of
+//! HPKE, is returned as a [`Sealing`], to be stored in the [`crate::fs::Manifest`] of the file.
+//!
+//! with [`disperse_threshold`], the key is not given to anyone but split into shards with
+//! [`crate::threshold`], one for each block, and any $k$ of them are required to open the file
+//! with [`collect_threshold`], i.e. fewer than $k$ storage nodes cannot open it together.
+//!
+//! > **Note**
+//! >
+//! > the keys and nonces are drawn from the given source of randomness, i.e. [`keypair`],
+//! > [`seal`], [`disperse`] and [`disperse_threshold`] are subject to the
+//! > [`crate::randomness::RandomnessPolicy`] and all have a `_secure` version, e.g.
+//! > [`keypair_secure`].
+//!
+//! # Example
+//! ```
+//! # use ark_bls12_381::{Fr as F, G1Projective as G};
+//! # use ark_poly::univariate::DensePolynomial as DP;
+//! use komodo::{
+//!     algebra::linalg::Matrix,
+//!     pipeline::ProvingScheme,
+//!     sealed::{self, OpeningKey, SealingKey},
+//!     semi_avid::SemiAvid,
+//! };
+//!
+//! # fn main() {
+//! let mut rng = ark_std::test_rng();
+//! let bytes = include_bytes!("../assets/dragoon_133x133.png").to_vec();
+//!
+//! let (secret_key, public_key) = sealed::keypair(&mut rng).unwrap();
+//!
+//! let scheme = SemiAvid::<F, G, DP<F>>::new(Matrix::random(3, 6, &mut rng));
+//! let setup = scheme.setup(bytes.len() + sealed::OVERHEAD, &mut rng).unwrap();
+//!
+//! let (blocks, sealing) = sealed::disperse(
+//!     &scheme,
+//!     &bytes,
+//!     &setup,
+//!     &SealingKey::Recipient(public_key),
+//!     &mut rng,
+//! )
+//! .unwrap();
+//! let opened = sealed::collect(
+//!     &scheme,
+//!     blocks[3..].to_vec(),
+//!     &setup,
+//!     &sealing,
+//!     &OpeningKey::Recipient(secret_key),
+//! )
+//! .unwrap();
+//! assert_eq!(opened, bytes);
+//! # }
+//! ```
+use anyhow::{anyhow, Result};
+use ark_std::rand::{CryptoRng, RngCore};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+
+use crate::{
+    algebra::linalg::Matrix,
+    fec::Shard,
+    fs::{Key, Sealing},
+    pipeline::{self, ProvingScheme},
+    threshold,
+};
+
+/// the number of bytes added to the data by the encryption, i.e. the authentication tag
+pub const OVERHEAD: usize = 16;
+
+const NONCE_LEN: usize = 24;
+
+/// the key a file is sealed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SealingKey {
+    /// a secret key shared beforehand with whoever should open the file
+    Shared(Key),
+    /// the X25519 public key of the recipient of the file, see [`keypair`]
+    Recipient([u8; 32]),
+}
+
+/// the key a sealed file is opened with
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OpeningKey {
+    /// the secret key the file has been sealed with
+    Shared(Key),
+    /// the X25519 secret key of the recipient of the file, see [`keypair`]
+    Recipient([u8; 32]),
+}
+
+impl std::fmt::Debug for OpeningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpeningKey::Shared(key) => write!(f, "Shared({:?})", key),
+            OpeningKey::Recipient(_) => write!(f, "Recipient(<redacted>)"),
+        }
+    }
+}
+
+/// generate an X25519 key pair for a recipient, as `(secret key, public key)`
+///
+/// > **Note**
+/// >
+/// > `rng` is subject to the [`crate::randomness::RandomnessPolicy`], see [`keypair_secure`].
+pub fn keypair<R: RngCore>(rng: &mut R) -> Result<([u8; 32], [u8; 32])> {
+    crate::randomness::check("sealed::keypair")?;
+    Ok(keypair_unchecked(rng))
+}
+
+/// the same as [`keypair`], with a cryptographically secure source of randomness
+pub fn keypair_secure<R: RngCore + CryptoRng>(rng: &mut R) -> ([u8; 32], [u8; 32]) {
+    keypair_unchecked(rng)
+}
+
+fn keypair_unchecked<R: RngCore>(rng: &mut R) -> ([u8; 32], [u8; 32]) {
+    let mut secret_key = [0; 32];
+    rng.fill_bytes(&mut secret_key);
+    (secret_key, hpke::public_key(&secret_key))
+}
+
+/// encrypt some data and return it together with what is needed to open it, apart from the key
+///
+/// > **Note**
+/// >
+/// > `rng` is subject to the [`crate::randomness::RandomnessPolicy`], see [`seal_secure`].
+pub fn seal<R: RngCore>(bytes: &[u8], key: &SealingKey, rng: &mut R) -> Result<(Vec<u8>, Sealing)> {
+    crate::randomness::check("sealed::seal")?;
+    seal_unchecked(bytes, key, rng)
+}
+
+/// the same as [`seal`], with a cryptographically secure source of randomness
+pub fn seal_secure<R: RngCore + CryptoRng>(
+    bytes: &[u8],
+    key: &SealingKey,
+    rng: &mut R,
+) -> Result<(Vec<u8>, Sealing)> {
+    seal_unchecked(bytes, key, rng)
+}
+
+fn seal_unchecked<R: RngCore>(
+    bytes: &[u8],
+    key: &SealingKey,
+    rng: &mut R,
+) -> Result<(Vec<u8>, Sealing)> {
+    let (key, encapsulated_key) = match key {
+        SealingKey::Shared(key) => (*key, vec![]),
+        SealingKey::Recipient(public_key) => {
+            let mut ephemeral_key = [0; 32];
+            rng.fill_bytes(&mut ephemeral_key);
+            let (shared_secret, encapsulated_key) = hpke::encap(public_key, ephemeral_key)?;
+            (hpke::derive_key(&shared_secret), encapsulated_key.to_vec())
+        }
+    };
+
+    let mut nonce = [0; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    let sealed = XChaCha20Poly1305::new(&key.0.into())
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: bytes,
+                aad: &encapsulated_key,
+            },
+        )
+        .map_err(|_| anyhow!("could not seal data"))?;
+
+    Ok((
+        sealed,
+        Sealing {
+            nonce: nonce.to_vec(),
+            encapsulated_key,
+        },
+    ))
+}
+
+/// decrypt some data that has been sealed with [`seal`]
+///
+/// it is an error if the key is wrong or if either the data or the [`Sealing`] have been
+/// tampered with.
+pub fn open(sealed: &[u8], sealing: &Sealing, key: &OpeningKey) -> Result<Vec<u8>> {
+    let key = match (key, sealing.encapsulated_key.is_empty()) {
+        (OpeningKey::Shared(key), true) => *key,
+        (OpeningKey::Recipient(secret_key), false) => {
+            let encapsulated_key: [u8; 32] = sealing
+                .encapsulated_key
+                .as_slice()
+                .try_into()
+                .map_err(|_| anyhow!("invalid encapsulated key"))?;
+            hpke::derive_key(&hpke::decap(&encapsulated_key, secret_key)?)
+        }
+        (OpeningKey::Shared(_), false) => {
+            return Err(anyhow!(
+                "data is sealed for a recipient, not with a shared key"
+            ))
+        }
+        (OpeningKey::Recipient(_), true) => {
+            return Err(anyhow!(
+                "data is sealed with a shared key, not for a recipient"
+            ))
+        }
+    };
+    if sealing.nonce.len() != NONCE_LEN {
+        return Err(anyhow!("invalid nonce of {} bytes", sealing.nonce.len()));
+    }
+
+    XChaCha20Poly1305::new(&key.0.into())
+        .decrypt(
+            XNonce::from_slice(&sealing.nonce),
+            Payload {
+                msg: sealed,
+                aad: &sealing.encapsulated_key,
+            },
+        )
+        .map_err(|_| anyhow!("could not open sealed data, wrong key or tampered data"))
+}
+
+/// seal some data and disperse it, see [`pipeline::disperse`]
+///
+/// > **Note**
+/// >
+/// > the encrypted data is [`OVERHEAD`] bytes larger than `bytes`, which has to be taken into
+/// > account when building the setup of `scheme`.
+/// >
+/// > `rng` is subject to the [`crate::randomness::RandomnessPolicy`], see [`disperse_secure`].
+pub fn disperse<S: ProvingScheme, R: RngCore>(
+    scheme: &S,
+    bytes: &[u8],
+    params: &S::SetupParams,
+    key: &SealingKey,
+    rng: &mut R,
+) -> Result<(Vec<S::Block>, Sealing)> {
+    let (sealed, sealing) = seal(bytes, key, rng)?;
+    Ok((pipeline::disperse(scheme, &sealed, params)?, sealing))
+}
+
+/// the same as [`disperse`], with a cryptographically secure source of randomness
+pub fn disperse_secure<S: ProvingScheme, R: RngCore + CryptoRng>(
+    scheme: &S,
+    bytes: &[u8],
+    params: &S::SetupParams,
+    key: &SealingKey,
+    rng: &mut R,
+) -> Result<(Vec<S::Block>, Sealing)> {
+    let (sealed, sealing) = seal_secure(bytes, key, rng)?;
+    Ok((pipeline::disperse(scheme, &sealed, params)?, sealing))
+}
+
+/// collect some blocks and open the data they encode, see [`pipeline::collect`]
+pub fn collect<S: ProvingScheme>(
+    scheme: &S,
+    blocks: Vec<S::Block>,
+    params: &S::SetupParams,
+    sealing: &Sealing,
+    key: &OpeningKey,
+) -> Result<Vec<u8>> {
+    open(&pipeline::collect(scheme, blocks, params)?, sealing, key)
+}
+
+/// the blocks of some data, the shards of its key and its sealing, see [`disperse_threshold`]
+pub type ThresholdDispersal<S> = (
+    Vec<<S as ProvingScheme>::Block>,
+    Vec<Shard<<S as ProvingScheme>::Field>>,
+    Sealing,
+);
+
+/// seal some data with a fresh key, disperse it and split the key into $n$ shards, see
+/// [`crate::threshold::share`]
+///
+/// the shard $i$ of the key is meant to be stored together with the block $i$, so that any $k$
+/// storage nodes can open the file, see [`collect_threshold`], but no fewer.
+///
+/// > **Note**
+/// >
+/// > `encoding_mat` splits the key and does not have to be the one of `scheme`, however it should
+/// > have the same shape so that the threshold on the key is the one on the blocks.
+/// >
+/// > `rng` is subject to the [`crate::randomness::RandomnessPolicy`], see
+/// > [`disperse_threshold_secure`].
+pub fn disperse_threshold<S: ProvingScheme, R: RngCore>(
+    scheme: &S,
+    bytes: &[u8],
+    params: &S::SetupParams,
+    encoding_mat: &Matrix<S::Field>,
+    rng: &mut R,
+) -> Result<ThresholdDispersal<S>> {
+    crate::randomness::check("sealed::disperse_threshold")?;
+
+    let mut key = [0; 32];
+    rng.fill_bytes(&mut key);
+
+    let (blocks, sealing) = disperse(scheme, bytes, params, &SealingKey::Shared(Key(key)), rng)?;
+    let key_shards = threshold::share(&key, encoding_mat, rng)?;
+
+    Ok((blocks, key_shards, sealing))
+}
+
+/// the same as [`disperse_threshold`], with a cryptographically secure source of randomness
+pub fn disperse_threshold_secure<S: ProvingScheme, R: RngCore + CryptoRng>(
+    scheme: &S,
+    bytes: &[u8],
+    params: &S::SetupParams,
+    encoding_mat: &Matrix<S::Field>,
+    rng: &mut R,
+) -> Result<ThresholdDispersal<S>> {
+    let mut key = [0; 32];
+    rng.fill_bytes(&mut key);
+
+    let sealing_key = SealingKey::Shared(Key(key));
+    let (blocks, sealing) = disperse_secure(scheme, bytes, params, &sealing_key, rng)?;
+    let key_shards = threshold::share_secure(&key, encoding_mat, rng)?;
+
+    Ok((blocks, key_shards, sealing))
+}
+
+/// collect some blocks and open the data they encode with the key reconstructed from at least
+/// $k$ of its shards, see [`disperse_threshold`]
+pub fn collect_threshold<S: ProvingScheme>(
+    scheme: &S,
+    blocks: Vec<S::Block>,
+    params: &S::SetupParams,
+    sealing: &Sealing,
+    key_shards: Vec<Shard<S::Field>>,
+) -> Result<Vec<u8>> {
+    let key: [u8; 32] = threshold::reconstruct(key_shards)?
+        .try_into()
+        .map_err(|k: Vec<u8>| anyhow!("invalid key of {} bytes", k.len()))?;
+    collect(
+        scheme,
+        blocks,
+        params,
+        sealing,
+        &OpeningKey::Shared(Key(key)),
+    )
+}
+
+/// the parts of [HPKE](https://www.rfc-editor.org/rfc/rfc9180) needed to derive a key for a
+/// recipient, i.e. the base mode with DHKEM(X25519, HKDF-SHA256), HKDF-SHA256 and the
+/// export-only AEAD
+mod hpke {
+    use anyhow::{anyhow, Result};
+    use hkdf::Hkdf;
+    use sha2::Sha256;
+    use x25519_dalek::{PublicKey, StaticSecret};
+
+    use crate::fs::Key;
+
+    const KEM_ID: u16 = 0x0020;
+    const KDF_ID: u16 = 0x0001;
+    const EXPORT_ONLY: u16 = 0xffff;
+    const MODE_BASE: u8 = 0x00;
+
+    /// the _info_ of the key schedule, binding the keys to Komodo
+    const INFO: &[u8] = b"komodo/sealed/1";
+    /// the context of the export of the key
+    const EXPORTER_CONTEXT: &[u8] = b"XChaCha20-Poly1305 key";
+
+    fn kem_suite_id() -> Vec<u8> {
+        [&b"KEM"[..], &KEM_ID.to_be_bytes()].concat()
+    }
+
+    fn hpke_suite_id(aead_id: u16) -> Vec<u8> {
+        [
+            &b"HPKE"[..],
+            &KEM_ID.to_be_bytes(),
+            &KDF_ID.to_be_bytes(),
+            &aead_id.to_be_bytes(),
+        ]
+        .concat()
+    }
+
+    fn labeled_extract(suite_id: &[u8], salt: &[u8], label: &[u8], ikm: &[u8]) -> [u8; 32] {
+        let (prk, _) =
+            Hkdf::<Sha256>::extract(Some(salt), &[b"HPKE-v1", suite_id, label, ikm].concat());
+        prk.into()
+    }
+
+    fn labeled_expand<const L: usize>(
+        suite_id: &[u8],
+        prk: &[u8; 32],
+        label: &[u8],
+        info: &[u8],
+    ) -> [u8; L] {
+        let mut okm = [0; L];
+        Hkdf::<Sha256>::from_prk(prk)
+            .expect("a PRK of 32 bytes is valid for HKDF-SHA256")
+            .expand(
+                &[
+                    &(L as u16).to_be_bytes(),
+                    &b"HPKE-v1"[..],
+                    suite_id,
+                    label,
+                    info,
+                ]
+                .concat(),
+                &mut okm,
+            )
+            .expect("the output of HKDF-SHA256 is small enough");
+        okm
+    }
+
+    pub(super) fn public_key(secret_key: &[u8; 32]) -> [u8; 32] {
+        PublicKey::from(&StaticSecret::from(*secret_key)).to_bytes()
+    }
+
+    fn extract_and_expand(
+        secret_key: &[u8; 32],
+        public_key: &[u8; 32],
+        kem_context: &[u8],
+    ) -> Result<[u8; 32]> {
+        let dh = StaticSecret::from(*secret_key).diffie_hellman(&PublicKey::from(*public_key));
+        if !dh.was_contributory() {
+            return Err(anyhow!("invalid X25519 public key"));
+        }
+        let suite_id = kem_suite_id();
+        let eae_prk = labeled_extract(&suite_id, b"", b"eae_prk", dh.as_bytes());
+        Ok(labeled_expand(
+            &suite_id,
+            &eae_prk,
+            b"shared_secret",
+            kem_context,
+        ))
+    }
+
+    /// encapsulate a shared secret for a recipient, with an ephemeral secret key, and return it
+    /// together with the encapsulated key
+    pub(super) fn encap(
+        public_key: &[u8; 32],
+        ephemeral_key: [u8; 32],
+    ) -> Result<([u8; 32], [u8; 32])> {
+        let enc = self::public_key(&ephemeral_key);
+        let shared_secret =
+            extract_and_expand(&ephemeral_key, public_key, &[enc, *public_key].concat())?;
+        Ok((shared_secret, enc))
+    }
+
+    /// recover the shared secret of [`encap`] with the secret key of the recipient
+    pub(super) fn decap(enc: &[u8; 32], secret_key: &[u8; 32]) -> Result<[u8; 32]> {
+        extract_and_expand(
+            secret_key,
+            enc,
+            &[*enc, self::public_key(secret_key)].concat(),
+        )
+    }
+
+    /// the secret export of the key schedule in base mode
+    fn export<const L: usize>(
+        shared_secret: &[u8; 32],
+        aead_id: u16,
+        info: &[u8],
+        exporter_context: &[u8],
+    ) -> [u8; L] {
+        let suite_id = hpke_suite_id(aead_id);
+        let psk_id_hash = labeled_extract(&suite_id, b"", b"psk_id_hash", b"");
+        let info_hash = labeled_extract(&suite_id, b"", b"info_hash", info);
+        let key_schedule_context = [&[MODE_BASE][..], &psk_id_hash, &info_hash].concat();
+
+        let secret = labeled_extract(&suite_id, shared_secret, b"secret", b"");
+        let exporter_secret: [u8; 32] =
+            labeled_expand(&suite_id, &secret, b"exp", &key_schedule_context);
+        labeled_expand(&suite_id, &exporter_secret, b"sec", exporter_context)
+    }
+
+    /// the key of the encryption, exported from the shared secret
+    pub(super) fn derive_key(shared_secret: &[u8; 32]) -> Key {
+        Key(export(shared_secret, EXPORT_ONLY, INFO, EXPORTER_CONTEXT))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{decap, encap, export, public_key};
+
+        fn h<const N: usize>(hex: &str) -> [u8; N] {
+            hex::decode(hex).unwrap().try_into().unwrap()
+        }
+
+        /// the test vector A.1.1 of RFC 9180, i.e. DHKEM(X25519, HKDF-SHA256), HKDF-SHA256 and
+        /// AES-128-GCM in base mode
+        #[test]
+        fn rfc9180() {
+            let sk_em = h("52c4a758a802cd8b936eceea314432798d5baf2d7e9235dc084ab1b9cfa2f736");
+            let sk_rm = h("4612c550263fc8ad58375df3f557aac531d26850903e55a9f23f21d8534e8ac8");
+            let pk_rm = h("3948cfe0ad1ddb695d780e59077195da6c56506b027329794ab02bca80815c4d");
+            let info = hex::decode("4f6465206f6e2061204772656369616e2055726e").unwrap();
+
+            assert_eq!(public_key(&sk_rm), pk_rm);
+            let (shared_secret, enc) = encap(&pk_rm, sk_em).unwrap();
+            assert_eq!(
+                enc,
+                h("37fda3567bdbd628e88668c3c8d7e97d1d1253b6d4ea6d44c150f741f1bf4431")
+            );
+            assert_eq!(
+                shared_secret,
+                h("fe0e18c9f024ce43799ae393c7e8fe8fce9d218875e8227b0187c04e7d2ea1fc")
+            );
+            assert_eq!(decap(&enc, &sk_rm).unwrap(), shared_secret);
+
+            assert_eq!(
+                export::<32>(&shared_secret, 0x0001, &info, b""),
+                h("3853fe2b4035195a573ffc53856e77058e15d9ea064de3e59f4961d0095250ee")
+            );
+
+            assert!(decap(&[0; 32], &sk_rm).is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+    use ark_std::test_rng;
+
+    use super::{
+        collect, collect_threshold, disperse, disperse_threshold, keypair, open, seal, OpeningKey,
+        SealingKey, OVERHEAD,
+    };
+    use crate::{
+        algebra::linalg::Matrix,
+        fs::{Key, Manifest},
+        pipeline::ProvingScheme,
+        semi_avid::SemiAvid,
+    };
+
+    type S = SemiAvid<Fr, G1Projective, DensePolynomial<Fr>>;
+
+    #[test]
+    fn seal_and_open() {
+        let mut rng = test_rng();
+        let bytes = b"komodo".to_vec();
+
+        let key = Key([42; 32]);
+        let (sealed, sealing) = seal(&bytes, &SealingKey::Shared(key), &mut rng).unwrap();
+        assert_eq!(sealed.len(), bytes.len() + OVERHEAD);
+        assert!(sealing.encapsulated_key.is_empty());
+        assert_eq!(
+            open(&sealed, &sealing, &OpeningKey::Shared(key)).unwrap(),
+            bytes
+        );
+        assert!(open(&sealed, &sealing, &OpeningKey::Shared(Key([0; 32]))).is_err());
+        assert!(open(&sealed, &sealing, &OpeningKey::Recipient([42; 32])).is_err());
+
+        let (secret_key, public_key) = keypair(&mut rng).unwrap();
+        let (sealed, sealing) = seal(&bytes, &SealingKey::Recipient(public_key), &mut rng).unwrap();
+        assert_eq!(sealing.encapsulated_key.len(), 32);
+        let key = OpeningKey::Recipient(secret_key);
+        assert_eq!(open(&sealed, &sealing, &key).unwrap(), bytes);
+        assert!(open(
+            &sealed,
+            &sealing,
+            &OpeningKey::Recipient(keypair(&mut rng).unwrap().0)
+        )
+        .is_err());
+        assert!(open(&sealed, &sealing, &OpeningKey::Shared(Key(secret_key))).is_err());
+
+        let mut tampered = sealing.clone();
+        tampered.nonce[0] ^= 1;
+        assert!(open(&sealed, &tampered, &key).is_err());
+        let mut tampered = sealing.clone();
+        tampered.encapsulated_key = keypair(&mut rng).unwrap().1.to_vec();
+        assert!(open(&sealed, &tampered, &key).is_err());
+        let mut tampered = sealed.clone();
+        tampered[0] ^= 1;
+        assert!(open(&tampered, &sealing, &key).is_err());
+    }
+
+    #[test]
+    fn pipeline() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        let scheme = S::new(Matrix::random(3, 5, &mut rng));
+        let setup = scheme.setup(bytes.len() + OVERHEAD, &mut rng).unwrap();
+        let (secret_key, public_key) = keypair(&mut rng).unwrap();
+        let (blocks, sealing) = disperse(
+            &scheme,
+            &bytes,
+            &setup,
+            &SealingKey::Recipient(public_key),
+            &mut rng,
+        )
+        .unwrap();
+        for block in &blocks {
+            assert!(scheme.verify(block, &setup).unwrap());
+        }
+
+        let key = OpeningKey::Recipient(secret_key);
+        assert_eq!(
+            collect(&scheme, blocks[2..].to_vec(), &setup, &sealing, &key).unwrap(),
+            bytes
+        );
+        assert!(collect(&scheme, blocks[3..].to_vec(), &setup, &sealing, &key).is_err());
+
+        // NOTE: the sealing is carried by the manifest, the manifests of clear files are unchanged
+        let manifest = Manifest {
+            file_hash: blocks[0].shard.hash.clone(),
+            size: bytes.len() + OVERHEAD,
+            k: 3,
+            n: 5,
+            curve: "BLS12-381".to_string(),
+            setup_digest: vec![],
+            block_hashes: vec![],
+            sealing: Some(sealing),
+        };
+        let clear = Manifest {
+            sealing: None,
+            ..manifest.clone()
+        };
+        for m in [&manifest, &clear] {
+            let mut serialized = vec![];
+            m.serialize_compressed(&mut serialized).unwrap();
+            assert_eq!(serialized.len(), m.compressed_size());
+            let deserialized =
+                Manifest::deserialize_with_mode(&serialized[..], Compress::Yes, Validate::Yes);
+            assert_eq!(&deserialized.unwrap(), m);
+        }
+        let mut serialized = vec![];
+        clear.serialize_compressed(&mut serialized).unwrap();
+        serialized.push(0xff);
+        assert!(Manifest::deserialize_compressed(&serialized[..]).is_err());
+    }
+
+    #[test]
+    fn threshold() {
+        let mut rng = test_rng();
+        let bytes = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+        let (k, n) = (3, 5);
+
+        let points = (1..=n as u64).map(Fr::from).collect::<Vec<_>>();
+        let encoding_mat = Matrix::vandermonde(&points, k).unwrap();
+        let scheme = S::new(encoding_mat.clone());
+        let setup = scheme.setup(bytes.len() + OVERHEAD, &mut rng).unwrap();
+
+        let (blocks, key_shards, sealing) =
+            disperse_threshold(&scheme, &bytes, &setup, &encoding_mat, &mut rng).unwrap();
+        assert_eq!(key_shards.len(), n);
+        assert!(sealing.encapsulated_key.is_empty());
+
+        assert_eq!(
+            collect_threshold(
+                &scheme,
+                blocks[n - k..].to_vec(),
+                &setup,
+                &sealing,
+                key_shards[..k].to_vec()
+            )
+            .unwrap(),
+            bytes
+        );
+        // NOTE: all the blocks but fewer than k shards of the key are not enough
+        assert!(collect_threshold(
+            &scheme,
+            blocks.clone(),
+            &setup,
+            &sealing,
+            key_shards[..k - 1].to_vec()
+        )
+        .is_err());
+    }
+}
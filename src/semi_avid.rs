@@ -138,14 +138,28 @@ use ark_ff::PrimeField;
 use ark_poly::DenseUVPolynomial;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::ops::Div;
-use ark_std::rand::RngCore;
+use ark_std::{fmt, format, vec::Vec};
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use ark_std::{
+    marker::PhantomData,
+    rand::{CryptoRng, RngCore},
+};
 
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
 use tracing::{debug, info};
 
+#[cfg(feature = "arbitrary")]
+use crate::fuzzing;
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use crate::{
+    algebra::{self, linalg::Matrix},
+    fec::Shard,
+    pipeline::ProvingScheme,
+};
 use crate::{
-    algebra,
     error::KomodoError,
-    fec::{self, Shard},
+    fec,
+    telemetry::operation,
     zk::{self, Commitment, Powers},
 };
 
@@ -156,11 +170,11 @@ use crate::{
 #[derive(Debug, Default, Clone, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
 pub struct Block<F: PrimeField, G: CurveGroup<ScalarField = F>> {
     pub shard: fec::Shard<F>,
-    proof: Vec<Commitment<F, G>>,
+    pub(crate) proof: Vec<Commitment<F, G>>,
 }
 
-impl<F: PrimeField, G: CurveGroup<ScalarField = F>> std::fmt::Display for Block<F, G> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl<F: PrimeField, G: CurveGroup<ScalarField = F>> fmt::Display for Block<F, G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{{")?;
         write!(f, "shard: {{")?;
         write!(f, "k: {},", self.shard.k)?;
@@ -208,6 +222,61 @@ impl<F: PrimeField, G: CurveGroup<ScalarField = F>> std::fmt::Display for Block<
     }
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a, F: PrimeField, G: CurveGroup<ScalarField = F>> arbitrary::Arbitrary<'a> for Block<F, G> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let shard = Shard::arbitrary(u)?;
+        // NOTE: there is one commitment per source shard
+        let proof = (0..shard.k)
+            .map(|_| Ok(Commitment(fuzzing::group_element::<G>(u)?.into_affine())))
+            .collect::<arbitrary::Result<_>>()?;
+
+        Ok(Self { shard, proof })
+    }
+}
+
+impl<F: PrimeField, G: CurveGroup<ScalarField = F>> Block<F, G> {
+    /// the commitments of the polynomials of the data, one per row, see [`prove`]
+    pub fn commits(&self) -> &[Commitment<F, G>] {
+        &self.proof
+    }
+}
+
+fn check_same_proofs<F: PrimeField, G: CurveGroup<ScalarField = F>>(
+    blocks: &[Block<F, G>],
+) -> Result<(), KomodoError> {
+    for (i, (b1, b2)) in blocks.iter().zip(blocks.iter().skip(1)).enumerate() {
+        if b1.proof != b2.proof {
+            return Err(KomodoError::IncompatibleBlocks(format!(
+                "proofs are not the same at {}: {:?} vs {:?}",
+                i, b1.proof, b2.proof
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// compute a recoded block from an arbitrary set of blocks and one coefficient for each block,
+/// e.g. to make recoding deterministic
+///
+/// returns [`None`] if the number of blocks is not the same as the number of coefficients or if
+/// no blocks are provided, see [`fec::recode_with_coeffs`].
+///
+/// if the blocks appear to come from different data, e.g. if the commits are
+/// different, an error will be returned.
+pub fn recode_with_coeffs<F: PrimeField, G: CurveGroup<ScalarField = F>>(
+    blocks: &[Block<F, G>],
+    coeffs: &[F],
+) -> Result<Option<Block<F, G>>, KomodoError> {
+    check_same_proofs(blocks)?;
+    let shards = blocks.iter().map(|b| b.shard.clone()).collect::<Vec<_>>();
+
+    Ok(fec::recode_with_coeffs(&shards, coeffs).map(|shard| Block {
+        shard,
+        proof: blocks[0].proof.clone(),
+    }))
+}
+
 /// compute a recoded block from an arbitrary set of blocks
 ///
 /// coefficients will be drawn at random, one for each block.
@@ -217,47 +286,46 @@ impl<F: PrimeField, G: CurveGroup<ScalarField = F>> std::fmt::Display for Block<
 ///
 /// > **Note**
 /// >
-/// > this is a wrapper around [`fec::recode_random`].
+/// > this is a wrapper around [`fec::recode_random`], i.e. `rng` is subject to the
+/// > [`crate::randomness::RandomnessPolicy`], see [`recode_secure`].
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
 pub fn recode<F: PrimeField, G: CurveGroup<ScalarField = F>>(
     blocks: &[Block<F, G>],
     rng: &mut impl RngCore,
 ) -> Result<Option<Block<F, G>>, KomodoError> {
-    for (i, (b1, b2)) in blocks.iter().zip(blocks.iter().skip(1)).enumerate() {
-        if b1.proof != b2.proof {
-            return Err(KomodoError::IncompatibleBlocks(format!(
-                "proofs are not the same at {}: {:?} vs {:?}",
-                i, b1.proof, b2.proof
-            )));
-        }
-    }
-    let shard = match fec::recode_random(
+    check_same_proofs(blocks)?;
+    let shard = fec::recode_random(
         &blocks.iter().map(|b| b.shard.clone()).collect::<Vec<_>>(),
         rng,
-    )? {
-        Some(s) => s,
-        None => return Ok(None),
-    };
+    )?;
 
-    Ok(Some(Block {
+    Ok(shard.map(|shard| Block {
         shard,
         proof: blocks[0].proof.clone(),
     }))
 }
 
-/// compute the Semi-AVID proof for some data
-pub fn prove<F, G, P>(
-    bytes: &[u8],
-    powers: &Powers<F, G>,
-    k: usize,
-) -> Result<Vec<Commitment<F, G>>, KomodoError>
-where
-    F: PrimeField,
-    G: CurveGroup<ScalarField = F>,
-    P: DenseUVPolynomial<F>,
-    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
-{
-    info!("encoding and proving {} bytes", bytes.len());
+/// the same as [`recode`], with a cryptographically secure source of randomness
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn recode_secure<F: PrimeField, G: CurveGroup<ScalarField = F>>(
+    blocks: &[Block<F, G>],
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<Option<Block<F, G>>, KomodoError> {
+    check_same_proofs(blocks)?;
+    let shard = fec::recode_random_secure(
+        &blocks.iter().map(|b| b.shard.clone()).collect::<Vec<_>>(),
+        rng,
+    )?;
 
+    Ok(shard.map(|shard| Block {
+        shard,
+        proof: blocks[0].proof.clone(),
+    }))
+}
+
+/// split some data into polynomials and transpose them, i.e. the polynomials to commit
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+fn polynomials_to_commit<F: PrimeField, P: DenseUVPolynomial<F>>(bytes: &[u8], k: usize) -> Vec<P> {
     debug!("splitting bytes into polynomials");
     let elements = algebra::split_data_into_field_elements(bytes, k);
     let polynomials = elements
@@ -271,18 +339,77 @@ where
     );
 
     debug!("transposing the polynomials to commit");
-    let polynomials_to_commit = (0..polynomials[0].coeffs().len())
+    (0..polynomials[0].coeffs().len())
         .map(|i| P::from_coefficients_vec(polynomials.iter().map(|p| p.coeffs()[i]).collect()))
-        .collect::<Vec<P>>();
+        .collect()
+}
+
+/// compute the Semi-AVID proof for some data
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn prove<F, G, P>(
+    bytes: &[u8],
+    powers: &Powers<F, G>,
+    k: usize,
+) -> Result<Vec<Commitment<F, G>>, KomodoError>
+where
+    F: PrimeField,
+    G: CurveGroup<ScalarField = F>,
+    P: DenseUVPolynomial<F>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    let op = operation!("prove", "semi-avid", bytes = bytes.len(), k);
+
+    info!("encoding and proving {} bytes", bytes.len());
+
+    let polynomials_to_commit = polynomials_to_commit::<F, P>(bytes, k);
 
     debug!("committing the polynomials");
     let commits = zk::batch_commit(powers, &polynomials_to_commit)?;
 
-    Ok(commits)
+    op.done(Ok(commits))
+}
+
+/// compute the Semi-AVID proofs of a batch of files at once, with the same trusted setup
+///
+/// the polynomials of all the files are committed together with [`zk::batch_commit`], instead of
+/// one file after the other, which amortizes the fixed costs of each call to [`prove`] when the
+/// files are small.
+///
+/// the proofs are in the same order as the files and are the same as the ones of [`prove`], i.e.
+/// `powers` should be large enough for the largest file.
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn prove_batch<F, G, P>(
+    files: &[&[u8]],
+    powers: &Powers<F, G>,
+    k: usize,
+) -> Result<Vec<Vec<Commitment<F, G>>>, KomodoError>
+where
+    F: PrimeField,
+    G: CurveGroup<ScalarField = F>,
+    P: DenseUVPolynomial<F>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    let op = operation!("prove_batch", "semi-avid", files = files.len(), k);
+
+    info!("encoding and proving a batch of {} files", files.len());
+
+    let polynomials = files
+        .iter()
+        .map(|bytes| polynomials_to_commit::<F, P>(bytes, k))
+        .collect::<Vec<_>>();
+
+    debug!("committing the polynomials of all the files");
+    let mut commits = zk::batch_commit(powers, &polynomials.concat())?.into_iter();
+
+    op.done(Ok(polynomials
+        .iter()
+        .map(|p| commits.by_ref().take(p.len()).collect())
+        .collect()))
 }
 
 /// attach a Semi-AVID proof to a collection of encoded shards
 #[inline(always)]
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
 pub fn build<F, G, P>(shards: &[Shard<F>], proof: &[Commitment<F, G>]) -> Vec<Block<F, G>>
 where
     F: PrimeField,
@@ -310,6 +437,8 @@ where
     P: DenseUVPolynomial<F>,
     for<'a, 'b> &'a P: Div<&'b P, Output = P>,
 {
+    let op = operation!("verify", "semi-avid", elements = block.shard.data.len());
+
     let elements = block.shard.data.clone();
     let polynomial = P::from_coefficients_vec(elements);
     let commit = zk::commit(verifier_key, &polynomial)?;
@@ -321,7 +450,107 @@ where
         .enumerate()
         .map(|(i, w)| block.proof[i].0.into() * w)
         .sum();
-    Ok(commit.0.into() == rhs)
+    Ok(op.verified(commit.0.into() == rhs))
+}
+
+/// Semi-AVID as a [`ProvingScheme`], with a given encoding matrix
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub struct SemiAvid<F: PrimeField, G, P> {
+    encoding_mat: Matrix<F>,
+    _phantom: PhantomData<(G, P)>,
+}
+
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+impl<F: PrimeField, G, P> SemiAvid<F, G, P> {
+    /// $k$ and $n$ are the height and the width of `encoding_mat`
+    pub fn new(encoding_mat: Matrix<F>) -> Self {
+        Self {
+            encoding_mat,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+impl<F, G, P> ProvingScheme for SemiAvid<F, G, P>
+where
+    F: PrimeField,
+    G: CurveGroup<ScalarField = F>,
+    P: DenseUVPolynomial<F>,
+    for<'a, 'b> &'a P: Div<&'b P, Output = P>,
+{
+    type Field = F;
+    type SetupParams = Powers<F, G>;
+    type Proof = Vec<Commitment<F, G>>;
+    type Block = Block<F, G>;
+
+    const RECODABLE: bool = true;
+
+    fn setup<R: RngCore>(&self, nb_bytes: usize, rng: &mut R) -> Result<Powers<F, G>, KomodoError> {
+        zk::setup::<F, G>(zk::nb_elements_in_setup::<F>(nb_bytes), rng)
+    }
+
+    fn setup_secure<R: RngCore + CryptoRng>(
+        &self,
+        nb_bytes: usize,
+        rng: &mut R,
+    ) -> Result<Powers<F, G>, KomodoError> {
+        zk::setup_secure::<F, G>(zk::nb_elements_in_setup::<F>(nb_bytes), rng)
+    }
+
+    fn encode(&self, bytes: &[u8]) -> Result<Vec<Shard<F>>, KomodoError> {
+        fec::encode(bytes, &self.encoding_mat)
+    }
+
+    fn encode_with_hash(&self, bytes: &[u8], hash: &[u8]) -> Result<Vec<Shard<F>>, KomodoError> {
+        fec::encode_with_hash(bytes, &self.encoding_mat, hash.to_vec())
+    }
+
+    fn prove(
+        &self,
+        bytes: &[u8],
+        _shards: &[Shard<F>],
+        params: &Powers<F, G>,
+    ) -> Result<Vec<Commitment<F, G>>, KomodoError> {
+        prove::<F, G, P>(bytes, params, self.encoding_mat.height)
+    }
+
+    fn prove_batch(
+        &self,
+        files: &[&[u8]],
+        _shards: &[Vec<Shard<F>>],
+        params: &Powers<F, G>,
+    ) -> Result<Vec<Vec<Commitment<F, G>>>, KomodoError> {
+        prove_batch::<F, G, P>(files, params, self.encoding_mat.height)
+    }
+
+    fn build(&self, shards: Vec<Shard<F>>, proof: Vec<Commitment<F, G>>) -> Vec<Block<F, G>> {
+        build::<F, G, P>(&shards, &proof)
+    }
+
+    fn verify(&self, block: &Block<F, G>, params: &Powers<F, G>) -> Result<bool, KomodoError> {
+        verify::<F, G, P>(block, params)
+    }
+
+    fn recode<R: RngCore>(
+        &self,
+        blocks: &[Block<F, G>],
+        rng: &mut R,
+    ) -> Result<Option<Block<F, G>>, KomodoError> {
+        recode(blocks, rng)
+    }
+
+    fn recode_secure<R: RngCore + CryptoRng>(
+        &self,
+        blocks: &[Block<F, G>],
+        rng: &mut R,
+    ) -> Result<Option<Block<F, G>>, KomodoError> {
+        recode_secure(blocks, rng)
+    }
+
+    fn shard(block: &Block<F, G>) -> &Shard<F> {
+        &block.shard
+    }
 }
 
 #[cfg(test)]
@@ -339,7 +568,7 @@ mod tests {
         zk::{setup, Commitment},
     };
 
-    use super::{build, prove, recode, verify};
+    use super::{build, prove, recode, recode_with_coeffs, verify};
 
     fn bytes() -> Vec<u8> {
         include_bytes!("../assets/dragoon_133x133.png").to_vec()
@@ -430,6 +659,28 @@ mod tests {
             &powers
         )?);
 
+        let coeffs = [F::from(2u64), F::from(3u64)];
+        let recoded = recode_with_coeffs(&blocks[2..=3], &coeffs)
+            .unwrap()
+            .unwrap();
+        assert!(verify(&recoded, &powers)?);
+        assert_eq!(
+            recode_with_coeffs(&blocks[2..=3], &coeffs).unwrap(),
+            Some(recoded),
+            "recoding with the same coefficients should be deterministic"
+        );
+        assert_eq!(
+            recode_with_coeffs(&blocks[2..=3], &[F::one(), F::zero()])
+                .unwrap()
+                .unwrap()
+                .shard
+                .data,
+            blocks[2].shard.data
+        );
+        assert!(recode_with_coeffs(&blocks[2..=3], &coeffs[..1])
+            .unwrap()
+            .is_none());
+
         Ok(())
     }
 
@@ -0,0 +1,198 @@
+//! structured instrumentation of the heavy operations of Komodo
+//!
+//! on top of the `info!` and `debug!` events scattered across the library, the heavy operations
+//! run inside a [`tracing`] span, at the `INFO` level, whose name is the operation:
+//!
+//! | span       | protocols                                      | fields                         |
+//! | ---------- | ---------------------------------------------- | ------------------------------ |
+//! | `encode`   | `fec`                                          | `bytes`, `k`, `n`              |
+//! | `commit`   | `zk`                                           | `polynomials`                  |
+//! | `prove`    | `semi-avid`, `kzg`, `aplonk`, `fri`            | `bytes` or `shards`            |
+//! | `verify`   | `semi-avid`, `kzg`, `aplonk`, `fri`            | `elements` or `blocks`         |
+//! | `decode`   | `fec`                                          | `shards`, `bytes`              |
+//!
+//! all the spans have the `protocol` field, see the table above, and are closed with the following
+//! fields
+//! - `outcome`: `ok` or `error`, and `valid` or `invalid` for the verifications that tell whether
+//!   blocks are valid
+//! - `duration_us`: the duration of the operation in microseconds, only with `std` and outside of
+//!   `wasm32-unknown-unknown` where there is no clock
+//!
+//! # Metrics
+//! with the `metrics` feature, the operations are also recorded through the
+//! [`metrics`](https://docs.rs/metrics) facade, i.e. they go to whatever recorder the application
+//! has installed, e.g. a _Prometheus_ exporter, and cost nothing otherwise:
+//! - [`OPERATIONS`]: a counter with the `operation`, `protocol` and `outcome` labels
+//! - [`DURATION`]: a histogram of durations in seconds with the `operation` and `protocol` labels
+use tracing::Span;
+
+/// the name of the counter of operations, see the [module-level documentation](self)
+pub const OPERATIONS: &str = "komodo_operations_total";
+/// the name of the histogram of the durations of operations, see the
+/// [module-level documentation](self)
+pub const DURATION: &str = "komodo_operation_duration_seconds";
+
+/// open the span of an operation, with the fields common to all operations, see [`Operation`]
+macro_rules! operation {
+    ($name:literal, $protocol:literal $(, $($fields:tt)+)?) => {
+        $crate::telemetry::Operation::start(
+            $name,
+            $protocol,
+            tracing::info_span!(
+                $name,
+                protocol = $protocol,
+                $($($fields)+,)?
+                outcome = tracing::field::Empty,
+                duration_us = tracing::field::Empty,
+            ),
+        )
+    };
+}
+pub(crate) use operation;
+
+/// an operation running inside its span, see [`operation!`]
+///
+/// the operation is closed either explicitly, with [`Operation::done`] or [`Operation::verified`],
+/// or when it is dropped, e.g. because of an early return with `?`, in which case it is an error.
+pub(crate) struct Operation {
+    name: &'static str,
+    protocol: &'static str,
+    outcome: &'static str,
+    span: tracing::span::EnteredSpan,
+    #[cfg(all(
+        feature = "std",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ))]
+    start: std::time::Instant,
+}
+
+impl Operation {
+    pub(crate) fn start(name: &'static str, protocol: &'static str, span: Span) -> Self {
+        Self {
+            name,
+            protocol,
+            outcome: "error",
+            span: span.entered(),
+            #[cfg(all(
+                feature = "std",
+                not(all(target_arch = "wasm32", target_os = "unknown"))
+            ))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    /// record a field of the span that is only known at the end of the operation
+    pub(crate) fn record<V: tracing::Value>(&self, field: &'static str, value: V) {
+        self.span.record(field, value);
+    }
+
+    /// close the operation with the outcome of a result
+    pub(crate) fn done<T, E>(mut self, result: Result<T, E>) -> Result<T, E> {
+        if result.is_ok() {
+            self.outcome = "ok";
+        }
+        result
+    }
+
+    /// close a verification with its outcome
+    pub(crate) fn verified(mut self, valid: bool) -> bool {
+        self.outcome = if valid { "valid" } else { "invalid" };
+        valid
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        self.span.record("outcome", self.outcome);
+
+        #[cfg(all(
+            feature = "std",
+            not(all(target_arch = "wasm32", target_os = "unknown"))
+        ))]
+        {
+            let duration = self.start.elapsed();
+            self.span.record("duration_us", duration.as_micros() as u64);
+
+            #[cfg(feature = "metrics")]
+            metrics::histogram!(DURATION, "operation" => self.name, "protocol" => self.protocol)
+                .record(duration.as_secs_f64());
+        }
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!(
+            OPERATIONS,
+            "operation" => self.name,
+            "protocol" => self.protocol,
+            "outcome" => self.outcome,
+        )
+        .increment(1);
+
+        #[cfg(not(feature = "metrics"))]
+        let _ = (self.name, self.protocol);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::KomodoError;
+
+    #[test]
+    fn outcomes() {
+        fn run(fail: bool) -> Result<u32, KomodoError> {
+            let op = operation!("encode", "fec", bytes = 3);
+            if fail {
+                return Err(KomodoError::DegreeIsZero);
+            }
+            op.record("bytes", 4);
+            op.done(Ok(4))
+        }
+
+        assert_eq!(run(false).unwrap(), 4);
+        assert!(run(true).is_err());
+
+        let op = operation!("verify", "semi-avid");
+        assert!(!op.verified(false));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics() {
+        use metrics_util::{
+            debugging::{DebugValue, DebuggingRecorder},
+            MetricKind,
+        };
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            let op = operation!("verify", "semi-avid");
+            op.verified(true);
+            let op = operation!("verify", "semi-avid");
+            op.verified(true);
+            let _op = operation!("decode", "fec");
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let counter = |operation: &str, outcome: &str| {
+            snapshot
+                .iter()
+                .find(|(key, ..)| {
+                    let (kind, key) = (key.kind(), key.key());
+                    kind == MetricKind::Counter
+                        && key.name() == super::OPERATIONS
+                        && key
+                            .labels()
+                            .any(|l| l.key() == "operation" && l.value() == operation)
+                        && key
+                            .labels()
+                            .any(|l| l.key() == "outcome" && l.value() == outcome)
+                })
+                .map(|(.., value)| value)
+        };
+
+        assert_eq!(counter("verify", "valid"), Some(&DebugValue::Counter(2)));
+        assert_eq!(counter("decode", "error"), Some(&DebugValue::Counter(1)));
+        assert_eq!(counter("verify", "invalid"), None);
+    }
+}
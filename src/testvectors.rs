@@ -0,0 +1,349 @@
+//! deterministic test vectors, to check other implementations of Komodo against this crate
+//!
+//! a [`TestVector`] is [`generate`]d from a seed by a [`ProvingScheme`] and contains everything
+//! that has been computed along the way: the input bytes, the encoding matrix, the trusted setup,
+//! the shards, the proof and the proven blocks, all serialized in the _compressed_ canonical
+//! format of _arkworks_. A vector can be written to a simple text format, with its [`Display`]
+//! implementation, and parsed back with [`FromStr`], to be shipped to implementations in other
+//! languages, e.g. a JS or Go verifier.
+//!
+//! [`check`] makes sure that a vector is consistent, i.e. that all its blocks verify, that their
+//! shards are the ones of the vector and that they decode to the input bytes. [`generate`] being
+//! deterministic, a vector can also be generated again and compared with a stored one.
+//!
+//! the text format has one `<key> <value>` pair per line, the values being in lowercase hexadecimal
+//! for binary data and in decimal for integers, with the following keys, in this order:
+//! - `protocol`, `curve`, `seed`, `k` and `n`: the parameters of the vector
+//! - `bytes`: the input bytes
+//! - `matrix`: the $k \times n$ encoding matrix, see [`Matrix`]
+//! - `setup`: the trusted setup, see [`ProvingScheme::SetupParams`]
+//! - `proof`: the proof of the shards, e.g. the commitments, see [`ProvingScheme::Proof`]
+//! - `shard`: a shard, see [`Shard`], once per shard
+//! - `block`: a block, see [`ProvingScheme::Block`], once per shard
+//!
+//! > **Note**
+//! >
+//! > the seed only makes sense to this crate, i.e. the randomness is drawn from [`StdRng`] with
+//! > [`SeedableRng::seed_from_u64`], which other implementations are not expected to reproduce,
+//! > and the vectors should be used as they are.
+//!
+//! # Example
+//! ```
+//! # use ark_bls12_381::{Fr, G1Projective};
+//! # use ark_poly::univariate::DensePolynomial;
+//! use komodo::{algebra::linalg::Matrix, semi_avid::SemiAvid, testvectors};
+//!
+//! # fn main() {
+//! let vector = testvectors::generate("semi-avid", "BLS12-381", 0, 3, 5, 100, |m, _| {
+//!     SemiAvid::<Fr, G1Projective, DensePolynomial<Fr>>::new(m.clone())
+//! })
+//! .unwrap();
+//! assert_eq!(vector.blocks.len(), 5);
+//!
+//! let text = vector.to_string();
+//! let parsed: testvectors::TestVector = text.parse().unwrap();
+//! assert_eq!(parsed, vector);
+//!
+//! let matrix = parsed.matrix::<Fr>().unwrap();
+//! testvectors::check(&parsed, &SemiAvid::<Fr, G1Projective, DensePolynomial<Fr>>::new(matrix))
+//!     .unwrap();
+//! # }
+//! ```
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use ark_std::rand::{rngs::StdRng, RngCore, SeedableRng};
+use tracing::info;
+
+use crate::{algebra::linalg::Matrix, fec::Shard, pipeline::ProvingScheme};
+
+/// the seeds of the vectors that are expected to be published for each protocol and curve
+pub const SEEDS: [u64; 3] = [0, 1, 42];
+
+/// a deterministic test vector, see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TestVector {
+    /// the name of the protocol, e.g. `"semi-avid"`
+    pub protocol: String,
+    /// the name of the curve, e.g. `"BLS12-381"`
+    pub curve: String,
+    /// the seed the vector has been generated from
+    pub seed: u64,
+    /// the number of source shards
+    pub k: usize,
+    /// the number of encoded shards
+    pub n: usize,
+    /// the input bytes
+    pub bytes: Vec<u8>,
+    /// the serialized $k \times n$ encoding matrix
+    pub matrix: Vec<u8>,
+    /// the serialized trusted setup
+    pub setup: Vec<u8>,
+    /// the serialized proof of the shards
+    pub proof: Vec<u8>,
+    /// the serialized shards
+    pub shards: Vec<Vec<u8>>,
+    /// the serialized blocks
+    pub blocks: Vec<Vec<u8>>,
+}
+
+fn serialize(serializable: &impl CanonicalSerialize) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    serializable.serialize_with_mode(&mut bytes, Compress::Yes)?;
+    Ok(bytes)
+}
+
+fn deserialize<T: CanonicalDeserialize>(bytes: &[u8]) -> Result<T> {
+    Ok(T::deserialize_with_mode(
+        bytes,
+        Compress::Yes,
+        Validate::Yes,
+    )?)
+}
+
+impl TestVector {
+    /// the encoding matrix of the vector
+    pub fn matrix<F: PrimeField>(&self) -> Result<Matrix<F>> {
+        deserialize(&self.matrix)
+    }
+}
+
+/// generate a test vector from a seed
+///
+/// the random encoding matrix is drawn first and given to `new`, together with the source of
+/// randomness, to build the [`ProvingScheme`], then the `nb_bytes` input bytes are drawn, the
+/// trusted setup is built and the bytes are encoded, proven and turned into blocks.
+///
+/// > **Note**
+/// >
+/// > the matrix given to `new` is the one stored in the vector, i.e. `new` should build a scheme
+/// > that encodes with it.
+pub fn generate<S, N>(
+    protocol: &str,
+    curve: &str,
+    seed: u64,
+    k: usize,
+    n: usize,
+    nb_bytes: usize,
+    new: N,
+) -> Result<TestVector>
+where
+    S: ProvingScheme,
+    S::SetupParams: CanonicalSerialize,
+    S::Proof: CanonicalSerialize,
+    S::Block: CanonicalSerialize,
+    N: FnOnce(&Matrix<S::Field>, &mut StdRng) -> S,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let matrix = Matrix::random(k, n, &mut rng);
+    let scheme = new(&matrix, &mut rng);
+
+    let mut bytes = vec![0; nb_bytes];
+    rng.fill_bytes(&mut bytes);
+
+    let setup = scheme.setup(nb_bytes, &mut rng)?;
+    let shards = scheme.encode(&bytes)?;
+    let proof = scheme.prove(&bytes, &shards, &setup)?;
+    let serialized_proof = serialize(&proof)?;
+    let blocks = scheme.build(shards.clone(), proof);
+
+    info!(
+        "generated {} test vector on {} with seed {}",
+        protocol, curve, seed
+    );
+    Ok(TestVector {
+        protocol: protocol.to_string(),
+        curve: curve.to_string(),
+        seed,
+        k,
+        n,
+        matrix: serialize(&matrix)?,
+        setup: serialize(&setup)?,
+        proof: serialized_proof,
+        shards: shards.iter().map(serialize).collect::<Result<_>>()?,
+        blocks: blocks.iter().map(serialize).collect::<Result<_>>()?,
+        bytes,
+    })
+}
+
+/// check that a test vector is consistent
+///
+/// it is an error if the vector has not $n$ shards and $n$ blocks, if a block does not verify with
+/// the trusted setup of the vector, if the shard of a block is not the one of the vector, if the
+/// shards have not been encoded with the matrix of the vector or if the blocks do not decode to
+/// the input bytes.
+pub fn check<S>(vector: &TestVector, scheme: &S) -> Result<()>
+where
+    S: ProvingScheme,
+    S::SetupParams: CanonicalDeserialize,
+    S::Block: CanonicalDeserialize,
+{
+    if vector.shards.len() != vector.n || vector.blocks.len() != vector.n {
+        return Err(anyhow!(
+            "expected {} shards and blocks, found {} shards and {} blocks",
+            vector.n,
+            vector.shards.len(),
+            vector.blocks.len()
+        ));
+    }
+
+    let setup: S::SetupParams = deserialize(&vector.setup)?;
+    let matrix: Matrix<S::Field> = vector.matrix()?;
+    if (matrix.height, matrix.width) != (vector.k, vector.n) {
+        return Err(anyhow!(
+            "expected a {}x{} matrix, found {}x{}",
+            vector.k,
+            vector.n,
+            matrix.height,
+            matrix.width
+        ));
+    }
+
+    let blocks = vector
+        .blocks
+        .iter()
+        .map(|b| deserialize::<S::Block>(b))
+        .collect::<Result<Vec<_>>>()?;
+    for (i, (block, shard)) in blocks.iter().zip(vector.shards.iter()).enumerate() {
+        if !scheme.verify(block, &setup)? {
+            return Err(anyhow!("block {} does not verify", i));
+        }
+        let shard: Shard<S::Field> = deserialize(shard)?;
+        if S::shard(block) != &shard {
+            return Err(anyhow!("the shard of block {} is not shard {}", i, i));
+        }
+        if shard.linear_combination != matrix.get_col(i).unwrap_or_default() {
+            return Err(anyhow!("shard {} is not column {} of the matrix", i, i));
+        }
+    }
+
+    if scheme.decode(blocks)? != vector.bytes {
+        return Err(anyhow!("the blocks do not decode to the input bytes"));
+    }
+
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+impl Display for TestVector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "protocol {}", self.protocol)?;
+        writeln!(f, "curve {}", self.curve)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "k {}", self.k)?;
+        writeln!(f, "n {}", self.n)?;
+        writeln!(f, "bytes {}", to_hex(&self.bytes))?;
+        writeln!(f, "matrix {}", to_hex(&self.matrix))?;
+        writeln!(f, "setup {}", to_hex(&self.setup))?;
+        writeln!(f, "proof {}", to_hex(&self.proof))?;
+        for shard in &self.shards {
+            writeln!(f, "shard {}", to_hex(shard))?;
+        }
+        for block in &self.blocks {
+            writeln!(f, "block {}", to_hex(block))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TestVector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut vector = Self::default();
+        for line in s.lines().filter(|l| !l.trim().is_empty()) {
+            let invalid = || anyhow!("invalid test vector line: {:?}", line);
+            let (key, value) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let hex = || from_hex(value).ok_or_else(invalid);
+            match key {
+                "protocol" => vector.protocol = value.to_string(),
+                "curve" => vector.curve = value.to_string(),
+                "seed" => vector.seed = value.parse().map_err(|_| invalid())?,
+                "k" => vector.k = value.parse().map_err(|_| invalid())?,
+                "n" => vector.n = value.parse().map_err(|_| invalid())?,
+                "bytes" => vector.bytes = hex()?,
+                "matrix" => vector.matrix = hex()?,
+                "setup" => vector.setup = hex()?,
+                "proof" => vector.proof = hex()?,
+                "shard" => vector.shards.push(hex()?),
+                "block" => vector.blocks.push(hex()?),
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(vector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Fr, G1Projective};
+    use ark_poly::univariate::DensePolynomial;
+
+    use super::{check, generate, TestVector, SEEDS};
+    use crate::{algebra::linalg::Matrix, semi_avid::SemiAvid};
+
+    type S = SemiAvid<Fr, G1Projective, DensePolynomial<Fr>>;
+
+    fn semi_avid(seed: u64) -> TestVector {
+        generate(
+            "semi-avid",
+            "BLS12-381",
+            seed,
+            3,
+            5,
+            123,
+            |m: &Matrix<Fr>, _| S::new(m.clone()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn determinism() {
+        for seed in SEEDS {
+            let vector = semi_avid(seed);
+            assert_eq!(vector, semi_avid(seed), "seed {}", seed);
+            assert_eq!(vector.to_string().parse::<TestVector>().unwrap(), vector);
+            check(&vector, &S::new(vector.matrix().unwrap())).unwrap();
+        }
+        assert_ne!(semi_avid(0), semi_avid(1));
+    }
+
+    #[test]
+    fn inconsistencies() {
+        let vector = semi_avid(0);
+        let scheme = S::new(vector.matrix().unwrap());
+
+        let mut bytes = vector.clone();
+        bytes.bytes[0] ^= 1;
+        assert!(check(&bytes, &scheme).is_err());
+
+        let mut swapped = vector.clone();
+        swapped.blocks.swap(0, 1);
+        assert!(check(&swapped, &scheme).is_err());
+
+        let mut truncated = vector.clone();
+        truncated.blocks.pop();
+        assert!(check(&truncated, &scheme).is_err());
+
+        assert!("protocol semi-avid\nseed x".parse::<TestVector>().is_err());
+        assert!("foo bar".parse::<TestVector>().is_err());
+        assert!("bytes 0".parse::<TestVector>().is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! threshold secret sharing of data with the encoding of [`crate::fec`]
+//!
+//! [`crate::fec::encode`] makes any $k$ shards enough to decode the data, but a single shard
+//! already reveals a lot about it, e.g. the data itself when the encoding matrix is systematic.
+//! [`share`] uses the same encoding, on source shards that are made of the data, as the first
+//! source shard, and of $k - 1$ source shards of uniformly random elements: this is
+//! [Shamir's secret sharing](https://en.wikipedia.org/wiki/Shamir%27s_secret_sharing), with
+//! the encoding matrix giving the evaluation points, and $(k, n)$ becomes an _access-control
+//! threshold_ on top of a durability parameter, i.e. any $k$ shards reconstruct the data with
+//! [`reconstruct`] and fewer than $k$ of them reveal nothing about it.
+//!
+//! > **Note**
+//! >
+//! > fewer than $k$ shards reveal nothing as long as the first source shard is not a linear
+//! > combination of them, see [`reveals`]. This is always the case with a Vandermonde matrix
+//! > on distinct non-zero points, which is what Shamir's secret sharing uses, but not with any
+//! > matrix, e.g. a systematic one gives the data away in the first shard.
+//!
+//! each shard is as large as the data, so this is meant for small secrets, e.g. the key the
+//! data has been encrypted with, see `sealed::disperse_threshold`*, rather than for the data
+//! itself.
+//!
+//! the shards are regular [`Shard`]s, they can be recoded with [`crate::fec::recode_random`]
+//! and are reconstructed from any $k$ linearly independent ones, with the same errors as
+//! [`crate::fec::decode`].
+//!
+//! # Example
+//! ```
+//! # use ark_bls12_381::Fr;
+//! use komodo::{algebra::linalg::Matrix, threshold};
+//!
+//! # fn main() {
+//! let mut rng = ark_std::test_rng();
+//! let secret = b"a very secret key".to_vec();
+//!
+//! let points: Vec<Fr> = (1..=5u64).map(Fr::from).collect();
+//! let encoding_mat = Matrix::vandermonde(&points, 3).unwrap();
+//! let shards = threshold::share(&secret, &encoding_mat, &mut rng).unwrap();
+//!
+//! assert!(!threshold::reveals(&shards[..2]));
+//! assert_eq!(threshold::reconstruct(shards[2..].to_vec()).unwrap(), secret);
+//! # }
+//! ```
+use ark_ff::PrimeField;
+use ark_std::{
+    rand::{CryptoRng, RngCore},
+    vec,
+    vec::Vec,
+};
+
+use crate::{
+    algebra::{self, linalg::Matrix},
+    error::KomodoError,
+    fec::{self, Shard},
+};
+
+/// the number of random bytes identifying the shards of a secret, see [`share`]
+const ID_SIZE: usize = 32;
+
+/// split a secret into shards such that any $k$ of them are required to reconstruct it
+///
+/// the secret is shared with the encoding matrix, $k$ being its height and $n$ its width, see the
+/// [module-level documentation](self).
+///
+/// > **Note**
+/// >
+/// > [`Shard::hash`] is _not_ the hash of the secret, which would allow to check a guess, but a
+/// > random identifier, the same for all the shards of a secret.
+/// >
+/// > the random parts of the shards are drawn from `rng`, which is thus subject to the
+/// > [`crate::randomness::RandomnessPolicy`], see [`share_secure`].
+///
+/// This is the inverse of [`reconstruct`].
+pub fn share<F: PrimeField, R: RngCore>(
+    secret: &[u8],
+    encoding_mat: &Matrix<F>,
+    rng: &mut R,
+) -> Result<Vec<Shard<F>>, KomodoError> {
+    crate::randomness::check("threshold::share")?;
+    share_unchecked(secret, encoding_mat, rng)
+}
+
+/// the same as [`share`], with a cryptographically secure source of randomness
+pub fn share_secure<F: PrimeField, R: RngCore + CryptoRng>(
+    secret: &[u8],
+    encoding_mat: &Matrix<F>,
+    rng: &mut R,
+) -> Result<Vec<Shard<F>>, KomodoError> {
+    share_unchecked(secret, encoding_mat, rng)
+}
+
+fn share_unchecked<F: PrimeField, R: RngCore>(
+    secret: &[u8],
+    encoding_mat: &Matrix<F>,
+    rng: &mut R,
+) -> Result<Vec<Shard<F>>, KomodoError> {
+    let k = encoding_mat.height;
+
+    let source_shards = Matrix::from_vec_vec(
+        algebra::split_data_into_field_elements::<F>(secret, 1)
+            .into_iter()
+            .map(|e| {
+                let mut row = vec![e];
+                row.extend((1..k).map(|_| F::rand(rng)));
+                row
+            })
+            .collect(),
+    )?;
+
+    let mut id = vec![0; ID_SIZE];
+    rng.fill_bytes(&mut id);
+
+    Ok(source_shards
+        .mul(encoding_mat)?
+        .transpose()
+        .elements
+        .chunks(source_shards.height)
+        .enumerate()
+        .map(|(j, s)| Shard {
+            k: k as u32,
+            linear_combination: encoding_mat.get_col(j).unwrap(),
+            hash: id.clone(),
+            data: s.to_vec(),
+            size: secret.len(),
+        })
+        .collect())
+}
+
+/// whether some shards, together, reveal the secret they have been built from with [`share`]
+///
+/// this is the case if and only if the first source shard, i.e. the secret, is a linear
+/// combination of the shards, e.g. when there are $k$ linearly independent shards.
+pub fn reveals<F: PrimeField>(shards: &[Shard<F>]) -> bool {
+    let Some(first) = shards.first() else {
+        return false;
+    };
+    let k = first.k as usize;
+
+    let mut secret = vec![F::zero(); k];
+    if let Some(s) = secret.first_mut() {
+        *s = F::one();
+    }
+
+    Matrix::from_vec_vec(
+        shards
+            .iter()
+            .map(|s| s.linear_combination.clone())
+            .collect(),
+    )
+    .is_ok_and(|m| m.is_in_row_space(&secret))
+}
+
+/// reconstruct a secret from at least $k$ of its shards
+///
+/// > **Note**
+/// >
+/// > the failures are the same as the ones of [`crate::fec::decode`], with the same context.
+///
+/// This is the inverse of [`share`].
+pub fn reconstruct<F: PrimeField>(shards: Vec<Shard<F>>) -> Result<Vec<u8>, KomodoError> {
+    let shards = fec::independent_shards(&shards)?;
+    let k = shards.len();
+
+    // NOTE: the source elements come row after row and the secret is the first source shard, i.e.
+    // the first element of each row
+    let secret = fec::Decoding::new(&shards)?
+        .source_elements(&shards, 0..shards[0].data.len())?
+        .into_iter()
+        .step_by(k)
+        .collect::<Vec<_>>();
+
+    let mut bytes = algebra::merge_elements_into_bytes(&secret);
+    bytes.resize(shards[0].size, 0);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Fr;
+    use ark_std::test_rng;
+
+    use super::{reconstruct, reveals, share};
+    use crate::{algebra::linalg::Matrix, error::KomodoError, fec::recode_random};
+
+    fn vandermonde(k: usize, n: usize) -> Matrix<Fr> {
+        let points = (1..=n as u64).map(Fr::from).collect::<Vec<_>>();
+        Matrix::vandermonde(&points, k).unwrap()
+    }
+
+    #[test]
+    fn share_and_reconstruct() {
+        let mut rng = test_rng();
+        let secret = include_bytes!("../assets/dragoon_32x32.png").to_vec();
+
+        for (k, n) in [(1, 3), (3, 5), (5, 5)] {
+            let shards = share(&secret, &vandermonde(k, n), &mut rng).unwrap();
+            assert_eq!(shards.len(), n);
+            assert!(shards.iter().all(|s| s.hash == shards[0].hash));
+
+            assert_eq!(reconstruct(shards[n - k..].to_vec()).unwrap(), secret);
+            assert!(reveals(&shards[n - k..]));
+            if k > 1 {
+                assert!(!reveals(&shards[..k - 1]));
+                assert!(matches!(
+                    reconstruct(shards[..k - 1].to_vec()),
+                    Err(KomodoError::TooFewShards(..))
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn recoding() {
+        let mut rng = test_rng();
+        let secret = b"komodo".to_vec();
+        let (k, n) = (3, 5);
+
+        let shards = share(&secret, &vandermonde(k, n), &mut rng).unwrap();
+        let recoded = recode_random(&shards[..2], &mut rng).unwrap().unwrap();
+
+        // NOTE: recoding does not add anything to what the recoded shards reveal
+        assert!(!reveals(&[
+            shards[0].clone(),
+            shards[1].clone(),
+            recoded.clone()
+        ]));
+        assert!(matches!(
+            reconstruct(vec![shards[0].clone(), shards[1].clone(), recoded.clone()]),
+            Err(KomodoError::LinearlyDependentShards(3, _))
+        ));
+        assert_eq!(
+            reconstruct(vec![recoded, shards[1].clone(), shards[4].clone()]).unwrap(),
+            secret
+        );
+
+        let mut forged = shards;
+        forged.iter_mut().for_each(|s| s.k = 0);
+        assert!(matches!(
+            reconstruct(forged),
+            Err(KomodoError::IncompatibleShards(..))
+        ));
+    }
+
+    #[test]
+    fn systematic_matrix() {
+        let mut rng = test_rng();
+        let secret = b"komodo".to_vec();
+
+        // NOTE: the first column of a systematic matrix is the first source shard, i.e. the secret
+        let systematic = Matrix::<Fr>::from_vec_vec(vec![
+            vec![Fr::from(1), Fr::from(0), Fr::from(1)],
+            vec![Fr::from(0), Fr::from(1), Fr::from(1)],
+        ])
+        .unwrap();
+        let shards = share(&secret, &systematic, &mut rng).unwrap();
+        assert!(reveals(&shards[..1]));
+        assert!(!reveals(&shards[1..2]));
+    }
+}
@@ -4,11 +4,20 @@
 //! [`ark_poly_commit::kzg10::KZG10::commit`] to be used with [`crate::semi_avid`].
 //!
 //! also defines some tool functions such as [`trim`] or [`nb_elements_in_setup`].
-use ark_ec::{scalar_mul::fixed_base::FixedBase, CurveGroup, VariableBaseMSM};
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use ark_ec::scalar_mul::fixed_base::FixedBase;
+use ark_ec::{CurveGroup, VariableBaseMSM};
 use ark_ff::PrimeField;
 use ark_poly::DenseUVPolynomial;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_std::{end_timer, ops::Div, rand::RngCore, start_timer};
+use ark_std::{end_timer, start_timer, vec, vec::Vec};
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use ark_std::{
+    ops::Div,
+    rand::{CryptoRng, RngCore},
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 #[cfg(any(feature = "kzg", feature = "aplonk"))]
 use ark_ec::pairing::Pairing;
@@ -16,6 +25,8 @@ use ark_ec::pairing::Pairing;
 use ark_poly_commit::kzg10;
 
 use crate::error::KomodoError;
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+use crate::{randomness, telemetry::operation};
 
 /// a ZK trusted setup
 ///
@@ -28,14 +39,29 @@ use crate::error::KomodoError;
 pub struct Powers<F: PrimeField, G: CurveGroup<ScalarField = F>>(Vec<G::Affine>);
 
 impl<F: PrimeField, G: CurveGroup<ScalarField = F>> Powers<F, G> {
-    fn len(&self) -> usize {
+    /// the number of powers, i.e. the maximum degree of the setup plus one
+    pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// keep only the first `len` powers, i.e. specialize the setup to polynomials of degree at
+    /// most `len - 1`
+    ///
+    /// > **Note**
+    /// >
+    /// > a truncated setup gives the same commitments as the original one.
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len)
+    }
 }
 
 impl<F: PrimeField, G: CurveGroup<ScalarField = F>> IntoIterator for Powers<F, G> {
     type Item = G::Affine;
-    type IntoIter = std::vec::IntoIter<Self::Item>;
+    type IntoIter = vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
@@ -57,9 +83,31 @@ pub struct Commitment<F: PrimeField, G: CurveGroup<ScalarField = F>>(pub G::Affi
 /// > **Note**
 /// >
 /// > this is a simpler version of [`ark_poly_commit::kzg10::KZG10::setup`]
+/// >
+/// > the secret of the setup is drawn from `rng`, which is thus subject to the
+/// > [`randomness::RandomnessPolicy`], see [`setup_secure`].
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
 pub fn setup<F: PrimeField, G: CurveGroup<ScalarField = F>>(
     max_degree: usize,
     rng: &mut impl RngCore,
+) -> Result<Powers<F, G>, KomodoError> {
+    randomness::check("zk::setup")?;
+    setup_unchecked(max_degree, rng)
+}
+
+/// the same as [`setup`], with a cryptographically secure source of randomness
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+pub fn setup_secure<F: PrimeField, G: CurveGroup<ScalarField = F>>(
+    max_degree: usize,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<Powers<F, G>, KomodoError> {
+    setup_unchecked(max_degree, rng)
+}
+
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
+fn setup_unchecked<F: PrimeField, G: CurveGroup<ScalarField = F>>(
+    max_degree: usize,
+    rng: &mut impl RngCore,
 ) -> Result<Powers<F, G>, KomodoError> {
     if max_degree < 1 {
         return Err(KomodoError::DegreeIsZero);
@@ -163,8 +211,10 @@ where
 /// > - `powers` can be generated with functions like [`setup`]
 /// > - if `polynomials` has length `m`, then [`batch_commit`] will generate `m` commits
 /// > - see [`commit`] for the individual _commit_ operations
+/// > - the polynomials are committed in parallel with the `parallel` feature
 #[allow(clippy::type_complexity)]
 #[inline(always)]
+#[cfg(any(feature = "prover", not(feature = "verify-only")))]
 pub fn batch_commit<F, G, P>(
     powers: &Powers<F, G>,
     polynomials: &[P],
@@ -175,13 +225,13 @@ where
     P: DenseUVPolynomial<F>,
     for<'a, 'b> &'a P: Div<&'b P, Output = P>,
 {
-    let mut commits = Vec::new();
-    for polynomial in polynomials {
-        let commit = commit(powers, polynomial)?;
-        commits.push(commit);
-    }
+    let op = operation!("commit", "zk", polynomials = polynomials.len());
 
-    Ok(commits)
+    op.done(
+        ark_std::cfg_iter!(polynomials)
+            .map(|polynomial| commit(powers, polynomial))
+            .collect(),
+    )
 }
 
 /// compute the number of elements that a _trusted setup_ should have for data of
@@ -339,4 +389,34 @@ mod tests {
             commit_template::<Fr, G1Projective, DensePolynomial<Fr>>(nb_kb * 1024);
         }
     }
+
+    fn truncate_template<F, G, P>()
+    where
+        F: PrimeField,
+        G: CurveGroup<ScalarField = F>,
+        P: DenseUVPolynomial<F>,
+    {
+        let rng = &mut test_rng();
+
+        let powers = setup::<F, G>(10, rng).unwrap();
+        let mut truncated = powers.clone();
+        truncated.truncate(4);
+        assert_eq!(truncated.len(), 4);
+
+        let polynomial = P::rand(3, rng);
+        assert_eq!(
+            commit_to_test(&truncated, &polynomial).unwrap(),
+            commit_to_test(&powers, &polynomial).unwrap(),
+            "a truncated setup should give the same commitments"
+        );
+        assert!(
+            commit_to_test(&truncated, &P::rand(4, rng)).is_err(),
+            "committing a polynomial with more coefficients than there are powers in the truncated setup should NOT work"
+        );
+    }
+
+    #[test]
+    fn truncate() {
+        truncate_template::<Fr, G1Projective, DensePolynomial<Fr>>();
+    }
 }